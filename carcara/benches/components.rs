@@ -0,0 +1,199 @@
+//! Micro-benchmarks for some of the hot components of Carcara. Unlike the end-to-end measurements
+//! from the `bench` command, these isolate a single component, so they are more useful to evaluate
+//! targeted performance work.
+//!
+//! The inputs are taken from a corpus of instances, found using the same loader as the `bench`
+//! command. By default, the corpus is the `test-examples` directory, but other paths can be given
+//! in the `CARCARA_BENCH_CORPUS` environment variable, separated like the paths in `PATH`.
+
+use carcara::{
+    ast::*,
+    benchmarking::corpus,
+    checker::{Config, ProofChecker, RuleArgs, RuleResult},
+    match_term,
+    parser::{self, Lexer, Token},
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use std::{env, fs, path::PathBuf};
+
+const DEFAULT_CORPUS: &str = "../test-examples";
+
+struct Instance {
+    proof_text: Vec<u8>,
+    prelude: ProblemPrelude,
+    proof: Proof,
+    pool: TermPool,
+}
+
+fn load_corpus() -> Vec<Instance> {
+    let paths: Vec<PathBuf> = match env::var_os("CARCARA_BENCH_CORPUS") {
+        Some(paths) => env::split_paths(&paths).collect(),
+        None => vec![DEFAULT_CORPUS.into()],
+    };
+    let instances = match corpus::find_instances(&paths) {
+        Ok(instances) => instances,
+        Err(e) => {
+            eprintln!("failed to load benchmark corpus: {}", e);
+            return Vec::new();
+        }
+    };
+
+    instances
+        .into_iter()
+        .filter_map(|(problem_file, proof_file)| {
+            let proof_text = fs::read(&proof_file).ok()?;
+            let problem = fs::read(&problem_file).ok()?;
+            let (prelude, proof, pool) = parser::parse_instance(
+                problem.as_slice(),
+                proof_text.as_slice(),
+                parser::ParserConfig::default(),
+            )
+            .ok()?;
+            Some(Instance { proof_text, prelude, proof, pool })
+        })
+        .collect()
+}
+
+/// Returns every distinct subterm of the terms in the proofs, in an order such that each term comes
+/// after all of its subterms.
+fn collect_terms(instances: &[Instance]) -> Vec<Rc<Term>> {
+    fn visit(term: &Rc<Term>, seen: &mut ahash::AHashSet<Rc<Term>>, acc: &mut Vec<Rc<Term>>) {
+        if seen.contains(term) {
+            return;
+        }
+        match term.as_ref() {
+            Term::App(f, args) => {
+                visit(f, seen, acc);
+                args.iter().for_each(|a| visit(a, seen, acc));
+            }
+            Term::Op(_, args) | Term::IndexedOp(_, _, args) => {
+                args.iter().for_each(|a| visit(a, seen, acc));
+            }
+            Term::Quant(_, _, inner, _) | Term::Choice(_, inner) | Term::Lambda(_, inner) => {
+                visit(inner, seen, acc);
+            }
+            Term::Let(bindings, inner) => {
+                bindings
+                    .iter()
+                    .for_each(|(_, value)| visit(value, seen, acc));
+                visit(inner, seen, acc);
+            }
+            Term::Terminal(_) => (),
+        }
+        seen.insert(term.clone());
+        acc.push(term.clone());
+    }
+
+    let mut seen = ahash::AHashSet::new();
+    let mut acc = Vec::new();
+    for instance in instances {
+        for command in instance.proof.iter() {
+            for term in command.clause() {
+                visit(term, &mut seen, &mut acc);
+            }
+        }
+    }
+    acc
+}
+
+fn lexer(c: &mut Criterion, instances: &[Instance]) {
+    c.bench_function("lexer", |b| {
+        b.iter(|| {
+            for instance in instances {
+                let mut lexer = Lexer::new(instance.proof_text.as_slice()).unwrap();
+                while lexer.next_token().unwrap().0 != Token::Eof {}
+            }
+        });
+    });
+}
+
+fn term_pool_add(c: &mut Criterion, terms: &[Rc<Term>]) {
+    c.bench_function("term_pool_add", |b| {
+        b.iter_batched_ref(
+            TermPool::new,
+            |pool| {
+                for t in terms {
+                    black_box(pool.add(t.as_ref().clone()));
+                }
+            },
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+fn match_term(c: &mut Criterion, terms: &[Rc<Term>]) {
+    c.bench_function("match_term", |b| {
+        b.iter(|| {
+            for t in terms {
+                black_box(match_term!((= a b) = t));
+                black_box(match_term!((not (or ...)) = t));
+                black_box(match_term!((=> (and ...) (= a b)) = t));
+            }
+        });
+    });
+}
+
+/// Benchmarks checking all the steps in the corpus that use the given rule. To isolate the rule,
+/// every other rule is replaced by one that accepts any step.
+fn rule(c: &mut Criterion, instances: &mut [Instance], rule_name: &str) {
+    fn accept(_: RuleArgs) -> RuleResult {
+        Ok(())
+    }
+
+    let mut instances: Vec<_> = instances
+        .iter_mut()
+        .filter_map(|instance| {
+            let mut other_rules = Vec::new();
+            let mut uses_rule = false;
+            for command in instance.proof.iter() {
+                if let ProofCommand::Step(s) = command {
+                    if s.rule == rule_name {
+                        uses_rule = true;
+                    } else {
+                        other_rules.push(s.rule.clone());
+                    }
+                }
+            }
+            uses_rule.then(|| (instance, other_rules))
+        })
+        .collect();
+    if instances.is_empty() {
+        eprintln!("no steps using rule '{}' in corpus, skipping", rule_name);
+        return;
+    }
+
+    c.bench_function(rule_name, |b| {
+        b.iter(|| {
+            for (instance, other_rules) in &mut instances {
+                let Instance { prelude, proof, pool, .. } = &mut **instance;
+                let config = Config {
+                    skip_unknown_rules: true,
+                    ..Config::default()
+                };
+                let mut checker = ProofChecker::new(pool, config, prelude.clone());
+                for name in other_rules.iter() {
+                    checker.add_rule(name, accept);
+                }
+                black_box(checker.check(proof).ok());
+            }
+        });
+    });
+}
+
+fn benchmarks(c: &mut Criterion) {
+    let mut instances = load_corpus();
+    if instances.is_empty() {
+        eprintln!("benchmark corpus is empty, skipping micro-benchmarks");
+        return;
+    }
+    let terms = collect_terms(&instances);
+
+    lexer(c, &instances);
+    term_pool_add(c, &terms);
+    match_term(c, &terms);
+    rule(c, &mut instances, "resolution");
+    rule(c, &mut instances, "la_generic");
+}
+
+criterion_group!(benches, benchmarks);
+criterion_main!(benches);
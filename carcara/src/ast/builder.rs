@@ -0,0 +1,300 @@
+//! A builder for constructing Alethe proofs programmatically, for tools that generate proofs, like
+//! preprocessors or translators from other proof formats. The built proof can then be checked, or
+//! written with the printer.
+
+use super::{Proof, ProofArg, ProofCommand, ProofStep, Rc, SortedVar, Subproof, Term};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+
+/// The error type for invalid commands added to a [`ProofBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofBuilderError {
+    /// A premise or discharged command doesn't refer to a command that can be used by the step.
+    /// That is, a command that was added before it, either in the current subproof or in one of
+    /// the enclosing subproofs.
+    InvalidPremise((usize, usize)),
+
+    /// A command discharged by a step is not an `assume` command. Holds the id of the command.
+    DischargedNotAssume(String),
+
+    /// A subproof was closed, but no subproof was open.
+    NoOpenSubproof,
+
+    /// The proof was finished while some subproofs were still open. Holds the number of open
+    /// subproofs.
+    UnclosedSubproofs(usize),
+}
+
+// Since this type is also available without `std`, we can't use `thiserror` to derive these
+// implementations
+impl fmt::Display for ProofBuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofBuilderError::InvalidPremise((depth, index)) => write!(
+                f,
+                "premise index ({}, {}) doesn't refer to a visible command",
+                depth, index
+            ),
+            ProofBuilderError::DischargedNotAssume(id) => {
+                write!(f, "discharged command '{}' is not an assume command", id)
+            }
+            ProofBuilderError::NoOpenSubproof => write!(f, "no subproof is open"),
+            ProofBuilderError::UnclosedSubproofs(n) => {
+                write!(f, "proof was finished with {} unclosed subproof(s)", n)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProofBuilderError {}
+
+/// A subproof that is being built. The root proof is also kept as a frame, with no arguments.
+#[derive(Debug, Default)]
+struct Frame {
+    /// The id of the step that ends the subproof, which prefixes the ids of the commands in it.
+    /// This is empty for the root proof.
+    id: String,
+    subproof: Subproof,
+}
+
+/// A builder for Alethe proofs.
+///
+/// Commands are added in order, and each command is identified by the index of the form (depth,
+/// index in subproof) that is used in [`ProofStep::premises`]. These indices are returned when the
+/// commands are added, and the premises of later steps are checked to refer to commands that they
+/// can use. Command ids are generated following the convention of Alethe proofs: `h1`, `t2`, etc.
+/// in the root proof, and `t3.t1`, `t3.t2`, etc. in a subproof that ends in the step `t3`.
+///
+/// Note that the index of a command in a subproof that was already closed may be valid again in a
+/// later subproof, so these indices must not be reused once their subproof is closed.
+///
+/// # Examples
+///
+/// ```
+/// # use carcara::ast::*;
+/// # fn main() -> Result<(), ProofBuilderError> {
+/// let mut pool = TermPool::new();
+/// let bool_sort = pool.add_sort(Sort::Bool);
+/// let p = pool.add(Term::var("p", bool_sort));
+/// let not_p = pool.add(Term::Op(Operator::Not, vec![p.clone()]));
+///
+/// let mut builder = ProofBuilder::new();
+/// let h1 = builder.assume(p.clone());
+/// let h2 = builder.assume(not_p.clone());
+/// builder.step("resolution").premises([h1, h2]).add()?;
+/// let proof = builder.finish()?;
+///
+/// let ids: Vec<_> = proof.iter().map(|c| c.id()).collect();
+/// assert_eq!(ids, ["h1", "h2", "t3"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ProofBuilder {
+    stack: Vec<Frame>,
+}
+
+impl Default for ProofBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProofBuilder {
+    /// Constructs a new builder for an empty proof.
+    pub fn new() -> Self {
+        Self { stack: vec![Frame::default()] }
+    }
+
+    /// Returns the current nesting depth, that is, the number of subproofs that are open.
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    fn top_frame(&self) -> &Frame {
+        self.stack.last().unwrap()
+    }
+
+    /// Returns the id of the next command added, given the prefix of its kind of command.
+    fn next_id(&self, prefix: &str) -> String {
+        let frame = self.top_frame();
+        let n = frame.subproof.commands.len() + 1;
+        if frame.id.is_empty() {
+            format!("{}{}", prefix, n)
+        } else {
+            format!("{}.{}{}", frame.id, prefix, n)
+        }
+    }
+
+    fn push_command(&mut self, command: ProofCommand) -> (usize, usize) {
+        let depth = self.depth();
+        let commands = &mut self.stack.last_mut().unwrap().subproof.commands;
+        commands.push(command);
+        (depth, commands.len() - 1)
+    }
+
+    /// Returns the command referenced by a premise index, if it can be used by the next command.
+    fn get_visible(&self, (depth, index): (usize, usize)) -> Option<&ProofCommand> {
+        self.stack.get(depth)?.subproof.commands.get(index)
+    }
+
+    /// Adds an `assume` command with the given term, and returns its index.
+    ///
+    /// The terms assumed in the root proof are also the premises of the built proof.
+    pub fn assume(&mut self, term: Rc<Term>) -> (usize, usize) {
+        let id = self.next_id("h");
+        self.push_command(ProofCommand::Assume { id, term })
+    }
+
+    /// Starts adding a `step` command that uses the given rule. The step is only added when
+    /// [`StepBuilder::add`] is called. By default, the step concludes the empty clause, and has no
+    /// premises, arguments, or discharged commands.
+    pub fn step(&mut self, rule: impl Into<String>) -> StepBuilder<'_> {
+        StepBuilder::new(self, rule.into(), false)
+    }
+
+    /// Opens a subproof, whose anchor has the given assignment and variable arguments. Commands
+    /// added after this are added to the subproof, until it is closed with
+    /// [`close_subproof`](Self::close_subproof).
+    pub fn open_subproof(
+        &mut self,
+        assignment_args: Vec<(String, Rc<Term>)>,
+        variable_args: Vec<SortedVar>,
+    ) {
+        let id = self.next_id("t");
+        self.stack.push(Frame {
+            id,
+            subproof: Subproof {
+                commands: Vec::new(),
+                assignment_args,
+                variable_args,
+            },
+        });
+    }
+
+    /// Starts adding the step that ends the current subproof, which uses the given rule. When
+    /// [`StepBuilder::add`] is called, the subproof is closed, and the index of the subproof in the
+    /// enclosing proof is returned. This index can then be used as a premise by later steps.
+    pub fn close_subproof(&mut self, rule: impl Into<String>) -> StepBuilder<'_> {
+        StepBuilder::new(self, rule.into(), true)
+    }
+
+    /// Returns the built proof. Fails if there are subproofs that were not closed.
+    pub fn finish(mut self) -> Result<Proof, ProofBuilderError> {
+        if self.depth() > 0 {
+            return Err(ProofBuilderError::UnclosedSubproofs(self.depth()));
+        }
+        let commands = self.stack.pop().unwrap().subproof.commands;
+        let premises = commands
+            .iter()
+            .filter_map(|c| match c {
+                ProofCommand::Assume { term, .. } => Some(term.clone()),
+                _ => None,
+            })
+            .collect();
+        Ok(Proof {
+            premises,
+            commands,
+            spans: Vec::new(),
+            metadata: Vec::new(),
+            named_assertions: Vec::new(),
+            comments: Vec::new(),
+        })
+    }
+}
+
+/// A builder for a `step` command, created by [`ProofBuilder::step`] or
+/// [`ProofBuilder::close_subproof`].
+#[must_use = "the step is only added to the proof when `add` is called"]
+pub struct StepBuilder<'a> {
+    builder: &'a mut ProofBuilder,
+    rule: String,
+    clause: Vec<Rc<Term>>,
+    premises: Vec<(usize, usize)>,
+    args: Vec<ProofArg>,
+    discharge: Vec<(usize, usize)>,
+    closes_subproof: bool,
+}
+
+impl<'a> StepBuilder<'a> {
+    fn new(builder: &'a mut ProofBuilder, rule: String, closes_subproof: bool) -> Self {
+        Self {
+            builder,
+            rule,
+            clause: Vec::new(),
+            premises: Vec::new(),
+            args: Vec::new(),
+            discharge: Vec::new(),
+            closes_subproof,
+        }
+    }
+
+    /// Sets the conclusion clause of the step.
+    pub fn clause(mut self, clause: impl IntoIterator<Item = Rc<Term>>) -> Self {
+        self.clause = clause.into_iter().collect();
+        self
+    }
+
+    /// Sets the premises of the step, given by the indices returned when they were added.
+    pub fn premises(mut self, premises: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        self.premises = premises.into_iter().collect();
+        self
+    }
+
+    /// Sets the arguments of the step.
+    pub fn args(mut self, args: impl IntoIterator<Item = ProofArg>) -> Self {
+        self.args = args.into_iter().collect();
+        self
+    }
+
+    /// Sets the `assume` commands discharged by the step, given by the indices returned when they
+    /// were added.
+    pub fn discharge(mut self, discharge: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        self.discharge = discharge.into_iter().collect();
+        self
+    }
+
+    /// Adds the step to the proof, and returns its index. If the step ends a subproof, the subproof
+    /// is closed, and its index is returned instead. Fails if a premise or discharged command can't
+    /// be used by the step, or if the step ends a subproof but no subproof is open.
+    pub fn add(self) -> Result<(usize, usize), ProofBuilderError> {
+        let builder = self.builder;
+        if self.closes_subproof && builder.depth() == 0 {
+            return Err(ProofBuilderError::NoOpenSubproof);
+        }
+        for &p in &self.premises {
+            if builder.get_visible(p).is_none() {
+                return Err(ProofBuilderError::InvalidPremise(p));
+            }
+        }
+        for &d in &self.discharge {
+            match builder.get_visible(d) {
+                Some(ProofCommand::Assume { .. }) => (),
+                Some(other) => {
+                    return Err(ProofBuilderError::DischargedNotAssume(other.id().into()))
+                }
+                None => return Err(ProofBuilderError::InvalidPremise(d)),
+            }
+        }
+
+        let id = if self.closes_subproof {
+            builder.top_frame().id.clone()
+        } else {
+            builder.next_id("t")
+        };
+        let index = builder.push_command(ProofCommand::Step(ProofStep {
+            id,
+            clause: self.clause,
+            rule: self.rule,
+            premises: self.premises,
+            args: self.args,
+            discharge: self.discharge,
+        }));
+        if !self.closes_subproof {
+            return Ok(index);
+        }
+        let subproof = builder.stack.pop().unwrap().subproof;
+        Ok(builder.push_command(ProofCommand::Subproof(subproof)))
+    }
+}
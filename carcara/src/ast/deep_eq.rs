@@ -2,50 +2,62 @@
 //! contains two definitions of equality that differ from `PartialEq`:
 //!
 //! - `deep_eq` considers `=` terms that are reflections of each other as equal, meaning the terms
-//! `(= a b)` and `(= b a)` are considered equal by this method.
+//!   `(= a b)` and `(= b a)` are considered equal by this method.
 //!
 //! - `are_alpha_equivalent` compares terms by alpha-equivalence, meaning it implements equality of
-//! terms modulo renaming of bound variables.
+//!   terms modulo renaming of bound variables.
 
 use super::{
     BindingList, Identifier, Operator, ProofArg, ProofCommand, ProofStep, Rc, Sort, Subproof, Term,
     Terminal,
 };
 use crate::utils::SymbolTable;
-use std::time::{Duration, Instant};
+use alloc::{string::String, vec, vec::Vec};
+use core::time::Duration;
 
 pub trait DeepEq {
     fn eq(checker: &mut DeepEqualityChecker, a: &Self, b: &Self) -> bool;
 }
 
+/// Calls `f`, and adds the time it took to run to `time`. Without the `std` feature, there is no
+/// clock available, so `time` is left unchanged.
+fn timed<T>(time: &mut Duration, f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "std")]
+    {
+        let start = std::time::Instant::now();
+        let result = f();
+        *time += start.elapsed();
+        result
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = time;
+        f()
+    }
+}
+
 pub fn deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
-    let start = Instant::now();
-    let result = DeepEq::eq(&mut DeepEqualityChecker::new(true, false), a, b);
-    *time += start.elapsed();
-    result
+    timed(time, || {
+        DeepEq::eq(&mut DeepEqualityChecker::new(true, false), a, b)
+    })
 }
 
 pub fn tracing_deep_eq(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> (bool, usize) {
-    let start = Instant::now();
-
-    let mut checker = DeepEqualityChecker::new(true, false);
-    let result = DeepEq::eq(&mut checker, a, b);
-
-    *time += start.elapsed();
-    (result, checker.max_depth)
+    timed(time, || {
+        let mut checker = DeepEqualityChecker::new(true, false);
+        let result = DeepEq::eq(&mut checker, a, b);
+        (result, checker.max_depth)
+    })
 }
 
 pub fn are_alpha_equivalent(a: &Rc<Term>, b: &Rc<Term>, time: &mut Duration) -> bool {
-    let start = Instant::now();
-
     // When we are checking for alpha-equivalence, we can't always assume that if `a` and `b` are
     // identical, they are alpha-equivalent, so that optimization is not used in `DeepEq::eq`.
     // However, here at the "root" level this assumption is valid, so we check if the terms are
     // directly equal before doing anything else
-    let result = a == b || DeepEq::eq(&mut DeepEqualityChecker::new(true, true), a, b);
-
-    *time += start.elapsed();
-    result
+    timed(time, || {
+        a == b || DeepEq::eq(&mut DeepEqualityChecker::new(true, true), a, b)
+    })
 }
 
 pub struct DeepEqualityChecker {
@@ -95,10 +107,10 @@ impl DeepEqualityChecker {
         }
     }
 
-    fn check_binder(
+    fn check_binder<T: DeepEq>(
         &mut self,
-        a_binds: &BindingList,
-        b_binds: &BindingList,
+        a_binds: &BindingList<T>,
+        b_binds: &BindingList<T>,
         a_inner: &Rc<Term>,
         b_inner: &Rc<Term>,
     ) -> bool {
@@ -107,7 +119,9 @@ impl DeepEqualityChecker {
             alpha_checker.push();
             self.cache.push_scope();
 
-            // Then, we check that the binding lists and the inner terms are equivalent
+            // Then, we check that the binding lists and the inner terms are equivalent. The values
+            // in a `let` binding list are not in the scope of any of its variables, so they are
+            // all compared before the variables are inserted into the alpha-equivalence checker
             for (a_var, b_var) in a_binds.iter().zip(b_binds.iter()) {
                 if !DeepEq::eq(self, &a_var.1, &b_var.1) {
                     // We must remember to pop the frames from the alpha equivalence checker and
@@ -116,8 +130,8 @@ impl DeepEqualityChecker {
                     self.cache.pop_scope();
                     return false;
                 }
-                // We also insert each variable in the binding lists into the alpha-equivalence
-                // checker
+            }
+            for (a_var, b_var) in a_binds.iter().zip(b_binds.iter()) {
                 self.alpha_equiv_checker
                     .as_mut()
                     .unwrap()
@@ -154,7 +168,7 @@ impl DeepEq for Rc<Term> {
         }
 
         checker.current_depth += 1;
-        checker.max_depth = std::cmp::max(checker.max_depth, checker.current_depth);
+        checker.max_depth = core::cmp::max(checker.max_depth, checker.current_depth);
         let result = DeepEq::eq(checker, a.as_ref(), b.as_ref());
         if result {
             checker.cache.insert((a.clone(), b.clone()), ());
@@ -184,7 +198,10 @@ impl DeepEq for Term {
                 // General case
                 op_a == op_b && DeepEq::eq(checker, args_a, args_b)
             }
-            (Term::Sort(a), Term::Sort(b)) => DeepEq::eq(checker, a, b),
+            (
+                Term::IndexedOp(op_a, indices_a, args_a),
+                Term::IndexedOp(op_b, indices_b, args_b),
+            ) => op_a == op_b && indices_a == indices_b && DeepEq::eq(checker, args_a, args_b),
             (Term::Terminal(a), Term::Terminal(b)) => match (a, b) {
                 // If we are checking for alpha-equivalence, and we encounter two variables, we
                 // check that they are equivalent using the alpha-equivalence checker
@@ -201,12 +218,16 @@ impl DeepEq for Term {
                 }
                 (a, b) => a == b,
             },
-            (Term::Quant(q_a, _, _), Term::Quant(q_b, _, _)) if q_a != q_b => false,
-            (Term::Quant(_, a_binds, a), Term::Quant(_, b_binds, b))
-            | (Term::Let(a_binds, a), Term::Let(b_binds, b))
+            (Term::Quant(q_a, ..), Term::Quant(q_b, ..)) if q_a != q_b => false,
+
+            // The patterns of quantifiers don't change their meaning, so they are not compared
+            (Term::Quant(_, a_binds, a, _), Term::Quant(_, b_binds, b, _))
             | (Term::Lambda(a_binds, a), Term::Lambda(b_binds, b)) => {
                 checker.check_binder(a_binds, b_binds, a, b)
             }
+            (Term::Let(a_binds, a), Term::Let(b_binds, b)) => {
+                checker.check_binder(a_binds, b_binds, a, b)
+            }
             (Term::Choice(a_var, a), Term::Choice(b_var, b)) => {
                 let a_binds = BindingList(vec![a_var.clone()]);
                 let b_binds = BindingList(vec![b_var.clone()]);
@@ -217,12 +238,19 @@ impl DeepEq for Term {
     }
 }
 
-impl DeepEq for BindingList {
+impl<T: DeepEq> DeepEq for BindingList<T> {
     fn eq(checker: &mut DeepEqualityChecker, a: &Self, b: &Self) -> bool {
         DeepEq::eq(checker, &a.0, &b.0)
     }
 }
 
+impl DeepEq for Rc<Sort> {
+    fn eq(checker: &mut DeepEqualityChecker, a: &Self, b: &Self) -> bool {
+        // Sorts don't have bound variables, so identical sorts are always equal
+        a == b || DeepEq::eq(checker, a.as_ref(), b.as_ref())
+    }
+}
+
 impl DeepEq for Sort {
     fn eq(checker: &mut DeepEqualityChecker, a: &Self, b: &Self) -> bool {
         match (a, b) {
@@ -235,10 +263,16 @@ impl DeepEq for Sort {
             (Sort::Bool, Sort::Bool)
             | (Sort::Int, Sort::Int)
             | (Sort::Real, Sort::Real)
-            | (Sort::String, Sort::String) => true,
+            | (Sort::String, Sort::String)
+            | (Sort::RegLan, Sort::RegLan)
+            | (Sort::RoundingMode, Sort::RoundingMode) => true,
             (Sort::Array(x_a, y_a), Sort::Array(x_b, y_b)) => {
                 DeepEq::eq(checker, x_a, x_b) && DeepEq::eq(checker, y_a, y_b)
             }
+            (Sort::BitVec(a), Sort::BitVec(b)) => a == b,
+            (Sort::FloatingPoint(eb_a, sb_a), Sort::FloatingPoint(eb_b, sb_b)) => {
+                eb_a == eb_b && sb_a == sb_b
+            }
             _ => false,
         }
     }
@@ -0,0 +1,164 @@
+//! Queries about which commands of a proof depend on each other. Commands are identified by their
+//! position in the order they are returned by [`Proof::iter`], the same order used by
+//! [`Proof::spans`]. Subproofs are identified by the position of their `anchor`.
+//!
+//! A step depends on the commands given in its `:premises` and `:discharge` attributes. When a
+//! premise refers to a subproof, the step depends on the last step of that subproof. The last step
+//! of a subproof also depends on the command that comes before it, and on the subproof's anchor,
+//! since rules like `subproof` and `bind` use them implicitly.
+
+use super::{Proof, ProofCommand};
+use alloc::{vec, vec::Vec};
+
+/// The dependencies between the commands of a proof. This struct is created by the
+/// [`dependency_graph`](Proof::dependency_graph) method on proofs, and can be used to answer many
+/// queries without traversing the proof again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    dependencies: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+}
+
+/// The dependencies of a single command, as returned by [`Proof::dependencies_of`]. All vectors are
+/// sorted and contain no duplicates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencySet {
+    /// The commands that this command uses directly.
+    pub direct: Vec<usize>,
+
+    /// All commands that this command depends on, directly or transitively.
+    pub transitive: Vec<usize>,
+
+    /// The commands that use this command directly.
+    pub used_by: Vec<usize>,
+
+    /// All commands that depend on this command, directly or transitively.
+    pub transitively_used_by: Vec<usize>,
+}
+
+impl DependencyGraph {
+    pub(super) fn new(proof: &Proof) -> Self {
+        let mut dependencies: Vec<Vec<usize>> = Vec::new();
+
+        // For each open subproof, the position of each of its commands that was already returned
+        // by the iterator. Once a subproof is closed, its anchor is replaced by its last step, since
+        // that is the command referenced by premises that point to the subproof
+        let mut positions: Vec<Vec<usize>> = vec![Vec::new()];
+
+        let mut iter = proof.iter();
+        while let Some(command) = iter.next() {
+            let current = dependencies.len();
+            // When a subproof is returned, the iterator has already entered it
+            let depth = match command {
+                ProofCommand::Subproof(_) => iter.depth() - 1,
+                _ => iter.depth(),
+            };
+            positions.truncate(depth + 1);
+
+            let mut direct = Vec::new();
+            if let ProofCommand::Step(step) = command {
+                let references = step.premises.iter().chain(&step.discharge);
+                direct.extend(references.map(|&(d, i)| positions[d][i]));
+                if iter.is_end_step() {
+                    direct.extend(positions[depth].last());
+                    let anchor = positions[depth - 1].last_mut().unwrap();
+                    direct.push(*anchor);
+                    *anchor = current;
+                }
+            }
+            direct.sort_unstable();
+            direct.dedup();
+            dependencies.push(direct);
+
+            positions[depth].push(current);
+            if let ProofCommand::Subproof(_) = command {
+                positions.push(Vec::new());
+            }
+        }
+
+        let mut dependents = vec![Vec::new(); dependencies.len()];
+        for (i, direct) in dependencies.iter().enumerate() {
+            for &d in direct {
+                dependents[d].push(i);
+            }
+        }
+        Self { dependencies, dependents }
+    }
+
+    /// Returns the number of commands in the proof, including the anchors of subproofs.
+    pub fn len(&self) -> usize {
+        self.dependencies.len()
+    }
+
+    /// Returns `true` if the proof has no commands.
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty()
+    }
+
+    /// Returns the commands that the command at position `index` uses directly.
+    pub fn direct_dependencies(&self, index: usize) -> &[usize] {
+        &self.dependencies[index]
+    }
+
+    /// Returns the commands that use the command at position `index` directly.
+    pub fn direct_dependents(&self, index: usize) -> &[usize] {
+        &self.dependents[index]
+    }
+
+    /// Returns all commands that the command at position `index` depends on, directly or
+    /// transitively, in the order they appear in the proof.
+    pub fn transitive_dependencies(&self, index: usize) -> Vec<usize> {
+        Self::reachable(&self.dependencies, index)
+    }
+
+    /// Returns all commands that depend on the command at position `index`, directly or
+    /// transitively, in the order they appear in the proof.
+    pub fn transitive_dependents(&self, index: usize) -> Vec<usize> {
+        Self::reachable(&self.dependents, index)
+    }
+
+    /// Returns the direct, transitive and reverse dependencies of the command at position `index`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `index` is not the position of a command in the proof.
+    pub fn dependencies_of(&self, index: usize) -> DependencySet {
+        DependencySet {
+            direct: self.dependencies[index].clone(),
+            transitive: self.transitive_dependencies(index),
+            used_by: self.dependents[index].clone(),
+            transitively_used_by: self.transitive_dependents(index),
+        }
+    }
+
+    fn reachable(edges: &[Vec<usize>], start: usize) -> Vec<usize> {
+        let mut seen = vec![false; edges.len()];
+        let mut stack = edges[start].clone();
+        while let Some(i) = stack.pop() {
+            if !seen[i] {
+                seen[i] = true;
+                stack.extend(&edges[i]);
+            }
+        }
+        (0..edges.len()).filter(|&i| seen[i]).collect()
+    }
+}
+
+impl Proof {
+    /// Builds the graph of dependencies between the commands of the proof. See
+    /// [`DependencyGraph`].
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        DependencyGraph::new(self)
+    }
+
+    /// Returns the direct, transitive and reverse dependencies of the command at position `index`
+    /// in the order given by [`Proof::iter`]. To make many queries on the same proof, build the
+    /// [`DependencyGraph`] once with [`Proof::dependency_graph`] instead.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `index` is not the position of a command in the proof.
+    pub fn dependencies_of(&self, index: usize) -> DependencySet {
+        self.dependency_graph().dependencies_of(index)
+    }
+}
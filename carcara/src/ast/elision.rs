@@ -0,0 +1,178 @@
+//! Abbreviated printing of large clauses. Clauses from bit-blasted proofs can be enormous when
+//! printed in full, even if most of their size comes from a few subterms that are repeated many
+//! times. An `ElidedClause` replaces each of these repeated subterms with a short reference like
+//! `...#1`, and lists the referenced terms in a footnote after the clause.
+
+use super::{printer::IndexedHead, Rc, Term};
+use crate::utils::AHashMap;
+use alloc::{collections::VecDeque, vec::Vec};
+use core::fmt;
+
+/// Clauses whose total size, in number of nodes when printed as a tree, is at most this value are
+/// printed in full.
+const MAX_UNELIDED_SIZE: usize = 200;
+
+/// Shared subterms whose size is smaller than this value are never elided, since the reference
+/// would not be much shorter than the term itself.
+const MIN_ELIDED_TERM_SIZE: usize = 4;
+
+/// A wrapper that implements `fmt::Display` for clauses, eliding shared subterms if the clause is
+/// too large.
+///
+/// Small clauses are printed as `(cl t_1 ... t_n)`. If the clause is large, every subterm that is
+/// referenced more than once is printed as `...#i` instead, and its definition is printed in a
+/// footnote, in a separate line of the form `#i = t`.
+pub struct ElidedClause<'a>(pub &'a [Rc<Term>]);
+
+impl fmt::Display for ElidedClause<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut sizes = AHashMap::new();
+        let total = self.0.iter().fold(0usize, |acc, t| {
+            acc.saturating_add(tree_size(t, &mut sizes))
+        });
+        if total <= MAX_UNELIDED_SIZE {
+            write!(f, "(cl")?;
+            for t in self.0 {
+                write!(f, " {}", t)?;
+            }
+            return write!(f, ")");
+        }
+
+        let mut printer = ElidingPrinter {
+            shared: count_references(self.0),
+            sizes,
+            indices: AHashMap::new(),
+            queue: VecDeque::new(),
+        };
+        write!(f, "(cl")?;
+        for t in self.0 {
+            write!(f, " ")?;
+            printer.write_term(f, t, true)?;
+        }
+        write!(f, ")")?;
+
+        // Printing a footnote may introduce new references, which are added to the end of the
+        // queue
+        while let Some((i, term)) = printer.queue.pop_front() {
+            write!(f, "\n  #{} = ", i)?;
+            printer.write_term(f, &term, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of nodes in `term`, when it is seen as a tree instead of a DAG. The result
+/// saturates at `usize::MAX`.
+fn tree_size(term: &Rc<Term>, cache: &mut AHashMap<Rc<Term>, usize>) -> usize {
+    if let Some(&size) = cache.get(term) {
+        return size;
+    }
+    let size = children(term).fold(1usize, |acc, child| {
+        acc.saturating_add(tree_size(child, cache))
+    });
+    cache.insert(term.clone(), size);
+    size
+}
+
+/// Returns the subterms of the clause that are referenced more than once, either as a term in the
+/// clause or as a child of a distinct subterm, together with their number of references.
+fn count_references(clause: &[Rc<Term>]) -> AHashMap<Rc<Term>, usize> {
+    let mut counts: AHashMap<Rc<Term>, usize> = AHashMap::new();
+    let mut stack: Vec<&Rc<Term>> = clause.iter().collect();
+    while let Some(term) = stack.pop() {
+        let count = counts.entry(term.clone()).or_default();
+        *count += 1;
+
+        // The children of a term are only visited the first time it is found, so they count as
+        // referenced only once per distinct parent
+        if *count == 1 {
+            stack.extend(children(term));
+        }
+    }
+    counts.retain(|_, count| *count > 1);
+    counts
+}
+
+fn children(term: &Rc<Term>) -> impl Iterator<Item = &Rc<Term>> {
+    let (args, inner): (&[Rc<Term>], _) = match term.as_ref() {
+        Term::App(f, args) => (args, Some(f)),
+        Term::Op(_, args) | Term::IndexedOp(_, _, args) => (args, None),
+        Term::Quant(_, _, inner, _) | Term::Choice(_, inner) | Term::Lambda(_, inner) => {
+            (&[], Some(inner))
+        }
+        // The values in `let` bindings are not visited, since they are printed together with the
+        // binding list
+        Term::Let(_, inner) => (&[], Some(inner)),
+        Term::Terminal(_) => (&[], None),
+    };
+    inner.into_iter().chain(args)
+}
+
+struct ElidingPrinter {
+    shared: AHashMap<Rc<Term>, usize>,
+    sizes: AHashMap<Rc<Term>, usize>,
+    indices: AHashMap<Rc<Term>, usize>,
+    queue: VecDeque<(usize, Rc<Term>)>,
+}
+
+impl ElidingPrinter {
+    fn write_term(&mut self, f: &mut fmt::Formatter, term: &Rc<Term>, elide: bool) -> fmt::Result {
+        let size = tree_size(term, &mut self.sizes);
+        if elide && size >= MIN_ELIDED_TERM_SIZE && self.shared.contains_key(term) {
+            let next = self.indices.len() + 1;
+            let i = *self.indices.entry(term.clone()).or_insert_with(|| {
+                self.queue.push_back((next, term.clone()));
+                next
+            });
+            return write!(f, "...#{}", i);
+        }
+        match term.as_ref() {
+            Term::App(func, args) => {
+                write!(f, "(")?;
+                self.write_term(f, func, true)?;
+                self.write_args(f, args)
+            }
+            Term::Op(op, _) if op.is_constant() => write!(f, "{}", op),
+            Term::Op(op, args) => {
+                write!(f, "({}", op)?;
+                self.write_args(f, args)
+            }
+            Term::IndexedOp(op, indices, _) if op.is_constant() => {
+                write!(f, "{}", IndexedHead(*op, indices))
+            }
+            Term::IndexedOp(op, indices, args) => {
+                write!(f, "({}", IndexedHead(*op, indices))?;
+                self.write_args(f, args)
+            }
+            Term::Quant(q, bindings, inner, _) => {
+                write!(f, "({} {} ", q, bindings)?;
+                self.write_term(f, inner, true)?;
+                write!(f, ")")
+            }
+            Term::Choice((name, sort), inner) => {
+                write!(f, "(choice (({} {})) ", name, sort)?;
+                self.write_term(f, inner, true)?;
+                write!(f, ")")
+            }
+            Term::Let(bindings, inner) => {
+                write!(f, "(let {} ", bindings)?;
+                self.write_term(f, inner, true)?;
+                write!(f, ")")
+            }
+            Term::Lambda(bindings, inner) => {
+                write!(f, "(lambda {} ", bindings)?;
+                self.write_term(f, inner, true)?;
+                write!(f, ")")
+            }
+            Term::Terminal(_) => write!(f, "{}", term),
+        }
+    }
+
+    fn write_args(&mut self, f: &mut fmt::Formatter, args: &[Rc<Term>]) -> fmt::Result {
+        for a in args {
+            write!(f, " ")?;
+            self.write_term(f, a, true)?;
+        }
+        write!(f, ")")
+    }
+}
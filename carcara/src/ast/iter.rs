@@ -1,4 +1,5 @@
 use super::*;
+use alloc::{vec, vec::Vec};
 
 /// An iterator over the proof commands in a proof.
 ///
@@ -25,7 +26,7 @@ use super::*;
 ///     (step t5 (cl) :rule resolution :premises (t4 h1 h2))
 /// "
 /// .as_bytes();
-/// let (_, proof, _) = parser::parse_instance("".as_bytes(), proof, true, false, false)?;
+/// let (_, proof, _) = parser::parse_instance("".as_bytes(), proof, Default::default())?;
 /// let ids: Vec<_> = proof.iter().map(|c| c.id()).collect();
 /// assert_eq!(ids, ["h1", "h2", "t3", "t3.t1", "t3.t2", "t3", "t4", "t5"]);
 /// # Ok(())
@@ -67,6 +68,18 @@ impl<'a> ProofIter<'a> {
         }
     }
 
+    /// Returns `true` if a premise index of the form (depth, index in subproof) refers to a command
+    /// that can be used as a premise by the last command that was returned. That is, a command that
+    /// comes before it, either in the current subproof or in one of the enclosing subproofs. Commands
+    /// in subproofs that were already closed are not visible, and an open subproof is also not
+    /// visible from inside of it.
+    pub fn is_premise_visible(&self, (depth, index): (usize, usize)) -> bool {
+        match self.stack.get(depth) {
+            Some(&(i, _)) => index + 1 < i,
+            None => false,
+        }
+    }
+
     /// Returns the command referenced by a premise index of the form (depth, index in subproof).
     /// This method may panic if the premise index does not refer to a valid command.
     pub fn get_premise(&self, (depth, index): (usize, usize)) -> &ProofCommand {
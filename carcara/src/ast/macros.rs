@@ -30,7 +30,7 @@
 /// # use carcara::{ast::*, match_term, parser::*};
 /// # pub fn parse_term(input: &str) -> Rc<Term> {
 /// #     let mut pool = TermPool::new();
-/// #     let mut parser = Parser::new(&mut pool, input.as_bytes(), true, false, false).unwrap();
+/// #     let parser = Parser::new(&mut pool, ParserConfig::default(), input.as_bytes()).unwrap();
 /// #     parser.parse_term().unwrap()
 /// # }
 /// # let t = parse_term("(and (=> false false) (> (+ 0 0) 0))");
@@ -50,7 +50,7 @@
 /// # use carcara::{ast::*, match_term, parser::*};
 /// # pub fn parse_term(input: &str) -> Rc<Term> {
 /// #     let mut pool = TermPool::new();
-/// #     let mut parser = Parser::new(&mut pool, input.as_bytes(), true, false, false).unwrap();
+/// #     let parser = Parser::new(&mut pool, ParserConfig::default(), input.as_bytes()).unwrap();
 /// #     parser.parse_term().unwrap()
 /// # }
 /// # let t = parse_term("(forall ((x Int) (y Int)) (> x y))");
@@ -73,7 +73,7 @@ macro_rules! match_term {
         if $var.is_bool_false() { Some(()) } else { None }
     };
     ((forall ... $args:tt) = $var:expr) => {
-        if let $crate::ast::Term::Quant($crate::ast::Quantifier::Forall, bindings, inner) =
+        if let $crate::ast::Term::Quant($crate::ast::Quantifier::Forall, bindings, inner, _) =
             &$var as &$crate::ast::Term
         {
             match_term!($args = inner).and_then(|inner| Some((bindings, inner)))
@@ -82,7 +82,7 @@ macro_rules! match_term {
         }
     };
     ((exists ... $args:tt) = $var:expr) => {
-        if let $crate::ast::Term::Quant($crate::ast::Quantifier::Exists, bindings, inner) =
+        if let $crate::ast::Term::Quant($crate::ast::Quantifier::Exists, bindings, inner, _) =
             &$var as &$crate::ast::Term
         {
             match_term!($args = inner).and_then(|inner| Some((bindings, inner)))
@@ -136,6 +136,8 @@ macro_rules! match_term {
     (@GET_VARIANT *)        => { $crate::ast::Operator::Mult };
     (@GET_VARIANT div)      => { $crate::ast::Operator::IntDiv };
     (@GET_VARIANT /)        => { $crate::ast::Operator::RealDiv };
+    (@GET_VARIANT mod)      => { $crate::ast::Operator::Mod };
+    (@GET_VARIANT abs)      => { $crate::ast::Operator::Abs };
     (@GET_VARIANT <)        => { $crate::ast::Operator::LessThan };
     (@GET_VARIANT >)        => { $crate::ast::Operator::GreaterThan };
     (@GET_VARIANT <=)       => { $crate::ast::Operator::LessEq };
@@ -197,7 +199,9 @@ macro_rules! build_term {
 ///
 /// # Examples
 ///
-/// ```
+/// Since this macro is not exported, this example can't be run as a doctest.
+///
+/// ```ignore
 /// # use carcara::impl_str_conversion_traits;
 /// # use std::str::FromStr;
 /// #[derive(Debug, PartialEq)]
@@ -221,7 +225,7 @@ macro_rules! build_term {
 /// ```
 macro_rules! impl_str_conversion_traits {
     ($enum_name:ident { $($variant:ident: $str:literal),* $(,)? }) => {
-        impl std::str::FromStr for $enum_name {
+        impl core::str::FromStr for $enum_name {
             type Err = ();
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -232,8 +236,8 @@ macro_rules! impl_str_conversion_traits {
             }
         }
 
-        impl std::fmt::Display for $enum_name {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        impl core::fmt::Display for $enum_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
                 let s = match self {
                     $($enum_name::$variant => $str,)*
                 };
@@ -300,8 +304,8 @@ mod tests {
             (declare-fun q () Bool)
         ";
         let mut pool = TermPool::new();
-        let bool_sort = pool.add(Term::Sort(Sort::Bool));
-        let int_sort = pool.add(Term::Sort(Sort::Int));
+        let bool_sort = pool.add_sort(Sort::Bool);
+        let int_sort = pool.add_sort(Sort::Int);
 
         let [one, two, three] = [1, 2, 3].map(|n| pool.add(Term::integer(n)));
         let [a, b] = ["a", "b"].map(|s| pool.add(Term::var(s, int_sort.clone())));
@@ -4,29 +4,53 @@
 
 #[macro_use]
 mod macros;
+mod builder;
 mod deep_eq;
+mod dependencies;
+mod elision;
 mod iter;
+mod nary;
 mod pool;
-pub(crate) mod printer;
+pub mod printer;
 mod rc;
+#[cfg(feature = "serde")]
+mod serialization;
 mod substitution;
 #[cfg(test)]
 mod tests;
+mod traversal;
+#[cfg(feature = "std")]
+mod tstp;
 
+pub use builder::{ProofBuilder, ProofBuilderError, StepBuilder};
 pub use deep_eq::{are_alpha_equivalent, deep_eq, tracing_deep_eq};
+pub use dependencies::{DependencyGraph, DependencySet};
+pub use elision::ElidedClause;
 pub use iter::ProofIter;
-pub use pool::TermPool;
-pub use printer::print_proof;
+pub use nary::{expand_nary, normalize_nary};
+pub use pool::{PoolStats, TermPool};
+#[cfg(feature = "std")]
+pub use printer::{print_proof, write_alethe_proof, write_proof};
 pub use rc::Rc;
-pub use substitution::{Substitution, SubstitutionError};
-
+#[cfg(feature = "serde")]
+pub use serialization::PoolSeed;
+pub use substitution::{
+    anchor_mappings, compose_subproof_substitutions, Substitution, SubstitutionError,
+};
+pub use traversal::{Subterms, TermVisitor};
+#[cfg(feature = "std")]
+pub use tstp::write_tstp_proof;
+
+#[cfg(feature = "std")]
 pub(crate) use deep_eq::{DeepEq, DeepEqualityChecker};
 
+use crate::num::{euclidean_div_mod, Integer, Rational};
+use crate::utils::{AHashMap, AHashSet};
+use alloc::{string::String, vec::Vec};
+use core::hash::Hash;
+
+#[cfg(feature = "std")]
 use crate::checker::error::CheckerError;
-use ahash::AHashSet;
-use rug::Integer;
-use rug::Rational;
-use std::hash::Hash;
 
 /// The prelude of an SMT-LIB problem instance.
 ///
@@ -34,22 +58,102 @@ use std::hash::Hash;
 #[derive(Debug, Clone, Default)]
 pub struct ProblemPrelude {
     pub(crate) sort_declarations: Vec<(String, usize)>,
-    pub(crate) function_declarations: Vec<(String, Rc<Term>)>,
+    pub(crate) function_declarations: Vec<(String, Rc<Sort>)>,
     pub(crate) logic: Option<String>,
 }
 
+#[cfg(feature = "std")]
+impl ProblemPrelude {
+    /// Adds the declarations of `other` after the ones in this prelude. If `other` sets the logic,
+    /// it replaces the logic of this prelude, like a later `set-logic` command would.
+    pub(crate) fn extend(&mut self, other: Self) {
+        self.sort_declarations.extend(other.sort_declarations);
+        self.function_declarations
+            .extend(other.function_declarations);
+        if other.logic.is_some() {
+            self.logic = other.logic;
+        }
+    }
+}
+
 /// A proof in the Alethe format.
 #[derive(Debug, Clone)]
 pub struct Proof {
     pub premises: AHashSet<Rc<Term>>,
     pub commands: Vec<ProofCommand>,
+
+    /// The location of each command in the proof file, in the order the commands are returned by
+    /// [`Proof::iter`]. For subproofs, this is the location of the `anchor` command. This is empty
+    /// if the proof was not parsed from an Alethe proof file, or if it was changed after parsing,
+    /// for example by elaboration.
+    pub spans: Vec<SourceSpan>,
+
+    /// The attributes given in `set-info` commands in the proof, like the name and version of the
+    /// solver that produced it, in the order they appear. Each attribute is a pair of its keyword,
+    /// without the leading `:`, and its value as written in the proof. Values that are a single
+    /// symbol or string literal are unquoted.
+    pub metadata: Vec<(String, String)>,
+
+    /// The assertions in the problem that were given a name with the `:named` attribute, as pairs
+    /// of the name and the asserted term, in the order they appear in the problem. Names given to
+    /// subterms of an assertion are not included.
+    pub named_assertions: Vec<(String, Rc<Term>)>,
+
+    /// The comments in the proof file, in the order they appear. These are only kept if the
+    /// `preserve_comments` option of the parser is enabled, so they can be written back when the
+    /// proof is printed. Otherwise, this is empty.
+    pub comments: Vec<Comment>,
 }
 
 impl Proof {
     /// Returns an iterator over the proof commands. See [`ProofIter`].
-    pub fn iter(&self) -> ProofIter {
+    pub fn iter(&self) -> ProofIter<'_> {
         ProofIter::new(&self.commands)
     }
+
+    /// Returns the index in `commands` and the name of each `assume` command whose term is a named
+    /// assertion. This can be used to relate an unsatisfiable core given by the names of the
+    /// assertions to the commands of the proof. If many assertions with different names have the
+    /// same term, the first name is used.
+    pub fn named_assumes(&self) -> Vec<(usize, &str)> {
+        let mut names = AHashMap::new();
+        for (name, term) in &self.named_assertions {
+            names.entry(term).or_insert(name.as_str());
+        }
+        self.commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, command)| match command {
+                ProofCommand::Assume { term, .. } => names.get(term).map(|&name| (i, name)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// The location of a proof command in the proof file. Both positions are given as a line and a
+/// column, starting at one.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// The position of the opening parenthesis of the command.
+    pub start: (usize, usize),
+
+    /// The position of the closing parenthesis of the command.
+    pub end: (usize, usize),
+}
+
+/// A comment in the proof file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comment {
+    /// The text of the comment, without the leading `;` and the line terminator.
+    pub text: String,
+
+    /// The location of the comment. The start is the position of the `;`, and the end is the
+    /// position right after the last character of the comment, which is usually the line
+    /// terminator.
+    pub span: SourceSpan,
 }
 
 /// A proof command.
@@ -84,7 +188,7 @@ impl ProofCommand {
     /// subproof.
     pub fn clause(&self) -> &[Rc<Term>] {
         match self {
-            ProofCommand::Assume { id: _, term } => std::slice::from_ref(term),
+            ProofCommand::Assume { id: _, term } => core::slice::from_ref(term),
             ProofCommand::Step(ProofStep { clause, .. }) => clause,
             ProofCommand::Subproof(s) => s.commands.last().unwrap().clause(),
         }
@@ -155,6 +259,7 @@ pub enum ProofArg {
     Assign(String, Rc<Term>),
 }
 
+#[cfg(feature = "std")]
 impl ProofArg {
     /// If this argument is a "term style" argument, extracts that term from it. Otherwise, returns
     /// an error.
@@ -176,6 +281,7 @@ impl ProofArg {
 }
 
 /// The operator of an operation term.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Operator {
     // Logic
@@ -252,6 +358,290 @@ pub enum Operator {
 
     /// The `store` operator.
     Store,
+
+    // Bitvectors
+    /// The `concat` operator.
+    Concat,
+
+    /// The `bvnot` operator.
+    BvNot,
+
+    /// The `bvneg` operator.
+    BvNeg,
+
+    /// The `bvand` operator.
+    BvAnd,
+
+    /// The `bvor` operator.
+    BvOr,
+
+    /// The `bvxor` operator.
+    BvXor,
+
+    /// The `bvnand` operator.
+    BvNand,
+
+    /// The `bvnor` operator.
+    BvNor,
+
+    /// The `bvxnor` operator.
+    BvXnor,
+
+    /// The `bvcomp` operator.
+    BvComp,
+
+    /// The `bvadd` operator.
+    BvAdd,
+
+    /// The `bvsub` operator.
+    BvSub,
+
+    /// The `bvmul` operator.
+    BvMul,
+
+    /// The `bvudiv` operator.
+    BvUDiv,
+
+    /// The `bvurem` operator.
+    BvURem,
+
+    /// The `bvsdiv` operator.
+    BvSDiv,
+
+    /// The `bvsrem` operator.
+    BvSRem,
+
+    /// The `bvsmod` operator.
+    BvSMod,
+
+    /// The `bvshl` operator.
+    BvShl,
+
+    /// The `bvlshr` operator.
+    BvLShr,
+
+    /// The `bvashr` operator.
+    BvAShr,
+
+    /// The `bvult` operator.
+    BvULt,
+
+    /// The `bvule` operator.
+    BvULe,
+
+    /// The `bvugt` operator.
+    BvUGt,
+
+    /// The `bvuge` operator.
+    BvUGe,
+
+    /// The `bvslt` operator.
+    BvSLt,
+
+    /// The `bvsle` operator.
+    BvSLe,
+
+    /// The `bvsgt` operator.
+    BvSGt,
+
+    /// The `bvsge` operator.
+    BvSGe,
+
+    // Strings
+    /// The `str.++` operator.
+    StrConcat,
+
+    /// The `str.len` operator.
+    StrLen,
+
+    /// The `str.<` operator.
+    StrLt,
+
+    /// The `str.<=` operator.
+    StrLe,
+
+    /// The `str.at` operator.
+    StrAt,
+
+    /// The `str.substr` operator.
+    StrSubstr,
+
+    /// The `str.prefixof` operator.
+    StrPrefixOf,
+
+    /// The `str.suffixof` operator.
+    StrSuffixOf,
+
+    /// The `str.contains` operator.
+    StrContains,
+
+    /// The `str.indexof` operator.
+    StrIndexOf,
+
+    /// The `str.replace` operator.
+    StrReplace,
+
+    /// The `str.replace_all` operator.
+    StrReplaceAll,
+
+    /// The `str.replace_re` operator.
+    StrReplaceRe,
+
+    /// The `str.replace_re_all` operator.
+    StrReplaceReAll,
+
+    /// The `str.is_digit` operator.
+    StrIsDigit,
+
+    /// The `str.to_code` operator.
+    StrToCode,
+
+    /// The `str.from_code` operator.
+    StrFromCode,
+
+    /// The `str.to_int` operator.
+    StrToInt,
+
+    /// The `str.from_int` operator.
+    StrFromInt,
+
+    /// The `str.to_re` operator.
+    StrToRe,
+
+    /// The `str.in_re` operator.
+    StrInRe,
+
+    // Regular expressions
+    /// The `re.none` operator.
+    ReNone,
+
+    /// The `re.all` operator.
+    ReAll,
+
+    /// The `re.allchar` operator.
+    ReAllChar,
+
+    /// The `re.++` operator.
+    ReConcat,
+
+    /// The `re.union` operator.
+    ReUnion,
+
+    /// The `re.inter` operator.
+    ReInter,
+
+    /// The `re.*` operator.
+    ReStar,
+
+    /// The `re.+` operator.
+    RePlus,
+
+    /// The `re.opt` operator.
+    ReOpt,
+
+    /// The `re.range` operator.
+    ReRange,
+
+    /// The `re.comp` operator.
+    ReComp,
+
+    /// The `re.diff` operator.
+    ReDiff,
+
+    // Floating-point numbers
+    /// The `fp` operator.
+    Fp,
+
+    /// The `fp.abs` operator.
+    FpAbs,
+
+    /// The `fp.neg` operator.
+    FpNeg,
+
+    /// The `fp.add` operator.
+    FpAdd,
+
+    /// The `fp.sub` operator.
+    FpSub,
+
+    /// The `fp.mul` operator.
+    FpMul,
+
+    /// The `fp.div` operator.
+    FpDiv,
+
+    /// The `fp.fma` operator.
+    FpFma,
+
+    /// The `fp.sqrt` operator.
+    FpSqrt,
+
+    /// The `fp.rem` operator.
+    FpRem,
+
+    /// The `fp.roundToIntegral` operator.
+    FpRoundToIntegral,
+
+    /// The `fp.min` operator.
+    FpMin,
+
+    /// The `fp.max` operator.
+    FpMax,
+
+    /// The `fp.leq` operator.
+    FpLeq,
+
+    /// The `fp.lt` operator.
+    FpLt,
+
+    /// The `fp.geq` operator.
+    FpGeq,
+
+    /// The `fp.gt` operator.
+    FpGt,
+
+    /// The `fp.eq` operator.
+    FpEq,
+
+    /// The `fp.isNormal` operator.
+    FpIsNormal,
+
+    /// The `fp.isSubnormal` operator.
+    FpIsSubnormal,
+
+    /// The `fp.isZero` operator.
+    FpIsZero,
+
+    /// The `fp.isInfinite` operator.
+    FpIsInfinite,
+
+    /// The `fp.isNaN` operator.
+    FpIsNaN,
+
+    /// The `fp.isNegative` operator.
+    FpIsNegative,
+
+    /// The `fp.isPositive` operator.
+    FpIsPositive,
+
+    /// The `fp.to_real` operator.
+    FpToReal,
+
+    // Rounding modes
+    /// The `RNE` rounding mode.
+    RoundNearestTiesToEven,
+
+    /// The `RNA` rounding mode.
+    RoundNearestTiesToAway,
+
+    /// The `RTP` rounding mode.
+    RoundTowardPositive,
+
+    /// The `RTN` rounding mode.
+    RoundTowardNegative,
+
+    /// The `RTZ` rounding mode.
+    RoundTowardZero,
 }
 
 impl_str_conversion_traits!(Operator {
@@ -281,25 +671,301 @@ impl_str_conversion_traits!(Operator {
 
     Select: "select",
     Store: "store",
+
+    Concat: "concat",
+    BvNot: "bvnot",
+    BvNeg: "bvneg",
+    BvAnd: "bvand",
+    BvOr: "bvor",
+    BvXor: "bvxor",
+    BvNand: "bvnand",
+    BvNor: "bvnor",
+    BvXnor: "bvxnor",
+    BvComp: "bvcomp",
+    BvAdd: "bvadd",
+    BvSub: "bvsub",
+    BvMul: "bvmul",
+    BvUDiv: "bvudiv",
+    BvURem: "bvurem",
+    BvSDiv: "bvsdiv",
+    BvSRem: "bvsrem",
+    BvSMod: "bvsmod",
+    BvShl: "bvshl",
+    BvLShr: "bvlshr",
+    BvAShr: "bvashr",
+    BvULt: "bvult",
+    BvULe: "bvule",
+    BvUGt: "bvugt",
+    BvUGe: "bvuge",
+    BvSLt: "bvslt",
+    BvSLe: "bvsle",
+    BvSGt: "bvsgt",
+    BvSGe: "bvsge",
+
+    StrConcat: "str.++",
+    StrLen: "str.len",
+    StrLt: "str.<",
+    StrLe: "str.<=",
+    StrAt: "str.at",
+    StrSubstr: "str.substr",
+    StrPrefixOf: "str.prefixof",
+    StrSuffixOf: "str.suffixof",
+    StrContains: "str.contains",
+    StrIndexOf: "str.indexof",
+    StrReplace: "str.replace",
+    StrReplaceAll: "str.replace_all",
+    StrReplaceRe: "str.replace_re",
+    StrReplaceReAll: "str.replace_re_all",
+    StrIsDigit: "str.is_digit",
+    StrToCode: "str.to_code",
+    StrFromCode: "str.from_code",
+    StrToInt: "str.to_int",
+    StrFromInt: "str.from_int",
+    StrToRe: "str.to_re",
+    StrInRe: "str.in_re",
+
+    ReNone: "re.none",
+    ReAll: "re.all",
+    ReAllChar: "re.allchar",
+    ReConcat: "re.++",
+    ReUnion: "re.union",
+    ReInter: "re.inter",
+    ReStar: "re.*",
+    RePlus: "re.+",
+    ReOpt: "re.opt",
+    ReRange: "re.range",
+    ReComp: "re.comp",
+    ReDiff: "re.diff",
+
+    Fp: "fp",
+    FpAbs: "fp.abs",
+    FpNeg: "fp.neg",
+    FpAdd: "fp.add",
+    FpSub: "fp.sub",
+    FpMul: "fp.mul",
+    FpDiv: "fp.div",
+    FpFma: "fp.fma",
+    FpSqrt: "fp.sqrt",
+    FpRem: "fp.rem",
+    FpRoundToIntegral: "fp.roundToIntegral",
+    FpMin: "fp.min",
+    FpMax: "fp.max",
+    FpLeq: "fp.leq",
+    FpLt: "fp.lt",
+    FpGeq: "fp.geq",
+    FpGt: "fp.gt",
+    FpEq: "fp.eq",
+    FpIsNormal: "fp.isNormal",
+    FpIsSubnormal: "fp.isSubnormal",
+    FpIsZero: "fp.isZero",
+    FpIsInfinite: "fp.isInfinite",
+    FpIsNaN: "fp.isNaN",
+    FpIsNegative: "fp.isNegative",
+    FpIsPositive: "fp.isPositive",
+    FpToReal: "fp.to_real",
+
+    RoundNearestTiesToEven: "RNE",
+    RoundNearestTiesToAway: "RNA",
+    RoundTowardPositive: "RTP",
+    RoundTowardNegative: "RTN",
+    RoundTowardZero: "RTZ",
+});
+
+/// An operator that is parameterized by one or more numeral indices, like `(_ extract i j)`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexedOperator {
+    /// The `extract` operator, indexed by the positions of the highest and lowest bits that are
+    /// extracted.
+    BvExtract,
+
+    /// The `repeat` operator, indexed by the number of repetitions.
+    BvRepeat,
+
+    /// The `zero_extend` operator, indexed by the number of bits added.
+    BvZeroExtend,
+
+    /// The `sign_extend` operator, indexed by the number of bits added.
+    BvSignExtend,
+
+    /// The `rotate_left` operator, indexed by the number of bits rotated.
+    BvRotateLeft,
+
+    /// The `rotate_right` operator, indexed by the number of bits rotated.
+    BvRotateRight,
+
+    /// The `re.^` operator, indexed by the number of repetitions of the regular expression.
+    RePower,
+
+    /// The `re.loop` operator, indexed by the minimum and maximum number of repetitions of the
+    /// regular expression.
+    ReLoop,
+
+    /// The `divisible` predicate, indexed by the divisor.
+    Divisible,
+
+    /// The `to_fp` operator, indexed by the exponent and significand widths of the result.
+    ToFp,
+
+    /// The `to_fp_unsigned` operator, indexed by the exponent and significand widths of the result.
+    ToFpUnsigned,
+
+    /// The `fp.to_ubv` operator, indexed by the width of the result.
+    FpToUbv,
+
+    /// The `fp.to_sbv` operator, indexed by the width of the result.
+    FpToSbv,
+
+    /// The `+oo` constant, indexed by its exponent and significand widths.
+    FpPlusInfinity,
+
+    /// The `-oo` constant, indexed by its exponent and significand widths.
+    FpMinusInfinity,
+
+    /// The `+zero` constant, indexed by its exponent and significand widths.
+    FpPlusZero,
+
+    /// The `-zero` constant, indexed by its exponent and significand widths.
+    FpMinusZero,
+
+    /// The `NaN` constant, indexed by its exponent and significand widths.
+    FpNaN,
+}
+
+impl_str_conversion_traits!(IndexedOperator {
+    BvExtract: "extract",
+    BvRepeat: "repeat",
+    BvZeroExtend: "zero_extend",
+    BvSignExtend: "sign_extend",
+    BvRotateLeft: "rotate_left",
+    BvRotateRight: "rotate_right",
+    RePower: "re.^",
+    ReLoop: "re.loop",
+    Divisible: "divisible",
+    ToFp: "to_fp",
+    ToFpUnsigned: "to_fp_unsigned",
+    FpToUbv: "fp.to_ubv",
+    FpToSbv: "fp.to_sbv",
+    FpPlusInfinity: "+oo",
+    FpMinusInfinity: "-oo",
+    FpPlusZero: "+zero",
+    FpMinusZero: "-zero",
+    FpNaN: "NaN",
 });
 
+impl IndexedOperator {
+    /// Returns the number of indices this operator takes.
+    pub fn num_indices(self) -> usize {
+        match self {
+            IndexedOperator::BvExtract | IndexedOperator::ReLoop => 2,
+            IndexedOperator::ToFp | IndexedOperator::ToFpUnsigned => 2,
+            _ if self.is_constant() => 2,
+            _ => 1,
+        }
+    }
+
+    /// Returns `true` if this operator is a constant, like `(_ +oo 8 24)`, which is not applied to
+    /// any arguments.
+    pub fn is_constant(self) -> bool {
+        matches!(
+            self,
+            IndexedOperator::FpPlusInfinity
+                | IndexedOperator::FpMinusInfinity
+                | IndexedOperator::FpPlusZero
+                | IndexedOperator::FpMinusZero
+                | IndexedOperator::FpNaN
+        )
+    }
+}
+
+/// The SMT-LIB attribute that defines the meaning of an operator applied to more than two
+/// arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NaryAttribute {
+    /// `(op a b c)` is equivalent to `(op (op a b) c)`.
+    LeftAssoc,
+
+    /// `(op a b c)` is equivalent to `(op a (op b c))`.
+    RightAssoc,
+
+    /// `(op a b c)` is equivalent to `(and (op a b) (op b c))`.
+    Chainable,
+
+    /// `(op a b c)` is equivalent to `(and (op a b) (op a c) (op b c))`.
+    Pairwise,
+}
+
+impl Operator {
+    /// Returns the attribute that defines the meaning of this operator when it is applied to more
+    /// than two arguments, or `None` if the operator does not accept more than two arguments.
+    pub fn nary_attribute(self) -> Option<NaryAttribute> {
+        use Operator::*;
+
+        Some(match self {
+            And | Or | Xor | Add | Sub | Mult | IntDiv | RealDiv => NaryAttribute::LeftAssoc,
+            Concat | BvAnd | BvOr | BvXor | BvAdd | BvMul => NaryAttribute::LeftAssoc,
+            StrConcat | ReConcat | ReUnion | ReInter | ReDiff => NaryAttribute::LeftAssoc,
+            Implies => NaryAttribute::RightAssoc,
+            Equals | LessThan | GreaterThan | LessEq | GreaterEq => NaryAttribute::Chainable,
+            StrLt | StrLe => NaryAttribute::Chainable,
+            FpLeq | FpLt | FpGeq | FpGt | FpEq => NaryAttribute::Chainable,
+            Distinct => NaryAttribute::Pairwise,
+            Not | Ite | Mod | Abs | ToReal | ToInt | IsInt | Select | Store => return None,
+            BvNot | BvNeg | BvNand | BvNor | BvXnor | BvComp | BvSub | BvUDiv | BvURem | BvSDiv
+            | BvSRem | BvSMod | BvShl | BvLShr | BvAShr | BvULt | BvULe | BvUGt | BvUGe | BvSLt
+            | BvSLe | BvSGt | BvSGe => return None,
+            StrLen | StrAt | StrSubstr | StrPrefixOf | StrSuffixOf | StrContains | StrIndexOf
+            | StrReplace | StrReplaceAll | StrReplaceRe | StrReplaceReAll | StrIsDigit
+            | StrToCode | StrFromCode | StrToInt | StrFromInt | StrToRe | StrInRe | ReNone
+            | ReAll | ReAllChar | ReStar | RePlus | ReOpt | ReRange | ReComp => return None,
+            Fp | FpAbs | FpNeg | FpAdd | FpSub | FpMul | FpDiv | FpFma | FpSqrt | FpRem
+            | FpRoundToIntegral | FpMin | FpMax | FpIsNormal | FpIsSubnormal | FpIsZero
+            | FpIsInfinite | FpIsNaN | FpIsNegative | FpIsPositive | FpToReal => return None,
+            RoundNearestTiesToEven
+            | RoundNearestTiesToAway
+            | RoundTowardPositive
+            | RoundTowardNegative
+            | RoundTowardZero => return None,
+        })
+    }
+
+    /// Returns `true` if this operator is a constant, like `re.none` or a rounding mode. Constants
+    /// are written without parentheses, instead of as an application to no arguments.
+    pub fn is_constant(self) -> bool {
+        matches!(
+            self,
+            Operator::ReNone
+                | Operator::ReAll
+                | Operator::ReAllChar
+                | Operator::RoundNearestTiesToEven
+                | Operator::RoundNearestTiesToAway
+                | Operator::RoundTowardPositive
+                | Operator::RoundTowardNegative
+                | Operator::RoundTowardZero
+        )
+    }
+}
+
 /// A variable and an associated sort.
-pub type SortedVar = (String, Rc<Term>);
+pub type SortedVar = (String, Rc<Sort>);
 
 /// The sort of a term.
+///
+/// Sorts are stored in the term pool separately from terms, and, like terms, equal sorts added to
+/// the same pool share the same allocation. See [`TermPool::add_sort`].
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Sort {
     /// A function sort.
     ///
-    /// The last term indicates the return sort of the function. The remaining terms are the sorts
-    /// of the parameters of the function.
-    Function(Vec<Rc<Term>>),
+    /// The last sort is the return sort of the function. The remaining sorts are the sorts of the
+    /// parameters of the function.
+    Function(Vec<Rc<Sort>>),
 
     /// A user-declared sort, from a `declare-sort` command.
     ///
-    /// The associated string is the sort name, and the associated terms are the sort arguments for
+    /// The associated string is the sort name, and the associated sorts are the sort arguments for
     /// this sort.
-    Atom(String, Vec<Rc<Term>>),
+    Atom(String, Vec<Rc<Sort>>),
 
     /// The `Bool` primitive sort.
     Bool,
@@ -313,13 +979,50 @@ pub enum Sort {
     /// The `String` primitive sort.
     String,
 
+    /// The `RegLan` primitive sort, of regular expressions over strings.
+    RegLan,
+
     /// An `Array` sort.
     ///
-    /// The two associated terms are the sort arguments for this sort.
-    Array(Rc<Term>, Rc<Term>),
+    /// The two associated sorts are the sort arguments for this sort.
+    Array(Rc<Sort>, Rc<Sort>),
+
+    /// A bitvector sort, of the form `(_ BitVec n)`.
+    ///
+    /// The associated number is the width of the bitvectors, which is always positive.
+    BitVec(u64),
+
+    /// A floating-point sort, of the form `(_ FloatingPoint eb sb)`.
+    ///
+    /// The associated numbers are the exponent width and the significand width, including the
+    /// hidden bit. Both are always greater than one.
+    FloatingPoint(u64, u64),
+
+    /// The `RoundingMode` sort, of the rounding modes used by floating-point operations.
+    RoundingMode,
+}
+
+impl Sort {
+    /// Returns the width of this sort if it is a bitvector sort, or `None` otherwise.
+    pub fn as_bitvec_width(&self) -> Option<u64> {
+        match self {
+            Sort::BitVec(width) => Some(*width),
+            _ => None,
+        }
+    }
+
+    /// Returns the exponent and significand widths of this sort if it is a floating-point sort, or
+    /// `None` otherwise.
+    pub fn as_float_widths(&self) -> Option<(u64, u64)> {
+        match self {
+            Sort::FloatingPoint(eb, sb) => Some((*eb, *sb)),
+            _ => None,
+        }
+    }
 }
 
 /// A quantifier, either `forall` or `exists`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Quantifier {
     /// The `forall` quantifier.
@@ -329,7 +1032,7 @@ pub enum Quantifier {
     Exists,
 }
 
-impl std::ops::Not for Quantifier {
+impl core::ops::Not for Quantifier {
     type Output = Self;
 
     fn not(self) -> Self::Output {
@@ -340,24 +1043,27 @@ impl std::ops::Not for Quantifier {
     }
 }
 
-/// A list of bindings, where each binding is a variable associated with a term.
+/// A list of bindings, where each binding is a variable associated with a value of type `T`.
 ///
-/// Depending on the context, it can be a "sort" binding list (like the ones present in quantifier
-/// terms) where the associated term of each variable is its sort; or a "value" binding list (like
-/// the ones present in `let` terms) where the associated term of each variable is its bound value.
+/// By default, this is a "sort" binding list (like the ones present in quantifier terms) where
+/// each variable is associated with its sort. A [`ValueBindingList`] (like the ones present in
+/// `let` terms) instead associates each variable with its bound value.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct BindingList(pub Vec<SortedVar>);
+pub struct BindingList<T = Rc<Sort>>(pub Vec<(String, T)>);
+
+/// A "value" binding list, where each variable is associated with the term it is bound to.
+pub type ValueBindingList = BindingList<Rc<Term>>;
 
-impl AsRef<[SortedVar]> for BindingList {
-    fn as_ref(&self) -> &[SortedVar] {
+impl<T> AsRef<[(String, T)]> for BindingList<T> {
+    fn as_ref(&self) -> &[(String, T)] {
         &self.0
     }
 }
 
-impl<'a> IntoIterator for &'a BindingList {
-    type Item = &'a SortedVar;
+impl<'a, T> IntoIterator for &'a BindingList<T> {
+    type Item = &'a (String, T);
 
-    type IntoIter = std::slice::Iter<'a, SortedVar>;
+    type IntoIter = core::slice::Iter<'a, (String, T)>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.iter()
@@ -366,8 +1072,10 @@ impl<'a> IntoIterator for &'a BindingList {
 
 impl BindingList {
     pub const EMPTY: &'static Self = &BindingList(Vec::new());
+}
 
-    pub fn iter(&self) -> std::slice::Iter<SortedVar> {
+impl<T> BindingList<T> {
+    pub fn iter(&self) -> core::slice::Iter<'_, (String, T)> {
         self.0.iter()
     }
 
@@ -379,7 +1087,7 @@ impl BindingList {
         self.len() == 0
     }
 
-    pub fn as_slice(&self) -> &[SortedVar] {
+    pub fn as_slice(&self) -> &[(String, T)] {
         self.0.as_slice()
     }
 }
@@ -396,17 +1104,20 @@ pub enum Term {
     /// An application of a bulit-in operator to one or more terms.
     Op(Operator, Vec<Rc<Term>>),
 
-    /// A sort.
-    Sort(Sort),
+    /// An application of a built-in indexed operator to one or more terms. The associated numbers
+    /// are the operator indices.
+    IndexedOp(IndexedOperator, Vec<u64>, Vec<Rc<Term>>),
 
-    /// A quantifier binder term.
-    Quant(Quantifier, BindingList, Rc<Term>),
+    /// A quantifier binder term. The last field holds the patterns given in `:pattern` attributes
+    /// of the inner term, which are used as triggers for quantifier instantiation. Each pattern is
+    /// a sequence of terms, and most quantifiers have no patterns.
+    Quant(Quantifier, BindingList, Rc<Term>, Vec<Vec<Rc<Term>>>),
 
     /// A `choice` term.
     Choice(SortedVar, Rc<Term>),
 
     /// A `let` binder term.
-    Let(BindingList, Rc<Term>),
+    Let(ValueBindingList, Rc<Term>),
 
     /// A `lambda` term.
     Lambda(BindingList, Rc<Term>),
@@ -435,8 +1146,13 @@ impl Term {
         Term::Terminal(Terminal::String(value.into()))
     }
 
+    /// Constructs a new bitvector term, with the given value and width.
+    pub fn bitvec(value: impl Into<Integer>, width: u64) -> Self {
+        Term::Terminal(Terminal::BitVec(value.into(), width))
+    }
+
     /// Constructs a new variable term.
-    pub fn var(name: impl Into<String>, sort: Rc<Term>) -> Self {
+    pub fn var(name: impl Into<String>, sort: Rc<Sort>) -> Self {
         Term::Terminal(Terminal::Var(Identifier::Simple(name.into()), sort))
     }
 
@@ -445,7 +1161,7 @@ impl Term {
     pub fn raw_sort(&self) -> Sort {
         let mut pool = TermPool::new();
         let added = pool.add(self.clone());
-        pool.sort(&added).clone()
+        pool.sort(&added).as_ref().clone()
     }
 
     /// Returns `true` if the term is a terminal.
@@ -494,13 +1210,34 @@ impl Term {
     /// `Some` if the term is:
     ///
     /// - A real or integer constant
-    /// - An application of the `/` or `div` operators on two real or integer constants
-    /// - An application of the unary `-` operator on one of the two previous cases
+    /// - An application of the `/` operator on two real or integer constants, where the second is
+    ///   not zero
+    /// - An application of the `div` operator on two integer constants, where the second is not
+    ///   zero. This uses the euclidean integer division defined by SMT-LIB, so `(div 7 2)` is 3
+    /// - An application of the `to_real` or `to_int` operators on one of the previous cases
+    /// - An application of the unary `-` operator on one of the previous cases
     pub fn as_fraction(&self) -> Option<Rational> {
         fn as_unsigned_fraction(term: &Term) -> Option<Rational> {
             match term {
-                Term::Op(Operator::IntDiv | Operator::RealDiv, args) if args.len() == 2 => {
-                    Some(args[0].as_signed_number()? / args[1].as_signed_number()?)
+                Term::Op(Operator::RealDiv, args) if args.len() == 2 => {
+                    let denominator = args[1].as_signed_number()?;
+                    if denominator == 0 {
+                        return None;
+                    }
+                    Some(args[0].as_signed_number()? / denominator)
+                }
+                Term::Op(Operator::IntDiv, args) if args.len() == 2 => {
+                    let (quotient, _) = euclidean_div_mod(
+                        &args[0].as_signed_number()?,
+                        &args[1].as_signed_number()?,
+                    )?;
+                    Some(quotient)
+                }
+                Term::Op(Operator::ToReal, args) => args[0].as_fraction(),
+                Term::Op(Operator::ToInt, args) => {
+                    let mut result = args[0].as_fraction()?;
+                    result.floor_mut();
+                    Some(result)
                 }
                 _ => term.as_number(),
             }
@@ -529,19 +1266,6 @@ impl Term {
         }
     }
 
-    /// Returns `true` if the term is a sort.
-    pub fn is_sort(&self) -> bool {
-        matches!(self, Term::Sort(_))
-    }
-
-    /// Tries to extract a sort from a term. Returns `Some` if the term is a sort.
-    pub fn as_sort(&self) -> Option<&Sort> {
-        match self {
-            Term::Sort(s) => Some(s),
-            _ => None,
-        }
-    }
-
     /// Tries to unwrap an operation term, returning the `Operator` and the arguments. Returns
     /// `None` if the term is not an operation term.
     pub fn unwrap_op(&self) -> Option<(Operator, &[Rc<Term>])> {
@@ -555,14 +1279,23 @@ impl Term {
     /// term. Returns `None` if the term is not a quantifier term.
     pub fn unwrap_quant(&self) -> Option<(Quantifier, &BindingList, &Rc<Term>)> {
         match self {
-            Term::Quant(q, b, t) => Some((*q, b, t)),
+            Term::Quant(q, b, t, _) => Some((*q, b, t)),
             _ => None,
         }
     }
 
+    /// Returns the patterns of a quantifier term. Returns an empty slice if the term is not a
+    /// quantifier term, or if it has no patterns.
+    pub fn patterns(&self) -> &[Vec<Rc<Term>>] {
+        match self {
+            Term::Quant(_, _, _, patterns) => patterns,
+            _ => &[],
+        }
+    }
+
     /// Tries to unwrap a `let` term, returning the bindings and the inner term. Returns `None` if
     /// the term is not a `let` term.
-    pub fn unwrap_let(&self) -> Option<(&BindingList, &Rc<Term>)> {
+    pub fn unwrap_let(&self) -> Option<(&ValueBindingList, &Rc<Term>)> {
         match self {
             Term::Let(b, t) => Some((b, t)),
             _ => None,
@@ -572,7 +1305,7 @@ impl Term {
     /// Returns `true` if the term is the boolean constant `true`.
     pub fn is_bool_true(&self) -> bool {
         if let Term::Terminal(Terminal::Var(Identifier::Simple(name), sort)) = self {
-            sort.as_sort() == Some(&Sort::Bool) && name == "true"
+            **sort == Sort::Bool && name == "true"
         } else {
             false
         }
@@ -581,7 +1314,7 @@ impl Term {
     /// Returns `true` if the term is the boolean constant `false`.
     pub fn is_bool_false(&self) -> bool {
         if let Term::Terminal(Terminal::Var(Identifier::Simple(name), sort)) = self {
-            sort.as_sort() == Some(&Sort::Bool) && name == "false"
+            **sort == Sort::Bool && name == "false"
         } else {
             false
         }
@@ -597,6 +1330,31 @@ impl Term {
 }
 
 impl Rc<Term> {
+    /// Returns the free variables in the term, that is, the variables that are not bound by a
+    /// quantifier, `choice`, `let` or `lambda` term. This uses the cache in the term pool, so it
+    /// is cheap to call repeatedly for the same term. See `TermPool::free_vars`.
+    pub fn free_vars<'p>(&self, pool: &'p mut TermPool) -> &'p AHashSet<Rc<Term>> {
+        pool.free_vars(self)
+    }
+
+    /// Returns `true` if the term contains no quantifier, `choice`, `let` or `lambda` terms. See
+    /// `TermPool::is_ground`.
+    pub fn is_ground(&self, pool: &TermPool) -> bool {
+        pool.is_ground(self)
+    }
+
+    /// Returns an iterator over the distinct subterms of the term, including itself. Subterms that
+    /// are shared are only yielded once. See [`Subterms`].
+    pub fn subterms(&self) -> Subterms<'_> {
+        Subterms::new(self)
+    }
+
+    /// Visits the distinct subterms of the term, including itself, with the given visitor. Subterms
+    /// that are shared are only visited once. See [`TermVisitor`].
+    pub fn traverse<V: TermVisitor + ?Sized>(&self, visitor: &mut V) {
+        traversal::traverse(self, visitor);
+    }
+
     /// Removes a leading negation from the term, if it exists. Same thing as `match_term!((not t)
     /// = term)`.
     pub fn remove_negation(&self) -> Option<&Self> {
@@ -605,6 +1363,7 @@ impl Rc<Term> {
 
     /// Removes a leading negation from the term, if it exists. If it doesn't, returns a
     /// `CheckerError::TermOfWrongForm` error. Same thing as `match_term_err!((not t) = term)`.
+    #[cfg(feature = "std")]
     pub fn remove_negation_err(&self) -> Result<&Self, CheckerError> {
         match_term_err!((not t) = self)
     }
@@ -627,18 +1386,21 @@ impl Rc<Term> {
     }
 
     /// Similar to `Term::as_number`, but returns a `CheckerError` on failure.
+    #[cfg(feature = "std")]
     pub fn as_number_err(&self) -> Result<Rational, CheckerError> {
         self.as_number()
             .ok_or_else(|| CheckerError::ExpectedAnyNumber(self.clone()))
     }
 
     /// Similar to `Term::as_signed_number`, but returns a `CheckerError` on failure.
+    #[cfg(feature = "std")]
     pub fn as_signed_number_err(&self) -> Result<Rational, CheckerError> {
         self.as_signed_number()
             .ok_or_else(|| CheckerError::ExpectedAnyNumber(self.clone()))
     }
 
     /// Similar to `Term::as_fraction`, but returns a `CheckerError` on failure.
+    #[cfg(feature = "std")]
     pub fn as_fraction_err(&self) -> Result<Rational, CheckerError> {
         self.as_fraction()
             .ok_or_else(|| CheckerError::ExpectedAnyNumber(self.clone()))
@@ -646,6 +1408,7 @@ impl Rc<Term> {
 
     /// Tries to unwrap an operation term, returning the `Operator` and the arguments. Returns a
     /// `CheckerError` if the term is not an operation term.
+    #[cfg(feature = "std")]
     pub fn unwrap_op_err(&self) -> Result<(Operator, &[Rc<Term>]), CheckerError> {
         self.unwrap_op()
             .ok_or_else(|| CheckerError::ExpectedOperationTerm(self.clone()))
@@ -653,6 +1416,7 @@ impl Rc<Term> {
 
     /// Tries to unwrap a quantifier term, returning the `Quantifier`, the bindings and the inner
     /// term. Returns a `CheckerError` if the term is not a quantifier term.
+    #[cfg(feature = "std")]
     pub fn unwrap_quant_err(&self) -> Result<(Quantifier, &BindingList, &Rc<Term>), CheckerError> {
         self.unwrap_quant()
             .ok_or_else(|| CheckerError::ExpectedQuantifierTerm(self.clone()))
@@ -660,7 +1424,8 @@ impl Rc<Term> {
 
     /// Tries to unwrap a `let` term, returning the bindings and the inner
     /// term. Returns a `CheckerError` if the term is not a `let` term.
-    pub fn unwrap_let_err(&self) -> Result<(&BindingList, &Rc<Term>), CheckerError> {
+    #[cfg(feature = "std")]
+    pub fn unwrap_let_err(&self) -> Result<(&ValueBindingList, &Rc<Term>), CheckerError> {
         self.unwrap_let()
             .ok_or_else(|| CheckerError::ExpectedLetTerm(self.clone()))
     }
@@ -678,11 +1443,16 @@ pub enum Terminal {
     /// A string literal term.
     String(String),
 
+    /// A bitvector constant term. The associated values are the value of the bitvector, as an
+    /// unsigned integer, and its width.
+    BitVec(Integer, u64),
+
     /// A variable, consisting of an identifier and a sort.
-    Var(Identifier, Rc<Term>),
+    Var(Identifier, Rc<Sort>),
 }
 
 /// An identifier.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Identifier {
     /// A simple identifier, consisting of a symbol.
@@ -693,6 +1463,7 @@ pub enum Identifier {
 }
 
 /// An index for an indexed identifier. This can be either a numeral or a symbol.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum IdentifierIndex {
     Numeral(u64),
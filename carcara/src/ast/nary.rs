@@ -0,0 +1,110 @@
+//! Utilities to interpret operators applied to more than two arguments, according to their SMT-LIB
+//! attributes (see [`NaryAttribute`]).
+
+use super::{BindingList, NaryAttribute, Operator, Rc, Term, TermPool};
+use crate::utils::AHashMap;
+use alloc::{vec, vec::Vec};
+
+/// Expands an application of the operator `op` to `args` into an equivalent term in which `op` is
+/// only applied to two arguments. For example, `(=> a b c)` is expanded into `(=> a (=> b c))`, and
+/// `(< a b c)` is expanded into `(and (< a b) (< b c))`.
+///
+/// Returns `None` if there are two or fewer arguments, or if the operator does not accept more
+/// than two arguments.
+pub fn expand_nary(pool: &mut TermPool, op: Operator, args: &[Rc<Term>]) -> Option<Rc<Term>> {
+    if args.len() <= 2 {
+        return None;
+    }
+    let result = match op.nary_attribute()? {
+        NaryAttribute::LeftAssoc => {
+            let mut result = pool.add(Term::Op(op, args[..2].to_vec()));
+            for a in &args[2..] {
+                result = pool.add(Term::Op(op, vec![result, a.clone()]));
+            }
+            result
+        }
+        NaryAttribute::RightAssoc => {
+            let (last, init) = args.split_last().unwrap();
+            let mut result = last.clone();
+            for a in init.iter().rev() {
+                result = pool.add(Term::Op(op, vec![a.clone(), result]));
+            }
+            result
+        }
+        NaryAttribute::Chainable => {
+            let pairs = args
+                .windows(2)
+                .map(|pair| pool.add(Term::Op(op, pair.to_vec())))
+                .collect();
+            pool.add(Term::Op(Operator::And, pairs))
+        }
+        NaryAttribute::Pairwise => {
+            let mut pairs = Vec::new();
+            for (i, a) in args.iter().enumerate() {
+                for b in &args[i + 1..] {
+                    pairs.push(pool.add(Term::Op(op, vec![a.clone(), b.clone()])));
+                }
+            }
+            pool.add(Term::Op(Operator::And, pairs))
+        }
+    };
+    Some(result)
+}
+
+/// Rewrites every application of a right-associative, chainable or pairwise operator to more than
+/// two arguments in `term`, using [`expand_nary`]. Applications of left-associative operators, like
+/// `and` or `+`, are kept as they are, since they are usually handled directly in their n-ary form.
+pub fn normalize_nary(pool: &mut TermPool, term: &Rc<Term>) -> Rc<Term> {
+    normalize(pool, term, &mut AHashMap::new())
+}
+
+fn normalize(
+    pool: &mut TermPool,
+    term: &Rc<Term>,
+    cache: &mut AHashMap<Rc<Term>, Rc<Term>>,
+) -> Rc<Term> {
+    if let Some(t) = cache.get(term) {
+        return t.clone();
+    }
+    let result = match term.as_ref() {
+        Term::Terminal(_) => term.clone(),
+        Term::Op(op, args) => {
+            let args: Vec<_> = args.iter().map(|a| normalize(pool, a, cache)).collect();
+            let expanded = match op.nary_attribute() {
+                Some(NaryAttribute::LeftAssoc) | None => None,
+                Some(_) => expand_nary(pool, *op, &args),
+            };
+            expanded.unwrap_or_else(|| pool.add(Term::Op(*op, args)))
+        }
+        Term::IndexedOp(op, indices, args) => {
+            let args: Vec<_> = args.iter().map(|a| normalize(pool, a, cache)).collect();
+            pool.add(Term::IndexedOp(*op, indices.clone(), args))
+        }
+        Term::App(f, args) => {
+            let args: Vec<_> = args.iter().map(|a| normalize(pool, a, cache)).collect();
+            pool.add(Term::App(f.clone(), args))
+        }
+        Term::Quant(q, bindings, inner, patterns) => {
+            let inner = normalize(pool, inner, cache);
+            pool.add(Term::Quant(*q, bindings.clone(), inner, patterns.clone()))
+        }
+        Term::Choice(var, inner) => {
+            let inner = normalize(pool, inner, cache);
+            pool.add(Term::Choice(var.clone(), inner))
+        }
+        Term::Let(bindings, inner) => {
+            let bindings = bindings
+                .iter()
+                .map(|(name, value)| (name.clone(), normalize(pool, value, cache)))
+                .collect();
+            let inner = normalize(pool, inner, cache);
+            pool.add(Term::Let(BindingList(bindings), inner))
+        }
+        Term::Lambda(bindings, inner) => {
+            let inner = normalize(pool, inner, cache);
+            pool.add(Term::Lambda(bindings.clone(), inner))
+        }
+    };
+    cache.insert(term.clone(), result.clone());
+    result
+}
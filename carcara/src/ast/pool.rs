@@ -1,5 +1,6 @@
 use super::{Identifier, Rc, Sort, Term, Terminal};
-use ahash::{AHashMap, AHashSet};
+use crate::utils::{AHashMap, AHashSet};
+use alloc::vec::Vec;
 
 /// A structure to store and manage all allocated terms.
 ///
@@ -8,16 +9,55 @@ use ahash::{AHashMap, AHashSet};
 /// allocation. This invariant allows terms to be safely compared and hashed by reference, instead
 /// of by value (see [`Rc`]).
 ///
+/// Sorts are stored separately from terms, and are added to the pool using [`TermPool::add_sort`],
+/// which gives the same guarantee for `Rc<Sort>`s.
+///
 /// This struct also provides other utility methods, like computing the sort of a term (see
-/// [`TermPool::sort`]) or its free variables (see [`TermPool::free_vars`]).
+/// [`TermPool::sort`]), its free variables (see [`TermPool::free_vars`]) or whether it is ground
+/// (see [`TermPool::is_ground`]).
+///
+/// A pool can be cheaply branched using [`TermPool::snapshot`], which allows different checks
+/// over the same problem to share the terms that were already added to the pool.
 pub struct TermPool {
     pub(crate) terms: AHashMap<Term, Rc<Term>>,
+    pub(crate) sorts: AHashMap<Sort, Rc<Sort>>,
     free_vars_cache: AHashMap<Rc<Term>, AHashSet<Rc<Term>>>,
-    sorts_cache: AHashMap<Rc<Term>, Sort>,
+    sorts_cache: AHashMap<Rc<Term>, Rc<Sort>>,
+
+    /// The terms in `terms` that are not ground. Since most terms are ground, this is smaller than
+    /// storing a flag for every term.
+    non_ground: AHashSet<Rc<Term>>,
+
+    /// The frozen layer shared with other snapshots of this pool. Terms in this layer are never also
+    /// present in `terms`.
+    base: Option<Rc<FrozenLayer>>,
+
     bool_true: Rc<Term>,
     bool_false: Rc<Term>,
 }
 
+/// Statistics about the terms stored in a pool. See [`TermPool::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// The number of terms that are stored only in this pool, that is, that were added to it after
+    /// its last snapshot was taken.
+    pub num_terms: usize,
+
+    /// The number of terms that are shared with other snapshots of this pool.
+    pub num_shared_terms: usize,
+}
+
+/// An immutable layer of terms, shared between all snapshots taken from a pool. Each pool has at
+/// most one frozen layer, so looking up a term never takes more than two lookups.
+#[derive(Clone, Default)]
+struct FrozenLayer {
+    terms: AHashMap<Term, Rc<Term>>,
+    sorts: AHashMap<Sort, Rc<Sort>>,
+    free_vars_cache: AHashMap<Rc<Term>, AHashSet<Rc<Term>>>,
+    sorts_cache: AHashMap<Rc<Term>, Rc<Sort>>,
+    non_ground: AHashSet<Rc<Term>>,
+}
+
 impl Default for TermPool {
     fn default() -> Self {
         Self::new()
@@ -29,11 +69,12 @@ impl TermPool {
     /// and `false`, as well as the `Bool` sort.
     pub fn new() -> Self {
         let mut terms = AHashMap::new();
+        let mut sorts = AHashMap::new();
         let mut sorts_cache = AHashMap::new();
-        let bool_sort = Self::add_term_to_map(&mut terms, Term::Sort(Sort::Bool));
+        let bool_sort = Self::add_to_map(&mut sorts, Sort::Bool);
 
         let [bool_true, bool_false] = ["true", "false"].map(|b| {
-            Self::add_term_to_map(
+            Self::add_to_map(
                 &mut terms,
                 Term::Terminal(Terminal::Var(
                     Identifier::Simple(b.into()),
@@ -42,19 +83,110 @@ impl TermPool {
             )
         });
 
-        sorts_cache.insert(bool_false.clone(), Sort::Bool);
-        sorts_cache.insert(bool_true.clone(), Sort::Bool);
-        sorts_cache.insert(bool_sort, Sort::Bool);
+        sorts_cache.insert(bool_false.clone(), bool_sort.clone());
+        sorts_cache.insert(bool_true.clone(), bool_sort);
 
         Self {
             terms,
+            sorts,
             free_vars_cache: AHashMap::new(),
             sorts_cache,
+            non_ground: AHashSet::new(),
+            base: None,
             bool_true,
             bool_false,
         }
     }
 
+    /// Creates a snapshot of the pool, which contains all the terms that were added to it so far.
+    /// Both the snapshot and the original pool can be used to add new terms independently, and
+    /// terms added to one of them are not visible in the other.
+    ///
+    /// Taking a snapshot doesn't clone any terms. Instead, the terms in the pool are moved to an
+    /// immutable layer that is shared by the pool and the snapshot, and new terms are added to a
+    /// separate layer in each of them. The terms added since the previous snapshot are merged into
+    /// the existing shared layer, which is copied if other snapshots still use it, so a term is
+    /// always found in at most two lookups. Note that, since terms use non-atomic reference
+    /// counting, the snapshot can't be sent to other threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use carcara::ast::*;
+    /// let mut pool = TermPool::new();
+    /// let one = pool.add(Term::integer(1));
+    ///
+    /// let mut snapshot = pool.snapshot();
+    /// assert_eq!(snapshot.add(Term::integer(1)), one);
+    ///
+    /// // Terms added after the snapshot was taken are not shared
+    /// let two = pool.add(Term::integer(2));
+    /// assert_ne!(snapshot.add(Term::integer(2)), two);
+    /// ```
+    pub fn snapshot(&mut self) -> Self {
+        // If nothing was added since the last snapshot, there is no need to create a new layer
+        let is_empty = self.terms.is_empty()
+            && self.sorts.is_empty()
+            && self.free_vars_cache.is_empty()
+            && self.sorts_cache.is_empty();
+        if !is_empty {
+            // The new terms are merged into the existing frozen layer, instead of being stacked on
+            // top of it. If that layer is still shared with other snapshots, it is copied first
+            let mut layer = match self.base.take() {
+                Some(base) => Rc::try_unwrap(base).unwrap_or_else(|shared| (*shared).clone()),
+                None => FrozenLayer::default(),
+            };
+            layer.terms.extend(core::mem::take(&mut self.terms));
+            layer.sorts.extend(core::mem::take(&mut self.sorts));
+            layer
+                .free_vars_cache
+                .extend(core::mem::take(&mut self.free_vars_cache));
+            layer
+                .sorts_cache
+                .extend(core::mem::take(&mut self.sorts_cache));
+            layer
+                .non_ground
+                .extend(core::mem::take(&mut self.non_ground));
+            self.base = Some(Rc::new(layer));
+        }
+        Self {
+            terms: AHashMap::new(),
+            sorts: AHashMap::new(),
+            free_vars_cache: AHashMap::new(),
+            sorts_cache: AHashMap::new(),
+            non_ground: AHashSet::new(),
+            base: self.base.clone(),
+            bool_true: self.bool_true.clone(),
+            bool_false: self.bool_false.clone(),
+        }
+    }
+
+    /// Returns the number of terms stored only in this pool, and the number of terms it shares
+    /// with other snapshots.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            num_terms: self.terms.len(),
+            num_shared_terms: self.frozen().map_or(0, |l| l.terms.len()),
+        }
+    }
+
+    /// Returns the frozen layer shared with other snapshots of this pool, if there is one.
+    fn frozen(&self) -> Option<&FrozenLayer> {
+        self.base.as_deref()
+    }
+
+    fn cached_sort(&self, term: &Rc<Term>) -> Option<&Rc<Sort>> {
+        self.sorts_cache
+            .get(term)
+            .or_else(|| self.frozen().and_then(|l| l.sorts_cache.get(term)))
+    }
+
+    fn cached_free_vars(&self, term: &Rc<Term>) -> Option<&AHashSet<Rc<Term>>> {
+        self.free_vars_cache
+            .get(term)
+            .or_else(|| self.frozen().and_then(|l| l.free_vars_cache.get(term)))
+    }
+
     /// Return the term corresponding to the boolean constant `true`.
     pub fn bool_true(&self) -> Rc<Term> {
         self.bool_true.clone()
@@ -73,14 +205,17 @@ impl TermPool {
         }
     }
 
-    fn add_term_to_map(terms_map: &mut AHashMap<Term, Rc<Term>>, term: Term) -> Rc<Term> {
-        use std::collections::hash_map::Entry;
+    fn add_to_map<T: Clone + Eq + core::hash::Hash>(
+        map: &mut AHashMap<T, Rc<T>>,
+        value: T,
+    ) -> Rc<T> {
+        use crate::utils::Entry;
 
-        match terms_map.entry(term) {
+        match map.entry(value) {
             Entry::Occupied(occupied_entry) => occupied_entry.get().clone(),
             Entry::Vacant(vacant_entry) => {
-                let term = vacant_entry.key().clone();
-                vacant_entry.insert(Rc::new(term)).clone()
+                let value = vacant_entry.key().clone();
+                vacant_entry.insert(Rc::new(value)).clone()
             }
         }
     }
@@ -89,13 +224,66 @@ impl TermPool {
     ///
     /// If the term was not originally in the term pool, it is added to it. Otherwise, this method
     /// just returns an `Rc` pointing to the existing allocation. This method also computes the
-    /// term's sort, and adds it to the sort cache.
+    /// term's sort and free variables, and adds them to their caches, and records whether the term
+    /// is ground.
     pub fn add(&mut self, term: Term) -> Rc<Term> {
-        let term = Self::add_term_to_map(&mut self.terms, term);
+        let frozen = self.frozen().and_then(|l| l.terms.get(&term)).cloned();
+        let term = match frozen {
+            Some(t) => t,
+            None => {
+                let num_terms = self.terms.len();
+                let term = Self::add_to_map(&mut self.terms, term);
+                if self.terms.len() > num_terms {
+                    self.record_ground(&term);
+                    // Since the subterms were added to the pool before this term, their free
+                    // variables are already cached, and the set is built from theirs
+                    self.free_vars(&term);
+                }
+                term
+            }
+        };
         self.compute_sort(&term);
         term
     }
 
+    /// Takes a sort and returns a possibly newly allocated `Rc` that references it.
+    ///
+    /// Like with terms, if the sort was already in the pool, this returns an `Rc` pointing to the
+    /// existing allocation, so two equal sorts added to the same pool can be compared by reference.
+    pub fn add_sort(&mut self, sort: Sort) -> Rc<Sort> {
+        let frozen = self.frozen().and_then(|l| l.sorts.get(&sort));
+        match frozen {
+            Some(s) => s.clone(),
+            None => Self::add_to_map(&mut self.sorts, sort),
+        }
+    }
+
+    /// Returns `true` if the term is ground, that is, if it contains no quantifiers, `choice`,
+    /// `let` or `lambda` terms. This is computed once, when the term is added to the pool.
+    ///
+    /// Note that the variables bound by a binder are represented in the same way as constants, so a
+    /// ground subterm of a binder term may still contain variables bound by it.
+    pub fn is_ground(&self, term: &Rc<Term>) -> bool {
+        !self.non_ground.contains(term)
+            && !self.frozen().map_or(false, |l| l.non_ground.contains(term))
+    }
+
+    /// Records whether a term that was just added to the pool is ground. Since the subterms of a
+    /// term are added to the pool before it, only its immediate subterms need to be checked.
+    fn record_ground(&mut self, term: &Rc<Term>) {
+        let is_ground = match term.as_ref() {
+            Term::Quant(..) | Term::Choice(..) | Term::Let(..) | Term::Lambda(..) => false,
+            Term::App(f, args) => self.is_ground(f) && args.iter().all(|a| self.is_ground(a)),
+            Term::Op(_, args) | Term::IndexedOp(_, _, args) => {
+                args.iter().all(|a| self.is_ground(a))
+            }
+            Term::Terminal(_) => true,
+        };
+        if !is_ground {
+            self.non_ground.insert(term.clone());
+        }
+    }
+
     /// Takes a vector of terms and calls [`TermPool::add`] on each.
     pub fn add_all(&mut self, terms: Vec<Term>) -> Vec<Rc<Term>> {
         terms.into_iter().map(|t| self.add(t)).collect()
@@ -104,25 +292,26 @@ impl TermPool {
     /// Returns the sort of the given term.
     ///
     /// This method assumes that the sorts of any subterms have already been checked, and are
-    /// correct. If `term` is itself a sort, this simply returns that sort.
-    pub fn sort(&self, term: &Rc<Term>) -> &Sort {
-        &self.sorts_cache[term]
+    /// correct.
+    pub fn sort(&self, term: &Rc<Term>) -> &Rc<Sort> {
+        self.cached_sort(term).expect("term is not in the pool")
     }
 
     /// Computes the sort of a term and adds it to the sort cache.
-    fn compute_sort<'a, 'b: 'a>(&'a mut self, term: &'b Rc<Term>) -> &'a Sort {
-        use super::Operator;
+    fn compute_sort<'a, 'b: 'a>(&'a mut self, term: &'b Rc<Term>) -> &'a Rc<Sort> {
+        use super::{IndexedOperator, Operator};
 
-        if self.sorts_cache.contains_key(term) {
-            return &self.sorts_cache[term];
+        if self.cached_sort(term).is_some() {
+            return self.cached_sort(term).unwrap();
         }
 
         let result = match term.as_ref() {
             Term::Terminal(t) => match t {
-                Terminal::Integer(_) => Sort::Int,
-                Terminal::Real(_) => Sort::Real,
-                Terminal::String(_) => Sort::String,
-                Terminal::Var(_, sort) => sort.as_sort().unwrap().clone(),
+                Terminal::Integer(_) => self.add_sort(Sort::Int),
+                Terminal::Real(_) => self.add_sort(Sort::Real),
+                Terminal::String(_) => self.add_sort(Sort::String),
+                Terminal::BitVec(_, width) => self.add_sort(Sort::BitVec(*width)),
+                Terminal::Var(_, sort) => sort.clone(),
             },
             Term::Op(op, args) => match op {
                 Operator::Not
@@ -136,39 +325,185 @@ impl TermPool {
                 | Operator::GreaterThan
                 | Operator::LessEq
                 | Operator::GreaterEq
-                | Operator::IsInt => Sort::Bool,
+                | Operator::IsInt
+                | Operator::BvULt
+                | Operator::BvULe
+                | Operator::BvUGt
+                | Operator::BvUGe
+                | Operator::BvSLt
+                | Operator::BvSLe
+                | Operator::BvSGt
+                | Operator::BvSGe
+                | Operator::StrLt
+                | Operator::StrLe
+                | Operator::StrPrefixOf
+                | Operator::StrSuffixOf
+                | Operator::StrContains
+                | Operator::StrIsDigit
+                | Operator::StrInRe
+                | Operator::FpLeq
+                | Operator::FpLt
+                | Operator::FpGeq
+                | Operator::FpGt
+                | Operator::FpEq
+                | Operator::FpIsNormal
+                | Operator::FpIsSubnormal
+                | Operator::FpIsZero
+                | Operator::FpIsInfinite
+                | Operator::FpIsNaN
+                | Operator::FpIsNegative
+                | Operator::FpIsPositive => self.add_sort(Sort::Bool),
                 Operator::Ite => self.compute_sort(&args[1]).clone(),
                 Operator::Add | Operator::Sub | Operator::Mult => {
-                    if args.iter().any(|a| *self.compute_sort(a) == Sort::Real) {
+                    let sort = if args.iter().any(|a| **self.compute_sort(a) == Sort::Real) {
                         Sort::Real
                     } else {
                         Sort::Int
-                    }
+                    };
+                    self.add_sort(sort)
+                }
+                Operator::RealDiv | Operator::ToReal | Operator::FpToReal => {
+                    self.add_sort(Sort::Real)
                 }
-                Operator::RealDiv | Operator::ToReal => Sort::Real,
-                Operator::IntDiv | Operator::Mod | Operator::Abs | Operator::ToInt => Sort::Int,
-                Operator::Select => match self.compute_sort(&args[0]) {
-                    Sort::Array(_, y) => y.as_sort().unwrap().clone(),
+                Operator::IntDiv
+                | Operator::Mod
+                | Operator::Abs
+                | Operator::ToInt
+                | Operator::StrLen
+                | Operator::StrIndexOf
+                | Operator::StrToCode
+                | Operator::StrToInt => self.add_sort(Sort::Int),
+                Operator::Select => match self.compute_sort(&args[0]).as_ref() {
+                    Sort::Array(_, y) => y.clone(),
                     _ => unreachable!(),
                 },
                 Operator::Store => self.compute_sort(&args[0]).clone(),
+                Operator::BvNot
+                | Operator::BvNeg
+                | Operator::BvAnd
+                | Operator::BvOr
+                | Operator::BvXor
+                | Operator::BvNand
+                | Operator::BvNor
+                | Operator::BvXnor
+                | Operator::BvAdd
+                | Operator::BvSub
+                | Operator::BvMul
+                | Operator::BvUDiv
+                | Operator::BvURem
+                | Operator::BvSDiv
+                | Operator::BvSRem
+                | Operator::BvSMod
+                | Operator::BvShl
+                | Operator::BvLShr
+                | Operator::BvAShr => self.compute_sort(&args[0]).clone(),
+                Operator::BvComp => self.add_sort(Sort::BitVec(1)),
+                Operator::Concat => {
+                    let mut width = 0;
+                    for a in args {
+                        width += self.compute_sort(a).as_bitvec_width().unwrap();
+                    }
+                    self.add_sort(Sort::BitVec(width))
+                }
+                Operator::StrConcat
+                | Operator::StrAt
+                | Operator::StrSubstr
+                | Operator::StrReplace
+                | Operator::StrReplaceAll
+                | Operator::StrReplaceRe
+                | Operator::StrReplaceReAll
+                | Operator::StrFromCode
+                | Operator::StrFromInt => self.add_sort(Sort::String),
+                Operator::StrToRe
+                | Operator::ReNone
+                | Operator::ReAll
+                | Operator::ReAllChar
+                | Operator::ReConcat
+                | Operator::ReUnion
+                | Operator::ReInter
+                | Operator::ReStar
+                | Operator::RePlus
+                | Operator::ReOpt
+                | Operator::ReRange
+                | Operator::ReComp
+                | Operator::ReDiff => self.add_sort(Sort::RegLan),
+                Operator::Fp => {
+                    let eb = self.compute_sort(&args[1]).as_bitvec_width().unwrap();
+                    let sb = self.compute_sort(&args[2]).as_bitvec_width().unwrap();
+                    self.add_sort(Sort::FloatingPoint(eb, sb + 1))
+                }
+                Operator::FpAbs
+                | Operator::FpNeg
+                | Operator::FpRem
+                | Operator::FpMin
+                | Operator::FpMax => self.compute_sort(&args[0]).clone(),
+
+                // The first argument of these operators is the rounding mode
+                Operator::FpAdd
+                | Operator::FpSub
+                | Operator::FpMul
+                | Operator::FpDiv
+                | Operator::FpFma
+                | Operator::FpSqrt
+                | Operator::FpRoundToIntegral => self.compute_sort(&args[1]).clone(),
+                Operator::RoundNearestTiesToEven
+                | Operator::RoundNearestTiesToAway
+                | Operator::RoundTowardPositive
+                | Operator::RoundTowardNegative
+                | Operator::RoundTowardZero => self.add_sort(Sort::RoundingMode),
             },
-            Term::App(f, _) => {
-                match self.compute_sort(f) {
-                    Sort::Function(sorts) => sorts.last().unwrap().as_sort().unwrap().clone(),
+            Term::IndexedOp(op, indices, args) => match op {
+                IndexedOperator::RePower | IndexedOperator::ReLoop => self.add_sort(Sort::RegLan),
+                IndexedOperator::Divisible => self.add_sort(Sort::Bool),
+                IndexedOperator::FpToUbv | IndexedOperator::FpToSbv => {
+                    self.add_sort(Sort::BitVec(indices[0]))
+                }
+                IndexedOperator::ToFp
+                | IndexedOperator::ToFpUnsigned
+                | IndexedOperator::FpPlusInfinity
+                | IndexedOperator::FpMinusInfinity
+                | IndexedOperator::FpPlusZero
+                | IndexedOperator::FpMinusZero
+                | IndexedOperator::FpNaN => {
+                    self.add_sort(Sort::FloatingPoint(indices[0], indices[1]))
+                }
+                IndexedOperator::BvExtract
+                | IndexedOperator::BvRepeat
+                | IndexedOperator::BvZeroExtend
+                | IndexedOperator::BvSignExtend
+                | IndexedOperator::BvRotateLeft
+                | IndexedOperator::BvRotateRight => {
+                    let width = self.compute_sort(&args[0]).as_bitvec_width().unwrap();
+                    let width = match op {
+                        IndexedOperator::BvExtract => indices[0] - indices[1] + 1,
+                        IndexedOperator::BvRepeat => width * indices[0],
+                        IndexedOperator::BvZeroExtend | IndexedOperator::BvSignExtend => {
+                            width + indices[0]
+                        }
+                        _ => width,
+                    };
+                    self.add_sort(Sort::BitVec(width))
+                }
+            },
+            Term::App(f, args) => {
+                match self.compute_sort(f).as_ref() {
+                    // A partial application results in a function of the remaining arguments
+                    Sort::Function(sorts) if args.len() + 1 < sorts.len() => {
+                        let remaining = Sort::Function(sorts[args.len()..].to_vec());
+                        self.add_sort(remaining)
+                    }
+                    Sort::Function(sorts) => sorts.last().unwrap().clone(),
                     _ => unreachable!(), // We assume that the function is correctly sorted
                 }
             }
-            Term::Sort(sort) => sort.clone(),
-            Term::Quant(_, _, _) => Sort::Bool,
-            Term::Choice((_, sort), _) => sort.as_sort().unwrap().clone(),
+            Term::Quant(..) => self.add_sort(Sort::Bool),
+            Term::Choice((_, sort), _) => sort.clone(),
             Term::Let(_, inner) => self.compute_sort(inner).clone(),
             Term::Lambda(bindings, body) => {
                 let mut result: Vec<_> =
                     bindings.iter().map(|(_name, sort)| sort.clone()).collect();
-                let return_sort = Term::Sort(self.compute_sort(body).clone());
-                result.push(self.add(return_sort));
-                Sort::Function(result)
+                result.push(self.compute_sort(body).clone());
+                self.add_sort(Sort::Function(result))
             }
         };
         self.sorts_cache.insert(term.clone(), result);
@@ -177,25 +512,25 @@ impl TermPool {
 
     /// Returns an `AHashSet` containing all the free variables in the given term.
     ///
-    /// This method uses a cache, so there is no additional cost to computing the free variables of
-    /// a term multiple times.
-    pub fn free_vars<'t>(&mut self, term: &'t Rc<Term>) -> &AHashSet<Rc<Term>> {
+    /// The free variables of each term are computed when it is added to the pool, so this is
+    /// usually just a lookup in the cache.
+    pub fn free_vars(&mut self, term: &Rc<Term>) -> &AHashSet<Rc<Term>> {
         // Here, I would like to do
         // ```
-        // if let Some(vars) = self.free_vars_cache.get(term) {
+        // if let Some(vars) = self.cached_free_vars(term) {
         //     return vars;
         // }
         // ```
         // However, because of a limitation in the borrow checker, the compiler thinks that
         // this immutable borrow of `cache` has to live until the end of the function, even
         // though the code immediately returns. This would stop me from mutating `cache` in the
-        // rest of the function. Because of that, I have to check if the hash map contains
-        // `term` as a key, and then get the value associated with it, meaning I have to access
-        // the hash map twice, which is a bit slower. This is an example of problem case #3
+        // rest of the function. Because of that, I have to check if the cache contains `term`,
+        // and then get the value associated with it, meaning I have to access the cache twice,
+        // which is a bit slower. This is an example of problem case #3
         // from the non-lexical lifetimes RFC:
         // https://github.com/rust-lang/rfcs/blob/master/text/2094-nll.md
-        if self.free_vars_cache.contains_key(term) {
-            return self.free_vars_cache.get(term).unwrap();
+        if self.cached_free_vars(term).is_some() {
+            return self.cached_free_vars(term).unwrap();
         }
         let set = match term.as_ref() {
             Term::App(f, args) => {
@@ -205,14 +540,25 @@ impl TermPool {
                 }
                 set
             }
-            Term::Op(_, args) => {
+            Term::Op(_, args) | Term::IndexedOp(_, _, args) => {
                 let mut set = AHashSet::new();
                 for a in args {
                     set.extend(self.free_vars(a).iter().cloned());
                 }
                 set
             }
-            Term::Quant(_, bindings, inner) | Term::Lambda(bindings, inner) => {
+            Term::Quant(_, bindings, inner, patterns) => {
+                let mut vars = self.free_vars(inner).clone();
+                for p in patterns.iter().flatten() {
+                    vars.extend(self.free_vars(p).iter().cloned());
+                }
+                for bound_var in bindings {
+                    let term = self.add(bound_var.clone().into());
+                    vars.remove(&term);
+                }
+                vars
+            }
+            Term::Lambda(bindings, inner) => {
                 let mut vars = self.free_vars(inner).clone();
                 for bound_var in bindings {
                     let term = self.add(bound_var.clone().into());
@@ -221,13 +567,18 @@ impl TermPool {
                 vars
             }
             Term::Let(bindings, inner) => {
+                // The bindings in a `let` term are parallel, so the values are not in the scope of
+                // any of the bound variables. We first remove all bound variables from the free
+                // variables of the inner term, and only then add the free variables of each value
                 let mut vars = self.free_vars(inner).clone();
                 for (var, value) in bindings {
-                    let sort = Term::Sort(self.sort(value).clone());
-                    let sort = self.add(sort);
+                    let sort = self.sort(value).clone();
                     let term = self.add((var.clone(), sort).into());
                     vars.remove(&term);
                 }
+                for (_, value) in bindings {
+                    vars.extend(self.free_vars(value).iter().cloned());
+                }
                 vars
             }
             Term::Choice(bound_var, inner) => {
@@ -241,7 +592,7 @@ impl TermPool {
                 set.insert(term.clone());
                 set
             }
-            Term::Terminal(_) | Term::Sort(_) => AHashSet::new(),
+            Term::Terminal(_) => AHashSet::new(),
         };
         self.free_vars_cache.insert(term.clone(), set);
         self.free_vars_cache.get(term).unwrap()
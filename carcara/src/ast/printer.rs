@@ -1,26 +1,73 @@
+//! Printers for proofs and terms. Proofs are written in the Alethe format, and terms are displayed
+//! in the SMT-LIB syntax, so anything printed here can be parsed again.
+//!
+//! [`write_alethe_proof`] writes a proof that can be parsed back together with the problem it
+//! proves, which allows proofs to be parsed, transformed through the AST and written out again.
+//! [`print_proof`] and [`write_proof`] only have access to the proof commands, so they write the
+//! assignment arguments of `anchor` commands in the legacy syntax, `(:= x t)`.
+
+use crate::{ast::*, utils::is_symbol_character};
+use alloc::{borrow::Cow, format};
+use core::fmt;
+
+#[cfg(feature = "std")]
 use crate::{
-    ast::*,
     parser::Token,
-    utils::{is_symbol_character, DedupIterator},
+    utils::{AHashMap, DedupIterator},
 };
-use ahash::AHashMap;
-use std::{borrow::Cow, fmt, io};
+#[cfg(feature = "std")]
+use std::io;
 
+#[cfg(feature = "std")]
 /// Prints a proof to the standard output.
 ///
 /// If `use_sharing` is `true`, terms that are used multiple times will make use of sharing. The
 /// first time a novel term appears, it receives a unique name using the `:named` attribute. After
 /// that, any occurrence of that term will simply use this name, instead of printing the whole term.
 pub fn print_proof(commands: &[ProofCommand], use_sharing: bool) -> io::Result<()> {
-    let mut stdout = io::stdout();
+    write_proof(&mut io::stdout(), commands, use_sharing)
+}
+
+#[cfg(feature = "std")]
+/// Similar to `print_proof`, but writes the proof to `dest` instead of the standard output.
+pub fn write_proof(
+    dest: &mut dyn io::Write,
+    commands: &[ProofCommand],
+    use_sharing: bool,
+) -> io::Result<()> {
     let mut printer = AlethePrinter {
-        inner: &mut stdout,
+        inner: dest,
+        pool: None,
         term_indices: use_sharing.then(AHashMap::new),
         term_sharing_variable_prefix: "@p_",
     };
     printer.write_proof(commands)
 }
 
+#[cfg(feature = "std")]
+/// Writes a proof to `dest` in the Alethe format, in a way that can be parsed again together with
+/// the problem it proves. The sorts of the terms in the proof are taken from `pool`, which must be
+/// the pool they were added to. These are used to write the assignment arguments of `anchor`
+/// commands in the current Alethe syntax, `(:= (x S) t)`.
+///
+/// If `use_sharing` is `true`, terms that are used multiple times are given a name, like in
+/// [`print_proof`].
+pub fn write_alethe_proof(
+    dest: &mut dyn io::Write,
+    proof: &Proof,
+    pool: &TermPool,
+    use_sharing: bool,
+) -> io::Result<()> {
+    let mut printer = AlethePrinter {
+        inner: dest,
+        pool: Some(pool),
+        term_indices: use_sharing.then(AHashMap::new),
+        term_sharing_variable_prefix: "@p_",
+    };
+    printer.write_proof(&proof.commands)
+}
+
+#[cfg(feature = "std")]
 /// Given the conclusion clause of a `lia_generic` step, this method will write to `dest` the
 /// corresponding SMT problem instance.
 pub fn write_lia_smt_instance(
@@ -30,40 +77,42 @@ pub fn write_lia_smt_instance(
 ) -> io::Result<()> {
     let mut printer = AlethePrinter {
         inner: dest,
+        pool: None,
         term_indices: use_sharing.then(AHashMap::new),
         term_sharing_variable_prefix: "p_",
     };
     printer.write_lia_smt_instance(clause)
 }
 
+#[cfg(feature = "std")]
 trait PrintProof {
     fn write_proof(&mut self, commands: &[ProofCommand]) -> io::Result<()>;
 }
 
+#[cfg(feature = "std")]
 trait PrintWithSharing {
     fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()>;
 }
 
+#[cfg(feature = "std")]
 impl<T: PrintWithSharing> PrintWithSharing for &T {
     fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
         PrintWithSharing::print_with_sharing(*self, p)
     }
 }
 
+#[cfg(feature = "std")]
 impl PrintWithSharing for Rc<Term> {
     fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
         if let Some(indices) = &mut p.term_indices {
-            // There are three cases where we don't use sharing when printing a term:
+            // There are two cases where we don't use sharing when printing a term:
             //
             // - Terminal terms (e.g., integers, reals, variables, etc.) could in theory be shared,
             // but, since they are very small, it's not worth it to give them a name.
             //
-            // - Sorts are represented as terms, but they are not actually terms in the grammar, so
-            // we can't use the `(! ... :named ...)` syntax to give them a name.
-            //
             // - If a term is only used once in the proof, there is no reason to give it a name. We
             // detect this case by checking if the number of references to it's `Rc` is exactly 1.
-            if !self.is_terminal() && !self.is_sort() && Rc::strong_count(self) > 1 {
+            if !self.is_terminal() && Rc::strong_count(self) > 1 {
                 return if let Some(i) = indices.get(self) {
                     write!(p.inner, "{}{}", p.term_sharing_variable_prefix, i)
                 } else {
@@ -79,7 +128,15 @@ impl PrintWithSharing for Rc<Term> {
     }
 }
 
-impl PrintWithSharing for SortedVar {
+#[cfg(feature = "std")]
+impl PrintWithSharing for Rc<Sort> {
+    fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
+        write!(p.inner, "{}", self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: PrintWithSharing> PrintWithSharing for (String, T) {
     fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
         let (name, value) = self;
         write!(p.inner, "({} ", quote_symbol(name))?;
@@ -88,7 +145,8 @@ impl PrintWithSharing for SortedVar {
     }
 }
 
-impl PrintWithSharing for BindingList {
+#[cfg(feature = "std")]
+impl<T: PrintWithSharing> PrintWithSharing for BindingList<T> {
     fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
         match self.as_slice() {
             [] => write!(p.inner, "()"),
@@ -97,31 +155,46 @@ impl PrintWithSharing for BindingList {
     }
 }
 
+#[cfg(feature = "std")]
 impl PrintWithSharing for Operator {
     fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
         write!(p.inner, "{}", self)
     }
 }
 
+#[cfg(feature = "std")]
+impl PrintWithSharing for IndexedHead<'_> {
+    fn print_with_sharing(&self, p: &mut AlethePrinter) -> io::Result<()> {
+        write!(p.inner, "{}", self)
+    }
+}
+
+#[cfg(feature = "std")]
 struct AlethePrinter<'a> {
     inner: &'a mut dyn io::Write,
+
+    /// The pool the terms in the proof were added to. If this is `None`, the sorts of terms are not
+    /// known, and the assignment arguments of `anchor` commands are written without a sort.
+    pool: Option<&'a TermPool>,
+
     term_indices: Option<AHashMap<Rc<Term>, usize>>,
     term_sharing_variable_prefix: &'static str,
 }
 
+#[cfg(feature = "std")]
 impl<'a> PrintProof for AlethePrinter<'a> {
     fn write_proof(&mut self, commands: &[ProofCommand]) -> io::Result<()> {
         let mut iter = ProofIter::new(commands);
         while let Some(command) = iter.next() {
             match command {
                 ProofCommand::Assume { id, term } => {
-                    write!(self.inner, "(assume {} ", id)?;
+                    write!(self.inner, "(assume {} ", quote_symbol(id))?;
                     term.print_with_sharing(self)?;
                     write!(self.inner, ")")?;
                 }
                 ProofCommand::Step(s) => self.write_step(&mut iter, s)?,
                 ProofCommand::Subproof(s) => {
-                    write!(self.inner, "(anchor :step {}", command.id())?;
+                    write!(self.inner, "(anchor :step {}", quote_symbol(command.id()))?;
 
                     if !s.variable_args.is_empty() || !s.assignment_args.is_empty() {
                         write!(self.inner, " :args (")?;
@@ -138,7 +211,12 @@ impl<'a> PrintProof for AlethePrinter<'a> {
                                 write!(self.inner, " ")?;
                             }
                             is_first = false;
-                            write!(self.inner, "(:= {} ", name)?;
+                            let name = quote_symbol(name);
+                            if let Some(pool) = self.pool {
+                                write!(self.inner, "(:= ({} {}) ", name, pool.sort(value))?;
+                            } else {
+                                write!(self.inner, "(:= {} ", name)?;
+                            }
                             value.print_with_sharing(self)?;
                             write!(self.inner, ")")?;
                         }
@@ -154,6 +232,7 @@ impl<'a> PrintProof for AlethePrinter<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> AlethePrinter<'a> {
     fn write_s_expr<H, T>(&mut self, head: &H, tail: &[T]) -> io::Result<()>
     where
@@ -173,13 +252,29 @@ impl<'a> AlethePrinter<'a> {
         match term.as_ref() {
             Term::Terminal(t) => write!(self.inner, "{}", t),
             Term::App(func, args) => self.write_s_expr(func, args),
+            Term::Op(op, _) if op.is_constant() => write!(self.inner, "{}", op),
             Term::Op(op, args) => self.write_s_expr(op, args),
-            Term::Sort(sort) => write!(self.inner, "{}", sort),
-            Term::Quant(quantifier, bindings, term) => {
+            Term::IndexedOp(op, indices, _) if op.is_constant() => {
+                write!(self.inner, "{}", IndexedHead(*op, indices))
+            }
+            Term::IndexedOp(op, indices, args) => {
+                self.write_s_expr(&IndexedHead(*op, indices), args)
+            }
+            Term::Quant(quantifier, bindings, term, patterns) => {
                 write!(self.inner, "({} ", quantifier)?;
                 bindings.print_with_sharing(self)?;
                 write!(self.inner, " ")?;
-                term.print_with_sharing(self)?;
+                if patterns.is_empty() {
+                    term.print_with_sharing(self)?;
+                } else {
+                    write!(self.inner, "(! ")?;
+                    term.print_with_sharing(self)?;
+                    for p in patterns {
+                        write!(self.inner, " :pattern ")?;
+                        self.write_s_expr(&p[0], &p[1..])?;
+                    }
+                    write!(self.inner, ")")?;
+                }
                 write!(self.inner, ")")
             }
             Term::Choice(var, term) => {
@@ -207,7 +302,7 @@ impl<'a> AlethePrinter<'a> {
     }
 
     fn write_step(&mut self, iter: &mut ProofIter, step: &ProofStep) -> io::Result<()> {
-        write!(self.inner, "(step {} (cl", step.id)?;
+        write!(self.inner, "(step {} (cl", quote_symbol(&step.id))?;
 
         for t in &step.clause {
             write!(self.inner, " ")?;
@@ -218,9 +313,10 @@ impl<'a> AlethePrinter<'a> {
         write!(self.inner, " :rule {}", step.rule)?;
 
         if let [head, tail @ ..] = step.premises.as_slice() {
-            write!(self.inner, " :premises ({}", iter.get_premise(*head).id())?;
+            let id = |i: &(usize, usize)| quote_symbol(iter.get_premise(*i).id());
+            write!(self.inner, " :premises ({}", id(head))?;
             for premise in tail {
-                write!(self.inner, " {}", iter.get_premise(*premise).id())?;
+                write!(self.inner, " {}", id(premise))?;
             }
             write!(self.inner, ")")?;
         }
@@ -236,9 +332,10 @@ impl<'a> AlethePrinter<'a> {
         }
 
         if let [head, tail @ ..] = step.discharge.as_slice() {
-            write!(self.inner, " :discharge ({}", iter.get_premise(*head).id())?;
-            for id in tail {
-                write!(self.inner, " {}", iter.get_premise(*id).id())?;
+            let id = |i: &(usize, usize)| quote_symbol(iter.get_premise(*i).id());
+            write!(self.inner, " :discharge ({}", id(head))?;
+            for i in tail {
+                write!(self.inner, " {}", id(i))?;
             }
             write!(self.inner, ")")?;
         }
@@ -251,7 +348,7 @@ impl<'a> AlethePrinter<'a> {
         match arg {
             ProofArg::Term(t) => t.print_with_sharing(self),
             ProofArg::Assign(name, value) => {
-                write!(self.inner, "(:= {} ", name)?;
+                write!(self.inner, "(:= {} ", quote_symbol(name))?;
                 value.print_with_sharing(self)?;
                 write!(self.inner, ")")
             }
@@ -280,9 +377,18 @@ where
     write!(f, ")")
 }
 
-fn quote_symbol(symbol: &str) -> Cow<str> {
-    use crate::parser::Reserved;
-    use std::str::FromStr;
+/// The head of an indexed operation term, which is printed as `(_ <operator> <indices>)`.
+pub struct IndexedHead<'a>(pub IndexedOperator, pub &'a [u64]);
+
+impl fmt::Display for IndexedHead<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_s_expr(f, format_args!("_ {}", self.0), self.1)
+    }
+}
+
+pub fn quote_symbol(symbol: &str) -> Cow<'_, str> {
+    use crate::utils::Reserved;
+    use core::str::FromStr;
 
     assert!(symbol.chars().all(|c| c != '|'));
 
@@ -303,9 +409,11 @@ fn quote_symbol(symbol: &str) -> Cow<str> {
     }
 }
 
-fn escape_string(string: &str) -> Cow<str> {
-    if string.contains('"') {
-        Cow::Owned(string.replace('"', "\"\""))
+/// Escapes a string so it can be printed as a string literal. Besides doubling quotes, a backslash
+/// followed by `u` is escaped as `\u{5c}`, so it is not read back as an escape sequence.
+fn escape_string(string: &str) -> Cow<'_, str> {
+    if string.contains('"') || string.contains("\\u") {
+        Cow::Owned(string.replace('"', "\"\"").replace("\\u", "\\u{5c}u"))
     } else {
         Cow::Borrowed(string)
     }
@@ -316,13 +424,25 @@ impl fmt::Display for Term {
         match self {
             Term::Terminal(t) => write!(f, "{}", t),
             Term::App(func, args) => write_s_expr(f, func, args),
+            Term::Op(op, _) if op.is_constant() => write!(f, "{}", op),
             Term::Op(op, args) => write_s_expr(f, op, args),
-            Term::Sort(sort) => write!(f, "{}", sort),
-            Term::Quant(quantifier, bindings, term) => {
+            Term::IndexedOp(op, indices, _) if op.is_constant() => {
+                write!(f, "{}", IndexedHead(*op, indices))
+            }
+            Term::IndexedOp(op, indices, args) => write_s_expr(f, IndexedHead(*op, indices), args),
+            Term::Quant(quantifier, bindings, term, patterns) if patterns.is_empty() => {
                 write!(f, "({} {} {})", quantifier, bindings, term)
             }
+            Term::Quant(quantifier, bindings, term, patterns) => {
+                write!(f, "({} {} (! {}", quantifier, bindings, term)?;
+                for p in patterns {
+                    write!(f, " :pattern ")?;
+                    write_s_expr(f, &p[0], &p[1..])?;
+                }
+                write!(f, "))")
+            }
             Term::Choice((symbol, sort), term) => {
-                write!(f, "(choice (({} {})) {})", symbol, sort, term)
+                write!(f, "(choice (({} {})) {})", quote_symbol(symbol), sort, term)
             }
             Term::Let(bindings, term) => {
                 write!(f, "(let {} {})", bindings, term)
@@ -352,6 +472,10 @@ impl fmt::Display for Terminal {
                 }
             }
             Terminal::String(s) => write!(f, "\"{}\"", escape_string(s)),
+            Terminal::BitVec(value, width) => {
+                let digits = value.to_string_radix(2);
+                write!(f, "#b{:0>width$}", digits, width = *width as usize)
+            }
             Terminal::Var(iden, _) => write!(f, "{}", iden),
         }
     }
@@ -390,7 +514,7 @@ impl fmt::Display for Quantifier {
     }
 }
 
-impl fmt::Display for BindingList {
+impl<T: fmt::Display> fmt::Display for BindingList<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.as_slice() {
             [] => write!(f, "()"),
@@ -410,7 +534,7 @@ impl fmt::Display for Sort {
         match self {
             // Function sorts should never be displayed, so the exact format we use is of little
             // importance
-            Sort::Function(args) => write_s_expr(f, "Func", args),
+            Sort::Function(args) => write_s_expr(f, "->", args),
             Sort::Atom(name, args) => match args.len() {
                 0 => write!(f, "{}", quote_symbol(name)),
                 _ => write_s_expr(f, quote_symbol(name), args),
@@ -419,11 +543,16 @@ impl fmt::Display for Sort {
             Sort::Int => write!(f, "Int"),
             Sort::Real => write!(f, "Real"),
             Sort::String => write!(f, "String"),
+            Sort::RegLan => write!(f, "RegLan"),
             Sort::Array(x, y) => write_s_expr(f, "Array", &[x, y]),
+            Sort::BitVec(width) => write!(f, "(_ BitVec {})", width),
+            Sort::FloatingPoint(eb, sb) => write!(f, "(_ FloatingPoint {} {})", eb, sb),
+            Sort::RoundingMode => write!(f, "RoundingMode"),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -433,6 +562,7 @@ impl fmt::Display for Token {
             Token::Keyword(k) => write!(f, ":{}", k),
             Token::Numeral(n) => write!(f, "{}", n),
             Token::Decimal(r) => write!(f, "{}", r),
+            Token::BitVec(value, width) => write!(f, "{}", Terminal::BitVec(value.clone(), *width)),
             Token::String(s) => write!(f, "\"{}\"", escape_string(s)),
             Token::ReservedWord(r) => write!(f, "{}", r),
             Token::Eof => write!(f, "EOF"),
@@ -450,7 +580,7 @@ impl fmt::Display for ProblemPrelude {
 
         for (name, sort) in &self.function_declarations {
             write!(f, "(declare-fun {} ", name)?;
-            if let Sort::Function(sorts) = sort.as_sort().unwrap() {
+            if let Sort::Function(sorts) = sort.as_ref() {
                 write_s_expr(f, &sorts[0], &sorts[1..sorts.len() - 1])?;
                 writeln!(f, " {})", sorts.last().unwrap())?;
             } else {
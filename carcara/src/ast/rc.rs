@@ -1,4 +1,5 @@
-use std::{fmt, hash::Hash, ops::Deref, rc};
+use alloc::rc;
+use core::{fmt, hash::Hash, ops::Deref};
 
 /// An `Rc` where equality and hashing are done by reference, instead of by value.
 ///
@@ -52,7 +53,7 @@ impl<T: ?Sized> PartialEq for Rc<T> {
 }
 
 impl<T: ?Sized> Hash for Rc<T> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         rc::Rc::as_ptr(&self.0).hash(state);
     }
 }
@@ -112,4 +113,9 @@ impl<T> Rc<T> {
     pub fn strong_count(this: &Self) -> usize {
         rc::Rc::strong_count(&this.0)
     }
+
+    /// Similar to [`std::rc::Rc::try_unwrap`].
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        rc::Rc::try_unwrap(this.0).map_err(Self)
+    }
 }
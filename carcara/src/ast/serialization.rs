@@ -0,0 +1,549 @@
+//! Serialization of proofs and terms using `serde`, which is enabled by the `serde` feature.
+//!
+//! Since terms are hash consed, a term may share many of its subterms, and a proof usually mentions
+//! the same terms many times. To keep this sharing, terms and sorts are not serialized in place.
+//! Instead, each distinct term and sort is serialized once, in the `terms` and `sorts` tables, and
+//! is referred to by its index in the table. The subterms of a term always come before it in the
+//! table, and likewise for sorts. For example, the term `(+ x x)`, where `x` is an `Int` variable,
+//! is serialized in JSON as:
+//!
+//! ```json
+//! {
+//!   "sorts": ["Int"],
+//!   "terms": [{ "Var": [{ "Simple": "x" }, 0] }, { "Op": ["Add", [0, 0]] }],
+//!   "root": 1
+//! }
+//! ```
+//!
+//! Terms must be added to a term pool to be used, so they can't be deserialized on their own.
+//! Instead, proofs, commands and terms are deserialized using a [`PoolSeed`], which adds the terms
+//! to the given pool.
+
+use super::*;
+use alloc::{format, string::ToString};
+use core::marker::PhantomData;
+use serde::{
+    de::{self, DeserializeSeed},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// A sort, with its sort arguments given as indices in the table of sorts.
+#[derive(Serialize, Deserialize)]
+enum FlatSort {
+    Function(Vec<usize>),
+    Atom(String, Vec<usize>),
+    Bool,
+    Int,
+    Real,
+    String,
+    RegLan,
+    Array(usize, usize),
+    BitVec(u64),
+    FloatingPoint(u64, u64),
+    RoundingMode,
+}
+
+/// A term, with its subterms given as indices in the table of terms, and its sorts given as
+/// indices in the table of sorts. Numbers are written in decimal, and reals as a fraction.
+#[derive(Serialize, Deserialize)]
+enum FlatTerm {
+    Integer(String),
+    Real(String),
+    String(String),
+    BitVec(String, u64),
+    Var(Identifier, usize),
+    App(usize, Vec<usize>),
+    Op(Operator, Vec<usize>),
+    IndexedOp(IndexedOperator, Vec<u64>, Vec<usize>),
+    Quant(Quantifier, Vec<(String, usize)>, usize, Vec<Vec<usize>>),
+    Choice((String, usize), usize),
+    Let(Vec<(String, usize)>, usize),
+    Lambda(Vec<(String, usize)>, usize),
+}
+
+#[derive(Serialize, Deserialize)]
+enum FlatArg {
+    Term(usize),
+    Assign(String, usize),
+}
+
+#[derive(Serialize, Deserialize)]
+enum FlatCommand {
+    Assume {
+        id: String,
+        term: usize,
+    },
+    Step {
+        id: String,
+        clause: Vec<usize>,
+        rule: String,
+        premises: Vec<(usize, usize)>,
+        args: Vec<FlatArg>,
+        discharge: Vec<(usize, usize)>,
+    },
+    Subproof {
+        commands: Vec<FlatCommand>,
+        assignment_args: Vec<(String, usize)>,
+        variable_args: Vec<(String, usize)>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct FlatProof {
+    premises: Vec<usize>,
+    commands: Vec<FlatCommand>,
+    spans: Vec<SourceSpan>,
+    metadata: Vec<(String, String)>,
+    named_assertions: Vec<(String, usize)>,
+    comments: Vec<Comment>,
+}
+
+/// The serialized form of a value, together with the tables of the terms and sorts it uses.
+#[derive(Serialize, Deserialize)]
+struct Flat<T> {
+    sorts: Vec<FlatSort>,
+    terms: Vec<FlatTerm>,
+    root: T,
+}
+
+/// Builds the tables of terms and sorts while serializing a value.
+#[derive(Default)]
+struct Flattener {
+    sorts: Vec<FlatSort>,
+    sort_indices: AHashMap<Rc<Sort>, usize>,
+    terms: Vec<FlatTerm>,
+    term_indices: AHashMap<Rc<Term>, usize>,
+}
+
+impl Flattener {
+    fn sort(&mut self, sort: &Rc<Sort>) -> usize {
+        if let Some(&i) = self.sort_indices.get(sort) {
+            return i;
+        }
+        let flat = match sort.as_ref() {
+            Sort::Function(sorts) => FlatSort::Function(self.sorts(sorts)),
+            Sort::Atom(name, args) => FlatSort::Atom(name.clone(), self.sorts(args)),
+            Sort::Bool => FlatSort::Bool,
+            Sort::Int => FlatSort::Int,
+            Sort::Real => FlatSort::Real,
+            Sort::String => FlatSort::String,
+            Sort::RegLan => FlatSort::RegLan,
+            Sort::Array(x, y) => FlatSort::Array(self.sort(x), self.sort(y)),
+            Sort::BitVec(width) => FlatSort::BitVec(*width),
+            Sort::FloatingPoint(eb, sb) => FlatSort::FloatingPoint(*eb, *sb),
+            Sort::RoundingMode => FlatSort::RoundingMode,
+        };
+        self.sorts.push(flat);
+        self.sort_indices.insert(sort.clone(), self.sorts.len() - 1);
+        self.sorts.len() - 1
+    }
+
+    fn sorts(&mut self, sorts: &[Rc<Sort>]) -> Vec<usize> {
+        sorts.iter().map(|s| self.sort(s)).collect()
+    }
+
+    fn term(&mut self, term: &Rc<Term>) -> usize {
+        if let Some(&i) = self.term_indices.get(term) {
+            return i;
+        }
+        let i = self.push_term(term);
+        self.term_indices.insert(term.clone(), i);
+        i
+    }
+
+    fn terms(&mut self, terms: &[Rc<Term>]) -> Vec<usize> {
+        terms.iter().map(|t| self.term(t)).collect()
+    }
+
+    /// Adds a term to the table, after adding its subterms, and returns its index. This doesn't
+    /// check if the term is already in the table.
+    fn push_term(&mut self, term: &Term) -> usize {
+        let flat = match term {
+            Term::Terminal(Terminal::Integer(i)) => FlatTerm::Integer(i.to_string()),
+            Term::Terminal(Terminal::Real(r)) => {
+                FlatTerm::Real(format!("{}/{}", r.numer(), r.denom()))
+            }
+            Term::Terminal(Terminal::String(s)) => FlatTerm::String(s.clone()),
+            Term::Terminal(Terminal::BitVec(value, width)) => {
+                FlatTerm::BitVec(value.to_string(), *width)
+            }
+            Term::Terminal(Terminal::Var(iden, sort)) => {
+                FlatTerm::Var(iden.clone(), self.sort(sort))
+            }
+            Term::App(f, args) => FlatTerm::App(self.term(f), self.terms(args)),
+            Term::Op(op, args) => FlatTerm::Op(*op, self.terms(args)),
+            Term::IndexedOp(op, indices, args) => {
+                FlatTerm::IndexedOp(*op, indices.clone(), self.terms(args))
+            }
+            Term::Quant(q, bindings, inner, patterns) => {
+                let bindings = self.binding_list(bindings, Self::sort);
+                let inner = self.term(inner);
+                let patterns = patterns.iter().map(|p| self.terms(p)).collect();
+                FlatTerm::Quant(*q, bindings, inner, patterns)
+            }
+            Term::Choice((name, sort), inner) => {
+                FlatTerm::Choice((name.clone(), self.sort(sort)), self.term(inner))
+            }
+            Term::Let(bindings, inner) => {
+                FlatTerm::Let(self.binding_list(bindings, Self::term), self.term(inner))
+            }
+            Term::Lambda(bindings, inner) => {
+                FlatTerm::Lambda(self.binding_list(bindings, Self::sort), self.term(inner))
+            }
+        };
+        self.terms.push(flat);
+        self.terms.len() - 1
+    }
+
+    fn binding_list<T>(
+        &mut self,
+        list: &BindingList<T>,
+        mut f: impl FnMut(&mut Self, &T) -> usize,
+    ) -> Vec<(String, usize)> {
+        list.iter()
+            .map(|(name, x)| (name.clone(), f(self, x)))
+            .collect()
+    }
+
+    fn command(&mut self, command: &ProofCommand) -> FlatCommand {
+        match command {
+            ProofCommand::Assume { id, term } => FlatCommand::Assume {
+                id: id.clone(),
+                term: self.term(term),
+            },
+            ProofCommand::Step(s) => FlatCommand::Step {
+                id: s.id.clone(),
+                clause: self.terms(&s.clause),
+                rule: s.rule.clone(),
+                premises: s.premises.clone(),
+                args: s
+                    .args
+                    .iter()
+                    .map(|a| match a {
+                        ProofArg::Term(t) => FlatArg::Term(self.term(t)),
+                        ProofArg::Assign(name, t) => FlatArg::Assign(name.clone(), self.term(t)),
+                    })
+                    .collect(),
+                discharge: s.discharge.clone(),
+            },
+            ProofCommand::Subproof(s) => FlatCommand::Subproof {
+                commands: s.commands.iter().map(|c| self.command(c)).collect(),
+                assignment_args: s
+                    .assignment_args
+                    .iter()
+                    .map(|(name, t)| (name.clone(), self.term(t)))
+                    .collect(),
+                variable_args: s
+                    .variable_args
+                    .iter()
+                    .map(|(name, sort)| (name.clone(), self.sort(sort)))
+                    .collect(),
+            },
+        }
+    }
+
+    fn proof(&mut self, proof: &Proof) -> FlatProof {
+        // The premises are sorted so the result doesn't depend on the iteration order of the set
+        let mut premises: Vec<_> = proof.premises.iter().map(|t| self.term(t)).collect();
+        premises.sort_unstable();
+        FlatProof {
+            premises,
+            commands: proof.commands.iter().map(|c| self.command(c)).collect(),
+            spans: proof.spans.clone(),
+            metadata: proof.metadata.clone(),
+            named_assertions: proof
+                .named_assertions
+                .iter()
+                .map(|(name, t)| (name.clone(), self.term(t)))
+                .collect(),
+            comments: proof.comments.clone(),
+        }
+    }
+
+    fn finish<T>(self, root: T) -> Flat<T> {
+        Flat {
+            sorts: self.sorts,
+            terms: self.terms,
+            root,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Rc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+impl Serialize for Term {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut flattener = Flattener::default();
+        let root = flattener.push_term(self);
+        flattener.finish(root).serialize(serializer)
+    }
+}
+
+impl Serialize for ProofCommand {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut flattener = Flattener::default();
+        let root = flattener.command(self);
+        flattener.finish(root).serialize(serializer)
+    }
+}
+
+impl Serialize for Proof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut flattener = Flattener::default();
+        let root = flattener.proof(self);
+        flattener.finish(root).serialize(serializer)
+    }
+}
+
+/// Adds the terms and sorts in the tables to a pool while deserializing a value.
+struct Unflattener {
+    sorts: Vec<Rc<Sort>>,
+    terms: Vec<Rc<Term>>,
+}
+
+impl Unflattener {
+    /// Adds all terms and sorts in the tables to the pool. Fails if a term or sort refers to one
+    /// that doesn't come before it in its table, or if a number is invalid.
+    fn new(
+        pool: &mut TermPool,
+        sorts: Vec<FlatSort>,
+        terms: Vec<FlatTerm>,
+    ) -> Result<Self, String> {
+        let mut result = Self {
+            sorts: Vec::with_capacity(sorts.len()),
+            terms: Vec::with_capacity(terms.len()),
+        };
+        for s in sorts {
+            let sort = result.unflatten_sort(s)?;
+            result.sorts.push(pool.add_sort(sort));
+        }
+        for t in terms {
+            let term = result.unflatten_term(t)?;
+            result.terms.push(pool.add(term));
+        }
+        Ok(result)
+    }
+
+    fn sort(&self, i: usize) -> Result<Rc<Sort>, String> {
+        self.sorts
+            .get(i)
+            .cloned()
+            .ok_or_else(|| format!("invalid sort index: {}", i))
+    }
+
+    fn sorts(&self, indices: Vec<usize>) -> Result<Vec<Rc<Sort>>, String> {
+        indices.into_iter().map(|i| self.sort(i)).collect()
+    }
+
+    fn term(&self, i: usize) -> Result<Rc<Term>, String> {
+        self.terms
+            .get(i)
+            .cloned()
+            .ok_or_else(|| format!("invalid term index: {}", i))
+    }
+
+    fn terms(&self, indices: Vec<usize>) -> Result<Vec<Rc<Term>>, String> {
+        indices.into_iter().map(|i| self.term(i)).collect()
+    }
+
+    fn unflatten_sort(&self, sort: FlatSort) -> Result<Sort, String> {
+        Ok(match sort {
+            FlatSort::Function(sorts) => Sort::Function(self.sorts(sorts)?),
+            FlatSort::Atom(name, args) => Sort::Atom(name, self.sorts(args)?),
+            FlatSort::Bool => Sort::Bool,
+            FlatSort::Int => Sort::Int,
+            FlatSort::Real => Sort::Real,
+            FlatSort::String => Sort::String,
+            FlatSort::RegLan => Sort::RegLan,
+            FlatSort::Array(x, y) => Sort::Array(self.sort(x)?, self.sort(y)?),
+            FlatSort::BitVec(width) => Sort::BitVec(width),
+            FlatSort::FloatingPoint(eb, sb) => Sort::FloatingPoint(eb, sb),
+            FlatSort::RoundingMode => Sort::RoundingMode,
+        })
+    }
+
+    fn unflatten_term(&self, term: FlatTerm) -> Result<Term, String> {
+        let parse_integer = |s: &str| {
+            s.parse::<Integer>()
+                .map_err(|_| format!("invalid integer: '{}'", s))
+        };
+        Ok(match term {
+            FlatTerm::Integer(i) => Term::Terminal(Terminal::Integer(parse_integer(&i)?)),
+            FlatTerm::Real(r) => {
+                let (numer, denom) = r
+                    .split_once('/')
+                    .ok_or_else(|| format!("invalid real: '{}'", r))?;
+                let value = Rational::from((parse_integer(numer)?, parse_integer(denom)?));
+                Term::Terminal(Terminal::Real(value))
+            }
+            FlatTerm::String(s) => Term::Terminal(Terminal::String(s)),
+            FlatTerm::BitVec(value, width) => {
+                Term::Terminal(Terminal::BitVec(parse_integer(&value)?, width))
+            }
+            FlatTerm::Var(iden, sort) => Term::Terminal(Terminal::Var(iden, self.sort(sort)?)),
+            FlatTerm::App(f, args) => Term::App(self.term(f)?, self.terms(args)?),
+            FlatTerm::Op(op, args) => Term::Op(op, self.terms(args)?),
+            FlatTerm::IndexedOp(op, indices, args) => {
+                Term::IndexedOp(op, indices, self.terms(args)?)
+            }
+            FlatTerm::Quant(q, bindings, inner, patterns) => Term::Quant(
+                q,
+                BindingList(self.binding_list(bindings, Self::sort)?),
+                self.term(inner)?,
+                patterns
+                    .into_iter()
+                    .map(|p| self.terms(p))
+                    .collect::<Result<_, _>>()?,
+            ),
+            FlatTerm::Choice((name, sort), inner) => {
+                Term::Choice((name, self.sort(sort)?), self.term(inner)?)
+            }
+            FlatTerm::Let(bindings, inner) => Term::Let(
+                BindingList(self.binding_list(bindings, Self::term)?),
+                self.term(inner)?,
+            ),
+            FlatTerm::Lambda(bindings, inner) => Term::Lambda(
+                BindingList(self.binding_list(bindings, Self::sort)?),
+                self.term(inner)?,
+            ),
+        })
+    }
+
+    fn binding_list<T>(
+        &self,
+        list: Vec<(String, usize)>,
+        f: impl Fn(&Self, usize) -> Result<T, String>,
+    ) -> Result<Vec<(String, T)>, String> {
+        list.into_iter()
+            .map(|(name, i)| Ok((name, f(self, i)?)))
+            .collect()
+    }
+
+    fn command(&self, command: FlatCommand) -> Result<ProofCommand, String> {
+        Ok(match command {
+            FlatCommand::Assume { id, term } => ProofCommand::Assume { id, term: self.term(term)? },
+            FlatCommand::Step {
+                id,
+                clause,
+                rule,
+                premises,
+                args,
+                discharge,
+            } => ProofCommand::Step(ProofStep {
+                id,
+                clause: self.terms(clause)?,
+                rule,
+                premises,
+                args: args
+                    .into_iter()
+                    .map(|a| match a {
+                        FlatArg::Term(t) => Ok(ProofArg::Term(self.term(t)?)),
+                        FlatArg::Assign(name, t) => Ok(ProofArg::Assign(name, self.term(t)?)),
+                    })
+                    .collect::<Result<_, String>>()?,
+                discharge,
+            }),
+            FlatCommand::Subproof {
+                commands,
+                assignment_args,
+                variable_args,
+            } => ProofCommand::Subproof(Subproof {
+                commands: self.commands(commands)?,
+                assignment_args: self.binding_list(assignment_args, Self::term)?,
+                variable_args: self.binding_list(variable_args, Self::sort)?,
+            }),
+        })
+    }
+
+    fn commands(&self, commands: Vec<FlatCommand>) -> Result<Vec<ProofCommand>, String> {
+        commands.into_iter().map(|c| self.command(c)).collect()
+    }
+
+    fn proof(&self, proof: FlatProof) -> Result<Proof, String> {
+        Ok(Proof {
+            premises: self.terms(proof.premises)?.into_iter().collect(),
+            commands: self.commands(proof.commands)?,
+            spans: proof.spans,
+            metadata: proof.metadata,
+            named_assertions: self.binding_list(proof.named_assertions, Self::term)?,
+            comments: proof.comments,
+        })
+    }
+}
+
+/// A seed used to deserialize a [`Proof`], a [`ProofCommand`], or a term, adding its terms to the
+/// given pool. Terms are deserialized as an `Rc<Term>`.
+///
+/// The deserialized value is assumed to have been serialized by Carcará, so its terms are not sort
+/// checked again.
+///
+/// # Examples
+///
+/// ```
+/// # use carcara::ast::*;
+/// use serde::de::DeserializeSeed;
+///
+/// let mut pool = TermPool::new();
+/// let int_sort = pool.add_sort(Sort::Int);
+/// let x = pool.add(Term::var("x", int_sort));
+/// let term = pool.add(Term::Op(Operator::Add, vec![x.clone(), x]));
+///
+/// let json = serde_json::to_string(&term).unwrap();
+/// let mut deserializer = serde_json::Deserializer::from_str(&json);
+/// let result = PoolSeed::<Rc<Term>>::new(&mut pool).deserialize(&mut deserializer).unwrap();
+/// assert_eq!(term, result);
+/// ```
+pub struct PoolSeed<'a, T> {
+    pool: &'a mut TermPool,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T> PoolSeed<'a, T> {
+    /// Constructs a new seed that adds the deserialized terms to `pool`.
+    pub fn new(pool: &'a mut TermPool) -> Self {
+        Self { pool, marker: PhantomData }
+    }
+
+    fn unflatten<'de, D, F, R>(
+        self,
+        deserializer: D,
+        f: impl FnOnce(&Unflattener, F) -> Result<R, String>,
+    ) -> Result<R, D::Error>
+    where
+        D: Deserializer<'de>,
+        F: Deserialize<'de>,
+    {
+        let flat = Flat::<F>::deserialize(deserializer)?;
+        Unflattener::new(self.pool, flat.sorts, flat.terms)
+            .and_then(|u| f(&u, flat.root))
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for PoolSeed<'_, Rc<Term>> {
+    type Value = Rc<Term>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.unflatten(deserializer, Unflattener::term)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for PoolSeed<'_, ProofCommand> {
+    type Value = ProofCommand;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.unflatten(deserializer, Unflattener::command)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for PoolSeed<'_, Proof> {
+    type Value = Proof;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.unflatten(deserializer, Unflattener::proof)
+    }
+}
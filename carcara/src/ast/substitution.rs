@@ -1,21 +1,76 @@
-use super::{BindingList, Rc, SortedVar, Term, TermPool};
-use ahash::{AHashMap, AHashSet};
-use thiserror::Error;
+use super::{BindingList, Rc, Sort, SortedVar, Term, TermPool, ValueBindingList};
+use crate::utils::{AHashMap, AHashSet};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
 
 /// The error type for errors when constructing or applying substitutions.
-#[derive(Debug, PartialEq, Eq, Error)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum SubstitutionError {
     /// A term in the left-hand side of the substitution was not a variable.
-    #[error("term in the left-hand side of substitution is not a variable: '{0}'")]
     NotAVariable(Rc<Term>),
 
     /// One of the mappings in the substitution was mapping a term to a term of a different sort.
-    #[error("trying to substitute term '{0}' with a term of a different sort: '{1}'")]
     DifferentSorts(Rc<Term>, Rc<Term>),
 }
 
+// Since this type is also available without `std`, we can't use `thiserror` to derive these
+// implementations
+impl fmt::Display for SubstitutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubstitutionError::NotAVariable(t) => write!(
+                f,
+                "term in the left-hand side of substitution is not a variable: '{}'",
+                t
+            ),
+            SubstitutionError::DifferentSorts(a, b) => write!(
+                f,
+                "trying to substitute term '{}' with a term of a different sort: '{}'",
+                a, b
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubstitutionError {}
+
 type SubstitutionResult<T> = Result<T, SubstitutionError>;
 
+/// Returns the variable and value of each assignment argument of an `anchor` command, like
+/// `(:= (x Int) t)`. The variable is given the sort of its value.
+pub fn anchor_mappings(
+    pool: &mut TermPool,
+    assignment_args: &[(String, Rc<Term>)],
+) -> Vec<(Rc<Term>, Rc<Term>)> {
+    assignment_args
+        .iter()
+        .map(|(var, value)| {
+            let var_term = Term::var(var, pool.sort(value).clone());
+            (pool.add(var_term), value.clone())
+        })
+        .collect()
+}
+
+/// Composes the assignment arguments of nested subproofs into a single substitution. The
+/// arguments are given from the outermost subproof to the innermost one. The substitution of each
+/// subproof is built with `Substitution::from_sequential`, and is composed with the substitution of
+/// the subproofs that enclose it with `Substitution::compose_nested`. The result is the
+/// substitution that the checker applies to the terms in the innermost subproof, for example in
+/// `refl` steps.
+pub fn compose_subproof_substitutions<'a>(
+    pool: &mut TermPool,
+    assignment_args: impl IntoIterator<Item = &'a [(String, Rc<Term>)]>,
+) -> SubstitutionResult<Substitution> {
+    let mut result = Substitution::empty();
+    for args in assignment_args {
+        let mappings = anchor_mappings(pool, args);
+        let inner = Substitution::from_sequential(pool, &mappings)?;
+        result = result.compose_nested(pool, &inner)?;
+    }
+    Ok(result)
+}
+
 /// Represents a capture-avoiding substitution over terms.
 ///
 /// A substitution is a mapping from variables to terms, that, when applied to a term, will replace
@@ -62,7 +117,7 @@ impl Substitution {
     /// returns an error if any term in the left-hand side is not a variable, or if any term is
     /// mapped to a term of a different sort.
     pub fn new(pool: &mut TermPool, map: AHashMap<Rc<Term>, Rc<Term>>) -> SubstitutionResult<Self> {
-        for (k, v) in map.iter() {
+        for (k, v) in &map {
             if !k.is_var() {
                 return Err(SubstitutionError::NotAVariable(k.clone()));
             }
@@ -78,6 +133,37 @@ impl Substitution {
         })
     }
 
+    /// Constructs a substitution from a sequence of mappings, where each mapping is applied to the
+    /// values of the mappings after it. For example, the mappings `y -> z` and `x -> (f y)` result
+    /// in the substitution `{y -> z, x -> (f z)}`. This is how the assignment arguments of an
+    /// `anchor` command are interpreted. This returns an error if any of the mappings is not valid,
+    /// as in `Substitution::new`.
+    pub fn from_sequential(
+        pool: &mut TermPool,
+        mappings: &[(Rc<Term>, Rc<Term>)],
+    ) -> SubstitutionResult<Self> {
+        let mut result = Self::empty();
+        for (var, value) in mappings {
+            let new_value = result.apply(pool, value);
+            result.insert(pool, var.clone(), new_value)?;
+        }
+        Ok(result)
+    }
+
+    /// Composes this substitution, of a subproof, with the substitution `inner` of a subproof
+    /// nested directly in it. In the result, each variable mapped by `inner` is mapped to its value
+    /// in `inner`, and each variable mapped by this substitution is mapped to its value here,
+    /// renamed by `inner` if that value is itself a variable mapped by `inner`. Mappings of this
+    /// substitution take precedence over mappings of `inner` for the same variable.
+    pub fn compose_nested(&self, pool: &mut TermPool, inner: &Self) -> SubstitutionResult<Self> {
+        let mut map = inner.map.clone();
+        for (k, v) in &self.map {
+            let value = inner.map.get(v).unwrap_or(v);
+            map.insert(k.clone(), value.clone());
+        }
+        Self::new(pool, map)
+    }
+
     /// Returns `true` if the substitution is empty.
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
@@ -101,11 +187,11 @@ impl Substitution {
         // Introducing new mappings may invalidate previously defined cache entries. In particular,
         // if a term contains `x` as a free variable, the result of applying the substitution to it
         // may be different after adding the `x -> t` mapping, so we remove these cache entries.
-        self.cache.retain(|k, _| !pool.free_vars(k).contains(&x));
+        self.cache.retain(|k, _| !k.free_vars(pool).contains(&x));
 
         if let Some(should_be_renamed) = &mut self.should_be_renamed {
             if x != t {
-                should_be_renamed.extend(pool.free_vars(&t).iter().cloned());
+                should_be_renamed.extend(t.free_vars(pool).iter().cloned());
                 if x.is_var() {
                     should_be_renamed.insert(x.clone());
                 }
@@ -140,11 +226,11 @@ impl Substitution {
         // See https://en.wikipedia.org/wiki/Lambda_calculus#Capture-avoiding_substitutions for
         // more details.
         let mut should_be_renamed = AHashSet::new();
-        for (x, t) in self.map.iter() {
+        for (x, t) in &self.map {
             if x == t {
                 continue; // We ignore reflexive substitutions
             }
-            should_be_renamed.extend(pool.free_vars(t).iter().cloned());
+            should_be_renamed.extend(t.free_vars(pool).iter().cloned());
             if x.is_var() {
                 should_be_renamed.insert(x.clone());
             }
@@ -180,24 +266,39 @@ impl Substitution {
                 let new_args = apply_to_sequence!(args);
                 pool.add(Term::Op(*op, new_args))
             }
-            Term::Quant(q, b, t) => {
-                self.apply_to_binder(pool, term, b.as_ref(), t, false, |b, t| {
-                    Term::Quant(*q, b, t)
+            Term::IndexedOp(op, indices, args) => {
+                let new_args = apply_to_sequence!(args);
+                pool.add(Term::IndexedOp(*op, indices.clone(), new_args))
+            }
+            Term::Quant(q, b, t, patterns) => match self.enter_binder(pool, b.as_ref(), true) {
+                // The patterns are in the scope of the bindings, so they are renamed and
+                // substituted in the same way as the inner term
+                Some((new_bindings, mut renaming)) => {
+                    let new_term = self.apply_in_scope(pool, &mut renaming, t);
+                    let new_patterns = patterns
+                        .iter()
+                        .map(|p| {
+                            p.iter()
+                                .map(|t| self.apply_in_scope(pool, &mut renaming, t))
+                                .collect()
+                        })
+                        .collect();
+                    pool.add(Term::Quant(*q, new_bindings, new_term, new_patterns))
+                }
+                None => term.clone(),
+            },
+            Term::Choice(var, t) => {
+                self.apply_to_binder(pool, term, core::slice::from_ref(var), t, |mut b, t| {
+                    Term::Choice(b.0.pop().unwrap(), t)
                 })
             }
-            Term::Choice(var, t) => self.apply_to_binder(
-                pool,
-                term,
-                std::slice::from_ref(var),
-                t,
-                true,
-                |mut b, t| Term::Choice(b.0.pop().unwrap(), t),
-            ),
-            Term::Let(b, t) => self.apply_to_binder(pool, term, b.as_ref(), t, true, Term::Let),
-            Term::Lambda(b, t) => {
-                self.apply_to_binder(pool, term, b.as_ref(), t, true, Term::Lambda)
+            Term::Let(b, t) => {
+                let (new_bindings, mut renaming) = self.rename_value_binding_list(pool, b.as_ref());
+                let new_term = self.apply_in_scope(pool, &mut renaming, t);
+                pool.add(Term::Let(new_bindings, new_term))
             }
-            Term::Terminal(_) | Term::Sort(_) => term.clone(),
+            Term::Lambda(b, t) => self.apply_to_binder(pool, term, b.as_ref(), t, Term::Lambda),
+            Term::Terminal(_) => term.clone(),
         };
 
         // Since frequently a term will have more than one identical subterms, we insert the
@@ -237,17 +338,35 @@ impl Substitution {
     }
 
     /// Applies the substitution to a binder term, renaming any bound variables as needed. This
-    /// method uses the function `build_function` to construct the resulting binder term. If the
-    /// binder is a `let` or `lambda` term, `is_value_list` should be true.
+    /// method uses the function `build_function` to construct the resulting binder term. This is
+    /// used for `choice` and `lambda` terms, for which the substitution is never skipped.
     fn apply_to_binder<F: Fn(BindingList, Rc<Term>) -> Term>(
         &mut self,
         pool: &mut TermPool,
         original_term: &Rc<Term>,
         binding_list: &[SortedVar],
         inner: &Rc<Term>,
-        is_value_list: bool,
         build_function: F,
     ) -> Rc<Term> {
+        match self.enter_binder(pool, binding_list, false) {
+            Some((new_bindings, mut renaming)) => {
+                let new_term = self.apply_in_scope(pool, &mut renaming, inner);
+                pool.add(build_function(new_bindings, new_term))
+            }
+            None => original_term.clone(),
+        }
+    }
+
+    /// Prepares the substitution to be applied to the terms in the scope of a binder, renaming any
+    /// bound variables as needed. Returns the new binding list, and the renaming that must be
+    /// given to `apply_in_scope` when applying the substitution to these terms. Returns `None` if
+    /// the binder term can be left unchanged, which is only possible if `can_skip` is true.
+    fn enter_binder(
+        &mut self,
+        pool: &mut TermPool,
+        binding_list: &[SortedVar],
+        can_skip: bool,
+    ) -> Option<(BindingList, Self)> {
         self.compute_should_be_renamed(pool);
 
         // In some situations, if the substitution has only one mapping (say, `x -> t`) we can skip
@@ -258,88 +377,103 @@ impl Substitution {
         // applying the substitution to the binder term. In this case, as there is only one mapping,
         // we can just skip the substitution entirely, which is way faster in some cases. In
         // particular, the skolemization rules require this optimization to have acceptable
-        // performance. Currently, this kind of skipping in only supported for "sort" binding lists,
-        // meaning quantifier and `choice` terms.
-        if !is_value_list && self.can_skip_instead_of_renaming(pool, binding_list) {
-            return original_term.clone();
+        // performance. Currently, this kind of skipping in only done for quantifier terms.
+        if can_skip && self.can_skip_instead_of_renaming(pool, binding_list) {
+            return None;
         }
+        let mut renaming = Self::empty();
+        let mut new_vars = AHashSet::new();
+        let new_binding_list = binding_list
+            .iter()
+            .map(|(var, sort)| {
+                let new_var = self.rename_var(pool, &mut renaming, &mut new_vars, var, sort);
+                (new_var, sort.clone())
+            })
+            .collect();
+        Some((BindingList(new_binding_list), renaming))
+    }
 
-        let (new_bindings, mut renaming) =
-            self.rename_binding_list(pool, binding_list, is_value_list);
-        let new_term = if renaming.is_empty() {
-            self.apply(pool, inner)
+    /// Applies the substitution to a term in the scope of a binder, given the renaming returned by
+    /// `enter_binder`.
+    fn apply_in_scope(
+        &mut self,
+        pool: &mut TermPool,
+        renaming: &mut Self,
+        term: &Rc<Term>,
+    ) -> Rc<Term> {
+        if renaming.is_empty() {
+            self.apply(pool, term)
         } else {
             // If there are variables that would be captured by the substitution, we need
             // to rename them first
-            let renamed = renaming.apply(pool, inner);
+            let renamed = renaming.apply(pool, term);
             self.apply(pool, &renamed)
-        };
-        pool.add(build_function(new_bindings, new_term))
+        }
     }
 
-    /// Creates a new substitution that renames all variables in the binding list that may be
-    /// captured by this substitution to a new, arbitrary name. Returns that substitution, and the
-    /// new binding list, with the bindings renamed. If no variable needs to be renamed, this just
-    /// returns a clone of the binding list and an empty substitution. The name chosen when renaming
-    /// a variable is the old name with '@' appended. If the binding list is a "value" list, like in
-    /// a `let` or `lambda` term, `is_value_list` should be true.
-    fn rename_binding_list(
+    /// Applies the substitution to the values of a `let` binding list, renaming any variables that
+    /// may be captured by the substitution. Returns the new binding list, and the renaming that
+    /// must be given to `apply_in_scope` when applying the substitution to the inner term.
+    fn rename_value_binding_list(
         &mut self,
         pool: &mut TermPool,
-        binding_list: &[SortedVar],
-        is_value_list: bool,
-    ) -> (BindingList, Self) {
-        let mut new_substitution = Self::empty();
+        binding_list: &[(String, Rc<Term>)],
+    ) -> (ValueBindingList, Self) {
+        self.compute_should_be_renamed(pool);
+        let mut renaming = Self::empty();
         let mut new_vars = AHashSet::new();
         let new_binding_list = binding_list
             .iter()
             .map(|(var, value)| {
-                // If the binding list is a "sort" binding list, then `value` will be the variable's
-                // sort. Otherwise, we need to get the sort of `value`
-                let sort = if is_value_list {
-                    pool.add(Term::Sort(pool.sort(value).clone()))
-                } else {
-                    value.clone()
-                };
-
-                let mut changed = false;
-                let mut new_var = var.clone();
-
-                // We keep adding `@`s to the variable name as long as it is necessary
-                loop {
-                    if !new_vars.contains(&new_var) {
-                        let new_term = pool.add((new_var.clone(), sort.clone()).into());
-                        if !self.should_be_renamed.as_ref().unwrap().contains(&new_term) {
-                            break;
-                        }
-                    }
-                    new_var.push('@');
-                    changed = true;
-                }
-
-                if changed {
-                    // If the variable was renamed, we have to add this renaming to the resulting
-                    // substitution
-                    let old = pool.add((var.clone(), sort.clone()).into());
-                    let new = pool.add((new_var.clone(), sort).into());
-
-                    // We can safely unwrap here because `old` and `new` are guaranteed to have the
-                    // same sort
-                    new_substitution.insert(pool, old, new).unwrap();
-                    new_vars.insert(new_var.clone());
-                }
-
-                // If the binding list is a "value" list, we need to apply the current substitution
-                // to each variable's value
-                let new_value = if is_value_list {
-                    new_substitution.apply(pool, value)
-                } else {
-                    value.clone()
-                };
+                // The values are not in the scope of any of the variables bound by the `let`
+                // term, so the renaming of these variables must not be applied to them
+                let new_value = self.apply(pool, value);
+                let sort = pool.sort(value).clone();
+                let new_var = self.rename_var(pool, &mut renaming, &mut new_vars, var, &sort);
                 (new_var, new_value)
             })
             .collect();
-        (BindingList(new_binding_list), new_substitution)
+        (BindingList(new_binding_list), renaming)
+    }
+
+    /// Returns the name that a bound variable must be given so it is not captured by this
+    /// substitution. If it needs to be renamed, the renaming is added to `renaming`. The name
+    /// chosen when renaming a variable is the old name with '@' appended.
+    fn rename_var(
+        &self,
+        pool: &mut TermPool,
+        renaming: &mut Self,
+        new_vars: &mut AHashSet<String>,
+        var: &str,
+        sort: &Rc<Sort>,
+    ) -> String {
+        let mut changed = false;
+        let mut new_var = String::from(var);
+
+        // We keep adding `@`s to the variable name as long as it is necessary
+        loop {
+            if !new_vars.contains(&new_var) {
+                let new_term = pool.add(Term::var(new_var.clone(), sort.clone()));
+                if !self.should_be_renamed.as_ref().unwrap().contains(&new_term) {
+                    break;
+                }
+            }
+            new_var.push('@');
+            changed = true;
+        }
+
+        if changed {
+            // If the variable was renamed, we have to add this renaming to the resulting
+            // substitution
+            let old = pool.add(Term::var(var, sort.clone()));
+            let new = pool.add(Term::var(new_var.clone(), sort.clone()));
+
+            // We can safely unwrap here because `old` and `new` are guaranteed to have the same
+            // sort
+            renaming.insert(pool, old, new).unwrap();
+            new_vars.insert(new_var.clone());
+        }
+        new_var
     }
 }
 
@@ -350,14 +484,13 @@ mod tests {
 
     fn run_test(definitions: &str, original: &str, x: &str, t: &str, result: &str) {
         let mut pool = TermPool::new();
-        let mut parser =
-            Parser::new(&mut pool, definitions.as_bytes(), true, false, false).unwrap();
-        parser.parse_problem().unwrap();
+        let (_, _, mut parser) =
+            Parser::new(&mut pool, ParserConfig::default(), definitions.as_bytes())
+                .and_then(Parser::parse_problem)
+                .unwrap();
 
-        let [original, x, t, result] = [original, x, t, result].map(|s| {
-            parser.reset(s.as_bytes()).unwrap();
-            parser.parse_term().unwrap()
-        });
+        let [original, x, t, result] =
+            [original, x, t, result].map(|s| parser.parse_term(s.as_bytes()).unwrap());
 
         let mut map = AHashMap::new();
         map.insert(x, t);
@@ -407,6 +540,10 @@ mod tests {
             "(forall ((x Int) (y Int)) (= x y))" [x -> x] => "(forall ((x Int) (y Int)) (= x y))",
             "(forall ((y Int)) (> y x))" [x -> (+ y 0)] => "(forall ((y@ Int)) (> y@ (+ y 0)))",
 
+            // Patterns are in the scope of the bindings
+            "(forall ((y Int)) (! (> y x) :pattern ((+ y x))))" [x -> y] =>
+                "(forall ((y@ Int)) (! (> y@ y) :pattern ((+ y@ y))))",
+
             "(forall ((y Int) (y@ Int)) (= y y@))" [x -> y] =>
                 "(forall ((y@ Int) (y@@ Int)) (= y@ y@@))",
             "(forall ((y Int) (y@ Int) (y@@ Int)) (= y y@ y@@))" [x -> y] =>
@@ -419,7 +556,49 @@ mod tests {
             // In theory, since x does not appear in this term, renaming y to y@ is unnecessary
             "(forall ((y Int)) (> y 0))" [x -> y] => "(forall ((y@ Int)) (> y@ 0))",
 
-            // TODO: Add tests for `choice`, `let`, and `lambda` terms
+            // Binders other than quantifiers
+            "(choice ((y Int)) (> y x))" [x -> y] => "(choice ((y@ Int)) (> y@ y))",
+            "(lambda ((y Int)) (+ y x))" [x -> y] => "(lambda ((y@ Int)) (+ y@ y))",
+            "(let ((p q)) (and p r))" [q -> r] => "(let ((p r)) (and p r))",
+            "(let ((p q)) p)" [p -> r] => "(let ((p@ q)) p@)",
+
+            // The bindings in a `let` term are parallel, so the values are not in the scope of any
+            // of the variables it binds
+            "(let ((y x)) (+ y x))" [x -> y] => "(let ((y@ y)) (+ y@ y))",
+            "(let ((x x)) (+ x 1))" [x -> y] => "(let ((x@ y)) (+ x@ 1))",
+            "(let ((p q) (r p)) (and p r))" [p -> r] => "(let ((p@ q) (r@ r)) (and p@ r@))",
+
+            // Nested binders that shadow each other
+            "(forall ((x Int)) (exists ((x Int)) (> x y)))" [y -> x] =>
+                "(forall ((x@ Int)) (exists ((x@ Int)) (> x@ x)))",
+            "(forall ((y Int)) (let ((y (+ y x))) (> y x)))" [x -> y] =>
+                "(forall ((y@ Int)) (let ((y@@ (+ y@ y))) (> y@@ y)))",
+            "(choice ((y Int)) (forall ((y Int)) (> y x)))" [x -> y] =>
+                "(choice ((y@ Int)) (forall ((y@ Int)) (> y@ y)))",
         }
     }
+
+    #[test]
+    fn test_compose_subproof_substitutions() {
+        let mut pool = TermPool::new();
+        let [y, z, y_plus_one, term, expected] = crate::parser::tests::parse_terms(
+            &mut pool,
+            "(declare-fun x () Int) (declare-fun y () Int) (declare-fun z () Int)
+            (declare-fun w () Int)",
+            ["y", "z", "(+ y 1)", "(+ x y w)", "(+ z z (+ z 1))"],
+        );
+
+        // The value of `x` in the outer subproof is renamed by the inner subproof, and the value
+        // of `w` uses the assignment to `y` that comes before it
+        let outer = [("x".to_owned(), y)];
+        let inner = [("y".to_owned(), z), ("w".to_owned(), y_plus_one)];
+
+        let mut substitution =
+            compose_subproof_substitutions(&mut pool, [&outer[..], &inner[..]]).unwrap();
+        assert_eq!(substitution.apply(&mut pool, &term), expected);
+
+        let mut substitution = compose_subproof_substitutions(&mut pool, []).unwrap();
+        assert!(substitution.is_empty());
+        assert_eq!(substitution.apply(&mut pool, &term), term);
+    }
 }
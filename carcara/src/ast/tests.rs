@@ -32,8 +32,29 @@ fn test_free_vars() {
             ("(forall ((a Int)) (forall ((b Int)) (= a b)))", &[]),
             ("(and (forall ((a Int)) (= a 0)) (= a 0))", &["a"]),
             ("(and (= a 0) (forall ((a Int)) (= a 0)))", &["a"]),
+            ("(choice ((a Int)) (= a b))", &["b"]),
+            ("(lambda ((a Int)) (+ a b))", &["b"]),
+            ("(let ((p q)) (and p r))", &["q", "r"]),
+            ("(let ((a b)) (= a 0))", &["b"]),
+            ("(let ((a a)) (= a 0))", &["a"]),
+            ("(let ((a b) (b a)) (= a b))", &["a", "b"]),
+            ("(let ((p (not p)) (q p) (r p)) (and p q r))", &["p"]),
+            ("(forall ((a Int)) (let ((a (+ a b))) (= a 0)))", &["b"]),
+            ("(forall ((a Int)) (exists ((a Bool)) (and a p)))", &["p"]),
+            ("(choice ((p Bool)) (forall ((p Bool)) (and p q)))", &["q"]),
+            ("(forall ((a Int)) (! (= a 0) :pattern ((+ a b))))", &["b"]),
         ],
     );
+
+    // The method in `Rc<Term>` uses the same cache as the term pool
+    let mut pool = TermPool::new();
+    let [term] = parse_terms(
+        &mut pool,
+        "(declare-fun a () Int)",
+        ["(forall ((b Int)) (= a b))"],
+    );
+    let expected = pool.free_vars(&term).clone();
+    assert_eq!(&expected, term.free_vars(&mut pool));
 }
 
 #[test]
@@ -72,6 +93,10 @@ fn test_deep_eq() {
         &[
             ("(= a b)", "(= b a)"),
             ("(= p (= p (= p q)))", "(= p (= (= p q) p))"),
+            (
+                "(forall ((x Int)) (! (= x y) :pattern ((+ x y))))",
+                "(forall ((x Int)) (= y x))",
+            ),
             (
                 "(ite (= a b) (= x (+ x y)) (and p (not (= x y))))",
                 "(ite (= b a) (= (+ x y) x) (and p (not (= y x))))",
@@ -98,9 +123,584 @@ fn test_deep_eq() {
             ),
             (
                 "(let ((x 0) (y (+ x 2)) (z (< x y))) (and z (= x y)))",
-                "(let ((z 0) (x (+ z 2)) (y (< z x))) (and y (= z x)))",
+                "(let ((z 0) (x (+ x 2)) (y (< x y))) (and y (= z x)))",
             ),
         ],
         TestType::AlphaEquiv,
     );
 }
+
+#[test]
+fn test_tstp_export() {
+    let problem = "
+        (declare-sort U 0)
+        (declare-fun f (U Int) Int)
+        (declare-fun a () U)
+        (declare-fun p () Bool)
+        (assert (forall ((x Int)) (< (f a x) (+ x 1))))
+        (assert (= p (not p)))
+    ";
+    let proof = "
+        (assume h1 (forall ((x Int)) (< (f a x) (+ x 1))))
+        (assume h2 (= p (not p)))
+        (step t3 (cl (not (forall ((x Int)) (< (f a x) (+ x 1)))) (< (f a 2) (+ 2 1)))
+            :rule forall_inst :args ((:= x 2)))
+        (step t4 (cl (= (f a 2) (f a 2))) :rule eq_reflexive)
+        (step t5 (cl) :rule hole :premises (h1 h2 t3 t4))
+    ";
+    let (prelude, proof, _) = crate::parser::parse_instance(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        crate::parser::ParserConfig::default(),
+    )
+    .unwrap();
+    let mut output = Vec::new();
+    crate::ast::write_tstp_proof(&mut output, &prelude, &proof).unwrap();
+    let expected = [
+        "tff('U_type', type, 'U': $tType).",
+        "tff(f_type, type, f: ('U' * $int) > $int).",
+        "tff(a_type, type, a: 'U').",
+        "tff(p_type, type, p: $o).",
+        "tff(h1, axiom, (! [X0: $int] : $less(f(a, X0), $sum(X0, 1)))).",
+        "tff(h2, axiom, (p <=> ~ p)).",
+        "tff(t3, plain, (~ (! [X1: $int] : $less(f(a, X1), $sum(X1, 1))) | $less(f(a, 2), \
+            $sum(2, 1))), inference(forall_inst, [status(thm)], [])).",
+        "tff(t4, plain, (f(a, 2) = f(a, 2)), inference(eq_reflexive, [status(thm), \
+            theory(equality)], [])).",
+        "tff(t5, plain, $false, inference(hole, [status(thm)], [h1, h2, t3, t4])).",
+    ];
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(expected.as_slice(), output.lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_nary_operators() {
+    use crate::ast::{expand_nary, normalize_nary, Operator, Term};
+
+    let cases = [
+        ("(=> p q r)", "(=> p (=> q r))"),
+        ("(xor p q r)", "(xor (xor p q) r)"),
+        ("(< a b c)", "(and (< a b) (< b c))"),
+        ("(= a b c)", "(and (= a b) (= b c))"),
+        (
+            "(distinct a b c)",
+            "(and (distinct a b) (distinct a c) (distinct b c))",
+        ),
+        ("(+ a b c)", "(+ (+ a b) c)"),
+    ];
+    let definitions = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (declare-fun r () Bool)
+        (declare-fun a () Int)
+        (declare-fun b () Int)
+        (declare-fun c () Int)
+    ";
+    let mut pool = TermPool::new();
+    for (original, expected) in cases {
+        let [original, expected] = parse_terms(&mut pool, definitions, [original, expected]);
+        let (op, args) = match original.as_ref() {
+            Term::Op(op, args) => (*op, args),
+            _ => unreachable!(),
+        };
+        assert_eq!(Some(expected), expand_nary(&mut pool, op, args));
+    }
+
+    // Applications to two arguments are not expanded
+    let [a, b] = parse_terms(&mut pool, definitions, ["a", "b"]);
+    assert_eq!(None, expand_nary(&mut pool, Operator::LessThan, &[a, b]));
+
+    // Left associative operators are kept in their n-ary form
+    let cases = [
+        (
+            "(and (=> p q r) (< a b c))",
+            "(and (=> p (=> q r)) (and (< a b) (< b c)))",
+        ),
+        ("(not (<= (+ a b c) a))", "(not (<= (+ a b c) a))"),
+        (
+            "(=> p (= a b (+ a b c)))",
+            "(=> p (and (= a b) (= b (+ a b c))))",
+        ),
+    ];
+    for (original, expected) in cases {
+        let [original, expected] = parse_terms(&mut pool, definitions, [original, expected]);
+        assert_eq!(expected, normalize_nary(&mut pool, &original));
+    }
+}
+
+#[test]
+fn test_premise_visibility() {
+    use crate::parser::tests::parse_proof;
+
+    let mut pool = TermPool::new();
+    let proof = parse_proof(
+        &mut pool,
+        "(assume h1 true)
+        (anchor :step t2)
+        (step t2.t1 (cl) :rule rule-name)
+        (step t2.t2 (cl) :rule rule-name)
+        (step t2 (cl) :rule rule-name)
+        (step t3 (cl) :rule rule-name)",
+    );
+    // For each command, the premise indices that are visible from it, and the ones that aren't
+    let expected = [
+        ("h1", vec![], vec![(0, 0)]),
+        ("t2", vec![(0, 0)], vec![(0, 1)]),
+        ("t2.t1", vec![(0, 0)], vec![(0, 1), (1, 0)]),
+        ("t2.t2", vec![(0, 0), (1, 0)], vec![(1, 1)]),
+        ("t2", vec![(1, 0), (1, 1)], vec![(0, 1), (1, 2), (2, 0)]),
+        ("t3", vec![(0, 0), (0, 1)], vec![(0, 2), (1, 0)]),
+    ];
+    let mut iter = proof.iter();
+    for (id, visible, not_visible) in expected {
+        assert_eq!(id, iter.next().unwrap().id());
+        for p in visible {
+            assert!(iter.is_premise_visible(p), "{:?} from {}", p, id);
+        }
+        for p in not_visible {
+            assert!(!iter.is_premise_visible(p), "{:?} from {}", p, id);
+        }
+    }
+}
+
+#[test]
+fn test_dependencies() {
+    use crate::parser::tests::parse_proof;
+
+    let mut pool = TermPool::new();
+    let proof = parse_proof(
+        &mut pool,
+        "(assume h1 true)
+        (assume h2 false)
+        (anchor :step t3)
+        (assume t3.a0 true)
+        (step t3.t1 (cl) :rule rule-name :premises (h1))
+        (step t3 (cl) :rule rule-name :discharge (t3.a0))
+        (step t4 (cl) :rule rule-name :premises (t3 h2))
+        (step t5 (cl) :rule rule-name)",
+    );
+    // Positions: h1 = 0, h2 = 1, anchor of t3 = 2, t3.a0 = 3, t3.t1 = 4, t3 = 5, t4 = 6, t5 = 7
+    let graph = proof.dependency_graph();
+    assert_eq!(graph.len(), 8);
+    assert_eq!(graph.direct_dependencies(4), [0]);
+    assert_eq!(graph.direct_dependencies(5), [2, 3, 4]);
+    assert_eq!(graph.direct_dependencies(6), [1, 5]);
+    assert_eq!(graph.direct_dependents(0), [4]);
+
+    let set = proof.dependencies_of(6);
+    assert_eq!(set.direct, [1, 5]);
+    assert_eq!(set.transitive, [0, 1, 2, 3, 4, 5]);
+    assert!(set.used_by.is_empty() && set.transitively_used_by.is_empty());
+
+    let set = proof.dependencies_of(0);
+    assert!(set.direct.is_empty() && set.transitive.is_empty());
+    assert_eq!(set.used_by, [4]);
+    assert_eq!(set.transitively_used_by, [4, 5, 6]);
+
+    assert_eq!(
+        proof.dependencies_of(7),
+        crate::ast::DependencySet::default()
+    );
+}
+
+#[test]
+fn test_elided_clause() {
+    use crate::ast::{ElidedClause, Operator, Term};
+
+    let mut pool = TermPool::new();
+    let [a, small] = parse_terms(
+        &mut pool,
+        "(declare-fun a () Int) (declare-fun b () Int)",
+        ["a", "(+ a b)"],
+    );
+    assert_eq!(
+        "(cl (+ a b))",
+        ElidedClause(std::slice::from_ref(&small)).to_string()
+    );
+
+    // Each term is used twice by the next one, so the tree size of the final term is exponential
+    let mut term = small;
+    for _ in 0..6 {
+        term = pool.add(Term::Op(Operator::Add, vec![term.clone(), term]));
+    }
+    let clause = [pool.add(Term::Op(Operator::Equals, vec![term, a]))];
+    let expected = "(cl (= (+ ...#1 ...#1) a))
+  #1 = (+ ...#2 ...#2)
+  #2 = (+ ...#3 ...#3)
+  #3 = (+ ...#4 ...#4)
+  #4 = (+ ...#5 ...#5)
+  #5 = (+ (+ a b) (+ a b))";
+    assert_eq!(expected, ElidedClause(&clause).to_string());
+}
+
+#[test]
+fn test_pool_snapshot() {
+    use crate::ast::Sort;
+
+    let mut pool = TermPool::new();
+    let [a_and_b, a] = parse_terms(
+        &mut pool,
+        "(declare-fun a () Bool) (declare-fun b () Bool)",
+        ["(and a b)", "a"],
+    );
+    let mut first = pool.snapshot();
+    let mut second = pool.snapshot();
+    let num_shared = pool.stats().num_shared_terms;
+    assert_eq!(0, second.stats().num_terms);
+    assert_eq!(num_shared, second.stats().num_shared_terms);
+
+    // Terms added before the snapshots are shared by all of them, as are their sorts and free
+    // variables
+    for p in [&mut pool, &mut first, &mut second] {
+        assert_eq!(p.add(a_and_b.as_ref().clone()), a_and_b);
+        assert_eq!(**p.sort(&a_and_b), Sort::Bool);
+        assert!(p.free_vars(&a_and_b).contains(&a));
+    }
+
+    // Terms added after the snapshot was taken are not shared
+    let [not_a] = parse_terms(&mut first, "(declare-fun a () Bool)", ["(not a)"]);
+    let term = not_a.as_ref().clone();
+    let in_second = second.add(term.clone());
+    assert_ne!(in_second, not_a);
+    assert_eq!(second.add(term.clone()), in_second);
+    assert_eq!(first.add(term), not_a);
+    assert_eq!(1, first.stats().num_terms);
+
+    // Snapshots can also be taken from other snapshots. The new terms are merged into a single
+    // shared layer, without changing the layer that is still used by the other snapshots
+    let mut third = first.snapshot();
+    assert_eq!(third.add(not_a.as_ref().clone()), not_a);
+    assert_eq!(third.bool_true(), pool.bool_true());
+    assert_eq!(num_shared + 1, third.stats().num_shared_terms);
+    assert_eq!(num_shared, second.stats().num_shared_terms);
+    assert_eq!(second.add(not_a.as_ref().clone()), in_second);
+}
+
+#[test]
+fn test_sort_hash_consing() {
+    use crate::ast::Sort;
+
+    let mut pool = TermPool::new();
+    let int = pool.add_sort(Sort::Int);
+    let array = pool.add_sort(Sort::Array(int.clone(), int.clone()));
+    assert_eq!(pool.add_sort(Sort::Int), int);
+    assert_eq!(pool.add_sort(Sort::Array(int.clone(), int.clone())), array);
+    let bool_sort = pool.add_sort(Sort::Bool);
+    assert_eq!(*pool.sort(&pool.bool_true()), bool_sort);
+
+    // The sorts of terms are the same allocations as the sorts added directly
+    let [x] = parse_terms(&mut pool, "(declare-fun x () (Array Int Int))", ["x"]);
+    assert_eq!(*pool.sort(&x), array);
+
+    // Sorts added before a snapshot was taken are shared with it
+    let mut snapshot = pool.snapshot();
+    assert_eq!(snapshot.add_sort(Sort::Int), int);
+    let real = snapshot.add_sort(Sort::Real);
+    assert_ne!(pool.add_sort(Sort::Real), real);
+}
+
+#[test]
+fn test_is_ground() {
+    let definitions = "
+        (declare-fun f (Int) Int)
+        (declare-fun p (Int) Bool)
+        (declare-const a Int)
+    ";
+    let mut pool = TermPool::new();
+    let [ground, quant, inside, choice, let_term] = parse_terms(
+        &mut pool,
+        definitions,
+        [
+            "(p (f (+ a 1)))",
+            "(and (p a) (forall ((x Int)) (p x)))",
+            "(f (ite (exists ((x Int)) (p x)) a 0))",
+            "(= a (choice ((x Int)) (p x)))",
+            "(let ((x a)) (p x))",
+        ],
+    );
+    assert!(ground.is_ground(&pool));
+    for term in [&quant, &inside, &choice, &let_term] {
+        assert!(!term.is_ground(&pool), "{}", term);
+    }
+
+    // Whether a term is ground is shared with snapshots of the pool
+    let snapshot = pool.snapshot();
+    assert!(snapshot.is_ground(&ground));
+    assert!(!snapshot.is_ground(&quant));
+}
+
+#[test]
+fn test_as_fraction() {
+    use crate::num::Rational;
+
+    let cases = [
+        ("(- 3)", Some(Rational::from(-3))),
+        ("(/ 1.0 4.0)", Some(Rational::from((1, 4)))),
+        ("(- (/ 1.0 2.0))", Some(Rational::from((-1, 2)))),
+        ("(to_real 2)", Some(Rational::from(2))),
+        ("(to_real (- 2))", Some(Rational::from(-2))),
+        ("(to_int 2.5)", Some(Rational::from(2))),
+        ("(to_int (- 2.5))", Some(Rational::from(-3))),
+        ("(to_int (/ 7.0 2.0))", Some(Rational::from(3))),
+        ("(- (to_real 1))", Some(Rational::from(-1))),
+        ("(/ 1.0 0.0)", None),
+        ("(to_real n)", None),
+    ];
+    for (term, expected) in cases {
+        let mut pool = TermPool::new();
+        let [term] = parse_terms(&mut pool, "(declare-fun n () Int)", [term]);
+        assert_eq!(expected, term.as_fraction(), "{}", term);
+    }
+}
+
+#[test]
+fn test_printing_round_trip() {
+    use crate::{ast::write_alethe_proof, parser::tests::parse_proof};
+
+    let definitions = "
+        (declare-fun f (Int) Int)
+        (declare-fun |a b| () Int)
+    ";
+    let proof = "
+        (assume |h 1| (= (f |a b|) 0))
+        (anchor :step t3 :args ((x Int) (:= (y Int) |a b|)))
+        (step t3.t1 (cl (= x y)) :rule refl)
+        (step t3.t2 (cl (= (f x) (f y))) :rule cong :premises (t3.t1))
+        (step t3 (cl (= (forall ((x Int)) (= (f x) 0)) (forall ((y Int)) (= (f y) 0))))
+            :rule bind)
+        (step t4 (cl (let ((z (f |a b|))) (= (choice ((w Int)) (= w z)) z))) :rule trans
+            :premises (|h 1|) :args (1 (:= k (f 2))))
+    ";
+    let print = |input: &str| {
+        let mut pool = TermPool::new();
+        let proof = parse_proof(&mut pool, &format!("{}{}", definitions, input));
+        let mut output = Vec::new();
+        write_alethe_proof(&mut output, &proof, &pool, false).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    let printed = print(proof);
+    assert!(printed.contains("(assume |h 1| (= (f |a b|) 0))"));
+    assert!(printed.contains("(anchor :step t3 :args ((x Int) (:= (y Int) |a b|)))"));
+    assert!(printed.contains(":premises (|h 1|) :args (1 (:= k (f 2)))"));
+
+    // Printing the proof again after parsing the printed proof gives the same result
+    assert_eq!(printed, print(&printed));
+}
+
+#[test]
+fn test_proof_builder() {
+    use crate::ast::{
+        write_alethe_proof, ProofArg, ProofBuilder, ProofBuilderError, ProofCommand, Sort, Term,
+    };
+
+    let mut pool = TermPool::new();
+    let [p, not_p, x_eq_y] = parse_terms(
+        &mut pool,
+        "(declare-fun p () Bool) (declare-fun x () Int) (declare-fun y () Int)",
+        ["p", "(not p)", "(= x y)"],
+    );
+    let int_sort = pool.add_sort(Sort::Int);
+    let y = pool.add(Term::var("y", int_sort.clone()));
+
+    let mut builder = ProofBuilder::new();
+    let h1 = builder.assume(p.clone());
+    assert_eq!(h1, (0, 0));
+    builder.open_subproof(vec![("x".into(), y)], vec![("z".into(), int_sort)]);
+    assert_eq!(builder.depth(), 1);
+    let t2_h1 = builder.assume(not_p.clone());
+    let t2_t2 = builder
+        .step("resolution")
+        .premises([h1, t2_h1])
+        .add()
+        .unwrap();
+    assert_eq!(t2_t2, (1, 1));
+    let t2 = builder
+        .close_subproof("subproof")
+        .clause([not_p.clone(), x_eq_y])
+        .premises([t2_t2])
+        .discharge([t2_h1])
+        .args([ProofArg::Term(p.clone())])
+        .add()
+        .unwrap();
+    assert_eq!(t2, (0, 1));
+
+    // Commands in a closed subproof can't be used as premises
+    assert_eq!(
+        builder.step("resolution").premises([t2_h1]).add(),
+        Err(ProofBuilderError::InvalidPremise((1, 0)))
+    );
+    assert_eq!(
+        builder.step("resolution").discharge([t2]).add(),
+        Err(ProofBuilderError::DischargedNotAssume("t2".into()))
+    );
+    assert_eq!(
+        builder.close_subproof("subproof").add(),
+        Err(ProofBuilderError::NoOpenSubproof)
+    );
+    builder.step("resolution").premises([h1, t2]).add().unwrap();
+
+    let proof = builder.finish().unwrap();
+    let ids: Vec<_> = proof.iter().map(ProofCommand::id).collect();
+    assert_eq!(ids, ["h1", "t2", "t2.h1", "t2.t2", "t2", "t3"]);
+    assert_eq!(proof.premises, [p].into_iter().collect());
+
+    let mut output = Vec::new();
+    write_alethe_proof(&mut output, &proof, &pool, false).unwrap();
+    let expected = "(assume h1 p)
+(anchor :step t2 :args ((z Int) (:= (x Int) y)))
+(assume t2.h1 (not p))
+(step t2.t2 (cl) :rule resolution :premises (h1 t2.h1))
+(step t2 (cl (not p) (= x y)) :rule subproof :premises (t2.t2) :args (p) :discharge (t2.h1))
+(step t3 (cl) :rule resolution :premises (h1 t2))
+";
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+    let mut builder = ProofBuilder::new();
+    builder.open_subproof(Vec::new(), Vec::new());
+    assert_eq!(
+        builder.finish().unwrap_err(),
+        ProofBuilderError::UnclosedSubproofs(1)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    use crate::{
+        ast::{PoolSeed, Proof, ProofCommand, Rc, Term},
+        parser::tests::parse_proof,
+    };
+    use serde::de::DeserializeSeed;
+
+    let definitions = "
+        (declare-sort U 1)
+        (declare-fun f (Int) Int)
+        (declare-fun g (Int) Real)
+        (declare-fun u () (U Int))
+    ";
+    let proof = "
+        (assume h1 (= (f (f 1)) (f (f 1))))
+        (anchor :step t2 :args ((x Int) (:= (y Int) (f 1))))
+        (step t2.t1 (cl (= x y)) :rule refl)
+        (step t2 (cl (= (forall ((x Int)) (= (g x) 2.5)) (forall ((y Int)) (= (g y) 2.5))))
+            :rule bind)
+        (step t3 (cl (let ((z (f 1))) (= u u)) (= (choice ((w (_ BitVec 3))) (= w #b101)) #b000))
+            :rule trans :premises (h1) :args ((f 1) (:= k (- 3))))
+    ";
+    let mut pool = TermPool::new();
+    let proof = parse_proof(&mut pool, &format!("{}{}", definitions, proof));
+    let json = serde_json::to_string(&proof).unwrap();
+
+    // Each distinct term is serialized only once
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let terms = value["terms"].as_array().unwrap();
+    let count = |v: serde_json::Value| terms.iter().filter(|&t| *t == v).count();
+    assert_eq!(count(serde_json::json!({ "Integer": "1" })), 1);
+    assert_eq!(count(serde_json::json!({ "Real": "5/2" })), 1);
+
+    // Deserializing into the same pool gives the same terms
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let result = PoolSeed::<Proof>::new(&mut pool)
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(proof.commands, result.commands);
+    assert_eq!(proof.premises, result.premises);
+
+    let command = &proof.commands[1];
+    let json = serde_json::to_string(command).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let result = PoolSeed::<ProofCommand>::new(&mut pool)
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(*command, result);
+
+    // Deserializing into a different pool gives equal terms
+    let mut other_pool = TermPool::new();
+    let term = proof.commands[2].clause()[0].clone();
+    let json = serde_json::to_string(&term).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let result = PoolSeed::<Rc<Term>>::new(&mut other_pool)
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(term.to_string(), result.to_string());
+    assert_eq!(**pool.sort(&term), **other_pool.sort(&result));
+
+    let invalid = r#"{"sorts": [], "terms": [{"Op": ["Not", [0]]}], "root": 0}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(invalid);
+    let result = PoolSeed::<Rc<Term>>::new(&mut pool).deserialize(&mut deserializer);
+    assert_eq!(result.unwrap_err().to_string(), "invalid term index: 0");
+}
+
+#[test]
+fn test_subterm_traversal() {
+    use crate::ast::{Operator, Rc, Term, TermVisitor};
+    use ahash::AHashMap;
+
+    // Computes the depth of each subterm from the depths of its own subterms
+    #[derive(Default)]
+    struct DepthVisitor {
+        entered: usize,
+        depths: AHashMap<Rc<Term>, usize>,
+    }
+    impl TermVisitor for DepthVisitor {
+        fn enter(&mut self, _: &Rc<Term>) -> bool {
+            self.entered += 1;
+            true
+        }
+
+        fn leave(&mut self, term: &Rc<Term>) {
+            let depth = match term.as_ref() {
+                Term::Op(_, args) => 1 + args.iter().map(|a| self.depths[a]).max().unwrap(),
+                _ => 0,
+            };
+            self.depths.insert(term.clone(), depth);
+        }
+    }
+
+    // Counts the terms entered, but doesn't visit the subterms of operations
+    struct SkipOps(usize);
+    impl TermVisitor for SkipOps {
+        fn enter(&mut self, term: &Rc<Term>) -> bool {
+            self.0 += 1;
+            !matches!(term.as_ref(), Term::Op(..))
+        }
+    }
+
+    let mut pool = TermPool::new();
+    let [term] = parse_terms(
+        &mut pool,
+        "(declare-fun f (Int) Int) (declare-fun a () Int)",
+        ["(forall ((x Int)) (! (= (f x) (let ((y (f a))) (+ y a))) :pattern ((f x))))"],
+    );
+    let subterms: Vec<_> = term.subterms().map(ToString::to_string).collect();
+    let expected = [
+        "(forall ((x Int)) (! (= (f x) (let ((y (f a))) (+ y a))) :pattern ((f x))))",
+        "(= (f x) (let ((y (f a))) (+ y a)))",
+        "(f x)",
+        "f",
+        "x",
+        "(let ((y (f a))) (+ y a))",
+        "(f a)",
+        "a",
+        "(+ y a)",
+        "y",
+    ];
+    assert_eq!(subterms, expected);
+
+    // A term whose tree has 2^100 leaves, but only 101 distinct subterms
+    let [mut term] = parse_terms(&mut pool, "(declare-fun b () Int)", ["b"]);
+    for _ in 0..100 {
+        term = pool.add(Term::Op(Operator::Add, vec![term.clone(), term]));
+    }
+    assert_eq!(term.subterms().count(), 101);
+
+    let mut visitor = DepthVisitor::default();
+    term.traverse(&mut visitor);
+    assert_eq!(visitor.entered, 101);
+    assert_eq!(visitor.depths[&term], 100);
+
+    // Returning `false` from `enter` skips the subterms of a term
+    let mut visitor = SkipOps(0);
+    term.traverse(&mut visitor);
+    assert_eq!(visitor.0, 1);
+}
@@ -0,0 +1,126 @@
+//! Traversals of the subterms of a term that visit each distinct subterm only once.
+//!
+//! Since terms are hash consed, a term may share the same subterm in many places, and the size of
+//! its tree can be exponential in the number of distinct subterms. These traversals treat the term
+//! as a directed acyclic graph instead, and identify subterms by reference, so a subterm that was
+//! already visited is skipped, together with its own subterms.
+
+use super::{Rc, Term};
+use crate::utils::AHashSet;
+use alloc::{vec, vec::Vec};
+
+/// Calls `f` on each immediate subterm of `term`, from the last to the first. The subterms include
+/// the function of an application, the patterns of a quantifier, and the values of a `let` term.
+fn for_each_child_rev<'a>(term: &'a Rc<Term>, mut f: impl FnMut(&'a Rc<Term>)) {
+    match term.as_ref() {
+        Term::Terminal(_) => (),
+        Term::App(func, args) => {
+            args.iter().rev().for_each(&mut f);
+            f(func);
+        }
+        Term::Op(_, args) | Term::IndexedOp(_, _, args) => args.iter().rev().for_each(f),
+        Term::Quant(_, _, inner, patterns) => {
+            patterns.iter().flatten().rev().for_each(&mut f);
+            f(inner);
+        }
+        Term::Choice(_, inner) | Term::Lambda(_, inner) => f(inner),
+        Term::Let(bindings, inner) => {
+            f(inner);
+            bindings.iter().rev().for_each(|(_, value)| f(value));
+        }
+    }
+}
+
+/// An iterator over the distinct subterms of a term, including the term itself.
+///
+/// Subterms are yielded in pre-order, from left to right, and each distinct subterm is yielded only
+/// once, the first time it is reached. Variables bound by a binder are yielded like any other
+/// variable, and sorts are not considered subterms.
+///
+/// This struct is created by the [`subterms`](Rc::subterms) method on terms.
+///
+/// # Examples
+///
+/// ```
+/// # use carcara::ast::*;
+/// let mut pool = TermPool::new();
+/// let int_sort = pool.add_sort(Sort::Int);
+/// let x = pool.add(Term::var("x", int_sort));
+/// let x_plus_x = pool.add(Term::Op(Operator::Add, vec![x.clone(), x.clone()]));
+/// let term = pool.add(Term::Op(Operator::Mult, vec![x_plus_x.clone(), x_plus_x.clone()]));
+///
+/// let subterms: Vec<_> = term.subterms().map(|t| t.to_string()).collect();
+/// assert_eq!(subterms, ["(* (+ x x) (+ x x))", "(+ x x)", "x"]);
+/// ```
+pub struct Subterms<'a> {
+    stack: Vec<&'a Rc<Term>>,
+    visited: AHashSet<&'a Rc<Term>>,
+}
+
+impl<'a> Subterms<'a> {
+    pub(super) fn new(root: &'a Rc<Term>) -> Self {
+        Self {
+            stack: vec![root],
+            visited: AHashSet::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for Subterms<'a> {
+    type Item = &'a Rc<Term>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let term = self.stack.pop()?;
+            if self.visited.insert(term) {
+                let stack = &mut self.stack;
+                for_each_child_rev(term, |child| stack.push(child));
+                return Some(term);
+            }
+        }
+    }
+}
+
+/// A visitor for the distinct subterms of a term, used by the [`traverse`](Rc::traverse) method on
+/// terms.
+///
+/// Each distinct subterm is entered only once, the first time it is reached, in the same order as
+/// [`Subterms`]. After all of its subterms were visited, the term is left. Since subterms are never
+/// visited twice, a subterm that was already visited through another term is not entered again,
+/// but it is always left before any term that contains it. This makes it possible to compute a
+/// value for each distinct subterm from the values of its own subterms.
+pub trait TermVisitor {
+    /// Called when a term is reached for the first time, before its subterms are visited. If this
+    /// returns `false`, the subterms of the term are skipped, unless they are also reached through
+    /// another term.
+    fn enter(&mut self, term: &Rc<Term>) -> bool {
+        let _ = term;
+        true
+    }
+
+    /// Called after the subterms of a term were visited, or skipped.
+    fn leave(&mut self, term: &Rc<Term>) {
+        let _ = term;
+    }
+}
+
+pub(super) fn traverse<V: TermVisitor + ?Sized>(root: &Rc<Term>, visitor: &mut V) {
+    let mut visited = AHashSet::new();
+
+    // Each term is pushed with a flag that indicates whether it was already entered, in which case
+    // it is left when popped again
+    let mut stack = vec![(root, false)];
+    while let Some((term, entered)) = stack.pop() {
+        if entered {
+            visitor.leave(term);
+            continue;
+        }
+        if !visited.insert(term) {
+            continue;
+        }
+        stack.push((term, true));
+        if visitor.enter(term) {
+            for_each_child_rev(term, |child| stack.push((child, false)));
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! An exporter from Alethe proofs to TSTP derivations, using the TFF (typed first-order form)
+//! language of the TPTP.
+//!
+//! Each `assume` command becomes an `axiom` (or an `assumption`, for assumptions introduced inside
+//! subproofs), and each `step` command becomes an inference, whose parents are the step premises.
+//! Steps that are only valid modulo a theory are annotated with `theory(equality)` or
+//! `theory(arithmetic)`, and skolemization steps are given the `esa` status, since they only
+//! preserve satisfiability. All other steps are given the `thm` status.
+//!
+//! Operators without a TPTP counterpart, such as `select` and `store`, are exported as
+//! uninterpreted functions. Terms using `let`, `lambda` or `choice` can't be exported, and result
+//! in an error.
+
+use crate::ast::*;
+use crate::utils::AHashMap;
+use std::{borrow::Cow, io};
+
+/// Writes a proof to `dest` as a TSTP derivation. The sorts and functions declared in the
+/// problem prelude are written first, as TFF type declarations.
+pub fn write_tstp_proof(
+    dest: &mut dyn io::Write,
+    prelude: &ProblemPrelude,
+    proof: &Proof,
+) -> io::Result<()> {
+    let mut printer = TstpPrinter {
+        inner: dest,
+        bound_vars: AHashMap::new(),
+        num_bound_vars: 0,
+    };
+    printer.write_declarations(prelude)?;
+    printer.write_proof(proof)
+}
+
+/// Returns the status and theory of the inference corresponding to a rule.
+fn inference_info(rule: &str) -> (&'static str, Option<&'static str>) {
+    match rule {
+        "sko_ex" | "sko_forall" => ("esa", None),
+        "eq_reflexive" | "eq_transitive" | "eq_congruent" | "eq_congruent_pred"
+        | "eq_symmetric" | "refl" | "trans" | "cong" => ("thm", Some("equality")),
+        r if r.starts_with("la_") || r == "lia_generic" => ("thm", Some("arithmetic")),
+        _ => ("thm", None),
+    }
+}
+
+/// Formats a symbol as a TPTP atomic word, quoting it if it's not a valid lower word.
+fn tptp_name(name: &str) -> Cow<'_, str> {
+    let mut chars = name.chars();
+    let is_lower_word = chars.next().map_or(false, |c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_lower_word {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!(
+            "'{}'",
+            name.replace('\\', "\\\\").replace('\'', "\\'")
+        ))
+    }
+}
+
+/// Returns `true` if a term has sort `Bool`, in which case it must be written as a TPTP formula.
+/// Since the printer has no access to the term pool, this only looks at the term structure.
+fn is_formula(term: &Rc<Term>) -> bool {
+    match term.as_ref() {
+        Term::Terminal(Terminal::Var(_, sort)) => **sort == Sort::Bool,
+        Term::App(func, _) => match func.as_ref() {
+            Term::Terminal(Terminal::Var(_, sort)) => match sort.as_ref() {
+                Sort::Function(sorts) => **sorts.last().unwrap() == Sort::Bool,
+                _ => false,
+            },
+            _ => false,
+        },
+        Term::Op(Operator::Ite, args) => is_formula(&args[1]),
+        Term::Op(op, _) => matches!(
+            op,
+            Operator::Not
+                | Operator::Implies
+                | Operator::And
+                | Operator::Or
+                | Operator::Xor
+                | Operator::Equals
+                | Operator::Distinct
+                | Operator::LessThan
+                | Operator::GreaterThan
+                | Operator::LessEq
+                | Operator::GreaterEq
+                | Operator::IsInt
+                | Operator::BvULt
+                | Operator::BvULe
+                | Operator::BvUGt
+                | Operator::BvUGe
+                | Operator::BvSLt
+                | Operator::BvSLe
+                | Operator::BvSGt
+                | Operator::BvSGe
+                | Operator::StrLt
+                | Operator::StrLe
+                | Operator::StrPrefixOf
+                | Operator::StrSuffixOf
+                | Operator::StrContains
+                | Operator::StrIsDigit
+                | Operator::StrInRe
+                | Operator::FpLeq
+                | Operator::FpLt
+                | Operator::FpGeq
+                | Operator::FpGt
+                | Operator::FpEq
+                | Operator::FpIsNormal
+                | Operator::FpIsSubnormal
+                | Operator::FpIsZero
+                | Operator::FpIsInfinite
+                | Operator::FpIsNaN
+                | Operator::FpIsNegative
+                | Operator::FpIsPositive
+        ),
+        Term::Quant(..) => true,
+        _ => false,
+    }
+}
+
+fn unsupported(term: &Rc<Term>) -> io::Error {
+    let message = format!("term cannot be exported to TSTP: '{}'", term);
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct TstpPrinter<'a> {
+    inner: &'a mut dyn io::Write,
+
+    /// The TPTP variable names given to the variables bound by the enclosing quantifiers. Since
+    /// TPTP variables must start with an uppercase letter, bound variables are renamed to `X0`,
+    /// `X1`, etc.
+    bound_vars: AHashMap<String, Vec<String>>,
+
+    num_bound_vars: usize,
+}
+
+impl<'a> TstpPrinter<'a> {
+    fn write_declarations(&mut self, prelude: &ProblemPrelude) -> io::Result<()> {
+        for (name, _) in &prelude.sort_declarations {
+            let id = tptp_name(&format!("{}_type", name)).into_owned();
+            writeln!(
+                self.inner,
+                "tff({}, type, {}: $tType).",
+                id,
+                tptp_name(name)
+            )?;
+        }
+        for (name, sort) in &prelude.function_declarations {
+            let id = tptp_name(&format!("{}_type", name)).into_owned();
+            write!(self.inner, "tff({}, type, {}: ", id, tptp_name(name))?;
+            match sort.as_ref() {
+                Sort::Function(sorts) => {
+                    let (result, args) = sorts.split_last().unwrap();
+                    if let [arg] = args {
+                        self.write_sort(arg)?;
+                    } else {
+                        write!(self.inner, "(")?;
+                        self.write_list(args, " * ", Self::write_sort)?;
+                        write!(self.inner, ")")?;
+                    }
+                    write!(self.inner, " > ")?;
+                    self.write_sort(result)?;
+                }
+                _ => self.write_sort(sort)?,
+            }
+            writeln!(self.inner, ").")?;
+        }
+        Ok(())
+    }
+
+    fn write_proof(&mut self, proof: &Proof) -> io::Result<()> {
+        let mut iter = proof.iter();
+        while let Some(command) = iter.next() {
+            match command {
+                ProofCommand::Assume { id, term } => {
+                    let role = if proof.premises.contains(term) && !iter.is_in_subproof() {
+                        "axiom"
+                    } else {
+                        "assumption"
+                    };
+                    write!(self.inner, "tff({}, {}, ", tptp_name(id), role)?;
+                    self.write_term(term)?;
+                    if role == "assumption" {
+                        write!(self.inner, ", introduced(assumption, [])")?;
+                    }
+                    writeln!(self.inner, ").")?;
+                }
+                ProofCommand::Step(step) => {
+                    write!(self.inner, "tff({}, plain, ", tptp_name(&step.id))?;
+                    match step.clause.as_slice() {
+                        [] => write!(self.inner, "$false")?,
+                        [literal] => self.write_term(literal)?,
+                        literals => {
+                            write!(self.inner, "(")?;
+                            self.write_list(literals, " | ", Self::write_term)?;
+                            write!(self.inner, ")")?;
+                        }
+                    }
+
+                    let (status, theory) = inference_info(&step.rule);
+                    write!(
+                        self.inner,
+                        ", inference({}, [status({})",
+                        tptp_name(&step.rule),
+                        status
+                    )?;
+                    if let Some(theory) = theory {
+                        write!(self.inner, ", theory({})", theory)?;
+                    }
+                    write!(self.inner, "], [")?;
+
+                    // The end step of a subproof implicitly depends on the previous command
+                    let mut parents: Vec<_> = step
+                        .premises
+                        .iter()
+                        .map(|&p| iter.get_premise(p).id())
+                        .collect();
+                    if iter.is_end_step() {
+                        let subproof = iter.current_subproof().unwrap();
+                        if let [.., previous, _] = subproof {
+                            parents.push(previous.id());
+                        }
+                    }
+                    for (i, parent) in parents.into_iter().enumerate() {
+                        if i > 0 {
+                            write!(self.inner, ", ")?;
+                        }
+                        write!(self.inner, "{}", tptp_name(parent))?;
+                    }
+                    writeln!(self.inner, "])).")?;
+                }
+                ProofCommand::Subproof(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn write_list<T>(
+        &mut self,
+        items: &[T],
+        separator: &str,
+        mut write_item: impl FnMut(&mut Self, &T) -> io::Result<()>,
+    ) -> io::Result<()> {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                write!(self.inner, "{}", separator)?;
+            }
+            write_item(self, item)?;
+        }
+        Ok(())
+    }
+
+    fn write_sort(&mut self, sort: &Rc<Sort>) -> io::Result<()> {
+        match sort.as_ref() {
+            Sort::Bool => write!(self.inner, "$o"),
+            Sort::Int => write!(self.inner, "$int"),
+            Sort::Real => write!(self.inner, "$real"),
+            Sort::Atom(name, args) if args.is_empty() => write!(self.inner, "{}", tptp_name(name)),
+            other => write!(self.inner, "{}", tptp_name(&other.to_string())),
+        }
+    }
+
+    /// Writes an application of a TPTP function or predicate, like `$sum(a, b)`.
+    fn write_app(&mut self, head: &str, args: &[Rc<Term>]) -> io::Result<()> {
+        write!(self.inner, "{}(", head)?;
+        self.write_list(args, ", ", Self::write_term)?;
+        write!(self.inner, ")")
+    }
+
+    /// Writes an application of a binary TPTP function to a list of arguments, associating to the
+    /// left. For example, `(+ a b c)` is written as `$sum($sum(a, b), c)`.
+    fn write_left_assoc(&mut self, head: &str, args: &[Rc<Term>]) -> io::Result<()> {
+        match args {
+            [] => unreachable!(),
+            [arg] => self.write_term(arg),
+            [init @ .., last] => {
+                write!(self.inner, "{}(", head)?;
+                self.write_left_assoc(head, init)?;
+                write!(self.inner, ", ")?;
+                self.write_term(last)?;
+                write!(self.inner, ")")
+            }
+        }
+    }
+
+    /// Writes a chain of binary comparisons as a conjunction. For example, `(< a b c)` is written
+    /// as `($less(a, b) & $less(b, c))`.
+    fn write_chain(
+        &mut self,
+        args: &[Rc<Term>],
+        mut write_pair: impl FnMut(&mut Self, &Rc<Term>, &Rc<Term>) -> io::Result<()>,
+    ) -> io::Result<()> {
+        if args.len() > 2 {
+            write!(self.inner, "(")?;
+        }
+        for (i, pair) in args.windows(2).enumerate() {
+            if i > 0 {
+                write!(self.inner, " & ")?;
+            }
+            write_pair(self, &pair[0], &pair[1])?;
+        }
+        if args.len() > 2 {
+            write!(self.inner, ")")?;
+        }
+        Ok(())
+    }
+
+    fn write_op(&mut self, op: Operator, args: &[Rc<Term>]) -> io::Result<()> {
+        let infix = |connective: &'static str| {
+            move |p: &mut Self, a: &Rc<Term>, b: &Rc<Term>| {
+                write!(p.inner, "(")?;
+                p.write_term(a)?;
+                write!(p.inner, " {} ", connective)?;
+                p.write_term(b)?;
+                write!(p.inner, ")")
+            }
+        };
+        let predicate = |head: &'static str| {
+            move |p: &mut Self, a: &Rc<Term>, b: &Rc<Term>| {
+                p.write_app(head, &[a.clone(), b.clone()])
+            }
+        };
+
+        match op {
+            Operator::Not => {
+                write!(self.inner, "~ ")?;
+                self.write_term(&args[0])
+            }
+            Operator::And | Operator::Or if args.is_empty() => {
+                let constant = if op == Operator::And {
+                    "$true"
+                } else {
+                    "$false"
+                };
+                write!(self.inner, "{}", constant)
+            }
+            Operator::And | Operator::Or => {
+                let separator = if op == Operator::And { " & " } else { " | " };
+                write!(self.inner, "(")?;
+                self.write_list(args, separator, Self::write_term)?;
+                write!(self.inner, ")")
+            }
+            Operator::Implies => {
+                let (last, init) = args.split_last().unwrap();
+                write!(self.inner, "(")?;
+                for a in init {
+                    self.write_term(a)?;
+                    write!(self.inner, " => (")?;
+                }
+                self.write_term(last)?;
+                write!(self.inner, "{})", ")".repeat(init.len()))
+            }
+            Operator::Xor => {
+                // `xor` is left associative, so `(xor a b c)` is written as `((a <~> b) <~> c)`
+                let (first, rest) = args.split_first().unwrap();
+                write!(self.inner, "{}", "(".repeat(rest.len()))?;
+                self.write_term(first)?;
+                for a in rest {
+                    write!(self.inner, " <~> ")?;
+                    self.write_term(a)?;
+                    write!(self.inner, ")")?;
+                }
+                Ok(())
+            }
+            Operator::Equals => {
+                let connective = if is_formula(&args[0]) { "<=>" } else { "=" };
+                self.write_chain(args, infix(connective))
+            }
+            Operator::Distinct => self.write_app("$distinct", args),
+            Operator::Ite => self.write_app("$ite", args),
+            Operator::Add => self.write_left_assoc("$sum", args),
+            Operator::Sub if args.len() == 1 => self.write_app("$uminus", args),
+            Operator::Sub => self.write_left_assoc("$difference", args),
+            Operator::Mult => self.write_left_assoc("$product", args),
+            Operator::IntDiv => self.write_left_assoc("$quotient_e", args),
+            Operator::RealDiv => self.write_left_assoc("$quotient", args),
+            Operator::Mod => self.write_app("$remainder_e", args),
+            Operator::LessThan => self.write_chain(args, predicate("$less")),
+            Operator::GreaterThan => self.write_chain(args, predicate("$greater")),
+            Operator::LessEq => self.write_chain(args, predicate("$lesseq")),
+            Operator::GreaterEq => self.write_chain(args, predicate("$greatereq")),
+            Operator::ToReal => self.write_app("$to_real", args),
+            Operator::ToInt => self.write_app("$to_int", args),
+            Operator::IsInt => self.write_app("$is_int", args),
+            // The remaining operators, like `abs`, `select` and the bitvector operators, have no
+            // counterpart in TPTP, so they are written as uninterpreted functions
+            _ => self.write_app(&tptp_name(&op.to_string()), args),
+        }
+    }
+
+    fn write_term(&mut self, term: &Rc<Term>) -> io::Result<()> {
+        match term.as_ref() {
+            Term::Terminal(Terminal::Integer(i)) => write!(self.inner, "{}", i),
+            Term::Terminal(Terminal::Real(r)) => {
+                if r.is_integer() {
+                    write!(self.inner, "{}.0", r.numer())
+                } else {
+                    write!(self.inner, "$quotient({}.0, {}.0)", r.numer(), r.denom())
+                }
+            }
+            Term::Terminal(Terminal::String(s)) => {
+                write!(
+                    self.inner,
+                    "\"{}\"",
+                    s.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+            }
+            _ if term.is_bool_true() => write!(self.inner, "$true"),
+            _ if term.is_bool_false() => write!(self.inner, "$false"),
+            Term::Terminal(Terminal::Var(iden, _)) => {
+                let name = iden.to_string();
+                match self.bound_vars.get(&name).and_then(|v| v.last()) {
+                    Some(var) => write!(self.inner, "{}", var),
+                    None => write!(self.inner, "{}", tptp_name(&name)),
+                }
+            }
+            Term::App(func, args) => match func.as_ref() {
+                Term::Terminal(Terminal::Var(iden, _)) => {
+                    self.write_app(&tptp_name(&iden.to_string()), args)
+                }
+                _ => Err(unsupported(term)),
+            },
+            Term::Op(op, args) => self.write_op(*op, args),
+            Term::Quant(quantifier, bindings, inner, _) => {
+                let symbol = match quantifier {
+                    Quantifier::Forall => "!",
+                    Quantifier::Exists => "?",
+                };
+                write!(self.inner, "({} [", symbol)?;
+                for (i, (name, sort)) in bindings.iter().enumerate() {
+                    let var = format!("X{}", self.num_bound_vars);
+                    self.num_bound_vars += 1;
+                    if i > 0 {
+                        write!(self.inner, ", ")?;
+                    }
+                    write!(self.inner, "{}: ", var)?;
+                    self.write_sort(sort)?;
+                    self.bound_vars.entry(name.clone()).or_default().push(var);
+                }
+                write!(self.inner, "] : ")?;
+                let result = self.write_term(inner);
+                for (name, _) in bindings {
+                    self.bound_vars.get_mut(name).unwrap().pop();
+                }
+                result?;
+                write!(self.inner, ")")
+            }
+            Term::Terminal(Terminal::BitVec(..))
+            | Term::IndexedOp(..)
+            | Term::Choice(..)
+            | Term::Let(..)
+            | Term::Lambda(..) => Err(unsupported(term)),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Discovery of benchmark instances. A corpus is given as a list of paths, each one either a proof
+//! file or a directory, which is searched recursively for proof files. The problem file of each
+//! instance is inferred from the name of its proof file. Both files may be compressed with gzip or
+//! zstd, in which case their names end in `.gz` or `.zst`.
+
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+const SMT_FILE_EXTENSIONS: [&str; 3] = ["smt", "smt2", "smt_in"];
+
+const COMPRESSED_FILE_EXTENSIONS: [&str; 2] = ["gz", "zst"];
+
+#[derive(Debug, Error)]
+pub enum CorpusError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("can't infer problem file: {}", .0.display())]
+    CantInferProblemFile(PathBuf),
+}
+
+/// Infers the problem file of a proof file, by removing extensions from its path until it ends in
+/// one of the SMT-LIB file extensions. For example, the problem file of `foo.smt2.alethe.proof` is
+/// `foo.smt2`. If that file doesn't exist, but a compressed version of it does, like
+/// `foo.smt2.gz`, that is used instead.
+pub fn infer_problem_path(proof_path: impl Into<PathBuf>) -> Result<PathBuf, CorpusError> {
+    fn inner(mut path: PathBuf) -> Option<PathBuf> {
+        while !SMT_FILE_EXTENSIONS.contains(&path.extension()?.to_str()?) {
+            path.set_extension("");
+        }
+        if path.exists() {
+            return Some(path);
+        }
+        let compressed = COMPRESSED_FILE_EXTENSIONS.iter().find_map(|ext| {
+            let mut name = path.clone().into_os_string();
+            name.push(".");
+            name.push(ext);
+            let candidate = PathBuf::from(name);
+            candidate.exists().then(|| candidate)
+        });
+        Some(compressed.unwrap_or(path))
+    }
+    let proof_path: PathBuf = proof_path.into();
+    inner(proof_path.clone()).ok_or(CorpusError::CantInferProblemFile(proof_path))
+}
+
+fn find_instances_in_dir(
+    path: PathBuf,
+    acc: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), CorpusError> {
+    let file_type = fs::metadata(&path)?.file_type();
+    if file_type.is_file() {
+        if is_proof_file(&path) {
+            let problem_file = infer_problem_path(&path)?;
+            acc.push((problem_file, path));
+        }
+    } else if file_type.is_dir() {
+        for entry in fs::read_dir(path)? {
+            find_instances_in_dir(entry?.path(), acc)?;
+        }
+    }
+    // We ignore anything that `fs::metadata` doesn't report as either a file or a directory.
+    // `fs::metadata` follows symlinks, so this should only happen if the path is something weird
+    // like a device file
+    Ok(())
+}
+
+/// Returns `true` if the file has the `.proof` extension, or the `.proof.gz` or `.proof.zst`
+/// extensions of a compressed proof.
+fn is_proof_file(path: &Path) -> bool {
+    let path = match path.extension().and_then(OsStr::to_str) {
+        Some(ext) if COMPRESSED_FILE_EXTENSIONS.contains(&ext) => {
+            Path::new(path.file_stem().unwrap())
+        }
+        _ => path,
+    };
+    path.extension() == Some(OsStr::new("proof"))
+}
+
+/// Finds all instances in the given paths, and returns the problem and proof file of each one.
+/// Paths that point to files are always considered proof files, while directories are searched
+/// recursively for files with the `.proof` extension, possibly followed by `.gz` or `.zst`.
+pub fn find_instances<P: AsRef<Path>>(
+    paths: impl IntoIterator<Item = P>,
+) -> Result<Vec<(PathBuf, PathBuf)>, CorpusError> {
+    let mut result = Vec::new();
+    for p in paths {
+        let p = p.as_ref();
+        if fs::metadata(p)?.file_type().is_file() {
+            let problem_file = infer_problem_path(p)?;
+            result.push((problem_file, p.into()));
+        } else {
+            find_instances_in_dir(p.into(), &mut result)?;
+        }
+    }
+    Ok(result)
+}
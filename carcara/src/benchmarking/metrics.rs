@@ -42,7 +42,11 @@ impl MetricsUnit for Duration {
     }
 
     fn mean_diff(self, mean: Self::MeanType) -> Self::MeanType {
-        self.abs_diff(mean)
+        if self > mean {
+            self - mean
+        } else {
+            mean - self
+        }
     }
 
     fn display(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -1,8 +1,14 @@
+pub mod corpus;
 mod metrics;
+pub mod phases;
+mod samples;
+mod slowest;
 #[cfg(test)]
 mod tests;
 
 pub use metrics::*;
+pub use samples::{SampleFormat, SampleRecorder};
+pub use slowest::{SlowStep, SlowestSteps};
 
 use ahash::AHashMap;
 use std::{fmt, io, time::Duration};
@@ -42,12 +48,63 @@ impl fmt::Display for StepId {
     }
 }
 
+impl StepId {
+    /// The proof file that contains the step.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The id of the step in the proof.
+    pub fn step_id(&self) -> &str {
+        &self.step_id
+    }
+
+    /// The rule used by the step.
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+}
+
 type RunId = (String, usize);
 
+/// The size of a step, used to compute step times normalized by the size of each step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepSize {
+    /// The number of literals in the conclusion of the step.
+    pub num_literals: usize,
+
+    /// The number of premises of the step.
+    pub num_premises: usize,
+}
+
+impl StepSize {
+    /// The size of an `assume` command, which has a single literal and no premises.
+    pub const ASSUME: Self = Self { num_literals: 1, num_premises: 0 };
+
+    /// Divides the time spent checking a step by its number of literals, or returns `None` if the
+    /// conclusion of the step is the empty clause.
+    fn time_per_literal(&self, time: Duration) -> Option<Duration> {
+        let n = u32::try_from(self.num_literals).unwrap_or(u32::MAX);
+        (n > 0).then(|| time / n)
+    }
+
+    /// Divides the time spent checking a step by its number of premises, or returns `None` if the
+    /// step has no premises.
+    fn time_per_premise(&self, time: Duration) -> Option<Duration> {
+        let n = u32::try_from(self.num_premises).unwrap_or(u32::MAX);
+        (n > 0).then(|| time / n)
+    }
+}
+
+/// The times measured in a run. `lexing` and `sort_checking` are part of `parsing`, and
+/// `rule_logic` is part of `checking`. See the `phases` module.
 #[derive(Debug, Default)]
 pub struct RunMeasurement {
     pub parsing: Duration,
+    pub lexing: Duration,
+    pub sort_checking: Duration,
     pub checking: Duration,
+    pub rule_logic: Duration,
     pub elaboration: Duration,
     pub total: Duration,
     pub deep_eq: Duration,
@@ -60,13 +117,20 @@ pub struct RunMeasurement {
 #[derive(Debug, Default)]
 pub struct BenchmarkResults<ByRun, ByStep, ByRunF64, ByDeepEq> {
     pub parsing: ByRun,
+    pub lexing: ByRun,
+    pub sort_checking: ByRun,
     pub checking: ByRun,
+    pub rule_logic: ByRun,
     pub elaborating: ByRun,
     pub total_accounted_for: ByRun,
     pub total: ByRun,
     pub step_time: ByStep,
     pub step_time_by_file: AHashMap<String, ByStep>,
     pub step_time_by_rule: AHashMap<String, ByStep>,
+    pub time_per_literal: ByStep,
+    pub time_per_literal_by_rule: AHashMap<String, ByStep>,
+    pub time_per_premise: ByStep,
+    pub time_per_premise_by_rule: AHashMap<String, ByStep>,
 
     pub deep_eq_time: ByRun,
     pub deep_eq_time_ratio: ByRunF64,
@@ -77,6 +141,12 @@ pub struct BenchmarkResults<ByRun, ByStep, ByRunF64, ByDeepEq> {
     pub deep_eq_depths: ByDeepEq,
     pub num_assumes: usize,
     pub num_easy_assumes: usize,
+
+    /// The slowest steps, if they are being tracked. See `with_slowest_steps`.
+    pub slowest_steps: SlowestSteps,
+
+    /// If present, every step and run sample is also streamed to this recorder.
+    pub recorder: Option<SampleRecorder>,
 }
 
 pub type OnlineBenchmarkResults = BenchmarkResults<
@@ -104,6 +174,22 @@ where
         Default::default()
     }
 
+    /// Constructs empty results that stream every sample added to them to `recorder`.
+    pub fn with_recorder(recorder: SampleRecorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..Default::default()
+        }
+    }
+
+    /// Keeps track of the `n` slowest steps, which can be retrieved with `slowest_steps`.
+    pub fn with_slowest_steps(self, n: usize) -> Self {
+        Self {
+            slowest_steps: SlowestSteps::new(n),
+            ..self
+        }
+    }
+
     /// Return `true` if the results have no entries.
     pub fn is_empty(&self) -> bool {
         self.total.is_empty()
@@ -114,11 +200,28 @@ where
         &self.parsing
     }
 
+    /// The time per run spent reading tokens while parsing.
+    pub fn lexing(&self) -> &ByRun {
+        &self.lexing
+    }
+
+    /// The time per run spent checking the sorts of the terms while parsing.
+    pub fn sort_checking(&self) -> &ByRun {
+        &self.sort_checking
+    }
+
     /// The time per run to check all the steps in the proof.
     pub fn checking(&self) -> &ByRun {
         &self.checking
     }
 
+    /// The time per run spent in the rules used by the steps while checking. The rest of the
+    /// checking time is spent by the checker itself, for example to check `assume` commands and
+    /// to open and close subproofs.
+    pub fn rule_logic(&self) -> &ByRun {
+        &self.rule_logic
+    }
+
     /// The time per run to elaborate the proof.
     pub fn elaborating(&self) -> &ByRun {
         &self.elaborating
@@ -148,13 +251,46 @@ where
     pub fn step_time_by_rule(&self) -> &AHashMap<String, ByStep> {
         &self.step_time_by_rule
     }
+
+    /// The time spent checking each step, divided by the number of literals in its conclusion.
+    /// Steps that conclude the empty clause are not included.
+    pub fn time_per_literal(&self) -> &ByStep {
+        &self.time_per_literal
+    }
+
+    /// For each rule, the time spent checking each step that uses that rule, divided by the number
+    /// of literals in its conclusion.
+    pub fn time_per_literal_by_rule(&self) -> &AHashMap<String, ByStep> {
+        &self.time_per_literal_by_rule
+    }
+
+    /// The time spent checking each step, divided by its number of premises. Steps that have no
+    /// premises are not included.
+    pub fn time_per_premise(&self) -> &ByStep {
+        &self.time_per_premise
+    }
+
+    /// For each rule, the time spent checking each step that uses that rule, divided by its number
+    /// of premises.
+    pub fn time_per_premise_by_rule(&self) -> &AHashMap<String, ByStep> {
+        &self.time_per_premise_by_rule
+    }
+
+    /// The slowest steps, from slowest to fastest. This is empty unless the results were
+    /// constructed using `with_slowest_steps`.
+    pub fn slowest_steps(&self) -> &[SlowStep] {
+        self.slowest_steps.steps()
+    }
 }
 
 #[derive(Default)]
 pub struct CsvBenchmarkResults {
     runs: AHashMap<RunId, RunMeasurement>,
     step_time_by_rule: AHashMap<String, OfflineMetrics<StepId>>,
+    time_per_literal_by_rule: AHashMap<String, OnlineMetrics<StepId>>,
+    time_per_premise_by_rule: AHashMap<String, OnlineMetrics<StepId>>,
     num_errors: usize,
+    recorder: Option<SampleRecorder>,
 }
 
 impl CsvBenchmarkResults {
@@ -162,6 +298,14 @@ impl CsvBenchmarkResults {
         Default::default()
     }
 
+    /// Constructs empty results that stream every sample added to them to `recorder`.
+    pub fn with_recorder(recorder: SampleRecorder) -> Self {
+        Self {
+            recorder: Some(recorder),
+            ..Default::default()
+        }
+    }
+
     pub fn num_errors(&self) -> usize {
         self.num_errors
     }
@@ -172,7 +316,12 @@ impl CsvBenchmarkResults {
         by_rule_dest: &mut dyn io::Write,
     ) -> io::Result<()> {
         Self::write_runs_csv(self.runs, runs_dest)?;
-        Self::write_by_rule_csv(self.step_time_by_rule, by_rule_dest)
+        Self::write_by_rule_csv(
+            self.step_time_by_rule,
+            &self.time_per_literal_by_rule,
+            &self.time_per_premise_by_rule,
+            by_rule_dest,
+        )
     }
 
     fn write_runs_csv(
@@ -181,8 +330,8 @@ impl CsvBenchmarkResults {
     ) -> io::Result<()> {
         writeln!(
             dest,
-            "proof_file,run_id,parsing,checking,elaboration,total_accounted_for,\
-            total,deep_eq,deep_eq_ratio,assume,assume_ratio"
+            "proof_file,run_id,parsing,lexing,sort_checking,checking,rule_logic,elaboration,\
+            total_accounted_for,total,deep_eq,deep_eq_ratio,assume,assume_ratio"
         )?;
 
         for (id, m) in data {
@@ -191,11 +340,14 @@ impl CsvBenchmarkResults {
             let assume_ratio = m.assume.as_secs_f64() / m.checking.as_secs_f64();
             writeln!(
                 dest,
-                "{},{},{},{},{},{},{},{},{},{},{}",
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
                 id.0,
                 id.1,
                 m.parsing.as_nanos(),
+                m.lexing.as_nanos(),
+                m.sort_checking.as_nanos(),
                 m.checking.as_nanos(),
+                m.rule_logic.as_nanos(),
                 m.elaboration.as_nanos(),
                 total_accounted_for.as_nanos(),
                 m.total.as_nanos(),
@@ -211,6 +363,8 @@ impl CsvBenchmarkResults {
 
     fn write_by_rule_csv(
         data: AHashMap<String, OfflineMetrics<StepId>>,
+        time_per_literal: &AHashMap<String, OnlineMetrics<StepId>>,
+        time_per_premise: &AHashMap<String, OnlineMetrics<StepId>>,
         dest: &mut dyn io::Write,
     ) -> io::Result<()> {
         let mut data: Vec<_> = data.into_iter().collect();
@@ -218,14 +372,22 @@ impl CsvBenchmarkResults {
 
         writeln!(
             dest,
-            "rule,count,total,mean,lower_whisker,first_quartile,median,third_quartile,upper_whisker"
+            "rule,count,total,mean,lower_whisker,first_quartile,median,third_quartile,\
+            upper_whisker,mean_per_literal,mean_per_premise"
         )?;
         for (rule, mut m) in data {
             let [lower_whisker, first_quartile, median, third_quartile, upper_whisker] =
                 m.quartiles().map(|(_, t)| t.as_nanos());
+
+            // Rules whose steps never have literals or premises have no normalized samples, so
+            // these columns are left empty
+            let [per_literal, per_premise] = [time_per_literal, time_per_premise].map(|map| {
+                map.get(&rule)
+                    .map_or(String::new(), |m| m.mean().as_nanos().to_string())
+            });
             writeln!(
                 dest,
-                "{},{},{},{},{},{},{},{},{}",
+                "{},{},{},{},{},{},{},{},{},{},{}",
                 rule,
                 m.count(),
                 m.total().as_nanos(),
@@ -235,14 +397,49 @@ impl CsvBenchmarkResults {
                 median,
                 third_quartile,
                 upper_whisker,
+                per_literal,
+                per_premise,
             )?;
         }
         Ok(())
     }
 }
 
+fn record_step(recorder: &Option<SampleRecorder>, id: &StepId, time: Duration) {
+    if let Some(recorder) = recorder {
+        recorder.record("step", id, time);
+    }
+}
+
+fn record_run(recorder: &Option<SampleRecorder>, id: &RunId, m: &RunMeasurement) {
+    if let Some(recorder) = recorder {
+        let key = format!("{}#{}", id.0, id.1);
+        for (metric, value) in [
+            ("parsing", m.parsing),
+            ("checking", m.checking),
+            ("elaboration", m.elaboration),
+            ("total", m.total),
+            ("deep_eq", m.deep_eq),
+            ("assume", m.assume),
+            ("assume_core", m.assume_core),
+            ("lexing", m.lexing),
+            ("sort_checking", m.sort_checking),
+            ("rule_logic", m.rule_logic),
+        ] {
+            recorder.record(metric, &key, value);
+        }
+    }
+}
+
 pub trait CollectResults {
-    fn add_step_measurement(&mut self, file: &str, step_id: &str, rule: &str, time: Duration);
+    fn add_step_measurement(
+        &mut self,
+        file: &str,
+        step_id: &str,
+        rule: &str,
+        size: StepSize,
+        time: Duration,
+    );
     fn add_assume_measurement(&mut self, file: &str, id: &str, is_easy: bool, time: Duration);
     fn add_deep_eq_depth(&mut self, depth: usize);
     fn add_run_measurement(&mut self, id: &RunId, measurement: RunMeasurement);
@@ -261,7 +458,14 @@ where
     ByRunF64: Metrics<RunId, f64> + Default,
     ByDeepEq: Metrics<(), usize> + Default,
 {
-    fn add_step_measurement(&mut self, file: &str, step_id: &str, rule: &str, time: Duration) {
+    fn add_step_measurement(
+        &mut self,
+        file: &str,
+        step_id: &str,
+        rule: &str,
+        size: StepSize,
+        time: Duration,
+    ) {
         let file = file.to_owned();
         let rule = rule.to_owned();
         let id = StepId {
@@ -269,11 +473,27 @@ where
             step_id: step_id.into(),
             rule: rule.clone().into_boxed_str(),
         };
+        record_step(&self.recorder, &id, time);
         self.step_time.add_sample(&id, time);
+        self.slowest_steps.add(&id, size, time);
         self.step_time_by_file
             .entry(file)
             .or_default()
             .add_sample(&id, time);
+        if let Some(t) = size.time_per_literal(time) {
+            self.time_per_literal.add_sample(&id, t);
+            self.time_per_literal_by_rule
+                .entry(rule.clone())
+                .or_default()
+                .add_sample(&id, t);
+        }
+        if let Some(t) = size.time_per_premise(time) {
+            self.time_per_premise.add_sample(&id, t);
+            self.time_per_premise_by_rule
+                .entry(rule.clone())
+                .or_default()
+                .add_sample(&id, t);
+        }
         self.step_time_by_rule
             .entry(rule)
             .or_default()
@@ -283,7 +503,7 @@ where
     fn add_assume_measurement(&mut self, file: &str, id: &str, is_easy: bool, time: Duration) {
         self.num_assumes += 1;
         self.num_easy_assumes += is_easy as usize;
-        self.add_step_measurement(file, id, "assume", time);
+        self.add_step_measurement(file, id, "assume", StepSize::ASSUME, time);
     }
 
     fn add_deep_eq_depth(&mut self, depth: usize) {
@@ -291,9 +511,13 @@ where
     }
 
     fn add_run_measurement(&mut self, id: &RunId, measurement: RunMeasurement) {
+        record_run(&self.recorder, id, &measurement);
         let RunMeasurement {
             parsing,
+            lexing,
+            sort_checking,
             checking,
+            rule_logic,
             elaboration,
             total,
             deep_eq,
@@ -302,7 +526,10 @@ where
         } = measurement;
 
         self.parsing.add_sample(id, parsing);
+        self.lexing.add_sample(id, lexing);
+        self.sort_checking.add_sample(id, sort_checking);
         self.checking.add_sample(id, checking);
+        self.rule_logic.add_sample(id, rule_logic);
         self.elaborating.add_sample(id, elaboration);
         self.total_accounted_for.add_sample(id, parsing + checking);
         self.total.add_sample(id, total);
@@ -320,13 +547,26 @@ where
     fn combine(a: Self, b: Self) -> Self {
         Self {
             parsing: a.parsing.combine(b.parsing),
+            lexing: a.lexing.combine(b.lexing),
+            sort_checking: a.sort_checking.combine(b.sort_checking),
             checking: a.checking.combine(b.checking),
+            rule_logic: a.rule_logic.combine(b.rule_logic),
             elaborating: a.elaborating.combine(b.elaborating),
             total_accounted_for: a.total_accounted_for.combine(b.total_accounted_for),
             total: a.total.combine(b.total),
             step_time: a.step_time.combine(b.step_time),
             step_time_by_file: combine_map(a.step_time_by_file, b.step_time_by_file),
             step_time_by_rule: combine_map(a.step_time_by_rule, b.step_time_by_rule),
+            time_per_literal: a.time_per_literal.combine(b.time_per_literal),
+            time_per_literal_by_rule: combine_map(
+                a.time_per_literal_by_rule,
+                b.time_per_literal_by_rule,
+            ),
+            time_per_premise: a.time_per_premise.combine(b.time_per_premise),
+            time_per_premise_by_rule: combine_map(
+                a.time_per_premise_by_rule,
+                b.time_per_premise_by_rule,
+            ),
 
             deep_eq_time: a.deep_eq_time.combine(b.deep_eq_time),
             deep_eq_time_ratio: a.deep_eq_time_ratio.combine(b.deep_eq_time_ratio),
@@ -337,6 +577,9 @@ where
             deep_eq_depths: a.deep_eq_depths.combine(b.deep_eq_depths),
             num_assumes: a.num_assumes + b.num_assumes,
             num_easy_assumes: a.num_easy_assumes + b.num_easy_assumes,
+
+            slowest_steps: a.slowest_steps.combine(b.slowest_steps),
+            recorder: a.recorder.or(b.recorder),
         }
     }
 
@@ -344,12 +587,32 @@ where
 }
 
 impl CollectResults for CsvBenchmarkResults {
-    fn add_step_measurement(&mut self, file: &str, step_id: &str, rule: &str, time: Duration) {
+    fn add_step_measurement(
+        &mut self,
+        file: &str,
+        step_id: &str,
+        rule: &str,
+        size: StepSize,
+        time: Duration,
+    ) {
         let id = StepId {
             file: file.into(),
             step_id: step_id.into(),
             rule: rule.into(),
         };
+        record_step(&self.recorder, &id, time);
+        if let Some(t) = size.time_per_literal(time) {
+            self.time_per_literal_by_rule
+                .entry(rule.to_owned())
+                .or_default()
+                .add_sample(&id, t);
+        }
+        if let Some(t) = size.time_per_premise(time) {
+            self.time_per_premise_by_rule
+                .entry(rule.to_owned())
+                .or_default()
+                .add_sample(&id, t);
+        }
         self.step_time_by_rule
             .entry(rule.to_owned())
             .or_default()
@@ -357,12 +620,13 @@ impl CollectResults for CsvBenchmarkResults {
     }
 
     fn add_assume_measurement(&mut self, file: &str, id: &str, _: bool, time: Duration) {
-        self.add_step_measurement(file, id, "assume", time);
+        self.add_step_measurement(file, id, "assume", StepSize::ASSUME, time);
     }
 
     fn add_deep_eq_depth(&mut self, _: usize) {}
 
     fn add_run_measurement(&mut self, id: &RunId, measurement: RunMeasurement) {
+        record_run(&self.recorder, id, &measurement);
         self.runs.insert(id.clone(), measurement);
     }
 
@@ -371,7 +635,12 @@ impl CollectResults for CsvBenchmarkResults {
         // in benchmarks anyway
         a.runs.extend(b.runs);
         a.step_time_by_rule = combine_map(a.step_time_by_rule, b.step_time_by_rule);
+        a.time_per_literal_by_rule =
+            combine_map(a.time_per_literal_by_rule, b.time_per_literal_by_rule);
+        a.time_per_premise_by_rule =
+            combine_map(a.time_per_premise_by_rule, b.time_per_premise_by_rule);
         a.num_errors += b.num_errors;
+        a.recorder = a.recorder.or(b.recorder);
         a
     }
 
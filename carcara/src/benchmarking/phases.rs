@@ -0,0 +1,172 @@
+//! A clock that breaks down the time of a run into phases, like parsing and checking, and the
+//! phases nested in them, like lexing and the logic of each rule.
+//!
+//! The clock is kept per thread, and is only running between calls to `start` and `finish`. While
+//! it is not running, marking a phase has almost no cost, so the parser and checker always mark
+//! their phases. All times are read from a single monotonic clock, and each reading both closes
+//! the phase that was running and opens the next one. This way, every instant between `start` and
+//! `finish` is attributed to exactly one phase, and the times of the phases always add up to the
+//! total time.
+
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+/// A phase of a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Parsing the problem and the proof.
+    Parsing,
+
+    /// Reading tokens from the input. This is part of parsing.
+    Lexing,
+
+    /// Running the sort rules of the operators in the terms that are parsed. This is part of
+    /// parsing.
+    SortChecking,
+
+    /// Checking the proof.
+    Checking,
+
+    /// Running the rules used by the steps, including elaborating them. This is part of checking.
+    RuleLogic,
+}
+
+impl Phase {
+    const ALL: [Phase; 5] = [
+        Phase::Parsing,
+        Phase::Lexing,
+        Phase::SortChecking,
+        Phase::Checking,
+        Phase::RuleLogic,
+    ];
+
+    /// Returns the phase that this phase is part of, if any.
+    pub fn parent(self) -> Option<Phase> {
+        match self {
+            Phase::Lexing | Phase::SortChecking => Some(Phase::Parsing),
+            Phase::RuleLogic => Some(Phase::Checking),
+            Phase::Parsing | Phase::Checking => None,
+        }
+    }
+}
+
+/// The time spent in each phase of a run. The time of each phase includes the time of the phases
+/// nested in it, so, for example, `parsing` includes `lexing`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimes {
+    pub parsing: Duration,
+    pub lexing: Duration,
+    pub sort_checking: Duration,
+    pub checking: Duration,
+    pub rule_logic: Duration,
+
+    /// The time between `start` and `finish`. This is the time of all phases, plus the time spent
+    /// outside of any phase.
+    pub total: Duration,
+}
+
+impl PhaseTimes {
+    fn get_mut(&mut self, phase: Phase) -> &mut Duration {
+        match phase {
+            Phase::Parsing => &mut self.parsing,
+            Phase::Lexing => &mut self.lexing,
+            Phase::SortChecking => &mut self.sort_checking,
+            Phase::Checking => &mut self.checking,
+            Phase::RuleLogic => &mut self.rule_logic,
+        }
+    }
+}
+
+struct PhaseClock {
+    start: Instant,
+    last: Instant,
+    open: Vec<Phase>,
+
+    /// The time spent in each phase, excluding the phases nested in it, indexed by the phase.
+    own_times: [Duration; Phase::ALL.len()],
+}
+
+impl PhaseClock {
+    /// Attributes the time since the last reading to the innermost open phase.
+    fn tick(&mut self) {
+        let now = Instant::now();
+        if let Some(&phase) = self.open.last() {
+            self.own_times[phase as usize] += now - self.last;
+        }
+        self.last = now;
+    }
+}
+
+thread_local! {
+    static CLOCK: RefCell<Option<PhaseClock>> = const { RefCell::new(None) };
+}
+
+/// Starts the clock of the current thread, discarding any times measured before.
+pub fn start() {
+    let now = Instant::now();
+    CLOCK.with(|c| {
+        *c.borrow_mut() = Some(PhaseClock {
+            start: now,
+            last: now,
+            open: Vec::new(),
+            own_times: Default::default(),
+        });
+    });
+}
+
+/// Stops the clock of the current thread, and returns the time spent in each phase since it was
+/// started. Phases that are still open are closed. If the clock was not running, all times are
+/// zero.
+pub fn finish() -> PhaseTimes {
+    let clock = CLOCK.with(|c| {
+        let mut clock = c.borrow_mut().take()?;
+        clock.tick();
+        Some(clock)
+    });
+    let clock = match clock {
+        Some(c) => c,
+        None => return PhaseTimes::default(),
+    };
+    let mut times = PhaseTimes {
+        total: clock.last - clock.start,
+        ..Default::default()
+    };
+    for phase in Phase::ALL {
+        let own = clock.own_times[phase as usize];
+        *times.get_mut(phase) += own;
+        if let Some(parent) = phase.parent() {
+            *times.get_mut(parent) += own;
+        }
+    }
+    times
+}
+
+/// Opens a phase. Until it is closed with `exit`, time is attributed to it.
+pub fn enter(phase: Phase) {
+    CLOCK.with(|c| {
+        if let Some(clock) = c.borrow_mut().as_mut() {
+            clock.tick();
+            clock.open.push(phase);
+        }
+    });
+}
+
+/// Closes the phase opened by the last call to `enter`.
+pub fn exit() {
+    CLOCK.with(|c| {
+        if let Some(clock) = c.borrow_mut().as_mut() {
+            clock.tick();
+            clock.open.pop();
+        }
+    });
+}
+
+/// Runs `f` inside of a phase.
+pub fn measure<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    enter(phase);
+    let result = f();
+    exit();
+    result
+}
@@ -0,0 +1,103 @@
+//! Streaming of raw benchmark samples. Metrics only keep aggregate values like the mean and the
+//! standard deviation, which can hide some features of the data, for example when a distribution
+//! is bimodal. A `SampleRecorder` writes every sample to a file as it is added, so the data can
+//! later be analyzed offline.
+
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The format in which samples are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// A CSV file with the columns `metric`, `key` and `nanoseconds`.
+    Csv,
+
+    /// A sequence of records, each one composed of the metric name and the key, as UTF-8 strings
+    /// prefixed by their length as a little-endian `u32`, followed by the sample value in
+    /// nanoseconds, as a little-endian `u64`.
+    Binary,
+}
+
+struct RecorderInner {
+    dest: Box<dyn io::Write + Send>,
+    format: SampleFormat,
+    error: Option<io::Error>,
+}
+
+impl RecorderInner {
+    fn write_sample(&mut self, metric: &str, key: &str, value: Duration) -> io::Result<()> {
+        // Durations longer than `u64::MAX` nanoseconds (about 584 years) are saturated
+        let nanos = u64::try_from(value.as_nanos()).unwrap_or(u64::MAX);
+        match self.format {
+            SampleFormat::Csv => writeln!(self.dest, "{},{},{}", metric, csv_field(key), nanos),
+            SampleFormat::Binary => {
+                for s in [metric, key] {
+                    self.dest.write_all(&(s.len() as u32).to_le_bytes())?;
+                    self.dest.write_all(s.as_bytes())?;
+                }
+                self.dest.write_all(&nanos.to_le_bytes())
+            }
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains any characters that would otherwise break the row.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// A destination to which raw samples are streamed. A recorder can be cloned and shared between
+/// threads, and all clones write to the same destination.
+///
+/// Since samples are recorded while metrics are collected, errors when writing them can't be
+/// reported immediately. Instead, after the first error, all further samples are ignored, and the
+/// error is returned by `SampleRecorder::finish`.
+#[derive(Clone)]
+pub struct SampleRecorder {
+    inner: Arc<Mutex<RecorderInner>>,
+}
+
+impl fmt::Debug for SampleRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SampleRecorder").finish_non_exhaustive()
+    }
+}
+
+impl SampleRecorder {
+    /// Creates a new recorder that writes to `dest` in the given format. If the format is CSV, the
+    /// header row is written immediately.
+    pub fn new(mut dest: Box<dyn io::Write + Send>, format: SampleFormat) -> io::Result<Self> {
+        if format == SampleFormat::Csv {
+            writeln!(dest, "metric,key,nanoseconds")?;
+        }
+        let inner = RecorderInner { dest, format, error: None };
+        Ok(Self { inner: Arc::new(Mutex::new(inner)) })
+    }
+
+    /// Records a sample of the metric `metric`, with the key `key`.
+    pub fn record(&self, metric: &str, key: &dyn fmt::Display, value: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.error.is_none() {
+            if let Err(e) = inner.write_sample(metric, &key.to_string(), value) {
+                inner.error = Some(e);
+            }
+        }
+    }
+
+    /// Flushes the destination, and returns the first error encountered while recording samples,
+    /// if there was any.
+    pub fn finish(&self) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.error.take() {
+            Some(e) => Err(e),
+            None => inner.dest.flush(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Tracking of the slowest steps in a benchmark. The step time metrics only keep the single worst
+//! step, which is not enough to find out where most of the checking time went. A `SlowestSteps`
+//! keeps the few slowest steps seen so far, together with their sizes.
+
+use super::{StepId, StepSize};
+use std::time::Duration;
+
+/// A step that was measured, with its size and the time spent checking it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowStep {
+    pub id: StepId,
+    pub size: StepSize,
+    pub time: Duration,
+}
+
+/// The slowest steps measured so far, up to a fixed number of steps. If a step is measured more
+/// than once, for example in different runs of a benchmark, only its slowest measurement is kept.
+#[derive(Debug, Clone, Default)]
+pub struct SlowestSteps {
+    capacity: usize,
+
+    /// The steps, from slowest to fastest.
+    steps: Vec<SlowStep>,
+}
+
+impl SlowestSteps {
+    /// Constructs an empty collection that keeps at most `capacity` steps. If `capacity` is zero,
+    /// no steps are kept.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            steps: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a measurement of a step, if it is among the slowest ones.
+    pub fn add(&mut self, id: &StepId, size: StepSize, time: Duration) {
+        let is_full = self.steps.len() >= self.capacity;
+        if self.capacity == 0 || is_full && self.steps.last().unwrap().time >= time {
+            return;
+        }
+        if let Some(i) = self.steps.iter().position(|s| s.id == *id) {
+            if self.steps[i].time >= time {
+                return;
+            }
+            self.steps.remove(i);
+        }
+        let i = self.steps.partition_point(|s| s.time >= time);
+        let step = SlowStep { id: id.clone(), size, time };
+        self.steps.insert(i, step);
+        self.steps.truncate(self.capacity);
+    }
+
+    /// Combines the steps of two collections, keeping the slowest ones.
+    pub fn combine(mut self, other: Self) -> Self {
+        self.capacity = self.capacity.max(other.capacity);
+        for step in other.steps {
+            self.add(&step.id, step.size, step.time);
+        }
+        self
+    }
+
+    /// Returns the slowest steps, from slowest to fastest.
+    pub fn steps(&self) -> &[SlowStep] {
+        &self.steps
+    }
+}
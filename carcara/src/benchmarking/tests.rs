@@ -1,6 +1,12 @@
-use super::{Duration, Metrics, MetricsUnit, OfflineMetrics, OnlineMetrics};
+use super::{
+    CollectResults, CsvBenchmarkResults, Duration, Metrics, MetricsUnit, OfflineMetrics,
+    OnlineBenchmarkResults, OnlineMetrics, RunMeasurement, SampleFormat, SampleRecorder, StepSize,
+};
 use rand::{prelude::ThreadRng, Rng};
-use std::fmt;
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+};
 
 trait IsClose {
     fn is_close(&self, other: Self) -> bool;
@@ -135,3 +141,165 @@ fn test_metrics_combine() {
     // `Metrics::add` with that entry, which makes the numerical error small again
     run_tests(10_000, 1, 1.0e-6);
 }
+
+#[test]
+fn test_sample_recorder() {
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = SharedBuffer::default();
+    let recorder = SampleRecorder::new(Box::new(buffer.clone()), SampleFormat::Csv).unwrap();
+    let mut results = OnlineBenchmarkResults::with_recorder(recorder.clone());
+    results.add_step_measurement(
+        "a,b.proof",
+        "t1",
+        "resolution",
+        StepSize::default(),
+        Duration::from_nanos(10),
+    );
+    results.add_run_measurement(
+        &("a.proof".to_owned(), 0),
+        RunMeasurement {
+            parsing: Duration::from_nanos(20),
+            ..Default::default()
+        },
+    );
+    recorder.finish().unwrap();
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(12, lines.len());
+    assert_eq!("metric,key,nanoseconds", lines[0]);
+    assert_eq!("step,\"a,b.proof:t1 (resolution)\",10", lines[1]);
+    assert_eq!("parsing,a.proof#0,20", lines[2]);
+    assert_eq!("checking,a.proof#0,0", lines[3]);
+
+    // The samples are still added to the metrics
+    assert_eq!(1, results.step_time().count());
+    assert_eq!(Duration::from_nanos(20), results.parsing().total());
+
+    let buffer = SharedBuffer::default();
+    let recorder = SampleRecorder::new(Box::new(buffer.clone()), SampleFormat::Binary).unwrap();
+    recorder.record("m", &"key", Duration::from_nanos(258));
+    recorder.finish().unwrap();
+
+    let expected: &[u8] = &[
+        1, 0, 0, 0, b'm', 3, 0, 0, 0, b'k', b'e', b'y', 2, 1, 0, 0, 0, 0, 0, 0,
+    ];
+    assert_eq!(expected, buffer.0.lock().unwrap().as_slice());
+}
+
+#[test]
+fn test_normalized_step_times() {
+    let steps = [
+        ("t1", "resolution", 2, 2, 60),
+        ("t2", "resolution", 0, 4, 40),
+        ("t3", "refl", 1, 0, 30),
+    ];
+    let mut results = OnlineBenchmarkResults::new();
+    let mut csv_results = CsvBenchmarkResults::new();
+    for (id, rule, num_literals, num_premises, nanos) in steps {
+        let size = StepSize { num_literals, num_premises };
+        let time = Duration::from_nanos(nanos);
+        results.add_step_measurement("a.proof", id, rule, size, time);
+        csv_results.add_step_measurement("a.proof", id, rule, size, time);
+    }
+
+    // Steps with an empty conclusion or no premises are not included in the respective metric
+    assert_eq!(2, results.time_per_literal().count());
+    assert_eq!(Duration::from_nanos(60), results.time_per_literal().total());
+    assert_eq!(2, results.time_per_premise().count());
+    assert_eq!(Duration::from_nanos(40), results.time_per_premise().total());
+
+    let by_rule = results.time_per_literal_by_rule();
+    assert_eq!(Duration::from_nanos(30), by_rule["resolution"].mean());
+    assert_eq!(Duration::from_nanos(30), by_rule["refl"].mean());
+    let by_rule = results.time_per_premise_by_rule();
+    assert_eq!(Duration::from_nanos(20), by_rule["resolution"].mean());
+    assert!(!by_rule.contains_key("refl"));
+
+    let (mut runs, mut by_rule) = (Vec::new(), Vec::new());
+    csv_results.write_csv(&mut runs, &mut by_rule).unwrap();
+    let by_rule = String::from_utf8(by_rule).unwrap();
+    let lines: Vec<_> = by_rule.lines().collect();
+    assert!(lines[0].ends_with(",mean_per_literal,mean_per_premise"));
+    assert!(lines[1].starts_with("refl,") && lines[1].ends_with(",30,"));
+    assert!(lines[2].starts_with("resolution,") && lines[2].ends_with(",30,20"));
+}
+
+#[test]
+fn test_slowest_steps() {
+    let steps = [
+        ("a.proof", "t1", 50),
+        ("a.proof", "t2", 10),
+        ("a.proof", "t3", 70),
+        ("b.proof", "t1", 30),
+        ("a.proof", "t1", 60),
+        ("a.proof", "t3", 20),
+    ];
+    let mut a = OnlineBenchmarkResults::new().with_slowest_steps(3);
+    let mut b = OnlineBenchmarkResults::new().with_slowest_steps(3);
+    for (i, (file, id, nanos)) in steps.into_iter().enumerate() {
+        let results = if i % 2 == 0 { &mut a } else { &mut b };
+        let size = StepSize { num_literals: i, num_premises: 0 };
+        results.add_step_measurement(file, id, "rule", size, Duration::from_nanos(nanos));
+    }
+
+    // Repeated steps only keep their slowest measurement
+    let slowest: Vec<_> = a
+        .slowest_steps()
+        .iter()
+        .map(|s| (s.id.step_id(), s.time.as_nanos(), s.size.num_literals))
+        .collect();
+    assert_eq!(vec![("t3", 70, 2), ("t1", 60, 4)], slowest);
+
+    // Results that don't track the slowest steps don't keep any
+    let mut untracked = OnlineBenchmarkResults::new();
+    untracked.add_step_measurement("a.proof", "t1", "rule", StepSize::default(), Duration::MAX);
+    assert!(untracked.slowest_steps().is_empty());
+
+    let combined = OnlineBenchmarkResults::combine(a, b);
+    let slowest: Vec<_> = combined
+        .slowest_steps()
+        .iter()
+        .map(|s| (s.id.step_id(), s.time.as_nanos()))
+        .collect();
+    assert_eq!(vec![("t3", 70), ("t1", 60), ("t1", 30)], slowest);
+}
+
+#[test]
+fn test_phase_clock() {
+    use super::phases::{self, Phase, PhaseTimes};
+    use std::thread::sleep;
+
+    // Marking phases while the clock is not running does nothing
+    phases::measure(Phase::Parsing, || sleep(Duration::from_millis(1)));
+    assert_eq!(PhaseTimes::default(), phases::finish());
+
+    phases::start();
+    phases::measure(Phase::Parsing, || {
+        phases::measure(Phase::Lexing, || sleep(Duration::from_millis(2)));
+        sleep(Duration::from_millis(1));
+    });
+    sleep(Duration::from_millis(1));
+    phases::enter(Phase::Checking);
+    phases::measure(Phase::RuleLogic, || sleep(Duration::from_millis(1)));
+    let times = phases::finish();
+
+    assert!(times.lexing >= Duration::from_millis(2));
+    assert!(times.parsing >= times.lexing + Duration::from_millis(1));
+    assert_eq!(Duration::ZERO, times.sort_checking);
+    assert!(times.rule_logic >= Duration::from_millis(1));
+    assert!(times.checking >= times.rule_logic);
+    assert!(times.total >= times.parsing + times.checking + Duration::from_millis(1));
+}
@@ -0,0 +1,49 @@
+//! Old names of rules. Some rules were renamed as the Alethe format evolved, but proofs produced by
+//! older versions of the solvers still use the old names. To keep these proofs checking, a step
+//! that uses an old name is checked by the rule that replaced it.
+
+/// Pairs of an old rule name and the current name of the rule.
+pub const RULE_ALIASES: &[(&str, &str)] = &[
+    ("connective_equiv", "connective_def"),
+    ("tmp_AC_simp", "ac_simp"),
+    ("tmp_bfun_elim", "bfun_elim"),
+    ("tmp_distinct_elim", "distinct_elim"),
+    ("tmp_nary_elim", "nary_elim"),
+];
+
+/// If `name` is the old name of a rule that was renamed, returns the current name of the rule.
+pub fn current_rule_name(name: &str) -> Option<&'static str> {
+    RULE_ALIASES
+        .iter()
+        .find(|(old, _)| *old == name)
+        .map(|&(_, new)| new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::ProofChecker;
+
+    #[test]
+    fn test_rule_aliases() {
+        for &(old, new) in RULE_ALIASES {
+            assert!(ProofChecker::get_rule(old, false).is_none());
+            assert!(ProofChecker::get_rule(new, false).is_some());
+        }
+        assert_eq!(current_rule_name("tmp_AC_simp"), Some("ac_simp"));
+        assert_eq!(current_rule_name("ac_simp"), None);
+
+        let problem = "
+            (declare-fun a () Int)
+            (declare-fun b () Int)
+            (assert (not (= (distinct a b) (not (= a b)))))
+        ";
+        let proof = "
+            (assume h1 (not (= (distinct a b) (not (= a b)))))
+            (step t2 (cl (= (distinct a b) (not (= a b)))) :rule tmp_distinct_elim)
+            (step t3 (cl) :rule resolution :premises (h1 t2))
+        ";
+        let result = crate::check(problem.as_bytes(), proof.as_bytes(), Default::default());
+        assert!(matches!(result, Ok(false)));
+    }
+}
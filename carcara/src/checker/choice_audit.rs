@@ -0,0 +1,163 @@
+//! An analysis that checks that the `choice` terms in a proof are used consistently.
+//!
+//! Skolemization rules introduce `choice` terms that stand for the Skolem constant of an
+//! existentially quantified variable. Since each step is checked independently, and many rules
+//! compare terms modulo alpha-equivalence, nothing prevents a proof from writing the same Skolem
+//! constant in different ways in different steps, for example by renaming its binder. Steps that
+//! compare terms syntactically will then consider these to be different constants, while steps that
+//! compare terms modulo renaming will consider them to be the same. This analysis rejects proofs in
+//! which two distinct `choice` terms are alpha-equivalent, so every Skolem constant must always be
+//! written with the same binder and the same body.
+
+use super::error::CheckerError;
+use crate::{ast::*, CarcaraResult, Error};
+use ahash::{AHashMap, AHashSet};
+use std::time::Duration;
+
+#[derive(Default)]
+struct ChoiceAudit {
+    visited: AHashSet<Rc<Term>>,
+
+    /// The `choice` terms found so far, grouped by the sort of their binder. Only terms in the same
+    /// group can be alpha-equivalent.
+    choice_terms: AHashMap<Rc<Sort>, Vec<Rc<Term>>>,
+}
+
+impl ChoiceAudit {
+    /// Visits all subterms of `root`, and returns an error if a `choice` term is found that is
+    /// alpha-equivalent to a different `choice` term found previously. Ground subterms can't
+    /// contain `choice` terms, so they are skipped.
+    fn visit(&mut self, pool: &TermPool, root: &Rc<Term>) -> Result<(), CheckerError> {
+        let mut stack = vec![root.clone()];
+        while let Some(term) = stack.pop() {
+            if term.is_ground(pool) || !self.visited.insert(term.clone()) {
+                continue;
+            }
+            match term.as_ref() {
+                Term::Terminal(_) => (),
+                Term::App(f, args) => {
+                    stack.push(f.clone());
+                    stack.extend(args.iter().cloned());
+                }
+                Term::Op(_, args) | Term::IndexedOp(_, _, args) => {
+                    stack.extend(args.iter().cloned());
+                }
+                Term::Quant(_, _, inner, _) | Term::Lambda(_, inner) => stack.push(inner.clone()),
+                Term::Let(bindings, inner) => {
+                    stack.extend(bindings.iter().map(|(_, value)| value.clone()));
+                    stack.push(inner.clone());
+                }
+                Term::Choice((_, sort), inner) => {
+                    let group = self.choice_terms.entry(sort.clone()).or_default();
+                    let mut time = Duration::ZERO;
+                    if let Some(previous) = group
+                        .iter()
+                        .find(|p| are_alpha_equivalent(p, &term, &mut time))
+                    {
+                        return Err(CheckerError::InconsistentChoiceTerms(
+                            previous.clone(),
+                            term.clone(),
+                        ));
+                    }
+                    group.push(term.clone());
+                    stack.push(inner.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that no two distinct `choice` terms in the proof are alpha-equivalent. The terms in
+/// every command are considered, including step arguments and anchor assignments.
+pub fn audit_choice_terms(pool: &TermPool, proof: &Proof) -> CarcaraResult<()> {
+    let mut audit = ChoiceAudit::default();
+    for (i, command) in proof.iter().enumerate() {
+        let (rule, result) = match command {
+            ProofCommand::Assume { term, .. } => ("assume", audit.visit(pool, term)),
+            ProofCommand::Step(step) => {
+                let args = step.args.iter().map(|arg| match arg {
+                    ProofArg::Term(t) | ProofArg::Assign(_, t) => t,
+                });
+                let result = step
+                    .clause
+                    .iter()
+                    .chain(args)
+                    .try_for_each(|t| audit.visit(pool, t));
+                (step.rule.as_str(), result)
+            }
+            ProofCommand::Subproof(s) => {
+                let mut values = s.assignment_args.iter().map(|(_, value)| value);
+                ("anchor", values.try_for_each(|t| audit.visit(pool, t)))
+            }
+        };
+        result.map_err(|inner| Error::Checker {
+            inner: Box::new(inner),
+            rule: rule.to_owned(),
+            step: command.id().to_owned(),
+            span: proof.spans.get(i).copied().map(Box::new),
+            premises: Vec::new(),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, ParserConfig};
+
+    #[test]
+    fn test_audit_choice_terms() {
+        let definitions = "
+            (declare-fun P (Int) Bool)
+            (declare-fun Q (Int Int) Bool)
+        ";
+        let cases = [
+            (
+                "(assume h1 (P (choice ((x Int)) (P x))))
+                (step t2 (cl (P (choice ((x Int)) (P x)))) :rule hole)",
+                true,
+            ),
+            (
+                "(assume h1 (P (choice ((x Int)) (P x))))
+                (assume h2 (P (choice ((x Int)) (not (P x)))))",
+                true,
+            ),
+            (
+                "(assume h1 (P (choice ((x Int)) (P x))))
+                (step t2 (cl (P (choice ((y Int)) (P y)))) :rule hole)",
+                false,
+            ),
+            (
+                "(assume h1 (P (choice ((x Int)) (forall ((y Int)) (Q x y)))))
+                (anchor :step t3 :args ((:= x (choice ((x Int)) (forall ((z Int)) (Q x z))))))
+                (step t3.t1 (cl) :rule hole)
+                (step t3 (cl) :rule hole)",
+                false,
+            ),
+        ];
+        for (input, expected) in cases {
+            let mut pool = TermPool::new();
+            let (_, _, mut parser) =
+                Parser::new(&mut pool, ParserConfig::default(), definitions.as_bytes())
+                    .and_then(Parser::parse_problem)
+                    .unwrap();
+            let commands = parser.parse_proof(input.as_bytes()).unwrap();
+            let proof = Proof {
+                premises: AHashSet::new(),
+                commands,
+                spans: Vec::new(),
+                metadata: Vec::new(),
+                named_assertions: Vec::new(),
+                comments: Vec::new(),
+            };
+            assert_eq!(
+                expected,
+                audit_choice_terms(&pool, &proof).is_ok(),
+                "{}",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! An index over clauses that supports duplicate and subsumption queries.
+//!
+//! Clauses are treated as sets of literals, so the order and multiplicity of the literals in a
+//! clause are ignored. A clause `c` subsumes a clause `d` if every literal of `c` is also in `d`.
+
+use crate::ast::*;
+use ahash::{AHashMap, AHashSet};
+
+/// Returns a literal from `clause` that is not in `other`, or `None` if `clause` subsumes `other`.
+pub fn find_unsubsumed_literal<'a>(
+    clause: &'a [Rc<Term>],
+    other: &[Rc<Term>],
+) -> Option<&'a Rc<Term>> {
+    let other: AHashSet<_> = other.iter().collect();
+    clause.iter().find(|l| !other.contains(l))
+}
+
+/// Returns `true` if `clause` subsumes `other`.
+pub fn subsumes(clause: &[Rc<Term>], other: &[Rc<Term>]) -> bool {
+    find_unsubsumed_literal(clause, other).is_none()
+}
+
+/// Similar to `find_unsubsumed_literal`, but the clauses are treated as multisets, so each literal
+/// must appear in `other` at least as many times as it appears in `clause`. Returns a literal that
+/// appears more times in `clause` than in `other`, or `None` if there is no such literal.
+pub fn find_unsubsumed_literal_multiset<'a>(
+    clause: &'a [Rc<Term>],
+    other: &[Rc<Term>],
+) -> Option<&'a Rc<Term>> {
+    let mut counts: AHashMap<&Rc<Term>, usize> = AHashMap::new();
+    for l in other {
+        *counts.entry(l).or_default() += 1;
+    }
+    clause.iter().find(|l| match counts.get_mut(l) {
+        Some(n) if *n > 0 => {
+            *n -= 1;
+            false
+        }
+        _ => true,
+    })
+}
+
+/// A database of clauses, each one identified by a value of type `I`. Usually, this is the index
+/// of the command that concludes the clause.
+#[derive(Debug, Clone)]
+pub struct ClauseDatabase<I> {
+    clauses: Vec<(I, AHashSet<Rc<Term>>)>,
+
+    /// For each literal, the indices in `clauses` of every clause that contains it.
+    occurrences: AHashMap<Rc<Term>, Vec<usize>>,
+
+    /// The indices in `clauses` of every empty clause. These are kept separately because they
+    /// don't appear in any occurrence list, but subsume every clause.
+    empty_clauses: Vec<usize>,
+}
+
+impl<I> Default for ClauseDatabase<I> {
+    fn default() -> Self {
+        Self {
+            clauses: Vec::new(),
+            occurrences: AHashMap::new(),
+            empty_clauses: Vec::new(),
+        }
+    }
+}
+
+impl<I> ClauseDatabase<I> {
+    /// Constructs a new, empty clause database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of clauses in the database.
+    pub fn len(&self) -> usize {
+        self.clauses.len()
+    }
+
+    /// Returns `true` if the database contains no clauses.
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    /// Inserts a clause in the database, identified by `id`. Duplicate clauses are still inserted,
+    /// and can be found with `find_duplicate` beforehand if that is not desired.
+    pub fn insert(&mut self, id: I, clause: &[Rc<Term>]) {
+        let index = self.clauses.len();
+        let literals: AHashSet<_> = clause.iter().cloned().collect();
+        if literals.is_empty() {
+            self.empty_clauses.push(index);
+        }
+        for l in &literals {
+            self.occurrences.entry(l.clone()).or_default().push(index);
+        }
+        self.clauses.push((id, literals));
+    }
+
+    /// For each clause in the database that contains at least one literal of `clause`, counts how
+    /// many distinct literals of `clause` it contains, and calls `f` with the clause index and the
+    /// current count every time this count is incremented. Stops early if `f` returns `true`, and
+    /// returns the index for which that happened.
+    fn count_hits(
+        &self,
+        clause: &[Rc<Term>],
+        mut f: impl FnMut(usize, usize) -> bool,
+    ) -> Option<usize> {
+        let literals: AHashSet<_> = clause.iter().collect();
+        let mut counts: AHashMap<usize, usize> = AHashMap::new();
+        for l in literals {
+            for &i in self.occurrences.get(l).into_iter().flatten() {
+                let count = counts.entry(i).or_insert(0);
+                *count += 1;
+                if f(i, *count) {
+                    return Some(i);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the id of a clause in the database that has exactly the same literals as `clause`,
+    /// if there is one.
+    pub fn find_duplicate(&self, clause: &[Rc<Term>]) -> Option<&I> {
+        let num_literals = clause.iter().collect::<AHashSet<_>>().len();
+        if num_literals == 0 {
+            return self.empty_clauses.first().map(|&i| &self.clauses[i].0);
+        }
+        self.count_hits(clause, |i, count| {
+            count == num_literals && self.clauses[i].1.len() == num_literals
+        })
+        .map(|i| &self.clauses[i].0)
+    }
+
+    /// Returns the id of a clause in the database that subsumes `clause`, if there is one.
+    pub fn find_subsuming(&self, clause: &[Rc<Term>]) -> Option<&I> {
+        if let Some(&i) = self.empty_clauses.first() {
+            return Some(&self.clauses[i].0);
+        }
+        self.count_hits(clause, |i, count| count == self.clauses[i].1.len())
+            .map(|i| &self.clauses[i].0)
+    }
+
+    /// Returns the ids of all clauses in the database that are subsumed by `clause`.
+    pub fn find_subsumed(&self, clause: &[Rc<Term>]) -> Vec<&I> {
+        let literals: AHashSet<_> = clause.iter().collect();
+
+        // Every clause subsumed by `clause` must appear in the occurrence list of each of its
+        // literals, so we only need to look at the shortest of these lists
+        let shortest = literals
+            .iter()
+            .map(|l| self.occurrences.get(*l).map_or(&[][..], Vec::as_slice))
+            .min_by_key(|list| list.len());
+        let candidates: Box<dyn Iterator<Item = usize>> = match shortest {
+            Some(list) => Box::new(list.iter().copied()),
+            None => Box::new(0..self.clauses.len()),
+        };
+        candidates
+            .filter(|&i| literals.iter().all(|l| self.clauses[i].1.contains(*l)))
+            .map(|i| &self.clauses[i].0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tests::parse_terms;
+
+    #[test]
+    fn test_clause_database() {
+        let mut pool = TermPool::new();
+        let [p, q, r, s] = parse_terms(
+            &mut pool,
+            "(declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (declare-fun r () Bool)
+            (declare-fun s () Bool)",
+            ["p", "q", "r", "s"],
+        );
+
+        let mut db = ClauseDatabase::new();
+        db.insert(0, &[p.clone(), q.clone()]);
+        db.insert(1, &[q.clone(), r.clone(), q.clone()]);
+        db.insert(2, &[p.clone(), q.clone(), r.clone()]);
+        assert_eq!(db.len(), 3);
+
+        assert_eq!(db.find_duplicate(&[q.clone(), p.clone()]), Some(&0));
+        assert_eq!(db.find_duplicate(&[r.clone(), q.clone()]), Some(&1));
+        assert_eq!(db.find_duplicate(std::slice::from_ref(&p)), None);
+        assert_eq!(db.find_duplicate(&[]), None);
+
+        assert_eq!(
+            db.find_subsuming(&[r.clone(), q.clone(), s.clone()]),
+            Some(&1)
+        );
+        assert_eq!(db.find_subsuming(&[p.clone(), r.clone()]), None);
+
+        let mut subsumed = db.find_subsumed(std::slice::from_ref(&q));
+        subsumed.sort();
+        assert_eq!(subsumed, [&0, &1, &2]);
+        assert_eq!(db.find_subsumed(&[p.clone(), r.clone()]), [&2]);
+        assert_eq!(
+            db.find_subsumed(std::slice::from_ref(&s)),
+            Vec::<&i32>::new()
+        );
+        assert_eq!(db.find_subsumed(&[]).len(), 3);
+
+        db.insert(3, &[]);
+        assert_eq!(db.find_duplicate(&[]), Some(&3));
+        assert_eq!(db.find_subsuming(std::slice::from_ref(&s)), Some(&3));
+    }
+
+    #[test]
+    fn test_subsumes() {
+        let mut pool = TermPool::new();
+        let [p, q, r] = parse_terms(
+            &mut pool,
+            "(declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (declare-fun r () Bool)",
+            ["p", "q", "r"],
+        );
+
+        assert!(subsumes(&[], std::slice::from_ref(&p)));
+        assert!(subsumes(
+            &[p.clone(), q.clone()],
+            &[q.clone(), r.clone(), p.clone()]
+        ));
+        assert!(subsumes(&[p.clone(), p.clone()], std::slice::from_ref(&p)));
+        assert!(!subsumes(&[p.clone(), r.clone()], &[p.clone(), q.clone()]));
+        assert_eq!(
+            find_unsubsumed_literal(&[p.clone(), r.clone()], &[p.clone(), q.clone()]),
+            Some(&r),
+        );
+
+        let multiset = find_unsubsumed_literal_multiset;
+        assert_eq!(
+            multiset(&[p.clone(), p.clone()], std::slice::from_ref(&p)),
+            Some(&p)
+        );
+        assert_eq!(
+            multiset(&[p.clone(), q.clone(), p.clone()], &[q, p.clone(), r, p]),
+            None
+        );
+    }
+}
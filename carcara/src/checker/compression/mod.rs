@@ -1,120 +1,96 @@
-#![allow(unused_must_use)]
-#![allow(unused_variables)]
-
-use crate::{ast::*};
-use std::collections::{HashMap, hash_map::Entry};
-use ahash::{AHashMap, AHashSet};
-use std::collections::VecDeque;
+use crate::ast::*;
 use crate::checker::rules::resolution::{binary_resolution, unremove_all_negations};
 use crate::checker::rules::Premise;
-use crate::ast::printer::print_proof;
-
+use ahash::{AHashMap, AHashSet};
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
 
 // Set the node as visited and, if it was visited for the second time, push it onto the the unit_nodes
-fn visit(idx: usize, visited: &mut HashMap<usize, i32>, unit_nodes: &mut Vec<usize>) -> (){
-    if !visited.contains_key(&idx) {
-        visited.insert(idx, 0);
-    }
-    else if visited[&idx] == 0 {
-        unit_nodes.push(idx);
-        *visited.get_mut(&idx).unwrap() = 1;
+fn visit(idx: usize, visited: &mut HashMap<usize, i32>, unit_nodes: &mut Vec<usize>) {
+    match visited.entry(idx) {
+        Entry::Vacant(e) => {
+            e.insert(0);
+        }
+        Entry::Occupied(mut e) => {
+            if *e.get() == 0 {
+                unit_nodes.push(idx);
+                e.insert(1);
+            }
+        }
     }
 }
 
 // Perform a DFS through the proof and get all unit nodes
-fn collect_units(proof : &Proof) -> Vec<usize> {
-    let mut curr = proof.commands.len() - 1;        // store the current node in the DFS
-    let mut queue = VecDeque::new();      // the next nodes that are going to be visited
+fn collect_units(proof: &Proof) -> Vec<usize> {
+    let mut queue = VecDeque::new(); // the next nodes that are going to be visited
     let mut visited = HashMap::new(); // the nodes that were already visited
-    let mut unit_nodes = Vec::new();           // the unit nodes that were visited more than once
-    queue.push_back(curr);
+    let mut unit_nodes = Vec::new(); // the unit nodes that were visited more than once
+    queue.push_back(proof.commands.len() - 1);
 
     // Bottom up dfs to go through the proof
-    while queue.len() > 0 {
-        curr = queue[0];
-        queue.pop_front();
-        
+    while let Some(curr) = queue.pop_front() {
         match &proof.commands[curr] {
             ProofCommand::Step(step) => {
                 // If the command has premises, add them to the queue
-                for i in 0..step.premises.len(){
-                    queue.push_front(step.premises[i].1);
+                for &(_, premise) in &step.premises {
+                    queue.push_front(premise);
                 }
 
                 // If it is a unit clause, then visit it
-                if step.clause.len() == 1{
+                if step.clause.len() == 1 {
                     visit(curr, &mut visited, &mut unit_nodes);
                 }
             }
-            ProofCommand::Assume {id: _, term} => {
-                match &**term {
-                    // If it is a terminal, it is a unit clause
-                    Term::Terminal(_) => {
-                        visit(curr, &mut visited, &mut unit_nodes);
-                    }
-                    Term::Op(_operator, terms) => {
-                        // Only visit it if it is a unit clause
-                        if terms.len() == 1{
-                            visit(curr, &mut visited, &mut unit_nodes);
-                        }
-                    }
-                    _ => {}
+            ProofCommand::Assume { term, .. } => match term.as_ref() {
+                // If it is a terminal, it is a unit clause
+                Term::Terminal(_) => visit(curr, &mut visited, &mut unit_nodes),
+
+                // Only visit it if it is a unit clause
+                Term::Op(_, terms) if terms.len() == 1 => {
+                    visit(curr, &mut visited, &mut unit_nodes);
                 }
-            }
-            _ => {}
+                _ => {}
+            },
+            ProofCommand::Subproof(_) => {}
         }
     }
 
-    return unit_nodes;
+    unit_nodes
 }
 
 // Get the node that replaced i (the answer can be i itself) using path compression
-fn find(i: usize, actual: &mut[usize]) -> usize {
+fn find(i: usize, actual: &mut [usize]) -> usize {
     if actual[i] == i {
         return i;
     }
     actual[i] = find(actual[i], actual);
-    return actual[i];
+    actual[i]
 }
 
 // Find out which nodes were replaced and by who by performing a recursive DFS
-fn fix_proof(curr: usize, proof: &Proof, unit_nodes: &[usize], dnm: &[bool], actual : &mut[usize]){
+fn fix_proof(curr: usize, proof: &Proof, dnm: &[bool], actual: &mut [usize]) {
     if dnm[curr] {
         return;
     }
 
     // Visit the current node
-    match &proof.commands[curr] {
-        ProofCommand::Step(step) => {
-            // If the command has premises, process them
-            for i in 0..step.premises.len(){
-                fix_proof(step.premises[i].1, proof, unit_nodes, dnm, actual);
-            }
-
-            // If one parent is a DNM, it must be replaced by other parent
-            let mut dnm_parents = Vec::new(); // Store all parents that were deleted
-            for i in 0..step.premises.len(){
-                let parent = step.premises[i].1;
-                if dnm[parent] {
-                    dnm_parents.push(parent);
-                }
-            }
+    if let ProofCommand::Step(step) = &proof.commands[curr] {
+        // If the command has premises, process them
+        for &(_, premise) in &step.premises {
+            fix_proof(premise, proof, dnm, actual);
+        }
 
-            // Replace the current node for its non deleted parent if it has a DNM parent
-            if dnm_parents.len() > 0 {
-                for i in 0..step.premises.len(){
-                    let parent = step.premises[i].1;
-                    if !dnm[parent] {
-                        actual[curr] = find(parent, actual);
-                    }
+        // If one parent is a DNM (that is, it was deleted), the current node must be replaced by
+        // its non deleted parent
+        if step.premises.iter().any(|&(_, parent)| dnm[parent]) {
+            for &(_, parent) in &step.premises {
+                if !dnm[parent] {
+                    actual[curr] = find(parent, actual);
                 }
             }
         }
-        _ => {}
     }
 }
 
-
 // Given the premises and conclusion of a resolution rule, find out which were the pivots used
 fn get_pivots<'a>(
     conclusion: &'a [Rc<Term>],
@@ -125,9 +101,10 @@ fn get_pivots<'a>(
     // conclusion clause
     if conclusion.is_empty() && premises.len() == 1 {
         if let [t] = premises[0].clause {
-            if match_term!((not true) = t).is_some() {
-                panic!("Cannot determine the pivots");
-            }
+            assert!(
+                match_term!((not true) = t).is_none(),
+                "Cannot determine the pivots"
+            );
         }
     }
 
@@ -148,7 +125,7 @@ fn get_pivots<'a>(
         .map(Rc::remove_all_negations)
         .map(|(n, t)| (n as i32, t))
         .collect();
-    
+
     // The working clause contains the terms from the conclusion clause that we already encountered
     let mut working_clause = AHashSet::new();
 
@@ -213,255 +190,239 @@ fn get_pivots<'a>(
     }
 
     // If we find a pivot that was set as true, then it is a valid pivot and we return it
-    for i in pivots{
-        if i.1{
-            return (pool, (i.0.0 as u32, i.0.1));
+    for ((n, pivot), eliminated) in pivots {
+        if eliminated {
+            return (pool, (n as u32, pivot));
         }
     }
     panic!("Cannot determine the pivots");
 }
 
+// Get the terms of the clause of a command, with their leading negations removed
+fn literals(command: &ProofCommand) -> Vec<(u32, &Rc<Term>)> {
+    match command {
+        ProofCommand::Step(step) => step.clause.iter().map(Rc::remove_all_negations).collect(),
+        ProofCommand::Assume { term, .. } => vec![term.remove_all_negations()],
+        ProofCommand::Subproof(_) => Vec::new(),
+    }
+}
+
 // Receives two parents and the resolution conclusion. From this, computes another resolution between the replaced parents
 fn binary_resolution_from_old(
-    pool : &mut TermPool,
-    left_parent : usize,
-    right_parent : usize,
-    new_commands : Vec<ProofCommand>,
-    curr_step : &ProofStep,
-) -> Vec<Rc<Term>>{
-    let mut current_vec = Vec::new(); //stores all the variables of the left clause with removed negations
-    let mut current = AHashSet::new(); //stores all the variables of the left clause with removed negations
-    match &new_commands[left_parent] {
-        ProofCommand::Step(step_l) => {
-            for i in 0..step_l.clause.len(){
-                current_vec.push(step_l.clause[i].remove_all_negations());
-                current.insert(step_l.clause[i].remove_all_negations());
-            }
-        }
-        ProofCommand::Assume {id: _, term: term_l} => {
-            current_vec.push(term_l.remove_all_negations());
-            current.insert(term_l.remove_all_negations());
-        }
-        _ => {}
-    }
-    
-    //create the premises necessary to decide the pivots of the resolution
-    let premises = [Premise::new((0 as usize, left_parent), &new_commands[left_parent]),
-                    Premise::new((0 as usize, right_parent),&new_commands[right_parent])];
+    pool: &mut TermPool,
+    left_parent: usize,
+    right_parent: usize,
+    new_commands: &[ProofCommand],
+    curr_step: &ProofStep,
+) -> Vec<Rc<Term>> {
+    // Stores all the terms of the left clause with removed negations
+    let current_vec = literals(&new_commands[left_parent]);
+    let mut current: AHashSet<_> = current_vec.iter().copied().collect();
+
+    // Create the premises necessary to decide the pivots of the resolution
+    let premises = [
+        Premise::new((0, left_parent), &new_commands[left_parent]),
+        Premise::new((0, right_parent), &new_commands[right_parent]),
+    ];
 
     let (pool, mut pivot) = get_pivots(&curr_step.clause, &premises, pool);
     pivot.0 = 0;
 
-    //find out if the is_pivot_in_current should be true or false
-    let mut is_pivot_in_current = true;
-    for i in 0..current_vec.len(){
-        if pivot.1 == current_vec[i].1 && current_vec[i].0 % 2 == 1{
-            is_pivot_in_current = false;
-        }
-    }
-
-    //perform the binary resolution step with the right clause and return the result
-    match &new_commands[right_parent] {
-        ProofCommand::Step(step_r) => {
-            binary_resolution(pool, &mut current, &step_r.clause, pivot, is_pivot_in_current);
-            let mut new_clause = Vec::new();
-            for i in current{
-                new_clause.push(unremove_all_negations(pool, i))
-            }
-            return new_clause;
-        }
-        ProofCommand::Assume {id: _, term: term_r} => {
-            let new_clause = [Rc::clone(term_r)];
-            binary_resolution(pool, &mut current, &new_clause[..], pivot, is_pivot_in_current);
-            let mut new_clause = Vec::new();
-            for i in current{
-                new_clause.push(unremove_all_negations(pool, i))
-            }
-            return new_clause;
-        }
-        _ => {}
-    }
-    panic!("Was not able to compute the resolution");
+    // Find out if the is_pivot_in_current should be true or false
+    let is_pivot_in_current = !current_vec.iter().any(|&(n, t)| t == pivot.1 && n % 2 == 1);
+
+    // Perform the binary resolution step with the right clause and return the result
+    let right_clause = match &new_commands[right_parent] {
+        ProofCommand::Step(step_r) => step_r.clause.as_slice(),
+        ProofCommand::Assume { term: term_r, .. } => core::slice::from_ref(term_r),
+        ProofCommand::Subproof(_) => panic!("Was not able to compute the resolution"),
+    };
+    let _ = binary_resolution(pool, &mut current, right_clause, pivot, is_pivot_in_current);
+    current
+        .into_iter()
+        .map(|t| unremove_all_negations(pool, t))
+        .collect()
 }
 
-//given a node from the old proof, add all of its ancestors and then it to a new proof
-fn add_node<'a>(curr: usize,
-            old_proof : &Proof,
-            actual : &[usize],
-            new_commands :  &'a mut Vec<ProofCommand>,
-            pool : &mut TermPool,
-            added: &mut Vec<Option<usize>>
-) -> (usize, &'a mut Vec<ProofCommand>){
-    //if it was already added, do not do anything
-    match added[curr] {
-        Some(idx) => return (idx, new_commands),
-        _ => (),
+// Given a node from the old proof, add all of its ancestors and then it to a new proof. Returns the
+// position of the node in the new proof
+fn add_node(
+    curr: usize,
+    old_proof: &Proof,
+    actual: &[usize],
+    new_commands: &mut Vec<ProofCommand>,
+    pool: &mut TermPool,
+    added: &mut Vec<Option<usize>>,
+    clauses: &mut AHashMap<Vec<Rc<Term>>, usize>,
+) -> usize {
+    // If it was already added, do not do anything
+    if let Some(idx) = added[curr] {
+        return idx;
     }
 
     match &old_proof.commands[curr] {
         ProofCommand::Step(step) => {
             // If the command has premises, add them
             let mut new_premises = Vec::new();
-            for i in 0..step.premises.len(){
-                let (added, mut _new_commands) = add_node(actual[step.premises[i].1], old_proof, actual, new_commands, pool, added);
-                new_premises.push((0 as usize, added));
-            }
-            
-            let new_clause;
-            // If it is a resolution, then perform the resolution step again because the parents may have changed
-            if step.rule == "resolution" || step.rule == "th_resolution"{
-                new_clause = binary_resolution_from_old(pool, new_premises[0].1, new_premises[1].1, new_commands.to_vec(), step);
+            for &(_, premise) in &step.premises {
+                let idx = add_node(
+                    actual[premise],
+                    old_proof,
+                    actual,
+                    new_commands,
+                    pool,
+                    added,
+                    clauses,
+                );
+                new_premises.push((0, idx));
             }
-            // If it is not a resolution, then do not replace it, insert the exact same node
-            else{
-                new_clause = Vec::from(old_proof.commands[curr].clause());
+
+            let new_clause = if step.rule == "resolution" || step.rule == "th_resolution" {
+                // If it is a resolution, then perform the resolution step again because the
+                // parents may have changed
+                let (left, right) = (new_premises[0].1, new_premises[1].1);
+                binary_resolution_from_old(pool, left, right, new_commands, step)
+            } else {
+                // If it is not a resolution, then do not replace it, insert the exact same node
+                step.clause.clone()
+            };
+
+            // If the new proof already concludes this exact clause, reuse it instead of adding a duplicate.
+            // The clauses must be identical, with the same literals in the same order and with the same
+            // number of occurrences, since the steps that use this one as a premise may depend on that
+            if let Some(&idx) = clauses.get(&new_clause) {
+                added[curr] = Some(idx);
+                return idx;
             }
-            
-            // Put the new term in the new_commands vec with the right format
-            let new_id = (new_commands.len() + 1).to_string();
-            let command = ProofCommand::Step(ProofStep{ id       : String::from("t") + &new_id,
-                                                            clause   : new_clause,
-                                                            rule     : step.rule.clone(),
-                                                            premises : new_premises,
-                                                            args     : vec![],
-                                                            discharge: vec![]});
-            new_commands.push(command);
 
+            // Put the new term in the new_commands vec with the right format
+            new_commands.push(ProofCommand::Step(ProofStep {
+                id: format!("t{}", new_commands.len() + 1),
+                clause: new_clause,
+                rule: step.rule.clone(),
+                premises: new_premises,
+                args: Vec::new(),
+                discharge: Vec::new(),
+            }));
         }
         // If it is an Assume, just replace the id
-        ProofCommand::Assume {id: _, term} => {
-            let new_id = (new_commands.len() + 1).to_string();
-            let command = ProofCommand::Assume{id : String::from("h") + &new_id, term : Rc::clone(term)};
-            new_commands.push(command);
+        ProofCommand::Assume { term, .. } => {
+            new_commands.push(ProofCommand::Assume {
+                id: format!("h{}", new_commands.len() + 1),
+                term: term.clone(),
+            });
         }
-        _ => {}
+        ProofCommand::Subproof(_) => {}
     }
 
-    // Return the new position on the commands vec (and the new_commands vec itself to abide to the ownership rules)
+    // Return the new position on the commands vec
     let idx = new_commands.len() - 1;
+    clauses
+        .entry(new_commands[idx].clause().to_vec())
+        .or_insert(idx);
     added[curr] = Some(idx);
-    return (idx, new_commands);
+    idx
 }
 
-
 // Perform a resolution step assuming that the right parent is a unit node
 fn binary_resolution_with_unit(
-    pool : &mut TermPool,
-    left_parent : usize,
-    right_parent : usize,
-    new_commands : Vec<ProofCommand>,
-) -> Vec<Rc<Term>>{
-    // Stores all the variables of the left clause with removed negations
-    let mut current_vec = Vec::new();
-    let mut current = AHashSet::new();
-    match &new_commands[left_parent] {
-        ProofCommand::Step(step_l) => {
-            for i in 0..step_l.clause.len(){
-                current.insert(step_l.clause[i].remove_all_negations());
-                current_vec.push(step_l.clause[i].remove_all_negations());
-            }
-        }
-        ProofCommand::Assume {id: _, term: term_l} => {
-            current.insert(term_l.remove_all_negations());
-            current_vec.push(term_l.remove_all_negations());
-        }
-        _ => {}
-    }
+    pool: &mut TermPool,
+    left_parent: usize,
+    right_parent: usize,
+    new_commands: &[ProofCommand],
+) -> Vec<Rc<Term>> {
+    // Stores all the terms of the left clause with removed negations
+    let current_vec = literals(&new_commands[left_parent]);
+    let mut current: AHashSet<_> = current_vec.iter().copied().collect();
 
     // Get the pivot from the right parent and perform the resolution step
-    match &new_commands[right_parent] {
-        ProofCommand::Step(step_r) => {
-            let mut pivot = step_r.clause[0].remove_all_negations();
-            pivot.0 = 0;
-            let mut is_pivot_in_current = true;
-            for i in 0..current_vec.len(){
-                if pivot.1 == current_vec[i].1 && current_vec[i].0 > 0{
-                    is_pivot_in_current = false;
-                }
-            }
-            binary_resolution(pool, &mut current, &step_r.clause, pivot, is_pivot_in_current);
-            let mut new_clause = Vec::new();
-            for i in current{
-                new_clause.push(unremove_all_negations(pool, i));
-            }
-            return new_clause;
-        }
-        ProofCommand::Assume {id: _, term: term_r} => {
-            let new_clause = [Rc::clone(term_r)];
-            let mut pivot = term_r.remove_all_negations();
-            pivot.0 = 0;
-            let mut is_pivot_in_current = true;
-            for i in 0..current_vec.len(){
-                if pivot.1 == current_vec[i].1 && current_vec[i].0 > 0{
-                    is_pivot_in_current = false;
-                }
-            }
-            binary_resolution(pool, &mut current, &new_clause[..], pivot, is_pivot_in_current);
-            let mut new_clause = Vec::new();
-            for i in current{
-                new_clause.push(unremove_all_negations(pool, i));
-            }
-            return new_clause;
-        }
-        _ => {}
-    }
-
-    panic!("Could not match the unit node");
+    let right_clause = match &new_commands[right_parent] {
+        ProofCommand::Step(step_r) => step_r.clause.as_slice(),
+        ProofCommand::Assume { term: term_r, .. } => core::slice::from_ref(term_r),
+        ProofCommand::Subproof(_) => panic!("Could not match the unit node"),
+    };
+    let mut pivot = right_clause[0].remove_all_negations();
+    pivot.0 = 0;
+    let is_pivot_in_current = !current_vec.iter().any(|&(n, t)| t == pivot.1 && n > 0);
+    let _ = binary_resolution(pool, &mut current, right_clause, pivot, is_pivot_in_current);
+    current
+        .into_iter()
+        .map(|t| unremove_all_negations(pool, t))
+        .collect()
 }
 
 // Compress the proof using the Lower Units algorithm
-pub fn compress_proof(proof: &Proof, pool : &mut TermPool){
-    let unit_nodes = collect_units(&proof);
+pub fn compress_proof(proof: &Proof, pool: &mut TermPool) -> Vec<ProofCommand> {
+    let unit_nodes = collect_units(proof);
 
     // If there are no unit nodes, the algorithm cannot do anything
-    if unit_nodes.len() == 0{
-        print_proof(&proof.commands, false);
-        return;
+    if unit_nodes.is_empty() {
+        return proof.commands.clone();
     }
-    
-    let mut dnm = Vec::new(); // dnm[i] is true if node i was deleted and false otherwise
-    dnm.resize(proof.commands.len(), false);
-    for i in &unit_nodes{
-        dnm[*i] = true;
+
+    // dnm[i] is true if node i was deleted and false otherwise
+    let mut dnm = vec![false; proof.commands.len()];
+    for &i in &unit_nodes {
+        dnm[i] = true;
     }
 
     // actual[i] is going to be the node that has replaced node i (it is i if it was not replaced)
-    let mut actual = Vec::new();
-    for i in 0..dnm.len(){
-        actual.push(i as usize);
-    }
+    let mut actual: Vec<usize> = (0..dnm.len()).collect();
 
     // fix_proof is the function that sets actual to the true values
     let curr = proof.commands.len() - 1;
-    fix_proof(curr, proof, &unit_nodes, &dnm, &mut actual);
+    fix_proof(curr, proof, &dnm, &mut actual);
 
     let mut new_proof_commands = Vec::new(); // the proof commands of the compressed proofs
-    let mut added: Vec<Option<usize>> = vec![None; proof.commands.len()]; // vec with all the nodes that have already been added
-    
+    let mut added = vec![None; proof.commands.len()]; // vec with all the nodes that have already been added
+    let mut clauses = AHashMap::new(); // index of each clause in the new proof, used to avoid adding duplicates
+
     // Add the last node of the original proof and all of its ancestors
-    let (_, mut new_proof_commands) = add_node(proof.commands.len() - 1, proof, &actual, &mut new_proof_commands, pool, &mut added);
+    let mut last_node = add_node(
+        curr,
+        proof,
+        &actual,
+        &mut new_proof_commands,
+        pool,
+        &mut added,
+        &mut clauses,
+    );
 
     // Add each unit node (and its ancestors) and then perform binary resolution between them and the current last node of the proof
-    for i in unit_nodes{
-        let previous_last_node = new_proof_commands.len() - 1;
-        let (_, new_proof_commands) = add_node(i, proof, &actual, &mut new_proof_commands, pool, &mut added);
+    for i in unit_nodes {
+        let previous_last_node = last_node;
+        let current_last_node = add_node(
+            i,
+            proof,
+            &actual,
+            &mut new_proof_commands,
+            pool,
+            &mut added,
+            &mut clauses,
+        );
 
         // Perform the binary resolution step
-        let current_last_node = new_proof_commands.len() - 1;
-        let new_premises = [(0 as usize, previous_last_node), (0 as usize, current_last_node)];
-        let new_clause = binary_resolution_with_unit(pool, previous_last_node, current_last_node, new_proof_commands.to_vec());
+        let new_clause = binary_resolution_with_unit(
+            pool,
+            previous_last_node,
+            current_last_node,
+            &new_proof_commands,
+        );
 
         // Add the new clause to the proof
-        let new_id = (new_proof_commands.len() + 1).to_string();
-        let command = ProofCommand::Step(ProofStep{ id       : String::from("t") + &new_id,
-                                                        clause   : new_clause,
-                                                        rule     : String::from("resolution"),
-                                                        premises : new_premises.to_vec(),
-                                                        args     : vec![],
-                                                        discharge: vec![]});
-        new_proof_commands.push(command);
+        new_proof_commands.push(ProofCommand::Step(ProofStep {
+            id: format!("t{}", new_proof_commands.len() + 1),
+            clause: new_clause,
+            rule: "resolution".to_owned(),
+            premises: vec![(0, previous_last_node), (0, current_last_node)],
+            args: Vec::new(),
+            discharge: Vec::new(),
+        }));
+        last_node = new_proof_commands.len() - 1;
+        clauses
+            .entry(new_proof_commands[last_node].clause().to_vec())
+            .or_insert(last_node);
     }
 
-    print_proof(new_proof_commands, false);
-
-}
\ No newline at end of file
+    new_proof_commands
+}
@@ -52,23 +52,15 @@ impl ContextStack {
         // `(:= x (f y))`, we insert the first substitution, and then, when introducing the second,
         // we use the current state of the hash map to transform `(f y)` into `(f z)`. The
         // resulting hash map will then contain `(:= y z)` and `(:= x (f z))`
-        for (var, value) in assignment_args.iter() {
-            let sort = Term::Sort(pool.sort(value).clone());
-            let var_term = Term::var(var, pool.add(sort));
+        for (var, value) in assignment_args {
+            let var_term = Term::var(var, pool.sort(value).clone());
             let var_term = pool.add(var_term);
             substitution.insert(pool, var_term.clone(), value.clone())?;
             let new_value = substitution_until_fixed_point.apply(pool, value);
             substitution_until_fixed_point.insert(pool, var_term, new_value)?;
         }
 
-        let mappings = assignment_args
-            .iter()
-            .map(|(var, value)| {
-                let sort = Term::Sort(pool.sort(value).clone());
-                let var_term = (var.clone(), pool.add(sort)).into();
-                (pool.add(var_term), value.clone())
-            })
-            .collect();
+        let mappings = anchor_mappings(pool, assignment_args);
         let bindings = variable_args.iter().cloned().collect();
         self.stack.push(Context {
             mappings,
@@ -86,25 +78,21 @@ impl ContextStack {
 
     fn catch_up_cumulative(&mut self, pool: &mut TermPool, up_to: usize) {
         for i in self.num_cumulative_calculated..std::cmp::max(up_to + 1, self.len()) {
-            let simultaneous = build_simultaneous_substitution(pool, &self.stack[i].mappings).map;
-            let mut cumulative_substitution = simultaneous.clone();
-
-            if i > 0 {
-                if let Some(previous_context) = self.stack.get(i - 1) {
-                    let previous_substitution =
-                        previous_context.cumulative_substitution.as_ref().unwrap();
-
-                    for (k, v) in previous_substitution.map.iter() {
-                        let value = match simultaneous.get(v) {
-                            Some(new_value) => new_value,
-                            None => v,
-                        };
-                        cumulative_substitution.insert(k.clone(), value.clone());
-                    }
+            // We can unwrap here safely because, by construction, the sort of each variable is the
+            // same as the sort of its value
+            let simultaneous =
+                Substitution::from_sequential(pool, &self.stack[i].mappings).unwrap();
+            let cumulative_substitution = match i.checked_sub(1) {
+                Some(previous) => {
+                    let previous = self.stack[previous]
+                        .cumulative_substitution
+                        .as_ref()
+                        .unwrap();
+                    previous.compose_nested(pool, &simultaneous).unwrap()
                 }
-            }
-            self.stack[i].cumulative_substitution =
-                Some(Substitution::new(pool, cumulative_substitution).unwrap());
+                None => simultaneous,
+            };
+            self.stack[i].cumulative_substitution = Some(cumulative_substitution);
             self.num_cumulative_calculated = i + 1;
         }
     }
@@ -133,25 +121,3 @@ impl ContextStack {
         }
     }
 }
-
-fn build_simultaneous_substitution(
-    pool: &mut TermPool,
-    mappings: &[(Rc<Term>, Rc<Term>)],
-) -> Substitution {
-    let mut result = Substitution::empty();
-
-    // We build the simultaneous substitution from the bottom up, by using the mappings already
-    // introduced to transform the result of a new mapping before inserting it into the
-    // substitution. So for instance, if the mappings are `y -> z` and `x -> (f y)`, we insert the
-    // first mapping, and then, when introducing the second, we use the current state of the
-    // substitution to transform `(f y)` into `(f z)`. The result will then contain `y -> z` and
-    // `x -> (f z)`.
-    for (var, value) in mappings {
-        let new_value = result.apply(pool, value);
-
-        // We can unwrap here safely because, by construction, the sort of `var` is the
-        // same as the sort of `new_value`
-        result.insert(pool, var.clone(), new_value).unwrap();
-    }
-    result
-}
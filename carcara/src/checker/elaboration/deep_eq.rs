@@ -68,7 +68,10 @@ impl<'a> DeepEqElaborator<'a> {
                 self.build_cong(pool, (&a, &b), (a_args, b_args))
             }
 
-            (Term::Quant(a_q, a_bindings, a_inner), Term::Quant(b_q, b_bindings, b_inner)) => {
+            (
+                Term::Quant(a_q, a_bindings, a_inner, _),
+                Term::Quant(b_q, b_bindings, b_inner, _),
+            ) => {
                 assert_eq!(a_q, b_q);
 
                 let (variable_args, assignment_args) = match &mut self.context {
@@ -133,10 +136,7 @@ impl<'a> DeepEqElaborator<'a> {
 
                 let variable_args: Vec<_> = a_bindings
                     .iter()
-                    .map(|(name, value)| {
-                        let sort = Term::Sort(pool.sort(value).clone());
-                        (name.clone(), pool.add(sort))
-                    })
+                    .map(|(name, value)| (name.clone(), pool.sort(value).clone()))
                     .collect();
 
                 self.open_subproof();
@@ -1,14 +1,19 @@
 use crate::{
     ast::*,
     checker::rules::linear_arithmetic::LinearComb,
+    num::Rational,
     utils::{Range, TypeName},
 };
-use rug::Rational;
 use std::{fmt, io};
 use thiserror::Error;
 
+/// The errors that may happen when checking a proof. Errors that are specific to a single rule or
+/// family of rules are grouped in their own enums, which are wrapped by this type, while general
+/// errors, like a clause with the wrong number of terms, are variants of this type directly.
 #[derive(Debug, Error)]
 pub enum CheckerError {
+    /// An error without any further information. This is never returned by the rules built into
+    /// Carcara, but may be used by custom rules.
     #[error("unspecified error")]
     Unspecified,
 
@@ -37,41 +42,25 @@ pub enum CheckerError {
     #[error(transparent)]
     Subproof(#[from] SubproofError),
 
-    #[error("reflexivity failed with terms '{0}' and '{1}'")]
-    ReflexivityFailed(Rc<Term>, Rc<Term>),
-
-    #[error("simplifying '{original}' resulted in '{result}', expected result to be '{target}'")]
-    SimplificationFailed {
-        original: Rc<Term>,
-        result: Rc<Term>,
-        target: Rc<Term>,
-    },
-
-    #[error("encountered cycle when simplifying term: '{0}'")]
-    CycleInSimplification(Rc<Term>),
-
-    #[error("'{0}' is not a valid simplification result for this rule")]
-    SumProdSimplifyInvalidConclusion(Rc<Term>),
-
-    #[error("term '{0}' is not a connective")]
-    TermIsNotConnective(Rc<Term>),
-
-    #[error("term '{0}' does not have the correct form for `ite_intro`")]
-    IsNotValidIteIntro(Rc<Term>),
+    #[error(transparent)]
+    Reflexivity(#[from] ReflexivityError),
 
-    #[error("broken transitivity chain: can't prove '(= {0} {1})'")]
-    BrokenTransitivityChain(Rc<Term>, Rc<Term>),
+    #[error(transparent)]
+    Transitivity(#[from] TransitivityError),
 
-    #[error("term '{0}' is missing in conclusion clause")]
-    ReorderingMissingTerm(Rc<Term>),
+    #[error(transparent)]
+    Simplification(#[from] SimplificationError),
 
-    #[error("term '{0}' was not expected in conclusion clause")]
-    ReorderingExtraTerm(Rc<Term>),
+    #[error(transparent)]
+    Tautology(#[from] TautologyError),
 
-    #[error("term '{0}' is not a valid n-ary operation")]
-    NotValidNaryTerm(Rc<Term>),
+    #[error(transparent)]
+    Reordering(#[from] ReorderingError),
 
     // General errors
+    #[error("premise at depth {0} and index {1} is not visible from this step")]
+    PremiseNotVisible(usize, usize),
+
     #[error("expected {0} premises, got {1}")]
     WrongNumberOfPremises(Range, usize),
 
@@ -123,6 +112,9 @@ pub enum CheckerError {
     #[error("this rule can only be used in the last step of a subproof")]
     MustBeLastStepInSubproof,
 
+    #[error("choice term '{1}' is alpha-equivalent to, but not the same as, choice term '{0}'")]
+    InconsistentChoiceTerms(Rc<Term>, Rc<Term>),
+
     // Equality errors
     #[error(transparent)]
     TermEquality(#[from] EqualityError<Rc<Term>>),
@@ -133,6 +125,9 @@ pub enum CheckerError {
     #[error(transparent)]
     BindingListEquality(#[from] EqualityError<BindingList>),
 
+    #[error(transparent)]
+    ValueBindingListEquality(#[from] EqualityError<ValueBindingList>),
+
     #[error("unknown rule")]
     UnknownRule,
 }
@@ -163,6 +158,9 @@ pub enum ResolutionError {
 
     #[error("pivot was not found in clause: '{0}'")]
     PivotNotFound(Rc<Term>),
+
+    #[error("term in premise is missing in weakened conclusion, or appears fewer times: '{0}'")]
+    WeakeningMissingTerm(Rc<Term>),
 }
 
 #[derive(Debug, Error)]
@@ -182,6 +180,9 @@ pub enum CongruenceError {
         premise: (Rc<Term>, Rc<Term>),
     },
 
+    #[error("no path between '{0}' and '{1}' given the premises")]
+    NoPath(Rc<Term>, Rc<Term>),
+
     #[error("functions don't match: '{0}' and '{1}'")]
     DifferentFunctions(Rc<Term>, Rc<Term>),
 
@@ -221,6 +222,69 @@ pub enum QuantifierError {
     ClauseDoesntAppearInCnf(Rc<Term>),
 }
 
+/// Errors relevant to the `refl` and `strict_refl` rules.
+#[derive(Debug, Error)]
+pub enum ReflexivityError {
+    #[error("terms '{0}' and '{1}' are not equal")]
+    NotEqual(Rc<Term>, Rc<Term>),
+
+    #[error("terms '{0}' and '{1}' are not equal, even after applying the context substitutions")]
+    NotEqualUnderContext(Rc<Term>, Rc<Term>),
+}
+
+/// Errors relevant to the `trans` rule.
+#[derive(Debug, Error)]
+pub enum TransitivityError {
+    #[error("broken transitivity chain: can't prove '(= {0} {1})'")]
+    BrokenChain(Rc<Term>, Rc<Term>),
+
+    #[error("no path between '{0}' and '{1}' given the premises")]
+    NoPath(Rc<Term>, Rc<Term>),
+}
+
+/// Errors relevant to the simplification rules.
+#[derive(Debug, Error)]
+pub enum SimplificationError {
+    #[error("simplifying '{original}' resulted in '{result}', expected result to be '{target}'")]
+    Failed {
+        original: Rc<Term>,
+        result: Rc<Term>,
+        target: Rc<Term>,
+    },
+
+    #[error("encountered cycle when simplifying term: '{0}'")]
+    Cycle(Rc<Term>),
+
+    #[error("'{0}' is not a valid simplification result for this rule")]
+    SumProdInvalidConclusion(Rc<Term>),
+
+    #[error("division by zero in term '{0}'")]
+    DivisionByZero(Rc<Term>),
+}
+
+/// Errors relevant to the tautology and clausification rules.
+#[derive(Debug, Error)]
+pub enum TautologyError {
+    #[error("term '{0}' is not a connective")]
+    TermIsNotConnective(Rc<Term>),
+
+    #[error("term '{0}' does not have the correct form for `ite_intro`")]
+    IsNotValidIteIntro(Rc<Term>),
+
+    #[error("term '{0}' is not a valid n-ary operation")]
+    NotValidNaryTerm(Rc<Term>),
+}
+
+/// Errors relevant to the `reordering` rule.
+#[derive(Debug, Error)]
+pub enum ReorderingError {
+    #[error("term '{0}' is missing in conclusion clause")]
+    MissingTerm(Rc<Term>),
+
+    #[error("term '{0}' was not expected in conclusion clause")]
+    ExtraTerm(Rc<Term>),
+}
+
 /// Errors relevant to the linear arithmetic rules.
 #[derive(Debug, Error)]
 pub enum LinearArithmeticError {
@@ -233,11 +297,23 @@ pub enum LinearArithmeticError {
     #[error("too many arguments in disequality '{0}'")]
     TooManyArgsInDisequality(Rc<Term>),
 
-    #[error("final disequality is not contradictory: '{}'", DisplayLinearComb(.0, .1))]
-    DisequalityIsNotContradiction(Operator, LinearComb),
+    #[error(
+        "final disequality is not contradictory: '{}'",
+        DisplayLinearComb(.final_op, .disequality)
+    )]
+    DisequalityNotContradictory {
+        final_op: Operator,
+        disequality: LinearComb,
+    },
 
-    #[error("final disequality is not tautological: '{}'", DisplayLinearComb(.0, .1))]
-    DisequalityIsNotTautology(Operator, LinearComb),
+    #[error(
+        "final disequality is not tautological: '{}'",
+        DisplayLinearComb(.final_op, .disequality)
+    )]
+    DisequalityNotTautological {
+        final_op: Operator,
+        disequality: LinearComb,
+    },
 
     #[error("expected term '{0}' to be less than term '{1}'")]
     ExpectedLessThan(Rc<Term>, Rc<Term>),
@@ -311,7 +387,7 @@ pub enum SubproofError {
 }
 
 /// A wrapper struct that implements `fmt::Display` for linear combinations.
-struct DisplayLinearComb<'a>(&'a Operator, &'a LinearComb);
+pub(crate) struct DisplayLinearComb<'a>(pub(crate) &'a Operator, pub(crate) &'a LinearComb);
 
 impl<'a> fmt::Display for DisplayLinearComb<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -330,7 +406,7 @@ impl<'a> fmt::Display for DisplayLinearComb<'a> {
             1 => write_var(f, vars.iter().next().unwrap()),
             _ => {
                 write!(f, "(+")?;
-                for var in vars.iter() {
+                for var in vars {
                     write!(f, " ")?;
                     write_var(f, var)?;
                 }
@@ -0,0 +1,101 @@
+//! Lemmas proved by standalone proofs. Usually, the `assume` commands of a proof must match the
+//! assertions of the problem, and the proof must conclude the empty clause. When a proof is checked
+//! as a lemma, its `assume` commands are instead taken as hypotheses, and the proof may conclude
+//! any clause. If such a proof is valid, the hypotheses imply the conclusion, independently of any
+//! problem, which allows verified lemmas to be stored in their own files and reused.
+
+use crate::ast::{Operator, Rc, Term, TermPool};
+use std::fmt;
+
+/// A lemma proved by a proof, stating that its hypotheses imply its conclusion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lemma {
+    /// The terms of the top-level `assume` commands in the proof, in the order they appear.
+    pub hypotheses: Vec<Rc<Term>>,
+
+    /// The clause concluded by the last top-level command of the proof.
+    pub conclusion: Vec<Rc<Term>>,
+}
+
+impl Lemma {
+    /// Returns the lemma as a single term, of the form `(=> (and h_1 ... h_n) (or c_1 ... c_m))`.
+    /// Conjunctions and disjunctions with only one argument are replaced by that argument, an
+    /// empty conclusion is replaced by `false`, and if there are no hypotheses, only the conclusion
+    /// is returned.
+    pub fn to_implication(&self, pool: &mut TermPool) -> Rc<Term> {
+        let conclusion = match self.conclusion.as_slice() {
+            [] => pool.bool_false(),
+            [t] => t.clone(),
+            c => pool.add(Term::Op(Operator::Or, c.to_vec())),
+        };
+        let hypotheses = match self.hypotheses.as_slice() {
+            [] => return conclusion,
+            [t] => t.clone(),
+            h => pool.add(Term::Op(Operator::And, h.to_vec())),
+        };
+        pool.add(Term::Op(Operator::Implies, vec![hypotheses, conclusion]))
+    }
+}
+
+impl fmt::Display for Lemma {
+    /// Prints the lemma as `h_1, ..., h_n ⊢ (cl c_1 ... c_m)`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some((first, rest)) = self.hypotheses.split_first() {
+            write!(f, "{}", first)?;
+            for h in rest {
+                write!(f, ", {}", h)?;
+            }
+            write!(f, " ")?;
+        }
+        write!(f, "⊢ (cl")?;
+        for c in &self.conclusion {
+            write!(f, " {}", c)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check, check_lemma, CarcaraOptions, Error};
+
+    #[test]
+    fn test_check_lemma() {
+        let problem = "(declare-fun p () Bool) (declare-fun q () Bool)";
+        let proof = "
+            (assume h1 (=> p q))
+            (assume h2 p)
+            (step t3 (cl (not p) q) :rule implies :premises (h1))
+            (step t4 (cl q) :rule resolution :premises (t3 h2))
+        ";
+        let run = |proof: &str| {
+            check_lemma(
+                problem.as_bytes(),
+                proof.as_bytes(),
+                CarcaraOptions::default(),
+            )
+        };
+
+        let (is_holey, lemma) = run(proof).unwrap();
+        assert!(!is_holey);
+        assert_eq!(lemma.to_string(), "(=> p q), p ⊢ (cl q)");
+
+        let implication = lemma.to_implication(&mut TermPool::new());
+        assert_eq!(implication.to_string(), "(=> (and (=> p q) p) q)");
+
+        // Outside of lemma mode, the `assume` commands must match the problem assertions
+        let result = check(
+            problem.as_bytes(),
+            proof.as_bytes(),
+            CarcaraOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::Checker { .. })));
+
+        // The steps are still checked
+        let invalid = "(assume h1 p) (step t2 (cl q) :rule resolution :premises (h1))";
+        assert!(matches!(run(invalid), Err(Error::Checker { .. })));
+
+        assert!(matches!(run(""), Err(Error::EmptyLemma)));
+    }
+}
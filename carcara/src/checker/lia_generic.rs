@@ -106,11 +106,21 @@ fn parse_and_check_cvc5_proof(
     problem: &[u8],
     proof: &[u8],
 ) -> CarcaraResult<Vec<ProofCommand>> {
-    let mut parser = parser::Parser::new(pool, problem, true, false, true)?;
-    let (prelude, premises) = parser.parse_problem()?;
-    parser.reset(proof)?;
-    let commands = parser.parse_proof()?;
-    let proof = Proof { premises, commands };
+    let config = parser::ParserConfig {
+        allow_int_real_subtyping: true,
+        ..Default::default()
+    };
+    let parser = parser::Parser::new(pool, config, problem)?;
+    let (prelude, premises, mut parser) = parser.parse_problem()?;
+    let commands = parser.parse_proof(proof)?;
+    let proof = Proof {
+        premises,
+        commands,
+        spans: Vec::new(),
+        metadata: Vec::new(),
+        named_assertions: Vec::new(),
+        comments: Vec::new(),
+    };
 
     let config = Config {
         strict: false,
@@ -118,6 +128,15 @@ fn parse_and_check_cvc5_proof(
         is_running_test: false,
         statistics: None,
         check_lia_using_cvc5: false,
+        audit_choice_terms: false,
+        commutative_transitivity: false,
+        sampling: None,
+        severities: Default::default(),
+        trace: None,
+        stream_results: None,
+        keep_going: None,
+        slow_step_threshold: None,
+        trusted_rules: AHashSet::new(),
     };
     ProofChecker::new(pool, config, prelude).check(&proof)?;
     Ok(proof.commands)
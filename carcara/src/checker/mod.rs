@@ -1,20 +1,65 @@
+mod aliases;
+mod choice_audit;
+pub mod clause_db;
 mod context;
 mod elaboration;
 pub mod error;
 pub mod compression;
+mod lemma;
 mod lia_generic;
+pub mod random_testing;
 mod rules;
-
-use crate::{ast::*, benchmarking::CollectResults, CarcaraResult, Error};
-use ahash::AHashSet;
+pub mod rule_info;
+mod sampling;
+mod severity;
+mod stream;
+pub mod testing;
+mod trace;
+
+use crate::{ast::*, benchmarking::{phases, CollectResults, StepSize}, CarcaraResult, Error};
+use ahash::{AHashMap, AHashSet};
+use choice_audit::audit_choice_terms;
 use context::*;
 use elaboration::Elaborator;
 use error::CheckerError;
-use rules::{ElaborationRule, Premise, Rule, RuleArgs, RuleResult};
+use rules::ElaborationRule;
+use sampling::StepSampler;
+
+pub use aliases::{current_rule_name, RULE_ALIASES};
+pub use lemma::Lemma;
+pub use rules::{Premise, Rule, RuleArgs, RuleResult};
+pub use sampling::SamplingConfig;
+pub use severity::{ErrorCategory, Severity, SeverityLevels, WarningCounts};
 use std::{
+    cell::RefCell,
     fmt,
     time::{Duration, Instant},
 };
+pub use stream::{write_step_result, ResultStream};
+pub use trace::{write_step_trace, StepTrace, TraceEntry, TraceOutput};
+
+thread_local! {
+    // The id of the step currently being checked in this thread, or an empty string if no step is
+    // being checked. The string is reused between steps to avoid an allocation for every step
+    static CURRENT_STEP: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Returns the id of the step currently being checked in this thread, or `None` if no step is
+/// being checked. Loggers can use this to filter or annotate the messages emitted by the checker.
+pub fn current_step() -> Option<String> {
+    CURRENT_STEP.with(|s| {
+        let s = s.borrow();
+        (!s.is_empty()).then(|| s.clone())
+    })
+}
+
+fn set_current_step(id: &str) {
+    CURRENT_STEP.with(|s| {
+        let mut s = s.borrow_mut();
+        s.clear();
+        s.push_str(id);
+    });
+}
 
 pub struct CheckerStatistics<'s> {
     pub file_name: &'s str,
@@ -49,6 +94,48 @@ pub struct Config<'c> {
     pub is_running_test: bool,
     pub statistics: Option<CheckerStatistics<'c>>,
     pub check_lia_using_cvc5: bool,
+
+    /// If `true`, the proof is rejected if it contains two distinct `choice` terms that are
+    /// alpha-equivalent. See the `choice_audit` module for details.
+    pub audit_choice_terms: bool,
+
+    /// If `true`, the premises of `trans` steps, and the inequalities in `eq_transitive` steps, are
+    /// searched for a chain connecting the terms in the conclusion. Otherwise, the chain is built by
+    /// always following the first premise that continues it, which fails if that premise leads to
+    /// a dead end. When elaborating, the chain found is emitted in order.
+    pub commutative_transitivity: bool,
+
+    /// If this is not `None`, only a sample of the steps of each rule is checked, and the other
+    /// steps are treated as holes. See the `sampling` module for details.
+    pub sampling: Option<SamplingConfig>,
+
+    /// The severity of each category of errors. Errors in categories with the `Warning` severity
+    /// are logged and counted instead of making the proof invalid. See the `severity` module.
+    pub severities: SeverityLevels,
+
+    /// If this is not `None`, the computation done to check each step is written to this output.
+    /// See the `trace` module for details.
+    pub trace: Option<TraceOutput<'c>>,
+
+    /// If this is not `None`, the result of each step is written to this output as soon as it is
+    /// checked. See the `stream` module for details.
+    pub stream_results: Option<ResultStream<'c>>,
+
+    /// If this is not `None`, the checker doesn't stop at the first invalid step. Instead, it keeps
+    /// checking the remaining commands, and all errors found are returned together at the end, in
+    /// an `Error::CheckerErrors`. Checking stops once the given maximum number of errors is found.
+    /// This has no effect when elaborating.
+    pub keep_going: Option<usize>,
+
+    /// If this is not `None`, a warning is logged for each step that takes longer than this to
+    /// check, with its rule and the size of its clause. The warning is logged as soon as the step
+    /// is checked, so slow steps are reported while the rest of the proof is still being checked.
+    pub slow_step_threshold: Option<Duration>,
+
+    /// The names of rules whose steps are accepted without being checked. Like holes, these steps
+    /// make the proof holey. Rules are matched by the name used in the step, so trusting the old
+    /// name of a renamed rule doesn't trust steps that use the current name, and vice versa.
+    pub trusted_rules: AHashSet<String>,
 }
 
 pub struct ProofChecker<'c> {
@@ -59,10 +146,30 @@ pub struct ProofChecker<'c> {
     elaborator: Option<Elaborator>,
     reached_empty_clause: bool,
     is_holey: bool,
+    custom_rules: AHashMap<String, Rule>,
+    sampler: Option<StepSampler>,
+    num_steps: usize,
+
+    /// When checking a proof as a lemma, the terms of the top-level `assume` commands seen so far.
+    lemma_hypotheses: Option<Vec<Rc<Term>>>,
+
+    /// The number of steps that used each old rule name. See the `aliases` module.
+    renamed_rules: AHashMap<String, usize>,
+
+    /// The number of errors of each category that were reported as warnings.
+    warnings: WarningCounts,
+
+    /// The errors found so far, when the checker keeps going after invalid steps.
+    errors: Vec<Error>,
+
+    /// The trace entries recorded while checking the current step.
+    step_trace: StepTrace,
 }
 
 impl<'c> ProofChecker<'c> {
     pub fn new(pool: &'c mut TermPool, config: Config<'c>, prelude: ProblemPrelude) -> Self {
+        let sampler = config.sampling.map(StepSampler::new);
+        let step_trace = StepTrace::new(config.trace.is_some());
         ProofChecker {
             pool,
             config,
@@ -71,14 +178,40 @@ impl<'c> ProofChecker<'c> {
             elaborator: None,
             reached_empty_clause: false,
             is_holey: false,
+            custom_rules: AHashMap::new(),
+            sampler,
+            num_steps: 0,
+            lemma_hypotheses: None,
+            renamed_rules: AHashMap::new(),
+            warnings: WarningCounts::default(),
+            errors: Vec::new(),
+            step_trace,
         }
     }
 
+    /// Adds a rule that is not built into the checker, which will be used to check steps that use
+    /// the rule name `name`. Rules added this way take precedence over built-in rules with the same
+    /// name, and are never elaborated.
+    pub fn add_rule(&mut self, name: &str, rule: Rule) {
+        self.custom_rules.insert(name.to_owned(), rule);
+    }
+
     pub fn check(&mut self, proof: &Proof) -> CarcaraResult<bool> {
+        if self.config.audit_choice_terms {
+            if let Err(e) = audit_choice_terms(self.pool, proof) {
+                if !self.report_as_warning(ErrorCategory::InconsistentChoiceTerms) {
+                    return Err(e);
+                }
+                log::warn!("{}", e);
+            }
+        }
+
         // Similarly to the parser, to avoid stack overflows in proofs with many nested subproofs,
         // we check the subproofs iteratively, instead of recursively
         let mut iter = proof.iter();
+        let mut spans = proof.spans.iter();
         while let Some(command) = iter.next() {
+            let span = spans.next().copied();
             match command {
                 ProofCommand::Step(step) => {
                     let is_end_of_subproof = iter.is_end_step();
@@ -94,12 +227,49 @@ impl<'c> ProofChecker<'c> {
                     } else {
                         None
                     };
-                    self.check_step(step, previous_command, &iter)
-                        .map_err(|e| Error::Checker {
-                            inner: e,
+                    set_current_step(&step.id);
+                    let time = Instant::now();
+                    let result = self.check_step(step, previous_command, &iter);
+                    set_current_step("");
+                    let time = time.elapsed();
+                    if let Some(stream) = &mut self.config.stream_results {
+                        let (index, error) = (self.num_steps - 1, result.as_ref().err());
+                        write_step_result(stream.0, index, &step.id, &step.rule, error, time)?;
+                    }
+                    match self.config.slow_step_threshold {
+                        Some(threshold) if time > threshold => log::warn!(
+                            "step '{}' with rule '{}' took {:?} to check, over the threshold of \
+                            {:?} (clause with {} literals, {} premises)",
+                            step.id,
+                            step.rule,
+                            time,
+                            threshold,
+                            step.clause.len(),
+                            step.premises.len(),
+                        ),
+                        _ => (),
+                    }
+                    if let Err(e) = result {
+                        self.record_error(Error::Checker {
+                            inner: Box::new(e),
                             rule: step.rule.clone(),
                             step: step.id.clone(),
+                            span: span.map(Box::new),
+                            premises: step
+                                .premises
+                                .iter()
+                                .filter(|&&p| iter.is_premise_visible(p))
+                                .map(|&p| {
+                                    let command = iter.get_premise(p);
+                                    (command.id().to_owned(), command.clause().to_vec())
+                                })
+                                .collect(),
                         })?;
+                    } else if let (Some(entries), Some(trace)) =
+                        (self.step_trace.end(), &mut self.config.trace)
+                    {
+                        write_step_trace(trace.0, &step.id, &step.rule, entries)?;
+                    }
 
                     // If this is the last command of a subproof, we have to pop the subproof
                     // commands off of the stack. The parser already ensures that the last command
@@ -122,9 +292,11 @@ impl<'c> ProofChecker<'c> {
                     self.context
                         .push(self.pool, &s.assignment_args, &s.variable_args)
                         .map_err(|e| Error::Checker {
-                            inner: e.into(),
+                            inner: Box::new(e.into()),
                             rule: "anchor".into(),
                             step: step_id.to_owned(),
+                            span: span.map(Box::new),
+                            premises: Vec::new(),
                         })?;
 
                     if let Some(elaborator) = &mut self.elaborator {
@@ -140,20 +312,128 @@ impl<'c> ProofChecker<'c> {
                             stats.file_name,
                             step_id,
                             &rule_name,
+                            StepSize::default(),
                             time.elapsed(),
                         );
                     }
                 }
-                ProofCommand::Assume { id, term } => {
-                    self.check_assume(id, term, &proof.premises, &iter)?;
-                }
+                ProofCommand::Assume { id, term } => match &mut self.lemma_hypotheses {
+                    Some(hypotheses) if !iter.is_in_subproof() => {
+                        hypotheses.push(term.clone());
+                        if let Some(elaborator) = &mut self.elaborator {
+                            elaborator.assume(term);
+                        }
+                    }
+                    _ => {
+                        if let Err(e) = self.check_assume(id, term, &proof.premises, &iter, span) {
+                            self.record_error(e)?;
+                        }
+                    }
+                },
             }
         }
-        if self.config.is_running_test || self.reached_empty_clause {
+        if let Some((checked, skipped)) = self.sampled_steps() {
+            log::info!(
+                "checked a sample of {} out of {} steps",
+                checked,
+                checked + skipped
+            );
+        }
+        for (old_name, count) in &self.renamed_rules {
+            log::info!(
+                "{} steps used '{}', the old name of rule '{}'",
+                count,
+                old_name,
+                current_rule_name(old_name).unwrap()
+            );
+        }
+        let is_lemma = self.lemma_hypotheses.is_some();
+        let result = if let Some(errors) = self.take_errors() {
+            Err(errors)
+        } else if self.config.is_running_test || self.reached_empty_clause || is_lemma {
             Ok(self.is_holey)
+        } else if self.report_as_warning(ErrorCategory::NoEmptyClause) {
+            log::warn!("proof does not conclude empty clause");
+            Ok(true)
         } else {
             Err(Error::DoesNotReachEmptyClause)
+        };
+        if let Some(trace) = &mut self.config.trace {
+            trace.0.flush()?;
+        }
+        if self.warnings.total() > 0 {
+            let counts: Vec<_> = (self.warnings.iter())
+                .map(|(category, n)| format!("{} {}", n, category))
+                .collect();
+            log::warn!(
+                "errors reported as warnings: {} ({})",
+                self.warnings.total(),
+                counts.join(", ")
+            );
+        }
+        result
+    }
+
+    /// Returns the number of errors of each category that were reported as warnings so far.
+    pub fn warnings(&self) -> WarningCounts {
+        self.warnings
+    }
+
+    /// Handles an error in a proof command. If the checker keeps going after invalid steps, and the
+    /// maximum number of errors wasn't reached yet, the error is recorded and checking continues.
+    /// Otherwise, the error is returned, together with the ones recorded so far.
+    fn record_error(&mut self, error: Error) -> CarcaraResult<()> {
+        match self.config.keep_going {
+            Some(max) if self.elaborator.is_none() => {
+                self.errors.push(error);
+                if self.errors.len() < max {
+                    return Ok(());
+                }
+                Err(self.take_errors().unwrap())
+            }
+            _ => Err(error),
+        }
+    }
+
+    fn take_errors(&mut self) -> Option<Error> {
+        let mut errors = std::mem::take(&mut self.errors);
+        match errors.len() {
+            0 => None,
+            1 => errors.pop(),
+            _ => Some(Error::CheckerErrors(errors)),
+        }
+    }
+
+    /// If errors of the given category have the `Warning` severity, counts one more warning and
+    /// returns `true`. Otherwise, returns `false`, and the error should be returned.
+    fn report_as_warning(&mut self, category: ErrorCategory) -> bool {
+        let is_warning = self.config.severities.get(category) == Severity::Warning;
+        if is_warning {
+            self.warnings.increment(category);
         }
+        is_warning
+    }
+
+    /// Checks a proof as a lemma, without requiring its `assume` commands to match the problem
+    /// premises, or the proof to conclude the empty clause. Instead, the top-level `assume` commands
+    /// are taken as the hypotheses of the lemma, and the clause of the last top-level command as its
+    /// conclusion. Returns whether the proof is holey, and the lemma it proves.
+    pub fn check_lemma(&mut self, proof: &Proof) -> CarcaraResult<(bool, Lemma)> {
+        self.lemma_hypotheses = Some(Vec::new());
+        let result = self.check(proof);
+        let hypotheses = self.lemma_hypotheses.take().unwrap();
+        let is_holey = result?;
+
+        let conclusion = match proof.commands.last() {
+            Some(command) => command.clause().to_vec(),
+            None => return Err(Error::EmptyLemma),
+        };
+        Ok((is_holey, Lemma { hypotheses, conclusion }))
+    }
+
+    /// If sampling is enabled, returns the number of steps that were checked and skipped so far.
+    pub fn sampled_steps(&self) -> Option<(usize, usize)> {
+        self.sampler.as_ref().map(StepSampler::counts)
     }
 
     pub fn check_and_elaborate(&mut self, mut proof: Proof) -> CarcaraResult<Proof> {
@@ -167,6 +447,7 @@ impl<'c> ProofChecker<'c> {
 
         let elaboration_time = Instant::now();
         proof.commands = elaborator.end(proof.commands);
+        proof.spans.clear();
         if let Some(stats) = &mut self.config.statistics {
             *stats.elaboration_time += elaboration_time.elapsed();
         }
@@ -179,6 +460,7 @@ impl<'c> ProofChecker<'c> {
         term: &Rc<Term>,
         premises: &AHashSet<Rc<Term>>,
         iter: &ProofIter,
+        span: Option<SourceSpan>,
     ) -> CarcaraResult<()> {
         let time = Instant::now();
 
@@ -244,11 +526,20 @@ impl<'c> ProofChecker<'c> {
                     .add_assume_measurement(s.file_name, id, false, time);
             }
             Ok(())
+        } else if self.report_as_warning(ErrorCategory::Assume) {
+            log::warn!("term '{}' was not in original problem's assumptions", term);
+            self.is_holey = true;
+            if let Some(elaborator) = &mut self.elaborator {
+                elaborator.assume(term);
+            }
+            Ok(())
         } else {
             Err(Error::Checker {
-                inner: CheckerError::Assume(term.clone()),
+                inner: Box::new(CheckerError::Assume(term.clone())),
                 rule: "assume".into(),
                 step: id.to_owned(),
+                span: span.map(Box::new),
+                premises: Vec::new(),
             })
         }
     }
@@ -259,9 +550,29 @@ impl<'c> ProofChecker<'c> {
         previous_command: Option<Premise<'a>>,
         iter: &'a ProofIter<'a>,
     ) -> RuleResult {
+        log::trace!("checking step '{}' with rule '{}'", step.id, step.rule);
         let time = Instant::now();
         let mut deep_eq_time = Duration::ZERO;
 
+        let position = self.num_steps;
+        self.num_steps += 1;
+        if let Some(sampler) = &mut self.sampler {
+            if !sampler.should_check(&step.rule, &step.id, position) {
+                self.is_holey = true;
+                if let Some(elaborator) = &mut self.elaborator {
+                    elaborator.unchanged(&step.clause);
+                }
+                return Ok(());
+            }
+        }
+        if self.config.trusted_rules.contains(&step.rule) {
+            self.is_holey = true;
+            if let Some(elaborator) = &mut self.elaborator {
+                elaborator.unchanged(&step.clause);
+            }
+            return Ok(());
+        }
+
         let mut elaborated = false;
         if step.rule == "lia_generic" {
             if self.config.check_lia_using_cvc5 {
@@ -282,22 +593,54 @@ impl<'c> ProofChecker<'c> {
                 }
             }
         } else {
-            let rule = match Self::get_rule(&step.rule, self.config.strict) {
+            let custom_rule = self.custom_rules.get(&step.rule).copied();
+            let is_custom = custom_rule.is_some();
+
+            // Steps that use the old name of a renamed rule are checked by the current rule
+            let rule_name = match current_rule_name(&step.rule) {
+                Some(name) if !is_custom => {
+                    *self.renamed_rules.entry(step.rule.clone()).or_default() += 1;
+                    name
+                }
+                _ => step.rule.as_str(),
+            };
+            let strict = self.config.strict;
+            let commutative = self.config.commutative_transitivity;
+            let rule = custom_rule
+                .or_else(|| commutative.then(|| Self::get_commutative_rule(rule_name))?)
+                .or_else(|| Self::get_rule(rule_name, strict));
+            let rule = match rule {
                 Some(r) => r,
-                None if self.config.skip_unknown_rules => {
+                None => {
+                    if !self.config.skip_unknown_rules {
+                        if !self.report_as_warning(ErrorCategory::UnknownRule) {
+                            return Err(CheckerError::UnknownRule);
+                        }
+                        log::warn!("unknown rule '{}', treating step as a hole", step.rule);
+                    }
                     self.is_holey = true;
                     if let Some(elaborator) = &mut self.elaborator {
                         elaborator.unchanged(&step.clause);
                     }
                     return Ok(());
                 }
-                None => return Err(CheckerError::UnknownRule),
             };
 
             if step.rule == "hole" || step.rule == "trust" {
                 self.is_holey = true;
             }
 
+            // The parser only allows steps to reference commands that are visible to them, but
+            // proofs may also be built or transformed programmatically, so we check that here
+            if let Some(&(depth, index)) = step
+                .premises
+                .iter()
+                .chain(&step.discharge)
+                .find(|&&p| !iter.is_premise_visible(p))
+            {
+                return Err(CheckerError::PremiseNotVisible(depth, index));
+            }
+
             let premises: Vec<_> = step
                 .premises
                 .iter()
@@ -312,6 +655,7 @@ impl<'c> ProofChecker<'c> {
                 .map(|&i| iter.get_premise(i))
                 .collect();
 
+            self.step_trace.begin();
             let rule_args = RuleArgs {
                 conclusion: &step.clause,
                 premises: &premises,
@@ -321,25 +665,36 @@ impl<'c> ProofChecker<'c> {
                 previous_command,
                 discharge: &discharge,
                 deep_eq_time: &mut deep_eq_time,
+                trace: &mut self.step_trace,
             };
 
-            if let Some(elaborator) = &mut self.elaborator {
-                if let Some(elaboration_rule) = Self::get_elaboration_rule(&step.rule) {
-                    elaboration_rule(rule_args, step.id.clone(), elaborator)?;
-                    elaborated = true;
+            phases::measure(phases::Phase::RuleLogic, || -> RuleResult {
+                if let Some(elaborator) = &mut self.elaborator {
+                    let elaboration_rule = (!is_custom)
+                        .then(|| Self::get_elaboration_rule(rule_name, commutative))
+                        .flatten();
+                    if let Some(elaboration_rule) = elaboration_rule {
+                        elaboration_rule(rule_args, step.id.clone(), elaborator)?;
+                        elaborated = true;
+                    } else {
+                        rule(rule_args)?;
+                        elaborator.unchanged(&step.clause);
+                    }
                 } else {
                     rule(rule_args)?;
-                    elaborator.unchanged(&step.clause);
                 }
-            } else {
-                rule(rule_args)?;
-            }
+                Ok(())
+            })?;
         }
 
         if let Some(s) = &mut self.config.statistics {
             let time = time.elapsed();
+            let size = StepSize {
+                num_literals: step.clause.len(),
+                num_premises: step.premises.len(),
+            };
             s.results
-                .add_step_measurement(s.file_name, &step.id, &step.rule, time);
+                .add_step_measurement(s.file_name, &step.id, &step.rule, size, time);
             *s.deep_eq_time += deep_eq_time;
             if elaborated {
                 *s.elaboration_time += time;
@@ -379,10 +734,15 @@ impl<'c> ProofChecker<'c> {
             "eq_congruent" => congruence::eq_congruent,
             "eq_congruent_pred" => congruence::eq_congruent_pred,
             "distinct_elim" => clausification::distinct_elim,
+            #[cfg(feature = "arith")]
             "la_rw_eq" => linear_arithmetic::la_rw_eq,
+            #[cfg(feature = "arith")]
             "la_generic" => linear_arithmetic::la_generic,
+            #[cfg(feature = "arith")]
             "la_disequality" => linear_arithmetic::la_disequality,
+            #[cfg(feature = "arith")]
             "la_totality" => linear_arithmetic::la_totality,
+            #[cfg(feature = "arith")]
             "la_tautology" => linear_arithmetic::la_tautology,
             "forall_inst" => quantifier::forall_inst,
             "qnt_join" => quantifier::qnt_join,
@@ -415,6 +775,7 @@ impl<'c> ProofChecker<'c> {
             "not_ite2" => tautology::not_ite2,
             "ite_intro" => tautology::ite_intro,
             "contraction" => resolution::contraction,
+            "weakening" => resolution::weakening,
             "connective_def" => tautology::connective_def,
             "ite_simplify" => simplification::ite_simplify,
             "eq_simplify" => simplification::eq_simplify,
@@ -425,13 +786,18 @@ impl<'c> ProofChecker<'c> {
             "equiv_simplify" => simplification::equiv_simplify,
             "bool_simplify" => simplification::bool_simplify,
             "qnt_simplify" => simplification::qnt_simplify,
+            #[cfg(feature = "arith")]
             "div_simplify" => simplification::div_simplify,
+            #[cfg(feature = "arith")]
             "prod_simplify" => simplification::prod_simplify,
+            #[cfg(feature = "arith")]
             // Despite being separate rules in the specification, proofs generated by veriT don't
             // differentiate between `unary_minus_simplify` and `minus_simplify`. To account for
             // that, `simplification::minus_simplify` implements both rules in the same function.
             "unary_minus_simplify" | "minus_simplify" => simplification::minus_simplify,
+            #[cfg(feature = "arith")]
             "sum_simplify" => simplification::sum_simplify,
+            #[cfg(feature = "arith")]
             "comp_simplify" => simplification::comp_simplify,
             "nary_elim" => clausification::nary_elim,
             "ac_simp" => simplification::ac_simp,
@@ -449,7 +815,9 @@ impl<'c> ProofChecker<'c> {
             "eq_symmetric" => extras::eq_symmetric,
             "or_intro" => extras::or_intro,
             "bind_let" => extras::bind_let,
+            #[cfg(feature = "arith")]
             "la_mult_pos" => extras::la_mult_pos,
+            #[cfg(feature = "arith")]
             "la_mult_neg" => extras::la_mult_neg,
 
             // Special rules that always check as valid, and are used to indicate holes in the
@@ -465,12 +833,26 @@ impl<'c> ProofChecker<'c> {
         })
     }
 
-    fn get_elaboration_rule(rule_name: &str) -> Option<ElaborationRule> {
+    /// Returns the versions of the transitivity rules that search for a chain among the premises,
+    /// which are used instead of the usual rules if `Config::commutative_transitivity` is `true`.
+    fn get_commutative_rule(rule_name: &str) -> Option<Rule> {
         use rules::*;
 
         Some(match rule_name {
+            "eq_transitive" => transitivity::commutative_eq_transitive,
+            "trans" => transitivity::commutative_trans,
+            _ => return None,
+        })
+    }
+
+    fn get_elaboration_rule(rule_name: &str, commutative: bool) -> Option<ElaborationRule> {
+        use rules::*;
+
+        Some(match rule_name {
+            "eq_transitive" if commutative => transitivity::elaborate_commutative_eq_transitive,
             "eq_transitive" => transitivity::elaborate_eq_transitive,
             "refl" => reflexivity::elaborate_refl,
+            "trans" if commutative => transitivity::elaborate_commutative_trans,
             "trans" => transitivity::elaborate_trans,
             _ => return None,
         })
@@ -512,3 +894,27 @@ pub fn generate_lia_smt_instances(
     }
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{check, CarcaraOptions, Error};
+
+    #[test]
+    fn test_trusted_rules() {
+        let problem = "(declare-fun p () Bool) (assert p)";
+        let run = |proof: &str, trusted_rules: &[&str]| {
+            let trusted_rules = trusted_rules.iter().map(|&r| r.to_owned()).collect();
+            let options = CarcaraOptions { trusted_rules, ..Default::default() };
+            check(problem.as_bytes(), proof.as_bytes(), options)
+        };
+
+        // A trusted step is accepted even if it is invalid, and makes the proof holey
+        let proof = "(assume h1 p) (step t2 (cl) :rule resolution :premises (h1))";
+        assert!(matches!(run(proof, &[]), Err(Error::Checker { .. })));
+        assert_eq!(run(proof, &["resolution"]).ok(), Some(true));
+
+        let proof = "(assume h1 p) (step t2 (cl) :rule foo :premises (h1))";
+        assert_eq!(run(proof, &["foo"]).ok(), Some(true));
+        assert!(run(proof, &["bar"]).is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! A randomized semantic test for proof steps.
+//!
+//! Each step is evaluated under a number of random models of the declared symbols. If, in some
+//! model, all premises of a step are true but its conclusion is false, the step is unsound, and
+//! if the checker accepted it, this most likely points to a bug in the implementation of its rule.
+//!
+//! Only ground terms in the core, integer and real theories can be evaluated. Steps that contain
+//! other terms, such as quantifiers or array operations, are skipped. Since the conclusions of
+//! steps inside subproofs only hold in the subproof context, these are also skipped, except for
+//! the last step of each top-level subproof.
+
+use crate::{
+    ast::*,
+    num::{euclidean_div_mod, Rational},
+};
+use ahash::AHashMap;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fmt;
+
+/// The number of elements in the domain of each uninterpreted sort.
+const DOMAIN_SIZE: usize = 4;
+
+/// The range from which random integers are drawn. This is kept small so arithmetic terms are
+/// likely to hit interesting cases, like equalities.
+const MAX_ABS_VALUE: i64 = 5;
+
+/// A value in a model.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Value {
+    Bool(bool),
+    Number(Rational),
+
+    /// An element of the domain of an uninterpreted sort.
+    Element(usize),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) if n.is_integer() => write!(f, "{}", n.numer()),
+            Value::Number(n) => write!(f, "{}/{}", n.numer(), n.denom()),
+            Value::Element(i) => write!(f, "@{}", i),
+        }
+    }
+}
+
+/// A step whose premises are all true in some model, while its conclusion is false.
+#[derive(Debug)]
+pub struct Counterexample {
+    pub step: String,
+    pub rule: String,
+
+    /// The interpretation of each constant and function application that was evaluated, in the
+    /// form `name = value` or `name(args) = value`.
+    pub model: Vec<String>,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "step '{}' with rule '{}' is falsified by model {{ {} }}",
+            self.step,
+            self.rule,
+            self.model.join(", ")
+        )
+    }
+}
+
+/// A random model, whose symbol interpretations are generated lazily, as they are needed.
+struct Model<'a> {
+    rng: &'a mut StdRng,
+    interpretations: AHashMap<(String, Vec<Value>), Value>,
+}
+
+impl<'a> Model<'a> {
+    fn random_value(&mut self, sort: &Sort) -> Option<Value> {
+        Some(match sort {
+            Sort::Bool => Value::Bool(self.rng.gen()),
+            Sort::Int => Value::Number(self.rng.gen_range(-MAX_ABS_VALUE..=MAX_ABS_VALUE).into()),
+            Sort::Real => {
+                let numer = self.rng.gen_range(-MAX_ABS_VALUE..=MAX_ABS_VALUE);
+                let denom = self.rng.gen_range(1..=3);
+                Value::Number(Rational::from((numer, denom)))
+            }
+            Sort::Atom(_, args) if args.is_empty() => {
+                Value::Element(self.rng.gen_range(0..DOMAIN_SIZE))
+            }
+            _ => return None,
+        })
+    }
+
+    fn interpret(&mut self, name: &str, args: Vec<Value>, sort: &Sort) -> Option<Value> {
+        let key = (name.to_owned(), args);
+        if let Some(value) = self.interpretations.get(&key) {
+            return Some(value.clone());
+        }
+        let value = self.random_value(sort)?;
+        self.interpretations.insert(key, value.clone());
+        Some(value)
+    }
+
+    fn eval_bool(&mut self, term: &Rc<Term>) -> Option<bool> {
+        match self.eval(term)? {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn eval_number(&mut self, term: &Rc<Term>) -> Option<Rational> {
+        match self.eval(term)? {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn eval_numbers(&mut self, args: &[Rc<Term>]) -> Option<Vec<Rational>> {
+        args.iter().map(|a| self.eval_number(a)).collect()
+    }
+
+    /// Evaluates a term in the model. Returns `None` if the term can't be evaluated, either
+    /// because it's not in a supported fragment or because its value is not defined, as in a
+    /// division by zero.
+    fn eval(&mut self, term: &Rc<Term>) -> Option<Value> {
+        match term.as_ref() {
+            Term::Terminal(Terminal::Integer(i)) => Some(Value::Number(i.clone().into())),
+            Term::Terminal(Terminal::Real(r)) => Some(Value::Number(r.clone())),
+            Term::Terminal(Terminal::String(_)) => None,
+            _ if term.is_bool_true() => Some(Value::Bool(true)),
+            _ if term.is_bool_false() => Some(Value::Bool(false)),
+            Term::Terminal(Terminal::Var(iden, sort)) => {
+                self.interpret(&iden.to_string(), Vec::new(), sort)
+            }
+            Term::App(f, args) => {
+                let (name, sorts) = match f.as_ref() {
+                    Term::Terminal(Terminal::Var(iden, sort)) => match sort.as_ref() {
+                        Sort::Function(sorts) => (iden.to_string(), sorts),
+                        _ => return None,
+                    },
+                    _ => return None,
+                };
+                let args = args.iter().map(|a| self.eval(a)).collect::<Option<_>>()?;
+                self.interpret(&name, args, sorts.last()?)
+            }
+            Term::Op(op, args) => self.eval_op(*op, args),
+            _ => None,
+        }
+    }
+
+    fn eval_op(&mut self, op: Operator, args: &[Rc<Term>]) -> Option<Value> {
+        let bools = |model: &mut Self| -> Option<Vec<bool>> {
+            args.iter().map(|a| model.eval_bool(a)).collect()
+        };
+        let compare = |model: &mut Self, f: fn(&Rational, &Rational) -> bool| {
+            let numbers = model.eval_numbers(args)?;
+            Some(Value::Bool(numbers.windows(2).all(|w| f(&w[0], &w[1]))))
+        };
+        let value = match op {
+            Operator::Not => Value::Bool(!self.eval_bool(&args[0])?),
+            Operator::And => Value::Bool(bools(self)?.into_iter().all(|b| b)),
+            Operator::Or => Value::Bool(bools(self)?.into_iter().any(|b| b)),
+            Operator::Xor => Value::Bool(bools(self)?.into_iter().fold(false, |a, b| a != b)),
+            Operator::Implies => {
+                let bools = bools(self)?;
+                let (last, init) = bools.split_last()?;
+                Value::Bool(init.iter().any(|b| !b) || *last)
+            }
+            Operator::Equals | Operator::Distinct => {
+                let values: Vec<_> = args.iter().map(|a| self.eval(a)).collect::<Option<_>>()?;
+                if op == Operator::Equals {
+                    Value::Bool(values.windows(2).all(|w| w[0] == w[1]))
+                } else {
+                    let all_distinct = (0..values.len())
+                        .all(|i| (i + 1..values.len()).all(|j| values[i] != values[j]));
+                    Value::Bool(all_distinct)
+                }
+            }
+            Operator::Ite => {
+                let branch = if self.eval_bool(&args[0])? {
+                    &args[1]
+                } else {
+                    &args[2]
+                };
+                self.eval(branch)?
+            }
+            Operator::Add => {
+                let numbers = self.eval_numbers(args)?.into_iter();
+                Value::Number(numbers.fold(Rational::new(), |acc, n| acc + n))
+            }
+            Operator::Mult => {
+                let numbers = self.eval_numbers(args)?.into_iter();
+                Value::Number(numbers.fold(Rational::from(1), |acc, n| acc * n))
+            }
+            Operator::Sub if args.len() == 1 => Value::Number(-self.eval_number(&args[0])?),
+            Operator::Sub => {
+                let mut numbers = self.eval_numbers(args)?.into_iter();
+                let first = numbers.next()?;
+                Value::Number(numbers.fold(first, |acc, n| acc - n))
+            }
+            Operator::RealDiv => {
+                let mut numbers = self.eval_numbers(args)?.into_iter();
+                let mut result = numbers.next()?;
+                for n in numbers {
+                    if n == 0 {
+                        return None;
+                    }
+                    result /= n;
+                }
+                Value::Number(result)
+            }
+            Operator::IntDiv | Operator::Mod => {
+                let (quotient, remainder) = match self.eval_numbers(args)?.as_slice() {
+                    [a, b] => euclidean_div_mod(a, b)?,
+                    _ => return None,
+                };
+                match op {
+                    Operator::IntDiv => Value::Number(quotient),
+                    _ => Value::Number(remainder),
+                }
+            }
+            Operator::Abs => Value::Number(self.eval_number(&args[0])?.abs()),
+            Operator::LessThan => compare(self, |a, b| a < b)?,
+            Operator::GreaterThan => compare(self, |a, b| a > b)?,
+            Operator::LessEq => compare(self, |a, b| a <= b)?,
+            Operator::GreaterEq => compare(self, |a, b| a >= b)?,
+            Operator::ToReal => Value::Number(self.eval_number(&args[0])?),
+            Operator::ToInt => {
+                let mut n = self.eval_number(&args[0])?;
+                n.floor_mut();
+                Value::Number(n)
+            }
+            Operator::IsInt => Value::Bool(self.eval_number(&args[0])?.is_integer()),
+            // Arrays and bitvectors are not supported
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn eval_clause(&mut self, clause: &[Rc<Term>]) -> Option<bool> {
+        let mut result = false;
+        for literal in clause {
+            result |= self.eval_bool(literal)?;
+        }
+        Some(result)
+    }
+
+    fn describe(&self) -> Vec<String> {
+        let mut entries: Vec<_> = self
+            .interpretations
+            .iter()
+            .map(|((name, args), value)| {
+                if args.is_empty() {
+                    format!("{} = {}", name, value)
+                } else {
+                    let args: Vec<_> = args.iter().map(ToString::to_string).collect();
+                    format!("{}({}) = {}", name, args.join(", "), value)
+                }
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+}
+
+/// Evaluates every step in the proof under `num_models` random models, generated from `seed`, and
+/// returns the steps that were falsified by some model. At most one counterexample is reported
+/// for each step.
+pub fn find_counterexamples(proof: &Proof, num_models: usize, seed: u64) -> Vec<Counterexample> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut result = Vec::new();
+    let mut iter = proof.iter();
+    while let Some(command) = iter.next() {
+        let step = match command {
+            ProofCommand::Step(s)
+                if iter.depth() == 0 || iter.is_end_step() && iter.depth() == 1 =>
+            {
+                s
+            }
+            _ => continue,
+        };
+        // The premises of an end step may refer to commands inside the subproof, whose
+        // conclusions only hold in the subproof context
+        if step.premises.iter().any(|&(depth, _)| depth > 0) {
+            continue;
+        }
+        let premises: Vec<_> = step
+            .premises
+            .iter()
+            .map(|&p| iter.get_premise(p).clause())
+            .collect();
+
+        for _ in 0..num_models {
+            let mut model = Model {
+                rng: &mut rng,
+                interpretations: AHashMap::new(),
+            };
+            let premises_hold = premises
+                .iter()
+                .try_fold(true, |acc, p| Some(acc && model.eval_clause(p)?));
+            let is_counterexample =
+                premises_hold == Some(true) && model.eval_clause(&step.clause) == Some(false);
+            if is_counterexample {
+                result.push(Counterexample {
+                    step: step.id.clone(),
+                    rule: step.rule.clone(),
+                    model: model.describe(),
+                });
+                break;
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, ParserConfig};
+
+    fn run(definitions: &str, proof: &str) -> Vec<String> {
+        let mut pool = TermPool::new();
+        let (_, _, mut parser) =
+            Parser::new(&mut pool, ParserConfig::default(), definitions.as_bytes())
+                .and_then(Parser::parse_problem)
+                .unwrap();
+        let commands = parser.parse_proof(proof.as_bytes()).unwrap();
+        let proof = Proof {
+            premises: Default::default(),
+            commands,
+            spans: Vec::new(),
+            metadata: Vec::new(),
+            named_assertions: Vec::new(),
+            comments: Vec::new(),
+        };
+        find_counterexamples(&proof, 100, 0)
+            .into_iter()
+            .map(|c| c.step)
+            .collect()
+    }
+
+    #[test]
+    fn test_find_counterexamples() {
+        let definitions = "
+            (declare-sort U 0)
+            (declare-fun a () U)
+            (declare-fun b () U)
+            (declare-fun f (U) U)
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (declare-fun x () Int)
+            (declare-fun y () Real)
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (not p))
+            (step t3 (cl q) :rule hole :premises (h1 h2))
+            (step t4 (cl p) :rule hole :premises (h1))
+            (step t5 (cl (not (= a b)) (= (f a) (f b))) :rule hole)
+            (step t6 (cl (= (f a) a)) :rule hole)
+            (step t7 (cl (< x (+ x 1)) (= (div x 2) (- (mod x 2) 1))) :rule hole)
+            (step t8 (cl (= (- x (* 2 (div x 2))) (mod x 2))) :rule hole)
+            (step t9 (cl (= (mod x (- 3)) (mod x 3)) (>= y (to_real (to_int y)))) :rule hole)
+            (step t10 (cl (< y (to_real (to_int y)))) :rule hole)
+            (step t11 (cl (forall ((z Int)) (< z x))) :rule hole)
+        ";
+        assert_eq!(run(definitions, proof), ["t4", "t6", "t10"]);
+    }
+}
@@ -0,0 +1,1173 @@
+//! Human-readable descriptions of the rules supported by the checker. Each rule has a short
+//! summary, the shape of the clause it concludes, the premises and arguments it expects, and a
+//! small example that the checker accepts. These are used by the `explain` command of the CLI, so
+//! users can see what a rule expects without having to look it up in the Alethe specification.
+//!
+//! In the shapes, `phi`, `psi` and `chi` stand for boolean terms, `t` and `u` stand for terms of
+//! any sort, and `...` stands for any number of terms following the same pattern.
+
+/// The description of a rule.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInfo {
+    /// The name of the rule.
+    pub name: &'static str,
+
+    /// Other names under which the rule is accepted by the checker.
+    pub aliases: &'static [&'static str],
+
+    /// A one-line summary of what the rule does.
+    pub summary: &'static str,
+
+    /// The shape of the clause concluded by the rule.
+    pub conclusion: &'static str,
+
+    /// The premises expected by the rule, or an empty string if it takes no premises.
+    pub premises: &'static str,
+
+    /// The meaning of the arguments expected by the rule, or an empty string if it takes no
+    /// arguments.
+    pub args: &'static str,
+
+    /// The declarations needed by the example, in SMT-LIB syntax, one command per line.
+    pub definitions: &'static str,
+
+    /// A small example of a valid use of the rule. The step that uses the rule is always the last
+    /// command, and any premises are introduced by `assume` commands or `hole` steps before it.
+    pub example: &'static str,
+}
+
+/// Returns the description of the rule with the given name, if there is one. Aliases of a rule, and
+/// the old names of renamed rules, are also accepted.
+pub fn get_rule_info(name: &str) -> Option<&'static RuleInfo> {
+    let name = super::current_rule_name(name).unwrap_or(name);
+    RULES
+        .iter()
+        .find(|info| info.name == name || info.aliases.contains(&name))
+}
+
+/// Returns the descriptions of all rules.
+pub fn all_rules() -> &'static [RuleInfo] {
+    RULES
+}
+
+const BOOLS: &str = "(declare-fun p () Bool)\n(declare-fun q () Bool)\n(declare-fun r () Bool)";
+
+const SORT_T: &str = "(declare-sort T 0)\n(declare-fun a () T)\n(declare-fun b () T)\n\
+    (declare-fun c () T)\n(declare-fun f (T) T)";
+
+const INTS: &str = "(declare-fun a () Int)\n(declare-fun b () Int)";
+
+const REALS: &str = "(declare-fun x () Real)\n(declare-fun y () Real)";
+
+static RULES: &[RuleInfo] = &[
+    RuleInfo {
+        name: "assume",
+        aliases: &[],
+        summary: "Introduces an assertion of the problem, or a local assumption in a subproof.",
+        conclusion: "phi, where phi is an assertion of the problem or the assumption of a subproof",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (and p q))",
+    },
+    RuleInfo {
+        name: "true",
+        aliases: &[],
+        summary: "The constant `true` holds.",
+        conclusion: "(cl true)",
+        premises: "",
+        args: "",
+        definitions: "",
+        example: "(step t1 (cl true) :rule true)",
+    },
+    RuleInfo {
+        name: "false",
+        aliases: &[],
+        summary: "The constant `false` does not hold.",
+        conclusion: "(cl (not false))",
+        premises: "",
+        args: "",
+        definitions: "",
+        example: "(step t1 (cl (not false)) :rule false)",
+    },
+    RuleInfo {
+        name: "not_not",
+        aliases: &[],
+        summary: "Double negation elimination, as a tautology.",
+        conclusion: "(cl (not (not (not phi))) phi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (not (not p))) p) :rule not_not)",
+    },
+    RuleInfo {
+        name: "and_pos",
+        aliases: &[],
+        summary: "A conjunction implies each of its conjuncts.",
+        conclusion: "(cl (not (and phi_1 ... phi_n)) phi_k)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (and p q r)) q) :rule and_pos)",
+    },
+    RuleInfo {
+        name: "and_neg",
+        aliases: &[],
+        summary: "A conjunction holds if all of its conjuncts hold.",
+        conclusion: "(cl (and phi_1 ... phi_n) (not phi_1) ... (not phi_n))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (and p q) (not p) (not q)) :rule and_neg)",
+    },
+    RuleInfo {
+        name: "or_pos",
+        aliases: &[],
+        summary: "A disjunction implies that one of its disjuncts holds.",
+        conclusion: "(cl (not (or phi_1 ... phi_n)) phi_1 ... phi_n)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (or p q)) p q) :rule or_pos)",
+    },
+    RuleInfo {
+        name: "or_neg",
+        aliases: &[],
+        summary: "A disjunction holds if any of its disjuncts holds.",
+        conclusion: "(cl (or phi_1 ... phi_n) (not phi_k))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (or p q r) (not r)) :rule or_neg)",
+    },
+    RuleInfo {
+        name: "xor_pos1",
+        aliases: &[],
+        summary: "If `(xor phi psi)` holds, one of its arguments holds.",
+        conclusion: "(cl (not (xor phi psi)) phi psi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (xor p q)) p q) :rule xor_pos1)",
+    },
+    RuleInfo {
+        name: "xor_pos2",
+        aliases: &[],
+        summary: "If `(xor phi psi)` holds, one of its arguments does not hold.",
+        conclusion: "(cl (not (xor phi psi)) (not phi) (not psi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (xor p q)) (not p) (not q)) :rule xor_pos2)",
+    },
+    RuleInfo {
+        name: "xor_neg1",
+        aliases: &[],
+        summary: "`(xor phi psi)` holds if `phi` holds and `psi` does not.",
+        conclusion: "(cl (xor phi psi) phi (not psi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (xor p q) p (not q)) :rule xor_neg1)",
+    },
+    RuleInfo {
+        name: "xor_neg2",
+        aliases: &[],
+        summary: "`(xor phi psi)` holds if `psi` holds and `phi` does not.",
+        conclusion: "(cl (xor phi psi) (not phi) psi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (xor p q) (not p) q) :rule xor_neg2)",
+    },
+    RuleInfo {
+        name: "implies_pos",
+        aliases: &[],
+        summary: "Modus ponens, as a tautology.",
+        conclusion: "(cl (not (=> phi psi)) (not phi) psi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (=> p q)) (not p) q) :rule implies_pos)",
+    },
+    RuleInfo {
+        name: "implies_neg1",
+        aliases: &[],
+        summary: "An implication holds if its antecedent does not hold.",
+        conclusion: "(cl (=> phi psi) phi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (=> p q) p) :rule implies_neg1)",
+    },
+    RuleInfo {
+        name: "implies_neg2",
+        aliases: &[],
+        summary: "An implication holds if its consequent holds.",
+        conclusion: "(cl (=> phi psi) (not psi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (=> p q) (not q)) :rule implies_neg2)",
+    },
+    RuleInfo {
+        name: "equiv_pos1",
+        aliases: &[],
+        summary: "If `(= phi psi)` holds and `psi` holds, `phi` holds.",
+        conclusion: "(cl (not (= phi psi)) phi (not psi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (= p q)) p (not q)) :rule equiv_pos1)",
+    },
+    RuleInfo {
+        name: "equiv_pos2",
+        aliases: &[],
+        summary: "If `(= phi psi)` holds and `phi` holds, `psi` holds.",
+        conclusion: "(cl (not (= phi psi)) (not phi) psi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (= p q)) (not p) q) :rule equiv_pos2)",
+    },
+    RuleInfo {
+        name: "equiv_neg1",
+        aliases: &[],
+        summary: "`(= phi psi)` holds if both `phi` and `psi` hold.",
+        conclusion: "(cl (= phi psi) (not phi) (not psi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= p q) (not p) (not q)) :rule equiv_neg1)",
+    },
+    RuleInfo {
+        name: "equiv_neg2",
+        aliases: &[],
+        summary: "`(= phi psi)` holds if neither `phi` nor `psi` hold.",
+        conclusion: "(cl (= phi psi) phi psi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= p q) p q) :rule equiv_neg2)",
+    },
+    RuleInfo {
+        name: "ite_pos1",
+        aliases: &[],
+        summary: "If `(ite phi psi chi)` holds and `phi` does not, `chi` holds.",
+        conclusion: "(cl (not (ite phi psi chi)) phi chi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (ite p q r)) p r) :rule ite_pos1)",
+    },
+    RuleInfo {
+        name: "ite_pos2",
+        aliases: &[],
+        summary: "If `(ite phi psi chi)` holds and `phi` holds, `psi` holds.",
+        conclusion: "(cl (not (ite phi psi chi)) (not phi) psi)",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not (ite p q r)) (not p) q) :rule ite_pos2)",
+    },
+    RuleInfo {
+        name: "ite_neg1",
+        aliases: &[],
+        summary: "`(ite phi psi chi)` holds if `phi` does not hold and `chi` holds.",
+        conclusion: "(cl (ite phi psi chi) phi (not chi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (ite p q r) p (not r)) :rule ite_neg1)",
+    },
+    RuleInfo {
+        name: "ite_neg2",
+        aliases: &[],
+        summary: "`(ite phi psi chi)` holds if `phi` and `psi` hold.",
+        conclusion: "(cl (ite phi psi chi) (not phi) (not psi))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (ite p q r) (not p) (not q)) :rule ite_neg2)",
+    },
+    RuleInfo {
+        name: "eq_reflexive",
+        aliases: &[],
+        summary: "Every term is equal to itself.",
+        conclusion: "(cl (= t t))",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (= a a)) :rule eq_reflexive)",
+    },
+    RuleInfo {
+        name: "eq_transitive",
+        aliases: &[],
+        summary: "Transitivity of equality, as a tautology.",
+        conclusion: "(cl (not (= t_1 t_2)) ... (not (= t_n-1 t_n)) (= t_1 t_n))",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (not (= a b)) (not (= b c)) (= a c)) :rule eq_transitive)",
+    },
+    RuleInfo {
+        name: "eq_congruent",
+        aliases: &[],
+        summary: "Congruence of function applications, as a tautology.",
+        conclusion: "(cl (not (= t_1 u_1)) ... (not (= t_n u_n)) (= (f t_1 ... t_n) (f u_1 ... \
+            u_n)))",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (not (= a b)) (= (f a) (f b))) :rule eq_congruent)",
+    },
+    RuleInfo {
+        name: "eq_congruent_pred",
+        aliases: &[],
+        summary: "Congruence of predicate applications, as a tautology.",
+        conclusion: "(cl (not (= t_1 u_1)) ... (not (= t_n u_n)) (not (P t_1 ... t_n)) (P u_1 \
+            ... u_n))",
+        premises: "",
+        args: "",
+        definitions: "(declare-sort T 0)\n(declare-fun a () T)\n(declare-fun b () T)\n\
+            (declare-fun P (T) Bool)",
+        example: "(step t1 (cl (not (= a b)) (not (P a)) (P b)) :rule eq_congruent_pred)",
+    },
+    RuleInfo {
+        name: "distinct_elim",
+        aliases: &[],
+        summary: "Eliminates a `distinct` term into pairwise disequalities.",
+        conclusion: "(cl (= (distinct t_1 ... t_n) (and (not (= t_1 t_2)) ... (not (= t_n-1 \
+            t_n)))))",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (= (distinct a b) (not (= a b)))) :rule distinct_elim)",
+    },
+    RuleInfo {
+        name: "la_rw_eq",
+        aliases: &[],
+        summary: "Rewrites an arithmetic equality into two inequalities.",
+        conclusion: "(cl (= (= t u) (and (<= t u) (<= u t))))",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (= (= a b) (and (<= a b) (<= b a)))) :rule la_rw_eq)",
+    },
+    RuleInfo {
+        name: "la_generic",
+        aliases: &[],
+        summary: "The negations of the literals in the clause are a contradictory set of linear \
+            inequalities.",
+        conclusion: "(cl phi_1 ... phi_n), where each phi_i is a linear (in)equality or its \
+            negation",
+        premises: "",
+        args: "the coefficients c_1 ... c_n by which the negation of each literal is multiplied; \
+            summing the results must give a trivially false inequality",
+        definitions: REALS,
+        example: "(step t1 (cl (> x 0.0) (<= x 0.0)) :rule la_generic :args (1.0 1.0))",
+    },
+    RuleInfo {
+        name: "la_disequality",
+        aliases: &[],
+        summary: "Two arithmetic terms are either equal, or one is less than the other.",
+        conclusion: "(cl (or (= t u) (not (<= t u)) (not (<= u t))))",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (or (= a b) (not (<= a b)) (not (<= b a)))) :rule la_disequality)",
+    },
+    RuleInfo {
+        name: "la_totality",
+        aliases: &[],
+        summary: "The order on arithmetic terms is total.",
+        conclusion: "(cl (or (<= t u) (<= u t)))",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (or (<= a b) (<= b a))) :rule la_totality)",
+    },
+    RuleInfo {
+        name: "la_tautology",
+        aliases: &[],
+        summary: "A trivially valid linear arithmetic literal or clause.",
+        conclusion: "(cl phi), where phi is a linear inequality that always holds, or one of a \
+            few clauses of two inequalities described in the specification",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (<= a (+ 1 a))) :rule la_tautology)",
+    },
+    RuleInfo {
+        name: "forall_inst",
+        aliases: &[],
+        summary: "Instantiates a universal quantifier.",
+        conclusion: "(cl (or (not (forall ((x_1 S_1) ... (x_n S_n)) phi)) phi'))",
+        premises: "",
+        args: "one `(:= x_i t_i)` for each bound variable, giving the term it is instantiated \
+            with; phi' is phi with each x_i replaced by t_i",
+        definitions: "(declare-fun a () Int)\n(declare-fun P (Int) Bool)",
+        example: "(step t1 (cl (or (not (forall ((x Int)) (P x))) (P a))) :rule forall_inst \
+            :args ((:= x a)))",
+    },
+    RuleInfo {
+        name: "qnt_join",
+        aliases: &[],
+        summary: "Joins two nested quantifiers of the same kind into one.",
+        conclusion: "(cl (= (Q (xs) (Q (ys) phi)) (Q (zs) phi))), where Q is `forall` or `exists` \
+            and zs is xs followed by ys, without repetitions",
+        premises: "",
+        args: "",
+        definitions: "",
+        example: "(step t1 (cl (= (forall ((x Int)) (forall ((y Int)) (= x y))) (forall ((x Int) \
+            (y Int)) (= x y)))) :rule qnt_join)",
+    },
+    RuleInfo {
+        name: "qnt_rm_unused",
+        aliases: &[],
+        summary: "Removes the bound variables that do not appear in the body of a quantifier.",
+        conclusion: "(cl (= (Q (xs) phi) (Q (ys) phi))), where ys are the variables of xs that \
+            appear in phi",
+        premises: "",
+        args: "",
+        definitions: "",
+        example: "(step t1 (cl (= (forall ((x Int) (y Int)) (= x 0)) (forall ((x Int)) (= x 0)))) \
+            :rule qnt_rm_unused)",
+    },
+    RuleInfo {
+        name: "resolution",
+        aliases: &["th_resolution"],
+        summary: "Resolution of two or more clauses.",
+        conclusion: "the clause obtained by resolving the premises, in any order and without \
+            repeated literals",
+        premises: "two or more clauses",
+        args: "optionally, for each premise after the first, a pivot literal and a boolean that \
+            indicates whether the pivot appears positively in the clause so far",
+        definitions: BOOLS,
+        example: "(assume h1 (not p))\n(step t2 (cl p q) :rule hole)\n(step t3 (cl q) :rule \
+            resolution :premises (h1 t2))",
+    },
+    RuleInfo {
+        name: "strict_resolution",
+        aliases: &[],
+        summary: "Resolution of two or more clauses, where the conclusion must be exactly the \
+            resolvent, including the order of the literals.",
+        conclusion: "the resolvent of the premises",
+        premises: "two or more clauses",
+        args: "for each premise after the first, a pivot literal and a boolean that indicates \
+            whether the pivot appears positively in the clause so far",
+        definitions: BOOLS,
+        example: "(step t1 (cl p q) :rule hole)\n(step t2 (cl (not q) r) :rule hole)\n(step t3 \
+            (cl p r) :rule strict_resolution :premises (t1 t2) :args (q true))",
+    },
+    RuleInfo {
+        name: "refl",
+        aliases: &[],
+        summary: "Equality between a term and itself, after applying the substitution of the \
+            current subproof context.",
+        conclusion: "(cl (= t u)), where u is t after applying the context substitution",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (= (f a) (f a))) :rule refl)",
+    },
+    RuleInfo {
+        name: "trans",
+        aliases: &[],
+        summary: "Transitivity of equality.",
+        conclusion: "(cl (= t_1 t_n))",
+        premises: "(= t_1 t_2), ..., (= t_n-1 t_n), in order",
+        args: "",
+        definitions: SORT_T,
+        example: "(assume h1 (= a b))\n(assume h2 (= b c))\n(step t3 (cl (= a c)) :rule trans \
+            :premises (h1 h2))",
+    },
+    RuleInfo {
+        name: "cong",
+        aliases: &[],
+        summary: "Congruence of function applications and operators.",
+        conclusion: "(cl (= (f t_1 ... t_n) (f u_1 ... u_n)))",
+        premises: "one equality (= t_i u_i) for each argument where t_i and u_i differ, in order",
+        args: "",
+        definitions: SORT_T,
+        example: "(assume h1 (= a b))\n(step t2 (cl (= (f a) (f b))) :rule cong :premises (h1))",
+    },
+    RuleInfo {
+        name: "ho_cong",
+        aliases: &[],
+        summary: "Congruence of function applications, where the functions themselves may differ.",
+        conclusion: "(cl (= (f t_1 ... t_n) (g u_1 ... u_n)))",
+        premises: "(= f g), followed by one equality (= t_i u_i) for each argument where t_i and \
+            u_i differ, in order",
+        args: "",
+        definitions: "(declare-sort T 0)\n(declare-fun a () T)\n(declare-fun b () T)\n\
+            (declare-fun f (T) T)\n(declare-fun g (T) T)",
+        example: "(assume h1 (= f g))\n(assume h2 (= a b))\n(step t3 (cl (= (f a) (g b))) :rule \
+            ho_cong :premises (h1 h2))",
+    },
+    RuleInfo {
+        name: "and",
+        aliases: &[],
+        summary: "Extracts a conjunct from a conjunction.",
+        conclusion: "(cl phi_k)",
+        premises: "(and phi_1 ... phi_n)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (and p q))\n(step t2 (cl q) :rule and :premises (h1))",
+    },
+    RuleInfo {
+        name: "tautology",
+        aliases: &[],
+        summary: "A clause that contains a literal and its negation is valid.",
+        conclusion: "(cl true)",
+        premises: "a clause that contains both phi and (not phi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (not p) p) :rule hole)\n(step t2 (cl true) :rule tautology \
+            :premises (t1))",
+    },
+    RuleInfo {
+        name: "not_or",
+        aliases: &[],
+        summary: "Extracts a negated disjunct from a negated disjunction.",
+        conclusion: "(cl (not phi_k))",
+        premises: "(not (or phi_1 ... phi_n))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (or p q)))\n(step t2 (cl (not q)) :rule not_or :premises (h1))",
+    },
+    RuleInfo {
+        name: "or",
+        aliases: &[],
+        summary: "Turns a disjunction into a clause.",
+        conclusion: "(cl phi_1 ... phi_n)",
+        premises: "(or phi_1 ... phi_n)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (or p q))\n(step t2 (cl p q) :rule or :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_and",
+        aliases: &[],
+        summary: "Turns a negated conjunction into a clause.",
+        conclusion: "(cl (not phi_1) ... (not phi_n))",
+        premises: "(not (and phi_1 ... phi_n))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (and p q)))\n(step t2 (cl (not p) (not q)) :rule not_and \
+            :premises (h1))",
+    },
+    RuleInfo {
+        name: "xor1",
+        aliases: &[],
+        summary: "If `(xor phi psi)` holds, one of its arguments holds.",
+        conclusion: "(cl phi psi)",
+        premises: "(xor phi psi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (xor p q))\n(step t2 (cl p q) :rule xor1 :premises (h1))",
+    },
+    RuleInfo {
+        name: "xor2",
+        aliases: &[],
+        summary: "If `(xor phi psi)` holds, one of its arguments does not hold.",
+        conclusion: "(cl (not phi) (not psi))",
+        premises: "(xor phi psi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (xor p q))\n(step t2 (cl (not p) (not q)) :rule xor2 :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_xor1",
+        aliases: &[],
+        summary: "If `(xor phi psi)` does not hold, `psi` implies `phi`.",
+        conclusion: "(cl phi (not psi))",
+        premises: "(not (xor phi psi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (xor p q)))\n(step t2 (cl p (not q)) :rule not_xor1 :premises \
+            (h1))",
+    },
+    RuleInfo {
+        name: "not_xor2",
+        aliases: &[],
+        summary: "If `(xor phi psi)` does not hold, `phi` implies `psi`.",
+        conclusion: "(cl (not phi) psi)",
+        premises: "(not (xor phi psi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (xor p q)))\n(step t2 (cl (not p) q) :rule not_xor2 :premises \
+            (h1))",
+    },
+    RuleInfo {
+        name: "implies",
+        aliases: &[],
+        summary: "Turns an implication into a clause.",
+        conclusion: "(cl (not phi) psi)",
+        premises: "(=> phi psi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (=> p q))\n(step t2 (cl (not p) q) :rule implies :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_implies1",
+        aliases: &[],
+        summary: "If an implication does not hold, its antecedent holds.",
+        conclusion: "(cl phi)",
+        premises: "(not (=> phi psi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (=> p q)))\n(step t2 (cl p) :rule not_implies1 :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_implies2",
+        aliases: &[],
+        summary: "If an implication does not hold, its consequent does not hold.",
+        conclusion: "(cl (not psi))",
+        premises: "(not (=> phi psi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (=> p q)))\n(step t2 (cl (not q)) :rule not_implies2 :premises \
+            (h1))",
+    },
+    RuleInfo {
+        name: "equiv1",
+        aliases: &[],
+        summary: "If `(= phi psi)` holds, `phi` implies `psi`.",
+        conclusion: "(cl (not phi) psi)",
+        premises: "(= phi psi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (= p q))\n(step t2 (cl (not p) q) :rule equiv1 :premises (h1))",
+    },
+    RuleInfo {
+        name: "equiv2",
+        aliases: &[],
+        summary: "If `(= phi psi)` holds, `psi` implies `phi`.",
+        conclusion: "(cl phi (not psi))",
+        premises: "(= phi psi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (= p q))\n(step t2 (cl p (not q)) :rule equiv2 :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_equiv1",
+        aliases: &[],
+        summary: "If `(= phi psi)` does not hold, one of its arguments holds.",
+        conclusion: "(cl phi psi)",
+        premises: "(not (= phi psi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (= p q)))\n(step t2 (cl p q) :rule not_equiv1 :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_equiv2",
+        aliases: &[],
+        summary: "If `(= phi psi)` does not hold, one of its arguments does not hold.",
+        conclusion: "(cl (not phi) (not psi))",
+        premises: "(not (= phi psi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (= p q)))\n(step t2 (cl (not p) (not q)) :rule not_equiv2 \
+            :premises (h1))",
+    },
+    RuleInfo {
+        name: "ite1",
+        aliases: &[],
+        summary: "If `(ite phi psi chi)` holds, either `phi` or `chi` holds.",
+        conclusion: "(cl phi chi)",
+        premises: "(ite phi psi chi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (ite p q r))\n(step t2 (cl p r) :rule ite1 :premises (h1))",
+    },
+    RuleInfo {
+        name: "ite2",
+        aliases: &[],
+        summary: "If `(ite phi psi chi)` holds, `phi` implies `psi`.",
+        conclusion: "(cl (not phi) psi)",
+        premises: "(ite phi psi chi)",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (ite p q r))\n(step t2 (cl (not p) q) :rule ite2 :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_ite1",
+        aliases: &[],
+        summary: "If `(ite phi psi chi)` does not hold, either `phi` holds or `chi` does not.",
+        conclusion: "(cl phi (not chi))",
+        premises: "(not (ite phi psi chi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (ite p q r)))\n(step t2 (cl p (not r)) :rule not_ite1 :premises \
+            (h1))",
+    },
+    RuleInfo {
+        name: "not_ite2",
+        aliases: &[],
+        summary: "If `(ite phi psi chi)` does not hold, `phi` implies that `psi` does not hold.",
+        conclusion: "(cl (not phi) (not psi))",
+        premises: "(not (ite phi psi chi))",
+        args: "",
+        definitions: BOOLS,
+        example: "(assume h1 (not (ite p q r)))\n(step t2 (cl (not p) (not q)) :rule not_ite2 \
+            :premises (h1))",
+    },
+    RuleInfo {
+        name: "ite_intro",
+        aliases: &[],
+        summary: "Introduces the defining properties of the `ite` terms that appear in a term.",
+        conclusion: "(cl (= t (and t (ite phi_1 (= u_1 t_1) (= v_1 t_1)) ...))), where each t_i \
+            is an `ite` term (ite phi_i u_i v_i) that appears in t",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (ite p q r) (and (ite p q r) (ite p (= q (ite p q r)) (= r (ite \
+            p q r)))))) :rule ite_intro)",
+    },
+    RuleInfo {
+        name: "contraction",
+        aliases: &[],
+        summary: "Removes duplicate literals from a clause.",
+        conclusion: "the premise clause without repeated literals",
+        premises: "a clause",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl p q q) :rule hole)\n(step t2 (cl p q) :rule contraction :premises \
+            (t1))",
+    },
+    RuleInfo {
+        name: "weakening",
+        aliases: &[],
+        summary:
+            "Adds literals to a clause. The literals of the premise may appear in any order in \
+            the conclusion, but each one must appear at least as many times as in the premise.",
+        conclusion: "(cl phi_1 ... phi_n psi_1 ... psi_m)",
+        premises: "(cl phi_1 ... phi_n)",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl p q) :rule hole)\n(step t2 (cl p q r) :rule weakening :premises \
+            (t1))",
+    },
+    RuleInfo {
+        name: "connective_def",
+        aliases: &[],
+        summary: "The definition of `xor`, `=` between booleans, `ite`, `forall` or `exists` in \
+            terms of other connectives.",
+        conclusion: "for example, (cl (= (xor phi psi) (or (and (not phi) psi) (and phi (not \
+            psi)))))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (xor p q) (or (and (not p) q) (and p (not q))))) :rule \
+            connective_def)",
+    },
+    RuleInfo {
+        name: "ite_simplify",
+        aliases: &[],
+        summary: "Simplifies an `ite` term.",
+        conclusion: "(cl (= t u)), where u is t after applying the `ite` simplifications \
+            described in the specification",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (ite true p q) p)) :rule ite_simplify)",
+    },
+    RuleInfo {
+        name: "eq_simplify",
+        aliases: &[],
+        summary: "Simplifies an equality between a term and itself, or between two different \
+            numerical constants.",
+        conclusion: "(cl (= (= t t) true)) or (cl (= (= c_1 c_2) false)), among others",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (= (= a a) true)) :rule eq_simplify)",
+    },
+    RuleInfo {
+        name: "and_simplify",
+        aliases: &[],
+        summary: "Simplifies a conjunction.",
+        conclusion: "(cl (= (and phi_1 ... phi_n) psi)), where psi is the conjunction after \
+            removing `true` and repeated conjuncts, or `false` if it is trivially false",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (and p true q) (and p q))) :rule and_simplify)",
+    },
+    RuleInfo {
+        name: "or_simplify",
+        aliases: &[],
+        summary: "Simplifies a disjunction.",
+        conclusion: "(cl (= (or phi_1 ... phi_n) psi)), where psi is the disjunction after \
+            removing `false` and repeated disjuncts, or `true` if it is trivially true",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (or p false q) (or p q))) :rule or_simplify)",
+    },
+    RuleInfo {
+        name: "not_simplify",
+        aliases: &[],
+        summary: "Simplifies a negation.",
+        conclusion: "(cl (= (not (not phi)) phi)), (cl (= (not false) true)) or (cl (= (not true) \
+            false))",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (not (not p)) p)) :rule not_simplify)",
+    },
+    RuleInfo {
+        name: "implies_simplify",
+        aliases: &[],
+        summary: "Simplifies an implication.",
+        conclusion: "(cl (= (=> phi psi) chi)), where chi is the simplified implication",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (=> (not p) (not q)) (=> q p))) :rule implies_simplify)",
+    },
+    RuleInfo {
+        name: "equiv_simplify",
+        aliases: &[],
+        summary: "Simplifies an equivalence between booleans.",
+        conclusion: "(cl (= (= phi psi) chi)), where chi is the simplified equivalence",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (= (not p) (not q)) (= p q))) :rule equiv_simplify)",
+    },
+    RuleInfo {
+        name: "bool_simplify",
+        aliases: &[],
+        summary: "Simplifies a combination of negations, implications, conjunctions and \
+            disjunctions.",
+        conclusion: "(cl (= phi psi)), where psi is phi after one of the simplifications \
+            described in the specification",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (not (=> p q)) (and p (not q)))) :rule bool_simplify)",
+    },
+    RuleInfo {
+        name: "qnt_simplify",
+        aliases: &[],
+        summary: "Simplifies a quantifier whose body is a boolean constant.",
+        conclusion: "(cl (= (Q (xs) phi) phi)), where Q is `forall` or `exists` and phi is `true` \
+            or `false`",
+        premises: "",
+        args: "",
+        definitions: "",
+        example: "(step t1 (cl (= (forall ((x Int)) false) false)) :rule qnt_simplify)",
+    },
+    RuleInfo {
+        name: "div_simplify",
+        aliases: &[],
+        summary: "Simplifies a division.",
+        conclusion: "(cl (= (div t u) v)), where v is the result of the division, if t equals u, \
+            u is 1, or both are constants",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (= (div a 1) a)) :rule div_simplify)",
+    },
+    RuleInfo {
+        name: "prod_simplify",
+        aliases: &[],
+        summary: "Simplifies a product by multiplying its constant factors.",
+        conclusion: "(cl (= (* t_1 ... t_n) u)), where u is the product after multiplying its \
+            constant factors, or 0 if any factor is 0",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (= (* 2 3 a) (* 6 a))) :rule prod_simplify)",
+    },
+    RuleInfo {
+        name: "minus_simplify",
+        aliases: &["unary_minus_simplify"],
+        summary: "Simplifies a subtraction or a negation.",
+        conclusion: "(cl (= (- t u) v)) or (cl (= (- t) v)), where v is the simplified term",
+        premises: "",
+        args: "",
+        definitions: REALS,
+        example: "(step t1 (cl (= (- x x) 0.0)) :rule minus_simplify)",
+    },
+    RuleInfo {
+        name: "sum_simplify",
+        aliases: &[],
+        summary: "Simplifies a sum by adding its constant terms.",
+        conclusion: "(cl (= (+ t_1 ... t_n) u)), where u is the sum after adding its constant \
+            terms and removing any zeros",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (= (+ 1 a 2) (+ 3 a))) :rule sum_simplify)",
+    },
+    RuleInfo {
+        name: "comp_simplify",
+        aliases: &[],
+        summary: "Simplifies an arithmetic comparison.",
+        conclusion: "(cl (= phi psi)), where phi is a comparison and psi is its simplified form",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (= (< 1 2) true)) :rule comp_simplify)",
+    },
+    RuleInfo {
+        name: "nary_elim",
+        aliases: &[],
+        summary: "Replaces an n-ary application of an associative or chainable operator by \
+            binary applications.",
+        conclusion: "(cl (= (op t_1 ... t_n) u)), where u is the equivalent term using only \
+            binary applications of op",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (= (= a b c) (and (= a b) (= b c)))) :rule nary_elim)",
+    },
+    RuleInfo {
+        name: "ac_simp",
+        aliases: &[],
+        summary: "Flattens nested conjunctions and disjunctions, and removes repeated arguments.",
+        conclusion: "(cl (= phi psi)), where psi is phi after flattening and removing duplicates",
+        premises: "",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl (= (and (and p q) (and q r)) (and p q r))) :rule ac_simp)",
+    },
+    RuleInfo {
+        name: "bfun_elim",
+        aliases: &[],
+        summary: "Eliminates boolean arguments of functions and boolean quantified variables.",
+        conclusion: "(cl psi), where psi is the premise after the eliminations",
+        premises: "phi",
+        args: "",
+        definitions: "(declare-fun P (Bool) Bool)",
+        example: "(assume h1 (forall ((x Bool)) (P x)))\n(step t2 (cl (and (P false) (P true))) \
+            :rule bfun_elim :premises (h1))",
+    },
+    RuleInfo {
+        name: "bind",
+        aliases: &[],
+        summary: "Renames the bound variables of a quantifier.",
+        conclusion: "(cl (= (Q (xs) phi) (Q (ys) psi)))",
+        premises: "a subproof whose context renames each x_i to y_i, and that concludes (cl (= \
+            phi psi))",
+        args: "",
+        definitions: "(declare-fun P (Int) Bool)",
+        example: "(anchor :step t1 :args ((y Int) (:= x y)))\n(step t1.t1 (cl (= (P x) (P y))) \
+            :rule refl)\n(step t1 (cl (= (forall ((x Int)) (P x)) (forall ((y Int)) (P y)))) :rule \
+            bind)",
+    },
+    RuleInfo {
+        name: "qnt_cnf",
+        aliases: &[],
+        summary: "Puts the body of a universal quantifier in clausal normal form.",
+        conclusion: "(cl (or (not (forall (xs) phi)) (forall (ys) psi))), where psi is one of the \
+            clauses of the clausal normal form of phi",
+        premises: "",
+        args: "",
+        definitions: "",
+        example: "(step t1 (cl (or (not (forall ((x Bool)) x)) (forall ((x Bool)) x))) :rule \
+            qnt_cnf)",
+    },
+    RuleInfo {
+        name: "subproof",
+        aliases: &[],
+        summary: "Concludes a subproof, discharging its local assumptions.",
+        conclusion: "(cl (not phi_1) ... (not phi_n) psi_1 ... psi_m)",
+        premises: "a subproof whose local assumptions are phi_1, ..., phi_n, and whose last step \
+            concludes (cl psi_1 ... psi_m)",
+        args: "",
+        definitions: BOOLS,
+        example: "(anchor :step t1)\n(assume t1.h1 p)\n(step t1.t2 (cl q) :rule hole)\n(step t1 \
+            (cl (not p) q) :rule subproof :discharge (t1.h1))",
+    },
+    RuleInfo {
+        name: "let",
+        aliases: &[],
+        summary: "Eliminates a `let` term.",
+        conclusion: "(cl (= (let ((x_1 t_1) ... (x_n t_n)) u) u'))",
+        premises: "equalities (= t_i s_i) for the bound values that are not equal to the value \
+            given to x_i by the context, followed by a subproof whose context maps each x_i to \
+            s_i, and that concludes (cl (= u u'))",
+        args: "",
+        definitions: "(declare-fun a () Int)\n(declare-fun P (Int) Bool)",
+        example: "(anchor :step t1 :args ((:= x a)))\n(step t1.t1 (cl (= (P x) (P a))) :rule \
+            refl)\n(step t1 (cl (= (let ((x a)) (P x)) (P a))) :rule let)",
+    },
+    RuleInfo {
+        name: "onepoint",
+        aliases: &[],
+        summary: "Eliminates quantified variables that are equal to a term, using the one-point \
+            rule.",
+        conclusion: "(cl (= (Q (xs) phi) (Q (ys) psi))), where ys are the variables in xs that \
+            were not eliminated",
+        premises: "a subproof whose context maps each eliminated variable to the term it equals, \
+            and that concludes (cl (= phi psi))",
+        args: "",
+        definitions: "(declare-fun p () Bool)",
+        example: "(anchor :step t1 :args ((t Int) (:= x t)))\n(step t1.t1 (cl (= (=> (= x t) p) \
+            (=> (= t t) p))) :rule hole)\n(step t1 (cl (= (forall ((x Int)) (=> (= x t) p)) (=> \
+            (= t t) p))) :rule onepoint)",
+    },
+    RuleInfo {
+        name: "sko_ex",
+        aliases: &[],
+        summary: "Skolemizes an existential quantifier using `choice` terms.",
+        conclusion: "(cl (= (exists (xs) phi) psi))",
+        premises: "a subproof whose context maps each x_i to the corresponding `choice` term, and \
+            that concludes (cl (= phi psi))",
+        args: "",
+        definitions: "(declare-fun P (Int) Bool)",
+        example: "(anchor :step t1 :args ((:= x (choice ((x Int)) (P x)))))\n(step t1.t1 (cl (= \
+            (P x) (P (choice ((x Int)) (P x))))) :rule hole)\n(step t1 (cl (= (exists ((x Int)) \
+            (P x)) (P (choice ((x Int)) (P x))))) :rule sko_ex)",
+    },
+    RuleInfo {
+        name: "sko_forall",
+        aliases: &[],
+        summary: "Skolemizes a universal quantifier using `choice` terms.",
+        conclusion: "(cl (= (forall (xs) phi) psi))",
+        premises: "a subproof whose context maps each x_i to the corresponding `choice` term, and \
+            that concludes (cl (= phi psi))",
+        args: "",
+        definitions: "(declare-fun P (Int) Bool)",
+        example: "(anchor :step t1 :args ((:= x (choice ((x Int)) (not (P x))))))\n(step t1.t1 \
+            (cl (= (P x) (P (choice ((x Int)) (not (P x)))))) :rule hole)\n(step t1 (cl (= \
+            (forall ((x Int)) (P x)) (P (choice ((x Int)) (not (P x)))))) :rule sko_forall)",
+    },
+    RuleInfo {
+        name: "reordering",
+        aliases: &[],
+        summary: "Changes the order of the literals in a clause.",
+        conclusion: "a permutation of the premise clause",
+        premises: "a clause",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl p q r) :rule hole)\n(step t2 (cl r p q) :rule reordering :premises \
+            (t1))",
+    },
+    RuleInfo {
+        name: "symm",
+        aliases: &[],
+        summary: "Symmetry of equality.",
+        conclusion: "(cl (= u t))",
+        premises: "(= t u)",
+        args: "",
+        definitions: SORT_T,
+        example: "(assume h1 (= a b))\n(step t2 (cl (= b a)) :rule symm :premises (h1))",
+    },
+    RuleInfo {
+        name: "not_symm",
+        aliases: &[],
+        summary: "Symmetry of disequality.",
+        conclusion: "(cl (not (= u t)))",
+        premises: "(not (= t u))",
+        args: "",
+        definitions: SORT_T,
+        example: "(assume h1 (not (= a b)))\n(step t2 (cl (not (= b a))) :rule not_symm :premises \
+            (h1))",
+    },
+    RuleInfo {
+        name: "eq_symmetric",
+        aliases: &[],
+        summary: "Symmetry of equality, as a tautology.",
+        conclusion: "(cl (not (= u t)) (= t u))",
+        premises: "",
+        args: "",
+        definitions: SORT_T,
+        example: "(step t1 (cl (not (= b a)) (= a b)) :rule eq_symmetric)",
+    },
+    RuleInfo {
+        name: "or_intro",
+        aliases: &[],
+        summary: "Adds literals to the end of a clause. This is the same as `weakening`.",
+        conclusion: "(cl phi_1 ... phi_n psi_1 ... psi_m)",
+        premises: "(cl phi_1 ... phi_n)",
+        args: "",
+        definitions: BOOLS,
+        example: "(step t1 (cl p q) :rule hole)\n(step t2 (cl p q r) :rule or_intro :premises \
+            (t1))",
+    },
+    RuleInfo {
+        name: "bind_let",
+        aliases: &[],
+        summary: "Congruence of `let` terms whose bound values are the same.",
+        conclusion: "(cl (= (let ((x_1 t_1) ... (x_n t_n)) u) (let ((x_1 t_1) ... (x_n t_n)) u')))",
+        premises: "a subproof that concludes (cl (= u u'))",
+        args: "",
+        definitions: "",
+        example: "(anchor :step t1 :args ((x Int) (y Int)))\n(step t1.t1 (cl (= x y)) :rule \
+            hole)\n(step t1 (cl (= (let ((a 0)) x) (let ((a 0)) y))) :rule bind_let)",
+    },
+    RuleInfo {
+        name: "la_mult_pos",
+        aliases: &[],
+        summary: "Multiplying both sides of a comparison by a positive number preserves it.",
+        conclusion: "(cl (=> (and (> m 0) (op t u)) (op (* m t) (* m u))))",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (=> (and (> 2 0) (> a b)) (> (* 2 a) (* 2 b)))) :rule la_mult_pos)",
+    },
+    RuleInfo {
+        name: "la_mult_neg",
+        aliases: &[],
+        summary: "Multiplying both sides of a comparison by a negative number reverses it.",
+        conclusion: "(cl (=> (and (< m 0) (op t u)) (op' (* m t) (* m u)))), where op' is the \
+            reverse of op",
+        premises: "",
+        args: "",
+        definitions: INTS,
+        example: "(step t1 (cl (=> (and (< (- 2) 0) (>= a b)) (<= (* (- 2) a) (* (- 2) b)))) \
+            :rule la_mult_neg)",
+    },
+    RuleInfo {
+        name: "hole",
+        aliases: &["trust"],
+        summary: "A step that is not justified by the proof. It is always accepted, but the proof \
+            is reported as holey.",
+        conclusion: "any clause",
+        premises: "any premises",
+        args: "any arguments",
+        definitions: BOOLS,
+        example: "(step t1 (cl p) :rule hole)",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{testing::run_tests, ProofChecker};
+
+    #[test]
+    fn test_examples_are_valid() {
+        for info in all_rules() {
+            // The `assume` command is not a rule, and the arithmetic rules are only available with
+            // the `arith` feature
+            if info.name != "assume" && ProofChecker::get_rule(info.name, false).is_none() {
+                continue;
+            }
+            run_tests(
+                info.definitions,
+                &[],
+                &[(info.name, &[(info.example, true)])],
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_rule_info() {
+        assert_eq!("resolution", get_rule_info("th_resolution").unwrap().name);
+        assert_eq!("hole", get_rule_info("trust").unwrap().name);
+        assert!(get_rule_info("not_a_rule").is_none());
+
+        for info in all_rules() {
+            assert!(!info.summary.is_empty() && !info.conclusion.is_empty());
+            assert!(
+                info.example.contains(&format!(":rule {}", info.name)) || info.name == "assume"
+            );
+        }
+    }
+}
@@ -1,9 +1,9 @@
 use super::{
     assert_clause_len, assert_deep_eq_is_expected, assert_eq, assert_is_expected,
-    assert_num_premises, assert_operation_len, get_premise_term, CheckerError, EqualityError,
-    RuleArgs, RuleResult,
+    assert_num_premises, assert_operation_len, get_premise_term, match_xor, CheckerError,
+    EqualityError, RuleArgs, RuleResult,
 };
-use crate::ast::*;
+use crate::{ast::*, checker::error::TautologyError};
 use ahash::AHashMap;
 
 pub fn distinct_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
@@ -23,7 +23,7 @@ pub fn distinct_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult
         }
         // If there are more than two boolean arguments to the distinct operator, the
         // second term must be `false`
-        args if *pool.sort(&args[0]) == Sort::Bool => {
+        args if **pool.sort(&args[0]) == Sort::Bool => {
             if second_term.is_bool_false() {
                 Ok(())
             } else {
@@ -120,48 +120,48 @@ pub fn not_and(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn xor1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn xor1(RuleArgs { conclusion, premises, pool, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
     let premise_term = get_premise_term(&premises[0])?;
-    let (phi_1, phi_2) = match_term_err!((xor phi_1 phi_2) = premise_term)?;
+    let (phi_1, phi_2) = match_xor(pool, premise_term)?;
 
-    assert_eq(phi_1, &conclusion[0])?;
-    assert_eq(phi_2, &conclusion[1])
+    assert_eq(&phi_1, &conclusion[0])?;
+    assert_eq(&phi_2, &conclusion[1])
 }
 
-pub fn xor2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn xor2(RuleArgs { conclusion, premises, pool, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
     let premise_term = get_premise_term(&premises[0])?;
-    let (phi_1, phi_2) = match_term_err!((xor phi_1 phi_2) = premise_term)?;
+    let (phi_1, phi_2) = match_xor(pool, premise_term)?;
 
-    assert_eq(phi_1, conclusion[0].remove_negation_err()?)?;
-    assert_eq(phi_2, conclusion[1].remove_negation_err()?)
+    assert_eq(&phi_1, conclusion[0].remove_negation_err()?)?;
+    assert_eq(&phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn not_xor1(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_xor1(RuleArgs { conclusion, premises, pool, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
     let premise_term = get_premise_term(&premises[0])?;
-    let (phi_1, phi_2) = match_term_err!((not (xor phi_1 phi_2)) = premise_term)?;
+    let (phi_1, phi_2) = match_xor(pool, premise_term.remove_negation_err()?)?;
 
-    assert_eq(phi_1, &conclusion[0])?;
-    assert_eq(phi_2, conclusion[1].remove_negation_err()?)
+    assert_eq(&phi_1, &conclusion[0])?;
+    assert_eq(&phi_2, conclusion[1].remove_negation_err()?)
 }
 
-pub fn not_xor2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+pub fn not_xor2(RuleArgs { conclusion, premises, pool, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
     assert_clause_len(conclusion, 2)?;
 
     let premise_term = get_premise_term(&premises[0])?;
-    let (phi_1, phi_2) = match_term_err!((not (xor phi_1 phi_2)) = premise_term)?;
+    let (phi_1, phi_2) = match_xor(pool, premise_term.remove_negation_err()?)?;
 
-    assert_eq(phi_1, conclusion[0].remove_negation_err()?)?;
-    assert_eq(phi_2, &conclusion[1])
+    assert_eq(&phi_1, conclusion[0].remove_negation_err()?)?;
+    assert_eq(&phi_2, &conclusion[1])
 }
 
 pub fn implies(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
@@ -196,64 +196,30 @@ pub fn not_implies2(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResu
 }
 
 pub fn nary_elim(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
-    /// The three possible cases for n-ary operators: chainable, right associative and left
-    /// associative
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-    enum Case {
-        Chainable,
-        RightAssoc,
-        LeftAssoc,
-    }
-
-    /// A function to expand terms that fall in the right or left associative cases. For example,
-    /// the term `(=> p q r s)` will be expanded into the term `(=> p (=> q (=> r s)))`.
-    fn expand_assoc(pool: &mut TermPool, op: Operator, args: &[Rc<Term>], case: Case) -> Rc<Term> {
-        let (head, tail) = match args {
-            [] => unreachable!(),
-            [t] => return t.clone(),
-
-            // The "head" term will be the first or last term in `args`, depending on if the
-            // operator is right or left associative
-            [first, rest @ ..] if case == Case::RightAssoc => (first, rest),
-            [rest @ .., last] => (last, rest),
-        };
-
-        // Note: if the argument list if very long, this may overflow the stack
-        let nested = expand_assoc(pool, op, tail, case);
-
-        let new_args = match case {
-            Case::RightAssoc => vec![head.clone(), nested],
-            Case::LeftAssoc => vec![nested, head.clone()],
-            Case::Chainable => unreachable!(),
-        };
-        pool.add(Term::Op(op, new_args))
-    }
-
     assert_clause_len(conclusion, 1)?;
 
     let (original, result) = match_term_err!((= o r) = &conclusion[0])?;
 
     let (op, args) = match original.as_ref() {
-        Term::Op(op, args) if args.len() >= 2 => (op, args),
-        _ => return Err(CheckerError::NotValidNaryTerm(original.clone())),
+        Term::Op(op, args) if args.len() >= 2 => (*op, args),
+        _ => return Err(TautologyError::NotValidNaryTerm(original.clone()).into()),
     };
 
-    let case = match op {
-        Operator::Equals => Case::Chainable,
-        Operator::Add | Operator::Sub | Operator::Mult => Case::LeftAssoc,
-        Operator::Implies => Case::RightAssoc,
-        _ => return Err(CheckerError::NotValidNaryTerm(original.clone())),
+    // Of the left associative operators, only the arithmetic ones are handled by this rule
+    let attribute = match (op, op.nary_attribute()) {
+        (Operator::Add | Operator::Sub | Operator::Mult, Some(a))
+        | (_, Some(a @ (NaryAttribute::Chainable | NaryAttribute::RightAssoc))) => a,
+        _ => return Err(TautologyError::NotValidNaryTerm(original.clone()).into()),
     };
 
-    let expected = match case {
-        Case::Chainable => {
-            let and_args: Vec<_> = args
-                .windows(2)
-                .map(|args| pool.add(Term::Op(*op, args.to_vec())))
-                .collect();
-            pool.add(Term::Op(Operator::And, and_args))
+    // If the operator is applied to only two arguments, there is nothing to expand, but a
+    // chainable operator still results in a conjunction with a single argument
+    let expected = match expand_nary(pool, op, args) {
+        Some(expanded) => expanded,
+        None if attribute == NaryAttribute::Chainable => {
+            pool.add(Term::Op(Operator::And, vec![original.clone()]))
         }
-        assoc_case => expand_assoc(pool, *op, args, assoc_case),
+        None => original.clone(),
     };
     assert_is_expected(result, expected)
 }
@@ -266,7 +232,7 @@ fn bfun_elim_first_step(
     acc: &mut Vec<Rc<Term>>,
 ) -> Result<(), SubstitutionError> {
     let var = match bindigns {
-        [.., var] if var.1.as_sort() == Some(&Sort::Bool) => pool.add(var.clone().into()),
+        [.., var] if *var.1 == Sort::Bool => pool.add(var.clone().into()),
         [rest @ .., _] => return bfun_elim_first_step(pool, rest, term, acc),
         [] => {
             acc.push(term.clone());
@@ -290,7 +256,9 @@ fn bfun_elim_second_step(
     processed: usize,
 ) -> Rc<Term> {
     for i in processed..args.len() {
-        if *pool.sort(&args[i]) == Sort::Bool && !args[i].is_bool_false() && !args[i].is_bool_true()
+        if **pool.sort(&args[i]) == Sort::Bool
+            && !args[i].is_bool_false()
+            && !args[i].is_bool_true()
         {
             let mut ite_args = Vec::with_capacity(3);
             ite_args.push(args[i].clone());
@@ -335,7 +303,7 @@ fn apply_bfun_elim(
                 .collect::<Result<_, _>>()?;
             pool.add(Term::Op(*op, args))
         }
-        Term::Quant(q, bindings, inner) => {
+        Term::Quant(q, bindings, inner, _) => {
             let op = match q {
                 Quantifier::Forall => Operator::And,
                 Quantifier::Exists => Operator::Or,
@@ -352,13 +320,18 @@ fn apply_bfun_elim(
 
             let new_bindings: Vec<_> = bindings
                 .iter()
+                .filter(|(_, sort)| **sort != Sort::Bool)
                 .cloned()
-                .filter(|(_, sort)| *sort.as_sort().unwrap() != Sort::Bool)
                 .collect();
             if new_bindings.is_empty() {
                 op_term
             } else {
-                pool.add(Term::Quant(*q, BindingList(new_bindings), op_term))
+                pool.add(Term::Quant(
+                    *q,
+                    BindingList(new_bindings),
+                    op_term,
+                    Vec::new(),
+                ))
             }
         }
         Term::Choice(var, inner) => {
@@ -621,10 +594,17 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(assume h1 (xor p q))
                 (step t2 (cl p q) :rule xor1 :premises (h1))": true,
+
+                "(assume h1 (xor p q r))
+                (step t2 (cl (xor p q) r) :rule xor1 :premises (h1))": true,
+
+                "(assume h1 (distinct p q))
+                (step t2 (cl p q) :rule xor1 :premises (h1))": true,
             }
             "Premise is of the wrong form" {
                 "(assume h1 (and p q))
@@ -646,6 +626,7 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(assume h1 (xor p q))
@@ -653,6 +634,9 @@ mod tests {
 
                 "(assume h1 (xor (not p) (not q)))
                 (step t2 (cl (not (not p)) (not (not q))) :rule xor2 :premises (h1))": true,
+
+                "(assume h1 (xor p q r))
+                (step t2 (cl (not (xor p q)) (not r)) :rule xor2 :premises (h1))": true,
             }
             "Premise is of the wrong form" {
                 "(assume h1 (and p q))
@@ -674,6 +658,7 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(assume h1 (not (xor p q)))
@@ -681,6 +666,12 @@ mod tests {
 
                 "(assume h1 (not (xor p (not q))))
                 (step t2 (cl p (not (not q))) :rule not_xor1 :premises (h1))": true,
+
+                "(assume h1 (not (xor p q r)))
+                (step t2 (cl (xor p q) (not r)) :rule not_xor1 :premises (h1))": true,
+
+                "(assume h1 (not (distinct p q)))
+                (step t2 (cl p (not q)) :rule not_xor1 :premises (h1))": true,
             }
             "Premise is of the wrong form" {
                 "(assume h1 (xor p q))
@@ -705,6 +696,7 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(assume h1 (not (xor p q)))
@@ -712,6 +704,12 @@ mod tests {
 
                 "(assume h1 (not (xor (not p) q)))
                 (step t2 (cl (not (not p)) q) :rule not_xor2 :premises (h1))": true,
+
+                "(assume h1 (not (xor p q r)))
+                (step t2 (cl (not (xor p q)) r) :rule not_xor2 :premises (h1))": true,
+
+                "(assume h1 (not (distinct p q)))
+                (step t2 (cl (not p) q) :rule not_xor2 :premises (h1))": true,
             }
             "Premise is of the wrong form" {
                 "(assume h1 (xor p q))
@@ -1,5 +1,6 @@
 use super::{
-    assert_clause_len, assert_num_premises, get_premise_term, CheckerError, RuleArgs, RuleResult,
+    assert_clause_len, assert_num_premises, equality_graph::EqualityGraph, get_premise_term,
+    CheckerError, RuleArgs, RuleResult,
 };
 use crate::{ast::*, checker::error::CongruenceError};
 
@@ -74,15 +75,27 @@ where
         );
     }
 
-    for (i, (t, u)) in premises.into_iter().enumerate() {
+    for (i, &(t, u)) in premises.iter().enumerate() {
         let (f, g) = (&f_args[i], &g_args[i]);
-        rassert!(
-            (f, g) == (t, u) || (f, g) == (u, t),
+        if (f, g) == (t, u) || (f, g) == (u, t) {
+            continue;
+        }
+
+        // If the premise doesn't justify the arguments, we check whether the premises justify them
+        // at all, to tell apart premises given in the wrong order from missing equalities
+        let mut graph = EqualityGraph::new();
+        for &(t, u) in &premises {
+            graph.add(t, u);
+        }
+        return Err(if graph.are_equal(f, g) {
             CongruenceError::PremiseDoesntJustifyArgs {
                 args: (f.clone(), g.clone()),
-                premise: (t.clone(), u.clone())
+                premise: (t.clone(), u.clone()),
             }
-        );
+        } else {
+            CongruenceError::NoPath(f.clone(), g.clone())
+        }
+        .into());
     }
     Ok(())
 }
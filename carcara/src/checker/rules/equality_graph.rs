@@ -0,0 +1,138 @@
+//! A small equality graph, used by the rules that reason about chains of equalities. As equalities
+//! are added, their terms are merged in a union-find structure, so checking whether two terms are
+//! equal is cheap. When the chain of equalities that connects two terms is needed, for example to
+//! elaborate a step, it is found with a breadth-first search over the equalities.
+
+use crate::ast::{Rc, Term};
+use ahash::AHashMap;
+use std::collections::VecDeque;
+
+#[derive(Debug, Default)]
+pub struct EqualityGraph<'a> {
+    indices: AHashMap<&'a Rc<Term>, usize>,
+    parents: Vec<usize>,
+
+    /// The indices of the two terms in each equality, in the order they were added.
+    equalities: Vec<(usize, usize)>,
+
+    /// For each term, the equalities in which it appears, and the index of the other term in each
+    /// of them.
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl<'a> EqualityGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn term_index(&mut self, term: &'a Rc<Term>) -> usize {
+        let next = self.parents.len();
+        let index = *self.indices.entry(term).or_insert(next);
+        if index == next {
+            self.parents.push(next);
+            self.edges.push(Vec::new());
+        }
+        index
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    /// Adds the equality `(= a b)` to the graph. Equalities are numbered in the order they are
+    /// added, starting at zero.
+    pub fn add(&mut self, a: &'a Rc<Term>, b: &'a Rc<Term>) {
+        let (i, j) = (self.term_index(a), self.term_index(b));
+        let index = self.equalities.len();
+        self.equalities.push((i, j));
+        self.edges[i].push((index, j));
+        self.edges[j].push((index, i));
+        let root = self.find(i);
+        self.parents[root] = self.find(j);
+    }
+
+    /// Returns `true` if `a` and `b` are equal, given the equalities added so far.
+    pub fn are_equal(&mut self, a: &Rc<Term>, b: &Rc<Term>) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.indices.get(a), self.indices.get(b)) {
+            (Some(&i), Some(&j)) => self.find(i) == self.find(j),
+            _ => false,
+        }
+    }
+
+    /// Returns the shortest chain of equalities that proves that `a` and `b` are equal, or `None`
+    /// if they are not equal. Each element is the index of an equality, and whether it must be
+    /// flipped to continue the chain. The chain starts at `a` and ends at `b`, and is empty if they
+    /// are the same term.
+    pub fn explain(&self, a: &Rc<Term>, b: &Rc<Term>) -> Option<Vec<(usize, bool)>> {
+        if a == b {
+            return Some(Vec::new());
+        }
+        let (&start, &end) = (self.indices.get(a)?, self.indices.get(b)?);
+
+        // For each term reached in the search, the equality through which it was reached, and the
+        // term on the other side of that equality
+        let mut reached_by: Vec<Option<(usize, usize)>> = vec![None; self.parents.len()];
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            if current == end {
+                break;
+            }
+            for &(equality, next) in &self.edges[current] {
+                if next != start && reached_by[next].is_none() {
+                    reached_by[next] = Some((equality, current));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut chain = Vec::new();
+        let mut current = end;
+        while current != start {
+            let (equality, previous) = reached_by[current]?;
+            chain.push((equality, self.equalities[equality].0 != previous));
+            current = previous;
+        }
+        chain.reverse();
+        Some(chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::TermPool, parser::tests::parse_terms};
+
+    #[test]
+    fn test_equality_graph() {
+        let mut pool = TermPool::new();
+        let [a, b, c, d, e] = parse_terms(
+            &mut pool,
+            "(declare-sort T 0)
+            (declare-fun a () T) (declare-fun b () T) (declare-fun c () T)
+            (declare-fun d () T) (declare-fun e () T)",
+            ["a", "b", "c", "d", "e"],
+        );
+        let mut graph = EqualityGraph::new();
+        graph.add(&a, &b);
+        graph.add(&b, &e);
+        graph.add(&c, &b);
+        graph.add(&a, &a);
+
+        assert!(graph.are_equal(&a, &c));
+        assert!(graph.are_equal(&e, &c));
+        assert!(graph.are_equal(&d, &d));
+        assert!(!graph.are_equal(&a, &d));
+
+        assert_eq!(graph.explain(&a, &c), Some(vec![(0, false), (2, true)]));
+        assert_eq!(graph.explain(&c, &e), Some(vec![(2, false), (1, false)]));
+        assert_eq!(graph.explain(&d, &d), Some(Vec::new()));
+        assert_eq!(graph.explain(&a, &d), None);
+    }
+}
@@ -4,9 +4,12 @@ use super::{
     assert_clause_len, assert_eq, assert_num_premises, get_premise_term, CheckerError,
     EqualityError, RuleArgs, RuleResult,
 };
-use crate::{ast::*, checker::rules::assert_operation_len};
+use crate::checker::error::ReorderingError;
 use ahash::AHashSet;
 
+#[cfg(feature = "arith")]
+use crate::{ast::*, checker::rules::assert_operation_len};
+
 pub fn reordering(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     assert_num_premises(premises, 1)?;
 
@@ -16,9 +19,9 @@ pub fn reordering(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult
     let premise_set: AHashSet<_> = premise.iter().collect();
     let conclusion_set: AHashSet<_> = conclusion.iter().collect();
     if let Some(&t) = premise_set.difference(&conclusion_set).next() {
-        Err(CheckerError::ReorderingMissingTerm(t.clone()))
+        Err(ReorderingError::MissingTerm(t.clone()).into())
     } else if let Some(&t) = conclusion_set.difference(&premise_set).next() {
-        Err(CheckerError::ReorderingExtraTerm(t.clone()))
+        Err(ReorderingError::ExtraTerm(t.clone()).into())
     } else {
         Ok(())
     }
@@ -110,16 +113,19 @@ pub fn bind_let(
     assert_eq(right, phi_prime)
 }
 
+#[cfg(feature = "arith")]
 pub fn la_mult_pos(args: RuleArgs) -> RuleResult {
     la_mult_generic(args.conclusion, true)
 }
 
+#[cfg(feature = "arith")]
 pub fn la_mult_neg(args: RuleArgs) -> RuleResult {
     la_mult_generic(args.conclusion, false)
 }
 
+#[cfg(feature = "arith")]
 fn la_mult_generic(conclusion: &[Rc<Term>], is_pos: bool) -> RuleResult {
-    use rug::Rational;
+    use crate::num::Rational;
 
     fn match_comparison_term(
         op: Operator,
@@ -328,6 +334,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn la_mult_pos() {
         test_cases! {
             definitions = "
@@ -348,6 +355,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn la_mult_neg() {
         test_cases! {
             definitions = "
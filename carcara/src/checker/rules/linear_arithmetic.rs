@@ -1,10 +1,13 @@
 use super::{assert_clause_len, assert_eq, assert_num_args, RuleArgs, RuleResult};
 use crate::{
     ast::*,
-    checker::error::{CheckerError, LinearArithmeticError},
+    checker::{
+        error::{CheckerError, DisplayLinearComb, LinearArithmeticError},
+        TraceEntry,
+    },
+    num::{Integer, NegAssign, Rational},
 };
 use ahash::AHashMap;
-use rug::{ops::NegAssign, Integer, Rational};
 
 pub fn la_rw_eq(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
@@ -18,14 +21,21 @@ pub fn la_rw_eq(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_eq(u_2, u_3)
 }
 
-/// Takes a disequality term and returns its negation, represented by an operator and two linear
-/// combinations.
+/// Takes a disequality term and returns its negation, represented by a list of disequalities, each
+/// one given by an operator and two linear combinations.
 /// The disequality can be:
 ///
 /// - An application of the `<`, `>`, `<=` or `>=` operators
 /// - The negation of an application of one of these operators
 /// - The negation of an application of the `=` operator
-fn negate_disequality(term: &Rc<Term>) -> Result<(Operator, LinearComb, LinearComb), CheckerError> {
+///
+/// Since these operators are chainable, the negation of a term like `(not (< a b c))` is the
+/// conjunction of the comparisons between each pair of adjacent arguments, so it results in more
+/// than one disequality. The negation of a chained comparison that is not negated would be a
+/// disjunction, so it is not supported.
+fn negate_disequality(
+    term: &Rc<Term>,
+) -> Result<Vec<(Operator, LinearComb, LinearComb)>, CheckerError> {
     use Operator::*;
 
     fn negate_operator(op: Operator) -> Option<Operator> {
@@ -38,24 +48,30 @@ fn negate_disequality(term: &Rc<Term>) -> Result<(Operator, LinearComb, LinearCo
         })
     }
 
-    fn inner(term: &Rc<Term>) -> Option<(Operator, &[Rc<Term>])> {
+    fn inner(term: &Rc<Term>) -> Option<(Operator, &[Rc<Term>], bool)> {
         if let Some(Term::Op(op, args)) = term.remove_negation().map(Rc::as_ref) {
             if matches!(op, GreaterEq | LessEq | GreaterThan | LessThan | Equals) {
-                return Some((*op, args));
+                return Some((*op, args, true));
             }
         } else if let Term::Op(op, args) = term.as_ref() {
-            return Some((negate_operator(*op)?, args));
+            return Some((negate_operator(*op)?, args, false));
         }
         None
     }
 
-    let (op, args) =
+    let (op, args, is_negated) =
         inner(term).ok_or_else(|| LinearArithmeticError::InvalidDisequalityOp(term.clone()))?;
 
-    match args {
-        [a, b] => Ok((op, LinearComb::from_term(a), LinearComb::from_term(b))),
-        _ => Err(LinearArithmeticError::TooManyArgsInDisequality(term.clone()).into()),
+    if args.len() > 2 && !is_negated {
+        return Err(LinearArithmeticError::TooManyArgsInDisequality(term.clone()).into());
     }
+    Ok(args
+        .windows(2)
+        .map(|pair| {
+            let [a, b] = [&pair[0], &pair[1]].map(LinearComb::from_term);
+            (op, a, b)
+        })
+        .collect())
 }
 
 /// A linear combination, represented by a hash map from non-constant terms to their coefficients,
@@ -185,7 +201,7 @@ impl LinearComb {
         }
 
         let mut result = self.1.numer().clone();
-        for (_, coeff) in self.0.iter() {
+        for (_, coeff) in &self.0 {
             if result == 1 {
                 return Integer::from(1);
             }
@@ -259,7 +275,7 @@ fn strengthen(op: Operator, disequality: &mut LinearComb, a: &Rational) -> Opera
     }
 }
 
-pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
+pub fn la_generic(RuleArgs { conclusion, args, trace, .. }: RuleArgs) -> RuleResult {
     assert_num_args(args, conclusion.len())?;
 
     let args: Vec<_> = args
@@ -271,13 +287,17 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
         })
         .collect::<Result<_, _>>()?;
 
-    let final_disequality = conclusion
-        .iter()
-        .zip(args)
-        .map(|(phi, a)| -> Result<_, CheckerError> {
-            // Steps 1 and 2: Negate the disequality
-            let (mut op, s1, s2) = negate_disequality(phi)?;
-
+    let mut disequalities = Vec::new();
+    for (phi, a) in conclusion.iter().zip(args) {
+        trace.record(|| TraceEntry::Coefficient {
+            literal: phi.clone(),
+            coefficient: a.clone(),
+        });
+
+        // Steps 1 and 2: Negate the disequality. If the literal is a chained comparison, this
+        // results in more than one disequality, and all of them are multiplied by the same
+        // coefficient
+        for (mut op, s1, s2) in negate_disequality(phi)? {
             // Step 3: Move all non constant terms to the left side, and the d terms to the right.
             // We move everything to the left side by subtracting s2 from s1
             let mut disequality = s1.sub(s2);
@@ -297,26 +317,32 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
 
             // Step 5: Multiply disequality by a
             let a = match op {
-                Operator::Equals => a,
-                _ => a.abs(),
+                Operator::Equals => a.clone(),
+                _ => a.clone().abs(),
             };
             disequality.mul(&a);
 
-            Ok((op, disequality))
-        })
-        .try_fold(
-            (Operator::Equals, LinearComb::new()),
-            |(acc_op, acc), item| -> Result<_, CheckerError> {
-                let (op, diseq) = item?;
-                let new_acc = acc.add(diseq);
-                let new_op = match (acc_op, op) {
-                    (_, Operator::GreaterEq) => Operator::GreaterEq,
-                    (Operator::Equals, Operator::GreaterThan) => Operator::GreaterThan,
-                    _ => acc_op,
-                };
-                Ok((new_op, new_acc))
-            },
-        )?;
+            disequalities.push((op, disequality));
+        }
+    }
+
+    let final_disequality = disequalities.into_iter().fold(
+        (Operator::Equals, LinearComb::new()),
+        |(acc_op, acc), (op, diseq)| {
+            let new_acc = acc.add(diseq);
+            let new_op = match (acc_op, op) {
+                (_, Operator::GreaterEq) => Operator::GreaterEq,
+                (Operator::Equals, Operator::GreaterThan) => Operator::GreaterThan,
+                _ => acc_op,
+            };
+            (new_op, new_acc)
+        },
+    );
+
+    trace.record(|| {
+        let (op, disequality) = &final_disequality;
+        TraceEntry::Disequality(DisplayLinearComb(op, disequality).to_string())
+    });
 
     let (op, LinearComb(left_side, right_side)) = &final_disequality;
 
@@ -337,7 +363,10 @@ pub fn la_generic(RuleArgs { conclusion, args, .. }: RuleArgs) -> RuleResult {
     // contradictory
     rassert!(
         left_side.is_empty() && !is_disequality_true,
-        LinearArithmeticError::DisequalityIsNotContradiction(*op, final_disequality.1),
+        LinearArithmeticError::DisequalityNotContradictory {
+            final_op: *op,
+            disequality: final_disequality.1,
+        },
     );
     Ok(())
 }
@@ -426,8 +455,13 @@ pub fn la_tautology(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     } else {
         // If the conclusion is of the first form, we apply steps 1 through 3 from `la_generic`
 
-        // Steps 1 and 2: Negate the disequality
-        let (mut op, s1, s2) = negate_disequality(&conclusion[0])?;
+        // Steps 1 and 2: Negate the disequality. Chained comparisons are not supported here
+        let mut negated = negate_disequality(&conclusion[0])?;
+        if negated.len() != 1 {
+            let err = LinearArithmeticError::TooManyArgsInDisequality(conclusion[0].clone());
+            return Err(err.into());
+        }
+        let (mut op, s1, s2) = negated.pop().unwrap();
 
         // Step 3: Move all non constant terms to the left side, and the d terms to the right.
         let mut disequality = s1.sub(s2);
@@ -447,13 +481,13 @@ pub fn la_tautology(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
             && (disequality.1 > 0 || op == Operator::GreaterThan && disequality.1 == 0);
         rassert!(
             is_disequality_true,
-            LinearArithmeticError::DisequalityIsNotTautology(op, disequality),
+            LinearArithmeticError::DisequalityNotTautological { final_op: op, disequality },
         );
         Ok(())
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "arith"))]
 mod tests {
     #[test]
     fn la_rw_eq() {
@@ -497,6 +531,9 @@ mod tests {
 
                 "(step t1 (cl (not (<= (- a b) (- c 1.0))) (<= (+ 1.0 (- a c)) b))
                     :rule la_generic :args (1.0 1.0))": true,
+
+                "(step t1 (cl (> (+ a (to_real 1)) a)) :rule la_generic :args (1.0))": true,
+                "(step t1 (cl (> (+ a (to_real (to_int 0.5))) a)) :rule la_generic :args (1.0))": false,
             }
             "Empty clause" {
                 "(step t1 (cl) :rule la_generic)": false,
@@ -525,6 +562,12 @@ mod tests {
                     (not (<= m 1))
                 ) :rule la_generic :args (1 1 1 1))": true,
             }
+            "Chained comparisons" {
+                "(step t1 (cl (not (< a b c)) (<= a c)) :rule la_generic :args (1.0 1.0))": true,
+                "(step t1 (cl (not (= a b c)) (= a c)) :rule la_generic :args (1.0 1.0))": false,
+                "(step t1 (cl (not (<= a b c)) (> a c)) :rule la_generic :args (1.0 1.0))": false,
+                "(step t1 (cl (< a b c) (>= a c)) :rule la_generic :args (1.0 1.0))": false,
+            }
         }
     }
 
@@ -1,6 +1,6 @@
 use super::{
     error::{CheckerError, EqualityError},
-    ContextStack, Elaborator,
+    ContextStack, Elaborator, StepTrace,
 };
 use crate::{
     ast::*,
@@ -28,6 +28,46 @@ pub struct RuleArgs<'a> {
     pub(super) discharge: &'a [&'a ProofCommand],
 
     pub(super) deep_eq_time: &'a mut Duration,
+
+    /// The entries of the trace of the step, which rules can use to record how they checked it.
+    pub(super) trace: &'a mut StepTrace,
+}
+
+impl<'a> RuleArgs<'a> {
+    /// The clause concluded by the step.
+    pub fn conclusion(&self) -> &'a [Rc<Term>] {
+        self.conclusion
+    }
+
+    /// The premises of the step.
+    pub fn premises(&self) -> &'a [Premise<'a>] {
+        self.premises
+    }
+
+    /// The arguments given to the rule.
+    pub fn args(&self) -> &'a [ProofArg] {
+        self.args
+    }
+
+    /// The term pool, which should be used to create any new terms.
+    pub fn pool(&mut self) -> &mut TermPool {
+        self.pool
+    }
+
+    /// If the step ends a subproof, the previous command in that subproof.
+    pub fn previous_command(&self) -> Option<Premise<'a>> {
+        self.previous_command
+    }
+
+    /// The commands discharged by the step.
+    pub fn discharge(&self) -> &'a [&'a ProofCommand] {
+        self.discharge
+    }
+
+    /// The trace of the step, where the rule may record how it was checked.
+    pub fn trace(&mut self) -> &mut StepTrace {
+        self.trace
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -60,12 +100,9 @@ fn get_premise_term<'a>(premise: &Premise<'a>) -> Result<&'a Rc<Term>, CheckerEr
     }
 }
 
-/// Asserts that the argument is true, and returns `None` otherwise. `rassert!(arg)` is identical
-/// to `to_option(arg)?`, but much more readable.
+/// Asserts that the argument is true, and returns the given error otherwise. The error is converted
+/// into a `CheckerError` with `?`, so it can also be one of the rule specific error types.
 macro_rules! rassert {
-    ($arg:expr) => {
-        $crate::checker::rules::to_option($arg)?
-    };
     ($arg:expr, $err:expr $(,)?) => {
         match $arg {
             true => Ok(()),
@@ -74,6 +111,32 @@ macro_rules! rassert {
     };
 }
 
+/// Matches a term of the form `(xor phi_1 ... phi_n)`, returning the two arguments of the equivalent
+/// binary `xor` term. Since `xor` is left associative, these are `(xor phi_1 ... phi_n-1)` and
+/// `phi_n`, which makes the parity of the whole term the parity of the first argument xor the
+/// last. A `distinct` term with two boolean arguments is also accepted, since it is equivalent to
+/// `xor`.
+fn match_xor(pool: &mut TermPool, term: &Rc<Term>) -> Result<(Rc<Term>, Rc<Term>), CheckerError> {
+    let args = match term.as_ref() {
+        Term::Op(Operator::Xor, args) if args.len() >= 2 => args,
+        Term::Op(Operator::Distinct, args)
+            if args.len() == 2 && **pool.sort(&args[0]) == Sort::Bool =>
+        {
+            args
+        }
+        _ => {
+            let pattern = "(xor phi_1 phi_2)";
+            return Err(CheckerError::TermOfWrongForm(pattern, term.clone()));
+        }
+    };
+    let (last, init) = args.split_last().unwrap();
+    let init = match init {
+        [phi] => phi.clone(),
+        _ => pool.add(Term::Op(Operator::Xor, init.to_vec())),
+    };
+    Ok((init, last.clone()))
+}
+
 fn assert_num_premises<T: Into<Range>>(premises: &[Premise], range: T) -> RuleResult {
     let range = range.into();
     if !range.contains(premises.len()) {
@@ -153,56 +216,44 @@ fn assert_is_bool_constant(got: &Rc<Term>, expected: bool) -> RuleResult {
     Ok(())
 }
 
-#[cfg(test)]
-fn run_tests(test_name: &str, definitions: &str, cases: &[(&str, bool)]) {
-    use crate::{
-        checker::{Config, ProofChecker},
-        parser::parse_instance,
-    };
-    use std::io::Cursor;
-
-    for (i, (proof, expected)) in cases.iter().enumerate() {
-        // This parses the definitions again for every case, which is not ideal
-        let (prelude, parsed, mut pool) = parse_instance(
-            Cursor::new(definitions),
-            Cursor::new(proof),
-            true,
-            false,
-            false,
-        )
-        .unwrap_or_else(|e| panic!("parser error during test \"{}\": {}", test_name, e));
-        let mut checker = ProofChecker::new(
-            &mut pool,
-            Config {
-                strict: false,
-                skip_unknown_rules: false,
-                is_running_test: true,
-                statistics: None,
-                check_lia_using_cvc5: true,
-            },
-            prelude,
-        );
-        let got = checker.check(&parsed).is_ok();
-        assert_eq!(
-            *expected, got,
-            "test case \"{}\" index {} failed",
-            test_name, i
-        );
-    }
-}
-
-#[cfg(test)]
+/// Declares test cases for one or more rules. The `definitions` are parsed once, and used as the
+/// problem prelude for every proof. Each proof is then checked, and the test fails if the result is
+/// different from the expected one. Optionally, rules that are not built into the checker can be
+/// given with `rules`, which allows crates that implement their own rules to test them using the
+/// same harness. See `checker::testing::run_tests` for details.
+///
+/// ```
+/// # use carcara::checker::{RuleArgs, RuleResult, error::CheckerError};
+/// fn empty(args: RuleArgs) -> RuleResult {
+///     if args.conclusion().is_empty() {
+///         Ok(())
+///     } else {
+///         Err(CheckerError::Unspecified)
+///     }
+/// }
+///
+/// carcara::test_cases! {
+///     definitions = "(declare-fun p () Bool)",
+///     rules = [("empty", empty)],
+///     "Simple working examples" {
+///         "(step t1 (cl) :rule empty)": true,
+///     }
+///     "Conclusion is not empty" {
+///         "(step t1 (cl p) :rule empty)": false,
+///     }
+/// }
+/// ```
+#[macro_export]
 macro_rules! test_cases {
     (
         definitions = $defs:expr,
+        $(rules = [$(($rule_name:expr, $rule:expr)),* $(,)?],)?
         $($name:literal { $($proof:literal: $exp:literal,)* } )*
     ) => {{
         let definitions: &str = $defs;
-        $({
-            let name: &str = $name;
-            let cases = [ $(($proof, $exp),)* ];
-            $crate::checker::rules::run_tests(name, definitions, &cases);
-        })*
+        let rules: &[(&str, $crate::checker::Rule)] = &[$($(($rule_name, $rule),)*)?];
+        let tests: &[(&str, &[(&str, bool)])] = &[$(($name, &[$(($proof, $exp),)*]),)*];
+        $crate::checker::testing::run_tests(definitions, rules, tests);
     }};
 }
 
@@ -210,7 +261,11 @@ macro_rules! test_cases {
 // macro is declared
 pub(super) mod clausification;
 pub(super) mod congruence;
+mod equality_graph;
 pub(super) mod extras;
+// `LinearComb` is part of `LinearArithmeticError`, so this module is always compiled, even though
+// its rules are only available with the `arith` feature
+#[cfg_attr(not(feature = "arith"), allow(dead_code))]
 pub(super) mod linear_arithmetic;
 pub(super) mod quantifier;
 pub(super) mod reflexivity;
@@ -28,7 +28,7 @@ pub fn forall_inst(
         .iter()
         .map(|arg| {
             let (arg_name, arg_value) = arg.as_assign()?;
-            let arg_sort = pool.add(Term::Sort(pool.sort(arg_value).clone()));
+            let arg_sort = pool.sort(arg_value).clone();
             rassert!(
                 bindings.remove(&(arg_name.clone(), arg_sort.clone())),
                 QuantifierError::NoBindingMatchesArg(arg_name.clone())
@@ -49,6 +49,7 @@ pub fn forall_inst(
     // Equalities may be reordered in the final term, so we need to use deep equality modulo
     // reordering
     let expected = substitution.apply(pool, original);
+    log::trace!("instantiated '{}' as '{}'", original, expected);
     assert_deep_eq_is_expected(substituted, expected, deep_eq_time)
 }
 
@@ -96,7 +97,7 @@ pub fn qnt_rm_unused(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult
     assert_eq(phi_1, phi_2)?;
 
     // Cloning here may be unnecessary
-    let free_vars = pool.free_vars(phi_1).clone();
+    let free_vars = phi_1.free_vars(pool).clone();
 
     let expected: Vec<_> = bindings_1
         .iter()
@@ -156,10 +157,10 @@ fn negation_normal_form(
     } else if let Some((quant, bindings, inner)) = term.unwrap_quant() {
         let quant = if polarity { quant } else { !quant };
         let inner = negation_normal_form(pool, inner, polarity, cache);
-        pool.add(Term::Quant(quant, bindings.clone(), inner))
+        pool.add(Term::Quant(quant, bindings.clone(), inner, Vec::new()))
     } else {
         match match_term!((= p q) = term) {
-            Some((left, right)) if *pool.sort(left) == Sort::Bool => {
+            Some((left, right)) if **pool.sort(left) == Sort::Bool => {
                 let a = negation_normal_form(pool, left, !polarity, cache);
                 let b = negation_normal_form(pool, right, polarity, cache);
                 let c = negation_normal_form(pool, right, !polarity, cache);
@@ -232,7 +233,7 @@ where
             let args = args.iter().map(|a| prenex_forall(pool, acc, a)).collect();
             pool.add(Term::Op(*op, args))
         }
-        Term::Quant(Quantifier::Forall, bindings, inner) => {
+        Term::Quant(Quantifier::Forall, bindings, inner, _) => {
             acc.extend(bindings.iter().cloned());
             prenex_forall(pool, acc, inner)
         }
@@ -304,7 +305,7 @@ pub fn qnt_cnf(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
         .ok_or_else(|| QuantifierError::ClauseDoesntAppearInCnf(phi_prime.clone()))?;
 
     // Cloning here may be unnecessary
-    let free_vars = pool.free_vars(selected_clause).clone();
+    let free_vars = selected_clause.free_vars(pool).clone();
 
     // While all bindings in `r_bindings` must also be in `new_bindings`, the same is not true in
     // the opposite direction. That is because some variables from the set may be omitted in the
@@ -496,6 +497,7 @@ mod tests {
                     Quantifier::Forall,
                     BindingList(bindings),
                     conjunctions,
+                    Vec::new(),
                 ))
             }
         }
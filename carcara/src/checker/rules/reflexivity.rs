@@ -1,5 +1,5 @@
-use super::{assert_clause_len, assert_eq, CheckerError, Elaborator, RuleArgs, RuleResult};
-use crate::ast::*;
+use super::{assert_clause_len, assert_eq, Elaborator, RuleArgs, RuleResult};
+use crate::{ast::*, checker::error::ReflexivityError};
 
 pub fn eq_reflexive(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
@@ -28,7 +28,7 @@ pub fn refl(
     }
 
     if context.is_empty() {
-        return Err(CheckerError::ReflexivityFailed(left.clone(), right.clone()));
+        return Err(ReflexivityError::NotEqual(left.clone(), right.clone()).into());
     }
 
     // In some cases, the substitution is only applied to the left or the right term, and in some
@@ -43,7 +43,7 @@ pub fn refl(
     };
     rassert!(
         result,
-        CheckerError::ReflexivityFailed(left.clone(), right.clone()),
+        ReflexivityError::NotEqualUnderContext(left.clone(), right.clone()),
     );
     Ok(())
 }
@@ -57,7 +57,7 @@ pub fn strict_refl(RuleArgs { conclusion, pool, context, .. }: RuleArgs) -> Rule
         return Ok(());
     }
     if context.is_empty() {
-        return Err(CheckerError::ReflexivityFailed(left.clone(), right.clone()));
+        return Err(ReflexivityError::NotEqual(left.clone(), right.clone()).into());
     }
 
     // This follows the same logic as the `refl` function, but without using alpha equivalence
@@ -68,7 +68,7 @@ pub fn strict_refl(RuleArgs { conclusion, pool, context, .. }: RuleArgs) -> Rule
     };
     rassert!(
         result,
-        CheckerError::ReflexivityFailed(left.clone(), right.clone()),
+        ReflexivityError::NotEqualUnderContext(left.clone(), right.clone()),
     );
     Ok(())
 }
@@ -173,7 +173,7 @@ pub fn elaborate_refl(
                 discharge: Vec::new(),
             });
         } else {
-            return Err(CheckerError::ReflexivityFailed(left.clone(), right.clone()));
+            return Err(ReflexivityError::NotEqualUnderContext(left.clone(), right.clone()).into());
         }
     }
 
@@ -2,7 +2,13 @@ use super::{
     assert_clause_len, assert_eq, assert_is_bool_constant, assert_is_expected, assert_num_args,
     assert_num_premises, CheckerError, Premise, RuleArgs, RuleResult,
 };
-use crate::{ast::*, checker::error::ResolutionError, utils::DedupIterator};
+use crate::{
+    ast::*,
+    checker::{
+        clause_db::find_unsubsumed_literal_multiset, error::ResolutionError, StepTrace, TraceEntry,
+    },
+    utils::DedupIterator,
+};
 use ahash::{AHashMap, AHashSet};
 use std::{collections::hash_map::Entry, iter::FromIterator};
 
@@ -55,10 +61,15 @@ pub fn resolution(rule_args: RuleArgs) -> RuleResult {
         // the pivots as arguments
         return resolution_with_args(rule_args);
     }
-    let RuleArgs { conclusion, premises, pool, .. } = rule_args;
+    let RuleArgs {
+        conclusion, premises, pool, trace, ..
+    } = rule_args;
 
-    greedy_resolution(conclusion, premises, pool).or_else(|greedy_error| {
+    greedy_resolution(conclusion, premises, pool, trace).or_else(|greedy_error| {
         if rup_resolution(conclusion, premises) {
+            // The pivots found by the greedy algorithm are not meaningful in this case
+            trace.clear();
+            trace.record(|| TraceEntry::ReverseUnitPropagation);
             Ok(())
         } else {
             // If RUP resolution also fails, we return the error originally returned by the greedy
@@ -72,6 +83,7 @@ fn greedy_resolution(
     conclusion: &[Rc<Term>],
     premises: &[Premise],
     pool: &mut TermPool,
+    trace: &mut StepTrace,
 ) -> RuleResult {
     // In some cases, this rule is used with a single premise `(not true)` to justify an empty
     // conclusion clause
@@ -153,6 +165,10 @@ fn greedy_resolution(
 
             if eliminated {
                 eliminated_clause_pivot = true;
+                trace.record(|| TraceEntry::Resolution {
+                    premise: premise.id.to_owned(),
+                    literal: term.clone(),
+                });
             } else if conclusion.contains(&(n, inner)) {
                 working_clause.insert((n, inner));
             } else {
@@ -259,10 +275,15 @@ fn rup_resolution(conclusion: &[Rc<Term>], premises: &[Premise]) -> bool {
 
 fn resolution_with_args(
     RuleArgs {
-        conclusion, premises, args, pool, ..
+        conclusion,
+        premises,
+        args,
+        pool,
+        trace,
+        ..
     }: RuleArgs,
 ) -> RuleResult {
-    let resolution_result = apply_generic_resolution::<AHashSet<_>>(premises, args, pool)?;
+    let resolution_result = apply_generic_resolution::<AHashSet<_>>(premises, args, pool, trace)?;
 
     let conclusion: AHashSet<_> = conclusion.iter().map(Rc::remove_all_negations).collect();
 
@@ -279,12 +300,17 @@ fn resolution_with_args(
 
 pub fn strict_resolution(
     RuleArgs {
-        conclusion, premises, args, pool, ..
+        conclusion,
+        premises,
+        args,
+        pool,
+        trace,
+        ..
     }: RuleArgs,
 ) -> RuleResult {
     use std::cmp::Ordering;
 
-    let resolution_result = apply_generic_resolution::<Vec<_>>(premises, args, pool)?;
+    let resolution_result = apply_generic_resolution::<Vec<_>>(premises, args, pool, trace)?;
 
     match conclusion.len().cmp(&resolution_result.len()) {
         Ordering::Less => {
@@ -310,6 +336,7 @@ fn apply_generic_resolution<'a, C: ClauseCollection<'a>>(
     premises: &'a [Premise],
     args: &'a [ProofArg],
     pool: &mut TermPool,
+    trace: &mut StepTrace,
 ) -> Result<C, CheckerError> {
     assert_num_premises(premises, 1..)?;
     let num_steps = premises.len() - 1;
@@ -339,6 +366,16 @@ fn apply_generic_resolution<'a, C: ClauseCollection<'a>>(
 
     for (premise, (pivot, polarity)) in premises[1..].iter().zip(args) {
         binary_resolution(pool, &mut current, premise.clause, pivot, polarity)?;
+        trace.record(|| {
+            // The literal eliminated from the premise is the negation of the literal eliminated
+            // from the current clause
+            let (n, term) = pivot;
+            let literal = if polarity { (n + 1, term) } else { (n, term) };
+            TraceEntry::Resolution {
+                premise: premise.id.to_owned(),
+                literal: unremove_all_negations(pool, literal),
+            }
+        });
     }
 
     Ok(current)
@@ -407,6 +444,18 @@ pub fn contraction(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResul
     Ok(())
 }
 
+pub fn weakening(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+    assert_num_premises(premises, 1)?;
+
+    // The premise clause must subsume the conclusion as a multiset, that is, every term in the
+    // premise must also appear in the conclusion, at least as many times. Otherwise, weakening
+    // could also be used to remove duplicate literals, which is the job of `contraction`
+    match find_unsubsumed_literal_multiset(premises[0].clause, conclusion) {
+        Some(t) => Err(ResolutionError::WeakeningMissingTerm(t.clone()).into()),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -687,4 +736,57 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn weakening() {
+        test_cases! {
+            definitions = "
+                (declare-fun p () Bool)
+                (declare-fun q () Bool)
+                (declare-fun r () Bool)
+                (declare-fun s () Bool)
+            ",
+            "Simple working examples" {
+                "(step t1 (cl p q) :rule hole)
+                (step t2 (cl p q r s) :rule weakening :premises (t1))": true,
+
+                "(step t1 (cl p q) :rule hole)
+                (step t2 (cl s q r p) :rule weakening :premises (t1))": true,
+
+                "(step t1 (cl p q) :rule hole)
+                (step t2 (cl p q) :rule weakening :premises (t1))": true,
+
+                "(step t1 (cl) :rule hole)
+                (step t2 (cl p) :rule weakening :premises (t1))": true,
+            }
+            "Number of premises != 1" {
+                "(step t1 (cl p q) :rule weakening)": false,
+
+                "(assume h1 q)
+                (assume h2 p)
+                (step t3 (cl p q) :rule weakening :premises (h1 h2))": false,
+            }
+            "Premise is an \"assume\" command" {
+                "(assume h1 q)
+                (step t2 (cl q p) :rule weakening :premises (h1))": true,
+            }
+            "Conclusion is missing terms" {
+                "(step t1 (cl p q r) :rule hole)
+                (step t2 (cl p q s) :rule weakening :premises (t1))": false,
+
+                "(step t1 (cl p q) :rule hole)
+                (step t2 (cl) :rule weakening :premises (t1))": false,
+            }
+            "Repeated terms" {
+                "(step t1 (cl p q p) :rule hole)
+                (step t2 (cl p r q p) :rule weakening :premises (t1))": true,
+
+                "(step t1 (cl p p) :rule hole)
+                (step t2 (cl p p p) :rule weakening :premises (t1))": true,
+
+                "(step t1 (cl p q p) :rule hole)
+                (step t2 (cl p q r) :rule weakening :premises (t1))": false,
+            }
+        }
+    }
 }
@@ -2,9 +2,11 @@ use super::{
     assert_clause_len, assert_eq, assert_is_bool_constant, CheckerError, EqualityError, RuleArgs,
     RuleResult,
 };
-use crate::{ast::*, utils::DedupIterator};
+use crate::{ast::*, checker::error::SimplificationError, utils::DedupIterator};
 use ahash::{AHashMap, AHashSet};
-use rug::Rational;
+
+#[cfg(feature = "arith")]
+use crate::num::{euclidean_div_mod, Rational};
 
 /// A macro to define the possible transformations for a "simplify" rule.
 macro_rules! simplify {
@@ -51,7 +53,7 @@ fn generic_simplify_rule(
             let mut seen = AHashSet::new();
             loop {
                 if !seen.insert(current.clone()) {
-                    return Err(CheckerError::CycleInSimplification(current.clone()));
+                    return Err(SimplificationError::Cycle(current.clone()).into());
                 }
                 match simplify_function(&current, pool) {
                     Some(next) => {
@@ -73,7 +75,7 @@ fn generic_simplify_rule(
     let got = result == *right || simplify_until_fixed_point(right, left)? == *left;
     rassert!(
         got,
-        CheckerError::SimplificationFailed {
+        SimplificationError::Failed {
             original: left.clone(),
             result,
             target: right.clone(),
@@ -400,6 +402,29 @@ pub fn bool_simplify(args: RuleArgs) -> RuleResult {
             (and (=> phi_1 phi_2) phi_3): ((phi_1, phi_2), phi_3) if phi_1 == phi_3 => {
                 build_term!(pool, (and {phi_1.clone()} {phi_2.clone()}))
             },
+
+            // ¬(phi_1 xor phi_2) => (phi_1 = phi_2)
+            (not (xor phi_1 phi_2)): (phi_1, phi_2) => {
+                build_term!(pool, (= {phi_1.clone()} {phi_2.clone()}))
+            },
+
+            // (phi xor phi) => false
+            (xor phi_1 phi_2): (phi_1, phi_2) if phi_1 == phi_2 => pool.bool_false(),
+
+            // (phi xor false) => phi
+            // (false xor phi) => phi
+            (xor phi false): (phi, _) => phi.clone(),
+            (xor false phi): (_, phi) => phi.clone(),
+
+            // (phi xor true) => ¬phi
+            // (true xor phi) => ¬phi
+            (xor phi true): (phi, _) => build_term!(pool, (not {phi.clone()})),
+            (xor true phi): (_, phi) => build_term!(pool, (not {phi.clone()})),
+
+            // If phi_1 and phi_2 are boolean, (distinct phi_1 phi_2) => (phi_1 xor phi_2)
+            (distinct phi_1 phi_2): (phi_1, phi_2) if **pool.sort(phi_1) == Sort::Bool => {
+                build_term!(pool, (xor {phi_1.clone()} {phi_2.clone()}))
+            },
         })
     })
 }
@@ -416,13 +441,36 @@ pub fn qnt_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
+/// Checks the transformations of `div_simplify` for the integer modulo operator, which behaves
+/// like the integer division, but results in the remainder instead of the quotient.
+#[cfg(feature = "arith")]
+fn mod_simplify(left: &Rc<Term>, t_1: &Rc<Term>, t_2: &Rc<Term>, right: &Rc<Term>) -> RuleResult {
+    let expected = if t_1 == t_2 || t_2.as_number().map_or(false, |n| n == 1) {
+        Rational::new()
+    } else {
+        let (n, d) = (t_1.as_signed_number_err()?, t_2.as_signed_number_err()?);
+        euclidean_div_mod(&n, &d)
+            .ok_or_else(|| SimplificationError::DivisionByZero(left.clone()))?
+            .1
+    };
+    rassert!(
+        right.as_signed_number_err()? == expected,
+        CheckerError::ExpectedNumber(expected, right.clone())
+    );
+    Ok(())
+}
+
+#[cfg(feature = "arith")]
 pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (left, right) = match_term_err!((= l r) = &conclusion[0])?;
-    let (t_1, t_2) = match match_term!((div n d) = left) {
-        Some(v) => Ok(v),
-        None => match_term_err!((/ n d) = left),
-    }?;
+    if let Some((t_1, t_2)) = match_term!((mod n d) = left) {
+        return mod_simplify(left, t_1, t_2, right);
+    }
+    let (is_int_div, (t_1, t_2)) = match match_term!((div n d) = left) {
+        Some(v) => (true, v),
+        None => (false, match_term_err!((/ n d) = left)?),
+    };
 
     if t_1 == t_2 {
         rassert!(
@@ -433,7 +481,16 @@ pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     } else if t_2.as_number().map_or(false, |n| n == 1) {
         assert_eq(right, t_1)
     } else {
-        let expected = t_1.as_signed_number_err()? / t_2.as_signed_number_err()?;
+        let (n, d) = (t_1.as_signed_number_err()?, t_2.as_signed_number_err()?);
+        rassert!(d != 0, SimplificationError::DivisionByZero(left.clone()));
+
+        // Integer division follows the euclidean definition from SMT-LIB, so it is not simply the
+        // quotient of the two constants
+        let expected = if is_int_div {
+            euclidean_div_mod(&n, &d).unwrap().0
+        } else {
+            n / d
+        };
         rassert!(
             right.as_fraction_err()? == expected,
             CheckerError::ExpectedNumber(expected, right.clone())
@@ -444,6 +501,7 @@ pub fn div_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
 
 /// Used for both the `sum_simplify` and `prod_simplify` rules, depending on `rule_kind`.
 /// `rule_kind` has to be either `Operator::Add` or `Operator::Mult`.
+#[cfg(feature = "arith")]
 fn generic_sum_prod_simplify_rule(
     pool: &mut TermPool,
     ts: &Rc<Term>,
@@ -482,7 +540,7 @@ fn generic_sum_prod_simplify_rule(
             None => (identity_value.clone(), std::slice::from_ref(u)),
         }),
     }
-    .ok_or_else(|| CheckerError::SumProdSimplifyInvalidConclusion(u.clone()))?;
+    .ok_or_else(|| SimplificationError::SumProdInvalidConclusion(u.clone()))?;
 
     let ts = match rule_kind {
         Operator::Add => match_term_err!((+ ...) = ts),
@@ -540,6 +598,7 @@ fn generic_sum_prod_simplify_rule(
     Ok(())
 }
 
+#[cfg(feature = "arith")]
 pub fn prod_simplify(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (first, second) = match_term_err!((= first second) = &conclusion[0])?;
@@ -552,6 +611,7 @@ pub fn prod_simplify(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult
     generic_sum_prod_simplify_rule(pool, first, second, Operator::Mult)
 }
 
+#[cfg(feature = "arith")]
 pub fn minus_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     // Despite being separate rules in the documentation, this rule is used to do the job of both
     // the `minus_simplify` and the `unary_minus_simplify` rules
@@ -578,7 +638,7 @@ pub fn minus_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
             );
             return Ok(());
         }
-        match (t_1.as_signed_number(), t_2.as_signed_number()) {
+        match (t_1.as_fraction(), t_2.as_fraction()) {
             (_, Some(z)) if z == 0 => assert_eq(u, t_1),
             (Some(z), _) if z == 0 => assert_eq(match_term_err!((-t) = u)?, t_2),
             (Some(t_1), Some(t_2)) => {
@@ -614,6 +674,7 @@ pub fn minus_simplify(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     check(t_1, t_2, right)
 }
 
+#[cfg(feature = "arith")]
 pub fn sum_simplify(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
     let (first, second) = match_term_err!((= first second) = &conclusion[0])?;
@@ -626,12 +687,13 @@ pub fn sum_simplify(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     generic_sum_prod_simplify_rule(pool, first, second, Operator::Add)
 }
 
+#[cfg(feature = "arith")]
 pub fn comp_simplify(args: RuleArgs) -> RuleResult {
     generic_simplify_rule(args.conclusion, args.pool, |term, pool| {
         simplify!(term {
             (< t_1 t_2): (t_1, t_2) => {
                 if let (Some(t_1), Some(t_2)) =
-                    (t_1.as_signed_number(), t_2.as_signed_number())
+                    (t_1.as_fraction(), t_2.as_fraction())
                 {
                     // t_1 < t_2 => phi, where t_1 and t_2 are numerical constants
                     pool.bool_constant(t_1 < t_2)
@@ -645,7 +707,7 @@ pub fn comp_simplify(args: RuleArgs) -> RuleResult {
             },
             (<= t_1 t_2): (t_1, t_2) => {
                 if let (Some(t_1), Some(t_2)) =
-                    (t_1.as_signed_number(), t_2.as_signed_number())
+                    (t_1.as_fraction(), t_2.as_fraction())
                 {
                     // t_1 <= t_2 => phi, where t_1 and t_2 are numerical constants
                     pool.bool_constant(t_1 <= t_2)
@@ -707,9 +769,12 @@ fn apply_ac_simp(
                 .collect();
             Term::App(func.clone(), args)
         }
-        Term::Quant(q, bindings, inner) => {
-            Term::Quant(*q, bindings.clone(), apply_ac_simp(pool, cache, inner))
-        }
+        Term::Quant(q, bindings, inner, patterns) => Term::Quant(
+            *q,
+            bindings.clone(),
+            apply_ac_simp(pool, cache, inner),
+            patterns.clone(),
+        ),
         Term::Let(binding, inner) => Term::Let(binding.clone(), apply_ac_simp(pool, cache, inner)),
         _ => return term.clone(),
     };
@@ -1146,6 +1211,17 @@ mod tests {
                     (and (=> p q) r) (and p q)
                 )) :rule bool_simplify)": false,
             }
+            "Transformations on xor and boolean distinct" {
+                "(step t1 (cl (= (not (xor p q)) (= p q))) :rule bool_simplify)": true,
+                "(step t1 (cl (= (xor p p) false)) :rule bool_simplify)": true,
+                "(step t1 (cl (= (xor p false) p)) :rule bool_simplify)": true,
+                "(step t1 (cl (= (xor true p) (not p))) :rule bool_simplify)": true,
+                "(step t1 (cl (= (distinct p q) (xor p q))) :rule bool_simplify)": true,
+                "(step t1 (cl (= (distinct p true) (not p))) :rule bool_simplify)": true,
+
+                "(step t1 (cl (= (xor p q) false)) :rule bool_simplify)": false,
+                "(step t1 (cl (= (distinct p q r) (xor p q r))) :rule bool_simplify)": false,
+            }
             // TODO: Add tests that combine more than one transformation
         }
     }
@@ -1171,6 +1247,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn div_simplify() {
         test_cases! {
             definitions = "
@@ -1193,11 +1270,28 @@ mod tests {
                 "(step t1 (cl (= (div 27 9) 3)) :rule div_simplify)": true,
                 "(step t1 (cl (= (/ 1.0 2.0) 0.5)) :rule div_simplify)": true,
                 "(step t1 (cl (= (/ 2.0 20.0) (/ 1.0 10.0))) :rule div_simplify)": true,
+                "(step t1 (cl (= (div 7 2) 3)) :rule div_simplify)": true,
+                "(step t1 (cl (= (div (- 7) 2) (- 4))) :rule div_simplify)": true,
+                "(step t1 (cl (= (div 7 (- 2)) (- 3))) :rule div_simplify)": true,
+                "(step t1 (cl (= (/ 7.0 (- 2.0)) (- 3.5))) :rule div_simplify)": true,
+                "(step t1 (cl (= (div 7 0) 0)) :rule div_simplify)": false,
+            }
+            "Modulo" {
+                "(step t1 (cl (= (mod n n) 0)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod n 1) 0)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod 7 2) 1)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod (- 7) 2) 1)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod (- 7) (- 2)) 1)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod 6 3) 0)) :rule div_simplify)": true,
+                "(step t1 (cl (= (mod 7 2) (- 1))) :rule div_simplify)": false,
+                "(step t1 (cl (= (mod n 2) 0)) :rule div_simplify)": false,
+                "(step t1 (cl (= (mod 7 0) 7)) :rule div_simplify)": false,
             }
         }
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn prod_simplify() {
         test_cases! {
             definitions = "
@@ -1254,6 +1348,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn minus_simplify() {
         test_cases! {
             definitions = "
@@ -1271,6 +1366,7 @@ mod tests {
                 "(step t1 (cl (= (- 4.5 2.0) 2.5)) :rule minus_simplify)": true,
                 "(step t1 (cl (= (- 5 7) (- 2))) :rule minus_simplify)": true,
                 "(step t1 (cl (= 4 (- 2 3))) :rule minus_simplify)": false,
+                "(step t1 (cl (= (- (div 7 2) 1) 2)) :rule minus_simplify)": true,
             }
             "Transformation #3" {
                 "(step t1 (cl (= (- x 0.0) x)) :rule minus_simplify)": true,
@@ -1297,6 +1393,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn sum_simplify() {
         test_cases! {
             definitions = "
@@ -1341,6 +1438,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "arith")]
     fn comp_simplify() {
         test_cases! {
             definitions = "
@@ -1351,6 +1449,11 @@ mod tests {
                 "(step t1 (cl (= (< 1 2) true)) :rule comp_simplify)": true,
                 "(step t1 (cl (= (< 1.0 1.0) false)) :rule comp_simplify)": true,
                 "(step t1 (cl (= (< 0.0 (- 1.0)) true)) :rule comp_simplify)": false,
+                "(step t1 (cl (= (< (to_real 1) 1.5) true)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (< 2.0 (to_real (to_int 2.5))) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (< 3 (div 7 2)) true)) :rule comp_simplify)": false,
+                "(step t1 (cl (= (< 3 (div 7 2)) false)) :rule comp_simplify)": true,
+                "(step t1 (cl (= (< (div (- 7) 2) (- 4)) false)) :rule comp_simplify)": true,
             }
             "Transformation #2" {
                 "(step t1 (cl (= (< a a) false)) :rule comp_simplify)": true,
@@ -1360,6 +1463,7 @@ mod tests {
                 "(step t1 (cl (= (<= 1 2) true)) :rule comp_simplify)": true,
                 "(step t1 (cl (= (<= 1.0 1.0) true)) :rule comp_simplify)": true,
                 "(step t1 (cl (= (<= 0.0 (- 1.0)) true)) :rule comp_simplify)": false,
+                "(step t1 (cl (= (<= (to_int (- 0.5)) (- 1)) true)) :rule comp_simplify)": true,
             }
             "Transformation #4" {
                 "(step t1 (cl (= (<= a a) true)) :rule comp_simplify)": true,
@@ -79,7 +79,7 @@ pub fn bind(
     assert_eq(right, phi_prime)?;
 
     // None of the bindings in the right side can appear as free variables in phi
-    let free_vars = pool.free_vars(phi);
+    let free_vars = phi.free_vars(pool);
     if let Some(y) = r_bindings
         .iter()
         .find(|&y| free_vars.contains(y) && !l_bindings.contains(y))
@@ -167,8 +167,7 @@ pub fn r#let(
     let mut pairs: Vec<_> = let_bindings
         .iter()
         .map(|(x, t)| {
-            let sort = pool.add(Term::Sort(pool.sort(t).clone()));
-            let x_term = pool.add((x.clone(), sort).into());
+            let x_term = pool.add(Term::var(x, pool.sort(t).clone()));
             let s = substitution
                 .get(&x_term)
                 .ok_or_else(|| SubproofError::BindingIsNotInContext(x.clone()))?;
@@ -374,6 +373,7 @@ fn generic_skolemization_rule(
                     rule_type,
                     BindingList(bindings.0[i + 1..].to_vec()),
                     inner,
+                    Vec::new(),
                 ));
             }
 
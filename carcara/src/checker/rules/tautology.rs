@@ -1,8 +1,11 @@
 use super::{
-    assert_clause_len, assert_deep_eq, assert_eq, assert_num_premises, get_premise_term,
+    assert_clause_len, assert_deep_eq, assert_eq, assert_num_premises, get_premise_term, match_xor,
     CheckerError, RuleArgs, RuleResult,
 };
-use crate::{ast::*, checker::rules::assert_operation_len};
+use crate::{
+    ast::*,
+    checker::{error::TautologyError, rules::assert_operation_len},
+};
 
 pub fn r#true(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
@@ -83,32 +86,32 @@ pub fn or_neg(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     Ok(())
 }
 
-pub fn xor_pos1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_pos1(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
-    let (phi_1, phi_2) = match_term_err!((not (xor phi_1 phi_2)) = &conclusion[0])?;
-    assert_eq(phi_1, &conclusion[1])?;
-    assert_eq(phi_2, &conclusion[2])
+    let (phi_1, phi_2) = match_xor(pool, conclusion[0].remove_negation_err()?)?;
+    assert_eq(&phi_1, &conclusion[1])?;
+    assert_eq(&phi_2, &conclusion[2])
 }
 
-pub fn xor_pos2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_pos2(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
-    let (phi_1, phi_2) = match_term_err!((not (xor phi_1 phi_2)) = &conclusion[0])?;
-    assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
-    assert_eq(phi_2, conclusion[2].remove_negation_err()?)
+    let (phi_1, phi_2) = match_xor(pool, conclusion[0].remove_negation_err()?)?;
+    assert_eq(&phi_1, conclusion[1].remove_negation_err()?)?;
+    assert_eq(&phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn xor_neg1(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_neg1(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
-    let (phi_1, phi_2) = match_term_err!((xor phi_1 phi_2) = &conclusion[0])?;
-    assert_eq(phi_1, &conclusion[1])?;
-    assert_eq(phi_2, conclusion[2].remove_negation_err()?)
+    let (phi_1, phi_2) = match_xor(pool, &conclusion[0])?;
+    assert_eq(&phi_1, &conclusion[1])?;
+    assert_eq(&phi_2, conclusion[2].remove_negation_err()?)
 }
 
-pub fn xor_neg2(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn xor_neg2(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3)?;
-    let (phi_1, phi_2) = match_term_err!((xor phi_1 phi_2) = &conclusion[0])?;
-    assert_eq(phi_1, conclusion[1].remove_negation_err()?)?;
-    assert_eq(phi_2, &conclusion[2])
+    let (phi_1, phi_2) = match_xor(pool, &conclusion[0])?;
+    assert_eq(&phi_1, conclusion[1].remove_negation_err()?)?;
+    assert_eq(&phi_2, &conclusion[2])
 }
 
 pub fn implies_pos(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
@@ -309,24 +312,24 @@ pub fn ite_intro(RuleArgs { conclusion, deep_eq_time, .. }: RuleArgs) -> RuleRes
             || is_valid(b, a, d, c);
 
         if !is_valid {
-            return Err(CheckerError::IsNotValidIteIntro(u_i.clone()));
+            return Err(TautologyError::IsNotValidIteIntro(u_i.clone()).into());
         }
     }
     Ok(())
 }
 
-pub fn connective_def(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+pub fn connective_def(RuleArgs { conclusion, pool, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
     let (first, second) = match_term_err!((= f s) = &conclusion[0])?;
 
-    if let Some((phi_1, phi_2)) = match_term!((xor phi_1 phi_2) = first) {
+    if let Ok((phi_1, phi_2)) = match_xor(pool, first) {
         // phi_1 xor phi_2 <-> (¬phi_1 ^ phi_2) v (phi_1 ^ ¬phi_2)
         let ((a, b), (c, d)) = match_term_err!((or (and (not a) b) (and c (not d))) = second)?;
-        assert_eq(a, phi_1)?;
-        assert_eq(b, phi_2)?;
-        assert_eq(c, phi_1)?;
-        assert_eq(d, phi_2)
+        assert_eq(a, &phi_1)?;
+        assert_eq(b, &phi_2)?;
+        assert_eq(c, &phi_1)?;
+        assert_eq(d, &phi_2)
     } else if let Some((phi_1, phi_2)) = match_term!((= phi_1 phi_2) = first) {
         // (phi_1 <-> phi_2) <-> (phi_1 -> phi_2) ^ (phi_2 -> phi_1)
         let ((a, b), (c, d)) = match_term_err!((and (=> a b) (=> c d)) = second)?;
@@ -346,7 +349,7 @@ pub fn connective_def(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
         assert_eq(first_inner, second_inner)?;
         assert_eq(first_bindings, second_bindings)
     } else {
-        Err(CheckerError::TermIsNotConnective(first.clone()))
+        Err(TautologyError::TermIsNotConnective(first.clone()).into())
     }
 }
 
@@ -522,6 +525,7 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(step t1 (cl (not (xor p q)) p q) :rule xor_pos1)": true,
@@ -535,6 +539,12 @@ mod tests {
                 "(step t1 (cl (not (xor p q)) q p) :rule xor_pos1)": false,
                 "(step t1 (cl (not (xor (not p) q)) p (not q)) :rule xor_pos1)": false,
             }
+            "N-ary and boolean distinct terms" {
+                "(step t1 (cl (not (xor p q r)) (xor p q) r) :rule xor_pos1)": true,
+                "(step t1 (cl (not (distinct p q)) p q) :rule xor_pos1)": true,
+                "(step t1 (cl (not (xor p q r)) p (xor q r)) :rule xor_pos1)": false,
+                "(step t1 (cl (not (distinct p q r)) (distinct p q) r) :rule xor_pos1)": false,
+            }
         }
     }
 
@@ -544,6 +554,7 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(step t1 (cl (not (xor p q)) (not p) (not q)) :rule xor_pos2)": true,
@@ -559,6 +570,10 @@ mod tests {
                 "(step t1 (cl (not (xor p q)) (not p) (not p)) :rule xor_pos2)": false,
                 "(step t1 (cl (not (xor p (not q))) (not p) q) :rule xor_pos2)": false,
             }
+            "N-ary and boolean distinct terms" {
+                "(step t1 (cl (not (xor p q r)) (not (xor p q)) (not r)) :rule xor_pos2)": true,
+                "(step t1 (cl (not (distinct p q)) (not p) (not q)) :rule xor_pos2)": true,
+            }
         }
     }
 
@@ -568,6 +583,9 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
+                (declare-fun a () Int)
+                (declare-fun b () Int)
             ",
             "Simple working examples" {
                 "(step t1 (cl (xor p q) p (not q)) :rule xor_neg1)": true,
@@ -581,6 +599,11 @@ mod tests {
                 "(step t1 (cl (xor p q) q (not p)) :rule xor_neg1)": false,
                 "(step t1 (cl (xor p q) p (not p)) :rule xor_neg1)": false,
             }
+            "N-ary and boolean distinct terms" {
+                "(step t1 (cl (xor p q r) (xor p q) (not r)) :rule xor_neg1)": true,
+                "(step t1 (cl (distinct p q) p (not q)) :rule xor_neg1)": true,
+                "(step t1 (cl (distinct a b) (= a b) (not (= a b))) :rule xor_neg1)": false,
+            }
         }
     }
 
@@ -590,6 +613,7 @@ mod tests {
             definitions = "
                 (declare-fun p () Bool)
                 (declare-fun q () Bool)
+                (declare-fun r () Bool)
             ",
             "Simple working examples" {
                 "(step t1 (cl (xor p q) (not p) q) :rule xor_neg2)": true,
@@ -602,6 +626,10 @@ mod tests {
                 "(step t1 (cl (xor p q) (not q) p) :rule xor_neg2)": false,
                 "(step t1 (cl (xor p q) (not p) p) :rule xor_neg2)": false,
             }
+            "N-ary and boolean distinct terms" {
+                "(step t1 (cl (xor p q r) (not (xor p q)) r) :rule xor_neg2)": true,
+                "(step t1 (cl (distinct p q) (not p) q) :rule xor_neg2)": true,
+            }
         }
     }
 
@@ -1183,6 +1211,10 @@ mod tests {
                     :rule connective_def)": false,
                 "(step t1 (cl (= (xor p q) (or (and p (not q)) (and (not p) q))))
                     :rule connective_def)": false,
+                "(step t1 (cl (= (xor p q r) (or (and (not (xor p q)) r) (and (xor p q) (not r)))))
+                    :rule connective_def)": true,
+                "(step t1 (cl (= (distinct p q) (or (and (not p) q) (and p (not q)))))
+                    :rule connective_def)": true,
             }
             "Case #2" {
                 "(step t1 (cl (= (= p q) (and (=> p q) (=> q p)))) :rule connective_def)": true,
@@ -1,5 +1,8 @@
-use super::{assert_clause_len, get_premise_term, CheckerError, Elaborator, RuleArgs, RuleResult};
-use crate::ast::*;
+use super::{
+    assert_clause_len, equality_graph::EqualityGraph, get_premise_term, CheckerError, Elaborator,
+    RuleArgs, RuleResult,
+};
+use crate::{ast::*, checker::error::TransitivityError};
 
 /// Function to find a transitive chain given a conclusion equality and a series of premise
 /// equalities.
@@ -29,7 +32,7 @@ fn find_chain(
         })
         .ok_or_else(|| {
             let (a, b) = conclusion;
-            CheckerError::BrokenTransitivityChain(a.clone(), b.clone())
+            TransitivityError::BrokenChain(a.clone(), b.clone())
         })?;
 
     // We remove the found equality by swapping it with the first element in `premises`.  The new
@@ -75,7 +78,7 @@ fn find_and_trace_chain<'a, T>(
             })
             .ok_or_else(|| {
                 let (a, b) = conclusion;
-                CheckerError::BrokenTransitivityChain(a.clone(), b.clone())
+                TransitivityError::BrokenChain(a.clone(), b.clone())
             })?;
 
         if found_index != i {
@@ -88,6 +91,58 @@ fn find_and_trace_chain<'a, T>(
     }
 }
 
+/// Similar to `find_chain`, but succeeds whenever the conclusion follows from the premises by
+/// transitivity, regardless of their order. Instead of always following the first premise that
+/// continues the chain, which fails if that premise leads to a dead end, this adds the premise
+/// equalities to an equality graph, and checks that the two terms in the conclusion are equal in it.
+fn find_chain_in_any_order(
+    conclusion: (&Rc<Term>, &Rc<Term>),
+    premises: &[(&Rc<Term>, &Rc<Term>)],
+) -> RuleResult {
+    let mut graph = EqualityGraph::new();
+    for &(t, u) in premises {
+        graph.add(t, u);
+    }
+    let (a, b) = conclusion;
+    rassert!(
+        graph.are_equal(a, b),
+        TransitivityError::NoPath(a.clone(), b.clone())
+    );
+    Ok(())
+}
+
+/// Similar to `find_and_trace_chain`, but finds the shortest chain using an equality graph, so it
+/// succeeds whenever `find_chain_in_any_order` does. The premises in the chain are moved to the
+/// start of both vectors, in the order in which they appear in it.
+fn search_and_trace_chain<'a, T: Clone>(
+    conclusion: (&'a Rc<Term>, &'a Rc<Term>),
+    premise_equalities: &mut [(&'a Rc<Term>, &'a Rc<Term>)],
+    premises: &mut [T],
+) -> Result<(bool, usize, Vec<usize>), CheckerError> {
+    let mut graph = EqualityGraph::new();
+    for &(t, u) in premise_equalities.iter() {
+        graph.add(t, u);
+    }
+    let (a, b) = conclusion;
+    let explanation = graph
+        .explain(a, b)
+        .ok_or_else(|| TransitivityError::NoPath(a.clone(), b.clone()))?;
+
+    let chain: Vec<usize> = explanation.iter().map(|&(i, _)| i).collect();
+    let should_flip = (0..explanation.len())
+        .filter(|&position| explanation[position].1)
+        .collect();
+
+    let mut order = chain.clone();
+    order.extend((0..premises.len()).filter(|i| !chain.contains(i)));
+    let reordered = order.iter().enumerate().any(|(position, &i)| position != i);
+    let new_equalities: Vec<_> = order.iter().map(|&i| premise_equalities[i]).collect();
+    let new_premises: Vec<_> = order.iter().map(|&i| premises[i].clone()).collect();
+    premise_equalities.copy_from_slice(&new_equalities);
+    premises.clone_from_slice(&new_premises);
+    Ok((reordered, chain.len(), should_flip))
+}
+
 pub fn eq_transitive(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     assert_clause_len(conclusion, 3..)?;
 
@@ -105,10 +160,43 @@ pub fn eq_transitive(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
     find_chain(chain_conclusion, &mut premises)
 }
 
+/// A version of `eq_transitive` that accepts any clause whose inequalities imply the final
+/// equality by transitivity, even if following them in the order they are given leads to a dead
+/// end. See `find_chain_in_any_order`.
+pub fn commutative_eq_transitive(RuleArgs { conclusion, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 3..)?;
+    let chain_conclusion = match_term_err!((= t u) = conclusion.last().unwrap())?;
+    let premises: Vec<_> = conclusion[..conclusion.len() - 1]
+        .iter()
+        .map(|term| match_term_err!((not (= t u)) = term))
+        .collect::<Result<_, _>>()?;
+
+    find_chain_in_any_order(chain_conclusion, &premises)
+}
+
 pub fn elaborate_eq_transitive(
+    args: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    elaborate_eq_transitive_chain(args, command_id, elaborator, false)
+}
+
+/// Elaborates a step checked by `commutative_eq_transitive`. The chain found by the search is
+/// emitted in order, with the inequalities that are not part of it added by an `or_intro` step.
+pub fn elaborate_commutative_eq_transitive(
+    args: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    elaborate_eq_transitive_chain(args, command_id, elaborator, true)
+}
+
+fn elaborate_eq_transitive_chain(
     RuleArgs { conclusion, pool, .. }: RuleArgs,
     command_id: String,
     elaborator: &mut Elaborator,
+    search: bool,
 ) -> RuleResult {
     assert_clause_len(conclusion, 3..)?;
     let n = conclusion.len();
@@ -125,7 +213,12 @@ pub fn elaborate_eq_transitive(
         .collect::<Result<_, _>>()?;
 
     let mut new_clause: Vec<_> = conclusion.to_vec();
-    let (needs_reordering, num_needed, should_flip) = find_and_trace_chain(
+    let trace_chain = if search {
+        search_and_trace_chain
+    } else {
+        find_and_trace_chain
+    };
+    let (needs_reordering, num_needed, should_flip) = trace_chain(
         conclusion_equality,
         &mut premise_equalities,
         &mut new_clause[..n - 1],
@@ -180,7 +273,7 @@ pub fn elaborate_eq_transitive(
 
     if !not_needed.is_empty() {
         let mut clause = latest_clause;
-        clause.extend(not_needed.into_iter());
+        clause.extend(not_needed);
         let or_intro_step = ProofStep {
             id: elaborator.get_new_id(&command_id),
             clause,
@@ -282,10 +375,44 @@ pub fn trans(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
     find_chain(conclusion, &mut premises)
 }
 
+/// A version of `trans` that accepts any premises that imply the conclusion by transitivity, even
+/// if following them in the order they are given leads to a dead end. See
+/// `find_chain_in_any_order`.
+pub fn commutative_trans(RuleArgs { conclusion, premises, .. }: RuleArgs) -> RuleResult {
+    assert_clause_len(conclusion, 1)?;
+
+    let conclusion = match_term_err!((= t u) = &conclusion[0])?;
+    let premises: Vec<_> = premises
+        .iter()
+        .map(|premise| match_term_err!((= t u) = get_premise_term(premise)?))
+        .collect::<Result<_, _>>()?;
+
+    find_chain_in_any_order(conclusion, &premises)
+}
+
 pub fn elaborate_trans(
+    args: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    elaborate_trans_chain(args, command_id, elaborator, false)
+}
+
+/// Elaborates a step checked by `commutative_trans`. The premises in the chain found by the search
+/// are given to the elaborated step in order, and the other premises are removed.
+pub fn elaborate_commutative_trans(
+    args: RuleArgs,
+    command_id: String,
+    elaborator: &mut Elaborator,
+) -> RuleResult {
+    elaborate_trans_chain(args, command_id, elaborator, true)
+}
+
+fn elaborate_trans_chain(
     RuleArgs { conclusion, premises, pool, .. }: RuleArgs,
     command_id: String,
     elaborator: &mut Elaborator,
+    search: bool,
 ) -> RuleResult {
     assert_clause_len(conclusion, 1)?;
 
@@ -296,7 +423,12 @@ pub fn elaborate_trans(
         .collect::<Result<_, _>>()?;
 
     let mut new_premises: Vec<_> = premises.iter().map(|p| p.index).collect();
-    let (_, num_needed, should_flip) = find_and_trace_chain(
+    let trace_chain = if search {
+        search_and_trace_chain
+    } else {
+        find_and_trace_chain
+    };
+    let (_, num_needed, should_flip) = trace_chain(
         conclusion_equality,
         &mut premise_equalities,
         &mut new_premises,
@@ -446,4 +578,93 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn commutative_transitivity() {
+        use super::{commutative_eq_transitive, commutative_trans};
+
+        test_cases! {
+            definitions = "
+                (declare-sort T 0)
+                (declare-fun a () T)
+                (declare-fun b () T)
+                (declare-fun c () T)
+                (declare-fun d () T)
+                (declare-fun e () T)
+            ",
+            rules = [
+                ("trans", commutative_trans),
+                ("eq_transitive", commutative_eq_transitive),
+            ],
+            "Premises that lead to a dead end" {
+                "(step t1 (cl (not (= a b)) (not (= b e)) (not (= b c)) (= a c))
+                    :rule eq_transitive)": true,
+
+                "(assume h1 (= a b)) (assume h2 (= b e)) (assume h3 (= c b)) (assume h4 (= d c))
+                (step t5 (cl (= a d)) :rule trans :premises (h1 h2 h3 h4))": true,
+            }
+            "Premises in different orders" {
+                "(step t1 (cl (not (= c d)) (not (= b c)) (not (= a b)) (= a d))
+                    :rule eq_transitive)": true,
+
+                "(assume h1 (= c d)) (assume h2 (= b c)) (assume h3 (= a b))
+                (step t4 (cl (= d a)) :rule trans :premises (h1 h2 h3))": true,
+            }
+            "Premises don't connect the conclusion terms" {
+                "(step t1 (cl (not (= a b)) (not (= c d)) (= a d)) :rule eq_transitive)": false,
+
+                "(assume h1 (= a b)) (assume h2 (= b c)) (assume h3 (= d e))
+                (step t4 (cl (= a e)) :rule trans :premises (h1 h2 h3))": false,
+            }
+        }
+    }
+
+    #[test]
+    fn elaborate_commutative_trans() {
+        use crate::{ast::ProofCommand, check_and_elaborate, CarcaraOptions};
+
+        let problem = "
+            (declare-sort T 0)
+            (declare-fun a () T)
+            (declare-fun b () T)
+            (declare-fun c () T)
+            (declare-fun e () T)
+            (assert (= b e))
+            (assert (= c b))
+            (assert (= a b))
+            (assert (not (= a c)))
+        ";
+        let proof = "
+            (assume h1 (= a b))
+            (assume h2 (= b e))
+            (assume h3 (= c b))
+            (step t4 (cl (= a c)) :rule trans :premises (h1 h2 h3))
+            (assume h5 (not (= a c)))
+            (step t6 (cl) :rule resolution :premises (t4 h5))
+        ";
+        let options = CarcaraOptions {
+            commutative_transitivity: true,
+            ..Default::default()
+        };
+        let commands = check_and_elaborate(problem.as_bytes(), proof.as_bytes(), options).unwrap();
+
+        // The elaborated step uses only the premises in the chain, in order, with `(= c b)` flipped
+        let trans = commands
+            .iter()
+            .find_map(|c| match c {
+                ProofCommand::Step(s) if s.rule == "trans" => Some(s),
+                _ => None,
+            })
+            .unwrap();
+        let premises: Vec<_> = trans
+            .premises
+            .iter()
+            .map(|&(_, i)| commands[i].clause()[0].to_string())
+            .collect();
+        assert_eq!(premises, ["(= a b)", "(= b c)"]);
+
+        let options = CarcaraOptions::default();
+        let result = check_and_elaborate(problem.as_bytes(), proof.as_bytes(), options);
+        assert!(matches!(result, Err(crate::Error::Checker { .. })));
+    }
 }
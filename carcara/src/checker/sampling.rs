@@ -0,0 +1,131 @@
+//! Sampling of proof steps, to get a quick estimate of whether a large set of proofs is valid
+//! without checking every step.
+//!
+//! Steps are sampled separately for each rule, so rare rules are not drowned out by common ones.
+//! For each rule, the sampler uses systematic sampling: with a rate of `r`, it checks one in every
+//! `1/r` steps that use the rule, starting from a random offset. The offset is derived from the
+//! seed, the rule name, and the id and position of the first step that uses the rule, so different
+//! proofs check different steps, but the same proof and seed always check the same steps.
+
+use ahash::AHashMap;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// The configuration of step sampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingConfig {
+    /// The fraction of the steps of each rule that are checked, between 0 and 1.
+    pub rate: f64,
+
+    /// The seed from which the sampled steps are chosen.
+    pub seed: u64,
+}
+
+#[derive(Debug)]
+struct RuleState {
+    count: u64,
+    offset: f64,
+}
+
+#[derive(Debug)]
+pub(super) struct StepSampler {
+    config: SamplingConfig,
+    rules: AHashMap<String, RuleState>,
+    num_checked: usize,
+    num_skipped: usize,
+}
+
+impl StepSampler {
+    pub fn new(config: SamplingConfig) -> Self {
+        Self {
+            config,
+            rules: AHashMap::new(),
+            num_checked: 0,
+            num_skipped: 0,
+        }
+    }
+
+    /// Decides whether a step should be checked. `position` is the index of the step among all the
+    /// steps in the proof.
+    pub fn should_check(&mut self, rule: &str, step_id: &str, position: usize) -> bool {
+        let seed = self.config.seed;
+        let state = self.rules.entry(rule.to_owned()).or_insert_with(|| {
+            let hash = [rule.as_bytes(), step_id.as_bytes(), &position.to_le_bytes()]
+                .iter()
+                .fold(fnv1a(FNV_OFFSET_BASIS, &seed.to_le_bytes()), |h, bytes| {
+                    fnv1a(h, bytes)
+                });
+            let offset = StdRng::seed_from_u64(hash).gen::<f64>();
+            RuleState { count: 0, offset }
+        });
+
+        // The step is checked if the sequence `count * rate + offset` crosses an integer between
+        // this step and the next one
+        let rate = self.config.rate.clamp(0.0, 1.0);
+        let current = (state.count as f64 * rate + state.offset).floor();
+        let next = ((state.count + 1) as f64 * rate + state.offset).floor();
+        state.count += 1;
+
+        let result = next > current;
+        if result {
+            self.num_checked += 1;
+        } else {
+            self.num_skipped += 1;
+        }
+        result
+    }
+
+    /// Returns the number of steps that were checked and skipped so far.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.num_checked, self.num_skipped)
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes `bytes` with the FNV-1a function, starting from the state `hash`. Unlike the hashers from
+/// `std` and `ahash`, this gives the same results in every platform and version, so the sampled
+/// steps are reproducible.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |h, &b| (h ^ u64::from(b)).wrapping_mul(FNV_PRIME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_rate() {
+        let config = SamplingConfig { rate: 0.1, seed: 0 };
+        let mut sampler = StepSampler::new(config);
+        let checked = (0..1000)
+            .filter(|&i| sampler.should_check("resolution", "t1", i))
+            .count();
+        assert_eq!(checked, 100);
+
+        // Every rule is sampled separately, so a rule with few steps is still checked
+        let checked = (0..10)
+            .filter(|&i| sampler.should_check("refl", "t2", i))
+            .count();
+        assert_eq!(checked, 1);
+        assert_eq!(sampler.counts(), (101, 909));
+
+        let config = SamplingConfig { rate: 1.0, seed: 0 };
+        let mut sampler = StepSampler::new(config);
+        assert!((0..100).all(|i| sampler.should_check("resolution", "t1", i)));
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic() {
+        let sample = |seed| {
+            let mut sampler = StepSampler::new(SamplingConfig { rate: 0.25, seed });
+            (0..100)
+                .filter(|&i| sampler.should_check("resolution", "t1", i))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sample(42), sample(42));
+        assert_ne!(sample(1), sample(2));
+    }
+}
@@ -0,0 +1,187 @@
+//! Severity levels of checker errors. By default, every error makes the proof invalid. However,
+//! some categories of errors are about the conformance of the proof to the Alethe specification,
+//! rather than about the reasoning in it, and users may want to accept proofs that have them. Each
+//! of these categories can be given the `Warning` severity, in which case the errors in it are
+//! logged and counted instead of stopping the check.
+//!
+//! Errors reported as warnings never make a proof valid: if the error means that part of the proof
+//! was not checked, like a step that uses an unknown rule, the proof is considered holey.
+
+use super::error::CheckerError;
+use crate::Error;
+use std::fmt;
+
+/// The severity of a category of errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Errors make the proof invalid.
+    Error,
+
+    /// Errors are logged and counted, but the checking continues.
+    Warning,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Self::Error
+    }
+}
+
+/// The categories of errors whose severity can be configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// A step uses a rule that is not known by the checker. The step is treated as a hole.
+    UnknownRule,
+
+    /// An `assume` command does not match any assertion in the problem. The command is treated as
+    /// a hole.
+    Assume,
+
+    /// Two alpha-equivalent `choice` terms are written in different ways. This is only checked
+    /// when auditing choice terms.
+    InconsistentChoiceTerms,
+
+    /// The proof does not conclude the empty clause. The proof is considered holey.
+    NoEmptyClause,
+}
+
+impl ErrorCategory {
+    /// All error categories.
+    pub const ALL: [Self; 4] = [
+        Self::UnknownRule,
+        Self::Assume,
+        Self::InconsistentChoiceTerms,
+        Self::NoEmptyClause,
+    ];
+
+    /// Returns the name of the category, as used in the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UnknownRule => "unknown-rule",
+            Self::Assume => "assume",
+            Self::InconsistentChoiceTerms => "inconsistent-choice-terms",
+            Self::NoEmptyClause => "no-empty-clause",
+        }
+    }
+
+    /// Returns the category of an error, or `None` if its severity can't be configured.
+    pub fn of(error: &Error) -> Option<Self> {
+        match error {
+            Error::Checker { inner, .. } => match inner.as_ref() {
+                CheckerError::UnknownRule => Some(Self::UnknownRule),
+                CheckerError::Assume(_) => Some(Self::Assume),
+                CheckerError::InconsistentChoiceTerms(..) => Some(Self::InconsistentChoiceTerms),
+                _ => None,
+            },
+            Error::DoesNotReachEmptyClause => Some(Self::NoEmptyClause),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The severity of each category of errors. By default, all categories have the `Error` severity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeverityLevels([Severity; ErrorCategory::ALL.len()]);
+
+impl SeverityLevels {
+    pub fn get(&self, category: ErrorCategory) -> Severity {
+        self.0[category.index()]
+    }
+
+    pub fn set(&mut self, category: ErrorCategory, severity: Severity) {
+        self.0[category.index()] = severity;
+    }
+
+    /// Returns the categories that have the `Warning` severity.
+    pub fn warnings(&self) -> impl Iterator<Item = ErrorCategory> + '_ {
+        ErrorCategory::ALL
+            .into_iter()
+            .filter(|&c| self.get(c) == Severity::Warning)
+    }
+}
+
+/// The number of errors of each category that were reported as warnings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WarningCounts([usize; ErrorCategory::ALL.len()]);
+
+impl WarningCounts {
+    pub fn get(&self, category: ErrorCategory) -> usize {
+        self.0[category.index()]
+    }
+
+    pub(super) fn increment(&mut self, category: ErrorCategory) {
+        self.0[category.index()] += 1;
+    }
+
+    /// Returns the total number of warnings.
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    /// Returns the categories with at least one warning, and their number of warnings.
+    pub fn iter(&self) -> impl Iterator<Item = (ErrorCategory, usize)> + '_ {
+        ErrorCategory::ALL
+            .into_iter()
+            .map(|c| (c, self.get(c)))
+            .filter(|&(_, n)| n > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check, CarcaraOptions};
+
+    #[test]
+    fn test_severity_levels() {
+        let problem = "(declare-fun p () Bool) (assert p)";
+        let run = |proof: &str, warnings: &[ErrorCategory]| {
+            let mut severities = SeverityLevels::default();
+            for &c in warnings {
+                severities.set(c, Severity::Warning);
+            }
+            let options = CarcaraOptions { severities, ..Default::default() };
+            check(problem.as_bytes(), proof.as_bytes(), options)
+        };
+
+        let proof = "(assume h1 p) (step t2 (cl) :rule foo :premises (h1))";
+        assert!(matches!(run(proof, &[]), Err(Error::Checker { .. })));
+        assert!(run(proof, &[ErrorCategory::Assume]).is_err());
+        assert_eq!(run(proof, &[ErrorCategory::UnknownRule]).ok(), Some(true));
+
+        let proof = "(assume h1 (not p)) (step t2 (cl) :rule hole :premises (h1))";
+        assert_eq!(run(proof, &[ErrorCategory::Assume]).ok(), Some(true));
+
+        let proof = "(assume h1 p)";
+        let result = run(proof, &[]);
+        assert!(matches!(result, Err(Error::DoesNotReachEmptyClause)));
+        assert_eq!(run(proof, &[ErrorCategory::NoEmptyClause]).ok(), Some(true));
+    }
+
+    #[test]
+    fn test_warning_counts() {
+        let mut counts = WarningCounts::default();
+        counts.increment(ErrorCategory::Assume);
+        counts.increment(ErrorCategory::Assume);
+        counts.increment(ErrorCategory::NoEmptyClause);
+        assert_eq!(counts.total(), 3);
+        let all: Vec<_> = counts.iter().collect();
+        assert_eq!(
+            all,
+            [
+                (ErrorCategory::Assume, 2),
+                (ErrorCategory::NoEmptyClause, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Streaming of the result of each step as the proof is checked. When enabled, the checker writes
+//! one line for each step as soon as it is checked, so tools that wrap the checker can show its
+//! progress live, or stop it at the first invalid step. Each line is a JSON object, like this:
+//!
+//! ```text
+//! {"index":3,"id":"t4","rule":"or","result":"valid","time":0.000012}
+//! ```
+//!
+//! `index` is the position of the step among the steps checked so far, starting at zero, and `time`
+//! is the time spent checking it, in seconds. If the step is invalid, `result` is `"invalid"`, and
+//! the object also has an `error` field with the error message. The output is flushed after each
+//! line.
+
+use super::{error::CheckerError, trace::JsonString};
+use std::{fmt, io, time::Duration};
+
+/// The destination of the results.
+pub struct ResultStream<'c>(pub &'c mut dyn io::Write);
+
+impl fmt::Debug for ResultStream<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ResultStream")
+    }
+}
+
+/// Writes the result of a step as a line containing a JSON object, and flushes the output.
+pub fn write_step_result(
+    dest: &mut dyn io::Write,
+    index: usize,
+    id: &str,
+    rule: &str,
+    error: Option<&CheckerError>,
+    time: Duration,
+) -> io::Result<()> {
+    write!(
+        dest,
+        "{{\"index\":{},\"id\":{},\"rule\":{},",
+        index,
+        JsonString(id),
+        JsonString(rule)
+    )?;
+    match error {
+        Some(e) => write!(
+            dest,
+            "\"result\":\"invalid\",\"error\":{},",
+            JsonString(&e.to_string())
+        )?,
+        None => write!(dest, "\"result\":\"valid\",")?,
+    }
+    writeln!(dest, "\"time\":{}}}", time.as_secs_f64())?;
+    dest.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        checker::{Config, ProofChecker},
+        parser::parse_instance,
+        Error,
+    };
+
+    #[test]
+    fn test_stream_results() {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (assert (or p q))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (step t2 (cl p q) :rule or :premises (h1))
+            (step t3 (cl q p) :rule reordering :premises (t2))
+            (step t4 (cl p) :rule or :premises (h1))
+        ";
+        let (prelude, proof, mut pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), Default::default()).unwrap();
+        let mut output = Vec::new();
+        let config = Config {
+            stream_results: Some(ResultStream(&mut output)),
+            ..Default::default()
+        };
+        let result = ProofChecker::new(&mut pool, config, prelude).check(&proof);
+        assert!(result.is_err());
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(r#"{"index":0,"id":"t2","rule":"or","result":"valid","#));
+        assert!(lines[1].starts_with(r#"{"index":1,"id":"t3","rule":"reordering","#));
+        assert!(lines[2].starts_with(r#"{"index":2,"id":"t4","rule":"or","result":"invalid","#));
+        assert!(lines[2].contains(r#""error":"#));
+    }
+
+    #[test]
+    fn test_keep_going() {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (assert (or p q))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (step t2 (cl p) :rule or :premises (h1))
+            (assume h3 q)
+            (step t4 (cl q) :rule or :premises (h1))
+            (step t5 (cl p q) :rule or :premises (h1))
+        ";
+        let run = |keep_going| {
+            let (prelude, proof, mut pool) =
+                parse_instance(problem.as_bytes(), proof.as_bytes(), Default::default()).unwrap();
+            let mut output = Vec::new();
+            let config = Config {
+                stream_results: Some(ResultStream(&mut output)),
+                keep_going,
+                ..Default::default()
+            };
+            let result = ProofChecker::new(&mut pool, config, prelude).check(&proof);
+            (result, String::from_utf8(output).unwrap().lines().count())
+        };
+
+        let (result, num_lines) = run(None);
+        assert!(matches!(result, Err(Error::Checker { step, .. }) if step == "t2"));
+        assert_eq!(num_lines, 1);
+
+        let (result, num_lines) = run(Some(100));
+        let errors = match result {
+            Err(Error::CheckerErrors(errors)) => errors,
+            other => panic!("expected many errors, got {:?}", other),
+        };
+        let steps: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                Error::Checker { step, .. } => step.as_str(),
+                other => panic!("unexpected error: {:?}", other),
+            })
+            .collect();
+        assert_eq!(steps, ["t2", "h3", "t4"]);
+        assert_eq!(num_lines, 3);
+
+        let (result, num_lines) = run(Some(2));
+        assert!(matches!(result, Err(Error::CheckerErrors(errors)) if errors.len() == 2));
+
+        // The second error is in an `assume` command, which doesn't have its result streamed
+        assert_eq!(num_lines, 1);
+    }
+}
@@ -0,0 +1,115 @@
+//! A harness for testing proof rules, used by the `test_cases!` macro. This can also be used to test
+//! rules implemented outside of this crate, which are given to the checker using
+//! `ProofChecker::add_rule`.
+
+use super::{Config, ProofChecker, Rule};
+use crate::{
+    ast::*,
+    parser::{Parser, ParserConfig},
+};
+
+/// Checks a series of proofs, and panics if any of them does not have the expected result.
+///
+/// The `definitions` are parsed only once, and the resulting declarations and problem prelude are
+/// shared between all proofs. Each element of `tests` is a test name together with its cases, and
+/// each case is a proof and whether it is expected to be valid. The proofs are checked in testing
+/// mode, so `assume` commands are always accepted and the proofs don't need to reach the empty
+/// clause. Any rules in `rules` are added to the checker, and take precedence over the built-in
+/// rules with the same name.
+pub fn run_tests(definitions: &str, rules: &[(&str, Rule)], tests: &[(&str, &[(&str, bool)])]) {
+    let mut pool = TermPool::new();
+    let parser = Parser::new(&mut pool, ParserConfig::default(), definitions.as_bytes())
+        .unwrap_or_else(|e| panic!("parser error in test definitions: {}", e));
+    let (prelude, premises, mut parser) = parser
+        .parse_problem()
+        .unwrap_or_else(|e| panic!("parser error in test definitions: {}", e));
+
+    // All proofs are parsed before any of them is checked, since the parser holds a mutable
+    // reference to the term pool
+    let parsed: Vec<Vec<_>> = tests
+        .iter()
+        .map(|(test_name, cases)| {
+            cases
+                .iter()
+                .map(|(proof, _)| {
+                    parser.parse_proof(proof.as_bytes()).unwrap_or_else(|e| {
+                        panic!("parser error during test \"{}\": {}", test_name, e)
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    for ((test_name, cases), parsed) in tests.iter().zip(parsed) {
+        for (i, ((_, expected), commands)) in cases.iter().zip(parsed).enumerate() {
+            let config = Config {
+                strict: false,
+                skip_unknown_rules: false,
+                is_running_test: true,
+                statistics: None,
+                check_lia_using_cvc5: true,
+                audit_choice_terms: false,
+                commutative_transitivity: false,
+                sampling: None,
+                severities: Default::default(),
+                trace: None,
+                stream_results: None,
+                keep_going: None,
+                slow_step_threshold: None,
+                trusted_rules: Default::default(),
+            };
+            let mut checker = ProofChecker::new(&mut pool, config, prelude.clone());
+            for &(name, rule) in rules {
+                checker.add_rule(name, rule);
+            }
+            let proof = Proof {
+                premises: premises.clone(),
+                commands,
+                spans: Vec::new(),
+                metadata: Vec::new(),
+                named_assertions: Vec::new(),
+                comments: Vec::new(),
+            };
+            let got = checker.check(&proof).is_ok();
+            assert_eq!(
+                *expected, got,
+                "test case \"{}\" index {} failed",
+                test_name, i
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checker::{error::CheckerError, RuleArgs, RuleResult};
+
+    fn unit_conclusion(args: RuleArgs) -> RuleResult {
+        match (args.conclusion(), args.premises()) {
+            ([_], []) => Ok(()),
+            _ => Err(CheckerError::Unspecified),
+        }
+    }
+
+    #[test]
+    fn custom_rules() {
+        crate::test_cases! {
+            definitions = "
+                (declare-fun p () Bool)
+                (declare-fun q () Bool)
+            ",
+            rules = [("unit_conclusion", unit_conclusion), ("resolution", unit_conclusion)],
+            "Custom rule" {
+                "(step t1 (cl p) :rule unit_conclusion)": true,
+                "(step t1 (cl p q) :rule unit_conclusion)": false,
+                "(assume h1 p) (step t2 (cl q) :rule unit_conclusion :premises (h1))": false,
+            }
+            "Custom rules take precedence" {
+                "(step t1 (cl p) :rule resolution)": true,
+            }
+            "Built-in rules are still available" {
+                "(step t1 (cl (= p p)) :rule eq_reflexive)": true,
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Traces of the reasoning done by the checker. When tracing is enabled, the checker writes, for
+//! each step that was checked by a rule, the computation that the rule performed to accept it. For
+//! example, the trace of a `resolution` step contains the literals eliminated from each premise,
+//! and the trace of a `la_generic` step contains the coefficient of each literal and the final
+//! disequality. These traces can be used to audit the checker independently, by redoing each
+//! computation with another tool.
+//!
+//! The trace is written in the JSON Lines format, with one object per step, like this:
+//!
+//! ```text
+//! {"id":"t3","rule":"resolution","entries":[{"kind":"resolution","premise":"t2","literal":"p"}]}
+//! ```
+//!
+//! Steps that are not checked, like steps skipped when sampling or that use unknown rules, are not
+//! written. Most rules don't record any entries, in which case `entries` is empty.
+
+use crate::{ast::*, num::Rational};
+use std::{fmt, io};
+
+/// A fact recorded by a rule while checking a step.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEntry {
+    /// The literal `literal` of premise `premise` was resolved against its negation in the clause
+    /// derived so far.
+    Resolution { premise: String, literal: Rc<Term> },
+
+    /// The resolution step was checked using reverse unit propagation, instead of by finding the
+    /// pivots.
+    ReverseUnitPropagation,
+
+    /// In a linear arithmetic step, the literal `literal` was multiplied by `coefficient`.
+    Coefficient {
+        literal: Rc<Term>,
+        coefficient: Rational,
+    },
+
+    /// In a linear arithmetic step, the disequality obtained by adding all literals, multiplied by
+    /// their coefficients.
+    Disequality(String),
+}
+
+/// The entries recorded while checking the current step.
+#[derive(Debug, Default)]
+pub struct StepTrace {
+    enabled: bool,
+    active: bool,
+    entries: Vec<TraceEntry>,
+}
+
+impl StepTrace {
+    pub(super) fn new(enabled: bool) -> Self {
+        Self { enabled, ..Self::default() }
+    }
+
+    /// Records an entry for the current step. The entry is only built if tracing is enabled.
+    pub fn record(&mut self, entry: impl FnOnce() -> TraceEntry) {
+        if self.active {
+            self.entries.push(entry());
+        }
+    }
+
+    /// Discards the entries recorded so far for the current step.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Starts recording the entries of a step that is being checked by a rule.
+    pub(super) fn begin(&mut self) {
+        self.entries.clear();
+        self.active = self.enabled;
+    }
+
+    /// Stops recording. Returns the recorded entries if a step was being recorded.
+    pub(super) fn end(&mut self) -> Option<&[TraceEntry]> {
+        let was_active = std::mem::replace(&mut self.active, false);
+        was_active.then(|| self.entries.as_slice())
+    }
+}
+
+/// The destination of the trace.
+pub struct TraceOutput<'c>(pub &'c mut dyn io::Write);
+
+impl fmt::Debug for TraceOutput<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TraceOutput")
+    }
+}
+
+/// A wrapper struct that prints a string as a JSON string literal.
+pub(super) struct JsonString<'a>(pub(super) &'a str);
+
+impl fmt::Display for JsonString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in self.0.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\t' => write!(f, "\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    /// Prints the entry as a JSON object.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceEntry::Resolution { premise, literal } => write!(
+                f,
+                "{{\"kind\":\"resolution\",\"premise\":{},\"literal\":{}}}",
+                JsonString(premise),
+                JsonString(&literal.to_string()),
+            ),
+            TraceEntry::ReverseUnitPropagation => write!(f, "{{\"kind\":\"rup\"}}"),
+            TraceEntry::Coefficient { literal, coefficient } => write!(
+                f,
+                "{{\"kind\":\"coefficient\",\"literal\":{},\"coefficient\":{}}}",
+                JsonString(&literal.to_string()),
+                JsonString(&coefficient.to_string()),
+            ),
+            TraceEntry::Disequality(d) => {
+                write!(
+                    f,
+                    "{{\"kind\":\"disequality\",\"result\":{}}}",
+                    JsonString(d)
+                )
+            }
+        }
+    }
+}
+
+/// Writes the trace of a step as a line containing a JSON object.
+pub fn write_step_trace(
+    dest: &mut dyn io::Write,
+    id: &str,
+    rule: &str,
+    entries: &[TraceEntry],
+) -> io::Result<()> {
+    write!(
+        dest,
+        "{{\"id\":{},\"rule\":{},\"entries\":[",
+        JsonString(id),
+        JsonString(rule)
+    )?;
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            write!(dest, ",")?;
+        }
+        write!(dest, "{}", entry)?;
+    }
+    writeln!(dest, "]}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        checker::{Config, ProofChecker},
+        parser::parse_instance,
+    };
+
+    #[test]
+    fn test_trace() {
+        let problem = "
+            (declare-fun p () Bool)
+            (declare-fun q () Bool)
+            (assert (or p q))
+            (assert (not p))
+            (assert (not q))
+        ";
+        let proof = "
+            (assume h1 (or p q))
+            (assume h2 (not p))
+            (assume h3 (not q))
+            (step t4 (cl p q) :rule or :premises (h1))
+            (step t5 (cl) :rule resolution :premises (t4 h2 h3))
+            (step t6 (cl) :rule hole)
+        ";
+        let (prelude, proof, mut pool) =
+            parse_instance(problem.as_bytes(), proof.as_bytes(), Default::default()).unwrap();
+        let mut output = Vec::new();
+        let config = Config {
+            trace: Some(TraceOutput(&mut output)),
+            ..Default::default()
+        };
+        let result = ProofChecker::new(&mut pool, config, prelude).check(&proof);
+        assert!(matches!(result, Ok(true)));
+
+        let expected = [
+            r#"{"id":"t4","rule":"or","entries":[]}"#,
+            concat!(
+                r#"{"id":"t5","rule":"resolution","entries":["#,
+                r#"{"kind":"resolution","premise":"h2","literal":"(not p)"},"#,
+                r#"{"kind":"resolution","premise":"h3","literal":"(not q)"}]}"#,
+            ),
+            r#"{"id":"t6","rule":"hole","entries":[]}"#,
+        ];
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+
+        let mut dest = Vec::new();
+        write_step_trace(&mut dest, "t\"1", "hole", &[]).unwrap();
+        assert_eq!(
+            dest,
+            b"{\"id\":\"t\\\"1\",\"rule\":\"hole\",\"entries\":[]}\n"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Rendering of errors as diagnostics with an excerpt of the source, in the style of the Rust
+//! compiler. A diagnostic shows the line where the error happened, with a caret under the offending
+//! position and a label that explains the error:
+//!
+//! ```text
+//! error: checking failed on step 't2' with rule 'resolution'
+//!  --> proof.alethe:2:1
+//!   |
+//! 2 | (step t2 (cl a) :rule resolution :premises (h1))
+//!   | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ pivot was not eliminated: '(not a)'
+//!   = note: premise 'h1': (cl (not a))
+//! ```
+//!
+//! Errors don't record the input they were found in, so the caller is responsible for giving the
+//! right source to `Diagnostic::render`. Checker errors are always in the proof, but parser errors
+//! may also be in the problem. See `Diagnostic::is_parser_error`.
+
+use crate::{ast, parser::ParserError, parser::Position, Error};
+use std::fmt::Write;
+
+/// A label attached to a range of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// The position of the first character that is marked.
+    pub start: Position,
+
+    /// The position of the last character that is marked. If this is `None`, only the character at
+    /// `start` is marked. If this is on a later line than `start`, the rest of the first line is
+    /// marked.
+    pub end: Option<Position>,
+
+    pub text: String,
+}
+
+/// An error, prepared to be rendered with an excerpt of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The main message, which is printed after `error: `.
+    pub message: String,
+
+    /// The label marking where the error happened, if its position is known.
+    pub label: Option<Label>,
+
+    /// Additional information printed after the excerpt, like the premises of a step.
+    pub notes: Vec<String>,
+
+    /// Whether the error was found by the parser. In that case, it may be in any of the inputs
+    /// given to the parser.
+    pub is_parser_error: bool,
+}
+
+impl Diagnostic {
+    /// Returns the diagnostics for an error. Errors that group many errors, found when recovering
+    /// from parser errors or when the checker keeps going, result in one diagnostic for each of
+    /// them.
+    pub fn from_error(error: &Error) -> Vec<Diagnostic> {
+        match error {
+            Error::Parser(e, pos) => vec![Self::from_parser_error(e, *pos)],
+            Error::ParserErrors(errors) => errors
+                .iter()
+                .map(|(e, pos)| Self::from_parser_error(e, *pos))
+                .collect(),
+            Error::Checker { inner, rule, step, span, premises } => {
+                let message = format!("checking failed on step '{}' with rule '{}'", step, rule);
+                let notes = premises
+                    .iter()
+                    .map(|(id, clause)| format!("premise '{}': {}", id, ast::ElidedClause(clause)))
+                    .collect();
+                let (message, label) = match span {
+                    Some(span) => {
+                        let label = Label {
+                            start: span.start,
+                            end: Some(span.end),
+                            text: inner.to_string(),
+                        };
+                        (message, Some(label))
+                    }
+                    None => (format!("{}: {}", message, inner), None),
+                };
+                vec![Diagnostic {
+                    message,
+                    label,
+                    notes,
+                    is_parser_error: false,
+                }]
+            }
+            Error::CheckerErrors(errors) => errors.iter().flat_map(Self::from_error).collect(),
+            other => vec![Diagnostic {
+                message: other.to_string(),
+                label: None,
+                notes: Vec::new(),
+                is_parser_error: false,
+            }],
+        }
+    }
+
+    fn from_parser_error(error: &ParserError, pos: Position) -> Self {
+        // Unclosed subproofs are only found at the end of the input, so their position doesn't
+        // point to anything useful
+        let (message, label) = if matches!(error, ParserError::UnclosedSubproof(_)) {
+            (format!("parser error: {}", error), None)
+        } else {
+            let label = Label {
+                start: pos,
+                end: None,
+                text: error.to_string(),
+            };
+            ("parser error".to_owned(), Some(label))
+        };
+        Diagnostic {
+            message,
+            label,
+            notes: Vec::new(),
+            is_parser_error: true,
+        }
+    }
+
+    /// Renders the diagnostic, showing the line of `source` where the error happened. The name of
+    /// the source, usually a file path, is printed before the excerpt. If the position of the error
+    /// is outside of the source, the excerpt is omitted.
+    pub fn render(&self, source_name: &str, source: &str) -> String {
+        let mut result = format!("error: {}\n", self.message);
+        let label = match &self.label {
+            Some(l) => l,
+            None => {
+                self.render_notes(&mut result, 0);
+                return result;
+            }
+        };
+        let (line_number, column) = label.start;
+        let gutter = line_number.to_string().len();
+        let pad = " ".repeat(gutter);
+        writeln!(
+            result,
+            "{}--> {}:{}:{}",
+            pad, source_name, line_number, column
+        )
+        .unwrap();
+
+        // The byte order mark is not counted in the columns, so it is removed from the excerpt
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+        let line = line_number
+            .checked_sub(1)
+            .and_then(|i| source.lines().nth(i));
+        match line {
+            Some(line) => {
+                // Each character counts as a single column, so tabs are replaced by a single space
+                // to keep the markers aligned with the excerpt
+                let line: String = line
+                    .chars()
+                    .map(|c| if c == '\t' { ' ' } else { c })
+                    .collect();
+                let line_length = line.chars().count();
+                let marker_end = match label.end {
+                    Some((end_line, end_column)) if end_line == line_number => end_column,
+                    Some(_) => line_length,
+                    None => column,
+                };
+                let marker_length = (marker_end + 1).saturating_sub(column).max(1);
+                writeln!(result, "{} |", pad).unwrap();
+                writeln!(result, "{} | {}", line_number, line).unwrap();
+                writeln!(
+                    result,
+                    "{} | {}{} {}",
+                    pad,
+                    " ".repeat(column.saturating_sub(1)),
+                    "^".repeat(marker_length),
+                    label.text
+                )
+                .unwrap();
+            }
+            None => writeln!(result, "{} = {}", pad, label.text).unwrap(),
+        }
+        self.render_notes(&mut result, gutter);
+        result
+    }
+
+    fn render_notes(&self, dest: &mut String, gutter: usize) {
+        for note in &self.notes {
+            writeln!(dest, "{} = note: {}", " ".repeat(gutter), note).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CarcaraOptions;
+
+    fn render(problem: &str, proof: &str) -> Vec<String> {
+        let error = crate::check(
+            problem.as_bytes(),
+            proof.as_bytes(),
+            CarcaraOptions::default(),
+        )
+        .unwrap_err();
+        Diagnostic::from_error(&error)
+            .iter()
+            .map(|d| d.render("proof.alethe", proof))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_diagnostics() {
+        let problem = "(declare-const a Bool) (assert (not a))";
+
+        let proof = "(assume h1 (not a))\n(step t2 (cl a) :rule resolution :premises (h1))\n";
+        let expected = "\
+error: checking failed on step 't2' with rule 'resolution'
+ --> proof.alethe:2:1
+  |
+2 | (step t2 (cl a) :rule resolution :premises (h1))
+  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ pivot was not eliminated: '(not a)'
+  = note: premise 'h1': (cl (not a))
+";
+        assert_eq!(render(problem, proof), [expected]);
+
+        let proof = "(assume h1 (not a))\n(step t2 (cl)\n\t:rule resolution :premises (h1 t9))\n";
+        let expected = "\
+error: parser error
+ --> proof.alethe:3:33
+  |
+3 |  :rule resolution :premises (h1 t9))
+  |                                 ^ step id 't9' is not defined
+";
+        assert_eq!(render(problem, proof), [expected]);
+
+        let proof = "(assume h1 (not a))\n(anchor :step t2)\n";
+        let expected = "error: parser error: subproof 't2' was not closed\n";
+        assert_eq!(render(problem, proof), [expected]);
+
+        // If the position is outside of the source, the excerpt is omitted
+        let diagnostic = Diagnostic {
+            message: "parser error".to_owned(),
+            label: Some(Label {
+                start: (12, 1),
+                end: None,
+                text: "unexpected end of input".to_owned(),
+            }),
+            notes: Vec::new(),
+            is_parser_error: true,
+        };
+        let expected = "\
+error: parser error
+  --> proof.alethe:12:1
+   = unexpected end of input
+";
+        assert_eq!(diagnostic.render("proof.alethe", "(assume h1 a)"), expected);
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(clippy::disallowed_methods)]
 #![deny(clippy::self_named_module_files)]
 #![deny(clippy::undocumented_unsafe_blocks)]
@@ -7,7 +8,6 @@
 #![warn(clippy::dbg_macro)]
 #![warn(clippy::doc_markdown)]
 #![warn(clippy::equatable_if_let)]
-#![warn(clippy::eval_order_dependence)]
 #![warn(clippy::explicit_into_iter_loop)]
 #![warn(clippy::explicit_iter_loop)]
 #![warn(clippy::from_iter_instead_of_collect)]
@@ -23,61 +23,197 @@
 #![warn(clippy::manual_ok_or)]
 #![warn(clippy::map_unwrap_or)]
 #![warn(clippy::match_wildcard_for_single_variants)]
+#![warn(clippy::mixed_read_write_in_expression)]
 #![warn(clippy::multiple_crate_versions)]
 #![warn(clippy::redundant_closure_for_method_calls)]
 #![warn(clippy::redundant_pub_crate)]
 #![warn(clippy::semicolon_if_nothing_returned)]
 #![warn(clippy::str_to_string)]
-#![warn(clippy::string_to_string)]
 #![warn(clippy::trivially_copy_pass_by_ref)]
 #![warn(clippy::unnecessary_wraps)]
 #![warn(clippy::unnested_or_patterns)]
 #![warn(clippy::unused_self)]
 
+// Without the default `std` feature, only the `ast` and `num` modules are available, and the crate
+// can be built using just the `alloc` crate. In this configuration, a term pool can be used to
+// construct, substitute and compare terms, but not to parse or check proofs.
+extern crate alloc;
+
 #[macro_use]
 pub mod ast;
+#[cfg(feature = "std")]
 pub mod benchmarking;
+#[cfg(feature = "std")]
 pub mod checker;
+#[cfg(feature = "std")]
+pub mod diagnostics;
+pub mod num;
+#[cfg(feature = "std")]
 pub mod parser;
+// Many of the utilities are only used by the parser and checker
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
 mod utils;
 
+#[cfg(feature = "std")]
 use ast::ProofCommand;
+#[cfg(feature = "std")]
 use checker::error::CheckerError;
+#[cfg(feature = "std")]
 use parser::ParserError;
+#[cfg(feature = "std")]
 use parser::Position;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use thiserror::Error;
 
+#[cfg(feature = "std")]
 pub type CarcaraResult<T> = Result<T, Error>;
 
+#[cfg(feature = "std")]
+#[derive(Clone)]
 pub struct CarcaraOptions {
     pub apply_function_defs: bool,
     pub expand_lets: bool,
     pub allow_int_real_subtyping: bool,
+
+    /// If `true`, `Int` arguments of arithmetic operators are promoted to `Real` when mixed with
+    /// `Real` arguments. See `parser::ParserConfig::coerce_int_to_real`.
+    pub coerce_int_to_real: bool,
+
     pub check_lia_using_cvc5: bool,
     pub strict: bool,
     pub skip_unknown_rules: bool,
+    pub audit_choice_terms: bool,
+    pub commutative_transitivity: bool,
+    pub sampling: Option<checker::SamplingConfig>,
+
+    /// The number of threads used to lex the proof. If this is greater than one, the proof is read
+    /// into memory and split into chunks that are lexed in parallel.
+    pub lexer_threads: usize,
+
+    /// How non-ASCII characters in symbols are handled by the lexer.
+    pub unicode_policy: parser::UnicodePolicy,
+
+    /// If `true`, the parser collects all errors in the proof, instead of stopping at the first
+    /// one. See `parser::ParserConfig::recover_from_errors`.
+    pub recover_from_errors: bool,
+
+    /// If `true`, unknown attributes in annotated terms are ignored instead of rejected. See
+    /// `parser::ParserConfig::ignore_unknown_attributes`.
+    pub ignore_unknown_attributes: bool,
+
+    /// The solver that produced the proof, whose differences from the Alethe specification are
+    /// normalized by the parser. See `parser::Producer`.
+    pub producer: parser::Producer,
+
+    /// The severity of each category of checker errors. See `checker::SeverityLevels`.
+    pub severities: checker::SeverityLevels,
+
+    /// If this is not `None`, a trace of the computation done to check each step is written to
+    /// this file. See `checker::TraceOutput`.
+    pub trace: Option<std::path::PathBuf>,
+
+    /// If `true`, the result of each step is written to the standard output as soon as it is
+    /// checked. See `checker::ResultStream`.
+    pub stream_results: bool,
+
+    /// If this is not `None`, the checker keeps going after invalid steps, and reports up to this
+    /// many errors at the end. See `checker::Config::keep_going`.
+    pub keep_going: Option<usize>,
+
+    /// If this is not `None`, a warning is logged for each step that takes longer than this to
+    /// check. See `checker::Config::slow_step_threshold`.
+    pub slow_step_threshold: Option<std::time::Duration>,
+
+    /// The limits on the inputs accepted by the parser. See `parser::ResourceLimits`.
+    pub parser_limits: parser::ResourceLimits,
+
+    /// The names of rules whose steps are accepted without being checked. See
+    /// `checker::Config::trusted_rules`.
+    pub trusted_rules: Vec<String>,
 }
 
+#[cfg(feature = "std")]
 impl Default for CarcaraOptions {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(feature = "std")]
 impl CarcaraOptions {
     fn new() -> Self {
         Self {
             apply_function_defs: true,
             expand_lets: false,
             allow_int_real_subtyping: false,
+            coerce_int_to_real: false,
             check_lia_using_cvc5: false,
             strict: false,
             skip_unknown_rules: false,
+            audit_choice_terms: false,
+            commutative_transitivity: false,
+            sampling: None,
+            lexer_threads: 1,
+            unicode_policy: parser::UnicodePolicy::default(),
+            recover_from_errors: false,
+            ignore_unknown_attributes: false,
+            producer: parser::Producer::default(),
+            severities: checker::SeverityLevels::default(),
+            trace: None,
+            stream_results: false,
+            keep_going: None,
+            slow_step_threshold: None,
+            parser_limits: parser::ResourceLimits::default(),
+            trusted_rules: Vec::new(),
+        }
+    }
+
+    /// Returns the configuration of the parser that corresponds to these options.
+    pub fn parser_config(&self) -> parser::ParserConfig {
+        parser::ParserConfig {
+            apply_function_defs: self.apply_function_defs,
+            expand_lets: self.expand_lets,
+            allow_int_real_subtyping: self.allow_int_real_subtyping,
+            coerce_int_to_real: self.coerce_int_to_real,
+            unicode_policy: self.unicode_policy,
+            recover_from_errors: self.recover_from_errors,
+            ignore_unknown_attributes: self.ignore_unknown_attributes,
+            producer: self.producer,
+            limits: self.parser_limits,
+            ..Default::default()
         }
     }
 }
 
+#[cfg(feature = "std")]
+struct DisplayPremises<'a>(&'a [(String, Vec<ast::Rc<ast::Term>>)]);
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for DisplayPremises<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (id, clause) in self.0 {
+            write!(f, "\npremise '{}': {}", id, ast::ElidedClause(clause))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+struct DisplaySpan<'a>(&'a Option<Box<ast::SourceSpan>>);
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for DisplaySpan<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0 {
+            Some(span) => write!(f, " (on line {}, column {})", span.start.0, span.start.1),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 fn wrap_parser_error_message(e: &ParserError, pos: &Position) -> String {
     // For unclosed subproof errors, we don't print the position
     if matches!(e, ParserError::UnclosedSubproof(_)) {
@@ -87,6 +223,22 @@ fn wrap_parser_error_message(e: &ParserError, pos: &Position) -> String {
     }
 }
 
+#[cfg(feature = "std")]
+fn wrap_parser_errors_message(errors: &[(ParserError, Position)]) -> String {
+    let messages: Vec<_> = errors
+        .iter()
+        .map(|(e, pos)| wrap_parser_error_message(e, pos))
+        .collect();
+    messages.join("\n")
+}
+
+#[cfg(feature = "std")]
+fn wrap_checker_errors_message(errors: &[Error]) -> String {
+    let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    messages.join("\n")
+}
+
+#[cfg(feature = "std")]
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("IO error: {0}")]
@@ -95,49 +247,200 @@ pub enum Error {
     #[error("{}", wrap_parser_error_message(.0, .1))]
     Parser(ParserError, Position),
 
-    #[error("checking failed on step '{step}' with rule '{rule}': {inner}")]
+    /// Many parser errors, found when the parser is recovering from errors. See
+    /// `parser::ParserConfig::recover_from_errors`.
+    #[error("{}", wrap_parser_errors_message(.0))]
+    ParserErrors(Vec<(ParserError, Position)>),
+
+    #[error(
+        "checking failed on step '{step}' with rule '{rule}'{}: {inner}{}",
+        DisplaySpan(.span),
+        DisplayPremises(.premises)
+    )]
     Checker {
-        inner: CheckerError,
+        inner: Box<CheckerError>,
         rule: String,
         step: String,
+
+        /// The location of the command in the proof file, if it is known.
+        span: Option<Box<ast::SourceSpan>>,
+
+        /// The id and clause of each premise of the step, which are printed after the error
+        /// message. Large clauses are abbreviated using `ast::ElidedClause`.
+        premises: Vec<(String, Vec<ast::Rc<ast::Term>>)>,
     },
 
+    /// Many checker errors, found when the checker keeps going after invalid steps. See
+    /// `checker::Config::keep_going`.
+    #[error("{}", wrap_checker_errors_message(.0))]
+    CheckerErrors(Vec<Error>),
+
     // While this is a kind of checking error, it does not happen in a specific step like all other
     // checker errors, so we model it as a different variant
     #[error("checker error: proof does not conclude empty clause")]
     DoesNotReachEmptyClause,
+
+    // When a proof is checked as a lemma, its conclusion is the clause of its last command, so it
+    // must have at least one command
+    #[error("checker error: lemma proof has no commands")]
+    EmptyLemma,
 }
 
-pub fn check<T: io::BufRead>(
-    problem: T,
+#[cfg(feature = "std")]
+fn create_trace_file(
+    path: Option<std::path::PathBuf>,
+) -> io::Result<Option<io::BufWriter<std::fs::File>>> {
+    path.map(|p| std::fs::File::create(p).map(io::BufWriter::new))
+        .transpose()
+}
+
+#[cfg(feature = "std")]
+pub fn check<T: io::BufRead>(problem: T, proof: T, options: CarcaraOptions) -> Result<bool, Error> {
+    check_with_problems(vec![problem], proof, options)
+}
+
+/// Similar to `check`, but the problem is split into several inputs, whose declarations and
+/// assertions are merged. See `parser::parse_instance_with_problems` for details.
+#[cfg(feature = "std")]
+pub fn check_with_problems<T: io::BufRead>(
+    problems: Vec<T>,
     proof: T,
     CarcaraOptions {
         apply_function_defs,
         expand_lets,
         allow_int_real_subtyping,
+        coerce_int_to_real,
         check_lia_using_cvc5,
         strict,
         skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        lexer_threads,
+        unicode_policy,
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer,
+        severities,
+        trace,
+        stream_results,
+        keep_going,
+        slow_step_threshold,
+        parser_limits,
+        trusted_rules,
     }: CarcaraOptions,
 ) -> Result<bool, Error> {
-    let (prelude, proof, mut pool) = parser::parse_instance(
-        problem,
+    let (prelude, proof, mut pool) = parser::parse_instance_with_problems(
+        problems,
         proof,
+        parser::ParserConfig {
+            apply_function_defs,
+            expand_lets,
+            allow_int_real_subtyping,
+            coerce_int_to_real,
+            unicode_policy,
+            recover_from_errors,
+            ignore_unknown_attributes,
+            producer,
+            limits: parser_limits,
+            ..Default::default()
+        },
+        lexer_threads,
+    )?;
+
+    let mut trace_file = create_trace_file(trace)?;
+    let mut stdout = io::stdout();
+    let config = checker::Config {
+        strict,
+        skip_unknown_rules,
+        is_running_test: false,
+        statistics: None,
+        check_lia_using_cvc5,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        severities,
+        trace: trace_file.as_mut().map(|f| checker::TraceOutput(f)),
+        stream_results: stream_results.then(|| checker::ResultStream(&mut stdout)),
+        keep_going,
+        slow_step_threshold,
+        trusted_rules: trusted_rules.into_iter().collect(),
+    };
+    checker::ProofChecker::new(&mut pool, config, prelude).check(&proof)
+}
+
+/// Checks a proof as a lemma, whose `assume` commands are taken as hypotheses instead of being
+/// matched against the problem assertions. The problem is still used for its declarations. See
+/// `checker::ProofChecker::check_lemma` for details.
+#[cfg(feature = "std")]
+pub fn check_lemma<T: io::BufRead>(
+    problem: T,
+    proof: T,
+    CarcaraOptions {
         apply_function_defs,
         expand_lets,
         allow_int_real_subtyping,
+        coerce_int_to_real,
+        check_lia_using_cvc5,
+        strict,
+        skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        lexer_threads,
+        unicode_policy,
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer,
+        severities,
+        trace,
+        stream_results,
+        keep_going,
+        slow_step_threshold,
+        parser_limits,
+        trusted_rules,
+    }: CarcaraOptions,
+) -> Result<(bool, checker::Lemma), Error> {
+    let (prelude, proof, mut pool) = parser::parse_instance_with_problems(
+        vec![problem],
+        proof,
+        parser::ParserConfig {
+            apply_function_defs,
+            expand_lets,
+            allow_int_real_subtyping,
+            coerce_int_to_real,
+            unicode_policy,
+            recover_from_errors,
+            ignore_unknown_attributes,
+            producer,
+            limits: parser_limits,
+            ..Default::default()
+        },
+        lexer_threads,
     )?;
 
+    let mut trace_file = create_trace_file(trace)?;
+    let mut stdout = io::stdout();
     let config = checker::Config {
         strict,
         skip_unknown_rules,
         is_running_test: false,
         statistics: None,
         check_lia_using_cvc5,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        severities,
+        trace: trace_file.as_mut().map(|f| checker::TraceOutput(f)),
+        stream_results: stream_results.then(|| checker::ResultStream(&mut stdout)),
+        keep_going,
+        slow_step_threshold,
+        trusted_rules: trusted_rules.into_iter().collect(),
     };
-    checker::ProofChecker::new(&mut pool, config, prelude).check(&proof)
+    checker::ProofChecker::new(&mut pool, config, prelude).check_lemma(&proof)
 }
 
+#[cfg(feature = "std")]
 pub fn check_and_elaborate<T: io::BufRead>(
     problem: T,
     proof: T,
@@ -145,31 +448,69 @@ pub fn check_and_elaborate<T: io::BufRead>(
         apply_function_defs,
         expand_lets,
         allow_int_real_subtyping,
+        coerce_int_to_real,
         check_lia_using_cvc5,
         strict,
         skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        lexer_threads,
+        unicode_policy,
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer,
+        severities,
+        trace,
+        stream_results,
+        keep_going,
+        slow_step_threshold,
+        parser_limits,
+        trusted_rules,
     }: CarcaraOptions,
 ) -> Result<Vec<ProofCommand>, Error> {
-    let (prelude, proof, mut pool) = parser::parse_instance(
-        problem,
+    let (prelude, proof, mut pool) = parser::parse_instance_with_problems(
+        vec![problem],
         proof,
-        apply_function_defs,
-        expand_lets,
-        allow_int_real_subtyping,
+        parser::ParserConfig {
+            apply_function_defs,
+            expand_lets,
+            allow_int_real_subtyping,
+            coerce_int_to_real,
+            unicode_policy,
+            recover_from_errors,
+            ignore_unknown_attributes,
+            producer,
+            limits: parser_limits,
+            ..Default::default()
+        },
+        lexer_threads,
     )?;
 
+    let mut trace_file = create_trace_file(trace)?;
+    let mut stdout = io::stdout();
     let config = checker::Config {
         strict,
         skip_unknown_rules,
         is_running_test: false,
         statistics: None,
         check_lia_using_cvc5,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        severities,
+        trace: trace_file.as_mut().map(|f| checker::TraceOutput(f)),
+        stream_results: stream_results.then(|| checker::ResultStream(&mut stdout)),
+        keep_going,
+        slow_step_threshold,
+        trusted_rules: trusted_rules.into_iter().collect(),
     };
     checker::ProofChecker::new(&mut pool, config, prelude)
         .check_and_elaborate(proof)
         .map(|p| p.commands)
 }
 
+#[cfg(feature = "std")]
 pub fn generate_lia_smt_instances<T: io::BufRead>(
     problem: T,
     proof: T,
@@ -178,16 +519,19 @@ pub fn generate_lia_smt_instances<T: io::BufRead>(
     allow_int_real_subtyping: bool,
     use_sharing: bool,
 ) -> Result<Vec<(String, String)>, Error> {
-    let (prelude, proof, _) = parser::parse_instance(
-        problem,
-        proof,
+    let config = parser::ParserConfig {
         apply_function_defs,
         expand_lets,
         allow_int_real_subtyping,
-    )?;
+        ..Default::default()
+    };
+    let (prelude, proof, _) = parser::parse_instance(problem, proof, config)?;
     checker::generate_lia_smt_instances(prelude, &proof, use_sharing)
 }
 
+#[cfg(feature = "std")]
+/// Compresses the proof, and checks the original proof. If it is valid, returns whether it is
+/// holey, and the commands of the compressed proof.
 pub fn compress<T: io::BufRead>(
     problem: T,
     proof: T,
@@ -195,28 +539,65 @@ pub fn compress<T: io::BufRead>(
         apply_function_defs,
         expand_lets : _,
         allow_int_real_subtyping,
+        coerce_int_to_real,
         check_lia_using_cvc5 : _,
         strict,
         skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        lexer_threads,
+        unicode_policy,
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer,
+        severities,
+        trace,
+        stream_results,
+        keep_going,
+        slow_step_threshold,
+        parser_limits,
+        trusted_rules,
     }: CarcaraOptions,
-) -> Result<bool, Error> {
-    let (prelude, proof, mut pool) = parser::parse_instance(
-        problem,
+) -> Result<(bool, Vec<ProofCommand>), Error> {
+    let (prelude, proof, mut pool) = parser::parse_instance_with_problems(
+        vec![problem],
         proof,
-        apply_function_defs,
-        false,
-        allow_int_real_subtyping,
+        parser::ParserConfig {
+            apply_function_defs,
+            allow_int_real_subtyping,
+            coerce_int_to_real,
+            unicode_policy,
+            recover_from_errors,
+            ignore_unknown_attributes,
+            producer,
+            limits: parser_limits,
+            ..Default::default()
+        },
+        lexer_threads,
     )?;
 
+    let mut trace_file = create_trace_file(trace)?;
+    let mut stdout = io::stdout();
     let config = checker::Config {
         strict,
         skip_unknown_rules,
         is_running_test: false,
         statistics: None,
         check_lia_using_cvc5: true,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        severities,
+        trace: trace_file.as_mut().map(|f| checker::TraceOutput(f)),
+        stream_results: stream_results.then(|| checker::ResultStream(&mut stdout)),
+        keep_going,
+        slow_step_threshold,
+        trusted_rules: trusted_rules.into_iter().collect(),
     };
 
-    checker::compression::compress_proof(&proof, &mut pool);
+    let compressed = checker::compression::compress_proof(&proof, &mut pool);
 
-    checker::ProofChecker::new(&mut pool, config, prelude).check(&proof)
+    let is_holey = checker::ProofChecker::new(&mut pool, config, prelude).check(&proof)?;
+    Ok((is_holey, compressed))
 }
\ No newline at end of file
@@ -0,0 +1,415 @@
+//! A fixed-precision replacement for the subset of the `rug` API used by the crate. Values are
+//! stored as 128-bit integers, and any operation that overflows will panic.
+
+use alloc::{string::String, vec::Vec};
+use core::{cmp::Ordering, fmt, ops, str::FromStr};
+
+const OVERFLOW_MESSAGE: &str =
+    "arithmetic overflow in fixed-precision numeral, consider enabling the `arith` feature";
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+/// Mirrors `rug::ops::Pow`.
+pub trait Pow<Rhs> {
+    type Output;
+    fn pow(self, rhs: Rhs) -> Self::Output;
+}
+
+/// Mirrors `rug::ops::NegAssign`.
+pub trait NegAssign {
+    fn neg_assign(&mut self);
+}
+
+/// A fixed-precision integer.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Integer(i128);
+
+/// The error returned when parsing an `Integer` fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntegerError;
+
+impl fmt::Display for ParseIntegerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid or too large integer")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseIntegerError {}
+
+impl Integer {
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn from_str_radix(src: &str, radix: i32) -> Result<Self, ParseIntegerError> {
+        i128::from_str_radix(src, radix as u32)
+            .map(Self)
+            .map_err(|_| ParseIntegerError)
+    }
+
+    pub fn to_usize(&self) -> Option<usize> {
+        usize::try_from(self.0).ok()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        u64::try_from(self.0).ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0 as f64
+    }
+
+    pub fn to_string_radix(&self, radix: i32) -> String {
+        let radix = radix as u32;
+        let mut n = self.0.unsigned_abs();
+        let mut digits = Vec::new();
+        loop {
+            digits.push(char::from_digit((n % u128::from(radix)) as u32, radix).unwrap());
+            n /= u128::from(radix);
+            if n == 0 {
+                break;
+            }
+        }
+        if self.0 < 0 {
+            digits.push('-');
+        }
+        digits.into_iter().rev().collect()
+    }
+
+    pub fn gcd_mut(&mut self, other: &Self) {
+        self.0 = gcd(self.0, other.0);
+    }
+}
+
+impl FromStr for Integer {
+    type Err = ParseIntegerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self).map_err(|_| ParseIntegerError)
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+// Like in `rug`, the `Debug` output is the bare number, so it can be used when printing terms
+impl fmt::Debug for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Pow<u32> for Integer {
+    type Output = Self;
+
+    fn pow(self, rhs: u32) -> Self {
+        Self(self.0.checked_pow(rhs).expect(OVERFLOW_MESSAGE))
+    }
+}
+
+impl NegAssign for Integer {
+    fn neg_assign(&mut self) {
+        self.0 = self.0.checked_neg().expect(OVERFLOW_MESSAGE);
+    }
+}
+
+/// A fixed-precision rational number. The fraction is always kept in lowest terms, with a
+/// positive denominator.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: Integer,
+    denom: Integer,
+}
+
+impl Default for Rational {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rational {
+    pub fn new() -> Self {
+        Self {
+            numer: Integer(0),
+            denom: Integer(1),
+        }
+    }
+
+    fn from_parts(numer: i128, denom: i128) -> Self {
+        assert!(denom != 0, "division by zero");
+        let sign = if denom < 0 { -1 } else { 1 };
+        let d = gcd(numer, denom);
+        Self {
+            numer: Integer(sign * numer / d),
+            denom: Integer(sign * denom / d),
+        }
+    }
+
+    pub fn numer(&self) -> &Integer {
+        &self.numer
+    }
+
+    pub fn denom(&self) -> &Integer {
+        &self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom.0 == 1
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numer.0 as f64 / self.denom.0 as f64
+    }
+
+    pub fn abs(mut self) -> Self {
+        if self.numer.0 < 0 {
+            self.neg_assign();
+        }
+        self
+    }
+
+    pub fn as_neg(&self) -> Self {
+        -self.clone()
+    }
+
+    pub fn floor_mut(&mut self) {
+        *self = Self::from(self.numer.0.div_euclid(self.denom.0));
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl fmt::Debug for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let a = self
+            .numer
+            .0
+            .checked_mul(other.denom.0)
+            .expect(OVERFLOW_MESSAGE);
+        let b = other
+            .numer
+            .0
+            .checked_mul(self.denom.0)
+            .expect(OVERFLOW_MESSAGE);
+        a.cmp(&b)
+    }
+}
+
+impl NegAssign for Rational {
+    fn neg_assign(&mut self) {
+        self.numer.neg_assign();
+    }
+}
+
+impl ops::Neg for Rational {
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        self.neg_assign();
+        self
+    }
+}
+
+impl ops::Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        -self.clone()
+    }
+}
+
+macro_rules! impl_from_primitive {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Integer {
+            fn from(value: $t) -> Self {
+                Self(value as i128)
+            }
+        }
+
+        impl From<$t> for Rational {
+            fn from(value: $t) -> Self {
+                Self::from(Integer::from(value))
+            }
+        }
+
+        impl PartialEq<$t> for Integer {
+            fn eq(&self, other: &$t) -> bool {
+                self.0 == *other as i128
+            }
+        }
+
+        impl PartialEq<$t> for Rational {
+            fn eq(&self, other: &$t) -> bool {
+                self.is_integer() && self.numer == *other
+            }
+        }
+
+        impl PartialOrd<$t> for Rational {
+            fn partial_cmp(&self, other: &$t) -> Option<Ordering> {
+                Some(self.cmp(&Self::from(*other)))
+            }
+        }
+
+        impl ops::AddAssign<$t> for Rational {
+            fn add_assign(&mut self, rhs: $t) {
+                *self += Self::from(rhs);
+            }
+        }
+    )*};
+}
+
+impl_from_primitive!(i32, i64, i128, u32, u64, usize);
+
+impl From<Integer> for Rational {
+    fn from(value: Integer) -> Self {
+        Self { numer: value, denom: Integer(1) }
+    }
+}
+
+impl<N: Into<Integer>, D: Into<Integer>> From<(N, D)> for Rational {
+    fn from((numer, denom): (N, D)) -> Self {
+        Self::from_parts(numer.into().0, denom.into().0)
+    }
+}
+
+impl ops::Add for Rational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let numer = (self.numer.0.checked_mul(rhs.denom.0))
+            .zip(rhs.numer.0.checked_mul(self.denom.0))
+            .and_then(|(a, b)| a.checked_add(b));
+        let denom = self.denom.0.checked_mul(rhs.denom.0);
+        Self::from_parts(
+            numer.expect(OVERFLOW_MESSAGE),
+            denom.expect(OVERFLOW_MESSAGE),
+        )
+    }
+}
+
+impl ops::Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl ops::Mul for Rational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let numer = self.numer.0.checked_mul(rhs.numer.0);
+        let denom = self.denom.0.checked_mul(rhs.denom.0);
+        Self::from_parts(
+            numer.expect(OVERFLOW_MESSAGE),
+            denom.expect(OVERFLOW_MESSAGE),
+        )
+    }
+}
+
+impl ops::Div for Rational {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let numer = self.numer.0.checked_mul(rhs.denom.0);
+        let denom = self.denom.0.checked_mul(rhs.numer.0);
+        Self::from_parts(
+            numer.expect(OVERFLOW_MESSAGE),
+            denom.expect(OVERFLOW_MESSAGE),
+        )
+    }
+}
+
+// Every combination of owned and borrowed operands, as well as the compound assignment operators,
+// is implemented in terms of the owned operations above. Cloning is cheap, since values are just
+// pairs of 128-bit integers.
+macro_rules! impl_ref_ops {
+    ($($op:ident $method:ident $op_assign:ident $method_assign:ident),*) => {$(
+        impl ops::$op<&Rational> for Rational {
+            type Output = Rational;
+
+            fn $method(self, rhs: &Rational) -> Rational {
+                ops::$op::$method(self, rhs.clone())
+            }
+        }
+
+        impl ops::$op<Rational> for &Rational {
+            type Output = Rational;
+
+            fn $method(self, rhs: Rational) -> Rational {
+                ops::$op::$method(self.clone(), rhs)
+            }
+        }
+
+        impl ops::$op<&Rational> for &Rational {
+            type Output = Rational;
+
+            fn $method(self, rhs: &Rational) -> Rational {
+                ops::$op::$method(self.clone(), rhs.clone())
+            }
+        }
+
+        impl ops::$op<Integer> for Rational {
+            type Output = Rational;
+
+            fn $method(self, rhs: Integer) -> Rational {
+                ops::$op::$method(self, Rational::from(rhs))
+            }
+        }
+
+        impl ops::$op_assign<Rational> for Rational {
+            fn $method_assign(&mut self, rhs: Rational) {
+                *self = ops::$op::$method(self.clone(), rhs);
+            }
+        }
+
+        impl ops::$op_assign<&Rational> for Rational {
+            fn $method_assign(&mut self, rhs: &Rational) {
+                *self = ops::$op::$method(self.clone(), rhs.clone());
+            }
+        }
+
+        impl ops::$op_assign<Integer> for Rational {
+            fn $method_assign(&mut self, rhs: Integer) {
+                *self = ops::$op::$method(self.clone(), Rational::from(rhs));
+            }
+        }
+    )*};
+}
+
+impl_ref_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign
+);
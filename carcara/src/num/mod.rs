@@ -0,0 +1,34 @@
+//! The numeric types used to represent integer and real constants.
+//!
+//! When the `arith` feature is enabled (the default), these are the arbitrary-precision `Integer`
+//! and `Rational` types from the `rug` crate. Otherwise, a fixed-precision fallback is used, which
+//! avoids depending on GMP. The fallback is only meant to represent numerals in propositional and
+//! uninterpreted function proofs, so the rules that do arithmetic reasoning are disabled without
+//! the `arith` feature.
+
+#[cfg(not(feature = "arith"))]
+mod fallback;
+
+#[cfg(feature = "arith")]
+pub use rug::{
+    ops::{NegAssign, Pow},
+    Integer, Rational,
+};
+
+#[cfg(not(feature = "arith"))]
+pub use fallback::{Integer, NegAssign, Pow, Rational};
+
+/// Computes the integer division and modulo of `a` by `b`, following the euclidean definition used
+/// by SMT-LIB, in which the remainder is always non-negative. Returns `None` if `b` is zero.
+pub fn euclidean_div_mod(a: &Rational, b: &Rational) -> Option<(Rational, Rational)> {
+    if *b == 0 {
+        return None;
+    }
+    let mut quotient = a.clone() / b.clone().abs();
+    quotient.floor_mut();
+    if *b < 0 {
+        quotient = -quotient;
+    }
+    let remainder = a.clone() - b.clone() * quotient.clone();
+    Some((quotient, remainder))
+}
@@ -0,0 +1,238 @@
+//! Lexing of large inputs using multiple threads. The input is split into chunks at the boundaries
+//! between top-level commands, each chunk is lexed by a separate thread, and the tokens are merged,
+//! in order, into a single stream that is consumed by the parser as usual.
+//!
+//! Only lexing is done in parallel. Parsing a command depends on the declarations made by the
+//! commands before it, and the terms created by the parser use non-atomic reference counting, so
+//! the parser state is never shared between threads.
+
+use super::{Lexer, LineIndex, Span, Token, UnicodePolicy};
+use crate::CarcaraResult;
+use std::{ops::Range, sync::Arc, thread};
+
+/// The minimum size of each chunk, in bytes. Splitting smaller inputs is not worth the cost of
+/// spawning the threads.
+const MIN_CHUNK_SIZE: usize = 1 << 16;
+
+/// The tokens of an input that was lexed ahead of time, together with its line index. This can be
+/// given to the parser using `ProofParser::parse_lexed_proof`.
+pub struct LexedInput {
+    pub(super) tokens: Vec<(Token, Span)>,
+    pub(super) line_index: LineIndex,
+
+    /// The length of the input, in bytes, which is where the `Eof` token is.
+    pub(super) len: usize,
+}
+
+/// Lexes the whole input using up to `num_threads` threads. Fewer threads are used if the input
+/// is not large enough to be worth splitting.
+///
+/// If there is a lexer error in any of the chunks, the input is lexed again sequentially, so the
+/// error that is returned is always the first one in the input. Note that, since the whole input
+/// is lexed before parsing starts, a lexer error is returned even if the parser would have found
+/// an error earlier in the input. Non-ASCII characters in symbols are handled according to
+/// `unicode_policy`, as in `Lexer::with_unicode_policy`.
+pub fn lex_parallel(
+    input: Arc<str>,
+    num_threads: usize,
+    unicode_policy: UnicodePolicy,
+) -> CarcaraResult<LexedInput> {
+    let num_chunks = num_threads.min(input.len() / MIN_CHUNK_SIZE).max(1);
+    lex_chunks(input, num_chunks, unicode_policy)
+}
+
+/// Splits the input into `num_chunks` chunks, or fewer, and lexes each of them in a separate
+/// thread.
+fn lex_chunks(
+    input: Arc<str>,
+    num_chunks: usize,
+    unicode_policy: UnicodePolicy,
+) -> CarcaraResult<LexedInput> {
+    let chunks = split_into_chunks(&input, num_chunks);
+    if chunks.len() == 1 {
+        return lex_sequential(&input, unicode_policy);
+    }
+    let handles: Vec<_> = chunks
+        .iter()
+        .map(|range| {
+            let input = input.clone();
+            let range = range.clone();
+            thread::spawn(move || lex_chunk(&input, range, unicode_policy))
+        })
+        .collect();
+
+    let mut tokens = Vec::new();
+    let mut line_index = LineIndex::default();
+    for (handle, range) in handles.into_iter().zip(chunks) {
+        let (chunk_tokens, chunk_index) = match handle.join().ok().flatten() {
+            Some(lexed) => lexed,
+            None => return lex_sequential(&input, unicode_policy),
+        };
+        let continues_line = range.start > 0 && input.as_bytes()[range.start - 1] != b'\n';
+        line_index.extend_from_chunk(chunk_index, range.start, continues_line);
+        tokens.extend(chunk_tokens);
+    }
+    Ok(LexedInput {
+        tokens,
+        line_index,
+        len: input.len(),
+    })
+}
+
+/// Lexes the whole input in the current thread.
+fn lex_sequential(input: &str, unicode_policy: UnicodePolicy) -> CarcaraResult<LexedInput> {
+    let mut lexer = Lexer::with_unicode_policy(input.as_bytes(), unicode_policy)?;
+    let mut tokens = Vec::new();
+    loop {
+        let (token, span) = lexer.next_token()?;
+        if token == Token::Eof {
+            break;
+        }
+        tokens.push((token, span));
+    }
+    let line_index = lexer.into_line_index();
+    Ok(LexedInput {
+        tokens,
+        line_index,
+        len: input.len(),
+    })
+}
+
+/// Lexes a chunk of the input, and returns its tokens and line index. The spans of the tokens are
+/// offsets in the whole input, but the line index is relative to the start of the chunk. Returns
+/// `None` if there is a lexer error in the chunk.
+fn lex_chunk(
+    input: &str,
+    range: Range<usize>,
+    unicode_policy: UnicodePolicy,
+) -> Option<(Vec<(Token, Span)>, LineIndex)> {
+    let chunk = &input[range.clone()];
+
+    // The lexer ignores a byte order mark at the start of its input, but one in the middle of the
+    // input is an error
+    if range.start > 0 && chunk.starts_with('\u{feff}') {
+        return None;
+    }
+    let mut lexer = Lexer::with_unicode_policy(chunk.as_bytes(), unicode_policy).ok()?;
+    let mut tokens = Vec::new();
+    loop {
+        let (token, span) = lexer.next_token().ok()?;
+        if token == Token::Eof {
+            break;
+        }
+        let span = Span {
+            start: span.start + range.start,
+            end: span.end + range.start,
+        };
+        tokens.push((token, span));
+    }
+    Some((tokens, lexer.into_line_index()))
+}
+
+/// Splits the input into at most `num_chunks` chunks of roughly the same size. Each chunk ends at
+/// the end of a top-level command, so no token is split between two chunks.
+fn split_into_chunks(input: &str, num_chunks: usize) -> Vec<Range<usize>> {
+    let boundaries = command_boundaries(input.as_bytes());
+    let mut chunks = Vec::with_capacity(num_chunks);
+    let mut start = 0;
+    for i in 1..num_chunks {
+        let target = input.len() * i / num_chunks;
+        let next = boundaries.partition_point(|&b| b < target);
+        match boundaries.get(next) {
+            Some(&end) if end > start => {
+                chunks.push(start..end);
+                start = end;
+            }
+            _ => (),
+        }
+    }
+    if start < input.len() || chunks.is_empty() {
+        chunks.push(start..input.len());
+    }
+    chunks
+}
+
+/// Returns the byte offsets at which the top-level commands in the input end, that is, the offsets
+/// right after each `)` that closes a command. Parentheses in comments, string literals and quoted
+/// symbols are ignored. If the input has an unbalanced `)`, no offsets after it are returned.
+fn command_boundaries(input: &[u8]) -> Vec<usize> {
+    // Returns the index of the first occurrence of `byte` at or after `start`, or the length of
+    // the input if there is none
+    let find = |start: usize, byte: u8| {
+        input[start..]
+            .iter()
+            .position(|&b| b == byte)
+            .map_or(input.len(), |i| start + i)
+    };
+
+    let mut boundaries = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'(' => depth += 1,
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    boundaries.push(i + 1);
+                }
+            }
+            b';' => i = find(i, b'\n'),
+            b'"' => i = find(i + 1, b'"'),
+            b'|' => i = find(i + 1, b'|'),
+            _ => (),
+        }
+        i += 1;
+    }
+    boundaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_boundaries() {
+        let input = "(a (b)) (c \")\" |)|) ; (\n(d)\n";
+        assert_eq!(vec![7, 19, 27], command_boundaries(input.as_bytes()));
+
+        // Nothing after an unbalanced parenthesis is a boundary
+        assert_eq!(vec![3], command_boundaries(b"(a)) (b)"));
+    }
+
+    #[test]
+    fn test_lex_chunks() {
+        let input = "(assume h1 (= a b))\r\n; comment (\n(step t1 (cl |)(|) :rule \"r\")\n\n(step \
+            t2 (cl (|ÿ| x)) :rule trust)(step t3 (cl) :rule resolution :premises (t1 t2))";
+
+        let mut expected = Vec::new();
+        let mut lexer = Lexer::new(input.as_bytes()).unwrap();
+        loop {
+            let (token, span) = lexer.next_token().unwrap();
+            if token == Token::Eof {
+                break;
+            }
+            expected.push((token, span));
+        }
+        let expected_index = lexer.into_line_index();
+
+        for num_chunks in 1..=6 {
+            let lexed = lex_chunks(input.into(), num_chunks, UnicodePolicy::Reject).unwrap();
+            assert_eq!(expected, lexed.tokens);
+            for (_, span) in &expected {
+                assert_eq!(
+                    expected_index.position(span.start),
+                    lexed.line_index.position(span.start),
+                );
+            }
+        }
+
+        // Lexer errors are reported at the right position, even if they are in a later chunk
+        let input = "(assume h1 true)\n(assume h2 true)\n(assume h3 |\\|)";
+        match lex_chunks(input.into(), 3, UnicodePolicy::Reject) {
+            Err(crate::Error::Parser(_, pos)) => assert_eq!((3, 13), pos),
+            _ => panic!(),
+        }
+    }
+}
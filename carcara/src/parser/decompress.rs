@@ -0,0 +1,98 @@
+//! Transparent decompression of inputs compressed with gzip or zstd. The format is detected from
+//! the first bytes of each input, and not from the file name, so compressed problems and proofs can
+//! be given to the parser like any other input, and are decompressed while they are parsed.
+
+use flate2::bufread::MultiGzDecoder;
+use ruzstd::{FrameDecoder, StreamingDecoder};
+use std::io::{self, BufRead, BufReader, Read};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// An input that may be compressed. This is created by `decompress`, and reading from it yields
+/// the decompressed contents of the input. The decoders are boxed, since their state is much
+/// larger than a plain reader.
+pub enum MaybeCompressed<R: BufRead> {
+    Plain(R),
+    Gzip(Box<BufReader<MultiGzDecoder<R>>>),
+    Zstd(Box<BufReader<StreamingDecoder<R, FrameDecoder>>>),
+}
+
+/// Wraps an input in a decoder if it starts with the magic number of the gzip or zstd formats.
+/// Otherwise, the input is read as is. For zstd, only the first frame of the input is read.
+pub fn decompress<R: BufRead>(mut input: R) -> io::Result<MaybeCompressed<R>> {
+    let start = input.fill_buf()?;
+    Ok(if start.starts_with(GZIP_MAGIC) {
+        MaybeCompressed::Gzip(Box::new(BufReader::new(MultiGzDecoder::new(input))))
+    } else if start.starts_with(ZSTD_MAGIC) {
+        let decoder = StreamingDecoder::new(input)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        MaybeCompressed::Zstd(Box::new(BufReader::new(decoder)))
+    } else {
+        MaybeCompressed::Plain(input)
+    })
+}
+
+impl<R: BufRead> Read for MaybeCompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(r) => r.read(buf),
+            Self::Gzip(r) => r.read(buf),
+            Self::Zstd(r) => r.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for MaybeCompressed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Plain(r) => r.fill_buf(),
+            Self::Gzip(r) => r.fill_buf(),
+            Self::Zstd(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Plain(r) => r.consume(amt),
+            Self::Gzip(r) => r.consume(amt),
+            Self::Zstd(r) => r.consume(amt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    fn read_all(input: &[u8]) -> String {
+        let mut result = String::new();
+        let mut input = decompress(input).unwrap();
+        input.read_to_string(&mut result).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_decompress() {
+        let text = "(assume h1 true)\n";
+        assert_eq!(read_all(text.as_bytes()), text);
+        assert_eq!(read_all(b""), "");
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        assert_eq!(read_all(&encoder.finish().unwrap()), text);
+
+        // Since `ruzstd` can't compress, this builds a zstd frame with a single uncompressed block
+        let mut frame = ZSTD_MAGIC.to_vec();
+        frame.push(0x20); // Frame header: single segment, with a one byte content size
+        frame.push(text.len() as u8);
+        let block_header = (text.len() as u32) << 3 | 1; // Last block, of raw type
+        frame.extend(&block_header.to_le_bytes()[..3]);
+        frame.extend(text.as_bytes());
+        assert_eq!(read_all(&frame), text);
+
+        assert!(decompress(ZSTD_MAGIC).is_err());
+    }
+}
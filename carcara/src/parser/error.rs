@@ -1,9 +1,9 @@
 use crate::{
-    ast::{Identifier, Sort},
-    parser::Token,
+    ast::{printer::quote_symbol, Identifier, Sort},
+    num::Integer,
+    parser::{suggestions::DidYouMean, ProofFormat, RepairHint, ResourceLimit, Token},
     utils::Range,
 };
-use rug::Integer;
 use std::fmt;
 use thiserror::Error;
 
@@ -14,10 +14,20 @@ pub enum ParserError {
     #[error("unexpected character: '{0}'")]
     UnexpectedChar(char),
 
+    /// The lexer encountered a non-ASCII character in a simple symbol or keyword, which is not
+    /// allowed by the Unicode policy (see `UnicodePolicy`).
+    #[error("non-ASCII character '{0}' in symbol, which is only allowed in quoted symbols")]
+    NonAsciiSymbolChar(char),
+
     /// The lexer encountered a numeral with a leading zero, e.g. `0123`.
     #[error("leading zero in numeral '{0}'")]
     LeadingZero(String),
 
+    /// The lexer encountered a numeral that is too large to be represented. This can only happen
+    /// without the `arith` feature, in which numerals have a fixed precision.
+    #[error("numeral '{0}' is out of range")]
+    NumeralOutOfRange(String),
+
     /// The lexer encountered a `\` character while reading a quoted symbol.
     #[error("quoted symbol contains backslash")]
     BackslashInQuotedSymbol,
@@ -39,6 +49,11 @@ pub enum ParserError {
     #[error("unexpected token: '{0}'")]
     UnexpectedToken(Token),
 
+    /// The parser encountered an unexpected token that matches a common mistake made by proof
+    /// producers, for which a fix can be suggested.
+    #[error("unexpected token: '{0}'; {1}")]
+    UnexpectedTokenWithHint(Token, RepairHint),
+
     /// The parser parsed an empty sequence where only non-empty sequences are allowed.
     #[error("expected non-empty sequence")]
     EmptySequence,
@@ -51,22 +66,35 @@ pub enum ParserError {
     #[error("'{0}' is not a function sort")]
     NotAFunction(Sort), // TODO: This should also carry the actual function term
 
-    /// The parser encountered an identifier that was not defined.
-    #[error("identifier '{0}' is not defined")]
-    UndefinedIden(Identifier),
+    /// The parser encountered an identifier that was not defined. This also holds the defined
+    /// identifiers with similar names, if any.
+    #[error("identifier '{0}' is not defined{}", DidYouMean(.1))]
+    UndefinedIden(Identifier, Vec<String>),
 
     /// The parser encountered a sort that was not defined.
     #[error("sort '{0}' is not defined")]
     UndefinedSort(String),
 
-    /// The parser encountered a step id that was not defined.
-    #[error("step id '{0}' is not defined")]
-    UndefinedStepIndex(String),
+    /// The parser encountered a step id that was not defined. This also holds the visible step ids
+    /// with similar names, if any.
+    #[error("step id '{0}' is not defined{}", DidYouMean(.1))]
+    UndefinedStepIndex(String, Vec<String>),
 
     /// The wrong number of arguments was given to a function, operator or sort.
     #[error("expected {0} arguments, got {1}")]
     WrongNumberOfArgs(Range, usize),
 
+    /// The indices of an indexed identifier are not valid, for example, a bitvector sort of width
+    /// zero, or an `extract` operator whose indices are out of the bounds of its argument.
+    #[error("invalid indices in '{0}'")]
+    InvalidIndices(Identifier),
+
+    /// A premise references a command inside a subproof that was already closed. Such commands
+    /// can only be referenced from inside their subproof, and the subproof as a whole is referenced
+    /// through the step that ends it.
+    #[error("step '{0}' is local to the subproof ended by step '{1}'")]
+    SubproofLocalStep(String, String),
+
     /// A step id was used in more than one step.
     #[error("step id '{0}' was repeated")]
     RepeatedStepIndex(String),
@@ -76,6 +104,20 @@ pub enum ParserError {
     #[error("{0} is not a valid sort arity")]
     InvalidSortArity(Integer),
 
+    /// A numeral index of an indexed identifier is too large. This only happens if the number is
+    /// too big to fit in a `u64`.
+    #[error("{0} is not a valid index")]
+    InvalidIndex(Integer),
+
+    /// The number of assertion levels in a `push` or `pop` command is too large. This only happens
+    /// if the number is too big to fit in a `usize`.
+    #[error("{0} is not a valid number of assertion levels")]
+    InvalidNumLevels(Integer),
+
+    /// A `pop` command tried to pop more assertion levels than were pushed.
+    #[error("cannot pop {0} assertion levels, only {1} were pushed")]
+    PopTooManyLevels(usize, usize),
+
     /// The parser encountered an empty subproof
     #[error("subproof '{0}' is empty")]
     EmptySubproof(String),
@@ -88,9 +130,29 @@ pub enum ParserError {
     #[error("subproof '{0}' was not closed")]
     UnclosedSubproof(String),
 
+    /// A function declared in the proof was already declared with a different sort.
+    #[error("'{0}' was declared with sort '{1}', but is redeclared with sort '{2}'")]
+    InconsistentDeclaration(String, Box<Sort>, Box<Sort>),
+
+    /// A sort declared in the proof was already declared with a different arity.
+    #[error("sort '{0}' was declared with arity {1}, but is redeclared with arity {2}")]
+    InconsistentSortDeclaration(String, usize, usize),
+
     /// An unknown attribute was given to an annotated term.
     #[error("unknown attribute: ':{0}'")]
     UnknownAttribute(String),
+
+    /// The proof appears to be in a format other than Alethe.
+    #[error("this looks like a proof in {0}, but Carcara only checks proofs in the Alethe format")]
+    WrongProofFormat(ProofFormat),
+
+    /// An LFSC proof uses a construct that is not supported by the importer.
+    #[error("unsupported LFSC construct: '{0}'")]
+    UnsupportedLfsc(String),
+
+    /// The input exceeds one of the limits set in `ParserConfig::limits`.
+    #[error("resource limit exceeded: {0}")]
+    ResourceLimitExceeded(ResourceLimit),
 }
 
 /// Returns an error if the length of `sequence` is not in the `expected` range.
@@ -106,59 +168,213 @@ where
     }
 }
 
+/// A sort that was expected by a sort check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpectedSort {
+    /// Exactly this sort.
+    Exact(Sort),
+
+    /// Any instance of the parametric sort with this name whose arguments match the given sorts,
+    /// where `None` matches any sort. This is printed as an SMT-LIB parametric sort, for example,
+    /// any array indexed by integers is printed as `(par (X) (Array Int X))`.
+    Parametric(String, Vec<Option<Sort>>),
+
+    /// Any bitvector sort, regardless of its width. This is printed as `(_ BitVec n)`.
+    AnyBitVec,
+
+    /// Any floating-point sort, regardless of its widths. This is printed as
+    /// `(_ FloatingPoint eb sb)`.
+    AnyFloatingPoint,
+}
+
+impl From<Sort> for ExpectedSort {
+    fn from(sort: Sort) -> Self {
+        Self::Exact(sort)
+    }
+}
+
+impl fmt::Display for ExpectedSort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const PARAMETER_NAMES: [&str; 3] = ["X", "Y", "Z"];
+
+        let (name, args) = match self {
+            Self::Exact(sort) => return write!(f, "{}", sort),
+            Self::AnyBitVec => return write!(f, "(_ BitVec n)"),
+            Self::AnyFloatingPoint => return write!(f, "(_ FloatingPoint eb sb)"),
+            Self::Parametric(name, args) => (quote_symbol(name), args),
+        };
+        let mut parameters = Vec::new();
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| match arg {
+                Some(sort) => sort.to_string(),
+                None => {
+                    let i = parameters.len();
+                    let param = match PARAMETER_NAMES.get(i) {
+                        Some(&p) => p.to_owned(),
+                        None => format!("X{}", i + 1),
+                    };
+                    parameters.push(param.clone());
+                    param
+                }
+            })
+            .collect();
+
+        if !parameters.is_empty() {
+            write!(f, "(par ({}) ", parameters.join(" "))?;
+        }
+        write!(f, "({} {})", name, args.join(" "))?;
+        if !parameters.is_empty() {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
 /// An error in sort checking.
 #[derive(Debug, Error)]
 pub struct SortError {
-    pub expected: Vec<Sort>,
+    pub expected: Vec<ExpectedSort>,
     pub got: Sort,
+
+    /// The index of the argument whose sort is wrong, starting from zero, if the error happened
+    /// while checking the arguments of a function or operator.
+    pub arg_index: Option<usize>,
+
+    /// The function application or operation term in which the error happened, if any.
+    pub application: Option<Box<ApplicationSorts>>,
+}
+
+/// The sorts involved in a function application or operation term that has an argument of the
+/// wrong sort.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplicationSorts {
+    /// The name of the function or operator.
+    pub head: String,
+
+    /// The sorts of the function parameters, followed by its return sort. This is `None` for
+    /// operators, since the sorts they accept can't always be described by a single rank.
+    pub rank: Option<Vec<Sort>>,
+
+    /// The sorts of the arguments that were given.
+    pub arg_sorts: Vec<Sort>,
+}
+
+/// Writes a sequence of sorts, surrounded by parentheses and separated by spaces.
+fn write_sort_list(f: &mut fmt::Formatter, sorts: &[Sort]) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, s) in sorts.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", s)?;
+    }
+    write!(f, ")")
 }
 
 impl fmt::Display for SortError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.expected.as_slice() {
             [] => unreachable!(),
-            [p] => write!(f, "expected '{}', got '{}'", p, self.got),
+            [p] => write!(f, "expected '{}', got '{}'", p, self.got)?,
             [first, middle @ .., last] => {
                 write!(f, "expected '{}'", first)?;
                 for p in middle {
                     write!(f, ", '{}'", p)?;
                 }
-                write!(f, " or '{}', got '{}'", last, self.got)
+                write!(f, " or '{}', got '{}'", last, self.got)?;
             }
         }
+        if let Some(i) = self.arg_index {
+            write!(f, " in argument {}", i + 1)?;
+        }
+        let app = match &self.application {
+            Some(app) => app,
+            None => return Ok(()),
+        };
+        write!(f, " of '{}'", app.head)?;
+
+        // The rank is printed in the same way as in a `declare-fun` command
+        if let Some((return_sort, params)) = app.rank.as_ref().and_then(|r| r.split_last()) {
+            write!(f, "\n  function rank:  ")?;
+            write_sort_list(f, params)?;
+            write!(f, " {}", return_sort)?;
+        }
+        write!(f, "\n  argument sorts: ")?;
+        write_sort_list(f, &app.arg_sorts)
     }
 }
 
 impl SortError {
     /// Returns a sort error if `got` does not equal `expected`.
-    pub(crate) fn assert_eq(expected: &Sort, got: &Sort) -> Result<(), Self> {
+    pub fn assert_eq(expected: &Sort, got: &Sort) -> Result<(), Self> {
         if expected == got {
             Ok(())
         } else {
             Err(Self {
-                expected: vec![expected.clone()],
+                expected: vec![expected.clone().into()],
                 got: got.clone(),
+                arg_index: None,
+                application: None,
             })
         }
     }
 
-    /// Makes sure all terms in `sequence` are equal to each other, otherwise returns an error.
-    pub(crate) fn assert_all_eq(sequence: &[&Sort]) -> Result<(), Self> {
+    /// Makes sure all sorts in `sequence` are equal to each other, otherwise returns an error. The
+    /// argument index of the error is the index of the first sort that differs from the previous
+    /// ones.
+    pub fn assert_all_eq(sequence: &[&Sort]) -> Result<(), Self> {
         for i in 1..sequence.len() {
-            Self::assert_eq(sequence[i - 1], sequence[i])?;
+            Self::assert_eq(sequence[i - 1], sequence[i]).map_err(|e| e.at_arg(i))?;
         }
         Ok(())
     }
 
     /// Returns a sort error if `got` is not one of `possibilities`.
-    pub(crate) fn assert_one_of(possibilities: &[Sort], got: &Sort) -> Result<(), Self> {
+    pub fn assert_one_of(possibilities: &[Sort], got: &Sort) -> Result<(), Self> {
         if possibilities.contains(got) {
             Ok(())
         } else {
             Err(Self {
-                expected: possibilities.to_vec(),
+                expected: possibilities.iter().cloned().map(Into::into).collect(),
                 got: got.clone(),
+                arg_index: None,
+                application: None,
             })
         }
     }
+
+    /// Returns a sort error if `got` is not a bitvector sort. Otherwise, returns its width.
+    pub fn assert_bitvec(got: &Sort) -> Result<u64, Self> {
+        got.as_bitvec_width().ok_or_else(|| Self {
+            expected: vec![ExpectedSort::AnyBitVec],
+            got: got.clone(),
+            arg_index: None,
+            application: None,
+        })
+    }
+
+    /// Returns a sort error if `got` is not a floating-point sort. Otherwise, returns its exponent
+    /// and significand widths.
+    pub fn assert_float(got: &Sort) -> Result<(u64, u64), Self> {
+        got.as_float_widths().ok_or_else(|| Self {
+            expected: vec![ExpectedSort::AnyFloatingPoint],
+            got: got.clone(),
+            arg_index: None,
+            application: None,
+        })
+    }
+
+    /// Sets the index of the argument whose sort is wrong.
+    pub fn at_arg(self, index: usize) -> Self {
+        Self { arg_index: Some(index), ..self }
+    }
+
+    /// Sets the function application or operation term in which the error happened.
+    pub fn in_application(self, application: ApplicationSorts) -> Self {
+        Self {
+            application: Some(Box::new(application)),
+            ..self
+        }
+    }
 }
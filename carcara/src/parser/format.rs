@@ -0,0 +1,123 @@
+use std::fmt;
+
+/// A proof format, as detected from the first non-comment line of a proof file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofFormat {
+    /// The Alethe format, which is the format the checker supports natively.
+    Alethe,
+
+    /// The LFSC format, as produced by cvc5. A subset of this format can be imported into the
+    /// Alethe AST.
+    Lfsc,
+
+    /// The DRAT format, used by SAT solvers.
+    Drat,
+
+    /// The internal proof format printed by cvc5 when no proof format is selected.
+    Cvc5Internal,
+
+    /// The format could not be determined. The proof is parsed as Alethe, and any errors are
+    /// reported as usual.
+    Unknown,
+}
+
+impl fmt::Display for ProofFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ProofFormat::Alethe => "Alethe",
+            ProofFormat::Lfsc => "LFSC",
+            ProofFormat::Drat => "DRAT",
+            ProofFormat::Cvc5Internal => "cvc5's internal format",
+            ProofFormat::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ProofFormat {
+    /// Tries to detect the format of a proof from the beginning of its input. Only the first line
+    /// that is not empty or a comment is considered. This is a heuristic, and is only intended to
+    /// give more helpful error messages when a proof in the wrong format is passed to the checker.
+    pub fn detect(input: &[u8]) -> Self {
+        let input = String::from_utf8_lossy(input);
+        let first_line = input.lines().map(str::trim).find(|line| {
+            !line.is_empty() && !line.starts_with(';') && *line != "c" && !line.starts_with("c ")
+        });
+        let first_line = match first_line {
+            Some(l) => l,
+            None => return ProofFormat::Unknown,
+        };
+
+        if let Some(rest) = first_line.strip_prefix('(') {
+            let head = rest
+                .trim_start()
+                .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+                .next()
+                .unwrap_or_default();
+            return match head {
+                "assume" | "step" | "anchor" | "define-fun" => ProofFormat::Alethe,
+                "check" | "define" | "declare" => ProofFormat::Lfsc,
+                _ if !head.is_empty()
+                    && head.chars().all(|c| c.is_ascii_uppercase() || c == '_') =>
+                {
+                    ProofFormat::Cvc5Internal
+                }
+                _ => ProofFormat::Unknown,
+            };
+        }
+
+        // A DRAT line is a sequence of non-zero integer literals terminated by a zero, optionally
+        // preceded by a `d`, for deleted clauses
+        let mut words = first_line.split_whitespace().peekable();
+        words.next_if_eq(&"d");
+        let literals: Vec<_> = words.collect();
+        let is_drat_line =
+            literals.last() == Some(&"0") && literals.iter().all(|l| l.parse::<i64>().is_ok());
+        if is_drat_line {
+            ProofFormat::Drat
+        } else {
+            ProofFormat::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        let cases = [
+            (
+                "(assume h1 (not p))\n(step t2 (cl) :rule hole)",
+                ProofFormat::Alethe,
+            ),
+            (
+                "\n  ; comment\n(step t1 (cl) :rule hole)",
+                ProofFormat::Alethe,
+            ),
+            ("(define-fun f () Bool true)\n", ProofFormat::Alethe),
+            ("(check\n(\\ p (: (holds cln) ...)))", ProofFormat::Lfsc),
+            ("; LFSC\n(define __t1 (f x))", ProofFormat::Lfsc),
+            ("c DRAT proof\n1 -2 0\nd 1 2 0\n", ProofFormat::Drat),
+            ("d -3 4 0", ProofFormat::Drat),
+            ("0\n", ProofFormat::Drat),
+            (
+                "(SCOPE :args (a b)\n  (EQ_RESOLVE ...))",
+                ProofFormat::Cvc5Internal,
+            ),
+            ("unsat\n(assume h1 p)", ProofFormat::Unknown),
+            ("(foo bar)", ProofFormat::Unknown),
+            ("1 2 3", ProofFormat::Unknown),
+            ("", ProofFormat::Unknown),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                expected,
+                ProofFormat::detect(input.as_bytes()),
+                "{:?}",
+                input
+            );
+        }
+    }
+}
@@ -1,9 +1,14 @@
-use crate::{parser::ParserError, utils::is_symbol_character, CarcaraResult, Error};
-use rug::{ops::Pow, Integer, Rational};
+use crate::{
+    num::{Integer, Rational},
+    parser::ParserError,
+    utils::{is_symbol_character, Reserved},
+    CarcaraResult, Error,
+};
 use std::{
     io::{self, BufRead},
     str::FromStr,
 };
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 
 /// A token in the SMT-LIB and Alethe languages.
 #[derive(Debug, PartialEq, Eq)]
@@ -30,6 +35,11 @@ pub enum Token {
     /// A decimal numeral literal.
     Decimal(Rational),
 
+    /// A bitvector literal, in binary or hexadecimal form, like `#b0110` or `#x01Ab`. The
+    /// associated values are the value of the literal, and its width in bits, which is given by the
+    /// number of digits.
+    BitVec(Integer, u64),
+
     /// A string literal.
     String(String),
 
@@ -40,125 +50,232 @@ pub enum Token {
     Eof,
 }
 
-/// A reserved word in the SMT-LIB and Alethe lexicon.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Reserved {
-    /// The `_` reserved word.
-    Underscore,
-
-    /// The `!` reserved word.
-    Bang,
-
-    /// The `as` reserved word.
-    As,
-
-    /// The `let` reserved word.
-    Let,
-
-    /// The `exists` reserved word.
-    Exists,
-
-    /// The `forall` reserved word.
-    Forall,
-
-    /// The `match` reserved word.
-    Match,
+/// Represents a position (line and column numbers) in the source input. Both numbers start at 1.
+///
+/// Columns count characters, not bytes, and every character counts as a single column, including
+/// tabs and multi-byte characters. This is the convention used by most editors when jumping to a
+/// line and column. The line terminators, either `\n` or `\r\n`, and the byte order mark at the
+/// start of a UTF-8 input are not counted.
+///
+/// The lexer only tracks byte offsets, in the form of `Span`s, and positions are computed from them
+/// using a `LineIndex` when they are needed, for example to report an error.
+pub type Position = (usize, usize);
 
-    /// The `choice` reserved word.
-    Choice,
+/// A range of bytes in the source input, from `start` (inclusive) to `end` (exclusive).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
 
-    /// The `lambda` reserved word.
-    Lambda,
+/// An index of the lines read from the source input, which is used to compute the line and column
+/// numbers of a byte offset.
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    /// The byte offset at which each line starts.
+    line_starts: Vec<usize>,
+
+    /// The byte offset and length of every character that is encoded with more than one byte.
+    /// These are needed to count columns in characters instead of bytes. Most inputs are entirely
+    /// ASCII, in which case this is empty.
+    multibyte_chars: Vec<(usize, usize)>,
+}
 
-    /// The `cl` reserved word.
-    Cl,
+impl LineIndex {
+    fn add_line(&mut self, start: usize, line: &str) {
+        self.line_starts.push(start);
+        if !line.is_ascii() {
+            let chars = line.char_indices().filter(|(_, c)| c.len_utf8() > 1);
+            self.multibyte_chars
+                .extend(chars.map(|(i, c)| (start + i, c.len_utf8())));
+        }
+    }
 
-    /// The `assume` reserved word.
-    Assume,
+    /// Appends the lines of an index that was built from a chunk of the input starting at byte
+    /// `offset`. If `continues_line` is true, the chunk starts in the middle of a line, so its first
+    /// line is a continuation of the last line in this index.
+    pub(super) fn extend_from_chunk(
+        &mut self,
+        other: LineIndex,
+        offset: usize,
+        continues_line: bool,
+    ) {
+        let skip = usize::from(continues_line);
+        self.line_starts
+            .extend(other.line_starts.into_iter().skip(skip).map(|s| s + offset));
+        self.multibyte_chars.extend(
+            other
+                .multibyte_chars
+                .into_iter()
+                .map(|(o, len)| (o + offset, len)),
+        );
+    }
 
-    /// The `step` reserved word.
-    Step,
+    /// Returns the number of lines in the index.
+    pub fn num_lines(&self) -> usize {
+        self.line_starts.len()
+    }
 
-    /// The `anchor` reserved word.
-    Anchor,
+    /// Returns the line and column of the character at the given byte offset. The offset must be
+    /// in one of the lines read so far, or at the end of the input.
+    pub fn position(&self, offset: usize) -> Position {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        };
+        let line_start = self.line_starts.get(line).copied().unwrap_or(0);
+        let first = self
+            .multibyte_chars
+            .partition_point(|&(o, _)| o < line_start);
+        let last = self.multibyte_chars.partition_point(|&(o, _)| o < offset);
+        let extra_bytes: usize = self.multibyte_chars[first..last]
+            .iter()
+            .map(|(_, len)| len - 1)
+            .sum();
+        (line + 1, offset - line_start - extra_bytes + 1)
+    }
+}
 
-    /// The `declare-fun` reserved word.
-    DeclareFun,
+/// How the lexer handles non-ASCII characters in symbols and keywords. Quoted symbols and string
+/// literals may always contain any character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodePolicy {
+    /// Simple symbols and keywords may only contain ASCII characters, as in the SMT-LIB standard.
+    Reject,
 
-    /// The `declare-const` reserved word.
-    DeclareConst,
+    /// Simple symbols and keywords may contain any non-ASCII character that is not whitespace.
+    Accept,
 
-    /// The `declare-sort` reserved word.
-    DeclareSort,
+    /// Similar to `UnicodePolicy::Accept`, but all symbols and keywords, including quoted ones, are
+    /// normalized to NFC. This way, an accented letter that is written with a precomposed character
+    /// in one place and with combining characters in another is still the same symbol.
+    Normalize,
+}
 
-    /// The `define-fun` reserved word.
-    DefineFun,
+impl Default for UnicodePolicy {
+    fn default() -> Self {
+        Self::Reject
+    }
+}
 
-    /// The `assert` reserved word.
-    Assert,
+impl UnicodePolicy {
+    /// Returns `true` if `c` can appear in a simple symbol or keyword under this policy.
+    fn is_symbol_character(self, c: char) -> bool {
+        is_symbol_character(c) || self != Self::Reject && !c.is_ascii() && !c.is_whitespace()
+    }
 
-    /// The `set-logic` reserved word.
-    SetLogic,
+    /// Applies the policy to a symbol or keyword that was read from the input.
+    fn apply(self, symbol: String) -> String {
+        match self {
+            Self::Normalize if !is_nfc(&symbol) => symbol.nfc().collect(),
+            _ => symbol,
+        }
+    }
 }
 
-impl_str_conversion_traits!(Reserved {
-    Underscore: "_",
-    Bang: "!",
-    As: "as",
-    Let: "let",
-    Exists: "exists",
-    Forall: "forall",
-    Match: "match",
-    Choice: "choice",
-    Lambda: "lambda",
-    Cl: "cl",
-    Assume: "assume",
-    Step: "step",
-    Anchor: "anchor",
-    DeclareFun: "declare-fun",
-    DeclareConst: "declare-const",
-    DeclareSort: "declare-sort",
-    DefineFun: "define-fun",
-    Assert: "assert",
-    SetLogic: "set-logic",
-});
-
-/// Represents a position (line and column numbers) in the source input.
-pub type Position = (usize, usize);
-
 /// A lexer for the Alethe proof format.
 pub struct Lexer<R> {
     input: R,
     current_line: Option<std::vec::IntoIter<char>>,
     current_char: Option<char>,
-    position: Position,
+
+    /// The byte offset of `current_char`.
+    offset: usize,
+
+    /// The byte offset of the character after `current_char`.
+    next_offset: usize,
+
+    /// The byte offset at which the next line of the input starts.
+    next_line_start: usize,
+
+    line_index: LineIndex,
+    unicode_policy: UnicodePolicy,
+
+    /// The comments read so far, if they are being captured. See `Lexer::capture_comments`.
+    comments: Option<Vec<(Span, String)>>,
 }
 
 impl<R: BufRead> Lexer<R> {
     /// Constructs a new `Lexer` from a type that implements `BufRead`. This operation can fail if
     /// there is an IO error on the first token.
-    pub fn new(mut input: R) -> io::Result<Self> {
+    pub fn new(input: R) -> io::Result<Self> {
+        Self::with_unicode_policy(input, UnicodePolicy::default())
+    }
+
+    /// Similar to `Lexer::new`, but non-ASCII characters in symbols are handled according to
+    /// `unicode_policy`.
+    pub fn with_unicode_policy(mut input: R, unicode_policy: UnicodePolicy) -> io::Result<Self> {
         let mut buf = String::new();
         let read = input.read_line(&mut buf)?;
+        let mut line_index = LineIndex::default();
         if read == 0 {
+            line_index.add_line(0, "");
             Ok(Lexer {
                 input,
                 current_line: None,
                 current_char: None,
-                position: (0, 0),
+                offset: 0,
+                next_offset: 0,
+                next_line_start: 0,
+                line_index,
+                unicode_policy,
+                comments: None,
             })
         } else {
-            let mut line = buf.chars().collect::<Vec<_>>().into_iter();
+            let line = buf.strip_prefix('\u{feff}').unwrap_or(&buf);
+            let start = buf.len() - line.len();
+            line_index.add_line(start, line);
+            let mut line = line_chars(line);
             let current_char = line.next();
             Ok(Lexer {
                 input,
                 current_line: Some(line),
                 current_char,
-                position: (1, 1),
+                offset: start,
+                next_offset: start + current_char.map_or(0, char::len_utf8),
+                next_line_start: read,
+                line_index,
+                unicode_policy,
+                comments: None,
             })
         }
     }
 
+    /// Returns the index of the lines read so far, which can be used to compute the line and
+    /// column of the spans returned by the lexer.
+    pub fn line_index(&self) -> &LineIndex {
+        &self.line_index
+    }
+
+    /// Consumes the lexer, returning the index of the lines read so far.
+    pub fn into_line_index(self) -> LineIndex {
+        self.line_index
+    }
+
+    /// Makes the lexer keep the comments in the input, instead of discarding them. The comments can
+    /// then be retrieved with `Lexer::comments`.
+    pub fn capture_comments(&mut self) {
+        self.comments.get_or_insert_with(Vec::new);
+    }
+
+    /// Returns the span and text of each comment read so far, in the order they appear in the
+    /// input. The text of a comment doesn't include the leading `;` or the line terminator. This is
+    /// always empty if `Lexer::capture_comments` was not called.
+    pub fn comments(&self) -> &[(Span, String)] {
+        self.comments.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the byte offset of the current character. At the end of the input, this is the
+    /// length of the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Constructs an error at the position of the current character.
+    fn error(&self, err: ParserError) -> Error {
+        Error::Parser(err, self.line_index.position(self.offset))
+    }
+
     /// Advances the lexer by one character, and returns the previous `current_char`.
     fn next_char(&mut self) -> io::Result<Option<char>> {
         // If there are no more characters in the current line, go to the next line
@@ -168,27 +285,25 @@ impl<R: BufRead> Lexer<R> {
             }
         }
 
-        let new = if let Some(line) = &mut self.current_line {
-            self.position.1 += 1;
-            line.next()
-        } else {
-            None
-        };
+        let new = self.current_line.as_mut().and_then(Iterator::next);
+        self.offset = self.next_offset;
+        self.next_offset += new.map_or(0, char::len_utf8);
         let old = std::mem::replace(&mut self.current_char, new);
         Ok(old)
     }
 
     /// Advances the lexer by one line, discarding the remaining contents of the current line.
     fn next_line(&mut self) -> io::Result<()> {
+        let start = self.next_line_start;
         let mut buf = String::new();
         let read = self.input.read_line(&mut buf)?;
+        self.next_offset = start;
         if read == 0 {
             self.current_line = None;
         } else {
-            let line = buf.chars().collect::<Vec<_>>().into_iter();
-            self.current_line = Some(line);
-            self.position.0 += 1;
-            self.position.1 = 0;
+            self.line_index.add_line(start, &buf);
+            self.current_line = Some(line_chars(&buf));
+            self.next_line_start += read;
         }
         Ok(())
     }
@@ -225,7 +340,15 @@ impl<R: BufRead> Lexer<R> {
     fn consume_whitespace(&mut self) -> io::Result<()> {
         self.drop_while_whitespace()?;
         while self.current_char == Some(';') {
-            self.next_line()?;
+            if self.comments.is_some() {
+                let start = self.offset;
+                self.next_char()?;
+                let text = self.read_chars_while(|c| c != '\n')?;
+                let span = Span { start, end: self.offset };
+                self.comments.as_mut().unwrap().push((span, text));
+            } else {
+                self.next_line()?;
+            }
             self.next_char()?;
             self.drop_while_whitespace()?;
         }
@@ -233,9 +356,9 @@ impl<R: BufRead> Lexer<R> {
     }
 
     /// Reads a token from the input source.
-    pub fn next_token(&mut self) -> CarcaraResult<(Token, Position)> {
+    pub fn next_token(&mut self) -> CarcaraResult<(Token, Span)> {
         self.consume_whitespace()?;
-        let start_position = self.position;
+        let start = self.offset;
         let token = match self.current_char {
             Some('(') => {
                 self.next_char()?;
@@ -248,21 +371,24 @@ impl<R: BufRead> Lexer<R> {
             Some('"') => self.read_string(),
             Some('|') => self.read_quoted_symbol(),
             Some(':') => self.read_keyword(),
-            Some('#') => self.read_number_with_base(),
+            Some('#') => self.read_bitvec_literal(),
             Some(c) if c.is_ascii_digit() => self.read_number(),
-            Some(c) if is_symbol_character(c) => self.read_simple_symbol(),
+            Some(c) if self.unicode_policy.is_symbol_character(c) => self.read_simple_symbol(),
             None => Ok(Token::Eof),
-            Some(other) => Err(Error::Parser(
-                ParserError::UnexpectedChar(other),
-                self.position,
-            )),
+            Some(other) if !other.is_ascii() && !other.is_whitespace() => {
+                Err(self.error(ParserError::NonAsciiSymbolChar(other)))
+            }
+            Some(other) => Err(self.error(ParserError::UnexpectedChar(other))),
         }?;
-        Ok((token, start_position))
+        Ok((token, Span { start, end: self.offset }))
     }
 
     /// Reads a simple symbol from the input source.
     fn read_simple_symbol(&mut self) -> CarcaraResult<Token> {
-        let symbol = self.read_chars_while(is_symbol_character)?;
+        let policy = self.unicode_policy;
+        let symbol = self.read_chars_while(|c| policy.is_symbol_character(c))?;
+        self.check_symbol_end()?;
+        let symbol = policy.apply(symbol);
         if let Ok(reserved) = Reserved::from_str(&symbol) {
             Ok(Token::ReservedWord(reserved))
         } else {
@@ -275,14 +401,11 @@ impl<R: BufRead> Lexer<R> {
         self.next_char()?; // Consume `|`
         let symbol = self.read_chars_while(|c| c != '|' && c != '\\')?;
         match self.current_char {
-            Some('\\') => Err(Error::Parser(
-                ParserError::BackslashInQuotedSymbol,
-                self.position,
-            )),
-            None => Err(Error::Parser(ParserError::EofInQuotedSymbol, self.position)),
+            Some('\\') => Err(self.error(ParserError::BackslashInQuotedSymbol)),
+            None => Err(self.error(ParserError::EofInQuotedSymbol)),
             Some('|') => {
                 self.next_char()?;
-                Ok(Token::Symbol(symbol))
+                Ok(Token::Symbol(self.unicode_policy.apply(symbol)))
             }
             _ => unreachable!(),
         }
@@ -291,27 +414,49 @@ impl<R: BufRead> Lexer<R> {
     /// Reads a keyword from the input source.
     fn read_keyword(&mut self) -> CarcaraResult<Token> {
         self.next_char()?; // Consume `:`
-        let symbol = self.read_chars_while(is_symbol_character)?;
-        Ok(Token::Keyword(symbol))
+        let policy = self.unicode_policy;
+        let symbol = self.read_chars_while(|c| policy.is_symbol_character(c))?;
+        self.check_symbol_end()?;
+        Ok(Token::Keyword(policy.apply(symbol)))
+    }
+
+    /// Returns an error if a simple symbol or keyword was interrupted by a non-ASCII character that
+    /// is not allowed by the Unicode policy. Otherwise, the rest of the symbol would be read as a
+    /// separate token, which leads to confusing errors.
+    fn check_symbol_end(&self) -> CarcaraResult<()> {
+        match self.current_char {
+            Some(c) if !c.is_ascii() && !c.is_whitespace() => {
+                Err(self.error(ParserError::NonAsciiSymbolChar(c)))
+            }
+            _ => Ok(()),
+        }
     }
 
-    /// Reads a binary or hexadecimal literal, e.g. `#b0110` or `#x01Ab`. Returns an error if any
-    /// character other than `b` or `x` is encountered after the `#`.
-    fn read_number_with_base(&mut self) -> CarcaraResult<Token> {
+    /// Reads a binary or hexadecimal bitvector literal, e.g. `#b0110` or `#x01Ab`. Returns an error
+    /// if any character other than `b` or `x` is encountered after the `#`, if there are no digits
+    /// after it, or if its value is too large to be represented.
+    fn read_bitvec_literal(&mut self) -> CarcaraResult<Token> {
         self.next_char()?; // Consume `#`
         let base = match self.next_char()? {
             Some('b') => 2,
             Some('x') => 16,
-            None => return Err(Error::Parser(ParserError::EofInNumeral, self.position)),
-            Some(other) => {
-                return Err(Error::Parser(
-                    ParserError::UnexpectedChar(other),
-                    self.position,
-                ))
-            }
+            None => return Err(self.error(ParserError::EofInNumeral)),
+            Some(other) => return Err(self.error(ParserError::UnexpectedChar(other))),
         };
         let s = self.read_chars_while(|c| c.is_digit(base as u32))?;
-        Ok(Token::Numeral(Integer::from_str_radix(&s, base).unwrap()))
+        if s.is_empty() {
+            let error = match self.current_char {
+                Some(c) => ParserError::UnexpectedChar(c),
+                None => ParserError::EofInNumeral,
+            };
+            return Err(self.error(error));
+        }
+        let (prefix, bits_per_digit) = if base == 2 { ('b', 1) } else { ('x', 4) };
+        let value = Integer::from_str_radix(&s, base).map_err(|_| {
+            let literal = format!("#{}{}", prefix, s);
+            self.error(ParserError::NumeralOutOfRange(literal))
+        })?;
+        Ok(Token::BitVec(value, s.len() as u64 * bits_per_digit))
     }
 
     /// Reads an integer or decimal numerical literal.
@@ -319,21 +464,29 @@ impl<R: BufRead> Lexer<R> {
         let int_part = self.read_chars_while(|c| c.is_ascii_digit())?;
 
         if int_part.len() > 1 && int_part.starts_with('0') {
-            return Err(Error::Parser(
-                ParserError::LeadingZero(int_part),
-                self.position,
-            ));
+            return Err(self.error(ParserError::LeadingZero(int_part)));
         }
 
         if self.current_char == Some('.') {
             self.next_char()?;
             let frac_part = self.read_chars_while(|c| c.is_ascii_digit())?;
-            let denom = Integer::from(10u32).pow(frac_part.len() as u32);
-            let numer = (int_part + &frac_part).parse::<Integer>().unwrap();
+            let out_of_range = |_| {
+                let literal = format!("{}.{}", int_part, frac_part);
+                self.error(ParserError::NumeralOutOfRange(literal))
+            };
+            let numer = format!("{}{}", int_part, frac_part)
+                .parse::<Integer>()
+                .map_err(out_of_range)?;
+            let denom = format!("1{}", "0".repeat(frac_part.len()))
+                .parse::<Integer>()
+                .map_err(out_of_range)?;
             let r = (numer, denom).into();
             Ok(Token::Decimal(r))
         } else {
-            Ok(Token::Numeral(int_part.parse().unwrap()))
+            match int_part.parse() {
+                Ok(n) => Ok(Token::Numeral(n)),
+                Err(_) => Err(self.error(ParserError::NumeralOutOfRange(int_part))),
+            }
         }
     }
 
@@ -344,7 +497,7 @@ impl<R: BufRead> Lexer<R> {
         loop {
             result += &self.read_chars_while(|c| c != '"')?;
             if self.current_char.is_none() {
-                return Err(Error::Parser(ParserError::EofInString, self.position));
+                return Err(self.error(ParserError::EofInString));
             }
             self.next_char()?; // Consume `"`
             if self.current_char == Some('"') {
@@ -354,10 +507,69 @@ impl<R: BufRead> Lexer<R> {
                 break;
             }
         }
-        Ok(Token::String(result))
+        Ok(Token::String(decode_escapes(result)))
     }
 }
 
+/// Decodes the escape sequences in the contents of a string literal, as defined by the SMT-LIB
+/// strings theory. An escape sequence is either `\ud₃d₂d₁d₀`, with exactly four hexadecimal digits,
+/// or `\u{d₀}` to `\u{d₄d₃d₂d₁d₀}`, with one to five digits, and denotes the character with that
+/// code point, which must be at most `0x2FFFF`. Anything else, including surrogate code points,
+/// which can't be represented in a Rust string, is kept as is.
+fn decode_escapes(string: String) -> String {
+    if !string.contains("\\u") {
+        return string;
+    }
+    let chars: Vec<char> = string.chars().collect();
+    let mut result = String::with_capacity(string.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match read_escape(&chars[i..]) {
+            Some((c, len)) => {
+                result.push(c);
+                i += len;
+            }
+            None => {
+                result.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Reads an escape sequence from the start of `chars`. Returns the character it denotes and the
+/// length of the sequence, or `None` if `chars` doesn't start with a valid escape sequence.
+fn read_escape(chars: &[char]) -> Option<(char, usize)> {
+    let rest = chars.strip_prefix(&['\\', 'u'])?;
+    let (digits, len) = if rest.first() == Some(&'{') {
+        let end = rest.iter().take(7).position(|&c| c == '}')?;
+        (&rest[1..end], end + 3)
+    } else {
+        (rest.get(..4)?, 6)
+    };
+    if digits.is_empty() || !digits.iter().all(char::is_ascii_hexdigit) {
+        return None;
+    }
+    let digits: String = digits.iter().collect();
+    let code = u32::from_str_radix(&digits, 16).unwrap();
+    if code > 0x2FFFF {
+        return None;
+    }
+    char::from_u32(code).map(|c| (c, len))
+}
+
+/// Splits a line read from the input into characters. A `\r\n` line terminator is replaced by
+/// `\n`, so carriage returns never end up in tokens, like string literals that span many lines.
+fn line_chars(line: &str) -> std::vec::IntoIter<char> {
+    let mut chars: Vec<_> = line.chars().collect();
+    if chars.ends_with(&['\r', '\n']) {
+        chars.truncate(chars.len() - 2);
+        chars.push('\n');
+    }
+    chars.into_iter()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,6 +612,33 @@ mod tests {
                 Token::Symbol("symbol".into())
             ]
         );
+
+        let input = "; first\r\n(a ;second\n;\n b) ; last";
+        let mut lex = Lexer::new(std::io::Cursor::new(input)).unwrap();
+        lex.capture_comments();
+        let mut tokens = Vec::new();
+        loop {
+            match lex.next_token().unwrap().0 {
+                Token::Eof => break,
+                tk => tokens.push(tk),
+            }
+        }
+        assert_eq!(tokens.len(), 4);
+        let comments: Vec<_> = lex
+            .comments()
+            .iter()
+            .map(|(span, text)| (&input[span.start..span.end], text))
+            .collect();
+        let expected = [
+            ("; first", " first"),
+            (";second", "second"),
+            (";", ""),
+            ("; last", " last"),
+        ];
+        assert_eq!(comments.len(), expected.len());
+        for ((source, text), (expected_source, expected_text)) in comments.iter().zip(expected) {
+            assert_eq!((*source, text.as_str()), (expected_source, expected_text));
+        }
     }
 
     #[test]
@@ -444,8 +683,8 @@ mod tests {
         let expected = vec![
             Token::Numeral(42.into()),
             Token::Decimal((314_159, 100_000).into()),
-            Token::Numeral(42.into()),
-            Token::Numeral(255.into()),
+            Token::BitVec(42.into(), 6),
+            Token::BitVec(255.into(), 12),
         ];
         assert_eq!(expected, lex_all(input));
 
@@ -463,6 +702,34 @@ mod tests {
             lex_one("#"),
             Err(Error::Parser(ParserError::EofInNumeral, _)),
         ));
+
+        assert!(matches!(
+            lex_one("#b"),
+            Err(Error::Parser(ParserError::EofInNumeral, _)),
+        ));
+
+        assert!(matches!(
+            lex_one("#x)"),
+            Err(Error::Parser(ParserError::UnexpectedChar(')'), _)),
+        ));
+
+        // Without the `arith` feature, numerals that don't fit in the fixed-precision fallback are
+        // rejected with an error
+        let large_numeral = "9".repeat(60);
+        let small_decimal = format!("0.{}1", "0".repeat(60));
+        let wide_bitvec = format!("#b1{}", "0".repeat(128));
+        let wide_hex_bitvec = format!("#x{}", "f".repeat(40));
+        for input in [large_numeral, small_decimal, wide_bitvec, wide_hex_bitvec] {
+            let result = lex_one(&input);
+            if cfg!(feature = "arith") {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(Error::Parser(ParserError::NumeralOutOfRange(_), _))
+                ));
+            }
+        }
     }
 
     #[test]
@@ -476,6 +743,29 @@ mod tests {
         ];
         assert_eq!(expected, lex_all(input));
 
+        let input = r#" "\u00e9t\u{E9}" "\u{1F600}\u{22}" "\u{0}\u{00000}" "\u{1f}\u001F" "#;
+        let expected = vec![
+            Token::String("\u{e9}t\u{e9}".into()),
+            Token::String("\u{1f600}\"".into()),
+            Token::String("\0\0".into()),
+            Token::String("\u{1f}\u{1f}".into()),
+        ];
+        assert_eq!(expected, lex_all(input));
+
+        // Invalid escape sequences are kept as is
+        let input = r#" "\u{}" "\u{123456}" "\u{30000}" "\u12" "\uD800" "\u{g}" "\x" "#;
+        let expected = [
+            r"\u{}",
+            r"\u{123456}",
+            r"\u{30000}",
+            r"\u12",
+            r"\uD800",
+            r"\u{g}",
+            r"\x",
+        ];
+        let expected: Vec<_> = expected.iter().map(|&s| Token::String(s.into())).collect();
+        assert_eq!(expected, lex_all(input));
+
         assert!(matches!(
             lex_one("\""),
             Err(Error::Parser(ParserError::EofInString, _))
@@ -499,4 +789,116 @@ mod tests {
         ];
         assert_eq!(expected, lex_all(input));
     }
+
+    #[test]
+    fn test_positions() {
+        fn positions(input: &str) -> Vec<Position> {
+            let mut lex = Lexer::new(std::io::Cursor::new(input)).unwrap();
+            let mut result = Vec::new();
+            loop {
+                let (tk, span) = lex.next_token().unwrap();
+                if tk == Token::Eof {
+                    break;
+                }
+                result.push(span);
+            }
+            result
+                .into_iter()
+                .map(|span| lex.line_index().position(span.start))
+                .collect()
+        }
+
+        let expected = vec![(1, 1), (1, 3), (2, 2), (2, 4)];
+        assert_eq!(expected, positions("( a\n\t(\tb"));
+        assert_eq!(expected, positions("( a\r\n\t(\tb"));
+        assert_eq!(expected, positions("\u{feff}( a\r\n\t(\tb"));
+        assert_eq!(vec![(1, 1), (3, 2)], positions("a ; comment\r\n\r\n b"));
+
+        assert_eq!(
+            vec![Token::String("two\nlines".into())],
+            lex_all("\"two\r\nlines\""),
+        );
+        assert_eq!(vec![Token::Symbol("a".into())], lex_all("\u{feff}a\r\n"));
+        assert_eq!(
+            vec![(1, 1), (1, 5), (2, 1), (2, 5)],
+            positions("\"\u{e9}\" a\n|\u{1f600}| b"),
+        );
+    }
+
+    #[test]
+    fn test_unicode_policy() {
+        fn lex_with(policy: UnicodePolicy, input: &str) -> CarcaraResult<Vec<(Token, Position)>> {
+            let mut lex = Lexer::with_unicode_policy(std::io::Cursor::new(input), policy)?;
+            let mut result = Vec::new();
+            loop {
+                let (tk, span) = lex.next_token()?;
+                if tk == Token::Eof {
+                    break;
+                }
+                result.push((tk, lex.line_index().position(span.start)));
+            }
+            Ok(result)
+        }
+
+        let input = "(caf\u{e9} :\u{3b1}\u{3b2}\n  |\u{1f600}| x)";
+        let expected = vec![
+            (Token::OpenParen, (1, 1)),
+            (Token::Symbol("caf\u{e9}".into()), (1, 2)),
+            (Token::Keyword("\u{3b1}\u{3b2}".into()), (1, 7)),
+            (Token::Symbol("\u{1f600}".into()), (2, 3)),
+            (Token::Symbol("x".into()), (2, 7)),
+            (Token::CloseParen, (2, 8)),
+        ];
+        assert_eq!(expected, lex_with(UnicodePolicy::Accept, input).unwrap());
+
+        // By default, non-ASCII characters are only allowed in quoted symbols
+        let cases = [
+            ("caf\u{e9}", '\u{e9}', (1, 4)),
+            ("\u{e9}t\u{e9}", '\u{e9}', (1, 1)),
+            (":\u{3b1}", '\u{3b1}', (1, 2)),
+            ("|caf\u{e9}|\n  caf\u{e9}", '\u{e9}', (2, 6)),
+        ];
+        for (input, expected_char, expected_pos) in cases {
+            match lex_with(UnicodePolicy::Reject, input) {
+                Err(Error::Parser(ParserError::NonAsciiSymbolChar(c), pos)) => {
+                    assert_eq!((expected_char, expected_pos), (c, pos));
+                }
+                other => panic!("expected error, got {:?}", other),
+            }
+        }
+
+        // When normalizing, precomposed characters and combining characters give the same symbol
+        let input = "caf\u{e9} cafe\u{301} |cafe\u{301}|";
+        let tokens = lex_with(UnicodePolicy::Normalize, input).unwrap();
+        assert!(tokens
+            .iter()
+            .all(|(tk, _)| *tk == Token::Symbol("caf\u{e9}".into())));
+        assert_eq!((1, 12), tokens[2].1);
+        let tokens = lex_with(UnicodePolicy::Accept, input).unwrap();
+        assert_eq!(Token::Symbol("cafe\u{301}".into()), tokens[1].0);
+
+        // Normalizing also decomposes characters that have a canonical decomposition, and puts
+        // combining characters in canonical order
+        let input = "\u{212b} A\u{30a} e\u{302}\u{323}";
+        let tokens = lex_with(UnicodePolicy::Normalize, input).unwrap();
+        assert_eq!(Token::Symbol("\u{c5}".into()), tokens[0].0);
+        assert_eq!(Token::Symbol("\u{c5}".into()), tokens[1].0);
+        assert_eq!(Token::Symbol("\u{1ec7}".into()), tokens[2].0);
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "(a \"\u{e9}\")\r\n  bc";
+        let mut lex = Lexer::new(std::io::Cursor::new(input)).unwrap();
+        let mut spans = Vec::new();
+        loop {
+            let (tk, span) = lex.next_token().unwrap();
+            if tk == Token::Eof {
+                assert_eq!(input.len(), span.start);
+                break;
+            }
+            spans.push((span.start, span.end));
+        }
+        assert_eq!(vec![(0, 1), (1, 2), (3, 7), (7, 8), (12, 14)], spans);
+    }
 }
@@ -0,0 +1,233 @@
+//! An importer for a subset of the LFSC proof format. This translates resolution proofs written
+//! using the clause and resolution constructs from the LFSC `sat.plf` signature into Alethe
+//! commands. The supported grammar is:
+//!
+//! ```text
+//! <proof>  ::= (check <term>)
+//! <term>   ::= (% <name> (holds <clause>) <term>)    ; an assumption
+//!            | (: (holds <clause>) <term>)           ; a type ascription
+//!            | (R _ _ <term> <term> <atom>)          ; resolution, pivot is positive in the first
+//!            | (Q _ _ <term> <term> <atom>)          ; resolution, pivot is negative in the first
+//!            | <name>                                ; a reference to an assumption
+//! <clause> ::= cln | (clc <lit> <clause>)
+//! <lit>    ::= (pos <atom>) | (neg <atom>)
+//! ```
+//!
+//! Atoms are SMT-LIB terms of sort `Bool`, using the declarations from the problem. Each assumption
+//! becomes an `assume` command, followed by an `or` step if the clause has more than one literal,
+//! and each resolution becomes a `resolution` step. Assumptions of the empty clause are not
+//! supported.
+
+use super::{Parser, ParserError, Reserved, Token};
+use crate::{ast::*, CarcaraResult};
+use ahash::AHashMap;
+use std::io::BufRead;
+
+#[derive(Default)]
+struct LfscImporter {
+    commands: Vec<ProofCommand>,
+
+    /// Maps each assumption name to the index of the command that concludes its clause.
+    assumptions: AHashMap<String, usize>,
+}
+
+impl LfscImporter {
+    fn next_step_id(&self) -> String {
+        format!("t{}", self.commands.len() + 1)
+    }
+
+    fn add_step(&mut self, clause: Vec<Rc<Term>>, rule: &str, premises: Vec<usize>) -> usize {
+        let step = ProofStep {
+            id: self.next_step_id(),
+            clause,
+            rule: rule.to_owned(),
+            premises: premises.into_iter().map(|i| (0, i)).collect(),
+            args: Vec::new(),
+            discharge: Vec::new(),
+        };
+        self.commands.push(ProofCommand::Step(step));
+        self.commands.len() - 1
+    }
+
+    fn add_assumption(&mut self, pool: &mut TermPool, name: String, clause: Vec<Rc<Term>>) {
+        let index = match clause.as_slice() {
+            [literal] => {
+                let id = name.clone();
+                let term = literal.clone();
+                self.commands.push(ProofCommand::Assume { id, term });
+                self.commands.len() - 1
+            }
+            _ => {
+                let term = pool.add(Term::Op(Operator::Or, clause.clone()));
+                let id = name.clone();
+                self.commands.push(ProofCommand::Assume { id, term });
+                let assume_index = self.commands.len() - 1;
+                self.add_step(clause, "or", vec![assume_index])
+            }
+        };
+        self.assumptions.insert(name, index);
+    }
+
+    fn clause(&self, index: usize) -> &[Rc<Term>] {
+        self.commands[index].clause()
+    }
+}
+
+impl<'a, R: BufRead> Parser<'a, R> {
+    /// Parses a proof in the supported subset of LFSC, and translates it into Alethe commands.
+    pub(super) fn parse_lfsc_commands(&mut self) -> CarcaraResult<Vec<ProofCommand>> {
+        let mut importer = LfscImporter::default();
+        self.expect_token(Token::OpenParen)?;
+        match self.next_token()? {
+            (Token::Symbol(s), _) if s == "check" => (),
+            (Token::Symbol(s), pos) => {
+                return Err(self.error_at(ParserError::UnsupportedLfsc(s), pos))
+            }
+            (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
+        }
+        self.parse_lfsc_term(&mut importer)?;
+        self.expect_token(Token::CloseParen)?;
+        self.expect_token(Token::Eof)?;
+        Ok(importer.commands)
+    }
+
+    /// Parses an LFSC proof term, and returns the index of the command that concludes it.
+    fn parse_lfsc_term(&mut self, importer: &mut LfscImporter) -> CarcaraResult<usize> {
+        // Proofs usually start with a long chain of nested assumptions, so we parse these
+        // iteratively, and consume all of their closing parentheses at the end
+        let mut num_assumptions = 0;
+        let result = loop {
+            match self.next_token()? {
+                (Token::Symbol(name), pos) => match importer.assumptions.get(&name) {
+                    Some(&index) => break index,
+                    None => {
+                        let err = ParserError::UndefinedStepIndex(name, Vec::new());
+                        return Err(self.error_at(err, pos));
+                    }
+                },
+                (Token::OpenParen, _) => (),
+                (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
+            }
+            match self.next_token()? {
+                (Token::Symbol(s), _) if s == "%" => {
+                    let name = self.expect_symbol()?;
+                    let pos = self.current_span;
+                    let clause = self.parse_lfsc_holds()?;
+                    if clause.is_empty() {
+                        let err = ParserError::UnsupportedLfsc("(holds cln)".to_owned());
+                        return Err(self.error_at(err, pos));
+                    }
+                    importer.add_assumption(self.pool, name, clause);
+                    num_assumptions += 1;
+                }
+                (Token::Keyword(k), _) if k.is_empty() => {
+                    let clause = self.parse_lfsc_holds()?;
+                    let index = self.parse_lfsc_term(importer)?;
+                    self.expect_token(Token::CloseParen)?;
+
+                    // If the ascribed term is a resolution, we use the ascribed clause as its
+                    // conclusion, since it may have the literals in a different order
+                    if let ProofCommand::Step(step) = &mut importer.commands[index] {
+                        if step.rule == "resolution" {
+                            step.clause = clause;
+                        }
+                    }
+                    break index;
+                }
+                (Token::Symbol(s), _) if s == "R" || s == "Q" => {
+                    break self.parse_lfsc_resolution(importer, s == "R")?
+                }
+                (Token::Symbol(s), pos) => {
+                    return Err(self.error_at(ParserError::UnsupportedLfsc(s), pos))
+                }
+                (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
+            }
+        };
+        for _ in 0..num_assumptions {
+            self.expect_token(Token::CloseParen)?;
+        }
+        Ok(result)
+    }
+
+    /// Parses the arguments of a resolution term, and adds the corresponding `resolution` step.
+    /// This method assumes that the `(` and `R` or `Q` tokens were already consumed.
+    fn parse_lfsc_resolution(
+        &mut self,
+        importer: &mut LfscImporter,
+        is_pivot_positive_in_first: bool,
+    ) -> CarcaraResult<usize> {
+        for _ in 0..2 {
+            self.expect_token(Token::ReservedWord(Reserved::Underscore))?;
+        }
+        let first = self.parse_lfsc_term(importer)?;
+        let second = self.parse_lfsc_term(importer)?;
+        let atom = self.parse_term_expecting_sort(&Sort::Bool)?;
+        self.expect_token(Token::CloseParen)?;
+
+        let negated = self.pool.add(Term::Op(Operator::Not, vec![atom.clone()]));
+        let (first_pivot, second_pivot) = if is_pivot_positive_in_first {
+            (atom, negated)
+        } else {
+            (negated, atom)
+        };
+
+        let mut resolvent: Vec<Rc<Term>> = Vec::new();
+        let first_clause = importer.clause(first).iter().filter(|&t| *t != first_pivot);
+        let second_clause = importer
+            .clause(second)
+            .iter()
+            .filter(|&t| *t != second_pivot);
+        for t in first_clause.chain(second_clause) {
+            if !resolvent.contains(t) {
+                resolvent.push(t.clone());
+            }
+        }
+        Ok(importer.add_step(resolvent, "resolution", vec![first, second]))
+    }
+
+    /// Parses a `(holds <clause>)` type.
+    fn parse_lfsc_holds(&mut self) -> CarcaraResult<Vec<Rc<Term>>> {
+        self.expect_token(Token::OpenParen)?;
+        self.expect_lfsc_symbol("holds")?;
+        let clause = self.parse_lfsc_clause()?;
+        self.expect_token(Token::CloseParen)?;
+        Ok(clause)
+    }
+
+    /// Parses an LFSC clause, that is, a list of literals built using `clc` and `cln`.
+    fn parse_lfsc_clause(&mut self) -> CarcaraResult<Vec<Rc<Term>>> {
+        // Clauses are nested lists, so, similarly to assumptions, we parse them iteratively
+        let mut clause = Vec::new();
+        loop {
+            match self.next_token()? {
+                (Token::Symbol(s), _) if s == "cln" => break,
+                (Token::OpenParen, _) => self.expect_lfsc_symbol("clc")?,
+                (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
+            }
+            self.expect_token(Token::OpenParen)?;
+            let is_positive = match self.next_token()? {
+                (Token::Symbol(s), _) if s == "pos" => true,
+                (Token::Symbol(s), _) if s == "neg" => false,
+                (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
+            };
+            let atom = self.parse_term_expecting_sort(&Sort::Bool)?;
+            self.expect_token(Token::CloseParen)?;
+            clause.push(if is_positive {
+                atom
+            } else {
+                self.pool.add(Term::Op(Operator::Not, vec![atom]))
+            });
+        }
+        for _ in 0..clause.len() {
+            self.expect_token(Token::CloseParen)?;
+        }
+        Ok(clause)
+    }
+
+    fn expect_lfsc_symbol(&mut self, expected: &str) -> CarcaraResult<()> {
+        match self.next_token()? {
+            (Token::Symbol(s), _) if s == expected => Ok(()),
+            (other, pos) => Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Limits on the inputs accepted by the parser, for checking proofs from untrusted sources. Terms
+//! are parsed recursively, so a deeply nested term could overflow the stack of the parser, and a
+//! proof with too many commands, or a single huge token, could exhaust the memory. By default, no
+//! limits are set.
+
+use std::fmt;
+
+/// The limits on the inputs accepted by the parser. A limit is disabled if it is `None`. When a
+/// limit is exceeded, the parser stops with a `ParserError::ResourceLimitExceeded` error, even if
+/// it is recovering from errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// The maximum number of parentheses that may be open at any point of the input. This bounds
+    /// the nesting depth of terms. Note that the parentheses of the enclosing command are also
+    /// counted, so, for example, the clause of a `step` command is already at depth two.
+    pub max_nesting_depth: Option<usize>,
+
+    /// The maximum number of commands in a proof, counted as in `ParseProgress::commands`.
+    pub max_commands: Option<usize>,
+
+    /// The maximum length of a single token, in bytes.
+    pub max_token_length: Option<usize>,
+}
+
+/// A limit that was exceeded, together with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    NestingDepth(usize),
+    Commands(usize),
+    TokenLength(usize),
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NestingDepth(n) => write!(f, "nesting depth is limited to {}", n),
+            Self::Commands(n) => write!(f, "number of commands is limited to {}", n),
+            Self::TokenLength(n) => write!(f, "token length is limited to {} bytes", n),
+        }
+    }
+}
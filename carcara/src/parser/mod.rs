@@ -1,54 +1,449 @@
 //! A parser for the Alethe proof format.
 
+mod chunks;
+mod decompress;
 mod error;
+mod format;
 mod lexer;
+mod lfsc;
+mod limits;
+mod producer;
+mod progress;
+mod signature;
+mod suggestions;
 pub(crate) mod tests;
 
-pub use error::{ParserError, SortError};
-pub use lexer::{Lexer, Position, Reserved, Token};
+pub use crate::utils::Reserved;
+pub use chunks::{lex_parallel, LexedInput};
+pub use decompress::{decompress, MaybeCompressed};
+pub use error::{ApplicationSorts, ExpectedSort, ParserError, SortError};
+pub use format::ProofFormat;
+pub use lexer::{Lexer, LineIndex, Position, Span, Token, UnicodePolicy};
+pub use limits::{ResourceLimit, ResourceLimits};
+pub use producer::Producer;
+pub use progress::ParseProgress;
+pub use signature::{
+    Arity, OperatorSignature, SignatureTable, SortRule, SortRuleArgs, ARITHMETIC, ARRAYS,
+    BITVECTORS, CORE, FLOATING_POINT, ROUNDING_MODE_ALIASES, STRINGS,
+};
+pub use suggestions::RepairHint;
 
 use crate::{
-    ast::*,
+    ast::{printer::IndexedHead, *},
+    benchmarking::phases::{self, Phase},
+    num::Integer,
     utils::{HashCache, SymbolTable},
     CarcaraResult, Error,
 };
 use ahash::{AHashMap, AHashSet};
 use error::assert_num_args;
-use rug::Integer;
-use std::{io::BufRead, str::FromStr};
+use std::io::{self, BufRead};
+
+/// The configuration of the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// If `true`, the functions defined by `define-fun` commands in the problem are replaced by
+    /// their bodies wherever they are used. Otherwise, each definition is interpreted as a function
+    /// declaration and an `assert` command stating that the function is equal to its body.
+    /// Function definitions in the proof itself are always expanded.
+    pub apply_function_defs: bool,
+
+    /// If `true`, `let` bindings are eliminated from terms when parsing.
+    pub expand_lets: bool,
+
+    /// If `true`, terms of sort `Int` can be passed to arithmetic operators that are expecting a
+    /// term of sort `Real`.
+    pub allow_int_real_subtyping: bool,
+
+    /// If `true`, arithmetic operations and comparisons that mix `Int` and `Real` arguments, like
+    /// `(+ x 1)` where `x` is a `Real`, are accepted by promoting the `Int` arguments to `Real`.
+    /// Integer numerals are replaced by the equivalent real numerals, and other terms are wrapped
+    /// in `to_real`. The arguments of `/` are always promoted. Unlike with
+    /// `allow_int_real_subtyping`, the resulting terms are well sorted.
+    pub coerce_int_to_real: bool,
+
+    /// Whether integer numerals are interpreted as terms of sort `Real`. If this is `None`, this
+    /// is decided by the `set-logic` command in the problem: numerals are interpreted as reals if
+    /// the logic has reals but not integers, like `QF_LRA`.
+    pub interpret_integers_as_reals: Option<bool>,
+
+    /// If `true`, attributes that are not known by the parser are rejected in proof commands,
+    /// instead of being ignored. Declarations in the proof that are inconsistent with earlier
+    /// declarations of the same function or sort are also rejected, instead of shadowing them.
+    pub strict: bool,
+
+    /// How non-ASCII characters in symbols are handled by the lexer.
+    pub unicode_policy: UnicodePolicy,
+
+    /// If `true`, the parser doesn't stop at the first error in a proof. Instead, it skips to the
+    /// next command and keeps going, and all errors found are returned together, in an
+    /// `Error::ParserErrors`. Errors in the problem still stop the parser.
+    pub recover_from_errors: bool,
+
+    /// If `true`, attributes in annotated terms other than `:named` and `:pattern`, like `:qid` or
+    /// `:weight`, are ignored, together with their values. Otherwise, they are rejected.
+    pub ignore_unknown_attributes: bool,
+
+    /// If `true`, the comments in the proof are kept in `Proof::comments`, together with their
+    /// locations, so a proof that is printed again can include them. Since the comments are
+    /// captured by the lexer, a proof is always lexed sequentially when this is enabled, even if
+    /// many lexer threads are requested.
+    pub preserve_comments: bool,
+
+    /// If the number of terms in the pool exceeds this while parsing a proof, a warning is logged,
+    /// since checking the proof may use a lot of memory. If this is `None`, no warning is logged.
+    pub term_count_warning: Option<usize>,
+
+    /// The limits on the size of the input, for parsing proofs from untrusted sources. See
+    /// `ResourceLimits`.
+    pub limits: ResourceLimits,
+
+    /// The solver that produced the proof. The differences from the Alethe specification in the
+    /// proofs of this solver are normalized while parsing. See `Producer`.
+    pub producer: Producer,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            apply_function_defs: true,
+            expand_lets: false,
+            allow_int_real_subtyping: false,
+            coerce_int_to_real: false,
+            interpret_integers_as_reals: None,
+            strict: false,
+            unicode_policy: UnicodePolicy::default(),
+            recover_from_errors: false,
+            ignore_unknown_attributes: false,
+            preserve_comments: false,
+            term_count_warning: Some(10_000_000),
+            limits: ResourceLimits::default(),
+            producer: Producer::default(),
+        }
+    }
+}
 
 /// Parses an SMT problem instance (in the SMT-LIB format) and its associated proof (in the Alethe
 /// format). Returns the parsed proof, as well as the `TermPool` used in parsing. Can take any type
 /// that implements `BufRead`.
+///
+/// If the proof appears to be in the LFSC format, it is imported and translated into Alethe. If it
+/// appears to be in any other format, such as DRAT, this returns a `ParserError::WrongProofFormat`
+/// error instead of trying to parse it. Inputs compressed with gzip or zstd are decompressed while
+/// they are parsed (see `decompress`).
 pub fn parse_instance<T: BufRead>(
     problem: T,
     proof: T,
-    apply_function_defs: bool,
-    expand_lets: bool,
-    allow_int_real_subtyping: bool,
+    config: ParserConfig,
 ) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    parse_instance_with_lexer_threads(problem, proof, config, 1)
+}
+
+/// Similar to `parse_instance`, but if `lexer_threads` is greater than one, the proof is read into
+/// memory and lexed using that many threads (see `lex_parallel`). The problem is always lexed
+/// sequentially, since it is usually much smaller than the proof.
+pub fn parse_instance_with_lexer_threads<T: BufRead>(
+    problem: T,
+    proof: T,
+    config: ParserConfig,
+    lexer_threads: usize,
+) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    parse_instance_with_problems(vec![problem], proof, config, lexer_threads)
+}
+
+/// Similar to `parse_instance_with_lexer_threads`, but the problem is split into several inputs,
+/// which are parsed in order, as if they were a single problem. This is useful for benchmark suites
+/// that keep the declarations shared by many instances in separate prelude files. The declarations
+/// and assertions of all inputs are merged, and each input can use the declarations of the ones
+/// before it.
+///
+/// If `problems` is empty, the proof is parsed as a standalone proof, which must declare the
+/// functions and sorts it uses itself, using `declare-fun`, `declare-const` and `declare-sort`
+/// commands. In that case, the terms of the top-level `assume` commands are taken as the premises.
+pub fn parse_instance_with_problems<T: BufRead>(
+    problems: Vec<T>,
+    proof: T,
+    config: ParserConfig,
+    lexer_threads: usize,
+) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+    let is_standalone = problems.is_empty();
+    let problems = problems
+        .into_iter()
+        .map(decompress)
+        .collect::<io::Result<Vec<_>>>()?;
+    let proof = decompress(proof)?;
     let mut pool = TermPool::new();
-    let mut parser = Parser::new(
-        &mut pool,
-        problem,
-        apply_function_defs,
-        expand_lets,
-        allow_int_real_subtyping,
-    )?;
-    let (prelude, premises) = parser.parse_problem()?;
-    parser.reset(proof)?;
-    let commands = parser.parse_proof()?;
-
-    let proof = Proof { premises, commands };
+    let mut problems = problems.into_iter();
+    let first = match problems.next() {
+        Some(p) => TokenSource::Lexer(Lexer::with_unicode_policy(p, config.unicode_policy)?),
+        None => TokenSource::Lexed {
+            tokens: Vec::new().into_iter(),
+            line_index: LineIndex::default(),
+            eof: Span { start: 0, end: 0 },
+        },
+    };
+    let parser = Parser::from_tokens(&mut pool, config, first)?;
+    let (mut prelude, mut premises, mut parser) = parser.parse_problem()?;
+    for problem in problems {
+        let (other_prelude, other_premises) = parser.parse_problem(problem)?;
+        prelude.extend(other_prelude);
+        premises.extend(other_premises);
+    }
+
+    if !is_standalone {
+        let commands = parser.parse_proof_with_lexer_threads(proof, lexer_threads)?;
+        let proof = Proof {
+            premises,
+            commands,
+            spans: parser.command_spans(),
+            metadata: parser.proof_metadata(),
+            named_assertions: parser.named_assertions(),
+            comments: parser.proof_comments(),
+        };
+        return Ok((prelude, proof, pool));
+    }
+
+    // The declarations in a standalone proof are added to the prelude while parsing it
+    parser.0.problem = Some((prelude, premises));
+    let commands = parser.parse_proof_with_lexer_threads(proof, lexer_threads)?;
+    let spans = parser.command_spans();
+    let metadata = parser.proof_metadata();
+    let named_assertions = parser.named_assertions();
+    let comments = parser.proof_comments();
+    let (prelude, _) = parser.0.problem.take().unwrap();
+    let premises = commands
+        .iter()
+        .filter_map(|c| match c {
+            ProofCommand::Assume { term, .. } => Some(term.clone()),
+            _ => None,
+        })
+        .collect();
+    let proof = Proof {
+        premises,
+        commands,
+        spans,
+        metadata,
+        named_assertions,
+        comments,
+    };
     Ok((prelude, proof, pool))
 }
 
+/// An SMT problem that was parsed once, and can be used to parse many proofs of it, for example
+/// when checking the proofs given by different solvers for the same problem.
+///
+/// The problem terms are kept in a base pool, and each proof is parsed into a snapshot of it (see
+/// `TermPool::snapshot`). This way, the problem terms are shared by all proofs, instead of being
+/// allocated again for each of them, but terms added while parsing one proof are never visible to
+/// the others.
+pub struct ParsedProblem {
+    pool: TermPool,
+    prelude: ProblemPrelude,
+    premises: AHashSet<Rc<Term>>,
+    state: ParserState,
+    interpret_integers_as_reals: bool,
+    config: ParserConfig,
+}
+
+impl ParsedProblem {
+    /// Parses an SMT problem. The configuration is also used when parsing each proof.
+    pub fn new<T: BufRead>(problem: T, config: ParserConfig) -> CarcaraResult<Self> {
+        let mut pool = TermPool::new();
+        let parser = Parser::new(&mut pool, config, decompress(problem)?)?;
+        let (prelude, premises, ProofParser(parser)) = parser.parse_problem()?;
+        let state = parser.state;
+        let interpret_integers_as_reals = parser.interpret_integers_as_reals;
+        Ok(Self {
+            pool,
+            prelude,
+            premises,
+            state,
+            interpret_integers_as_reals,
+            config,
+        })
+    }
+
+    /// Returns the base pool, which contains the problem terms.
+    pub fn pool(&self) -> &TermPool {
+        &self.pool
+    }
+
+    /// Parses a proof of this problem, using `lexer_threads` threads to lex it, as in
+    /// `parse_instance_with_lexer_threads`. Returns the parsed proof, as well as the `TermPool`
+    /// used in parsing, which is a snapshot of the base pool.
+    pub fn parse_proof<T: BufRead>(
+        &mut self,
+        proof: T,
+        lexer_threads: usize,
+    ) -> CarcaraResult<(ProblemPrelude, Proof, TermPool)> {
+        let mut pool = self.pool.snapshot();
+        let mut parser = Parser::from_tokens(
+            &mut pool,
+            self.config,
+            TokenSource::Lexed {
+                tokens: Vec::new().into_iter(),
+                line_index: LineIndex::default(),
+                eof: Span::default(),
+            },
+        )?;
+        parser.state = self.state.clone();
+        parser.interpret_integers_as_reals = self.interpret_integers_as_reals;
+        let commands = parser.parse_proof_input(decompress(proof)?, lexer_threads)?;
+
+        let proof = Proof {
+            premises: self.premises.clone(),
+            commands,
+            spans: parser.command_spans(),
+            metadata: parser.proof_metadata.clone(),
+            named_assertions: self.state.named_assertions.clone(),
+            comments: parser.proof_comments(),
+        };
+        Ok((self.prelude.clone(), proof, pool))
+    }
+}
+
+/// The stage of parsing that comes after a problem was parsed, returned by `Parser::parse_problem`.
+/// This keeps the declarations and definitions of the problem, and can parse any number of proofs
+/// and terms that use them, each from its own input.
+pub struct ProofParser<'a, R>(Parser<'a, R>);
+
+impl<'a, R: BufRead> ProofParser<'a, R> {
+    /// Returns a mutable reference to the table of operator signatures used by the parser.
+    pub fn signatures_mut(&mut self) -> &mut SignatureTable {
+        self.0.signatures_mut()
+    }
+
+    /// Sets a function that is called while parsing each proof. See `Parser::on_progress`.
+    pub fn on_progress(&mut self, interval: usize, callback: impl FnMut(&ParseProgress) + 'static) {
+        self.0.on_progress(interval, callback);
+    }
+
+    /// Returns the statistics of the last proof parsed. See `Parser::progress`.
+    pub fn progress(&self) -> ParseProgress {
+        self.0.progress()
+    }
+
+    /// Parses an additional problem from `input`. Its declarations are added to the ones already
+    /// in the parser, and can use them. Returns only the prelude and premises of the new input.
+    pub fn parse_problem(
+        &mut self,
+        input: R,
+    ) -> CarcaraResult<(ProblemPrelude, AHashSet<Rc<Term>>)> {
+        self.0.reset(input)?;
+        self.0.parse_problem_commands()
+    }
+
+    /// Parses a proof from `input`. As in `parse_instance`, the format of the proof is detected
+    /// from the start of the input, and proofs in the LFSC format are imported.
+    pub fn parse_proof(&mut self, input: R) -> CarcaraResult<Vec<ProofCommand>> {
+        self.parse_proof_with_lexer_threads(input, 1)
+    }
+
+    /// Returns an iterator that parses the commands of a proof in the Alethe format from `input`
+    /// one at a time, as they are requested. This way, a large proof can be processed without
+    /// holding all of its commands in memory. The iterator returns the top-level commands of the
+    /// proof, and a subproof is only returned after its last step is parsed. After an error, the
+    /// iterator stops.
+    ///
+    /// The premises of a step reference commands by their index, so consumers that need them must
+    /// keep the commands that may be referenced. Unlike `ProofParser::parse_proof`, proofs in
+    /// other formats are not imported, and an error is returned instead.
+    pub fn proof_commands(&mut self, mut input: R) -> CarcaraResult<ProofCommands<'_, 'a, R>> {
+        match ProofFormat::detect(input.fill_buf()?) {
+            ProofFormat::Alethe | ProofFormat::Unknown => (),
+            other => return Err(Error::Parser(ParserError::WrongProofFormat(other), (1, 1))),
+        }
+        self.0.reset(input)?;
+        Ok(ProofCommands {
+            parser: &mut self.0,
+            open: OpenSubproofs::new(),
+            done: false,
+        })
+    }
+
+    /// Similar to `ProofParser::parse_proof`, but if `lexer_threads` is greater than one, the
+    /// proof is read into memory and lexed using that many threads (see `lex_parallel`).
+    pub fn parse_proof_with_lexer_threads(
+        &mut self,
+        input: R,
+        lexer_threads: usize,
+    ) -> CarcaraResult<Vec<ProofCommand>> {
+        self.0.parse_proof_input(input, lexer_threads)
+    }
+
+    /// Parses a proof in the Alethe format from an input that was already lexed, for example by
+    /// `lex_parallel`.
+    pub fn parse_lexed_proof(&mut self, input: LexedInput) -> CarcaraResult<Vec<ProofCommand>> {
+        self.0.reset_with_lexed(input);
+        self.0.parse_proof_commands()
+    }
+
+    /// Parses a single term from `input`.
+    pub fn parse_term(&mut self, input: R) -> CarcaraResult<Rc<Term>> {
+        self.0.reset(input)?;
+        self.0.parse_next_term()
+    }
+
+    /// Returns the location of each command in the last proof parsed, in the order the commands
+    /// are returned by `Proof::iter`. See `Proof::spans`.
+    pub fn command_spans(&self) -> Vec<SourceSpan> {
+        self.0.command_spans()
+    }
+
+    /// Returns the attributes given in `set-info` commands in the last proof parsed. See
+    /// `Proof::metadata`.
+    pub fn proof_metadata(&self) -> Vec<(String, String)> {
+        self.0.proof_metadata.clone()
+    }
+
+    /// Returns the comments in the last proof parsed, if the `preserve_comments` option is
+    /// enabled. See `Proof::comments`.
+    pub fn proof_comments(&self) -> Vec<Comment> {
+        self.0.proof_comments()
+    }
+
+    /// Returns the assertions in the problems parsed so far that were given a name with the
+    /// `:named` attribute. See `Proof::named_assertions`.
+    pub fn named_assertions(&self) -> Vec<(String, Rc<Term>)> {
+        self.0.state.named_assertions.clone()
+    }
+}
+
+/// An iterator over the top-level commands of a proof, returned by `ProofParser::proof_commands`.
+pub struct ProofCommands<'p, 'a, R> {
+    parser: &'p mut Parser<'a, R>,
+    open: OpenSubproofs,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for ProofCommands<'_, '_, R> {
+    type Item = CarcaraResult<ProofCommand>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.parser.next_top_level_command(&mut self.open);
+        self.done = !matches!(result, Ok(Some(_)));
+        result.transpose()
+    }
+}
+
 /// A function definition, from a `define-fun` command.
+#[derive(Clone)]
 struct FunctionDef {
     params: Vec<SortedVar>,
     body: Rc<Term>,
 }
 
+/// A sort definition, from a `define-sort` command. In the body, each parameter is represented as
+/// an `Atom` sort with no arguments.
+#[derive(Clone)]
+struct SortDef {
+    params: Vec<String>,
+    body: Rc<Sort>,
+}
+
 /// Represents a "raw" `anchor` command. This is only used while parsing, and does not appear in
 /// the final AST.
 struct AnchorCommand {
@@ -57,6 +452,37 @@ struct AnchorCommand {
     variable_args: Vec<SortedVar>,
 }
 
+/// The subproofs that were opened by an `anchor` command, but not yet closed, while parsing a proof.
+/// The first element of `commands_stack` holds the commands of the proof itself.
+struct OpenSubproofs {
+    commands_stack: Vec<Vec<ProofCommand>>,
+    end_step_stack: Vec<String>,
+    subproof_args_stack: Vec<AnchorArgs>,
+    finished_assumes: bool,
+
+    /// The number of top-level commands that were already taken out of the commands stack, when
+    /// the commands are returned one at a time.
+    num_taken: usize,
+}
+
+impl OpenSubproofs {
+    fn new() -> Self {
+        Self {
+            commands_stack: vec![Vec::new()],
+            end_step_stack: Vec::new(),
+            subproof_args_stack: Vec::new(),
+            finished_assumes: false,
+            num_taken: 0,
+        }
+    }
+}
+
+/// The assignment and variable arguments of an `anchor` command.
+type AnchorArgs = (Vec<(String, Rc<Term>)>, Vec<SortedVar>);
+
+/// The patterns of a quantifier term, given by `:pattern` attributes. See `Term::Quant`.
+type Patterns = Vec<Vec<Rc<Term>>>;
+
 /// Represents a "raw" `anchor` argument. This is only used while parsing, and does not appear in
 /// the final AST.
 enum AnchorArg {
@@ -66,81 +492,332 @@ enum AnchorArg {
 
 /// The state of the parser. This holds all the function, constant or sort declarations and
 /// definitions, as well as the term pool used by the parser.
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct ParserState {
-    symbol_table: SymbolTable<HashCache<Identifier>, Rc<Term>>,
+    symbol_table: SymbolTable<HashCache<Identifier>, Rc<Sort>>,
     function_defs: AHashMap<String, FunctionDef>,
     sort_declarations: AHashMap<String, usize>,
+    sort_defs: AHashMap<String, SortDef>,
     step_ids: SymbolTable<HashCache<String>, usize>,
+
+    /// Maps the id of each command in a subproof that was already closed to the id of the step
+    /// that ended it. This is only used to report better errors when one of these commands is
+    /// referenced from outside its subproof.
+    subproof_local_ids: AHashMap<String, String>,
+
+    /// The name of each assertion in the problem that was given a name with the `:named`
+    /// attribute, and the asserted term, in the order they were asserted.
+    named_assertions: Vec<(String, Rc<Term>)>,
+}
+
+/// The parts of the parser state that are restored when an assertion level is popped in a problem.
+/// The symbol table is not saved here, since it has scopes of its own.
+struct AssertionLevel {
+    function_defs: AHashMap<String, FunctionDef>,
+    sort_declarations: AHashMap<String, usize>,
+    sort_defs: AHashMap<String, SortDef>,
+    premises: AHashSet<Rc<Term>>,
+    num_prelude_sorts: usize,
+    num_prelude_functions: usize,
+    num_named_assertions: usize,
+}
+
+/// Where the parser reads its tokens from. Usually, tokens are lexed from the input as they are
+/// needed, but they may also have been lexed ahead of time, for example by `lex_parallel`.
+enum TokenSource<R> {
+    Lexer(Lexer<R>),
+    Lexed {
+        tokens: std::vec::IntoIter<(Token, Span)>,
+        line_index: LineIndex,
+        eof: Span,
+    },
+}
+
+impl<R: BufRead> TokenSource<R> {
+    /// Returns the next token. If its length exceeds `max_token_length`, an error is returned
+    /// instead.
+    fn next_token(&mut self, max_token_length: Option<usize>) -> CarcaraResult<(Token, Span)> {
+        let (token, span) = match self {
+            Self::Lexer(lexer) => phases::measure(Phase::Lexing, || lexer.next_token())?,
+            Self::Lexed { tokens, eof, .. } => tokens.next().unwrap_or((Token::Eof, *eof)),
+        };
+        match max_token_length {
+            Some(max) if span.end - span.start > max => Err(Error::Parser(
+                ParserError::ResourceLimitExceeded(ResourceLimit::TokenLength(max)),
+                self.line_index().position(span.start),
+            )),
+            _ => Ok((token, span)),
+        }
+    }
+
+    fn line_index(&self) -> &LineIndex {
+        match self {
+            Self::Lexer(lexer) => lexer.line_index(),
+            Self::Lexed { line_index, .. } => line_index,
+        }
+    }
 }
 
 /// A parser for the Alethe proof format.
+///
+/// The input given to `Parser::new` is parsed by one of `Parser::parse_problem`,
+/// `Parser::parse_proof` or `Parser::parse_term`. These consume the parser, so it can't be used
+/// again after it reached the end of its input. To parse proofs or terms that use the declarations
+/// of a problem, use the `ProofParser` returned by `Parser::parse_problem`.
 pub struct Parser<'a, R> {
     pool: &'a mut TermPool,
-    lexer: Lexer<R>,
+    tokens: TokenSource<R>,
     current_token: Token,
-    current_position: Position,
+    current_span: Span,
     state: ParserState,
+    config: ParserConfig,
     interpret_integers_as_reals: bool,
-    apply_function_defs: bool,
-    expand_lets: bool,
     problem: Option<(ProblemPrelude, AHashSet<Rc<Term>>)>,
     has_seen_trust_rule: bool,
-    allow_int_real_subtyping: bool,
+    signatures: SignatureTable,
+
+    /// The number of parentheses that were opened and not yet closed. This is used to find the
+    /// start of the next command when recovering from an error.
+    parens_depth: usize,
+
+    /// The errors collected so far when recovering from errors.
+    errors: Vec<(ParserError, Position)>,
+
+    /// The span of the last token that was consumed.
+    previous_span: Span,
+
+    /// The byte offsets of the opening and closing parentheses of each proof command parsed so
+    /// far. The line and column of each command are only computed when they are requested.
+    command_offsets: Vec<(usize, usize)>,
+
+    /// The attributes given in `set-info` commands in the proof parsed so far.
+    proof_metadata: Vec<(String, String)>,
+
+    /// While parsing an assertion, the names given with `:named` attributes to the terms in it.
+    assertion_names: Option<Vec<(String, Rc<Term>)>>,
+
+    /// The statistics of the proof parsed so far.
+    progress: ParseProgress,
+
+    /// The function set by `Parser::on_progress`, and the number of commands between each call.
+    progress_callback: Option<(usize, ProgressCallback)>,
+    has_warned_term_count: bool,
 }
 
+type ProgressCallback = Box<dyn FnMut(&ParseProgress)>;
+
 impl<'a, R: BufRead> Parser<'a, R> {
-    /// Constructs a new `Parser` from a type that implements `BufRead`. This operation can fail if
-    /// there is an IO or lexer error on the first token.
-    pub fn new(
+    /// Constructs a new `Parser` from a type that implements `BufRead`, using the given
+    /// configuration. This operation can fail if there is an IO or lexer error on the first token.
+    pub fn new(pool: &'a mut TermPool, config: ParserConfig, input: R) -> CarcaraResult<Self> {
+        let tokens = TokenSource::Lexer(Lexer::with_unicode_policy(input, config.unicode_policy)?);
+        Self::from_tokens(pool, config, tokens)
+    }
+
+    fn from_tokens(
         pool: &'a mut TermPool,
-        input: R,
-        apply_function_defs: bool,
-        expand_lets: bool,
-        allow_int_real_subtyping: bool,
+        config: ParserConfig,
+        mut tokens: TokenSource<R>,
     ) -> CarcaraResult<Self> {
         let mut state = ParserState::default();
-        let bool_sort = pool.add(Term::Sort(Sort::Bool));
+        let bool_sort = pool.add_sort(Sort::Bool);
         for iden in ["true", "false"] {
             let iden = HashCache::new(Identifier::Simple(iden.to_owned()));
             state.symbol_table.insert(iden, bool_sort.clone());
         }
-        let mut lexer = Lexer::new(input)?;
-        let (current_token, current_position) = lexer.next_token()?;
+        let (current_token, current_span) = tokens.next_token(config.limits.max_token_length)?;
         Ok(Parser {
             pool,
-            lexer,
+            tokens,
             current_token,
-            current_position,
+            current_span,
             state,
-            interpret_integers_as_reals: false,
-            apply_function_defs,
-            expand_lets,
+            config,
+            interpret_integers_as_reals: config.interpret_integers_as_reals.unwrap_or(false),
             problem: None,
             has_seen_trust_rule: false,
-            allow_int_real_subtyping,
+            signatures: SignatureTable::default(),
+            parens_depth: 0,
+            errors: Vec::new(),
+            previous_span: Span::default(),
+            command_offsets: Vec::new(),
+            proof_metadata: Vec::new(),
+            assertion_names: None,
+            progress: ParseProgress::default(),
+            progress_callback: None,
+            has_warned_term_count: false,
         })
     }
 
+    /// Returns a mutable reference to the table of operator signatures used by the parser. This
+    /// can be used to register additional operator names before parsing.
+    pub fn signatures_mut(&mut self) -> &mut SignatureTable {
+        &mut self.signatures
+    }
+
+    /// Sets a function that is called with the statistics of the proof being parsed every
+    /// `interval` commands, and once more when the proof is finished.
+    pub fn on_progress(&mut self, interval: usize, callback: impl FnMut(&ParseProgress) + 'static) {
+        self.progress_callback = Some((interval.max(1), Box::new(callback)));
+    }
+
+    /// Returns the statistics of the proof parsed so far.
+    pub fn progress(&self) -> ParseProgress {
+        self.progress
+    }
+
+    /// Parses the input as an SMT-LIB problem (see `Parser::parse_problem_commands`). Returns the
+    /// problem prelude and premises, as well as a `ProofParser` that can be used to parse proofs
+    /// and terms that use the problem declarations.
+    pub fn parse_problem(
+        mut self,
+    ) -> CarcaraResult<(ProblemPrelude, AHashSet<Rc<Term>>, ProofParser<'a, R>)> {
+        let (prelude, premises) = self.parse_problem_commands()?;
+        Ok((prelude, premises, ProofParser(self)))
+    }
+
+    /// Parses the input as a proof in the Alethe format, that doesn't depend on any declarations.
+    pub fn parse_proof(mut self) -> CarcaraResult<Vec<ProofCommand>> {
+        self.parse_proof_commands()
+    }
+
+    /// Parses the input as a single term, that doesn't depend on any declarations.
+    pub fn parse_term(mut self) -> CarcaraResult<Rc<Term>> {
+        self.parse_next_term()
+    }
+
     /// Resets the parser position and sets its input to `input`. This keeps the parser state,
-    /// including all function, constant and sort declarations.
-    pub fn reset(&mut self, input: R) -> CarcaraResult<()> {
-        let mut lexer = Lexer::new(input)?;
-        let (current_token, current_position) = lexer.next_token()?;
-        self.lexer = lexer;
+    /// including all function, constant and sort declarations, but forgets the step ids seen so
+    /// far, so the same declarations can be used to parse many independent proofs.
+    fn reset(&mut self, input: R) -> CarcaraResult<()> {
+        let mut lexer = Lexer::with_unicode_policy(input, self.config.unicode_policy)?;
+        if self.config.preserve_comments {
+            lexer.capture_comments();
+        }
+        self.set_tokens(TokenSource::Lexer(lexer))
+    }
+
+    /// Similar to `Parser::reset`, but the new input was already lexed, for example by
+    /// `lex_parallel`.
+    fn reset_with_lexed(&mut self, input: LexedInput) {
+        let eof = Span { start: input.len, end: input.len };
+        let tokens = TokenSource::Lexed {
+            tokens: input.tokens.into_iter(),
+            line_index: input.line_index,
+            eof,
+        };
+        // Reading from tokens that were already lexed can't fail
+        self.set_tokens(tokens).unwrap();
+    }
+
+    /// Resets the parser to read a proof from `proof`, and parses it. The format of the proof is
+    /// detected from the start of the input, and LFSC proofs are imported using
+    /// `Parser::parse_lfsc_commands`. If `lexer_threads` is greater than one, the proof is lexed in
+    /// parallel using `lex_parallel`.
+    fn parse_proof_input(
+        &mut self,
+        mut proof: R,
+        lexer_threads: usize,
+    ) -> CarcaraResult<Vec<ProofCommand>> {
+        let format = ProofFormat::detect(proof.fill_buf()?);
+        if lexer_threads > 1 && !self.config.preserve_comments {
+            let mut input = String::new();
+            proof.read_to_string(&mut input)?;
+            let lexed = phases::measure(Phase::Lexing, || {
+                lex_parallel(input.into(), lexer_threads, self.config.unicode_policy)
+            })?;
+            self.reset_with_lexed(lexed);
+        } else {
+            self.reset(proof)?;
+        }
+        match format {
+            ProofFormat::Alethe | ProofFormat::Unknown => self.parse_proof_commands(),
+            ProofFormat::Lfsc => self.parse_lfsc_commands(),
+            other => Err(Error::Parser(ParserError::WrongProofFormat(other), (1, 1))),
+        }
+    }
+
+    fn set_tokens(&mut self, mut tokens: TokenSource<R>) -> CarcaraResult<()> {
+        let max_token_length = self.config.limits.max_token_length;
+        let (current_token, current_span) = tokens.next_token(max_token_length)?;
+        self.tokens = tokens;
         self.current_token = current_token;
-        self.current_position = current_position;
+        self.current_span = current_span;
+        self.parens_depth = 0;
+        self.errors.clear();
+        self.command_offsets.clear();
+        self.proof_metadata.clear();
+        self.progress = ParseProgress::default();
+        self.has_warned_term_count = false;
+        self.state.step_ids = SymbolTable::new();
+        self.state.subproof_local_ids.clear();
         Ok(())
     }
 
     /// Advances the parser one token, and returns the previous `current_token`.
-    fn next_token(&mut self) -> CarcaraResult<(Token, Position)> {
+    fn next_token(&mut self) -> CarcaraResult<(Token, Span)> {
         use std::mem::replace;
 
-        let (new_token, new_position) = self.lexer.next_token()?;
+        let max_token_length = self.config.limits.max_token_length;
+        let (new_token, new_span) = self.tokens.next_token(max_token_length)?;
         let old_token = replace(&mut self.current_token, new_token);
-        let old_position = replace(&mut self.current_position, new_position);
-        Ok((old_token, old_position))
+        let old_span = replace(&mut self.current_span, new_span);
+        self.previous_span = old_span;
+        match old_token {
+            Token::OpenParen => {
+                self.parens_depth += 1;
+                match self.config.limits.max_nesting_depth {
+                    Some(max) if self.parens_depth > max => {
+                        let error =
+                            ParserError::ResourceLimitExceeded(ResourceLimit::NestingDepth(max));
+                        return Err(self.error_at(error, old_span));
+                    }
+                    _ => (),
+                }
+            }
+            Token::CloseParen => self.parens_depth = self.parens_depth.saturating_sub(1),
+            _ => (),
+        }
+        Ok((old_token, old_span))
+    }
+
+    /// Returns the location of each proof command parsed since the input was last reset.
+    fn command_spans(&self) -> Vec<SourceSpan> {
+        let line_index = self.tokens.line_index();
+        self.command_offsets
+            .iter()
+            .map(|&(start, end)| SourceSpan {
+                start: line_index.position(start),
+                end: line_index.position(end),
+            })
+            .collect()
+    }
+
+    /// Returns the comments read since the input was last reset. Comments are only captured when
+    /// the input is read by a lexer, and not when it was lexed ahead of time.
+    fn proof_comments(&self) -> Vec<Comment> {
+        let lexer = match &self.tokens {
+            TokenSource::Lexer(lexer) => lexer,
+            TokenSource::Lexed { .. } => return Vec::new(),
+        };
+        let line_index = lexer.line_index();
+        lexer
+            .comments()
+            .iter()
+            .map(|(span, text)| Comment {
+                text: text.clone(),
+                span: SourceSpan {
+                    start: line_index.position(span.start),
+                    end: line_index.position(span.end),
+                },
+            })
+            .collect()
+    }
+
+    /// Constructs an error at the start of the given span. The line and column of the error are
+    /// only computed here, so spans can be stored cheaply while parsing.
+    fn error_at(&self, err: ParserError, span: Span) -> Error {
+        Error::Parser(err, self.tokens.line_index().position(span.start))
     }
 
     /// Helper method to insert a `SortedVar` into the parser symbol table.
@@ -150,6 +827,77 @@ impl<'a, R: BufRead> Parser<'a, R> {
             .insert(HashCache::new(Identifier::Simple(symbol)), sort);
     }
 
+    /// Declares a function or constant, adding it to the prelude if a problem is being parsed.
+    fn declare_function(&mut self, name: String, sort: Rc<Sort>) {
+        self.insert_sorted_var((name.clone(), sort.clone()));
+        if let Some((prelude, _)) = &mut self.problem {
+            prelude.function_declarations.push((name, sort));
+        }
+    }
+
+    /// Declares a sort, adding it to the prelude if a problem is being parsed.
+    fn declare_sort(&mut self, name: String, arity: usize) {
+        if let Some((prelude, _)) = &mut self.problem {
+            prelude.sort_declarations.push((name.clone(), arity));
+        }
+
+        // User declared sorts are represented with the `Atom` sort kind, and an argument which is
+        // a string terminal representing the sort name.
+        self.state.sort_declarations.insert(name, arity);
+    }
+
+    /// Declares a function or constant in a proof. If a function with the same name was already
+    /// declared with the same sort, for example in the problem, the declaration is accepted and
+    /// nothing changes. If it was declared with a different sort, this is an error in strict mode.
+    /// Otherwise, a warning is logged, and the new declaration shadows the old one.
+    fn redeclare_function(
+        &mut self,
+        name: String,
+        sort: Rc<Sort>,
+        span: Span,
+    ) -> CarcaraResult<()> {
+        let iden = HashCache::new(Identifier::Simple(name.clone()));
+        if let Some(existing) = self.state.symbol_table.get(&iden) {
+            if *existing == sort {
+                return Ok(());
+            }
+            let err = ParserError::InconsistentDeclaration(
+                name.clone(),
+                Box::new(existing.as_ref().clone()),
+                Box::new(sort.as_ref().clone()),
+            );
+            self.report_inconsistent_declaration(err, span)?;
+        }
+        self.declare_function(name, sort);
+        Ok(())
+    }
+
+    /// Declares a sort in a proof. Like functions, sorts that were already declared with the same
+    /// arity are accepted, and sorts declared with a different arity are an error in strict mode.
+    fn redeclare_sort(&mut self, name: String, arity: usize, span: Span) -> CarcaraResult<()> {
+        match self.state.sort_declarations.get(&name) {
+            Some(&existing) if existing == arity => return Ok(()),
+            Some(&existing) => {
+                let err = ParserError::InconsistentSortDeclaration(name.clone(), existing, arity);
+                self.report_inconsistent_declaration(err, span)?;
+            }
+            None => (),
+        }
+        self.declare_sort(name, arity);
+        Ok(())
+    }
+
+    /// Returns an error for an inconsistent declaration in strict mode, and logs it as a warning
+    /// otherwise.
+    fn report_inconsistent_declaration(&self, err: ParserError, span: Span) -> CarcaraResult<()> {
+        let err = self.error_at(err, span);
+        if self.config.strict {
+            return Err(err);
+        }
+        log::warn!("{}", err);
+        Ok(())
+    }
+
     /// Shortcut for `self.problem.as_mut().unwrap().0`
     fn prelude(&mut self) -> &mut ProblemPrelude {
         &mut self.problem.as_mut().unwrap().0
@@ -165,172 +913,356 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let cached = HashCache::new(iden);
         let sort = match self.state.symbol_table.get(&cached) {
             Some(s) => s.clone(),
-            None => return Err(ParserError::UndefinedIden(cached.unwrap())),
+            None => {
+                let iden = cached.unwrap();
+                let suggestions = self.similar_identifiers(&iden);
+                return Err(ParserError::UndefinedIden(iden, suggestions));
+            }
         };
         Ok(self
             .pool
             .add(Term::Terminal(Terminal::Var(cached.unwrap(), sort))))
     }
 
-    /// Constructs and sort checks an operation term.
-    fn make_op(&mut self, op: Operator, args: Vec<Rc<Term>>) -> Result<Rc<Term>, ParserError> {
-        let sorts: Vec<_> = args.iter().map(|t| self.pool.sort(t)).collect();
-        match op {
-            Operator::Not => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Bool, sorts[0])?;
-            }
-            Operator::Implies => {
-                assert_num_args(&args, 2..)?;
-                for s in sorts {
-                    SortError::assert_eq(&Sort::Bool, s)?;
-                }
-            }
-            Operator::Or | Operator::And | Operator::Xor => {
-                // These operators can be called with only one argument
-                assert_num_args(&args, 1..)?;
-                for s in sorts {
-                    SortError::assert_eq(&Sort::Bool, s)?;
-                }
+    /// Returns the declared or defined functions and constants whose names are similar to `iden`,
+    /// to be suggested in an error. Only simple identifiers have suggestions.
+    fn similar_identifiers(&self, iden: &Identifier) -> Vec<String> {
+        let name = match iden {
+            Identifier::Simple(s) => s,
+            Identifier::Indexed(..) => return Vec::new(),
+        };
+        let declared = self
+            .state
+            .symbol_table
+            .keys()
+            .filter_map(|k| match k.as_ref() {
+                Identifier::Simple(s) => Some(s.as_str()),
+                Identifier::Indexed(..) => None,
+            });
+        let defined = self.state.function_defs.keys().map(String::as_str);
+        suggestions::closest_matches(name, declared.chain(defined))
+    }
+
+    /// Returns `true` if a symbol with the given name is declared in the current scope.
+    fn is_declared(&self, name: &str) -> bool {
+        let iden = HashCache::new(Identifier::Simple(name.to_owned()));
+        self.state.symbol_table.get(&iden).is_some()
+    }
+
+    /// Returns the signature of the operator with the given name if it is a constant, like
+    /// `re.none`, that is not shadowed by a declared symbol.
+    fn constant_signature(&self, name: &str) -> Option<OperatorSignature> {
+        let signature = self.signatures.get(name)?;
+        (signature.operator.is_constant() && !self.is_declared(name)).then(|| *signature)
+    }
+
+    /// Constructs and sort checks an operation term, using the given operator signature.
+    fn make_op(
+        &mut self,
+        signature: OperatorSignature,
+        args: Vec<Rc<Term>>,
+    ) -> Result<Rc<Term>, ParserError> {
+        assert_num_args(&args, signature.arity)?;
+        let args = if self.config.coerce_int_to_real {
+            self.coerce_int_args(signature.operator, args)
+        } else {
+            args
+        };
+        let sort_rule_args = SortRuleArgs {
+            pool: self.pool,
+            args: &args,
+            allow_int_real_subtyping: self.config.allow_int_real_subtyping,
+        };
+        phases::measure(Phase::SortChecking, || {
+            (signature.sort_rule)(sort_rule_args)
+        })
+        .map_err(|err| match err {
+            ParserError::SortError(e) => {
+                let head = signature.operator.to_string();
+                e.in_application(self.application_sorts(head, None, &args))
+                    .into()
             }
-            Operator::Equals | Operator::Distinct => {
-                assert_num_args(&args, 2..)?;
-                SortError::assert_all_eq(&sorts)?;
+            other => other,
+        })?;
+        Ok(self.pool.add(Term::Op(signature.operator, args)))
+    }
+
+    /// If the operator is an arithmetic operation or comparison, and its arguments mix the `Int`
+    /// and `Real` sorts, promotes the `Int` arguments to `Real`. See
+    /// `ParserConfig::coerce_int_to_real`.
+    fn coerce_int_args(&mut self, op: Operator, mut args: Vec<Rc<Term>>) -> Vec<Rc<Term>> {
+        use Operator::*;
+
+        let has_real_arg = || args.iter().any(|a| **self.pool.sort(a) == Sort::Real);
+        let should_coerce = match op {
+            RealDiv => true,
+            Add | Sub | Mult | LessThan | GreaterThan | LessEq | GreaterEq | Equals | Distinct => {
+                has_real_arg()
             }
-            Operator::Ite => {
-                assert_num_args(&args, 3)?;
-                SortError::assert_eq(&Sort::Bool, sorts[0])?;
-                SortError::assert_eq(sorts[1], sorts[2])?;
+            _ => false,
+        };
+        if !should_coerce {
+            return args;
+        }
+        for arg in &mut args {
+            if **self.pool.sort(arg) != Sort::Int {
+                continue;
             }
-            Operator::Add | Operator::Sub | Operator::Mult => {
-                // The `-` operator, in particular, can be called with only one argument, in which
-                // case it means negation instead of subtraction
-                if op == Operator::Sub {
-                    assert_num_args(&args, 1..)?;
-                } else {
-                    assert_num_args(&args, 2..)?;
+            *arg = match arg.as_ref() {
+                Term::Terminal(Terminal::Integer(i)) => self.pool.add(Term::real(i.clone())),
+                Term::Op(Sub, inner) if inner.len() == 1 && inner[0].is_number() => {
+                    let n = self.pool.add(Term::real(inner[0].as_number().unwrap()));
+                    self.pool.add(Term::Op(Sub, vec![n]))
                 }
+                _ => self.pool.add(Term::Op(ToReal, vec![arg.clone()])),
+            };
+        }
+        args
+    }
 
-                // All the arguments must be either Int or Real. Also, if we are not allowing
-                // Int/Real subtyping, all arguments must have the same sort
-                if self.allow_int_real_subtyping {
-                    for s in sorts {
-                        SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
-                    }
-                } else {
-                    SortError::assert_one_of(&[Sort::Int, Sort::Real], sorts[0])?;
-                    SortError::assert_all_eq(&sorts)?;
-                }
-            }
-            Operator::IntDiv => {
-                assert_num_args(&args, 2..)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-                SortError::assert_all_eq(&sorts)?;
+    /// Constructs and sort checks an indexed operation term, like `((_ extract i j) x)`.
+    fn make_indexed_op(
+        &mut self,
+        op: IndexedOperator,
+        indices: Vec<u64>,
+        args: Vec<Rc<Term>>,
+    ) -> Result<Rc<Term>, ParserError> {
+        let invalid_indices = || {
+            let indices = indices
+                .iter()
+                .map(|&i| IdentifierIndex::Numeral(i))
+                .collect();
+            ParserError::InvalidIndices(Identifier::Indexed(op.to_string(), indices))
+        };
+        if indices.len() != op.num_indices() {
+            return Err(invalid_indices());
+        }
+        match op {
+            // The floating-point constants, like `(_ NaN eb sb)`, can't be applied to arguments
+            _ if op.is_constant() => {
+                return Err(ParserError::WrongNumberOfArgs(0.into(), args.len()))
             }
-            Operator::RealDiv => {
-                assert_num_args(&args, 2..)?;
-
-                // Normally, the `/` operator may only receive Real arguments, but if we are
-                // allowing Int/Real subtyping, it may also receive Ints
-                if self.allow_int_real_subtyping {
-                    for s in sorts {
-                        SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
-                    }
-                } else {
-                    SortError::assert_eq(&Sort::Real, sorts[0])?;
-                    SortError::assert_all_eq(&sorts)?;
+            IndexedOperator::ToFp
+            | IndexedOperator::ToFpUnsigned
+            | IndexedOperator::FpToUbv
+            | IndexedOperator::FpToSbv => {
+                let are_indices_valid = match op {
+                    IndexedOperator::FpToUbv | IndexedOperator::FpToSbv => indices[0] > 0,
+                    _ => indices[0] > 1 && indices[1] > 1,
+                };
+                if !are_indices_valid {
+                    return Err(invalid_indices());
                 }
+                return self.make_float_conversion(op, indices, args);
             }
-            Operator::Mod => {
-                assert_num_args(&args, 2)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
-                SortError::assert_eq(&Sort::Int, sorts[1])?;
-            }
-            Operator::Abs => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
+            _ => (),
+        }
+        assert_num_args(&args, 1)?;
+
+        // The regular expression operators receive a `RegLan`, `divisible` receives an `Int`, and
+        // all others a bitvector, whose width is needed to check the indices
+        let arg_sort = self.pool.sort(&args[0]);
+        let width = match op {
+            IndexedOperator::RePower | IndexedOperator::ReLoop => {
+                SortError::assert_eq(&Sort::RegLan, arg_sort).map(|()| None)
             }
-            Operator::LessThan | Operator::GreaterThan | Operator::LessEq | Operator::GreaterEq => {
-                assert_num_args(&args, 2..)?;
-                // All the arguments must be either Int or Real sorted, but they don't need to all
-                // have the same sort
-                for s in sorts {
-                    SortError::assert_one_of(&[Sort::Int, Sort::Real], s)?;
-                }
+            IndexedOperator::Divisible => SortError::assert_eq(&Sort::Int, arg_sort).map(|()| None),
+            _ => SortError::assert_bitvec(arg_sort).map(Some),
+        };
+        let width = match width {
+            Ok(width) => width,
+            Err(e) => {
+                let head = IndexedHead(op, &indices).to_string();
+                let app = self.application_sorts(head, None, &args);
+                return Err(e.at_arg(0).in_application(app).into());
             }
-            Operator::ToReal => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Int, sorts[0])?;
+        };
+        let are_indices_valid = match op {
+            IndexedOperator::BvExtract => indices[1] <= indices[0] && indices[0] < width.unwrap(),
+            IndexedOperator::BvRepeat | IndexedOperator::Divisible => indices[0] > 0,
+            IndexedOperator::ReLoop => indices[0] <= indices[1],
+            IndexedOperator::BvZeroExtend
+            | IndexedOperator::BvSignExtend
+            | IndexedOperator::BvRotateLeft
+            | IndexedOperator::BvRotateRight
+            | IndexedOperator::RePower => true,
+            _ => unreachable!(),
+        };
+        if !are_indices_valid {
+            return Err(invalid_indices());
+        }
+        Ok(self.pool.add(Term::IndexedOp(op, indices, args)))
+    }
+
+    /// Constructs a conversion to or from a floating-point sort, like `((_ to_fp eb sb) RNE x)`.
+    /// The indices must have already been checked.
+    fn make_float_conversion(
+        &mut self,
+        op: IndexedOperator,
+        indices: Vec<u64>,
+        args: Vec<Rc<Term>>,
+    ) -> Result<Rc<Term>, ParserError> {
+        match op {
+            IndexedOperator::ToFp => assert_num_args(&args, 1..3)?,
+            _ => assert_num_args(&args, 2)?,
+        }
+        let sorts: Vec<&Sort> = args.iter().map(|a| self.pool.sort(a).as_ref()).collect();
+        let result = match (op, sorts.as_slice()) {
+            // A bitvector of width `eb + sb`, reinterpreted as a floating-point number
+            (IndexedOperator::ToFp, [bv]) => {
+                SortError::assert_eq(&Sort::BitVec(indices[0] + indices[1]), bv)
+                    .map_err(|e| e.at_arg(0))
             }
-            Operator::ToInt | Operator::IsInt => {
-                assert_num_args(&args, 1)?;
-                SortError::assert_eq(&Sort::Real, sorts[0])?;
+            (_, [rm, x]) => SortError::assert_eq(&Sort::RoundingMode, rm)
+                .map_err(|e| e.at_arg(0))
+                .and_then(|()| check_float_conversion_arg(op, x).map_err(|e| e.at_arg(1))),
+            _ => unreachable!(),
+        };
+        if let Err(e) = result {
+            let head = IndexedHead(op, &indices).to_string();
+            let app = self.application_sorts(head, None, &args);
+            return Err(e.in_application(app).into());
+        }
+        Ok(self.pool.add(Term::IndexedOp(op, indices, args)))
+    }
+
+    /// Constructs an application whose head is an indexed identifier. Currently, the only such
+    /// applications are of built-in indexed operators.
+    fn make_indexed_app(
+        &mut self,
+        iden: Identifier,
+        args: Vec<Rc<Term>>,
+    ) -> Result<Rc<Term>, ParserError> {
+        if let Identifier::Indexed(name, indices) = &iden {
+            if let (Ok(op), Some(indices)) = (name.parse(), numeral_indices(indices)) {
+                return self.make_indexed_op(op, indices, args);
             }
-            Operator::Select => {
-                assert_num_args(&args, 2)?;
-                match sorts[0] {
-                    Sort::Array(_, _) => (),
-                    got => {
-                        // Instead of creating some special case for sort errors with parametric
-                        // sorts, we just create a sort `Y` to represent the sort parameter. We
-                        // infer the `X` sort from the second operator argument. This may be
-                        // changed later
-                        let got = got.clone();
-                        let x = sorts[1].clone();
-                        let x = self.pool.add(Term::Sort(x));
-                        let y = self
-                            .pool
-                            .add(Term::Sort(Sort::Atom("Y".to_owned(), Vec::new())));
-                        return Err(SortError {
-                            expected: vec![Sort::Array(x, y)],
-                            got,
-                        }
-                        .into());
+        }
+        Err(ParserError::UndefinedIden(iden, Vec::new()))
+    }
+
+    /// Constructs a term from an indexed identifier that is not applied to any arguments. This is
+    /// either a bitvector constant of the form `(_ bvX n)`, a floating-point constant like
+    /// `(_ NaN eb sb)`, or a variable.
+    fn make_indexed_constant(&mut self, iden: Identifier) -> Result<Rc<Term>, ParserError> {
+        if let Identifier::Indexed(name, indices) = &iden {
+            let op = name
+                .parse::<IndexedOperator>()
+                .ok()
+                .filter(|op| op.is_constant());
+            if let Some(op) = op {
+                return match numeral_indices(indices).as_deref() {
+                    Some(&[eb, sb]) if eb > 1 && sb > 1 => {
+                        Ok(self.pool.add(Term::IndexedOp(op, vec![eb, sb], Vec::new())))
                     }
-                }
+                    _ => Err(ParserError::InvalidIndices(iden)),
+                };
             }
-            Operator::Store => {
-                assert_num_args(&args, 3)?;
-                match sorts[0] {
-                    Sort::Array(x, y) => {
-                        SortError::assert_eq(x.as_sort().unwrap(), sorts[1])?;
-                        SortError::assert_eq(y.as_sort().unwrap(), sorts[2])?;
+        }
+        let literal = match &iden {
+            Identifier::Indexed(name, indices) => name
+                .strip_prefix("bv")
+                .filter(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+                .zip(numeral_indices(indices)),
+            Identifier::Simple(_) => None,
+        };
+        match literal {
+            Some((digits, indices)) => match indices.as_slice() {
+                &[width] if width > 0 => {
+                    let value: Integer = digits.parse().unwrap();
+                    if value.to_string_radix(2).len() as u64 > width {
+                        return Err(ParserError::InvalidIndices(iden));
                     }
-                    got => {
-                        let got = got.clone();
-                        let [x, y] = [sorts[0], sorts[1]].map(|s| Term::Sort(s.clone()));
-                        return Err(SortError {
-                            expected: vec![Sort::Array(self.pool.add(x), self.pool.add(y))],
-                            got,
-                        }
-                        .into());
+                    Ok(self.pool.add(Term::bitvec(value, width)))
+                }
+                _ => Err(ParserError::InvalidIndices(iden)),
+            },
+            None => self.make_var(iden),
+        }
+    }
+
+    /// Constructs a sort from an indexed identifier, like `(_ BitVec n)` or
+    /// `(_ FloatingPoint eb sb)`.
+    fn make_indexed_sort(iden: Identifier) -> Result<Sort, ParserError> {
+        match &iden {
+            Identifier::Indexed(name, indices) if name == "BitVec" => match indices.as_slice() {
+                &[IdentifierIndex::Numeral(width)] if width > 0 => Ok(Sort::BitVec(width)),
+                _ => Err(ParserError::InvalidIndices(iden)),
+            },
+            Identifier::Indexed(name, indices) if name == "FloatingPoint" => {
+                match indices.as_slice() {
+                    &[IdentifierIndex::Numeral(eb), IdentifierIndex::Numeral(sb)]
+                        if eb > 1 && sb > 1 =>
+                    {
+                        Ok(Sort::FloatingPoint(eb, sb))
                     }
+                    _ => Err(ParserError::InvalidIndices(iden)),
                 }
             }
+            _ => Err(ParserError::UndefinedSort(iden.to_string())),
+        }
+    }
+
+    /// Collects the sorts involved in a function application or operation term, to be reported in
+    /// a sort error.
+    fn application_sorts(
+        &self,
+        head: String,
+        rank: Option<Vec<Sort>>,
+        args: &[Rc<Term>],
+    ) -> ApplicationSorts {
+        ApplicationSorts {
+            head,
+            rank,
+            arg_sorts: args
+                .iter()
+                .map(|a| self.pool.sort(a).as_ref().clone())
+                .collect(),
         }
-        Ok(self.pool.add(Term::Op(op, args)))
     }
 
-    /// Constructs and sort checks an application term.
+    /// Constructs and sort checks an application term. If `allow_partial` is `true`, the function
+    /// may be given fewer arguments than it expects, in which case the result is a function of the
+    /// remaining arguments.
     fn make_app(
         &mut self,
         function: Rc<Term>,
-        args: Vec<Rc<Term>>,
+        mut args: Vec<Rc<Term>>,
+        allow_partial: bool,
     ) -> Result<Rc<Term>, ParserError> {
         let sorts = {
             let function_sort = self.pool.sort(&function);
-            if let Sort::Function(sorts) = function_sort {
-                sorts
+            if let Sort::Function(sorts) = function_sort.as_ref() {
+                sorts.clone()
             } else {
                 // Function does not have function sort
-                return Err(ParserError::NotAFunction(function_sort.clone()));
+                return Err(ParserError::NotAFunction(function_sort.as_ref().clone()));
             }
         };
-        assert_num_args(&args, sorts.len() - 1)?;
-        for i in 0..args.len() {
-            SortError::assert_eq(sorts[i].as_sort().unwrap(), self.pool.sort(&args[i]))?;
+        let (return_sort, params) = sorts.split_last().unwrap();
+
+        // If the function returns another function, any extra arguments are passed to the result,
+        // as in `(f a b)` when `f` has sort `(-> A (-> B C))`
+        let extra_args = match return_sort.as_ref() {
+            Sort::Function(_) if args.len() > params.len() => args.split_off(params.len()),
+            _ => Vec::new(),
+        };
+        if !allow_partial || args.len() > params.len() {
+            assert_num_args(&args, params.len())?;
+        }
+        for (i, (arg, param)) in args.iter().zip(params).enumerate() {
+            if let Err(e) = SortError::assert_eq(param, self.pool.sort(arg)) {
+                let rank = sorts.iter().map(|s| s.as_ref().clone()).collect();
+                let app = self.application_sorts(function.to_string(), Some(rank), &args);
+                return Err(e.at_arg(i).in_application(app).into());
+            }
+        }
+        let app = self.pool.add(Term::App(function, args));
+        if extra_args.is_empty() {
+            Ok(app)
+        } else {
+            self.make_app(app, extra_args, allow_partial)
         }
-        Ok(self.pool.add(Term::App(function, args)))
     }
 
     /// Consumes the current token if it equals `expected`. Returns an error otherwise.
@@ -339,7 +1271,16 @@ impl<'a, R: BufRead> Parser<'a, R> {
         if got == expected {
             Ok(())
         } else {
-            Err(Error::Parser(ParserError::UnexpectedToken(got), pos))
+            Err(self.error_at(ParserError::UnexpectedToken(got), pos))
+        }
+    }
+
+    /// Returns the error for an unexpected token, with a hint if the token is a known near miss of
+    /// another token.
+    fn unexpected_token(token: Token) -> ParserError {
+        match RepairHint::for_near_miss(&token) {
+            Some(hint) => ParserError::UnexpectedTokenWithHint(token, hint),
+            None => ParserError::UnexpectedToken(token),
         }
     }
 
@@ -348,7 +1289,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     fn expect_symbol(&mut self) -> CarcaraResult<String> {
         match self.next_token()? {
             (Token::Symbol(s), _) => Ok(s),
-            (other, pos) => Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
+            (other, pos) => Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
         }
     }
 
@@ -357,7 +1298,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
     fn expect_keyword(&mut self) -> CarcaraResult<String> {
         match self.next_token()? {
             (Token::Keyword(s), _) => Ok(s),
-            (other, pos) => Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
+            (other, pos) => Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
         }
     }
 
@@ -366,10 +1307,29 @@ impl<'a, R: BufRead> Parser<'a, R> {
     fn expect_numeral(&mut self) -> CarcaraResult<Integer> {
         match self.next_token()? {
             (Token::Numeral(n), _) => Ok(n),
-            (other, pos) => Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
+            (other, pos) => Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
         }
     }
 
+    /// Parses an indexed identifier, of the form `(_ <symbol> <index>+)`, where each index is either
+    /// a numeral or a symbol. This method assumes that the `(` and `_` tokens were already
+    /// consumed.
+    fn parse_indexed_identifier(&mut self) -> CarcaraResult<Identifier> {
+        let name = self.expect_symbol()?;
+        let indices = self.parse_sequence(
+            |p| match p.next_token()? {
+                (Token::Numeral(n), pos) => match n.to_u64() {
+                    Some(n) => Ok(IdentifierIndex::Numeral(n)),
+                    None => Err(p.error_at(ParserError::InvalidIndex(n), pos)),
+                },
+                (Token::Symbol(s), _) => Ok(IdentifierIndex::Symbol(s)),
+                (other, pos) => Err(p.error_at(ParserError::UnexpectedToken(other), pos)),
+            },
+            true,
+        )?;
+        Ok(Identifier::Indexed(name, indices))
+    }
+
     /// Calls `parse_func` repeatedly until a closing parenthesis is reached. If `non_empty` is
     /// true, empty sequences will result in an error. This method consumes the ending `)` token.
     fn parse_sequence<T, F>(&mut self, mut parse_func: F, non_empty: bool) -> CarcaraResult<Vec<T>>
@@ -381,10 +1341,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
             result.push(parse_func(self)?);
         }
         if non_empty && result.is_empty() {
-            Err(Error::Parser(
-                ParserError::EmptySequence,
-                self.current_position,
-            ))
+            Err(self.error_at(ParserError::EmptySequence, self.current_span))
         } else {
             self.next_token()?; // Consume `)` token
             Ok(result)
@@ -401,7 +1358,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
                 (Token::OpenParen, _) => 1,
                 (Token::CloseParen, _) => -1,
                 (Token::Eof, pos) => {
-                    return Err(Error::Parser(ParserError::UnexpectedToken(Token::Eof), pos))
+                    return Err(self.error_at(ParserError::UnexpectedToken(Token::Eof), pos))
                 }
                 _ => 0,
             };
@@ -416,33 +1373,23 @@ impl<'a, R: BufRead> Parser<'a, R> {
         Ok(())
     }
 
-    /// Consumes and ignores attributes and their values until a closing parenthesis is reached.
+    /// Consumes and ignores attributes and their values until a closing parenthesis is reached. In
+    /// strict mode, these attributes are instead rejected.
     fn ignore_remaining_attributes(&mut self) -> CarcaraResult<()> {
-        while let Token::Keyword(_) = self.current_token {
-            self.next_token()?;
-            match self.current_token {
-                // If we reached the closing parenthesis or the end of the file, we stop
-                Token::CloseParen | Token::Eof => break,
-
-                // If there is no value for this attribute, we may encounter the next attribute, in
-                // which case we must continue without consuming the keyword token
-                Token::Keyword(_) => (),
-
-                // If there is a single token as a value we consume it
-                Token::Symbol(_)
-                | Token::Numeral(_)
-                | Token::Decimal(_)
-                | Token::String(_)
-                | Token::ReservedWord(_) => {
-                    self.next_token()?;
-                }
-
-                // And if the value is an s-expression we read tokens until it's closed
-                Token::OpenParen => {
-                    self.next_token()?;
-                    self.ignore_until_close_parens()?;
-                }
+        while let Token::Keyword(k) = &self.current_token {
+            // Misspellings of the known attributes are never ignored, since the attribute that was
+            // meant would be silently lost
+            if let Some(hint) = RepairHint::for_near_miss(&self.current_token) {
+                let (token, pos) = self.next_token()?;
+                let err = ParserError::UnexpectedTokenWithHint(token, hint);
+                return Err(self.error_at(err, pos));
             }
+            if self.config.strict {
+                let err = ParserError::UnknownAttribute(k.clone());
+                return Err(self.error_at(err, self.current_span));
+            }
+            self.next_token()?;
+            self.ignore_attribute_value()?;
             if self.current_token == Token::CloseParen {
                 break;
             }
@@ -450,6 +1397,33 @@ impl<'a, R: BufRead> Parser<'a, R> {
         Ok(())
     }
 
+    /// Consumes and ignores the value of an attribute whose keyword was already consumed. If the
+    /// attribute has no value, nothing is consumed.
+    fn ignore_attribute_value(&mut self) -> CarcaraResult<()> {
+        match self.current_token {
+            // If there is no value for this attribute, we may encounter the next attribute, the
+            // closing parenthesis or the end of the file, none of which should be consumed
+            Token::Keyword(_) | Token::CloseParen | Token::Eof => (),
+
+            // If there is a single token as a value we consume it
+            Token::Symbol(_)
+            | Token::Numeral(_)
+            | Token::Decimal(_)
+            | Token::BitVec(..)
+            | Token::String(_)
+            | Token::ReservedWord(_) => {
+                self.next_token()?;
+            }
+
+            // And if the value is an s-expression we read tokens until it's closed
+            Token::OpenParen => {
+                self.next_token()?;
+                self.ignore_until_close_parens()?;
+            }
+        }
+        Ok(())
+    }
+
     /// Reads an SMT-LIB script and parses the assertions, declarations and definitions. The
     /// following commands are parsed:
     ///
@@ -458,48 +1432,52 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// - `declare-fun`
     /// - `declare-sort`
     /// - `define-fun`
+    /// - `define-sort`
     /// - `set-logic`
+    /// - `push`
+    /// - `pop`
     ///
     /// All other commands are ignored. This method returns a hash set containing the premises
-    /// introduced in `assert` commands.
-    pub fn parse_problem(&mut self) -> CarcaraResult<(ProblemPrelude, AHashSet<Rc<Term>>)> {
+    /// introduced in `assert` commands. Declarations, definitions and assertions made after a
+    /// `push` command are discarded by the matching `pop` command, so only the ones in the
+    /// assertion levels that are still open at the end of the problem are kept.
+    fn parse_problem_commands(&mut self) -> CarcaraResult<(ProblemPrelude, AHashSet<Rc<Term>>)> {
         self.problem = Some((ProblemPrelude::default(), AHashSet::new()));
+        let mut levels = Vec::new();
+        let mut depth = 0usize;
 
         while self.current_token != Token::Eof {
             self.expect_token(Token::OpenParen)?;
             match self.next_token()?.0 {
                 Token::ReservedWord(Reserved::DeclareFun) => {
                     let (name, sort) = self.parse_declare_fun()?;
-                    self.insert_sorted_var((name.clone(), sort.clone()));
-                    self.prelude().function_declarations.push((name, sort));
+                    self.declare_function(name, sort);
                     continue;
                 }
                 Token::ReservedWord(Reserved::DeclareConst) => {
-                    let name = self.expect_symbol()?;
-                    let sort = self.parse_sort()?;
-                    let sort = self.pool.add(sort);
-                    self.expect_token(Token::CloseParen)?;
-                    self.insert_sorted_var((name.clone(), sort.clone()));
-                    self.prelude().function_declarations.push((name, sort));
+                    let (name, sort) = self.parse_declare_const()?;
+                    self.declare_function(name, sort);
                     continue;
                 }
                 Token::ReservedWord(Reserved::DeclareSort) => {
                     let (name, arity) = self.parse_declare_sort()?;
-
-                    self.prelude().sort_declarations.push((name.clone(), arity));
-
-                    // User declared sorts are represented with the `Atom` sort kind, and an
-                    // argument which is a string terminal representing the sort name.
-                    self.state.sort_declarations.insert(name, arity);
+                    self.declare_sort(name, arity);
+                    continue;
+                }
+                Token::ReservedWord(Reserved::DefineSort) => {
+                    // Sort definitions are expanded when they are used, so they don't need to be
+                    // added to the prelude
+                    let (name, sort_def) = self.parse_define_sort()?;
+                    self.state.sort_defs.insert(name, sort_def);
                     continue;
                 }
                 Token::ReservedWord(Reserved::DefineFun) => {
                     let (name, func_def) = self.parse_define_fun()?;
 
-                    if self.apply_function_defs {
+                    if self.config.apply_function_defs {
                         self.state.function_defs.insert(name, func_def);
                     } else {
-                        // If `self.apply_function_defs` is false, we instead add the function name
+                        // If `apply_function_defs` is false, we instead add the function name
                         // to the symbol table, and add a new premise that defines the function
                         let lambda_term = if func_def.params.is_empty() {
                             func_def.body
@@ -507,9 +1485,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
                             self.pool
                                 .add(Term::Lambda(BindingList(func_def.params), func_def.body))
                         };
-                        let sort = self
-                            .pool
-                            .add(Term::Sort(self.pool.sort(&lambda_term).clone()));
+                        let sort = self.pool.sort(&lambda_term).clone();
                         let var = (name, sort);
                         self.insert_sorted_var(var.clone());
                         let var_term = self.pool.add(var.into());
@@ -521,8 +1497,16 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     continue;
                 }
                 Token::ReservedWord(Reserved::Assert) => {
-                    let term = self.parse_term()?;
+                    self.assertion_names = Some(Vec::new());
+                    let term = self.parse_next_term();
+                    let names = self.assertion_names.take().unwrap();
+                    let term = term?;
                     self.expect_token(Token::CloseParen)?;
+
+                    // Only the names given to the whole assertion are kept, not the ones given
+                    // to its subterms
+                    let named = names.into_iter().filter(|(_, t)| *t == term);
+                    self.state.named_assertions.extend(named);
                     self.premises().insert(term);
                 }
                 Token::ReservedWord(Reserved::SetLogic) => {
@@ -532,8 +1516,57 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
                     // When the problem's logic contains real numbers but not integers, integer
                     // literals should be parsed as reals. For instance, `1` should be interpreted
-                    // as `1.0`.
-                    self.interpret_integers_as_reals = logic.contains('R') && !logic.contains('I');
+                    // as `1.0`. This can be overridden in the parser configuration
+                    if self.config.interpret_integers_as_reals.is_none() {
+                        self.interpret_integers_as_reals =
+                            logic.contains('R') && !logic.contains('I');
+                    }
+                }
+                Token::ReservedWord(Reserved::CheckSatAssuming) => {
+                    // The proof of a `check-sat-assuming` query may use the assumed literals as
+                    // if they were asserted, so we add them to the premises
+                    self.expect_token(Token::OpenParen)?;
+                    let assumptions =
+                        self.parse_sequence(|p| p.parse_term_expecting_sort(&Sort::Bool), false)?;
+                    self.expect_token(Token::CloseParen)?;
+                    self.premises().extend(assumptions);
+                }
+                Token::ReservedWord(Reserved::Push) => {
+                    // All the levels opened by a single `push` command start from the same state,
+                    // so we save it only once, together with the number of levels
+                    let n = self.parse_num_levels()?;
+                    if n > 0 {
+                        levels.push((self.save_assertion_level(), n));
+                        depth = depth.saturating_add(n);
+                    }
+                }
+                Token::ReservedWord(Reserved::Pop) => {
+                    let pos = self.current_span;
+                    let mut n = self.parse_num_levels()?;
+                    if n > depth {
+                        let err = ParserError::PopTooManyLevels(n, depth);
+                        return Err(self.error_at(err, pos));
+                    }
+                    depth -= n;
+                    while n > 0 {
+                        let (level, count) = levels.pop().unwrap();
+                        self.restore_assertion_level(level);
+                        if n < count {
+                            // Some of the levels opened by this `push` are still open, so the
+                            // restored state must be saved again for them
+                            levels.push((self.save_assertion_level(), count - n));
+                            break;
+                        }
+                        n -= count;
+                    }
+                }
+                Token::ReservedWord(Reserved::GetProof) => {
+                    self.expect_token(Token::CloseParen)?;
+                }
+                Token::ReservedWord(Reserved::Exit) => {
+                    // Anything after an `exit` command is ignored by the solver, so we stop parsing
+                    self.expect_token(Token::CloseParen)?;
+                    break;
                 }
                 _ => {
                     // If the command is not one of the commands we care about, we just ignore it.
@@ -545,116 +1578,388 @@ impl<'a, R: BufRead> Parser<'a, R> {
         Ok(self.problem.take().unwrap())
     }
 
+    /// Parses the number of assertion levels in a `push` or `pop` command, and the closing
+    /// parenthesis. If the number is omitted, it defaults to one. This method assumes that the `(`
+    /// and `push` or `pop` tokens were already consumed.
+    fn parse_num_levels(&mut self) -> CarcaraResult<usize> {
+        if self.current_token == Token::CloseParen {
+            self.next_token()?;
+            return Ok(1);
+        }
+        let pos = self.current_span;
+        let n = self.expect_numeral()?;
+        self.expect_token(Token::CloseParen)?;
+        n.to_usize()
+            .ok_or_else(|| self.error_at(ParserError::InvalidNumLevels(n), pos))
+    }
+
+    /// Saves the current declarations, definitions and premises, and opens a new scope in the
+    /// symbol table. This is used to handle `push` commands in problems.
+    fn save_assertion_level(&mut self) -> AssertionLevel {
+        self.state.symbol_table.push_scope();
+        let (prelude, premises) = self.problem.as_ref().unwrap();
+        AssertionLevel {
+            function_defs: self.state.function_defs.clone(),
+            sort_declarations: self.state.sort_declarations.clone(),
+            sort_defs: self.state.sort_defs.clone(),
+            premises: premises.clone(),
+            num_prelude_sorts: prelude.sort_declarations.len(),
+            num_prelude_functions: prelude.function_declarations.len(),
+            num_named_assertions: self.state.named_assertions.len(),
+        }
+    }
+
+    /// Restores the state saved by `Parser::save_assertion_level`, discarding everything that was
+    /// declared, defined or asserted since then. This is used to handle `pop` commands in problems.
+    fn restore_assertion_level(&mut self, level: AssertionLevel) {
+        self.state.symbol_table.pop_scope();
+        self.state.function_defs = level.function_defs;
+        self.state.sort_declarations = level.sort_declarations;
+        self.state.sort_defs = level.sort_defs;
+        self.state
+            .named_assertions
+            .truncate(level.num_named_assertions);
+        let (prelude, premises) = self.problem.as_mut().unwrap();
+        *premises = level.premises;
+        prelude.sort_declarations.truncate(level.num_prelude_sorts);
+        prelude
+            .function_declarations
+            .truncate(level.num_prelude_functions);
+    }
+
     /// Parses a proof in the Alethe format. All function, constant and sort declarations needed
     /// should already be in the parser state.
-    pub fn parse_proof(&mut self) -> CarcaraResult<Vec<ProofCommand>> {
+    ///
+    /// If `ParserConfig::recover_from_errors` is `true`, an error in a command doesn't stop the
+    /// parser. Instead, the rest of the command is skipped, and the errors in all commands are
+    /// returned together at the end. Since the skipped commands are missing from the proof, later
+    /// commands that reference them may also be reported as errors.
+    fn parse_proof_commands(&mut self) -> CarcaraResult<Vec<ProofCommand>> {
+        let mut open = OpenSubproofs::new();
+        let mut commands = Vec::new();
+        while let Some(command) = self.next_top_level_command(&mut open)? {
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+
+    /// Parses proof commands until a command is completed at the top level of the proof, and
+    /// returns it. A subproof is only completed when its last step is parsed. Returns `None` when
+    /// the end of the input is reached.
+    fn next_top_level_command(
+        &mut self,
+        open: &mut OpenSubproofs,
+    ) -> CarcaraResult<Option<ProofCommand>> {
         // To avoid stack overflows in proofs with many nested subproofs, we parse the subproofs
         // iteratively, instead of recursively
-        let mut commands_stack = vec![Vec::new()];
-        let mut end_step_stack = Vec::new();
-        let mut subproof_args_stack = Vec::new();
-
-        let mut finished_assumes = false;
-
         while self.current_token != Token::Eof {
-            self.expect_token(Token::OpenParen)?;
-            let (token, position) = self.next_token()?;
-            let (id, command) = match token {
-                Token::ReservedWord(Reserved::Assume) => {
-                    let (id, term) = self.parse_assume_command()?;
-                    if end_step_stack.is_empty() && finished_assumes {
-                        log::warn!("`assume` command '{}' appears after `step` commands", &id);
-                    }
-                    (id.clone(), ProofCommand::Assume { id, term })
+            let start = self.current_span;
+            let num_scopes = self.state.symbol_table.num_scopes();
+            if let Err(e) = self.parse_proof_command(open) {
+                self.recover_from_error(e, start, num_scopes)?;
+            }
+            if open.commands_stack.len() == 1 {
+                if let Some(command) = open.commands_stack[0].pop() {
+                    open.num_taken += 1;
+                    return Ok(Some(command));
                 }
-                Token::ReservedWord(Reserved::Step) => {
-                    finished_assumes = true;
-                    let step = self.parse_step_command()?;
-                    (step.id.clone(), ProofCommand::Step(step))
+            }
+        }
+        self.finish_proof_commands(open)?;
+        Ok(None)
+    }
+
+    /// Finishes parsing a proof once the end of the input is reached. Returns an error if a
+    /// subproof was not closed, or if any errors were found while recovering from errors.
+    fn finish_proof_commands(&mut self, open: &mut OpenSubproofs) -> CarcaraResult<()> {
+        // If there is an end step in the stack, we are inside a subproof that should be closed
+        // before the outer proof is finished
+        let result = match open.end_step_stack.pop() {
+            Some(id) => Err(self.error_at(ParserError::UnclosedSubproof(id), self.current_span)),
+            None => Ok(()),
+        };
+
+        if let Some((_, callback)) = &mut self.progress_callback {
+            callback(&self.progress);
+        }
+
+        // If any errors were found while recovering, they are returned together
+        if self.errors.is_empty() {
+            return result;
+        }
+        if let Err(Error::Parser(e, pos)) = result {
+            self.errors.push((e, pos));
+        }
+        Err(self.take_errors().unwrap())
+    }
+
+    /// Parses a single command in a proof, adding it to the innermost open subproof. If the
+    /// command is an `anchor`, a new subproof is opened, and if it is the last step of a subproof,
+    /// the subproof is closed.
+    fn parse_proof_command(&mut self, open: &mut OpenSubproofs) -> CarcaraResult<()> {
+        let OpenSubproofs {
+            commands_stack,
+            end_step_stack,
+            subproof_args_stack,
+            finished_assumes,
+            num_taken,
+        } = open;
+
+        let start = self.current_span.start;
+        if self.current_token == Token::CloseParen {
+            let (token, pos) = self.next_token()?;
+            let err = ParserError::UnexpectedTokenWithHint(token, RepairHint::ExtraCloseParen);
+            return Err(self.error_at(err, pos));
+        }
+        self.expect_token(Token::OpenParen)?;
+        let (token, position) = self.next_token()?;
+        let (id, command) = match token {
+            Token::ReservedWord(Reserved::Assume) => {
+                let (id, term) = self.parse_assume_command()?;
+                if end_step_stack.is_empty() && *finished_assumes {
+                    log::warn!("`assume` command '{}' appears after `step` commands", &id);
                 }
-                Token::ReservedWord(Reserved::DefineFun) => {
-                    let (name, func_def) = self.parse_define_fun()?;
-                    self.state.function_defs.insert(name, func_def);
-                    continue;
+                (id.clone(), ProofCommand::Assume { id, term })
+            }
+            Token::ReservedWord(Reserved::Step) => {
+                *finished_assumes = true;
+                let step = self.parse_step_command()?;
+                (step.id.clone(), ProofCommand::Step(step))
+            }
+            Token::ReservedWord(Reserved::DefineFun) => {
+                let (name, func_def) = self.parse_define_fun()?;
+                self.state.function_defs.insert(name, func_def);
+                return Ok(());
+            }
+            // Proofs that are not accompanied by their problem may declare the functions and
+            // sorts they use themselves. Some solvers also repeat the declarations of the problem
+            Token::ReservedWord(Reserved::DeclareFun) => {
+                let name_span = self.current_span;
+                let (name, sort) = self.parse_declare_fun()?;
+                self.redeclare_function(name, sort, name_span)?;
+                return Ok(());
+            }
+            Token::ReservedWord(Reserved::DeclareConst) => {
+                let name_span = self.current_span;
+                let (name, sort) = self.parse_declare_const()?;
+                self.redeclare_function(name, sort, name_span)?;
+                return Ok(());
+            }
+            Token::ReservedWord(Reserved::DeclareSort) => {
+                let name_span = self.current_span;
+                let (name, arity) = self.parse_declare_sort()?;
+                self.redeclare_sort(name, arity, name_span)?;
+                return Ok(());
+            }
+            Token::ReservedWord(Reserved::DefineSort) => {
+                let (name, sort_def) = self.parse_define_sort()?;
+                self.state.sort_defs.insert(name, sort_def);
+                return Ok(());
+            }
+            // Some solvers start their proofs with `set-info` commands that describe how the
+            // proof was produced. These are not proof commands, but are kept as metadata
+            Token::Symbol(s) if s == "set-info" => {
+                let attribute = self.parse_set_info()?;
+                self.proof_metadata.push(attribute);
+                return Ok(());
+            }
+            Token::ReservedWord(Reserved::Anchor) => {
+                let anchor = self.parse_anchor_command()?;
+
+                // The id of the step that ends the subproof must not be used by any other
+                // command in the same scope, and must be different from the end steps of the
+                // enclosing subproofs. Otherwise, the step that ends this subproof could be
+                // mistaken for the one ending another. Ids from enclosing scopes may be reused,
+                // in which case they are shadowed inside the subproof
+                let end_step_id = HashCache::new(anchor.end_step_id);
+                let step_ids = &self.state.step_ids;
+                if step_ids.get_in_current_scope(&end_step_id).is_some()
+                    || end_step_stack.contains(end_step_id.as_ref())
+                {
+                    return Err(self.error_at(
+                        ParserError::RepeatedStepIndex(end_step_id.unwrap()),
+                        position,
+                    ));
                 }
-                Token::ReservedWord(Reserved::Anchor) => {
-                    let anchor = self.parse_anchor_command()?;
-
-                    // When we encounter an `anchor` command, we push a new scope into the step ids
-                    // symbol table, a fresh commands vector into the commands stack for the
-                    // subproof to fill, and the `anchor` data (end step and arguments) into their
-                    // respective stacks. All of this will be popped off at the end of the subproof.
-                    // We don't need to push a new scope into the symbol table because
-                    // `Parser::parse_anchor_command` already does that for us
-                    self.state.step_ids.push_scope();
-                    commands_stack.push(Vec::new());
-                    end_step_stack.push(anchor.end_step_id);
-                    subproof_args_stack.push((anchor.assignment_args, anchor.variable_args));
-                    continue;
+
+                // When we encounter an `anchor` command, we push a new scope into the step ids
+                // symbol table, a fresh commands vector into the commands stack for the
+                // subproof to fill, and the `anchor` data (end step and arguments) into their
+                // respective stacks. All of this will be popped off at the end of the subproof.
+                // We don't need to push a new scope into the symbol table because
+                // `Parser::parse_anchor_command` already does that for us
+                self.state.step_ids.push_scope();
+                self.command_offsets.push((start, self.previous_span.start));
+                self.count_command(None, position)?;
+                commands_stack.push(Vec::new());
+                end_step_stack.push(end_step_id.unwrap());
+                subproof_args_stack.push((anchor.assignment_args, anchor.variable_args));
+                return Ok(());
+            }
+            _ => return Err(self.error_at(Self::unexpected_token(token), position)),
+        };
+        // Step ids only need to be unique in their scope. Commands inside a subproof may reuse
+        // the ids of commands in the enclosing scopes, shadowing them
+        let id = HashCache::new(id);
+        if self.state.step_ids.get_in_current_scope(&id).is_some() {
+            return Err(self.error_at(ParserError::RepeatedStepIndex(id.unwrap()), position));
+        }
+
+        self.command_offsets.push((start, self.previous_span.start));
+        let clause_len = match &command {
+            ProofCommand::Step(s) => Some(s.clause.len()),
+            _ => None,
+        };
+        self.count_command(clause_len, position)?;
+        commands_stack.last_mut().unwrap().push(command);
+        if end_step_stack.last() == Some(id.as_ref()) {
+            // If this is the last step in a subproof, we need to pop all the subproof data off
+            // of the stacks and build the subproof command with it
+            self.state.symbol_table.pop_scope();
+            self.state.step_ids.pop_scope();
+            let commands = commands_stack.pop().unwrap();
+            end_step_stack.pop().unwrap();
+            let (assignment_args, variable_args) = subproof_args_stack.pop().unwrap();
+            for command in &commands[..commands.len() - 1] {
+                self.state
+                    .subproof_local_ids
+                    .insert(command.id().to_owned(), id.as_ref().clone());
+            }
+
+            // The subproof must contain at least two commands: the end step and the previous
+            // command it implicitly references
+            if commands.len() < 2 {
+                return Err(self.error_at(ParserError::EmptySubproof(id.unwrap()), position));
+            }
+
+            // We also need to make sure that the last command is in fact a `step`
+            match commands.last() {
+                Some(ProofCommand::Step(_)) => (),
+                _ => {
+                    return Err(self.error_at(
+                        ParserError::LastSubproofStepIsNotStep(id.unwrap()),
+                        position,
+                    ))
                 }
-                _ => return Err(Error::Parser(ParserError::UnexpectedToken(token), position)),
             };
-            let id = HashCache::new(id);
-            if self.state.step_ids.get(&id).is_some() {
-                return Err(Error::Parser(
-                    ParserError::RepeatedStepIndex(id.unwrap()),
-                    position,
-                ));
+
+            commands_stack
+                .last_mut()
+                .unwrap()
+                .push(ProofCommand::Subproof(Subproof {
+                    commands,
+                    assignment_args,
+                    variable_args,
+                }));
+        }
+
+        // The top-level commands that were already returned are not in the commands stack
+        // anymore, so they must be counted separately
+        let mut index = commands_stack.last().unwrap().len() - 1;
+        if commands_stack.len() == 1 {
+            index += *num_taken;
+        }
+        self.state.step_ids.insert(id, index);
+        Ok(())
+    }
+
+    /// Updates the statistics of the proof after a command was parsed. `clause_len` is the number
+    /// of literals in the clause of the command, if it is a `step`. If the number of terms exceeds
+    /// `ParserConfig::term_count_warning`, a warning is logged. Returns an error if the number of
+    /// commands exceeds the limit set in `ParserConfig::limits`.
+    fn count_command(&mut self, clause_len: Option<usize>, start: Span) -> CarcaraResult<()> {
+        match self.config.limits.max_commands {
+            Some(max) if self.progress.commands >= max => {
+                let limit = ResourceLimit::Commands(max);
+                return Err(self.error_at(ParserError::ResourceLimitExceeded(limit), start));
+            }
+            _ => (),
+        }
+        let progress = &mut self.progress;
+        progress.commands += 1;
+        if let Some(n) = clause_len {
+            progress.steps += 1;
+            progress.literals += n;
+        }
+        let pool_stats = self.pool.stats();
+        progress.terms = pool_stats.num_terms + pool_stats.num_shared_terms;
+        progress.bytes_read = self.current_span.start;
+
+        match self.config.term_count_warning {
+            Some(limit) if progress.terms > limit && !self.has_warned_term_count => {
+                // We only warn once per proof, to avoid flooding the log
+                self.has_warned_term_count = true;
+                log::warn!(
+                    "proof has more than {} terms, checking it may use a lot of memory",
+                    limit
+                );
             }
+            _ => (),
+        }
+        if let Some((interval, callback)) = &mut self.progress_callback {
+            if progress.commands % *interval == 0 {
+                callback(progress);
+            }
+        }
+        Ok(())
+    }
 
-            commands_stack.last_mut().unwrap().push(command);
-            if end_step_stack.last() == Some(id.as_ref()) {
-                // If this is the last step in a subproof, we need to pop all the subproof data off
-                // of the stacks and build the subproof command with it
-                self.state.symbol_table.pop_scope();
-                self.state.step_ids.pop_scope();
-                let commands = commands_stack.pop().unwrap();
-                end_step_stack.pop().unwrap();
-                let (assignment_args, variable_args) = subproof_args_stack.pop().unwrap();
-
-                // The subproof must contain at least two commands: the end step and the previous
-                // command it implicitly references
-                if commands.len() < 2 {
-                    return Err(Error::Parser(
-                        ParserError::EmptySubproof(id.unwrap()),
-                        position,
-                    ));
+    /// Handles an error in a proof command. If the parser is recovering from errors, the error is
+    /// recorded, the symbol table scopes opened by the command are popped, and the tokens until the
+    /// start of the next command are skipped. Otherwise, or if the error doesn't come from the
+    /// parser itself, like an IO error, the error is returned.
+    fn recover_from_error(
+        &mut self,
+        err: Error,
+        start: Span,
+        num_scopes: usize,
+    ) -> CarcaraResult<()> {
+        match err {
+            Error::Parser(e, pos) if self.config.recover_from_errors => {
+                // Resource limits are meant to stop the parser, so they can't be recovered from
+                let is_limit = matches!(e, ParserError::ResourceLimitExceeded(_));
+                self.errors.push((e, pos));
+                if is_limit {
+                    return Err(self.take_errors().unwrap());
                 }
+            }
+            other => return Err(other),
+        }
+        while self.state.symbol_table.num_scopes() > num_scopes {
+            self.state.symbol_table.pop_scope();
+        }
 
-                // We also need to make sure that the last command is in fact a `step`
-                match commands.last() {
-                    Some(ProofCommand::Step(_)) => (),
-                    _ => {
-                        return Err(Error::Parser(
-                            ParserError::LastSubproofStepIsNotStep(id.unwrap()),
-                            position,
-                        ))
-                    }
-                };
+        // If the command didn't consume any tokens, the error came from the lexer, and we can't
+        // make progress by parsing the same command again
+        if self.current_span == start {
+            return Err(self.take_errors().unwrap());
+        }
+        while self.current_token != Token::Eof
+            && (self.parens_depth > 0 || self.current_token != Token::OpenParen)
+        {
+            match self.next_token() {
+                Ok(_) => (),
+                Err(Error::Parser(e, pos)) => {
+                    self.errors.push((e, pos));
+                    return Err(self.take_errors().unwrap());
+                }
+                Err(other) => return Err(other),
+            }
+        }
+        Ok(())
+    }
 
-                commands_stack
-                    .last_mut()
-                    .unwrap()
-                    .push(ProofCommand::Subproof(Subproof {
-                        commands,
-                        assignment_args,
-                        variable_args,
-                    }));
+    /// Returns the errors collected while recovering from errors as a single error, or `None` if
+    /// there are none.
+    fn take_errors(&mut self) -> Option<Error> {
+        let mut errors = std::mem::take(&mut self.errors);
+        match errors.len() {
+            0 => None,
+            1 => {
+                let (e, pos) = errors.pop().unwrap();
+                Some(Error::Parser(e, pos))
             }
-            self.state
-                .step_ids
-                .insert(id, commands_stack.last().unwrap().len() - 1);
-        }
-        match commands_stack.len() {
-            0 => unreachable!(),
-            1 => Ok(commands_stack.pop().unwrap()),
-
-            // If there is more than one vector in the commands stack, we are inside a subproof
-            // that should be closed before the outer proof is finished
-            _ => Err(Error::Parser(
-                ParserError::UnclosedSubproof(end_step_stack.pop().unwrap()),
-                self.current_position,
-            )),
+            _ => Some(Error::ParserErrors(errors)),
         }
     }
 
@@ -672,12 +1977,20 @@ impl<'a, R: BufRead> Parser<'a, R> {
     fn parse_step_command(&mut self) -> CarcaraResult<ProofStep> {
         let id = self.expect_symbol()?;
         let clause = self.parse_clause()?;
-        self.expect_token(Token::Keyword("rule".into()))?;
+        match self.next_token()? {
+            (Token::Keyword(k), _) if k == "rule" => (),
+            (other, pos) => {
+                let hint = RepairHint::for_near_miss(&other).unwrap_or(RepairHint::MissingRule);
+                let err = ParserError::UnexpectedTokenWithHint(other, hint);
+                return Err(self.error_at(err, pos));
+            }
+        }
         let rule = match self.next_token()? {
             (Token::Symbol(s), _) => s,
             (Token::ReservedWord(r), _) => format!("{}", r),
-            (other, pos) => return Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
+            (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
         };
+        let rule = self.config.producer.normalize_rule_name(rule);
 
         if rule == "trust" && !self.has_seen_trust_rule {
             // We do this to avoid printing more than one warning message if there are multiple
@@ -738,13 +2051,12 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// Parses a premise for a `step` command. This already converts it into the depth and command
     /// index used to reference commands in the AST.
     fn parse_step_premise(&mut self) -> CarcaraResult<(usize, usize)> {
-        let position = self.current_position;
+        let position = self.current_span;
         let id = HashCache::new(self.expect_symbol()?);
-        self.state
-            .step_ids
-            .get_with_depth(&id)
-            .map(|(d, &i)| (d, i))
-            .ok_or_else(|| Error::Parser(ParserError::UndefinedStepIndex(id.unwrap()), position))
+        match self.state.step_ids.get_with_depth(&id) {
+            Some((d, &i)) => Ok((d, i)),
+            None => Err(self.undefined_step_error(id.unwrap(), position)),
+        }
     }
 
     /// Parses an argument for the `:discharge` attribute. Due to a bug in veriT, commands local to
@@ -752,17 +2064,57 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// passed as simply `h2`. This behavior is not present in other SMT solvers, like cvc5. To
     /// work around that, this function tries to find the command considering both possibilities.
     fn parse_discharge_premise(&mut self, root_id: &str) -> CarcaraResult<(usize, usize)> {
-        let position = self.current_position;
+        let position = self.current_span;
         let id = self.expect_symbol()?;
         let absolute_id = format!("{}.{}", root_id, &id);
         let id = HashCache::new(id);
         let absolute_id = HashCache::new(absolute_id);
-        self.state
+        let found = self
+            .state
             .step_ids
             .get_with_depth(&absolute_id)
-            .or_else(|| self.state.step_ids.get_with_depth(&id))
-            .map(|(d, &i)| (d, i))
-            .ok_or_else(|| Error::Parser(ParserError::UndefinedStepIndex(id.unwrap()), position))
+            .or_else(|| self.state.step_ids.get_with_depth(&id));
+        match found {
+            Some((d, &i)) => Ok((d, i)),
+            None => Err(self.undefined_step_error(id.unwrap(), position)),
+        }
+    }
+
+    /// Builds the error for a premise that references the step id `id`, which is not visible. If
+    /// `id` is the id of a command in a subproof that was already closed, the error says so.
+    fn undefined_step_error(&self, id: String, position: Span) -> Error {
+        let err = match self.state.subproof_local_ids.get(&id) {
+            Some(end_step_id) => ParserError::SubproofLocalStep(id, end_step_id.clone()),
+            None => {
+                let step_ids = self.state.step_ids.keys().map(|id| id.as_ref().as_str());
+                let suggestions = suggestions::closest_matches(&id, step_ids);
+                ParserError::UndefinedStepIndex(id, suggestions)
+            }
+        };
+        self.error_at(err, position)
+    }
+
+    /// Parses a `set-info` command in a proof, and returns its keyword and value. This method
+    /// assumes that the `(` and `set-info` tokens were already consumed.
+    fn parse_set_info(&mut self) -> CarcaraResult<(String, String)> {
+        let keyword = self.expect_keyword()?;
+        let mut tokens = self.read_until_close_parens()?;
+        tokens.pop(); // Remove the `)` token that closes the command
+        let value = match tokens.as_slice() {
+            [Token::Symbol(s) | Token::String(s)] => s.clone(),
+            _ => {
+                let mut value = String::new();
+                for (i, token) in tokens.iter().enumerate() {
+                    let follows_open = i > 0 && tokens[i - 1] == Token::OpenParen;
+                    if i > 0 && !follows_open && *token != Token::CloseParen {
+                        value.push(' ');
+                    }
+                    value.push_str(&token.to_string());
+                }
+                value
+            }
+        };
+        Ok((keyword, value))
     }
 
     /// Parses an `anchor` proof command. This method assumes that the `(` and `anchor` tokens were
@@ -799,61 +2151,136 @@ impl<'a, R: BufRead> Parser<'a, R> {
     }
 
     /// Parses an argument for an `anchor` proof command. This can be either a variable binding of
-    /// the form `(<symbol> <sort>)` or an assignment, of the form `(:= <symbol> <term>)`.
+    /// the form `(<symbol> <sort>)` or an assignment. Assignments can be written in the current
+    /// Alethe syntax, `(:= (<symbol> <sort>) <term>)`, or in the legacy syntax, `(:= <symbol>
+    /// <term>)`, in which the sort of the variable is the sort of the term.
     fn parse_anchor_argument(&mut self) -> CarcaraResult<AnchorArg> {
         self.expect_token(Token::OpenParen)?;
         Ok(if self.current_token == Token::Keyword("=".into()) {
             self.next_token()?;
-            let var = self.expect_symbol()?;
-            let value = self.parse_term()?;
-            let sort = Term::Sort(self.pool.sort(&value).clone());
-            let sort = self.pool.add(sort);
+            let (var, value) = if self.current_token == Token::OpenParen {
+                self.next_token()?;
+                let var = self.expect_symbol()?;
+                let declared = self.parse_sort()?;
+                self.expect_token(Token::CloseParen)?;
+                let span = self.current_span;
+                let value = self.parse_next_term()?;
+                if let Err(e) = SortError::assert_eq(&declared, self.pool.sort(&value)) {
+                    return Err(self.error_at(e.into(), span));
+                }
+                (var, value)
+            } else {
+                (self.expect_symbol()?, self.parse_next_term()?)
+            };
+            let sort = self.pool.sort(&value).clone();
             self.insert_sorted_var((var.clone(), sort));
             self.expect_token(Token::CloseParen)?;
             AnchorArg::Assign(var, value)
         } else {
             let symbol = self.expect_symbol()?;
-            let sort = self.parse_sort()?;
-            let var = (symbol, self.pool.add(sort));
+            let var = (symbol, self.parse_sort()?);
             self.insert_sorted_var(var.clone());
             self.expect_token(Token::CloseParen)?;
             AnchorArg::Variable(var)
         })
     }
 
-    /// Parses a `declare-fun` proof command. Returns the function name and a term representing its
-    /// sort. This method assumes that the `(` and `declare-fun` tokens were already consumed.
-    fn parse_declare_fun(&mut self) -> CarcaraResult<(String, Rc<Term>)> {
+    /// Parses a `declare-fun` proof command. Returns the function name and its sort. This method
+    /// assumes that the `(` and `declare-fun` tokens were already consumed.
+    fn parse_declare_fun(&mut self) -> CarcaraResult<(String, Rc<Sort>)> {
         let name = self.expect_symbol()?;
         let sort = {
             self.expect_token(Token::OpenParen)?;
             let mut sorts = self.parse_sequence(Self::parse_sort, false)?;
             sorts.push(self.parse_sort()?);
-            let sorts = self.pool.add_all(sorts);
             if sorts.len() == 1 {
                 sorts.into_iter().next().unwrap()
             } else {
-                self.pool.add(Term::Sort(Sort::Function(sorts)))
+                self.pool.add_sort(Sort::Function(sorts))
             }
         };
         self.expect_token(Token::CloseParen)?;
         Ok((name, sort))
     }
 
+    /// Parses a `declare-const` command. Returns the constant name and its sort. This method
+    /// assumes that the `(` and `declare-const` tokens were already consumed.
+    fn parse_declare_const(&mut self) -> CarcaraResult<(String, Rc<Sort>)> {
+        let name = self.expect_symbol()?;
+        let sort = self.parse_sort()?;
+        self.expect_token(Token::CloseParen)?;
+        Ok((name, sort))
+    }
+
     /// Parses a declare-sort proof command. Returns the sort name and its arity. This method
     /// assumes that the `(` and `declare-sort` tokens were already consumed.
     fn parse_declare_sort(&mut self) -> CarcaraResult<(String, usize)> {
         let name = self.expect_symbol()?;
-        let arity_pos = self.current_position;
+        let arity_pos = self.current_span;
         let arity = self.expect_numeral()?;
         self.expect_token(Token::CloseParen)?;
-        let arity = arity.to_usize().ok_or(Error::Parser(
-            ParserError::InvalidSortArity(arity),
-            arity_pos,
-        ))?;
+        let arity = arity
+            .to_usize()
+            .ok_or_else(|| self.error_at(ParserError::InvalidSortArity(arity), arity_pos))?;
         Ok((name, arity))
     }
 
+    /// Parses a `define-sort` command. Returns the sort name and its definition. This method
+    /// assumes that the `(` and `define-sort` tokens were already consumed.
+    fn parse_define_sort(&mut self) -> CarcaraResult<(String, SortDef)> {
+        let name = self.expect_symbol()?;
+        self.expect_token(Token::OpenParen)?;
+        let params = self.parse_sequence(Self::expect_symbol, false)?;
+
+        // While parsing the body, the parameters are declared as sorts with no arguments. Any
+        // sorts with the same names are restored afterwards
+        let shadowed: Vec<_> = params
+            .iter()
+            .map(|p| self.state.sort_declarations.insert(p.clone(), 0))
+            .collect();
+        let body = self.parse_sort();
+        for (p, previous) in params.iter().zip(shadowed) {
+            match previous {
+                Some(arity) => self.state.sort_declarations.insert(p.clone(), arity),
+                None => self.state.sort_declarations.remove(p),
+            };
+        }
+        let body = body?;
+        self.expect_token(Token::CloseParen)?;
+        Ok((name, SortDef { params, body }))
+    }
+
+    /// Replaces the parameters of a sort definition with the given sorts in `sort`.
+    fn substitute_sort_params(
+        &mut self,
+        sort: &Rc<Sort>,
+        params: &[String],
+        args: &[Rc<Sort>],
+    ) -> Rc<Sort> {
+        let substitute_all = |p: &mut Self, sorts: &[Rc<Sort>]| -> Vec<Rc<Sort>> {
+            sorts
+                .iter()
+                .map(|s| p.substitute_sort_params(s, params, args))
+                .collect()
+        };
+        let new_sort = match sort.as_ref() {
+            Sort::Atom(name, sorts) if sorts.is_empty() => {
+                match params.iter().position(|p| p == name) {
+                    Some(i) => return args[i].clone(),
+                    None => return sort.clone(),
+                }
+            }
+            Sort::Atom(name, sorts) => Sort::Atom(name.clone(), substitute_all(self, sorts)),
+            Sort::Function(sorts) => Sort::Function(substitute_all(self, sorts)),
+            Sort::Array(x, y) => {
+                let [x, y] = [x, y].map(|s| self.substitute_sort_params(s, params, args));
+                Sort::Array(x, y)
+            }
+            _ => return sort.clone(),
+        };
+        self.pool.add_sort(new_sort)
+    }
+
     /// Parses a `define-fun` proof command. Returns the function name and its definition. This
     /// method assumes that the `(` and `define-fun` tokens were already consumed.
     fn parse_define_fun(&mut self) -> CarcaraResult<(String, FunctionDef)> {
@@ -868,7 +2295,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
         for var in &params {
             self.insert_sorted_var(var.clone());
         }
-        let body = self.parse_term_expecting_sort(return_sort.as_sort().unwrap())?;
+        let body = self.parse_term_expecting_sort(&return_sort)?;
         self.state.symbol_table.pop_scope();
 
         self.expect_token(Token::CloseParen)?;
@@ -894,18 +2321,18 @@ impl<'a, R: BufRead> Parser<'a, R> {
             if self.current_token == Token::Keyword("=".into()) {
                 self.next_token()?; // Consume `:=` token
                 let name = self.expect_symbol()?;
-                let value = self.parse_term()?;
+                let value = self.parse_next_term()?;
                 self.expect_token(Token::CloseParen)?;
                 Ok(ProofArg::Assign(name, value))
             } else {
                 // If the first token is not `:=`, this argument is just a regular term. Since
                 // we already consumed the `(` token, we have to call `parse_application`
-                // instead of `parse_term`.
+                // instead of `parse_next_term`.
                 let term = self.parse_application()?;
                 Ok(ProofArg::Term(term))
             }
         } else {
-            let term = self.parse_term()?;
+            let term = self.parse_next_term()?;
             Ok(ProofArg::Term(term))
         }
     }
@@ -916,15 +2343,16 @@ impl<'a, R: BufRead> Parser<'a, R> {
         let symbol = self.expect_symbol()?;
         let sort = self.parse_sort()?;
         self.expect_token(Token::CloseParen)?;
-        Ok((symbol, self.pool.add(sort)))
+        Ok((symbol, sort))
     }
 
-    /// Parses a term.
-    pub fn parse_term(&mut self) -> CarcaraResult<Rc<Term>> {
+    /// Parses the next term in the input.
+    fn parse_next_term(&mut self) -> CarcaraResult<Rc<Term>> {
         let term = match self.next_token()? {
             (Token::Numeral(n), _) if self.interpret_integers_as_reals => Term::real(n),
             (Token::Numeral(n), _) => Term::integer(n),
             (Token::Decimal(r), _) => Term::real(r),
+            (Token::BitVec(value, width), _) => Term::bitvec(value, width),
             (Token::String(s), _) => Term::string(s),
             (Token::Symbol(s), pos) => {
                 // Check to see if there is a nullary function defined with this name
@@ -932,28 +2360,31 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     if func_def.params.is_empty() {
                         func_def.body.clone()
                     } else {
-                        return Err(Error::Parser(
+                        return Err(self.error_at(
                             ParserError::WrongNumberOfArgs(func_def.params.len().into(), 0),
                             pos,
                         ));
                     }
+                } else if let Some(signature) = self.constant_signature(&s) {
+                    self.make_op(signature, Vec::new())
+                        .map_err(|err| self.error_at(err, pos))?
                 } else {
                     self.make_var(Identifier::Simple(s))
-                        .map_err(|err| Error::Parser(err, pos))?
+                        .map_err(|err| self.error_at(err, pos))?
                 });
             }
             (Token::OpenParen, _) => return self.parse_application(),
-            (other, pos) => return Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
+            (other, pos) => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
         };
         Ok(self.pool.add(term))
     }
 
     /// Parses a term and checks that its sort matches the expected sort. If not, returns an error.
     fn parse_term_expecting_sort(&mut self, expected_sort: &Sort) -> CarcaraResult<Rc<Term>> {
-        let pos = self.current_position;
-        let term = self.parse_term()?;
+        let pos = self.current_span;
+        let term = self.parse_next_term()?;
         SortError::assert_eq(expected_sort, self.pool.sort(&term))
-            .map_err(|e| Error::Parser(e.into(), pos))?;
+            .map_err(|e| self.error_at(e.into(), pos))?;
         Ok(term)
     }
 
@@ -970,12 +2401,33 @@ impl<'a, R: BufRead> Parser<'a, R> {
             },
             true,
         )?;
-        let term = self.parse_term_expecting_sort(&Sort::Bool)?;
+        let pos = self.current_span;
+        let (term, patterns) = self.parse_term_with_patterns()?;
+        SortError::assert_eq(&Sort::Bool, self.pool.sort(&term))
+            .map_err(|e| self.error_at(e.into(), pos))?;
         self.state.symbol_table.pop_scope();
         self.expect_token(Token::CloseParen)?;
-        Ok(self
-            .pool
-            .add(Term::Quant(quantifier, BindingList(bindings), term)))
+        Ok(self.pool.add(Term::Quant(
+            quantifier,
+            BindingList(bindings),
+            term,
+            patterns,
+        )))
+    }
+
+    /// Parses a term, returning it together with the patterns given in its `:pattern` attributes.
+    /// If the term is not an annotated term, it has no patterns.
+    fn parse_term_with_patterns(&mut self) -> CarcaraResult<(Rc<Term>, Patterns)> {
+        if self.current_token != Token::OpenParen {
+            return Ok((self.parse_next_term()?, Vec::new()));
+        }
+        self.next_token()?;
+        if self.current_token == Token::ReservedWord(Reserved::Bang) {
+            self.next_token()?;
+            self.parse_annotated_term_with_patterns()
+        } else {
+            Ok((self.parse_application()?, Vec::new()))
+        }
     }
 
     /// Parses a `choice` term. This method assumes that the `(` and `choice` tokens were already
@@ -983,9 +2435,11 @@ impl<'a, R: BufRead> Parser<'a, R> {
     fn parse_choice_term(&mut self) -> CarcaraResult<Rc<Term>> {
         self.expect_token(Token::OpenParen)?;
         let var = self.parse_sorted_var()?;
+        self.state.symbol_table.push_scope();
         self.insert_sorted_var(var.clone());
         self.expect_token(Token::CloseParen)?;
-        let inner = self.parse_term()?;
+        let inner = self.parse_next_term()?;
+        self.state.symbol_table.pop_scope();
         self.expect_token(Token::CloseParen)?;
         Ok(self.pool.add(Term::Choice(var, inner)))
     }
@@ -1003,7 +2457,7 @@ impl<'a, R: BufRead> Parser<'a, R> {
             },
             true,
         )?;
-        let body = self.parse_term()?;
+        let body = self.parse_next_term()?;
         self.state.symbol_table.pop_scope();
         self.expect_token(Token::CloseParen)?;
         Ok(self.pool.add(Term::Lambda(BindingList(bindings), body)))
@@ -1013,52 +2467,63 @@ impl<'a, R: BufRead> Parser<'a, R> {
     /// consumed.
     fn parse_let_term(&mut self) -> CarcaraResult<Rc<Term>> {
         self.expect_token(Token::OpenParen)?;
-        self.state.symbol_table.push_scope();
         let bindings = self.parse_sequence(
             |p| {
                 p.expect_token(Token::OpenParen)?;
                 let name = p.expect_symbol()?;
-                let value = p.parse_term()?;
-                let sort = p.pool.add(Term::Sort(p.pool.sort(&value).clone()));
-                p.insert_sorted_var((name.clone(), sort));
+                let value = p.parse_next_term()?;
                 p.expect_token(Token::CloseParen)?;
                 Ok((name, value))
             },
             true,
         )?;
-        let inner = self.parse_term()?;
+
+        // The bindings in a `let` term are parallel, so the values are parsed in the outer scope,
+        // and the bound variables are only introduced after all of them were parsed
+        self.state.symbol_table.push_scope();
+        for (name, value) in &bindings {
+            let sort = self.pool.sort(value).clone();
+            self.insert_sorted_var((name.clone(), sort));
+        }
+        let inner = self.parse_next_term()?;
         self.expect_token(Token::CloseParen)?;
         self.state.symbol_table.pop_scope();
 
-        if self.expand_lets {
+        if self.config.expand_lets {
             let substitution = bindings
                 .into_iter()
                 .map(|(name, value)| {
-                    let sort = Term::Sort(self.pool.sort(&value).clone());
-                    let var = Term::var(name, self.pool.add(sort));
+                    let var = Term::var(name, self.pool.sort(&value).clone());
                     (self.pool.add(var), value)
                 })
                 .collect();
 
-            let result = Substitution::new(self.pool, substitution)
-                .unwrap()
-                .apply(self.pool, &inner);
-
-            Ok(result)
+            // We can safely unwrap here because each variable has the same sort as its value
+            let mut substitution = Substitution::new(self.pool, substitution).unwrap();
+            Ok(substitution.apply(self.pool, &inner))
         } else {
             Ok(self.pool.add(Term::Let(BindingList(bindings), inner)))
         }
     }
 
     /// Parses an annotated term, of the form `(! <term> <attribute>+)`. The two supported
-    /// attributes are `:named` and `:pattern`, though the latter is ignored. If any other
-    /// attribute is present, an error will be returned. This method assumes that the `(` and `!`
-    /// tokens were already consumed.
+    /// attributes are `:named` and `:pattern`. The patterns are only kept if the annotated term is
+    /// the inner term of a quantifier, and are ignored otherwise. If any other attribute is
+    /// present, an error will be returned. This method assumes that the `(` and `!` tokens were
+    /// already consumed.
     fn parse_annotated_term(&mut self) -> CarcaraResult<Rc<Term>> {
-        let inner = self.parse_term()?;
+        self.parse_annotated_term_with_patterns()
+            .map(|(term, _)| term)
+    }
+
+    /// Parses an annotated term, returning it together with the patterns given in its `:pattern`
+    /// attributes, including the ones in nested annotations. This method assumes that the `(` and
+    /// `!` tokens were already consumed.
+    fn parse_annotated_term_with_patterns(&mut self) -> CarcaraResult<(Rc<Term>, Patterns)> {
+        let (inner, mut patterns) = self.parse_term_with_patterns()?;
         self.parse_sequence(
             |p| {
-                let attribute_pos = p.current_position;
+                let attribute_pos = p.current_span;
                 let attribute = p.expect_keyword()?;
                 match attribute.as_str() {
                     "named" => {
@@ -1069,30 +2534,34 @@ impl<'a, R: BufRead> Parser<'a, R> {
                             params: Vec::new(),
                             body: inner.clone(),
                         };
+                        if let Some(names) = &mut p.assertion_names {
+                            names.push((name.clone(), inner.clone()));
+                        }
                         p.state.function_defs.insert(name, func_def);
                         Ok(())
                     }
                     "pattern" => {
-                        // We just ignore the values of `:pattern` attributes
                         p.expect_token(Token::OpenParen)?;
-                        p.parse_sequence(Parser::parse_term, true)?;
+                        patterns.push(p.parse_sequence(Parser::parse_next_term, true)?);
                         Ok(())
                     }
-                    _ => Err(Error::Parser(
-                        ParserError::UnknownAttribute(attribute),
-                        attribute_pos,
-                    )),
+                    _ if p.config.ignore_unknown_attributes
+                        || p.config.producer.ignores_unknown_attributes() =>
+                    {
+                        p.ignore_attribute_value()
+                    }
+                    _ => Err(p.error_at(ParserError::UnknownAttribute(attribute), attribute_pos)),
                 }
             },
             true,
         )?;
-        Ok(inner)
+        Ok((inner, patterns))
     }
 
     /// Parses any term that starts with `(`, that is, any term that is not a constant or a
     /// variable. This method assumes that the `(` token was already consumed.
     fn parse_application(&mut self) -> CarcaraResult<Rc<Term>> {
-        let head_pos = self.current_position;
+        let head_pos = self.current_span;
         match &self.current_token {
             &Token::ReservedWord(reserved) => {
                 self.next_token()?;
@@ -1103,7 +2572,12 @@ impl<'a, R: BufRead> Parser<'a, R> {
                     Reserved::Lambda => self.parse_lambda_term(),
                     Reserved::Bang => self.parse_annotated_term(),
                     Reserved::Let => self.parse_let_term(),
-                    _ => Err(Error::Parser(
+                    Reserved::Underscore => {
+                        let iden = self.parse_indexed_identifier()?;
+                        self.make_indexed_constant(iden)
+                            .map_err(|err| self.error_at(err, head_pos))
+                    }
+                    _ => Err(self.error_at(
                         ParserError::UnexpectedToken(Token::ReservedWord(reserved)),
                         head_pos,
                     )),
@@ -1111,30 +2585,39 @@ impl<'a, R: BufRead> Parser<'a, R> {
             }
             // Here, I would like to use an `if let` guard, like:
             //
-            //     Token::Symbol(s) if let Ok(operator) = Operator::from_str(s) => { ... }
+            //     Token::Symbol(s) if let Some(signature) = self.signatures.get(s) => { ... }
             //
             // However, `if let` guards are still nightly only. For more info, see:
             // https://github.com/rust-lang/rust/issues/51114
-            Token::Symbol(s) if Operator::from_str(s).is_ok() => {
-                let operator = Operator::from_str(s).unwrap();
+            Token::Symbol(s) if self.signatures.get(s).is_some() => {
+                let signature = *self.signatures.get(s).unwrap();
                 self.next_token()?;
-                let args = self.parse_sequence(Self::parse_term, true)?;
-                self.make_op(operator, args)
-                    .map_err(|err| Error::Parser(err, head_pos))
+                let args = self.parse_sequence(Self::parse_next_term, true)?;
+                self.make_op(signature, args)
+                    .map_err(|err| self.error_at(err, head_pos))
             }
             Token::Symbol(s) if self.state.function_defs.get(s).is_some() => {
-                let head_pos = self.current_position;
+                let head_pos = self.current_span;
                 let func_name = self.expect_symbol()?;
-                let args = self.parse_sequence(Self::parse_term, true)?;
+                let args = self.parse_sequence(Self::parse_next_term, true)?;
                 let func = self.state.function_defs.get(&func_name).unwrap();
 
                 // If there is a function definition with this function name, we sort check
                 // the arguments and apply the definition by performing a beta reduction.
                 assert_num_args(&args, func.params.len())
-                    .map_err(|err| Error::Parser(err, head_pos))?;
-                for (arg, param) in args.iter().zip(func.params.iter()) {
-                    SortError::assert_eq(param.1.as_sort().unwrap(), self.pool.sort(arg))
-                        .map_err(|err| Error::Parser(err.into(), head_pos))?;
+                    .map_err(|err| self.error_at(err, head_pos))?;
+                for (i, (arg, param)) in args.iter().zip(func.params.iter()).enumerate() {
+                    if let Err(e) = SortError::assert_eq(&param.1, self.pool.sort(arg)) {
+                        let params = func.params.iter().map(|(_, s)| s);
+                        let return_sort = self.pool.sort(&func.body);
+                        let rank = params
+                            .chain([return_sort])
+                            .map(|s| s.as_ref().clone())
+                            .collect();
+                        let app = self.application_sorts(func_name, Some(rank), &args);
+                        let e = e.at_arg(i).in_application(app);
+                        return Err(self.error_at(e.into(), head_pos));
+                    }
                 }
 
                 // Build a hash map of all the parameter names and the values they will
@@ -1154,54 +2637,149 @@ impl<'a, R: BufRead> Parser<'a, R> {
 
                 Ok(result)
             }
+            Token::OpenParen => {
+                self.next_token()?;
+                if self.current_token == Token::ReservedWord(Reserved::Underscore) {
+                    self.next_token()?;
+                    let iden = self.parse_indexed_identifier()?;
+                    let args = self.parse_sequence(Self::parse_next_term, true)?;
+                    return self
+                        .make_indexed_app(iden, args)
+                        .map_err(|err| self.error_at(err, head_pos));
+                }
+                let func = self.parse_application()?;
+                let args = self.parse_sequence(Self::parse_next_term, true)?;
+                self.make_app(func, args, false)
+                    .map_err(|err| self.error_at(err, head_pos))
+            }
+            // Some solvers write choice terms with a different binder. See `Producer`
+            Token::Symbol(s) if self.config.producer.is_choice_alias(s) && !self.is_declared(s) => {
+                self.next_token()?;
+                self.parse_choice_term()
+            }
+            // In higher-order logics, `(@ f a)` explicitly applies `f` to `a`, which allows
+            // partial applications, unless `@` was declared as a regular function
+            Token::Symbol(s) if s == "@" && !self.is_declared(s) => {
+                self.next_token()?;
+                let func = self.parse_next_term()?;
+                let args = self.parse_sequence(Self::parse_next_term, true)?;
+                self.make_app(func, args, true)
+                    .map_err(|err| self.error_at(err, head_pos))
+            }
             _ => {
-                let func = self.parse_term()?;
-                let args = self.parse_sequence(Self::parse_term, true)?;
-                self.make_app(func, args)
-                    .map_err(|err| Error::Parser(err, head_pos))
+                let func = self.parse_next_term()?;
+                let args = self.parse_sequence(Self::parse_next_term, true)?;
+                self.make_app(func, args, false)
+                    .map_err(|err| self.error_at(err, head_pos))
             }
         }
     }
 
     /// Parses a sort.
-    fn parse_sort(&mut self) -> CarcaraResult<Term> {
-        let pos = self.current_position;
+    fn parse_sort(&mut self) -> CarcaraResult<Rc<Sort>> {
+        let pos = self.current_span;
         let (name, args) = match self.next_token()?.0 {
             Token::Symbol(s) => (s, Vec::new()),
+            Token::OpenParen if self.current_token == Token::ReservedWord(Reserved::Underscore) => {
+                self.next_token()?;
+                let iden = self.parse_indexed_identifier()?;
+                let sort = Self::make_indexed_sort(iden).map_err(|err| self.error_at(err, pos))?;
+                return Ok(self.pool.add_sort(sort));
+            }
             Token::OpenParen => {
                 let name = self.expect_symbol()?;
                 let args = self.parse_sequence(Parser::parse_sort, true)?;
-                (name, self.pool.add_all(args))
+                (name, args)
             }
-            other => return Err(Error::Parser(ParserError::UnexpectedToken(other), pos)),
+            other => return Err(self.error_at(ParserError::UnexpectedToken(other), pos)),
         };
 
         let sort = match name.as_str() {
-            "Bool" | "Int" | "Real" | "String" if !args.is_empty() => Err(Error::Parser(
-                ParserError::WrongNumberOfArgs(0.into(), args.len()),
-                pos,
-            )),
+            "Bool" | "Int" | "Real" | "String" | "RegLan" | "RoundingMode" | "Float16"
+            | "Float32" | "Float64" | "Float128"
+                if !args.is_empty() =>
+            {
+                Err(self.error_at(ParserError::WrongNumberOfArgs(0.into(), args.len()), pos))
+            }
             "Bool" => Ok(Sort::Bool),
             "Int" => Ok(Sort::Int),
             "Real" => Ok(Sort::Real),
             "String" => Ok(Sort::String),
-
+            "RegLan" => Ok(Sort::RegLan),
+            "RoundingMode" => Ok(Sort::RoundingMode),
+            "Float16" => Ok(Sort::FloatingPoint(5, 11)),
+            "Float32" => Ok(Sort::FloatingPoint(8, 24)),
+            "Float64" => Ok(Sort::FloatingPoint(11, 53)),
+            "Float128" => Ok(Sort::FloatingPoint(15, 113)),
+
+            "->" if args.len() < 2 => Err(self.error_at(
+                ParserError::WrongNumberOfArgs((2..).into(), args.len()),
+                pos,
+            )),
+            "->" => Ok(Sort::Function(args)),
             "Array" => match args.as_slice() {
                 [x, y] => Ok(Sort::Array(x.clone(), y.clone())),
-                _ => Err(Error::Parser(
-                    ParserError::WrongNumberOfArgs(2.into(), args.len()),
-                    pos,
-                )),
+                _ => Err(self.error_at(ParserError::WrongNumberOfArgs(2.into(), args.len()), pos)),
             },
             _ => match self.state.sort_declarations.get(&name) {
                 Some(arity) if *arity == args.len() => Ok(Sort::Atom(name, args)),
-                Some(arity) => Err(Error::Parser(
+                Some(arity) => Err(self.error_at(
                     ParserError::WrongNumberOfArgs((*arity).into(), args.len()),
                     pos,
                 )),
-                None => Err(Error::Parser(ParserError::UndefinedSort(name), pos)),
+                None => match self.state.sort_defs.get(&name).cloned() {
+                    Some(def) if def.params.len() == args.len() => {
+                        return Ok(self.substitute_sort_params(&def.body, &def.params, &args));
+                    }
+                    Some(def) => Err(self.error_at(
+                        ParserError::WrongNumberOfArgs(def.params.len().into(), args.len()),
+                        pos,
+                    )),
+                    None => Err(self.error_at(ParserError::UndefinedSort(name), pos)),
+                },
             },
         }?;
-        Ok(Term::Sort(sort))
+        Ok(self.pool.add_sort(sort))
+    }
+}
+
+/// Checks the sort of the converted argument in a floating-point conversion whose first argument
+/// is a rounding mode.
+fn check_float_conversion_arg(op: IndexedOperator, got: &Sort) -> Result<(), SortError> {
+    let is_valid = match op {
+        IndexedOperator::ToFp => {
+            *got == Sort::Real || got.as_float_widths().is_some() || got.as_bitvec_width().is_some()
+        }
+        IndexedOperator::ToFpUnsigned => got.as_bitvec_width().is_some(),
+        _ => got.as_float_widths().is_some(),
+    };
+    if is_valid {
+        return Ok(());
     }
+    let expected = match op {
+        IndexedOperator::ToFp => vec![
+            Sort::Real.into(),
+            ExpectedSort::AnyFloatingPoint,
+            ExpectedSort::AnyBitVec,
+        ],
+        IndexedOperator::ToFpUnsigned => vec![ExpectedSort::AnyBitVec],
+        _ => vec![ExpectedSort::AnyFloatingPoint],
+    };
+    Err(SortError {
+        expected,
+        got: got.clone(),
+        arg_index: None,
+        application: None,
+    })
+}
+
+/// Returns the indices of an indexed identifier as numbers, or `None` if any of them is a symbol.
+fn numeral_indices(indices: &[IdentifierIndex]) -> Option<Vec<u64>> {
+    indices
+        .iter()
+        .map(|i| match i {
+            IdentifierIndex::Numeral(n) => Some(*n),
+            IdentifierIndex::Symbol(_) => None,
+        })
+        .collect()
 }
@@ -0,0 +1,50 @@
+//! Compatibility modes for the proofs produced by specific solvers. Some solvers emit a dialect of
+//! Alethe that differs from the specification in small ways, which are normalized by the parser
+//! when the producer of the proof is known, so these proofs can be checked without changes.
+
+/// The solver that produced a proof, which selects the differences from the Alethe specification
+/// that are accepted by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Producer {
+    /// Any solver that follows the Alethe specification. Nothing is normalized.
+    Generic,
+
+    /// cvc5. Its proofs may differ from the specification in these ways:
+    ///
+    /// - Rule names may be written in upper case, like `RESOLUTION` or `ALL_SIMPLIFY`. These are
+    ///   read in lower case.
+    /// - Terms, including step arguments, may be wrapped in annotations with attributes that are
+    ///   not known by the parser, like `(! t :skolem_id 3)`. These attributes are ignored.
+    /// - Choice terms may be written with the `witness` binder, as in `(witness ((x Int)) φ)`,
+    ///   which is read as `choice`.
+    Cvc5,
+}
+
+impl Default for Producer {
+    fn default() -> Self {
+        Self::Generic
+    }
+}
+
+impl Producer {
+    /// Returns the name of a rule as it is known by the checker.
+    pub(super) fn normalize_rule_name(self, name: String) -> String {
+        let is_upper_case = name.chars().any(|c| c.is_ascii_uppercase())
+            && !name.chars().any(|c| c.is_ascii_lowercase());
+        match self {
+            Self::Cvc5 if is_upper_case => name.to_ascii_lowercase(),
+            _ => name,
+        }
+    }
+
+    /// Returns `true` if attributes in annotated terms that are not known by the parser are
+    /// ignored, regardless of `ParserConfig::ignore_unknown_attributes`.
+    pub(super) fn ignores_unknown_attributes(self) -> bool {
+        self == Self::Cvc5
+    }
+
+    /// Returns `true` if `symbol` is read as the `choice` binder.
+    pub(super) fn is_choice_alias(self, symbol: &str) -> bool {
+        self == Self::Cvc5 && symbol == "witness"
+    }
+}
@@ -0,0 +1,37 @@
+//! Running statistics about the proof being parsed. These can be reported while parsing through
+//! `Parser::on_progress`, for example to show a progress bar, or to stop early when a proof is too
+//! large to be checked.
+
+use crate::ast::{Rc, Term};
+use std::mem::size_of;
+
+/// The statistics of the proof parsed so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseProgress {
+    /// The number of proof commands parsed so far. This includes `assume` and `anchor` commands,
+    /// but not declarations or definitions.
+    pub commands: usize,
+
+    /// The number of `step` commands parsed so far.
+    pub steps: usize,
+
+    /// The total number of literals in the clauses of the steps parsed so far.
+    pub literals: usize,
+
+    /// The number of distinct terms in the term pool, including the ones added by the problem.
+    pub terms: usize,
+
+    /// The number of bytes of the proof that were read so far.
+    pub bytes_read: usize,
+}
+
+impl ParseProgress {
+    /// Returns a rough estimate of the memory used by the terms in the pool, in bytes. Each term is
+    /// stored once as a key in the pool, and once in the allocation shared by its `Rc`s. The heap
+    /// memory owned by the terms, like their argument vectors, is not counted, so this is a lower
+    /// bound.
+    pub fn memory_estimate(&self) -> usize {
+        let term_size = 2 * size_of::<Term>() + size_of::<Rc<Term>>() + 2 * size_of::<usize>();
+        self.terms * term_size
+    }
+}
@@ -0,0 +1,563 @@
+//! Operator signatures, used by the parser to resolve operator names and to sort check operation
+//! terms.
+//!
+//! Each theory provides a table that associates the name of each of its operators with a
+//! signature, which describes the number of arguments the operator may receive and the rule used
+//! to check their sorts. The parser looks operators up in a `SignatureTable` built from these
+//! tables, so supporting a new operator only requires adding it to its theory table. Additional
+//! names can also be registered in a parser's table, for example to accept an alias for an
+//! existing operator, or to restrict the sorts it accepts.
+
+use super::error::{ExpectedSort, ParserError, SortError};
+use crate::{ast::*, utils::Range};
+use ahash::AHashMap;
+
+/// The number of arguments an operator may receive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    /// Exactly this number of arguments.
+    Exactly(usize),
+
+    /// This number of arguments or more.
+    AtLeast(usize),
+}
+
+impl From<Arity> for Range {
+    fn from(arity: Arity) -> Self {
+        match arity {
+            Arity::Exactly(n) => n.into(),
+            Arity::AtLeast(n) => (n..).into(),
+        }
+    }
+}
+
+/// The arguments given to a sort rule.
+pub struct SortRuleArgs<'a> {
+    pub pool: &'a mut TermPool,
+    pub args: &'a [Rc<Term>],
+    pub allow_int_real_subtyping: bool,
+}
+
+impl SortRuleArgs<'_> {
+    /// Returns the sorts of the operator arguments.
+    pub fn sorts(&self) -> Vec<&Sort> {
+        self.args
+            .iter()
+            .map(|t| self.pool.sort(t).as_ref())
+            .collect()
+    }
+}
+
+/// A function that checks the sorts of the arguments of an operation term. Sort rules are only
+/// called after the number of arguments was checked against the operator arity.
+pub type SortRule = fn(SortRuleArgs) -> Result<(), ParserError>;
+
+/// The signature of an operator.
+#[derive(Debug, Clone, Copy)]
+pub struct OperatorSignature {
+    /// The operator in the AST that terms using this signature are built with.
+    pub operator: Operator,
+    pub arity: Arity,
+    pub sort_rule: SortRule,
+}
+
+/// A table of operator signatures, indexed by operator name.
+#[derive(Debug, Clone)]
+pub struct SignatureTable {
+    signatures: AHashMap<String, OperatorSignature>,
+}
+
+impl Default for SignatureTable {
+    /// Constructs a table with the signatures of all the theories supported by Carcara.
+    fn default() -> Self {
+        let mut table = Self::new();
+        for theory in [
+            CORE,
+            ARITHMETIC,
+            ARRAYS,
+            BITVECTORS,
+            STRINGS,
+            FLOATING_POINT,
+        ] {
+            table.register_theory(theory);
+        }
+        table.register_theory(ROUNDING_MODE_ALIASES);
+        table
+    }
+}
+
+impl SignatureTable {
+    /// Constructs an empty table.
+    pub fn new() -> Self {
+        Self { signatures: AHashMap::new() }
+    }
+
+    /// Registers an operator signature with the given name. If a signature was already registered
+    /// with this name, it is replaced.
+    pub fn register(&mut self, name: &str, signature: OperatorSignature) {
+        self.signatures.insert(name.to_owned(), signature);
+    }
+
+    /// Registers all the signatures in a theory table.
+    pub fn register_theory(&mut self, theory: &[(&str, OperatorSignature)]) {
+        for &(name, signature) in theory {
+            self.register(name, signature);
+        }
+    }
+
+    /// Returns the signature registered with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&OperatorSignature> {
+        self.signatures.get(name)
+    }
+}
+
+const fn signature(operator: Operator, arity: Arity, sort_rule: SortRule) -> OperatorSignature {
+    OperatorSignature { operator, arity, sort_rule }
+}
+
+/// The operators from the SMT-LIB core theory.
+pub const CORE: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        ("not", signature(Not, Exactly(1), all_bool)),
+        ("=>", signature(Implies, AtLeast(2), all_bool)),
+        // `and`, `or` and `xor` can be called with only one argument
+        ("and", signature(And, AtLeast(1), all_bool)),
+        ("or", signature(Or, AtLeast(1), all_bool)),
+        ("xor", signature(Xor, AtLeast(1), all_bool)),
+        ("=", signature(Equals, AtLeast(2), all_equal)),
+        ("distinct", signature(Distinct, AtLeast(2), all_equal)),
+        ("ite", signature(Ite, Exactly(3), ite)),
+    ]
+};
+
+/// The operators from the SMT-LIB integer and real arithmetic theories.
+pub const ARITHMETIC: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        ("+", signature(Add, AtLeast(2), arithmetic)),
+        // The `-` operator can be called with only one argument, in which case it means negation
+        // instead of subtraction
+        ("-", signature(Sub, AtLeast(1), arithmetic)),
+        ("*", signature(Mult, AtLeast(2), arithmetic)),
+        ("div", signature(IntDiv, AtLeast(2), all_int)),
+        ("/", signature(RealDiv, AtLeast(2), real_division)),
+        ("mod", signature(Mod, Exactly(2), all_int)),
+        ("abs", signature(Abs, Exactly(1), all_int)),
+        ("<", signature(LessThan, AtLeast(2), comparison)),
+        (">", signature(GreaterThan, AtLeast(2), comparison)),
+        ("<=", signature(LessEq, AtLeast(2), comparison)),
+        (">=", signature(GreaterEq, AtLeast(2), comparison)),
+        ("to_real", signature(ToReal, Exactly(1), all_int)),
+        ("to_int", signature(ToInt, Exactly(1), all_real)),
+        ("is_int", signature(IsInt, Exactly(1), all_real)),
+    ]
+};
+
+/// The operators from the SMT-LIB arrays theory.
+pub const ARRAYS: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        ("select", signature(Select, Exactly(2), select)),
+        ("store", signature(Store, Exactly(3), store)),
+    ]
+};
+
+/// The operators from the SMT-LIB fixed-size bitvectors theory. The indexed operators, like
+/// `(_ extract i j)`, are not in this table, and are handled separately by the parser.
+pub const BITVECTORS: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        ("concat", signature(Concat, AtLeast(2), all_bitvec)),
+        ("bvnot", signature(BvNot, Exactly(1), same_width)),
+        ("bvneg", signature(BvNeg, Exactly(1), same_width)),
+        ("bvand", signature(BvAnd, AtLeast(2), same_width)),
+        ("bvor", signature(BvOr, AtLeast(2), same_width)),
+        ("bvxor", signature(BvXor, AtLeast(2), same_width)),
+        ("bvnand", signature(BvNand, Exactly(2), same_width)),
+        ("bvnor", signature(BvNor, Exactly(2), same_width)),
+        ("bvxnor", signature(BvXnor, Exactly(2), same_width)),
+        ("bvcomp", signature(BvComp, Exactly(2), same_width)),
+        ("bvadd", signature(BvAdd, AtLeast(2), same_width)),
+        ("bvsub", signature(BvSub, Exactly(2), same_width)),
+        ("bvmul", signature(BvMul, AtLeast(2), same_width)),
+        ("bvudiv", signature(BvUDiv, Exactly(2), same_width)),
+        ("bvurem", signature(BvURem, Exactly(2), same_width)),
+        ("bvsdiv", signature(BvSDiv, Exactly(2), same_width)),
+        ("bvsrem", signature(BvSRem, Exactly(2), same_width)),
+        ("bvsmod", signature(BvSMod, Exactly(2), same_width)),
+        ("bvshl", signature(BvShl, Exactly(2), same_width)),
+        ("bvlshr", signature(BvLShr, Exactly(2), same_width)),
+        ("bvashr", signature(BvAShr, Exactly(2), same_width)),
+        ("bvult", signature(BvULt, Exactly(2), same_width)),
+        ("bvule", signature(BvULe, Exactly(2), same_width)),
+        ("bvugt", signature(BvUGt, Exactly(2), same_width)),
+        ("bvuge", signature(BvUGe, Exactly(2), same_width)),
+        ("bvslt", signature(BvSLt, Exactly(2), same_width)),
+        ("bvsle", signature(BvSLe, Exactly(2), same_width)),
+        ("bvsgt", signature(BvSGt, Exactly(2), same_width)),
+        ("bvsge", signature(BvSGe, Exactly(2), same_width)),
+    ]
+};
+
+/// The operators from the SMT-LIB Unicode strings theory, including regular expressions. The
+/// indexed operators `(_ re.^ n)` and `(_ re.loop i j)` are handled separately by the parser.
+pub const STRINGS: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        ("str.++", signature(StrConcat, AtLeast(2), all_string)),
+        ("str.len", signature(StrLen, Exactly(1), all_string)),
+        ("str.<", signature(StrLt, AtLeast(2), all_string)),
+        ("str.<=", signature(StrLe, AtLeast(2), all_string)),
+        ("str.at", signature(StrAt, Exactly(2), str_at)),
+        ("str.substr", signature(StrSubstr, Exactly(3), str_substr)),
+        (
+            "str.prefixof",
+            signature(StrPrefixOf, Exactly(2), all_string),
+        ),
+        (
+            "str.suffixof",
+            signature(StrSuffixOf, Exactly(2), all_string),
+        ),
+        (
+            "str.contains",
+            signature(StrContains, Exactly(2), all_string),
+        ),
+        (
+            "str.indexof",
+            signature(StrIndexOf, Exactly(3), str_indexof),
+        ),
+        ("str.replace", signature(StrReplace, Exactly(3), all_string)),
+        (
+            "str.replace_all",
+            signature(StrReplaceAll, Exactly(3), all_string),
+        ),
+        (
+            "str.replace_re",
+            signature(StrReplaceRe, Exactly(3), str_replace_re),
+        ),
+        (
+            "str.replace_re_all",
+            signature(StrReplaceReAll, Exactly(3), str_replace_re),
+        ),
+        (
+            "str.is_digit",
+            signature(StrIsDigit, Exactly(1), all_string),
+        ),
+        ("str.to_code", signature(StrToCode, Exactly(1), all_string)),
+        ("str.from_code", signature(StrFromCode, Exactly(1), all_int)),
+        ("str.to_int", signature(StrToInt, Exactly(1), all_string)),
+        ("str.from_int", signature(StrFromInt, Exactly(1), all_int)),
+        ("str.to_re", signature(StrToRe, Exactly(1), all_string)),
+        ("str.in_re", signature(StrInRe, Exactly(2), str_in_re)),
+        ("re.none", signature(ReNone, Exactly(0), constant)),
+        ("re.all", signature(ReAll, Exactly(0), constant)),
+        ("re.allchar", signature(ReAllChar, Exactly(0), constant)),
+        ("re.++", signature(ReConcat, AtLeast(2), all_reglan)),
+        ("re.union", signature(ReUnion, AtLeast(2), all_reglan)),
+        ("re.inter", signature(ReInter, AtLeast(2), all_reglan)),
+        ("re.*", signature(ReStar, Exactly(1), all_reglan)),
+        ("re.+", signature(RePlus, Exactly(1), all_reglan)),
+        ("re.opt", signature(ReOpt, Exactly(1), all_reglan)),
+        ("re.range", signature(ReRange, Exactly(2), all_string)),
+        ("re.comp", signature(ReComp, Exactly(1), all_reglan)),
+        ("re.diff", signature(ReDiff, AtLeast(2), all_reglan)),
+    ]
+};
+
+/// The operators from the SMT-LIB floating-point theory, and the rounding modes. The indexed
+/// operators and constants, like `(_ to_fp eb sb)` and `(_ NaN eb sb)`, are handled separately by
+/// the parser.
+pub const FLOATING_POINT: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        ("fp", signature(Fp, Exactly(3), fp_literal)),
+        ("fp.abs", signature(FpAbs, Exactly(1), same_float)),
+        ("fp.neg", signature(FpNeg, Exactly(1), same_float)),
+        ("fp.add", signature(FpAdd, Exactly(3), rounded_float)),
+        ("fp.sub", signature(FpSub, Exactly(3), rounded_float)),
+        ("fp.mul", signature(FpMul, Exactly(3), rounded_float)),
+        ("fp.div", signature(FpDiv, Exactly(3), rounded_float)),
+        ("fp.fma", signature(FpFma, Exactly(4), rounded_float)),
+        ("fp.sqrt", signature(FpSqrt, Exactly(2), rounded_float)),
+        ("fp.rem", signature(FpRem, Exactly(2), same_float)),
+        (
+            "fp.roundToIntegral",
+            signature(FpRoundToIntegral, Exactly(2), rounded_float),
+        ),
+        ("fp.min", signature(FpMin, Exactly(2), same_float)),
+        ("fp.max", signature(FpMax, Exactly(2), same_float)),
+        ("fp.leq", signature(FpLeq, AtLeast(2), same_float)),
+        ("fp.lt", signature(FpLt, AtLeast(2), same_float)),
+        ("fp.geq", signature(FpGeq, AtLeast(2), same_float)),
+        ("fp.gt", signature(FpGt, AtLeast(2), same_float)),
+        ("fp.eq", signature(FpEq, AtLeast(2), same_float)),
+        ("fp.isNormal", signature(FpIsNormal, Exactly(1), same_float)),
+        (
+            "fp.isSubnormal",
+            signature(FpIsSubnormal, Exactly(1), same_float),
+        ),
+        ("fp.isZero", signature(FpIsZero, Exactly(1), same_float)),
+        (
+            "fp.isInfinite",
+            signature(FpIsInfinite, Exactly(1), same_float),
+        ),
+        ("fp.isNaN", signature(FpIsNaN, Exactly(1), same_float)),
+        (
+            "fp.isNegative",
+            signature(FpIsNegative, Exactly(1), same_float),
+        ),
+        (
+            "fp.isPositive",
+            signature(FpIsPositive, Exactly(1), same_float),
+        ),
+        ("fp.to_real", signature(FpToReal, Exactly(1), same_float)),
+        (
+            "RNE",
+            signature(RoundNearestTiesToEven, Exactly(0), constant),
+        ),
+        (
+            "RNA",
+            signature(RoundNearestTiesToAway, Exactly(0), constant),
+        ),
+        ("RTP", signature(RoundTowardPositive, Exactly(0), constant)),
+        ("RTN", signature(RoundTowardNegative, Exactly(0), constant)),
+        ("RTZ", signature(RoundTowardZero, Exactly(0), constant)),
+    ]
+};
+
+/// The long names of the rounding modes, like `roundNearestTiesToEven`, which are aliases for the
+/// short names in the `FLOATING_POINT` table.
+pub const ROUNDING_MODE_ALIASES: &[(&str, OperatorSignature)] = {
+    use {Arity::*, Operator::*};
+    &[
+        (
+            "roundNearestTiesToEven",
+            signature(RoundNearestTiesToEven, Exactly(0), constant),
+        ),
+        (
+            "roundNearestTiesToAway",
+            signature(RoundNearestTiesToAway, Exactly(0), constant),
+        ),
+        (
+            "roundTowardPositive",
+            signature(RoundTowardPositive, Exactly(0), constant),
+        ),
+        (
+            "roundTowardNegative",
+            signature(RoundTowardNegative, Exactly(0), constant),
+        ),
+        (
+            "roundTowardZero",
+            signature(RoundTowardZero, Exactly(0), constant),
+        ),
+    ]
+};
+
+fn assert_all(sorts: &[&Sort], expected: &Sort) -> Result<(), ParserError> {
+    for (i, s) in sorts.iter().enumerate() {
+        SortError::assert_eq(expected, s).map_err(|e| e.at_arg(i))?;
+    }
+    Ok(())
+}
+
+fn all_bool(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_all(&args.sorts(), &Sort::Bool)
+}
+
+fn all_int(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_all(&args.sorts(), &Sort::Int)
+}
+
+fn all_real(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_all(&args.sorts(), &Sort::Real)
+}
+
+fn all_string(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_all(&args.sorts(), &Sort::String)
+}
+
+fn all_reglan(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_all(&args.sorts(), &Sort::RegLan)
+}
+
+/// Checks that each argument has the corresponding sort in `expected`, for operators whose
+/// arguments have different sorts.
+fn assert_each(sorts: &[&Sort], expected: &[Sort]) -> Result<(), ParserError> {
+    for (i, (s, e)) in sorts.iter().zip(expected).enumerate() {
+        SortError::assert_eq(e, s).map_err(|e| e.at_arg(i))?;
+    }
+    Ok(())
+}
+
+fn str_at(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_each(&args.sorts(), &[Sort::String, Sort::Int])
+}
+
+fn str_substr(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_each(&args.sorts(), &[Sort::String, Sort::Int, Sort::Int])
+}
+
+fn str_indexof(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_each(&args.sorts(), &[Sort::String, Sort::String, Sort::Int])
+}
+
+fn str_replace_re(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_each(&args.sorts(), &[Sort::String, Sort::RegLan, Sort::String])
+}
+
+fn str_in_re(args: SortRuleArgs) -> Result<(), ParserError> {
+    assert_each(&args.sorts(), &[Sort::String, Sort::RegLan])
+}
+
+// Operators that take no arguments have nothing to check, but still need a sort rule
+#[allow(clippy::unnecessary_wraps)]
+fn constant(_: SortRuleArgs) -> Result<(), ParserError> {
+    Ok(())
+}
+
+fn fp_literal(args: SortRuleArgs) -> Result<(), ParserError> {
+    // The sign bit must be a bitvector of width one, and the exponent and significand can be
+    // bitvectors of any width
+    let sorts = args.sorts();
+    SortError::assert_eq(&Sort::BitVec(1), sorts[0]).map_err(|e| e.at_arg(0))?;
+    all_bitvec(args)
+}
+
+fn same_float(args: SortRuleArgs) -> Result<(), ParserError> {
+    let sorts = args.sorts();
+    SortError::assert_float(sorts[0]).map_err(|e| e.at_arg(0))?;
+    Ok(SortError::assert_all_eq(&sorts)?)
+}
+
+fn rounded_float(args: SortRuleArgs) -> Result<(), ParserError> {
+    // The first argument is the rounding mode, and the others are floating-point numbers of the
+    // same sort
+    let sorts = args.sorts();
+    SortError::assert_eq(&Sort::RoundingMode, sorts[0]).map_err(|e| e.at_arg(0))?;
+    SortError::assert_float(sorts[1]).map_err(|e| e.at_arg(1))?;
+    for i in 2..sorts.len() {
+        SortError::assert_eq(sorts[1], sorts[i]).map_err(|e| e.at_arg(i))?;
+    }
+    Ok(())
+}
+
+fn all_equal(args: SortRuleArgs) -> Result<(), ParserError> {
+    Ok(SortError::assert_all_eq(&args.sorts())?)
+}
+
+fn ite(args: SortRuleArgs) -> Result<(), ParserError> {
+    let sorts = args.sorts();
+    SortError::assert_eq(&Sort::Bool, sorts[0]).map_err(|e| e.at_arg(0))?;
+    Ok(SortError::assert_eq(sorts[1], sorts[2]).map_err(|e| e.at_arg(2))?)
+}
+
+fn arithmetic(args: SortRuleArgs) -> Result<(), ParserError> {
+    // All the arguments must be either Int or Real. Also, if we are not allowing Int/Real
+    // subtyping, all arguments must have the same sort
+    if args.allow_int_real_subtyping {
+        return comparison(args);
+    }
+    let sorts = args.sorts();
+    SortError::assert_one_of(&[Sort::Int, Sort::Real], sorts[0]).map_err(|e| e.at_arg(0))?;
+    Ok(SortError::assert_all_eq(&sorts)?)
+}
+
+fn real_division(args: SortRuleArgs) -> Result<(), ParserError> {
+    // Normally, the `/` operator may only receive Real arguments, but if we are allowing Int/Real
+    // subtyping, it may also receive Ints
+    if args.allow_int_real_subtyping {
+        comparison(args)
+    } else {
+        all_real(args)
+    }
+}
+
+fn comparison(args: SortRuleArgs) -> Result<(), ParserError> {
+    // All the arguments must be either Int or Real sorted, but they don't need to all have the
+    // same sort
+    for (i, s) in args.sorts().into_iter().enumerate() {
+        SortError::assert_one_of(&[Sort::Int, Sort::Real], s).map_err(|e| e.at_arg(i))?;
+    }
+    Ok(())
+}
+
+fn all_bitvec(args: SortRuleArgs) -> Result<(), ParserError> {
+    // The arguments may have different widths
+    for (i, s) in args.sorts().into_iter().enumerate() {
+        SortError::assert_bitvec(s).map_err(|e| e.at_arg(i))?;
+    }
+    Ok(())
+}
+
+fn same_width(args: SortRuleArgs) -> Result<(), ParserError> {
+    let sorts = args.sorts();
+    SortError::assert_bitvec(sorts[0]).map_err(|e| e.at_arg(0))?;
+    Ok(SortError::assert_all_eq(&sorts)?)
+}
+
+fn select(args: SortRuleArgs) -> Result<(), ParserError> {
+    let sorts = args.sorts();
+    match sorts[0] {
+        Sort::Array(_, _) => Ok(()),
+
+        // The sort of the array index is inferred from the second argument, but the sort of its
+        // elements can be anything
+        got => Err(SortError {
+            expected: vec![ExpectedSort::Parametric(
+                "Array".to_owned(),
+                vec![Some(sorts[1].clone()), None],
+            )],
+            got: got.clone(),
+            arg_index: Some(0),
+            application: None,
+        }
+        .into()),
+    }
+}
+
+fn store(args: SortRuleArgs) -> Result<(), ParserError> {
+    let sorts = args.sorts();
+    match sorts[0] {
+        Sort::Array(x, y) => {
+            SortError::assert_eq(x, sorts[1]).map_err(|e| e.at_arg(1))?;
+            Ok(SortError::assert_eq(y, sorts[2]).map_err(|e| e.at_arg(2))?)
+        }
+        got => {
+            let got = got.clone();
+            let (x, y) = (sorts[1].clone(), sorts[2].clone());
+            let expected = vec![Sort::Array(args.pool.add_sort(x), args.pool.add_sort(y)).into()];
+            Err(SortError {
+                expected,
+                got,
+                arg_index: Some(0),
+                application: None,
+            }
+            .into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_theory_tables() {
+        let table = SignatureTable::default();
+        for theory in [
+            CORE,
+            ARITHMETIC,
+            ARRAYS,
+            BITVECTORS,
+            STRINGS,
+            FLOATING_POINT,
+        ] {
+            for (name, signature) in theory {
+                assert_eq!(Ok(signature.operator), Operator::from_str(name));
+                assert!(table.get(name).is_some());
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Suggestions for names that are not defined, like "did you mean 'x'?". These are the defined
+//! names that are closest to the undefined one by edit distance, which usually catches typos in
+//! hand-edited proofs. Suggestions are only computed when an error is reported, so their cost
+//! doesn't matter for valid proofs.
+//!
+//! This also has the hints given for common mistakes made by proof producers, like misspelled
+//! attributes or an extra `)` at the end of a command. Unlike the suggestions for names, these are
+//! driven by a fixed table of known near misses.
+
+use super::Token;
+use std::fmt;
+
+/// The maximum number of suggestions given for a single name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the Levenshtein distance between two strings, that is, the smallest number of character
+/// insertions, deletions or substitutions that turn one into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Returns the candidates that are close enough to `name` to be suggested in its place, ordered
+/// from closest to farthest. A candidate is close enough if it is within an edit distance of a
+/// third of the length of `name`, and at least one. Names of a single character have no
+/// suggestions, since any other name of one character would be as close.
+pub fn closest_matches<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    let len = name.chars().count();
+    let max_distance = (len / 3).max(1);
+    if len <= max_distance {
+        return Vec::new();
+    }
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|c| c.chars().count().abs_diff(len) <= max_distance && *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|&(d, _)| d <= max_distance)
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, c)| c.to_owned())
+        .collect()
+}
+
+/// Formats a list of suggestions to be appended to an error message. This is empty if there are no
+/// suggestions.
+pub struct DidYouMean<'a>(pub &'a [String]);
+
+impl fmt::Display for DidYouMean<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (last, rest) = match self.0.split_last() {
+            Some(s) => s,
+            None => return Ok(()),
+        };
+        write!(f, "; did you mean ")?;
+        for (i, s) in rest.iter().enumerate() {
+            let separator = if i + 1 < rest.len() { ", " } else { " or " };
+            write!(f, "'{}'{}", s, separator)?;
+        }
+        write!(f, "'{}'?", last)
+    }
+}
+
+/// Tokens that are common mistakes in proof commands, together with the token that was probably
+/// meant, both as they are written. These are misspelled attributes of commands, and wrong names
+/// of commands.
+const NEAR_MISSES: &[(&str, &str)] = &[
+    (":premise", ":premises"),
+    (":premisses", ":premises"),
+    (":prems", ":premises"),
+    (":arg", ":args"),
+    (":arguments", ":args"),
+    (":rules", ":rule"),
+    (":discharges", ":discharge"),
+    ("assert", "assume"),
+    ("assumption", "assume"),
+    ("steps", "step"),
+    ("anchors", "anchor"),
+];
+
+/// A fix suggested for an unexpected token, when it matches a common mistake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairHint {
+    /// The token is a known near miss of another token, which is held here as it is written.
+    Replace(&'static str),
+
+    /// A `step` command doesn't have a `:rule` attribute right after its clause.
+    MissingRule,
+
+    /// A `)` was found where a command should start. This usually means that the previous command
+    /// has an extra `)` at its end.
+    ExtraCloseParen,
+}
+
+impl RepairHint {
+    /// Returns the hint for a token, if it is a known near miss of another token.
+    pub fn for_near_miss(token: &Token) -> Option<Self> {
+        if !matches!(
+            token,
+            Token::Keyword(_) | Token::Symbol(_) | Token::ReservedWord(_)
+        ) {
+            return None;
+        }
+        let written = token.to_string();
+        NEAR_MISSES
+            .iter()
+            .find(|(near_miss, _)| *near_miss == written)
+            .map(|&(_, meant)| RepairHint::Replace(meant))
+    }
+}
+
+impl fmt::Display for RepairHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepairHint::Replace(meant) => write!(f, "did you mean '{}'?", meant),
+            RepairHint::MissingRule => write!(f, "expected ':rule' after the clause of the step"),
+            RepairHint::ExtraCloseParen => write!(f, "the previous command may have an extra ')'"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("t1.t2", "t1.t3"), 1);
+        assert_eq!(edit_distance("ação", "acao"), 2);
+    }
+
+    #[test]
+    fn test_closest_matches() {
+        let candidates = ["t1", "t2", "t12", "h1", "foo", "t1.t1"];
+        assert_eq!(closest_matches("t3", candidates), ["t1", "t2"]);
+        assert_eq!(closest_matches("t1.t2", candidates), ["t1.t1"]);
+        assert_eq!(closest_matches("fo", candidates), ["foo"]);
+        assert!(closest_matches("x", ["y", "xx"]).is_empty());
+        assert!(closest_matches("bar", candidates).is_empty());
+
+        let message = |s: &[&str]| {
+            let s: Vec<_> = s.iter().map(|&s| s.to_owned()).collect();
+            DidYouMean(&s).to_string()
+        };
+        assert_eq!(message(&[]), "");
+        assert_eq!(message(&["a"]), "; did you mean 'a'?");
+        assert_eq!(message(&["a", "b"]), "; did you mean 'a' or 'b'?");
+        assert_eq!(message(&["a", "b", "c"]), "; did you mean 'a', 'b' or 'c'?");
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let hint = |t: Token| RepairHint::for_near_miss(&t);
+        assert_eq!(
+            hint(Token::Keyword("premise".into())),
+            Some(RepairHint::Replace(":premises"))
+        );
+        assert_eq!(
+            hint(Token::Symbol("assumption".into())),
+            Some(RepairHint::Replace("assume"))
+        );
+        assert_eq!(hint(Token::Keyword("premises".into())), None);
+        assert_eq!(hint(Token::Symbol("premise".into())), None);
+        assert_eq!(hint(Token::CloseParen), None);
+    }
+}
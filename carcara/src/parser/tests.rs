@@ -11,19 +11,16 @@ pub fn parse_terms<const N: usize>(
     definitions: &str,
     terms: [&str; N],
 ) -> [Rc<Term>; N] {
-    let mut parser =
-        Parser::new(pool, definitions.as_bytes(), true, false, false).expect(ERROR_MESSAGE);
-    parser.parse_problem().expect(ERROR_MESSAGE);
+    let (_, _, mut parser) = Parser::new(pool, ParserConfig::default(), definitions.as_bytes())
+        .and_then(Parser::parse_problem)
+        .expect(ERROR_MESSAGE);
 
-    terms.map(|s| {
-        parser.reset(s.as_bytes()).expect(ERROR_MESSAGE);
-        parser.parse_term().expect(ERROR_MESSAGE)
-    })
+    terms.map(|s| parser.parse_term(s.as_bytes()).expect(ERROR_MESSAGE))
 }
 
 pub fn parse_term(pool: &mut TermPool, input: &str) -> Rc<Term> {
-    Parser::new(pool, input.as_bytes(), true, false, false)
-        .and_then(|mut parser| parser.parse_term())
+    Parser::new(pool, ParserConfig::default(), input.as_bytes())
+        .and_then(Parser::parse_term)
         .expect(ERROR_MESSAGE)
 }
 
@@ -31,18 +28,25 @@ pub fn parse_term(pool: &mut TermPool, input: &str) -> Rc<Term> {
 /// panics if no error is encountered.
 pub fn parse_term_err(input: &str) -> Error {
     let mut pool = TermPool::new();
-    Parser::new(&mut pool, input.as_bytes(), true, false, false)
-        .and_then(|mut p| p.parse_term())
+    Parser::new(&mut pool, ParserConfig::default(), input.as_bytes())
+        .and_then(Parser::parse_term)
         .expect_err("expected error")
 }
 
 /// Parses a proof from a `&str`. Panics if any error is encountered.
 pub fn parse_proof(pool: &mut TermPool, input: &str) -> Proof {
-    let commands = Parser::new(pool, input.as_bytes(), true, false, false)
+    let commands = Parser::new(pool, ParserConfig::default(), input.as_bytes())
         .expect(ERROR_MESSAGE)
         .parse_proof()
         .expect(ERROR_MESSAGE);
-    Proof { premises: AHashSet::new(), commands }
+    Proof {
+        premises: AHashSet::new(),
+        commands,
+        spans: Vec::new(),
+        metadata: Vec::new(),
+        named_assertions: Vec::new(),
+        comments: Vec::new(),
+    }
 }
 
 fn run_parser_tests(pool: &mut TermPool, cases: &[(&str, Rc<Term>)]) {
@@ -64,7 +68,7 @@ fn test_hash_consing() {
         )
         (* 2 2)
     )";
-    let mut parser = Parser::new(&mut pool, input.as_bytes(), true, false, false).unwrap();
+    let parser = Parser::new(&mut pool, ParserConfig::default(), input.as_bytes()).unwrap();
     parser.parse_term().unwrap();
 
     // We expect this input to result in 7 unique terms after parsing:
@@ -76,9 +80,7 @@ fn test_hash_consing() {
     //   (* 2 2)
     //   (- (- ...) (* 2 2))
     let expected = vec![
-        // The `Bool` sort and the boolean constants `true` and `false` are always added to the
-        // terms map
-        "Bool",
+        // The boolean constants `true` and `false` are always added to the terms map
         "true",
         "false",
         "1",
@@ -98,6 +100,11 @@ fn test_hash_consing() {
         let formatted: &str = &format!("{}", got);
         assert!(expected.contains(formatted), "{}", formatted);
     }
+
+    // Sorts are stored separately from terms. The `Bool` sort is always added to the pool
+    let mut sorts: Vec<_> = pool.sorts.keys().map(ToString::to_string).collect();
+    sorts.sort();
+    assert_eq!(sorts, ["Bool", "Int"]);
 }
 
 #[test]
@@ -136,6 +143,264 @@ fn test_arithmetic_ops() {
         parse_term_err("(+ (- 1 2) (* 3.0 4.2))"),
         Error::Parser(ParserError::SortError(_), _),
     ));
+
+    let [a, b] = parse_terms(&mut p, "", ["(mod (div 7 2) 2)", "(abs (- 3))"]);
+    assert_eq!("(mod (div 7 2) 2)", a.to_string());
+    assert_eq!(Sort::Int, **p.sort(&a));
+    assert_eq!(Sort::Int, **p.sort(&b));
+    assert!(matches!(
+        parse_term_err("(mod 7.0 2)"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(abs 1 2)"),
+        Error::Parser(ParserError::WrongNumberOfArgs(_, 2), _),
+    ));
+}
+
+#[test]
+fn test_bitvectors() {
+    let mut p = TermPool::new();
+    let definitions = "
+        (declare-fun x () (_ BitVec 8))
+        (declare-fun y () (_ BitVec 8))
+    ";
+    let [x, a, b, c, d, e] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            "x",
+            "#b0101",
+            "(_ bv5 4)",
+            "(bvadd x #x0f y)",
+            "(bvult ((_ extract 3 0) x) ((_ zero_extend 2) #b01))",
+            "(concat x ((_ repeat 2) y))",
+        ],
+    );
+    assert_eq!(Term::bitvec(5, 4), *a);
+    assert_eq!(a, b);
+    assert_eq!(Sort::BitVec(8), **p.sort(&c));
+    assert_eq!(Sort::Bool, **p.sort(&d));
+    assert_eq!(Sort::BitVec(24), **p.sort(&e));
+
+    let (op, args) = d.unwrap_op().unwrap();
+    assert_eq!(Operator::BvULt, op);
+    assert_eq!(
+        Term::IndexedOp(IndexedOperator::BvExtract, vec![3, 0], vec![x]),
+        *args[0],
+    );
+    assert_eq!(Sort::BitVec(4), **p.sort(&args[1]));
+
+    assert_eq!("(concat x ((_ repeat 2) y))", e.to_string());
+    assert_eq!("#b00001111", parse_term(&mut p, "#x0f").to_string());
+
+    assert!(matches!(
+        parse_term_err("(bvadd #b01 #b011)"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("((_ extract 2 0) #b01)"),
+        Error::Parser(ParserError::InvalidIndices(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(_ bv4 2)"),
+        Error::Parser(ParserError::InvalidIndices(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("((_ foo 1) #b01)"),
+        Error::Parser(ParserError::UndefinedIden(..), _),
+    ));
+}
+
+#[test]
+fn test_indexed_identifiers() {
+    // Any identifier of the form `(_ <symbol> <index>+)` is read generically, whether or not it is
+    // a known indexed operator, constant or sort. Unknown ones are reported with all their indices
+    let indexed = |name: &str, indices: &[IdentifierIndex]| {
+        Identifier::Indexed(name.to_owned(), indices.to_vec())
+    };
+    let cases = [
+        ("(_ foo 1)", indexed("foo", &[IdentifierIndex::Numeral(1)])),
+        (
+            "((_ foo 2 bar) 3)",
+            indexed(
+                "foo",
+                &[
+                    IdentifierIndex::Numeral(2),
+                    IdentifierIndex::Symbol("bar".to_owned()),
+                ],
+            ),
+        ),
+        (
+            "((_ extract x 0) #b01)",
+            indexed(
+                "extract",
+                &[
+                    IdentifierIndex::Symbol("x".to_owned()),
+                    IdentifierIndex::Numeral(0),
+                ],
+            ),
+        ),
+    ];
+    for (input, expected) in cases {
+        match parse_term_err(input) {
+            Error::Parser(ParserError::UndefinedIden(got, _), _) => assert_eq!(expected, got),
+            other => panic!("expected undefined identifier error, got {:?}", other),
+        }
+    }
+    assert!(matches!(
+        parse_term_err("(_ foo)"),
+        Error::Parser(ParserError::EmptySequence, _),
+    ));
+    assert!(matches!(
+        parse_term_err("(_ foo 1.5)"),
+        Error::Parser(ParserError::UnexpectedToken(_), _),
+    ));
+
+    // The same identifiers are used for sorts
+    let mut p = TermPool::new();
+    let [x] = parse_terms(&mut p, "(declare-fun x () (_ BitVec 4))", ["x"]);
+    assert_eq!(Sort::BitVec(4), **p.sort(&x));
+    assert!(matches!(
+        Parser::new(
+            &mut TermPool::new(),
+            ParserConfig::default(),
+            "(declare-fun x () (_ Foo 4))".as_bytes(),
+        )
+        .and_then(Parser::parse_problem),
+        Err(Error::Parser(ParserError::UndefinedSort(s), _)) if s == "(_ Foo 4)",
+    ));
+}
+
+#[test]
+fn test_divisible() {
+    let mut p = TermPool::new();
+    let [n, a] = parse_terms(
+        &mut p,
+        "(declare-fun n () Int)",
+        ["n", "(and ((_ divisible 3) n) ((_ divisible 1) 7))"],
+    );
+    assert_eq!(Sort::Bool, **p.sort(&a));
+    let (_, args) = a.unwrap_op().unwrap();
+    assert_eq!(
+        Term::IndexedOp(IndexedOperator::Divisible, vec![3], vec![n]),
+        *args[0],
+    );
+    assert_eq!("((_ divisible 3) n)", args[0].to_string());
+
+    assert!(matches!(
+        parse_term_err("((_ divisible 0) 4)"),
+        Error::Parser(ParserError::InvalidIndices(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("((_ divisible 2 3) 4)"),
+        Error::Parser(ParserError::InvalidIndices(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("((_ divisible 2) 4.0)"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+}
+
+#[test]
+fn test_strings() {
+    let mut p = TermPool::new();
+    let definitions = "
+        (declare-fun s () String)
+        (declare-fun r () RegLan)
+    ";
+    let [a, b, c, d, e] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            "(str.++ s \"a\" s)",
+            "(str.len (str.substr s 0 2))",
+            "(str.in_re s (re.++ (str.to_re \"ab\") re.allchar (re.* r)))",
+            "((_ re.loop 1 3) (re.range \"a\" \"z\"))",
+            "(str.< s \"b\" \"c\")",
+        ],
+    );
+    assert_eq!(Sort::String, **p.sort(&a));
+    assert_eq!(Sort::Int, **p.sort(&b));
+    assert_eq!(Sort::Bool, **p.sort(&c));
+    assert_eq!(Sort::RegLan, **p.sort(&d));
+    assert_eq!(Sort::Bool, **p.sort(&e));
+
+    let (_, args) = c.unwrap_op().unwrap();
+    let (op, args) = args[1].unwrap_op().unwrap();
+    assert_eq!(Operator::ReConcat, op);
+    assert_eq!(Term::Op(Operator::ReAllChar, Vec::new()), *args[1]);
+    assert_eq!(
+        "(str.in_re s (re.++ (str.to_re \"ab\") re.allchar (re.* r)))",
+        c.to_string()
+    );
+
+    // Escape sequences are decoded, and strings are printed so they are read back the same way
+    let s = parse_term(&mut p, r#""\u{e9}\u{5c}u0041""#);
+    assert_eq!(Term::string("\u{e9}\\u0041"), *s);
+    assert_eq!(r#""é\u{5c}u0041""#, s.to_string());
+
+    assert!(matches!(
+        parse_term_err("(str.at \"a\" \"b\")"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(str.in_re \"a\" \"a\")"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("((_ re.loop 3 1) re.all)"),
+        Error::Parser(ParserError::InvalidIndices(_), _),
+    ));
+}
+
+#[test]
+fn test_floating_point() {
+    let mut p = TermPool::new();
+    let definitions = "
+        (declare-fun x () Float32)
+        (declare-fun y () (_ FloatingPoint 8 24))
+        (declare-fun r () Real)
+    ";
+    let [a, b, c, d, e, f] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            "(fp.add RNE x y)",
+            "(fp.leq x y (fp.abs x))",
+            "(fp #b0 #b10000000 #b00000000000000000000000)",
+            "((_ to_fp 11 53) roundTowardZero r)",
+            "(_ NaN 5 11)",
+            "((_ fp.to_sbv 16) RTN x)",
+        ],
+    );
+    assert_eq!(Sort::FloatingPoint(8, 24), **p.sort(&a));
+    assert_eq!(Sort::Bool, **p.sort(&b));
+    assert_eq!(Sort::FloatingPoint(8, 24), **p.sort(&c));
+    assert_eq!(Sort::FloatingPoint(11, 53), **p.sort(&d));
+    assert_eq!(Sort::FloatingPoint(5, 11), **p.sort(&e));
+    assert_eq!(Sort::BitVec(16), **p.sort(&f));
+
+    assert_eq!("(fp.add RNE x y)", a.to_string());
+    assert_eq!("((_ to_fp 11 53) RTZ r)", d.to_string());
+    assert_eq!("(_ NaN 5 11)", e.to_string());
+
+    assert!(matches!(
+        parse_term_err("(fp.add (_ +zero 8 24) (_ +zero 8 24) (_ +zero 8 24))"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(fp #b01 #b1 #b1)"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("((_ to_fp 8 24) RNE true)"),
+        Error::Parser(ParserError::SortError(_), _),
+    ));
+    assert!(matches!(
+        parse_term_err("(_ +zero 1 24)"),
+        Error::Parser(ParserError::InvalidIndices(_), _),
+    ));
 }
 
 #[test]
@@ -260,8 +525,8 @@ fn test_ite() {
 #[test]
 fn test_quantifiers() {
     let mut p = TermPool::new();
-    let bool_sort = p.add(Term::Sort(Sort::Bool));
-    let real_sort = p.add(Term::Sort(Sort::Real));
+    let bool_sort = p.add_sort(Sort::Bool);
+    let real_sort = p.add_sort(Sort::Real);
     let cases = [
         ("(exists ((p Bool)) p)", {
             let inner = p.add(Term::var("p", bool_sort.clone()));
@@ -269,6 +534,7 @@ fn test_quantifiers() {
                 Quantifier::Exists,
                 BindingList(vec![("p".into(), bool_sort)]),
                 inner,
+                Vec::new(),
             ))
         }),
         ("(forall ((x Real) (y Real)) (= (+ x y) 0.0))", {
@@ -283,6 +549,7 @@ fn test_quantifiers() {
                     ("y".into(), real_sort),
                 ]),
                 inner,
+                Vec::new(),
             ))
         }),
     ];
@@ -297,11 +564,111 @@ fn test_quantifiers() {
     ));
 }
 
+#[test]
+fn test_quantifier_patterns() {
+    let mut p = TermPool::new();
+    let definitions = "(declare-fun f (Int) Int) (declare-fun g (Int Int) Bool)";
+    let [term, plain, nested, not_quantifier] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            "(forall ((x Int) (y Int)) (! (g (f x) y) :pattern ((f x) y) :pattern ((g x y))))",
+            "(forall ((x Int) (y Int)) (g (f x) y))",
+            "(exists ((x Int)) (! (! (g x x) :pattern ((f x))) :named foo))",
+            "(g (! (f 0) :pattern ((f 1))) 0)",
+        ],
+    );
+
+    let patterns: Vec<Vec<_>> = term
+        .patterns()
+        .iter()
+        .map(|p| p.iter().map(ToString::to_string).collect())
+        .collect();
+    assert_eq!(patterns, [vec!["(f x)", "y"], vec!["(g x y)"]]);
+    assert_eq!(
+        "(forall ((x Int) (y Int)) (! (g (f x) y) :pattern ((f x) y) :pattern ((g x y))))",
+        term.to_string()
+    );
+
+    // Patterns are part of the term, but only the inner term is annotated
+    assert_ne!(term, plain);
+    assert_eq!(
+        term.unwrap_quant().unwrap().2,
+        plain.unwrap_quant().unwrap().2
+    );
+    assert!(plain.patterns().is_empty());
+
+    assert_eq!(nested.patterns().len(), 1);
+    assert_eq!("(g (f 0) 0)", not_quantifier.to_string());
+}
+
+#[test]
+fn test_shadowed_variables() {
+    let mut p = TermPool::new();
+    let definitions = "
+        (declare-fun x () Bool)
+        (declare-fun y () Int)
+    ";
+    let [a, b, c, d] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            // The bindings in a `let` term are parallel, so the values are not in the scope of any
+            // of the variables it binds
+            "(let ((z x) (x 1) (w (and x (= y 0)))) (and z w (= x y)))",
+            "(forall ((x Int)) (let ((x (> x 0))) (and x (exists ((x Real)) (> x 0.0)))))",
+            // The variable in a `choice` term is only in scope in its body
+            "(and (= (choice ((x Int)) (= x y)) y) x)",
+            "(choice ((y Bool)) (let ((y (+ 1 1))) (forall ((y Bool)) y)))",
+        ],
+    );
+    assert_eq!(Sort::Bool, **p.sort(&a));
+    assert_eq!(Sort::Bool, **p.sort(&b));
+    assert_eq!(Sort::Bool, **p.sort(&c));
+    assert_eq!(Sort::Bool, **p.sort(&d));
+
+    // The free variables are the ones declared in the problem
+    let mut free_vars = |term: &Rc<Term>| {
+        let mut vars: Vec<_> = term
+            .free_vars(&mut p)
+            .iter()
+            .map(|v| v.as_var().unwrap().to_owned())
+            .collect();
+        vars.sort();
+        vars
+    };
+    assert_eq!(free_vars(&a), ["x", "y"]);
+    assert!(free_vars(&b).is_empty());
+    assert_eq!(free_vars(&c), ["x", "y"]);
+    assert!(free_vars(&d).is_empty());
+
+    // When expanding `let` terms, the values are not expanded using the other bindings
+    let mut pool = TermPool::new();
+    let config = ParserConfig {
+        expand_lets: true,
+        ..ParserConfig::default()
+    };
+    let (_, _, mut parser) = Parser::new(&mut pool, config, definitions.as_bytes())
+        .and_then(Parser::parse_problem)
+        .unwrap();
+    let expanded = parser
+        .parse_term("(let ((z y) (y (+ y 1)) (w (+ y 2))) (forall ((z Int)) (= w z y)))".as_bytes())
+        .unwrap();
+    assert_eq!(
+        "(forall ((z@ Int)) (= (+ y 2) z@ (+ y 1)))",
+        expanded.to_string()
+    );
+    let expanded = parser
+        .parse_term("(let ((y 1) (x y)) x)".as_bytes())
+        .unwrap();
+    assert_eq!("y", expanded.to_string());
+}
+
 #[test]
 fn test_choice_terms() {
     let mut p = TermPool::new();
-    let bool_sort = p.add(Term::Sort(Sort::Bool));
-    let int_sort = p.add(Term::Sort(Sort::Int));
+    let bool_sort = p.add_sort(Sort::Bool);
+    let int_sort = p.add_sort(Sort::Int);
     let cases = [
         ("(choice ((p Bool)) p)", {
             let inner = p.add(Term::var("p", bool_sort.clone()));
@@ -328,8 +695,8 @@ fn test_choice_terms() {
 #[test]
 fn test_let_terms() {
     let mut p = TermPool::new();
-    let int_sort = p.add(Term::Sort(Sort::Int));
-    let bool_sort = p.add(Term::Sort(Sort::Bool));
+    let int_sort = p.add_sort(Sort::Int);
+    let bool_sort = p.add_sort(Sort::Bool);
     let cases = [
         ("(let ((p false)) p)", {
             let inner = p.add(Term::var("p", bool_sort));
@@ -358,7 +725,7 @@ fn test_let_terms() {
 #[test]
 fn test_lambda_terms() {
     let mut p = TermPool::new();
-    let int_sort = p.add(Term::Sort(Sort::Int));
+    let int_sort = p.add_sort(Sort::Int);
     let cases = [
         ("(lambda ((x Int)) x)", {
             let x = p.add(Term::var("x", int_sort.clone()));
@@ -387,6 +754,55 @@ fn test_lambda_terms() {
     ));
 }
 
+#[test]
+fn test_higher_order_applications() {
+    let definitions = "
+        (declare-fun f (Int Int) Int)
+        (declare-fun g (Int) (-> Int Bool))
+        (declare-fun h ((-> Int Int)) Int)
+    ";
+    let mut p = TermPool::new();
+    let [partial, curried, lambda_arg, applied] = parse_terms(
+        &mut p,
+        definitions,
+        [
+            "(@ f 1)",
+            "(g 1 2)",
+            "(h (lambda ((x Int)) (+ x 1)))",
+            "(= ((@ f 1) 2) (f 1 2))",
+        ],
+    );
+    let int_sort = p.add_sort(Sort::Int);
+    assert_eq!(
+        **p.sort(&partial),
+        Sort::Function(vec![int_sort.clone(), int_sort])
+    );
+    assert_eq!("(-> Int Int)", p.sort(&partial).to_string());
+    assert_eq!(**p.sort(&curried), Sort::Bool);
+    assert_eq!("((g 1) 2)", curried.to_string());
+    assert_eq!(**p.sort(&lambda_arg), Sort::Int);
+    assert_eq!(**p.sort(&applied), Sort::Bool);
+
+    // Partial applications are only allowed with `@`
+    let mut parser = Parser::new(&mut p, ParserConfig::default(), definitions.as_bytes())
+        .and_then(Parser::parse_problem)
+        .unwrap()
+        .2;
+    let mut parse = |input: &'static str| parser.parse_term(input.as_bytes());
+    assert!(matches!(
+        parse("(f 1)"),
+        Err(Error::Parser(ParserError::WrongNumberOfArgs(_, 1), _))
+    ));
+    assert!(matches!(
+        parse("(@ f 1 2 3)"),
+        Err(Error::Parser(ParserError::WrongNumberOfArgs(_, 3), _))
+    ));
+    assert!(matches!(
+        parse("(h f)"),
+        Err(Error::Parser(ParserError::SortError(_), _))
+    ));
+}
+
 #[test]
 fn test_annotated_terms() {
     let mut p = TermPool::new();
@@ -416,6 +832,29 @@ fn test_annotated_terms() {
         parse_term_err("(! true :named 1 2 3)"),
         Error::Parser(ParserError::UnexpectedToken(_), _),
     ));
+
+    // In lenient mode, unknown attributes are skipped together with their values
+    let config = ParserConfig {
+        ignore_unknown_attributes: true,
+        ..Default::default()
+    };
+    let input =
+        "(forall ((x Int)) (! (> x 0) :qid q1 :weight 2 :skolemid :pattern (x) :named foo))";
+    let mut pool = TermPool::new();
+    let term = Parser::new(&mut pool, config, input.as_bytes())
+        .and_then(Parser::parse_term)
+        .unwrap();
+    assert_eq!(
+        "(forall ((x Int)) (! (> x 0) :pattern (x)))",
+        term.to_string()
+    );
+    let mut pool = TermPool::new();
+    let result =
+        Parser::new(&mut pool, config, "(! true :named)".as_bytes()).and_then(Parser::parse_term);
+    assert!(matches!(
+        result,
+        Err(Error::Parser(ParserError::UnexpectedToken(_), _))
+    ));
 }
 
 #[test]
@@ -436,7 +875,7 @@ fn test_declare_fun() {
     );
 
     let [got] = parse_terms(&mut p, "(declare-fun x () Real)", ["x"]);
-    let real_sort = p.add(Term::Sort(Sort::Real));
+    let real_sort = p.add_sort(Sort::Real);
     assert_eq!(p.add(Term::var("x", real_sort)), got);
 }
 
@@ -461,10 +900,130 @@ fn test_declare_sort() {
         (declare-fun x () T)",
         ["x"],
     );
-    let expected_sort = p.add(Term::Sort(Sort::Atom("T".to_owned(), Vec::new())));
+    let expected_sort = p.add_sort(Sort::Atom("T".to_owned(), Vec::new()));
     assert_eq!(p.add(Term::var("x", expected_sort)), got);
 }
 
+#[test]
+fn test_define_sort() {
+    let mut p = TermPool::new();
+    let definitions = "
+        (declare-sort T 1)
+        (define-sort Index () Int)
+        (define-sort MyArr (X) (Array Index X))
+        (define-sort Pair (X Y) (MyArr (T (Array X Y))))
+        (declare-fun a () (MyArr Bool))
+        (declare-fun b () (Pair Real Bool))
+    ";
+    let [a, b] = parse_terms(&mut p, definitions, ["a", "b"]);
+    let [int, real, bool] = [Sort::Int, Sort::Real, Sort::Bool].map(|s| p.add_sort(s));
+    assert_eq!(Sort::Array(int.clone(), bool.clone()), **p.sort(&a));
+
+    let inner = p.add_sort(Sort::Array(real, bool));
+    let t = p.add_sort(Sort::Atom("T".to_owned(), vec![inner]));
+    assert_eq!(Sort::Array(int, t), **p.sort(&b));
+
+    // The parameters of a sort definition are only declared in its body
+    let mut pool = TermPool::new();
+    let parser = Parser::new(
+        &mut pool,
+        ParserConfig::default(),
+        "(define-sort S (X) X) (declare-fun x () X)".as_bytes(),
+    )
+    .unwrap();
+    assert!(matches!(
+        parser.parse_problem(),
+        Err(Error::Parser(ParserError::UndefinedSort(_), _)),
+    ));
+
+    let mut pool = TermPool::new();
+    let parser = Parser::new(
+        &mut pool,
+        ParserConfig::default(),
+        "(define-sort S (X) X) (declare-fun x () (S Int Int))".as_bytes(),
+    )
+    .unwrap();
+    assert!(matches!(
+        parser.parse_problem(),
+        Err(Error::Parser(ParserError::WrongNumberOfArgs(_, 2), _)),
+    ));
+}
+
+#[test]
+fn test_sort_error_messages() {
+    let definitions = "
+        (declare-sort Pair 2)
+        (declare-fun f (Int (Pair Bool Int) Real) Bool)
+        (declare-fun a () (Array Int (Pair Bool Int)))
+        (declare-fun p () (Pair Bool Int))
+        (define-fun g ((x Int)) Bool (> x 0))
+    ";
+    let sort_error = |term: &str| {
+        let mut pool = TermPool::new();
+        let (_, _, mut parser) =
+            Parser::new(&mut pool, ParserConfig::default(), definitions.as_bytes())
+                .and_then(Parser::parse_problem)
+                .expect(ERROR_MESSAGE);
+        match parser.parse_term(term.as_bytes()) {
+            Err(Error::Parser(ParserError::SortError(e), _)) => e.to_string(),
+            other => panic!("expected sort error, got {:?}", other),
+        }
+    };
+
+    // The first line of the message describes the argument with the wrong sort
+    let cases = [
+        ("(f 1 p 2)", "expected 'Real', got 'Int' in argument 3 of 'f'"),
+        (
+            "(f 1 a 2.0)",
+            "expected '(Pair Bool Int)', got '(Array Int (Pair Bool Int))' in argument 2 of 'f'",
+        ),
+        (
+            "(select p 1)",
+            "expected '(par (X) (Array Int X))', got '(Pair Bool Int)' in argument 1 of 'select'",
+        ),
+        (
+            "(store p 1 p)",
+            "expected '(Array Int (Pair Bool Int))', got '(Pair Bool Int)' in argument 1 of 'store'",
+        ),
+        (
+            "(store a 1 1)",
+            "expected '(Pair Bool Int)', got 'Int' in argument 3 of 'store'",
+        ),
+        (
+            "(ite true 1 2.0)",
+            "expected 'Int', got 'Real' in argument 3 of 'ite'",
+        ),
+        ("(+ 1 2 true)", "expected 'Int', got 'Bool' in argument 3 of '+'"),
+        (
+            "(< 1 p)",
+            "expected 'Int' or 'Real', got '(Pair Bool Int)' in argument 2 of '<'",
+        ),
+    ];
+    for (term, expected) in cases {
+        assert_eq!(Some(expected), sort_error(term).lines().next());
+    }
+
+    // The following lines show the rank of the function, if it has one, and the sorts of all
+    // arguments
+    assert_eq!(
+        "expected 'Real', got 'Int' in argument 3 of 'f'\n  \
+        function rank:  (Int (Pair Bool Int) Real) Bool\n  \
+        argument sorts: (Int (Pair Bool Int) Int)",
+        sort_error("(f 1 p 2)"),
+    );
+    assert_eq!(
+        "expected 'Int', got 'Bool' in argument 3 of '+'\n  \
+        argument sorts: (Int Int Bool)",
+        sort_error("(+ 1 2 true)"),
+    );
+    assert_eq!(
+        "expected 'Int', got 'Bool' in argument 1 of 'g'\n  \
+        function rank:  (Int) Bool\n  \
+        argument sorts: (Bool)",
+        sort_error("(g true)"),
+    );
+}
+
 #[test]
 fn test_define_fun() {
     let mut p = TermPool::new();
@@ -488,6 +1047,197 @@ fn test_define_fun() {
     assert_eq!(expected, got);
 }
 
+#[test]
+fn test_problem_commands() {
+    let mut p = TermPool::new();
+    let problem = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (declare-fun r () Bool)
+        (assert (or p q))
+        (check-sat-assuming ((not p) r))
+        (get-proof)
+        (exit)
+        (assert q)
+        this is not valid SMT-LIB, but it should be ignored
+    ";
+    let parser = Parser::new(&mut p, ParserConfig::default(), problem.as_bytes()).unwrap();
+    let (_, premises, mut parser) = parser.parse_problem().unwrap();
+
+    let expected: AHashSet<_> = ["(or p q)", "(not p)", "r"]
+        .iter()
+        .map(|s| parser.parse_term(s.as_bytes()).unwrap())
+        .collect();
+    assert_eq!(expected, premises);
+
+    // The assumptions in `check-sat-assuming` must be boolean
+    let problem = "(declare-fun x () Int) (check-sat-assuming (x))";
+    let parser = Parser::new(&mut p, ParserConfig::default(), problem.as_bytes()).unwrap();
+    assert!(matches!(
+        parser.parse_problem(),
+        Err(Error::Parser(ParserError::SortError(_), _))
+    ));
+}
+
+#[test]
+fn test_push_pop() {
+    let mut p = TermPool::new();
+    let problem = "
+        (declare-fun p () Bool)
+        (assert p)
+        (push 1)
+        (declare-fun x () Int)
+        (define-fun f () Bool (> x 0))
+        (assert f)
+        (pop 1)
+        (push 2)
+        (declare-fun x () Bool)
+        (define-sort S () Int)
+        (assert (and p x))
+        (pop)
+        (declare-fun y () Int)
+        (assert (= y 0))
+        (push)
+        (assert false)
+    ";
+    let parser = Parser::new(&mut p, ParserConfig::default(), problem.as_bytes()).unwrap();
+    let (prelude, premises, mut parser) = parser.parse_problem().unwrap();
+
+    let expected: AHashSet<_> = ["p", "(= y 0)", "false"]
+        .iter()
+        .map(|s| parser.parse_term(s.as_bytes()).unwrap())
+        .collect();
+    assert_eq!(expected, premises);
+    let declared: Vec<_> = (prelude.function_declarations.iter())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(declared, ["p", "y"]);
+
+    // The declarations and definitions in popped levels are no longer visible
+    for term in ["x", "f"] {
+        assert!(matches!(
+            parser.parse_term(term.as_bytes()),
+            Err(Error::Parser(ParserError::UndefinedIden(..), _))
+        ));
+    }
+    assert!(matches!(
+        parser.parse_problem("(declare-fun z () S)".as_bytes()),
+        Err(Error::Parser(ParserError::UndefinedSort(_), _))
+    ));
+
+    let problem = "(push 2) (pop 1) (pop 2)";
+    let parser = Parser::new(&mut p, ParserConfig::default(), problem.as_bytes()).unwrap();
+    assert!(matches!(
+        parser.parse_problem(),
+        Err(Error::Parser(ParserError::PopTooManyLevels(2, 1), _))
+    ));
+
+    // Pushing many levels at once doesn't save the state once per level
+    let problem = "
+        (declare-fun p () Bool)
+        (push 4294967295)
+        (declare-fun q () Int)
+        (pop 4294967294)
+        (declare-fun q () Bool)
+        (assert q)
+        (pop 1)
+        (push 4294967295)
+        (assert p)
+    ";
+    let parser = Parser::new(&mut p, ParserConfig::default(), problem.as_bytes()).unwrap();
+    let (_, premises, mut parser) = parser.parse_problem().unwrap();
+    let expected: AHashSet<_> = [parser.parse_term(b"p").unwrap()].into_iter().collect();
+    assert_eq!(expected, premises);
+}
+
+#[test]
+fn test_multiple_problems() {
+    let shared = "
+        (set-logic QF_UF)
+        (declare-sort U 0)
+        (declare-fun p () Bool)
+        (assert p)
+    ";
+    let instance = "
+        (declare-fun a () U)
+        (declare-fun b () U)
+        (assert (= a b))
+    ";
+    let proof = "
+        (assume h1 p)
+        (assume h2 (= a b))
+    ";
+    let (prelude, proof, _) = parse_instance_with_problems(
+        vec![shared.as_bytes(), instance.as_bytes()],
+        proof.as_bytes(),
+        ParserConfig::default(),
+        1,
+    )
+    .unwrap();
+    assert_eq!(prelude.logic.as_deref(), Some("QF_UF"));
+    assert_eq!(prelude.sort_declarations.len(), 1);
+    assert_eq!(prelude.function_declarations.len(), 3);
+
+    let expected: AHashSet<_> = proof
+        .commands
+        .iter()
+        .map(|c| c.clause()[0].clone())
+        .collect();
+    assert_eq!(expected, proof.premises);
+    assert_eq!(expected.len(), 2);
+
+    // The problems are parsed in order, so an input can't use the declarations of later ones
+    let result = parse_instance_with_problems(
+        vec![instance.as_bytes(), shared.as_bytes()],
+        "".as_bytes(),
+        ParserConfig::default(),
+        1,
+    );
+    assert!(matches!(
+        result,
+        Err(Error::Parser(ParserError::UndefinedSort(_), _))
+    ));
+}
+
+#[test]
+fn test_standalone_proof() {
+    let proof = "
+        (declare-sort U 0)
+        (declare-fun f (U) U)
+        (declare-const a U)
+        (define-sort V () U)
+        (declare-const b V)
+        (assume h1 (= (f a) b))
+        (anchor :step t2)
+        (assume t2.h1 (= a b))
+        (step t2 (cl (not (= a b)) (= (f a) b)) :rule subproof)
+    ";
+    let (prelude, proof, _) =
+        parse_instance_with_problems(Vec::new(), proof.as_bytes(), ParserConfig::default(), 1)
+            .unwrap();
+    assert_eq!(prelude.sort_declarations, [("U".to_owned(), 0)]);
+    let declared: Vec<_> = (prelude.function_declarations.iter())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(declared, ["f", "a", "b"]);
+
+    // Only the top-level `assume` commands are taken as premises
+    assert_eq!(proof.premises.len(), 1);
+    assert!(proof.premises.contains(&proof.commands[0].clause()[0]));
+
+    // Without the declarations, the proof can't be parsed
+    let result = parse_instance_with_problems(
+        Vec::new(),
+        "(assume h1 p)".as_bytes(),
+        ParserConfig::default(),
+        1,
+    );
+    assert!(matches!(
+        result,
+        Err(Error::Parser(ParserError::UndefinedIden(..), _))
+    ));
+}
+
 #[test]
 fn test_step() {
     let mut p = TermPool::new();
@@ -626,3 +1376,1002 @@ fn test_premises_in_subproofs() {
         })
     );
 }
+
+#[test]
+fn test_anchor_syntax() {
+    let problem = "(declare-fun a () Int)";
+    let parse = |proof: &str| {
+        parse_instance(problem.as_bytes(), proof.as_bytes(), Default::default())
+            .map(|(_, proof, pool)| (proof, pool))
+    };
+
+    // Assignments can be written with or without the sort of the variable
+    for args in ["((:= x a) (y Int))", "((:= (x Int) a) (y Int))"] {
+        let proof = format!(
+            "(anchor :step t1 :args {})
+            (step t1.t1 (cl (= x y)) :rule hole)
+            (step t1 (cl) :rule hole)",
+            args
+        );
+        let (proof, mut pool) = parse(&proof).unwrap();
+        let subproof = match &proof.commands[0] {
+            ProofCommand::Subproof(s) => s,
+            _ => panic!(),
+        };
+        let [a] = parse_terms(&mut pool, problem, ["a"]);
+        assert_eq!(subproof.assignment_args, [("x".to_owned(), a)]);
+        assert_eq!(subproof.variable_args.len(), 1);
+        assert_eq!(subproof.variable_args[0].0, "y");
+    }
+
+    // In the current syntax, the sort of the variable must match the sort of its value
+    let proof = "(anchor :step t1 :args ((:= (x Bool) a))) (step t1 (cl) :rule hole)";
+    assert!(matches!(
+        parse(proof),
+        Err(Error::Parser(ParserError::SortError(_), (1, 38)))
+    ));
+}
+
+#[test]
+fn test_parsed_problem() {
+    let problem = "
+        (set-logic QF_LRA)
+        (declare-fun p () Bool)
+        (declare-fun x () Real)
+        (assert (or p (> x 1)))
+    ";
+    let mut problem = ParsedProblem::new(problem.as_bytes(), ParserConfig::default()).unwrap();
+    let num_problem_terms = problem.pool().stats().num_terms;
+
+    let first = "
+        (define-fun f () Real (+ x 2))
+        (assume h1 (or p (> x 1)))
+        (step t2 (cl (not p) (> f 1)) :rule hole)
+    ";
+    let (_, first, first_pool) = problem.parse_proof(first.as_bytes(), 1).unwrap();
+    let second = "(assume h1 (or p (> x 1))) (step t2 (cl (not p)) :rule hole)";
+    let (_, second, mut second_pool) = problem.parse_proof(second.as_bytes(), 1).unwrap();
+
+    // The problem terms are shared by both proofs, and the problem's logic is used when parsing
+    // them, so `1` is parsed as a real
+    assert_eq!(first.premises, second.premises);
+    assert_eq!(first.commands[0].clause(), second.commands[0].clause());
+    assert_eq!(num_problem_terms, second_pool.stats().num_shared_terms);
+    assert_eq!(num_problem_terms, first_pool.stats().num_shared_terms);
+
+    // Terms and definitions added by one proof are not visible to the other
+    let not_p = first.commands[1].clause()[0].clone();
+    assert_ne!(second_pool.add(not_p.as_ref().clone()), not_p);
+    let third = "(step t1 (cl (> f 1)) :rule hole)";
+    assert!(matches!(
+        problem.parse_proof(third.as_bytes(), 1),
+        Err(Error::Parser(ParserError::UndefinedIden(..), _)),
+    ));
+}
+
+#[test]
+fn test_parser_config() {
+    let problem = "(set-logic QF_LRA) (declare-fun x () Real) (declare-fun n () Int)";
+    let parse = |config, proof: &str| parse_instance(problem.as_bytes(), proof.as_bytes(), config);
+    let is_real = |config| {
+        let (_, proof, _) = parse(config, "(step t1 (cl (= 1 1)) :rule hole)").unwrap();
+        let one = proof.commands[0].clause()[0].clone();
+        matches!(one.as_ref(), Term::Op(_, args) if matches!(args[0].as_ref(), Term::Terminal(Terminal::Real(_))))
+    };
+
+    // By default, the logic decides whether numerals are reals, but the config can override it
+    assert!(is_real(ParserConfig::default()));
+    let config = ParserConfig {
+        interpret_integers_as_reals: Some(false),
+        ..Default::default()
+    };
+    assert!(!is_real(config));
+
+    // In strict mode, unknown attributes in proof commands are rejected
+    let proof = "(step t1 (cl (= x x)) :rule hole :unknown 1)";
+    assert!(parse(ParserConfig::default(), proof).is_ok());
+    let config = ParserConfig { strict: true, ..Default::default() };
+    assert!(matches!(
+        parse(config, proof),
+        Err(Error::Parser(ParserError::UnknownAttribute(a), _)) if a == "unknown",
+    ));
+
+    // Int/Real subtyping is only allowed when enabled
+    let proof = "(step t1 (cl (= (+ x n) x)) :rule hole)";
+    assert!(parse(ParserConfig::default(), proof).is_err());
+    let config = ParserConfig {
+        allow_int_real_subtyping: true,
+        ..Default::default()
+    };
+    assert!(parse(config, proof).is_ok());
+}
+
+#[test]
+fn test_int_real_coercion() {
+    let definitions = "(declare-fun x () Real) (declare-fun n () Int)";
+    let parse = |coerce_int_to_real, term: &str| {
+        let mut pool = TermPool::new();
+        let config = ParserConfig {
+            coerce_int_to_real,
+            ..Default::default()
+        };
+        let (_, _, mut parser) = Parser::new(&mut pool, config, definitions.as_bytes())
+            .and_then(Parser::parse_problem)
+            .expect(ERROR_MESSAGE);
+        parser.parse_term(term.as_bytes()).map(|t| t.to_string())
+    };
+
+    let cases = [
+        ("(+ x 1)", "(+ x 1.0)"),
+        ("(+ x n (- 2))", "(+ x (to_real n) (- 2.0))"),
+        ("(< n x)", "(< (to_real n) x)"),
+        ("(= x (* 2 n))", "(= x (to_real (* 2 n)))"),
+        ("(/ n 2)", "(/ (to_real n) 2.0)"),
+        ("(+ n 1)", "(+ n 1)"),
+        ("(= n 1)", "(= n 1)"),
+    ];
+    for (term, expected) in cases {
+        assert_eq!(expected, parse(true, term).unwrap());
+    }
+
+    // Mixed terms are still rejected if coercion is disabled, and other sorts are never coerced
+    assert!(matches!(
+        parse(false, "(+ x 1)"),
+        Err(Error::Parser(ParserError::SortError(_), _))
+    ));
+    assert!(matches!(
+        parse(true, "(+ x true)"),
+        Err(Error::Parser(ParserError::SortError(_), _))
+    ));
+}
+
+#[test]
+fn test_error_recovery() {
+    let problem = "(declare-fun p () Bool) (declare-fun q () Bool)";
+    let config = ParserConfig {
+        recover_from_errors: true,
+        ..Default::default()
+    };
+    let parse = |proof: &str| parse_instance(problem.as_bytes(), proof.as_bytes(), config);
+
+    // Each malformed command is reported, and the commands after it are still parsed
+    let proof = "
+        (assume h1 (and p r))
+        (step t2 (cl p) :rule hole)
+        (step t3 (cl (not q)) :rule)
+        (anchor :step t5 :args ((x Int)))
+        (step t4 (cl (= x (+ x 1 ))) :rule hole)
+        (step t5 (cl (foo x)) :rule hole)
+        (step t5 (cl p) :rule hole)
+    ";
+    let errors = match parse(proof) {
+        Err(Error::ParserErrors(errors)) => errors,
+        other => panic!("expected many errors, got {:?}", other.map(|_| ())),
+    };
+    let errors: Vec<_> = errors.into_iter().map(|(e, pos)| (e, pos.0)).collect();
+    assert!(matches!(
+        errors.as_slice(),
+        [
+            (ParserError::UndefinedIden(..), 2),
+            (ParserError::UnexpectedToken(Token::CloseParen), 4),
+            (ParserError::UndefinedIden(..), 7),
+        ]
+    ));
+
+    // The symbols bound by a malformed anchor don't leak into the following commands
+    let proof = "
+        (anchor :step t3 :args ((x Bool) (y)))
+        (step t1 (cl x) :rule hole)
+    ";
+    let errors = match parse(proof) {
+        Err(Error::ParserErrors(errors)) => errors,
+        other => panic!("expected many errors, got {:?}", other.map(|_| ())),
+    };
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[1].0, ParserError::UndefinedIden(..)));
+
+    // A single error is reported as usual, and valid proofs are not affected
+    let proof = "(step t1 (cl r) :rule hole)";
+    assert!(matches!(
+        parse(proof),
+        Err(Error::Parser(ParserError::UndefinedIden(..), _))
+    ));
+    assert!(parse("(step t1 (cl p q) :rule hole)").is_ok());
+}
+
+#[test]
+fn test_command_spans() {
+    let problem = "(declare-fun p () Bool)";
+    let proof = "(assume h1 p)
+(anchor :step t3)
+  (step t3.t1 (cl p)
+    :rule hole)
+(step t3 (cl p) :rule subproof)
+(step t4 (cl) :rule hole)";
+    let (_, proof, _) = parse_instance(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        ParserConfig::default(),
+    )
+    .unwrap();
+    let spans: Vec<_> = proof.spans.iter().map(|s| (s.start, s.end)).collect();
+    assert_eq!(
+        spans,
+        [
+            ((1, 1), (1, 13)),
+            ((2, 1), (2, 17)),
+            ((3, 3), (4, 15)),
+            ((5, 1), (5, 31)),
+            ((6, 1), (6, 25)),
+        ]
+    );
+
+    // Checking errors report the position of the step
+    let result = crate::check(
+        problem.as_bytes(),
+        "(step t1 (cl p) :rule hole)\n(step t2 (cl) :rule false)".as_bytes(),
+        Default::default(),
+    );
+    match result {
+        Err(e @ Error::Checker { .. }) => {
+            assert!(e.to_string().contains("(on line 2, column 1)"));
+        }
+        other => panic!("expected checker error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_progress() {
+    use std::sync::{Arc, Mutex};
+
+    let mut pool = TermPool::new();
+    let (_, _, mut parser) = Parser::new(
+        &mut pool,
+        ParserConfig::default(),
+        "(declare-fun p () Bool)".as_bytes(),
+    )
+    .and_then(Parser::parse_problem)
+    .unwrap();
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    parser.on_progress(2, move |p| sink.lock().unwrap().push(*p));
+    let proof = "
+        (assume h1 p)
+        (anchor :step t3)
+        (step t3.t1 (cl p (not p)) :rule hole)
+        (step t3 (cl p) :rule hole)
+        (step t4 (cl) :rule hole)
+    ";
+    parser.parse_proof(proof.as_bytes()).unwrap();
+
+    let progress = parser.progress();
+    assert_eq!(
+        (progress.commands, progress.steps, progress.literals),
+        (5, 3, 3)
+    );
+    assert_eq!(progress.bytes_read, proof.len());
+    assert!(progress.terms >= 2);
+    assert!(progress.memory_estimate() > 0);
+
+    // The callback is called every two commands, and once more at the end
+    let commands: Vec<_> = reports.lock().unwrap().iter().map(|p| p.commands).collect();
+    assert_eq!(commands, [2, 4, 5]);
+}
+
+#[test]
+fn test_lfsc_proof() {
+    let problem = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (assert (or p q))
+        (assert (not p))
+        (assert (not q))
+    ";
+    let proof = "(check
+        (% a1 (holds (clc (pos p) (clc (pos q) cln)))
+        (% a2 (holds (clc (neg p) cln))
+        (% a3 (holds (clc (neg q) cln))
+        (: (holds cln)
+        (Q _ _ a3 (R _ _ a1 a2 p) q))))))
+    ";
+    let (_, parsed, _) = parse_instance(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        ParserConfig::default(),
+    )
+    .unwrap();
+    let rules: Vec<_> = parsed
+        .commands
+        .iter()
+        .map(|c| match c {
+            ProofCommand::Assume { .. } => "assume",
+            ProofCommand::Step(s) => s.rule.as_str(),
+            ProofCommand::Subproof(_) => panic!(),
+        })
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            "assume",
+            "or",
+            "assume",
+            "assume",
+            "resolution",
+            "resolution"
+        ]
+    );
+    assert_eq!(parsed.commands[4].clause().len(), 1);
+    assert!(parsed.commands[5].clause().is_empty());
+
+    let result = crate::check(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        crate::CarcaraOptions::default(),
+    );
+    assert!(matches!(result, Ok(false)));
+
+    let errors = [
+        (
+            "(define __t1 p)",
+            ParserError::UnsupportedLfsc("define".into()),
+        ),
+        (
+            "(check (% a (holds cln) a))",
+            ParserError::UnsupportedLfsc("(holds cln)".into()),
+        ),
+        (
+            "(check (trust f))",
+            ParserError::UnsupportedLfsc("trust".into()),
+        ),
+        (
+            "(check a1)",
+            ParserError::UndefinedStepIndex("a1".into(), Vec::new()),
+        ),
+    ];
+    for (proof, expected) in errors {
+        let got = parse_instance(
+            problem.as_bytes(),
+            proof.as_bytes(),
+            ParserConfig::default(),
+        );
+        match got {
+            Err(Error::Parser(e, _)) => assert_eq!(format!("{}", expected), format!("{}", e)),
+            other => panic!("expected parser error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
+
+#[test]
+fn test_premise_error_positions() {
+    // Errors about undefined premises point at the premise itself, not at the start of the step
+    let cases = [
+        (
+            "(step t1 (cl) :rule hole)\n(step t2 (cl) :rule hole :premises (t1 t9))",
+            (2, 40),
+        ),
+        ("(step t1 (cl) :rule hole\n  :premises (t0))", (2, 14)),
+        (
+            "(anchor :step t1)\n(step t1 (cl) :rule subproof :discharge (h1))",
+            (2, 42),
+        ),
+    ];
+    for (proof, expected) in cases {
+        let mut pool = TermPool::new();
+        let got = Parser::new(&mut pool, ParserConfig::default(), proof.as_bytes())
+            .and_then(Parser::parse_proof);
+        match got {
+            Err(Error::Parser(ParserError::UndefinedStepIndex(..), pos)) => {
+                assert_eq!(expected, pos);
+            }
+            other => panic!("expected undefined step error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
+
+#[test]
+fn test_subproof_premise_visibility() {
+    let errors = [
+        (
+            "(anchor :step t1)
+            (step t1.t1 (cl) :rule rule-name)
+            (step t1 (cl) :rule rule-name)
+            (step t2 (cl) :rule rule-name :premises (t1.t1))",
+            ParserError::SubproofLocalStep("t1.t1".into(), "t1".into()),
+        ),
+        (
+            "(anchor :step t1)
+            (anchor :step t1.t1)
+            (step t1.t1.t1 (cl) :rule rule-name)
+            (step t1.t1 (cl) :rule rule-name)
+            (step t1.t2 (cl) :rule rule-name :premises (t1.t1.t1))
+            (step t1 (cl) :rule rule-name)",
+            ParserError::SubproofLocalStep("t1.t1.t1".into(), "t1.t1".into()),
+        ),
+        (
+            "(anchor :step t1)
+            (step t1.t1 (cl) :rule rule-name :premises (t1))
+            (step t1 (cl) :rule rule-name)",
+            ParserError::UndefinedStepIndex("t1".into(), Vec::new()),
+        ),
+        (
+            "(anchor :step t1)
+            (anchor :step t1)
+            (step t1.t1 (cl) :rule rule-name)
+            (step t1 (cl) :rule rule-name)
+            (step t1 (cl) :rule rule-name)",
+            ParserError::RepeatedStepIndex("t1".into()),
+        ),
+        (
+            "(step t1 (cl) :rule rule-name)
+            (anchor :step t1)
+            (step t1.t1 (cl) :rule rule-name)
+            (step t1 (cl) :rule rule-name)",
+            ParserError::RepeatedStepIndex("t1".into()),
+        ),
+        (
+            "(anchor :step t1)
+            (step t1.t1 (cl) :rule rule-name)
+            (step t1 (cl) :rule rule-name)
+            (anchor :step t2)
+            (step t2.t1 (cl) :rule rule-name :premises (t1.t1))
+            (step t2 (cl) :rule rule-name)",
+            ParserError::SubproofLocalStep("t1.t1".into(), "t1".into()),
+        ),
+    ];
+    for (proof, expected) in errors {
+        let mut pool = TermPool::new();
+        let got = Parser::new(&mut pool, ParserConfig::default(), proof.as_bytes())
+            .and_then(Parser::parse_proof);
+        match got {
+            Err(Error::Parser(e, _)) => assert_eq!(format!("{}", expected), format!("{}", e)),
+            other => panic!("expected parser error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    // Commands in enclosing subproofs, as well as the end steps of closed subproofs, are visible
+    let mut pool = TermPool::new();
+    parse_proof(
+        &mut pool,
+        "(assume h1 true)
+        (anchor :step t2)
+        (anchor :step t2.t1)
+        (step t2.t1.t1 (cl) :rule rule-name :premises (h1))
+        (step t2.t1 (cl) :rule rule-name)
+        (step t2.t2 (cl) :rule rule-name :premises (h1 t2.t1))
+        (step t2 (cl) :rule rule-name)
+        (step t3 (cl) :rule rule-name :premises (t2))",
+    );
+}
+
+#[test]
+fn test_step_ids_in_nested_scopes() {
+    fn subproof(command: &ProofCommand) -> &[ProofCommand] {
+        match command {
+            ProofCommand::Subproof(s) => &s.commands,
+            _ => panic!("expected subproof"),
+        }
+    }
+
+    fn ids(commands: &[ProofCommand]) -> Vec<&str> {
+        commands.iter().map(ProofCommand::id).collect()
+    }
+
+    fn premises(command: &ProofCommand) -> &[(usize, usize)] {
+        match command {
+            ProofCommand::Step(s) => &s.premises,
+            _ => panic!("expected step"),
+        }
+    }
+
+    let mut p = TermPool::new();
+
+    // Commands in a subproof may shadow commands from the enclosing scopes, and premises refer to
+    // the innermost command with that id
+    let proof = parse_proof(
+        &mut p,
+        "(assume h1 true)
+        (anchor :step t2)
+        (assume h1 false)
+        (step t2.t1 (cl) :rule rule-name :premises (h1))
+        (step t2 (cl) :rule rule-name)
+        (step t3 (cl) :rule rule-name :premises (h1))",
+    );
+    assert_eq!(premises(&subproof(&proof.commands[1])[1]), [(1, 0)]);
+    assert_eq!(premises(&proof.commands[2]), [(0, 0)]);
+
+    // A step inside a nested subproof that has the same id as the end step of an enclosing
+    // subproof does not close it
+    let proof = parse_proof(
+        &mut p,
+        "(anchor :step t1)
+        (anchor :step t1.t1)
+        (step t1 (cl) :rule rule-name)
+        (step t1.t1 (cl) :rule rule-name)
+        (step t1.t2 (cl) :rule rule-name)
+        (step t1 (cl) :rule rule-name)",
+    );
+    assert_eq!(ids(&proof.commands), ["t1"]);
+    let outer = subproof(&proof.commands[0]);
+    assert_eq!(ids(outer), ["t1.t1", "t1.t2", "t1"]);
+    assert_eq!(ids(subproof(&outer[0])), ["t1", "t1.t1"]);
+
+    // Distinct subproofs may reuse the same ids, including for their nested subproofs
+    let proof = parse_proof(
+        &mut p,
+        "(anchor :step t1)
+        (anchor :step a)
+        (step a.t1 (cl) :rule rule-name)
+        (step a (cl) :rule rule-name)
+        (step t1 (cl) :rule rule-name :premises (a))
+        (anchor :step t2)
+        (anchor :step a)
+        (step a.t1 (cl) :rule rule-name)
+        (step a.t2 (cl) :rule rule-name)
+        (step a (cl) :rule rule-name)
+        (step t2 (cl) :rule rule-name :premises (a))",
+    );
+    assert_eq!(ids(subproof(&proof.commands[0])), ["a", "t1"]);
+    let second = subproof(&proof.commands[1]);
+    assert_eq!(ids(second), ["a", "t2"]);
+    assert_eq!(ids(subproof(&second[0])), ["a.t1", "a.t2", "a"]);
+
+    // Ids still need to be unique in each scope
+    let errors = [
+        "(anchor :step t1)
+        (step t1.t1 (cl) :rule rule-name)
+        (step t1.t1 (cl) :rule rule-name)
+        (step t1 (cl) :rule rule-name)",
+        "(anchor :step t1)
+        (anchor :step t1.t1)
+        (step t1.t1.t1 (cl) :rule rule-name)
+        (step t1.t1 (cl) :rule rule-name)
+        (step t1.t1 (cl) :rule rule-name)
+        (step t1 (cl) :rule rule-name)",
+    ];
+    for proof in errors {
+        let got = Parser::new(&mut p, ParserConfig::default(), proof.as_bytes())
+            .and_then(Parser::parse_proof);
+        assert!(matches!(
+            got,
+            Err(Error::Parser(ParserError::RepeatedStepIndex(_), _))
+        ));
+    }
+}
+
+#[test]
+fn test_operator_signatures() {
+    fn only_int(args: SortRuleArgs) -> Result<(), ParserError> {
+        for s in args.sorts() {
+            SortError::assert_eq(&Sort::Int, s)?;
+        }
+        Ok(())
+    }
+
+    let mut pool = TermPool::new();
+    let (_, _, mut parser) = Parser::new(&mut pool, ParserConfig::default(), "".as_bytes())
+        .and_then(Parser::parse_problem)
+        .unwrap();
+    let implies = *parser.signatures_mut().get("=>").unwrap();
+    parser.signatures_mut().register("implies", implies);
+    parser.signatures_mut().register(
+        "<",
+        OperatorSignature {
+            operator: Operator::LessThan,
+            arity: Arity::Exactly(2),
+            sort_rule: only_int,
+        },
+    );
+
+    let mut parse = |input: &'static str| parser.parse_term(input.as_bytes());
+    let term = parse("(implies false true)").unwrap();
+    assert_eq!(term.to_string(), "(=> false true)");
+    assert!(parse("(< 1 2)").is_ok());
+    assert!(matches!(
+        parse("(< 1 2 3)"),
+        Err(Error::Parser(ParserError::WrongNumberOfArgs(_, 3), _))
+    ));
+    assert!(matches!(
+        parse("(< 1.0 2.0)"),
+        Err(Error::Parser(ParserError::SortError(_), _))
+    ));
+}
+
+#[test]
+fn test_proof_metadata() {
+    let problem = "(declare-fun p () Bool)";
+    let proof = r#"
+        (set-info :name "cvc5")
+        (set-info :version |1.0.5|)
+        (set-info :options (--dump-proofs (tlimit 10)))
+        (assume h1 p)
+        (step t2 (cl) :rule hole :premises (h1))
+    "#;
+    let (_, proof, _) = parse_instance(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        ParserConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(proof.commands.len(), 2);
+    assert_eq!(proof.spans.len(), 2);
+    let expected = [
+        ("name", "cvc5"),
+        ("version", "1.0.5"),
+        ("options", "(--dump-proofs (tlimit 10))"),
+    ];
+    let metadata: Vec<_> = proof
+        .metadata
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(metadata, expected);
+
+    let proof = "(set-info name) (assume h1 p)";
+    assert!(matches!(
+        parse_instance(
+            problem.as_bytes(),
+            proof.as_bytes(),
+            ParserConfig::default()
+        ),
+        Err(Error::Parser(ParserError::UnexpectedToken(_), (1, 11)))
+    ));
+}
+
+#[test]
+fn test_named_assertions() {
+    let problem = "
+        (declare-fun p () Bool)
+        (declare-fun q () Bool)
+        (assert (! p :named a1))
+        (assert (or (! q :named sub) p))
+        (push 1)
+        (assert (! (not q) :named a2))
+        (pop 1)
+        (assert (! (! (not p) :named a3) :named a4))
+    ";
+    let proof = "
+        (assume h1 (or q p))
+        (assume h2 (not p))
+        (assume h3 p)
+        (step t4 (cl) :rule resolution :premises (h2 h3))
+    ";
+    let (_, proof, _) = parse_instance(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        ParserConfig::default(),
+    )
+    .unwrap();
+    let names: Vec<_> = proof
+        .named_assertions
+        .iter()
+        .map(|(name, term)| (name.as_str(), term.to_string()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("a1", "p".to_owned()),
+            ("a3", "(not p)".to_owned()),
+            ("a4", "(not p)".to_owned()),
+        ]
+    );
+    assert_eq!(proof.named_assumes(), [(1, "a3"), (2, "a1")]);
+}
+
+#[test]
+fn test_redeclarations_in_proof() {
+    let problem = "
+        (declare-sort S 0)
+        (declare-fun f (S) Bool)
+        (declare-const a S)
+    ";
+    let parse = |proof: &str, strict: bool| {
+        let config = ParserConfig { strict, ..ParserConfig::default() };
+        parse_instance(problem.as_bytes(), proof.as_bytes(), config)
+    };
+
+    // Declarations that are equal to the ones in the problem are always accepted
+    let proof = "
+        (declare-sort S 0)
+        (declare-fun f (S) Bool)
+        (declare-const a S)
+        (step t1 (cl (f a)) :rule hole)
+    ";
+    assert!(parse(proof, false).is_ok());
+    assert!(parse(proof, true).is_ok());
+
+    // Inconsistent declarations are only rejected in strict mode, and otherwise shadow the
+    // original ones
+    let proof = "(declare-fun f (S S) Bool)\n(step t1 (cl (f a a)) :rule hole)";
+    assert!(parse(proof, false).is_ok());
+    assert!(matches!(
+        parse(proof, true),
+        Err(Error::Parser(ParserError::InconsistentDeclaration(f, _, _), (1, 14))) if f == "f"
+    ));
+
+    let proof = "(declare-sort S 1)";
+    assert!(parse(proof, false).is_ok());
+    assert!(matches!(
+        parse(proof, true),
+        Err(Error::Parser(
+            ParserError::InconsistentSortDeclaration(_, 0, 1),
+            (1, 15)
+        ))
+    ));
+}
+
+#[test]
+fn test_proof_commands_iterator() {
+    let problem = "(declare-fun p () Bool)";
+    let proof = "
+        (assume h1 p)
+        (anchor :step t3)
+        (step t3.t1 (cl p) :rule hole)
+        (step t3.t2 (cl p) :rule hole :premises (t3.t1 h1))
+        (step t3 (cl p) :rule subproof)
+        (step t4 (cl) :rule hole :premises (h1 t3))
+    ";
+    let mut pool = TermPool::new();
+    let (_, _, mut parser) = Parser::new(&mut pool, ParserConfig::default(), problem.as_bytes())
+        .and_then(Parser::parse_problem)
+        .unwrap();
+    let expected = parser.parse_proof(proof.as_bytes()).unwrap();
+    let commands = parser
+        .proof_commands(proof.as_bytes())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(commands.len(), 3);
+    assert!(matches!(commands[1], ProofCommand::Subproof(_)));
+    assert_eq!(expected, commands);
+    match &commands[2] {
+        ProofCommand::Step(s) => assert_eq!(s.premises, [(0, 0), (0, 1)]),
+        _ => panic!(),
+    }
+    assert_eq!(parser.command_spans().len(), 6);
+
+    // The iterator stops after an error, which may only be found at the end of the input
+    let proof = "(assume h1 p) (anchor :step t2) (step t2.t1 (cl p) :rule hole)";
+    let mut commands = parser.proof_commands(proof.as_bytes()).unwrap();
+    assert!(matches!(
+        commands.next(),
+        Some(Ok(ProofCommand::Assume { .. }))
+    ));
+    assert!(matches!(
+        commands.next(),
+        Some(Err(Error::Parser(ParserError::UnclosedSubproof(_), _)))
+    ));
+    assert!(commands.next().is_none());
+}
+
+#[test]
+fn test_preserve_comments() {
+    let problem = "(declare-fun p () Bool) ; not kept";
+    let proof = "; produced by a solver
+        (assume h1 p) ; the premise
+        (step t2 (cl p) :rule hole :premises (h1))";
+    let config = ParserConfig {
+        preserve_comments: true,
+        ..Default::default()
+    };
+    let (_, parsed, _) = parse_instance(problem.as_bytes(), proof.as_bytes(), config).unwrap();
+    let expected = [
+        (" produced by a solver", (1, 1), (1, 23)),
+        (" the premise", (2, 23), (2, 36)),
+    ];
+    let comments: Vec<_> = parsed
+        .comments
+        .iter()
+        .map(|c| (c.text.as_str(), c.span.start, c.span.end))
+        .collect();
+    assert_eq!(comments, expected);
+
+    let (_, parsed, _) =
+        parse_instance(problem.as_bytes(), proof.as_bytes(), Default::default()).unwrap();
+    assert!(parsed.comments.is_empty());
+}
+
+#[test]
+fn test_resource_limits() {
+    let problem = "(declare-fun p () Bool) (declare-fun q () Bool)";
+    let parse = |proof: &str, limits: ResourceLimits| {
+        let config = ParserConfig { limits, ..Default::default() };
+        parse_instance(problem.as_bytes(), proof.as_bytes(), config)
+    };
+    let exceeded = |proof: &str, limits: ResourceLimits| match parse(proof, limits) {
+        Err(Error::Parser(ParserError::ResourceLimitExceeded(limit), pos)) => (limit, pos),
+        other => panic!("expected resource limit error, got {:?}", other.map(|_| ())),
+    };
+
+    let proof = "(assume h1 (not (not p)))\n(step t2 (cl p) :rule hole)";
+    assert!(parse(proof, ResourceLimits::default()).is_ok());
+
+    // The parentheses of the command are also counted
+    let limits = ResourceLimits {
+        max_nesting_depth: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(
+        exceeded(proof, limits),
+        (ResourceLimit::NestingDepth(2), (1, 17))
+    );
+    let limits = ResourceLimits {
+        max_nesting_depth: Some(3),
+        ..Default::default()
+    };
+    assert!(parse(proof, limits).is_ok());
+
+    let limits = ResourceLimits {
+        max_commands: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(
+        exceeded(proof, limits),
+        (ResourceLimit::Commands(1), (2, 2))
+    );
+    let limits = ResourceLimits {
+        max_commands: Some(2),
+        ..Default::default()
+    };
+    assert!(parse(proof, limits).is_ok());
+
+    // The limit on the length of tokens also applies to the problem
+    let limits = ResourceLimits {
+        max_token_length: Some(11),
+        ..Default::default()
+    };
+    assert!(parse(proof, limits).is_ok());
+    assert_eq!(
+        exceeded("(assume a_long_step_id p)", limits),
+        (ResourceLimit::TokenLength(11), (1, 9))
+    );
+    let limits = ResourceLimits {
+        max_token_length: Some(10),
+        ..Default::default()
+    };
+    assert_eq!(
+        exceeded(proof, limits),
+        (ResourceLimit::TokenLength(10), (1, 2))
+    );
+
+    // Resource limits stop the parser even when it is recovering from errors
+    let config = ParserConfig {
+        recover_from_errors: true,
+        limits: ResourceLimits {
+            max_commands: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let proof = "(assume h1 r)\n(assume h2 p)\n(assume h3 q)";
+    match parse_instance(problem.as_bytes(), proof.as_bytes(), config) {
+        Err(Error::ParserErrors(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert!(matches!(errors[1].0, ParserError::ResourceLimitExceeded(_)));
+        }
+        other => panic!("expected many errors, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_did_you_mean() {
+    let problem = "(declare-fun flag_a () Bool) (declare-fun flag_b () Bool)
+        (define-fun flag_c () Bool true)";
+    let parse = |proof: &str| {
+        let config = ParserConfig::default();
+        match parse_instance(problem.as_bytes(), proof.as_bytes(), config) {
+            Err(Error::Parser(e, _)) => e,
+            other => panic!("expected parser error, got {:?}", other.map(|_| ())),
+        }
+    };
+
+    let err = parse("(assume h1 flag_d)");
+    assert!(matches!(&err, ParserError::UndefinedIden(_, s) if s.len() == 3));
+    assert_eq!(
+        err.to_string(),
+        "identifier 'flag_d' is not defined; did you mean 'flag_a', 'flag_b' or 'flag_c'?"
+    );
+    assert_eq!(
+        parse("(assume h1 other)").to_string(),
+        "identifier 'other' is not defined"
+    );
+
+    let err = parse("(assume h1 flag_a)\n(step t2 (cl) :rule hole :premises (h2))");
+    assert_eq!(
+        err.to_string(),
+        "step id 'h2' is not defined; did you mean 'h1'?"
+    );
+}
+
+#[test]
+fn test_repair_hints() {
+    let problem = "(declare-fun a () Bool)";
+    let parse = |proof: &str| {
+        let config = ParserConfig::default();
+        match parse_instance(problem.as_bytes(), proof.as_bytes(), config) {
+            Err(Error::Parser(e, pos)) => (e.to_string(), pos),
+            other => panic!("expected parser error, got {:?}", other.map(|_| ())),
+        }
+    };
+
+    assert_eq!(
+        parse("(assume h1 a)\n(step t2 (cl a) :rule hole :premise (h1))"),
+        (
+            "unexpected token: ':premise'; did you mean ':premises'?".to_owned(),
+            (2, 28)
+        )
+    );
+    assert_eq!(
+        parse("(anchor :step t1 :arg ((x Bool)))").0,
+        "unexpected token: ':arg'; did you mean ':args'?"
+    );
+    assert_eq!(
+        parse("(step t1 (cl a) :rules hole)").0,
+        "unexpected token: ':rules'; did you mean ':rule'?"
+    );
+    assert_eq!(
+        parse("(step t1 (cl a) :premises (t1) :rule hole)").0,
+        "unexpected token: ':premises'; expected ':rule' after the clause of the step"
+    );
+    assert_eq!(
+        parse("(step t1 (cl (not a))) :rule hole)").0,
+        "unexpected token: ')'; expected ':rule' after the clause of the step"
+    );
+    assert_eq!(
+        parse("(assume h1 a))\n(step t2 (cl a) :rule hole)"),
+        (
+            "unexpected token: ')'; the previous command may have an extra ')'".to_owned(),
+            (1, 14)
+        )
+    );
+    assert_eq!(
+        parse("(assert h1 a)").0,
+        "unexpected token: 'assert'; did you mean 'assume'?"
+    );
+
+    // Unknown attributes that are not near misses are still ignored
+    let proof = "(assume h1 a)\n(step t2 (cl a) :rule hole :premises (h1) :comment \"x\")";
+    parse_instance(
+        problem.as_bytes(),
+        proof.as_bytes(),
+        ParserConfig::default(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_cvc5_producer() {
+    let problem = "(declare-fun p (Int) Bool)";
+    let proof = "
+        (assume h1 (! (p 0) :skolem_id 1))
+        (step t2 (cl (p (witness ((x Int)) (p x)))) :rule HOLE :args ((! 0 :qid q)))
+        (step t3 (cl) :rule RESOLUTION :premises (h1 t2))
+        (step t4 (cl) :rule tmp_AC_simp)
+    ";
+    let parse = |producer| {
+        let config = ParserConfig { producer, ..Default::default() };
+        parse_instance(problem.as_bytes(), proof.as_bytes(), config)
+    };
+
+    let (_, proof, mut pool) = parse(Producer::Cvc5).unwrap();
+    let rules: Vec<_> = proof
+        .iter()
+        .filter_map(|c| match c {
+            ProofCommand::Step(s) => Some(s.rule.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rules, ["hole", "resolution", "tmp_AC_simp"]);
+
+    let [expected] = parse_terms(
+        &mut pool,
+        "(declare-fun p (Int) Bool)",
+        ["(p (choice ((x Int)) (p x)))"],
+    );
+    assert_eq!(proof.commands[1].clause(), [expected]);
+
+    assert!(matches!(
+        parse(Producer::Generic),
+        Err(Error::Parser(ParserError::UnknownAttribute(_), _))
+    ));
+}
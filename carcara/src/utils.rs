@@ -1,12 +1,27 @@
 use crate::ast::{BindingList, Quantifier, Rc, Term};
-use ahash::{AHashMap, AHashSet, AHasher};
-use std::{
+use ahash::AHasher;
+use alloc::{vec, vec::Vec};
+use core::{
     borrow::Borrow,
     fmt,
     hash::{Hash, Hasher},
     ops,
 };
 
+#[cfg(feature = "std")]
+pub use ahash::{AHashMap, AHashSet};
+#[cfg(feature = "std")]
+pub use std::collections::hash_map::Entry;
+
+// The `ahash` collection types are only available with `std`, so without it we use the `hashbrown`
+// collections instead, which also use aHash as their default hasher
+#[cfg(not(feature = "std"))]
+pub type AHashMap<K, V> = hashbrown::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub type AHashSet<T> = hashbrown::HashSet<T>;
+#[cfg(not(feature = "std"))]
+pub use hashbrown::hash_map::Entry;
+
 /// Returns `true` if the character is a valid symbol character in the SMT-LIB and Alethe languages.
 pub fn is_symbol_character(ch: char) -> bool {
     match ch {
@@ -17,6 +32,113 @@ pub fn is_symbol_character(ch: char) -> bool {
     }
 }
 
+/// A reserved word in the SMT-LIB and Alethe lexicon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reserved {
+    /// The `_` reserved word.
+    Underscore,
+
+    /// The `!` reserved word.
+    Bang,
+
+    /// The `as` reserved word.
+    As,
+
+    /// The `let` reserved word.
+    Let,
+
+    /// The `exists` reserved word.
+    Exists,
+
+    /// The `forall` reserved word.
+    Forall,
+
+    /// The `match` reserved word.
+    Match,
+
+    /// The `choice` reserved word.
+    Choice,
+
+    /// The `lambda` reserved word.
+    Lambda,
+
+    /// The `cl` reserved word.
+    Cl,
+
+    /// The `assume` reserved word.
+    Assume,
+
+    /// The `step` reserved word.
+    Step,
+
+    /// The `anchor` reserved word.
+    Anchor,
+
+    /// The `declare-fun` reserved word.
+    DeclareFun,
+
+    /// The `declare-const` reserved word.
+    DeclareConst,
+
+    /// The `declare-sort` reserved word.
+    DeclareSort,
+
+    /// The `define-fun` reserved word.
+    DefineFun,
+
+    /// The `define-sort` reserved word.
+    DefineSort,
+
+    /// The `assert` reserved word.
+    Assert,
+
+    /// The `set-logic` reserved word.
+    SetLogic,
+
+    /// The `check-sat-assuming` reserved word.
+    CheckSatAssuming,
+
+    /// The `push` reserved word.
+    Push,
+
+    /// The `pop` reserved word.
+    Pop,
+
+    /// The `get-proof` reserved word.
+    GetProof,
+
+    /// The `exit` reserved word.
+    Exit,
+}
+
+impl_str_conversion_traits!(Reserved {
+    Underscore: "_",
+    Bang: "!",
+    As: "as",
+    Let: "let",
+    Exists: "exists",
+    Forall: "forall",
+    Match: "match",
+    Choice: "choice",
+    Lambda: "lambda",
+    Cl: "cl",
+    Assume: "assume",
+    Step: "step",
+    Anchor: "anchor",
+    DeclareFun: "declare-fun",
+    DeclareConst: "declare-const",
+    DeclareSort: "declare-sort",
+    DefineFun: "define-fun",
+    DefineSort: "define-sort",
+    Assert: "assert",
+    SetLogic: "set-logic",
+    CheckSatAssuming: "check-sat-assuming",
+    Push: "push",
+    Pop: "pop",
+    GetProof: "get-proof",
+    Exit: "exit",
+});
+
 /// An iterator that removes duplicate elements from `iter`. This will yield the elements in
 /// `iter` in order, skipping elements that have already been seen before.
 pub struct Dedup<T, I> {
@@ -58,6 +180,7 @@ impl<T, I: Iterator<Item = T>> DedupIterator<T> for I {
     }
 }
 
+#[derive(Clone)]
 pub struct HashCache<T> {
     hash: u64,
     value: T,
@@ -95,7 +218,7 @@ impl<T> AsRef<T> for HashCache<T> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolTable<K, V> {
     scopes: Vec<AHashMap<K, V>>,
 }
@@ -121,6 +244,11 @@ impl<K, V> SymbolTable<K, V> {
             }
         }
     }
+
+    /// Returns the number of scopes in the symbol table, including the outermost one.
+    pub fn num_scopes(&self) -> usize {
+        self.scopes.len()
+    }
 }
 
 impl<K: Eq + Hash, V> SymbolTable<K, V> {
@@ -151,9 +279,25 @@ impl<K: Eq + Hash, V> SymbolTable<K, V> {
             .find_map(|(depth, scope)| scope.get(key).map(|v| (depth, v)))
     }
 
+    /// Returns the value associated with `key` in the innermost scope only, ignoring any values
+    /// from the enclosing scopes.
+    pub fn get_in_current_scope<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.scopes.last().unwrap().get(key)
+    }
+
     pub fn insert(&mut self, key: K, value: V) {
         self.scopes.last_mut().unwrap().insert(key, value);
     }
+
+    /// Returns an iterator over the keys in all scopes. A key that is shadowed in an inner scope is
+    /// returned once for each scope it appears in.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.scopes.iter().flat_map(|scope| scope.keys())
+    }
 }
 
 impl<K, V> Default for SymbolTable<K, V> {
@@ -228,6 +372,6 @@ impl TypeName for Quantifier {
     const NAME: &'static str = "quantifier";
 }
 
-impl TypeName for BindingList {
+impl<T> TypeName for BindingList<T> {
     const NAME: &'static str = "binding list";
 }
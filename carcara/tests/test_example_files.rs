@@ -4,7 +4,7 @@ use std::{ffi::OsStr, fs, io, path::Path};
 fn get_truncated_message(e: &Error) -> String {
     const ERROR_MESSAGE_LIMIT: usize = 350;
     const TRUNCATION_MESSAGE: &str = "... (long message truncated)";
-    const TRUNCATION_LEN: usize = TRUNCATION_MESSAGE.as_bytes().len();
+    const TRUNCATION_LEN: usize = TRUNCATION_MESSAGE.len();
 
     let mut error_message = format!("{}", e);
 
@@ -23,15 +23,22 @@ fn run_test(problem_path: &Path, proof_path: &Path) -> CarcaraResult<()> {
             is_running_test: false,
             statistics: None,
             check_lia_using_cvc5: true,
+            audit_choice_terms: false,
+            commutative_transitivity: false,
+            sampling: None,
+            severities: Default::default(),
+            trace: None,
+            stream_results: None,
+            keep_going: None,
+            slow_step_threshold: None,
+            trusted_rules: Default::default(),
         }
     }
 
     let (prelude, proof, mut pool) = parser::parse_instance(
         io::BufReader::new(fs::File::open(problem_path)?),
         io::BufReader::new(fs::File::open(proof_path)?),
-        true,
-        false,
-        false,
+        parser::ParserConfig::default(),
     )?;
 
     // First, we check the proof normally
@@ -1,14 +1,26 @@
+use crate::cache::ResultsCache;
 use carcara::{
-    benchmarking::{CollectResults, CsvBenchmarkResults, RunMeasurement},
+    ast::{ProblemPrelude, Proof, TermPool},
+    benchmarking::{
+        phases::{self, Phase},
+        CollectResults, CsvBenchmarkResults, RunMeasurement, StepSize,
+    },
     checker,
-    parser::parse_instance,
+    parser::{parse_instance_with_problems, ParsedProblem, ParserConfig, Producer, UnicodePolicy},
     CarcaraOptions,
 };
-use crossbeam::queue::ArrayQueue;
+use crossbeam::deque::{Injector, Steal, Stealer, Worker};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs::File,
-    io::{self, BufReader},
+    io::{self, BufRead, BufReader},
+    iter,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
     time::{Duration, Instant},
 };
 
@@ -19,32 +31,152 @@ struct JobDescriptor<'a> {
     run_index: usize,
 }
 
-fn run_job<T: CollectResults + Default>(
+/// Statistics on the problem terms shared between proofs, when each problem is only parsed once
+/// per worker thread (see `JobOptions::sharing`).
+#[derive(Default)]
+pub struct SharingStats {
+    num_problems: AtomicUsize,
+    num_proofs: AtomicUsize,
+    num_shared_terms: AtomicUsize,
+    num_proof_terms: AtomicUsize,
+}
+
+impl SharingStats {
+    /// Returns the number of problems and proofs parsed, and the average number of problem terms
+    /// reused by each proof and of new terms added by it.
+    pub fn summary(&self) -> (usize, usize, f64, f64) {
+        let num_problems = self.num_problems.load(Ordering::Relaxed);
+        let num_proofs = self.num_proofs.load(Ordering::Relaxed);
+        let average = |total: &AtomicUsize| match num_proofs {
+            0 => 0.0,
+            n => total.load(Ordering::Relaxed) as f64 / n as f64,
+        };
+        (
+            num_problems,
+            num_proofs,
+            average(&self.num_shared_terms),
+            average(&self.num_proof_terms),
+        )
+    }
+}
+
+/// The problems already parsed by a worker thread, when problem terms are shared between proofs.
+/// Since terms use non-atomic reference counting, each thread has its own cache.
+struct ProblemCache<'a> {
+    problems: HashMap<&'a Path, ParsedProblem>,
+    options: &'a CarcaraOptions,
+    stats: &'a SharingStats,
+}
+
+impl<'a> ProblemCache<'a> {
+    /// The maximum number of problems kept by each thread. When this is exceeded, the cache is
+    /// cleared, so memory use stays bounded even if the proofs are of many different problems.
+    const MAX_PROBLEMS: usize = 64;
+
+    fn new(options: &'a CarcaraOptions, stats: &'a SharingStats) -> Self {
+        Self {
+            problems: HashMap::new(),
+            options,
+            stats,
+        }
+    }
+
+    /// Parses the proof of a job, reusing its problem if it was already parsed by this thread.
+    fn parse(
+        &mut self,
+        job: JobDescriptor<'a>,
+    ) -> Result<(ProblemPrelude, Proof, TermPool), carcara::Error> {
+        if !self.problems.contains_key(job.problem_file) {
+            if self.problems.len() >= Self::MAX_PROBLEMS {
+                self.problems.clear();
+            }
+            let problem = ParsedProblem::new(
+                BufReader::new(File::open(job.problem_file)?),
+                self.options.parser_config(),
+            )?;
+            self.stats.num_problems.fetch_add(1, Ordering::Relaxed);
+            self.problems.insert(job.problem_file, problem);
+        }
+        let problem = self.problems.get_mut(job.problem_file).unwrap();
+        let parsed = problem.parse_proof(
+            BufReader::new(File::open(job.proof_file)?),
+            self.options.lexer_threads,
+        )?;
+
+        let (pool_stats, stats) = (parsed.2.stats(), self.stats);
+        stats.num_proofs.fetch_add(1, Ordering::Relaxed);
+        stats
+            .num_shared_terms
+            .fetch_add(pool_stats.num_shared_terms, Ordering::Relaxed);
+        stats
+            .num_proof_terms
+            .fetch_add(pool_stats.num_terms, Ordering::Relaxed);
+        Ok(parsed)
+    }
+}
+
+/// Parses and checks a proof, recording the measurements in `results`. Returns `true` if the proof is
+/// holey. Holes are not reported when elaborating, so in that case this always returns `false`.
+fn run_job<'a, T: CollectResults + Default>(
     results: &mut T,
-    job: JobDescriptor,
+    job: JobDescriptor<'a>,
     &CarcaraOptions {
         apply_function_defs,
         expand_lets,
         allow_int_real_subtyping,
+        coerce_int_to_real,
         check_lia_using_cvc5,
         strict,
         skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        lexer_threads,
+        unicode_policy,
+        recover_from_errors: _,
+        ignore_unknown_attributes,
+        producer,
+        severities,
+        trace: _,
+        stream_results: _,
+        keep_going: _,
+        slow_step_threshold: _,
+        parser_limits,
+        ref trusted_rules,
     }: &CarcaraOptions,
     elaborate: bool,
-) -> Result<(), carcara::Error> {
+    problems: Option<&mut ProblemCache<'a>>,
+) -> Result<bool, carcara::Error> {
     let proof_file_name = job.proof_file.to_str().unwrap();
 
-    let total = Instant::now();
-
-    let parsing = Instant::now();
-    let (prelude, proof, mut pool) = parse_instance(
-        BufReader::new(File::open(job.problem_file)?),
-        BufReader::new(File::open(job.proof_file)?),
-        apply_function_defs,
-        expand_lets,
-        allow_int_real_subtyping,
-    )?;
-    let parsing = parsing.elapsed();
+    // All times except for the elaboration, deep equality and assume times are measured by the
+    // phase clock. If there is an error, the clock is stopped and its times are discarded
+    phases::start();
+    let parsed = phases::measure(Phase::Parsing, || -> Result<_, carcara::Error> {
+        match problems {
+            Some(problems) => problems.parse(job),
+            None => parse_instance_with_problems(
+                vec![BufReader::new(File::open(job.problem_file)?)],
+                BufReader::new(File::open(job.proof_file)?),
+                ParserConfig {
+                    apply_function_defs,
+                    expand_lets,
+                    allow_int_real_subtyping,
+                    coerce_int_to_real,
+                    unicode_policy,
+                    ignore_unknown_attributes,
+                    producer,
+                    limits: parser_limits,
+                    ..Default::default()
+                },
+                lexer_threads,
+            ),
+        }
+    });
+    let (prelude, proof, mut pool) = parsed.map_err(|e| {
+        phases::finish();
+        e
+    })?;
 
     let mut elaboration = Duration::ZERO;
     let mut deep_eq = Duration::ZERO;
@@ -64,88 +196,646 @@ fn run_job<T: CollectResults + Default>(
             results,
         }),
         check_lia_using_cvc5,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        severities,
+        trace: None,
+        stream_results: None,
+        keep_going: None,
+        slow_step_threshold: None,
+        trusted_rules: trusted_rules.iter().cloned().collect(),
     };
     let mut checker = checker::ProofChecker::new(&mut pool, config, prelude);
 
-    let checking = Instant::now();
-
     // If any errors are encountered when checking a proof, we return from this function and do not
     // record the `RunMeasurement`. However, the data for each individual step is recorded as they
     // are checked, so any steps that were run before the error will be recorded.
-    if elaborate {
-        checker.check_and_elaborate(proof)?;
-    } else {
-        checker.check(&proof)?;
-    }
-    let checking = checking.elapsed();
-
-    let total = total.elapsed();
+    let checked = phases::measure(Phase::Checking, || {
+        if elaborate {
+            checker.check_and_elaborate(proof).map(|_| false)
+        } else {
+            checker.check(&proof)
+        }
+    });
+    let times = phases::finish();
+    let is_holey = checked?;
 
     results.add_run_measurement(
         &(proof_file_name.to_string(), job.run_index),
         RunMeasurement {
-            parsing,
-            checking,
+            parsing: times.parsing,
+            lexing: times.lexing,
+            sort_checking: times.sort_checking,
+            checking: times.checking,
+            rule_logic: times.rule_logic,
             elaboration,
-            total,
+            total: times.total,
             deep_eq,
             assume,
             assume_core,
         },
     );
-    Ok(())
+    Ok(is_holey)
+}
+
+/// Checks a single proof, while recording the time spent on each step in `results`. Returns `true`
+/// if the proof is holey, like `carcara::check`.
+pub fn check_with_results<T: CollectResults + Default>(
+    results: &mut T,
+    problem_file: &Path,
+    proof_file: &Path,
+    options: &CarcaraOptions,
+) -> Result<bool, carcara::Error> {
+    let job = JobDescriptor {
+        problem_file,
+        proof_file,
+        run_index: 0,
+    };
+    run_job(results, job, options, false, None)
+}
+
+/// Limits applied to each job. If any limit is set, each job is run in a separate worker process,
+/// which is killed if it exceeds the limits.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JobLimits {
+    /// The maximum wall-clock time a job may take.
+    pub timeout: Option<Duration>,
+
+    /// The maximum size, in bytes, of the address space of the worker process. This is only
+    /// enforced on Unix systems.
+    pub memory_limit: Option<u64>,
+}
+
+impl JobLimits {
+    fn is_isolated(&self) -> bool {
+        self.timeout.is_some() || self.memory_limit.is_some()
+    }
+}
+
+/// The options used to run each job in a benchmark.
+pub struct JobOptions<'a> {
+    pub carcara: &'a CarcaraOptions,
+    pub elaborate: bool,
+    pub limits: JobLimits,
+
+    /// The cache in which the results of each job are stored, if any.
+    pub cache: Option<&'a ResultsCache>,
+
+    /// If present, each worker thread parses each problem only once, and shares its terms between
+    /// all proofs of it. Statistics on the shared terms are recorded here. This is ignored if jobs
+    /// are run in separate processes.
+    pub sharing: Option<&'a SharingStats>,
+}
+
+/// A message sent from a worker process to the benchmark driver. Each message is written to the
+/// standard output of the worker, as a single line of JSON.
+#[derive(Serialize, Deserialize)]
+enum WorkerMessage {
+    Step {
+        file: String,
+        step_id: String,
+        rule: String,
+        num_literals: usize,
+        num_premises: usize,
+        time: Duration,
+    },
+    Assume {
+        file: String,
+        id: String,
+        is_easy: bool,
+        time: Duration,
+    },
+    DeepEqDepth(usize),
+    Run {
+        file: String,
+        run_index: usize,
+        parsing: Duration,
+        lexing: Duration,
+        sort_checking: Duration,
+        checking: Duration,
+        rule_logic: Duration,
+        elaboration: Duration,
+        total: Duration,
+        deep_eq: Duration,
+        assume: Duration,
+        assume_core: Duration,
+    },
+    Error(String),
+}
+
+impl WorkerMessage {
+    fn replay<T: CollectResults>(self, results: &mut T) {
+        match self {
+            WorkerMessage::Step {
+                file,
+                step_id,
+                rule,
+                num_literals,
+                num_premises,
+                time,
+            } => {
+                let size = StepSize { num_literals, num_premises };
+                results.add_step_measurement(&file, &step_id, &rule, size, time);
+            }
+            WorkerMessage::Assume { file, id, is_easy, time } => {
+                results.add_assume_measurement(&file, &id, is_easy, time);
+            }
+            WorkerMessage::DeepEqDepth(depth) => results.add_deep_eq_depth(depth),
+            WorkerMessage::Run {
+                file,
+                run_index,
+                parsing,
+                lexing,
+                sort_checking,
+                checking,
+                rule_logic,
+                elaboration,
+                total,
+                deep_eq,
+                assume,
+                assume_core,
+            } => {
+                let measurement = RunMeasurement {
+                    parsing,
+                    lexing,
+                    sort_checking,
+                    checking,
+                    rule_logic,
+                    elaboration,
+                    total,
+                    deep_eq,
+                    assume,
+                    assume_core,
+                };
+                results.add_run_measurement(&(file, run_index), measurement);
+            }
+            WorkerMessage::Error(message) => {
+                let error = io::Error::new(io::ErrorKind::Other, message);
+                results.register_error(&carcara::Error::Io(error));
+            }
+        }
+    }
+}
+
+/// A results collector that records every measurement as a `WorkerMessage`. In worker processes,
+/// the messages are instead sent to the driver through the standard output as soon as they are
+/// made, so they are not lost if the worker is killed.
+#[derive(Default)]
+struct MessageLog {
+    messages: Vec<WorkerMessage>,
+    stream: bool,
 }
 
-fn worker_thread<T: CollectResults + Default>(
-    jobs_queue: &ArrayQueue<JobDescriptor>,
+impl MessageLog {
+    fn send(&mut self, message: WorkerMessage) {
+        if self.stream {
+            println!("{}", serde_json::to_string(&message).unwrap());
+        } else {
+            self.messages.push(message);
+        }
+    }
+}
+
+impl CollectResults for MessageLog {
+    fn add_step_measurement(
+        &mut self,
+        file: &str,
+        step_id: &str,
+        rule: &str,
+        size: StepSize,
+        time: Duration,
+    ) {
+        self.send(WorkerMessage::Step {
+            file: file.to_owned(),
+            step_id: step_id.to_owned(),
+            rule: rule.to_owned(),
+            num_literals: size.num_literals,
+            num_premises: size.num_premises,
+            time,
+        });
+    }
+
+    fn add_assume_measurement(&mut self, file: &str, id: &str, is_easy: bool, time: Duration) {
+        self.send(WorkerMessage::Assume {
+            file: file.to_owned(),
+            id: id.to_owned(),
+            is_easy,
+            time,
+        });
+    }
+
+    fn add_deep_eq_depth(&mut self, depth: usize) {
+        self.send(WorkerMessage::DeepEqDepth(depth));
+    }
+
+    fn add_run_measurement(&mut self, (file, run_index): &(String, usize), m: RunMeasurement) {
+        self.send(WorkerMessage::Run {
+            file: file.clone(),
+            run_index: *run_index,
+            parsing: m.parsing,
+            lexing: m.lexing,
+            sort_checking: m.sort_checking,
+            checking: m.checking,
+            rule_logic: m.rule_logic,
+            elaboration: m.elaboration,
+            total: m.total,
+            deep_eq: m.deep_eq,
+            assume: m.assume,
+            assume_core: m.assume_core,
+        });
+    }
+
+    fn register_error(&mut self, error: &carcara::Error) {
+        self.send(WorkerMessage::Error(error.to_string()));
+    }
+
+    fn combine(a: Self, _: Self) -> Self {
+        a
+    }
+}
+
+/// Runs a single job in the current process, sending the results to the standard output. This is
+/// the entry point of worker processes, which are started by the driver using the hidden
+/// `bench-worker` command.
+pub fn run_worker_job(
+    problem_file: &Path,
+    proof_file: &Path,
+    run_index: usize,
     options: &CarcaraOptions,
     elaborate: bool,
-) -> T {
-    let mut results = T::default();
+) {
+    let job = JobDescriptor { problem_file, proof_file, run_index };
+    let mut log = MessageLog { messages: Vec::new(), stream: true };
+    if let Err(e) = run_job(&mut log, job, options, elaborate, None) {
+        log.register_error(&e);
+    }
+}
+
+/// Returns the command line flags that make a worker process use the given options. The options
+/// are destructured without `..`, so adding a field to `CarcaraOptions` fails to compile until it is
+/// either passed to the worker here, or explicitly ignored.
+fn worker_flags(
+    &CarcaraOptions {
+        apply_function_defs,
+        expand_lets,
+        allow_int_real_subtyping,
+        coerce_int_to_real,
+        check_lia_using_cvc5,
+        strict,
+        skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling,
+        lexer_threads,
+        unicode_policy,
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer,
+        ref severities,
+        // These are ignored by `run_job`, so they don't need to be passed to the worker
+        trace: _,
+        stream_results: _,
+        keep_going: _,
+        slow_step_threshold: _,
+        parser_limits,
+        ref trusted_rules,
+    }: &CarcaraOptions,
+    elaborate: bool,
+) -> Vec<String> {
+    let flags = [
+        (apply_function_defs, "--apply-function-defs"),
+        (expand_lets, "--expand-let-bindings"),
+        (allow_int_real_subtyping, "--allow-int-real-subtyping"),
+        (coerce_int_to_real, "--coerce-int-to-real"),
+        (check_lia_using_cvc5, "--lia-via-cvc5"),
+        (strict, "--strict"),
+        (skip_unknown_rules, "--skip-unknown-rules"),
+        (audit_choice_terms, "--audit-choice-terms"),
+        (commutative_transitivity, "--commutative-transitivity"),
+        (recover_from_errors, "--recover-from-errors"),
+        (ignore_unknown_attributes, "--ignore-unknown-attributes"),
+        (elaborate, "--elaborate"),
+    ];
+    let mut flags: Vec<String> = flags
+        .into_iter()
+        .filter(|&(enabled, _)| enabled)
+        .map(|(_, flag)| flag.to_owned())
+        .collect();
+    if lexer_threads > 1 {
+        flags.push(format!("--lexer-threads={}", lexer_threads));
+    }
+    match unicode_policy {
+        UnicodePolicy::Reject => (),
+        UnicodePolicy::Accept => flags.push("--unicode-symbols=accept".to_owned()),
+        UnicodePolicy::Normalize => flags.push("--unicode-symbols=normalize".to_owned()),
+    }
+    match producer {
+        Producer::Generic => (),
+        Producer::Cvc5 => flags.push("--producer=cvc5".to_owned()),
+    }
+    if let Some(sampling) = sampling {
+        flags.push(format!("--sample={}", sampling.rate));
+        flags.push(format!("--sample-seed={}", sampling.seed));
+    }
+    for category in severities.warnings() {
+        flags.push(format!("--warn={}", category));
+    }
+    for rule in trusted_rules {
+        flags.push(format!("--trust-rule={}", rule));
+    }
+    let limits = [
+        (parser_limits.max_nesting_depth, "--max-nesting-depth"),
+        (parser_limits.max_commands, "--max-commands"),
+        (parser_limits.max_token_length, "--max-token-length"),
+    ];
+    for (limit, flag) in limits {
+        if let Some(limit) = limit {
+            flags.push(format!("{}={}", flag, limit));
+        }
+    }
+    flags
+}
+
+#[cfg(unix)]
+fn set_memory_limit(command: &mut Command, limit: u64) {
+    use std::os::unix::process::CommandExt;
+
+    let limit = libc::rlimit {
+        rlim_cur: limit as libc::rlim_t,
+        rlim_max: limit as libc::rlim_t,
+    };
+    // SAFETY: the closure runs in the child process after `fork`, so it may only call
+    // async-signal-safe functions. `setrlimit` is one of them, and the closure doesn't allocate
+    let pre_exec = move || match unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    };
+    // SAFETY: see above
+    unsafe { command.pre_exec(pre_exec) };
+}
+
+#[cfg(not(unix))]
+fn set_memory_limit(_: &mut Command, _: u64) {
+    log::warn!("memory limits are only supported on Unix systems, ignoring");
+}
+
+/// The measurements made by a job, and whether they may be stored in the results cache. Failures
+/// that depend on the environment, like timeouts, are not cached.
+struct JobOutput {
+    messages: Vec<WorkerMessage>,
+    cacheable: bool,
+}
+
+impl JobOutput {
+    fn failed(mut self, message: String) -> Self {
+        self.messages.push(WorkerMessage::Error(message));
+        self.cacheable = false;
+        self
+    }
+}
+
+fn run_in_process<'a>(
+    job: JobDescriptor<'a>,
+    options: &JobOptions,
+    problems: Option<&mut ProblemCache<'a>>,
+) -> JobOutput {
+    let mut log = MessageLog::default();
+    if let Err(e) = run_job(&mut log, job, options.carcara, options.elaborate, problems) {
+        log.register_error(&e);
+    }
+    JobOutput {
+        messages: log.messages,
+        cacheable: true,
+    }
+}
+
+/// Runs a job in a separate worker process, killing it if it exceeds the given limits.
+fn run_isolated(job: JobDescriptor, options: &JobOptions) -> JobOutput {
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    let spawn = || -> io::Result<_> {
+        let mut command = Command::new(std::env::current_exe()?);
+        command
+            .arg("bench-worker")
+            .args(worker_flags(options.carcara, options.elaborate))
+            .arg("--run-index")
+            .arg(job.run_index.to_string())
+            .arg(job.proof_file)
+            .arg(job.problem_file)
+            .stdout(Stdio::piped());
+        if let Some(limit) = options.limits.memory_limit {
+            set_memory_limit(&mut command, limit);
+        }
+        command.spawn()
+    };
+    let output = JobOutput {
+        messages: Vec::new(),
+        cacheable: true,
+    };
+    let mut child = match spawn() {
+        Ok(c) => c,
+        Err(e) => return output.failed(format!("failed to start worker process: {}", e)),
+    };
+
+    // We read the output of the worker in a separate thread, so it doesn't block if the pipe
+    // buffer fills up while we wait for the worker to exit
+    let stdout = child.stdout.take().unwrap();
+    let reader = thread::spawn(move || -> io::Result<Vec<String>> {
+        BufReader::new(stdout).lines().collect()
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) => (),
+            Err(e) => break Err(e.to_string()),
+        }
+        if options
+            .limits
+            .timeout
+            .map_or(false, |t| start.elapsed() > t)
+        {
+            // If the worker can't be killed, it probably already exited
+            let _ = child.kill().and_then(|_| child.wait());
+            break Err("job timed out".to_owned());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    // Even if the worker was killed, we still record the measurements it made before that
+    let output = match reader.join().unwrap() {
+        Ok(lines) => match parse_messages(&lines) {
+            Ok(messages) => JobOutput { messages, ..output },
+            Err(e) => return output.failed(e),
+        },
+        Err(e) => return output.failed(format!("failed to read worker output: {}", e)),
+    };
+    match status {
+        Ok(status) if status.success() => output,
+        Ok(status) => output.failed(format!("worker process failed with {}", status)),
+        Err(e) => output.failed(e),
+    }
+}
+
+fn parse_messages(lines: &[String]) -> Result<Vec<WorkerMessage>, String> {
+    lines
+        .iter()
+        .map(|line| serde_json::from_str(line))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid worker output: {}", e))
+}
+
+/// Runs a job, or takes its measurements from the results cache if possible.
+fn run_cached_job<'a>(
+    job: JobDescriptor<'a>,
+    options: &JobOptions,
+    mut problems: Option<&mut ProblemCache<'a>>,
+) -> Vec<WorkerMessage> {
+    let mut run = || {
+        if options.limits.is_isolated() {
+            run_isolated(job, options)
+        } else {
+            run_in_process(job, options, problems.as_deref_mut())
+        }
+    };
+    let cache = match options.cache {
+        Some(c) => c,
+        None => return run().messages,
+    };
+    let flags = worker_flags(options.carcara, options.elaborate);
+    let flags: Vec<&str> = flags.iter().map(String::as_str).collect();
+    let key = match cache.key(job.problem_file, job.proof_file, &flags, job.run_index) {
+        Ok(k) => k,
+        Err(e) => {
+            log::warn!("couldn't compute cache key: {}", e);
+            return run().messages;
+        }
+    };
+    if let Some(messages) = cache
+        .get(&key)
+        .and_then(|lines| parse_messages(&lines).ok())
+    {
+        return messages;
+    }
 
-    while let Some(job) = jobs_queue.pop() {
-        let result = run_job(&mut results, job, options, elaborate);
-        if let Err(e) = &result {
-            log::error!("encountered error in file '{}'", job.proof_file.display());
-            results.register_error(e);
+    let output = run();
+    if output.cacheable {
+        let lines: Vec<_> = output
+            .messages
+            .iter()
+            .map(|m| serde_json::to_string(m).unwrap())
+            .collect();
+        if let Err(e) = cache.put(&key, &lines) {
+            log::warn!("couldn't write to results cache: {}", e);
+        }
+    }
+    output.messages
+}
+
+/// Returns the next job to be run by a worker thread. Each thread first takes jobs from its local
+/// queue, and when that is empty, it takes a batch of jobs from the global queue, or steals them
+/// from other threads.
+fn find_job<'a>(
+    local: &Worker<JobDescriptor<'a>>,
+    global: &Injector<JobDescriptor<'a>>,
+    stealers: &[Stealer<JobDescriptor<'a>>],
+) -> Option<JobDescriptor<'a>> {
+    local.pop().or_else(|| {
+        iter::repeat_with(|| {
+            global
+                .steal_batch_and_pop(local)
+                .or_else(|| stealers.iter().map(Stealer::steal).collect())
+        })
+        .find(|s| !s.is_retry())
+        .and_then(Steal::success)
+    })
+}
+
+fn worker_thread<'a, T: CollectResults + Default>(
+    mut results: T,
+    local: &Worker<JobDescriptor<'a>>,
+    global: &Injector<JobDescriptor<'a>>,
+    stealers: &[Stealer<JobDescriptor<'a>>],
+    options: &'a JobOptions,
+) -> T {
+    let mut problems = options
+        .sharing
+        .map(|stats| ProblemCache::new(options.carcara, stats));
+    while let Some(job) = find_job(local, global, stealers) {
+        for message in run_cached_job(job, options, problems.as_mut()) {
+            if let WorkerMessage::Error(e) = &message {
+                log::error!(
+                    "encountered error in file '{}': {}",
+                    job.proof_file.display(),
+                    e
+                );
+            }
+            message.replay(&mut results);
         }
     }
 
     results
 }
 
+/// Returns the number of threads to use for a benchmark. If `requested` is zero, this is the
+/// number of available cores.
+pub fn resolve_num_threads(requested: usize) -> usize {
+    if requested != 0 {
+        return requested;
+    }
+    thread::available_parallelism().map_or_else(
+        |e| {
+            log::warn!(
+                "couldn't detect number of available cores, using one thread: {}",
+                e
+            );
+            1
+        },
+        NonZeroUsize::get,
+    )
+}
+
+/// Runs a benchmark, using `new_results` to create the initial results for each worker thread.
+/// The jobs are distributed using a work-stealing scheduler, so threads that finish their jobs
+/// early take over jobs from the threads that are still busy.
 pub fn run_benchmark<T: CollectResults + Default + Send>(
     instances: &[(PathBuf, PathBuf)],
     num_runs: usize,
     num_threads: usize,
-    options: &CarcaraOptions,
-    elaborate: bool,
+    options: &JobOptions,
+    new_results: impl Fn() -> T,
 ) -> T {
     const STACK_SIZE: usize = 128 * 1024 * 1024;
 
-    let jobs_queue = ArrayQueue::new(instances.len() * num_runs);
+    let global = Injector::new();
     for run_index in 0..num_runs {
         for (problem, proof) in instances {
-            let job = JobDescriptor {
+            global.push(JobDescriptor {
                 problem_file: problem,
                 proof_file: proof,
                 run_index,
-            };
-            jobs_queue.push(job).unwrap();
+            });
         }
     }
+    let locals: Vec<_> = (0..num_threads).map(|_| Worker::new_fifo()).collect();
+    let stealers: Vec<_> = locals.iter().map(Worker::stealer).collect();
 
     crossbeam::scope(|s| {
-        let jobs_queue = &jobs_queue; // So we don't try to move the queue into the thread closure
+        // So we don't try to move these into the thread closures
+        let (global, stealers) = (&global, stealers.as_slice());
 
         // We of course need to `collect` here to ensure we spawn all threads before starting to
         // `join` them
         #[allow(clippy::needless_collect)]
-        let workers: Vec<_> = (0..num_threads)
-            .map(|_| {
+        let workers: Vec<_> = locals
+            .into_iter()
+            .map(|local| {
+                let results = new_results();
                 s.builder()
                     .stack_size(STACK_SIZE)
-                    .spawn(move |_| worker_thread::<T>(jobs_queue, options, elaborate))
+                    .spawn(move |_| worker_thread(results, &local, global, stealers, options))
                     .unwrap()
             })
             .collect();
@@ -159,20 +849,61 @@ pub fn run_benchmark<T: CollectResults + Default + Send>(
     .unwrap()
 }
 
-pub fn run_csv_benchmark(
-    instances: &[(PathBuf, PathBuf)],
-    num_runs: usize,
-    num_threads: usize,
-    options: &CarcaraOptions,
-    elaborate: bool,
+pub fn write_csv_results(
+    result: CsvBenchmarkResults,
     runs_dest: &mut dyn io::Write,
     by_rule_dest: &mut dyn io::Write,
 ) -> io::Result<()> {
-    let result: CsvBenchmarkResults =
-        run_benchmark(instances, num_runs, num_threads, options, elaborate);
     println!(
         "{} errors encountered during benchmark",
         result.num_errors()
     );
     result.write_csv(runs_dest, by_rule_dest)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cli, Command};
+    use clap::Parser;
+
+    #[test]
+    fn test_worker_flags() {
+        // Every option that can be passed to a worker, in the order `worker_flags` returns them
+        let flags = [
+            "--apply-function-defs",
+            "--expand-let-bindings",
+            "--allow-int-real-subtyping",
+            "--coerce-int-to-real",
+            "--lia-via-cvc5",
+            "--strict",
+            "--skip-unknown-rules",
+            "--audit-choice-terms",
+            "--commutative-transitivity",
+            "--recover-from-errors",
+            "--ignore-unknown-attributes",
+            "--elaborate",
+            "--lexer-threads=4",
+            "--unicode-symbols=normalize",
+            "--producer=cvc5",
+            "--sample=0.5",
+            "--sample-seed=42",
+            "--warn=unknown-rule",
+            "--trust-rule=lia_generic",
+            "--max-nesting-depth=1000",
+            "--max-commands=100000",
+            "--max-token-length=4096",
+        ];
+        let positional = ["--run-index", "0", "a.smt2.proof", "a.smt2"];
+        let args = ["carcara", "bench-worker"]
+            .into_iter()
+            .chain(flags)
+            .chain(positional);
+        let options = match Cli::parse_from(args).command {
+            Some(Command::BenchWorker(options)) => options,
+            _ => unreachable!(),
+        };
+        let carcara_options = crate::build_carcara_options(options.parsing, options.checking);
+        assert_eq!(worker_flags(&carcara_options, options.elaborate), flags);
+    }
+}
@@ -0,0 +1,123 @@
+//! A content-addressed cache of benchmark results, which allows reruns of a benchmark to skip the
+//! files that didn't change since the last run.
+//!
+//! Each entry is keyed by a hash of the Carcara executable, the problem and proof contents, the
+//! options used and the run index, and contains the measurements recorded for that job, in the same format that is used by
+//! worker processes.
+
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The version of the format of cache entries. This is part of the key of every entry, so it must
+/// be changed whenever the format of the messages sent by worker processes changes, to prevent old
+/// entries from being used.
+const ENTRY_FORMAT_VERSION: u32 = 2;
+
+pub struct ResultsCache {
+    dir: PathBuf,
+
+    /// A hash of the running executable, which identifies the build of Carcara that produced the
+    /// results. Rebuilding Carcara with any change, even without bumping its version, invalidates
+    /// all existing entries.
+    build_id: Vec<u8>,
+
+    /// If this is `true`, existing entries are ignored, but new results are still written to the
+    /// cache, replacing the old entries.
+    refresh: bool,
+
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ResultsCache {
+    /// Opens the cache in the directory `dir`, creating it if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>, refresh: bool) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let build_id = Sha256::digest(fs::read(std::env::current_exe()?)?).to_vec();
+        Ok(Self {
+            dir,
+            build_id,
+            refresh,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    /// Computes the key of the entry for a job. Besides the contents of the problem and proof
+    /// files, the key depends on the build of Carcara, on the version of the entry format, on the
+    /// command line flags that affect the results, and on the run index.
+    pub fn key(
+        &self,
+        problem_file: &Path,
+        proof_file: &Path,
+        flags: &[&str],
+        run_index: usize,
+    ) -> io::Result<String> {
+        let mut hasher = Sha256::new();
+
+        // Every component is prefixed by its length, so different sequences of components can't
+        // produce the same input for the hash function
+        let mut add = |bytes: &[u8]| {
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        };
+        add(&self.build_id);
+        add(&ENTRY_FORMAT_VERSION.to_le_bytes());
+        add(flags.join(" ").as_bytes());
+        add(&(run_index as u64).to_le_bytes());
+        add(&fs::read(problem_file)?);
+        add(&fs::read(proof_file)?);
+
+        let mut key = String::with_capacity(64);
+        for byte in hasher.finalize() {
+            write!(key, "{:02x}", byte).unwrap();
+        }
+        Ok(key)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// Returns the lines of the entry with the given key, or `None` if there is no such entry.
+    pub fn get(&self, key: &str) -> Option<Vec<String>> {
+        let entry = if self.refresh {
+            None
+        } else {
+            fs::read_to_string(self.entry_path(key)).ok()
+        };
+        let counter = if entry.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        entry.map(|contents| contents.lines().map(str::to_owned).collect())
+    }
+
+    /// Writes an entry to the cache. The entry is first written to a temporary file, which is then
+    /// renamed, so other processes never see a partially written entry.
+    pub fn put(&self, key: &str, lines: &[String]) -> io::Result<()> {
+        let temp_path = self.dir.join(format!("{}.tmp{}", key, std::process::id()));
+        let mut file = io::BufWriter::new(fs::File::create(&temp_path)?);
+        for line in lines {
+            writeln!(file, "{}", line)?;
+        }
+        file.into_inner()?.sync_all()?;
+        fs::rename(temp_path, self.entry_path(key))
+    }
+
+    /// Returns the number of cache hits and misses so far.
+    pub fn stats(&self) -> (usize, usize) {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        (hits, misses)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{
+    error::{CliError, CliResult},
+    parse_sample_rate, ProofProducer, UnicodeSymbols, WarningCategory,
+};
+use clap::ArgEnum;
+use serde::{de, Deserialize, Deserializer};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the config file that is searched for when no file is explicitly given.
+pub const CONFIG_FILE_NAME: &str = "carcara.toml";
+
+/// A named set of options, defined in a config file. Flags that are enabled in the profile are
+/// enabled regardless of the command line, and values given in the command line take precedence
+/// over values given in the profile. Each key has the name of the corresponding command line
+/// option, and is only used by the commands that accept that option.
+///
+/// The options that only affect the output of a single run, like `--trace`, `--stream-results`
+/// or `--top-slowest`, and the options that choose the input files can't be set in a profile.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    // Parsing options
+    pub apply_function_defs: bool,
+    pub expand_let_bindings: bool,
+    pub allow_int_real_subtyping: bool,
+    pub coerce_int_to_real: bool,
+    pub lexer_threads: Option<usize>,
+    pub unicode_symbols: UnicodeSymbols,
+    pub recover_from_errors: bool,
+    pub ignore_unknown_attributes: bool,
+    pub producer: ProofProducer,
+    pub max_nesting_depth: Option<usize>,
+    pub max_commands: Option<usize>,
+    pub max_token_length: Option<usize>,
+
+    // Checking options
+    pub strict: bool,
+    pub skip_unknown_rules: bool,
+    pub lia_via_cvc5: bool,
+    pub audit_choice_terms: bool,
+    pub commutative_transitivity: bool,
+    #[serde(deserialize_with = "deserialize_sample_rate")]
+    pub sample: Option<f64>,
+    pub sample_seed: Option<u64>,
+    pub warn: Vec<WarningCategory>,
+    pub trusted_rules: Vec<String>,
+
+    // Options of the `check` and `cross-check` commands
+    pub keep_going: bool,
+    pub max_errors: Option<usize>,
+    pub slow_step_threshold: Option<f64>,
+
+    // Options of the `bench` command
+    pub elaborate: bool,
+    pub num_threads: Option<usize>,
+    pub timeout: Option<f64>,
+    pub memory_limit: Option<u64>,
+    pub share_problem_terms: bool,
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// Deserializes the sample rate of a profile, which is written as in the `--sample` option, either
+/// as a string like `"10%"` or as a number between 0 and 1.
+fn deserialize_sample_rate<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Rate {
+        Number(f64),
+        Text(String),
+    }
+
+    let rate = match Rate::deserialize(deserializer)? {
+        Rate::Number(n) => n.to_string(),
+        Rate::Text(s) => s,
+    };
+    parse_sample_rate(&rate)
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
+/// A set of options for the proofs produced by a specific solver, built into the checker. A preset
+/// is applied like a profile, and can be combined with one.
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Proofs produced by cvc5. These may mix `Int` and `Real` terms in arithmetic, and use rules
+    /// that are not yet supported by the checker, which are treated as holes. The differences from
+    /// the Alethe specification in cvc5 proofs are also normalized, as with `--producer cvc5`.
+    Cvc5,
+
+    /// Proofs produced by veriT. The function definitions in the problem are applied, since veriT
+    /// expands them in its proofs.
+    Verit,
+}
+
+impl Preset {
+    /// Returns the options of the preset, as a profile.
+    pub fn profile(self) -> Profile {
+        match self {
+            Preset::Cvc5 => Profile {
+                allow_int_real_subtyping: true,
+                ignore_unknown_attributes: true,
+                producer: ProofProducer::Cvc5,
+                warn: vec![WarningCategory::UnknownRule],
+                ..Default::default()
+            },
+            Preset::Verit => Profile {
+                apply_function_defs: true,
+                ignore_unknown_attributes: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Finds the config file by looking for a file named `carcara.toml` in the current directory and
+/// in each of its ancestors.
+fn find_config_file() -> CliResult<PathBuf> {
+    let current_dir = env::current_dir()?;
+    current_dir
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+        .ok_or(CliError::NoConfigFile)
+}
+
+/// Loads the profile named `name` from the config file at `path`. If `path` is `None`, the config
+/// file is searched for starting from the current directory.
+pub fn load_profile(path: Option<&Path>, name: &str) -> CliResult<Profile> {
+    let path = match path {
+        Some(p) => p.to_owned(),
+        None => find_config_file()?,
+    };
+    let contents = fs::read_to_string(&path)?;
+    let mut config: ConfigFile =
+        toml::from_str(&contents).map_err(|e| CliError::InvalidConfigFile(path.clone(), e))?;
+    config
+        .profiles
+        .remove(name)
+        .ok_or_else(|| CliError::UnknownProfile(name.to_owned(), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_keys() {
+        let contents = r#"
+            [profiles.all]
+            apply-function-defs = true
+            expand-let-bindings = true
+            allow-int-real-subtyping = true
+            coerce-int-to-real = true
+            lexer-threads = 4
+            unicode-symbols = "normalize"
+            recover-from-errors = true
+            ignore-unknown-attributes = true
+            producer = "cvc5"
+            max-nesting-depth = 1000
+            max-commands = 100000
+            max-token-length = 4096
+            strict = true
+            skip-unknown-rules = true
+            lia-via-cvc5 = true
+            audit-choice-terms = true
+            commutative-transitivity = true
+            sample = "10%"
+            sample-seed = 42
+            warn = ["unknown-rule"]
+            trusted-rules = ["lia_generic"]
+            keep-going = true
+            max-errors = 10
+            slow-step-threshold = 0.5
+            elaborate = true
+            num-threads = 8
+            timeout = 30.0
+            memory-limit = 2048
+            share-problem-terms = true
+            cache-dir = "cache"
+
+            [profiles.fraction]
+            sample = 0.25
+        "#;
+        let mut config: ConfigFile = toml::from_str(contents).unwrap();
+        let all = config.profiles.remove("all").unwrap();
+        assert_eq!(all.unicode_symbols, UnicodeSymbols::Normalize);
+        assert_eq!(all.max_nesting_depth, Some(1000));
+        assert_eq!(all.sample, Some(0.1));
+        assert_eq!(all.trusted_rules, ["lia_generic"]);
+        assert_eq!(all.memory_limit, Some(2048));
+
+        let fraction = config.profiles.remove("fraction").unwrap();
+        assert_eq!(fraction.sample, Some(0.25));
+        assert_eq!(fraction.sample_seed, None);
+
+        assert!(toml::from_str::<ConfigFile>("[profiles.a]\nsample = 2.0").is_err());
+        assert!(toml::from_str::<ConfigFile>("[profiles.a]\ntrace = \"x\"").is_err());
+    }
+}
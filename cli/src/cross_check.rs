@@ -0,0 +1,316 @@
+use crate::error::{CliError, CliResult};
+use carcara::{check, CarcaraOptions};
+use std::{
+    fmt,
+    fs::File,
+    io::BufReader,
+    path::Path,
+    process::{Command, Output},
+};
+
+/// The final verdict given by a checker on a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Valid,
+    Holey,
+    Invalid,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Verdict::Valid => "valid",
+            Verdict::Holey => "holey",
+            Verdict::Invalid => "invalid",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The outcome of running a checker on a proof. If the proof was deemed invalid, `failed_steps`
+/// contains the ids of the steps that the checker reported as failed, in the order they were
+/// reported. This may be empty even if the proof is invalid, if the checker didn't say which steps
+/// failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub verdict: Verdict,
+    pub failed_steps: Vec<String>,
+    pub message: Option<String>,
+}
+
+/// A step on which two checkers disagree: one of them reported it as failed, and the other didn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepDisagreement {
+    pub step: String,
+
+    /// Whether the step was reported as failed by the first checker (the one on which
+    /// `Outcome::step_disagreements` was called) or by the second.
+    pub failed_in_first: bool,
+}
+
+impl Outcome {
+    /// Returns `true` if the steps that failed in this outcome are known. That is the case if the
+    /// proof is not invalid, or if the checker reported at least one failed step.
+    fn knows_failed_steps(&self) -> bool {
+        self.verdict != Verdict::Invalid || !self.failed_steps.is_empty()
+    }
+
+    /// Compares the two outcomes step by step, and returns the steps that failed in only one of
+    /// them. If either outcome is invalid but doesn't say which steps failed, the steps can't be
+    /// compared, and this returns an empty list.
+    pub fn step_disagreements(&self, other: &Outcome) -> Vec<StepDisagreement> {
+        if !self.knows_failed_steps() || !other.knows_failed_steps() {
+            return Vec::new();
+        }
+        let only_in = |a: &Outcome, b: &Outcome, failed_in_first| {
+            a.failed_steps
+                .iter()
+                .filter(|s| !b.failed_steps.contains(s))
+                .map(|s| StepDisagreement { step: s.clone(), failed_in_first })
+                .collect::<Vec<_>>()
+        };
+        let mut result = only_in(self, other, true);
+        result.extend(only_in(other, self, false));
+        result
+    }
+
+    /// Returns `true` if the two outcomes don't contradict each other. Two outcomes agree if they
+    /// have the same verdict and, where the failed steps of both are known, the same steps failed.
+    pub fn agrees_with(&self, other: &Outcome) -> bool {
+        self.verdict == other.verdict && self.step_disagreements(other).is_empty()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.verdict)?;
+        if let Some((first, rest)) = self.failed_steps.split_first() {
+            write!(f, " (on step '{}'", first)?;
+            for step in rest {
+                write!(f, ", '{}'", step)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// An external Alethe proof checker. The checker is called with `args`, where any occurrences of
+/// `{proof}` and `{problem}` are replaced by the proof and problem file paths. If none of the
+/// arguments contain these placeholders, the proof and problem file paths are appended to the
+/// arguments, in that order.
+pub struct ExternalChecker<'a> {
+    pub path: &'a str,
+    pub args: &'a [String],
+}
+
+impl<'a> ExternalChecker<'a> {
+    fn run(&self, problem_file: &Path, proof_file: &Path) -> CliResult<Output> {
+        let (problem_file, proof_file) =
+            (problem_file.to_string_lossy(), proof_file.to_string_lossy());
+        let has_placeholders = self
+            .args
+            .iter()
+            .any(|a| a.contains("{proof}") || a.contains("{problem}"));
+
+        let mut command = Command::new(self.path);
+        for arg in self.args {
+            command.arg(
+                arg.replace("{proof}", &proof_file)
+                    .replace("{problem}", &problem_file),
+            );
+        }
+        if !has_placeholders {
+            command.args([proof_file.as_ref(), problem_file.as_ref()]);
+        }
+        command
+            .output()
+            .map_err(|e| CliError::FailedToRunChecker(self.path.to_owned(), e))
+    }
+
+    /// Runs the external checker on the given instance, and interprets its output.
+    ///
+    /// If the last non-empty line printed to the standard output is "valid", "holey" or
+    /// "invalid", it is used as the verdict. Otherwise, the verdict is determined by the exit
+    /// status of the checker. If the proof is invalid, this also tries to find which steps failed
+    /// by looking for messages of the form "step '<id>'" in the output.
+    pub fn check(&self, problem_file: &Path, proof_file: &Path) -> CliResult<Outcome> {
+        let output = self.run(problem_file, proof_file)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        let last_line = stdout.lines().rev().find(|l| !l.trim().is_empty());
+        let verdict = match last_line.map(str::trim) {
+            Some("valid") => Verdict::Valid,
+            Some("holey") => Verdict::Holey,
+            Some("invalid") => Verdict::Invalid,
+            _ if output.status.success() => Verdict::Valid,
+            _ => Verdict::Invalid,
+        };
+
+        let (failed_steps, message) = if verdict == Verdict::Invalid {
+            let mut failed_steps = find_step_ids(&stderr);
+            for id in find_step_ids(&stdout) {
+                if !failed_steps.contains(&id) {
+                    failed_steps.push(id);
+                }
+            }
+            let message = stderr.lines().find(|l| !l.trim().is_empty());
+            (failed_steps, message.map(ToOwned::to_owned))
+        } else {
+            (Vec::new(), None)
+        };
+        Ok(Outcome { verdict, failed_steps, message })
+    }
+}
+
+/// Finds all occurrences of "step '<id>'" in `output`, and returns the step ids, without
+/// duplicates, in the order they first appear.
+fn find_step_ids(output: &str) -> Vec<String> {
+    const PATTERN: &str = "step '";
+
+    let mut result = Vec::new();
+    let mut rest = output;
+    while let Some(start) = rest.find(PATTERN) {
+        rest = &rest[start + PATTERN.len()..];
+        let len = match rest.find('\'') {
+            Some(len) => len,
+            None => break,
+        };
+        let id = &rest[..len];
+        if !id.is_empty() && !result.iter().any(|s| s == id) {
+            result.push(id.to_owned());
+        }
+        rest = &rest[len + 1..];
+    }
+    result
+}
+
+/// Returns the ids of the steps that failed in a checker error, including the ones in a list of
+/// errors collected with `keep_going`.
+fn failed_steps_of(error: &carcara::Error, result: &mut Vec<String>) {
+    match error {
+        carcara::Error::Checker { step, .. } if !result.contains(step) => {
+            result.push(step.clone());
+        }
+        carcara::Error::CheckerErrors(errors) => {
+            for e in errors {
+                failed_steps_of(e, result);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Checks the given instance using both Carcara and the external checker, and returns both
+/// outcomes, in that order. To compare the checkers step by step, `options.keep_going` should be
+/// set, so Carcara reports all the steps that failed, and not only the first one.
+pub fn cross_check(
+    problem_file: &Path,
+    proof_file: &Path,
+    options: CarcaraOptions,
+    external: &ExternalChecker,
+) -> CliResult<(Outcome, Outcome)> {
+    let ours = match check(
+        BufReader::new(File::open(problem_file)?),
+        BufReader::new(File::open(proof_file)?),
+        options,
+    ) {
+        Ok(false) => Outcome {
+            verdict: Verdict::Valid,
+            failed_steps: Vec::new(),
+            message: None,
+        },
+        Ok(true) => Outcome {
+            verdict: Verdict::Holey,
+            failed_steps: Vec::new(),
+            message: None,
+        },
+        Err(e) => {
+            let mut failed_steps = Vec::new();
+            failed_steps_of(&e, &mut failed_steps);
+            Outcome {
+                verdict: Verdict::Invalid,
+                failed_steps,
+                message: Some(e.to_string()),
+            }
+        }
+    };
+    let theirs = external.check(problem_file, proof_file)?;
+    Ok((ours, theirs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(verdict: Verdict, failed_steps: &[&str]) -> Outcome {
+        Outcome {
+            verdict,
+            failed_steps: failed_steps.iter().map(|s| s.to_string()).collect(),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_find_step_ids() {
+        assert!(find_step_ids("").is_empty());
+        assert!(find_step_ids("invalid").is_empty());
+        assert_eq!(find_step_ids("checker error on step 't3'"), ["t3"]);
+        assert_eq!(
+            find_step_ids("error on step 't3.t1': ...\nerror on step 't5'\nagain step 't3.t1'"),
+            ["t3.t1", "t5"],
+        );
+
+        // Unterminated or empty ids are ignored
+        assert_eq!(find_step_ids("step '' and step 't2'"), ["t2"]);
+        assert_eq!(find_step_ids("step 't1' and step 't2"), ["t1"]);
+    }
+
+    #[test]
+    fn test_agrees_with() {
+        let valid = outcome(Verdict::Valid, &[]);
+        let holey = outcome(Verdict::Holey, &[]);
+        let invalid = outcome(Verdict::Invalid, &[]);
+        let invalid_t1 = outcome(Verdict::Invalid, &["t1"]);
+        let invalid_t1_t2 = outcome(Verdict::Invalid, &["t1", "t2"]);
+        let invalid_t2_t1 = outcome(Verdict::Invalid, &["t2", "t1"]);
+
+        assert!(valid.agrees_with(&valid));
+        assert!(!valid.agrees_with(&holey));
+        assert!(!valid.agrees_with(&invalid_t1));
+        assert!(!invalid_t1.agrees_with(&holey));
+
+        // The order in which failed steps were reported doesn't matter
+        assert!(invalid_t1_t2.agrees_with(&invalid_t2_t1));
+        assert!(!invalid_t1.agrees_with(&invalid_t1_t2));
+        assert!(!invalid_t1_t2.agrees_with(&invalid_t1));
+
+        // If a checker doesn't say which steps failed, only the verdicts are compared
+        assert!(invalid.agrees_with(&invalid_t1));
+        assert!(invalid_t1_t2.agrees_with(&invalid));
+    }
+
+    #[test]
+    fn test_step_disagreements() {
+        let valid = outcome(Verdict::Valid, &[]);
+        let invalid = outcome(Verdict::Invalid, &[]);
+        let invalid_t1_t2 = outcome(Verdict::Invalid, &["t1", "t2"]);
+        let invalid_t2_t3 = outcome(Verdict::Invalid, &["t2", "t3"]);
+
+        let disagreement = |step: &str, failed_in_first| StepDisagreement {
+            step: step.to_owned(),
+            failed_in_first,
+        };
+        assert_eq!(
+            invalid_t1_t2.step_disagreements(&invalid_t2_t3),
+            [disagreement("t1", true), disagreement("t3", false)],
+        );
+        assert_eq!(
+            valid.step_disagreements(&invalid_t2_t3),
+            [disagreement("t2", false), disagreement("t3", false)],
+        );
+        assert!(invalid.step_disagreements(&invalid_t1_t2).is_empty());
+        assert!(valid.step_disagreements(&valid).is_empty());
+    }
+}
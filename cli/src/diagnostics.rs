@@ -0,0 +1,115 @@
+//! Reporting of errors with excerpts of the files where they happened, using the diagnostics from
+//! `carcara::diagnostics`.
+
+use crate::{error::CliError, path_args::infer_problem_path, ParsingOptions};
+use carcara::{diagnostics::Diagnostic, parser};
+use std::{
+    fs::File,
+    io::{self, BufRead, Read},
+    path::{Path, PathBuf},
+};
+
+/// The files read by a command, which are used to show excerpts of them when reporting an error.
+pub struct ErrorSources {
+    pub proof_file: String,
+    pub problem_file: Option<String>,
+
+    /// Whether other problem files are parsed before the problem file. In that case, it is not
+    /// known which file a parser error happened in, unless the proof is standalone.
+    pub has_preludes: bool,
+
+    pub standalone: bool,
+    pub parsing: ParsingOptions,
+}
+
+impl ErrorSources {
+    /// Renders the diagnostics of an error. Returns `None` if the error has no position, or if the
+    /// file where it happened can't be read again, for example because it was the standard input.
+    fn render(&self, error: &carcara::Error) -> Option<String> {
+        let diagnostics = Diagnostic::from_error(error);
+        if diagnostics.iter().all(|d| d.label.is_none()) {
+            return None;
+        }
+        let proof_path = PathBuf::from(&self.proof_file);
+        let parser_error_path = if diagnostics.iter().any(|d| d.is_parser_error) {
+            Some(self.parser_error_path()?)
+        } else {
+            None
+        };
+
+        let mut sources: Vec<(PathBuf, String)> = Vec::new();
+        let mut result = String::new();
+        for d in diagnostics {
+            let path = match &parser_error_path {
+                Some(p) if d.is_parser_error => p,
+                _ => &proof_path,
+            };
+            let index = match sources.iter().position(|(p, _)| p == path) {
+                Some(i) => i,
+                None => {
+                    sources.push((path.clone(), read_source(path)?));
+                    sources.len() - 1
+                }
+            };
+            result += &d.render(&path.display().to_string(), &sources[index].1);
+        }
+        Some(result)
+    }
+
+    /// Returns the file where a parser error happened. Since errors don't record the file they were
+    /// found in, this parses the problem file again on its own, and checks if that fails.
+    fn parser_error_path(&self) -> Option<PathBuf> {
+        if self.standalone {
+            return Some(PathBuf::from(&self.proof_file));
+        }
+        if self.has_preludes || self.proof_file == "-" {
+            return None;
+        }
+        let problem_path = match &self.problem_file {
+            Some(p) if p == "-" => return None,
+            Some(p) => PathBuf::from(p),
+            None => infer_problem_path(&self.proof_file).ok()?,
+        };
+        let problem: Box<dyn BufRead> =
+            Box::new(io::BufReader::new(File::open(&problem_path).ok()?));
+        let empty_proof: Box<dyn BufRead> = Box::new(io::empty());
+        let problem_is_valid = parser::parse_instance_with_problems(
+            vec![problem],
+            empty_proof,
+            self.parsing.config(),
+            1,
+        )
+        .is_ok();
+        Some(if problem_is_valid {
+            PathBuf::from(&self.proof_file)
+        } else {
+            problem_path
+        })
+    }
+}
+
+/// Reads a file where an error happened, decompressing it if needed.
+fn read_source(path: &Path) -> Option<String> {
+    let file = io::BufReader::new(File::open(path).ok()?);
+    let mut source = String::new();
+    parser::decompress(file)
+        .ok()?
+        .read_to_string(&mut source)
+        .ok()?;
+    Some(source)
+}
+
+/// Reports an error that made a command fail. If the error has a position in one of the files read
+/// by the command, it is printed with an excerpt of the file. Otherwise, it is logged as usual.
+pub fn report_error(error: &CliError, sources: Option<&ErrorSources>) {
+    let rendered = match (error, sources) {
+        (CliError::CarcaraError(e), Some(sources)) => sources.render(e),
+        _ => None,
+    };
+    match rendered {
+        // The diagnostics are printed directly, so the log level must be checked here
+        Some(r) if log::log_enabled!(log::Level::Error) => eprint!("{}", r),
+        Some(_) => (),
+        None => log::error!("{}", error),
+    }
+}
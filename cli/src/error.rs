@@ -1,3 +1,4 @@
+use carcara::benchmarking::corpus::CorpusError;
 use std::{fmt, io, path::PathBuf};
 
 #[derive(Debug)]
@@ -5,6 +6,14 @@ pub enum CliError {
     CarcaraError(carcara::Error),
     CantInferProblemFile(PathBuf),
     BothFilesStdin,
+    StdinNotSupported,
+    FailedToRunChecker(String, io::Error),
+    NoConfigFile,
+    InvalidConfigFile(PathBuf, toml::de::Error),
+    UnknownProfile(String, PathBuf),
+    UnknownRule(String),
+    VerificationFailed(carcara::Error),
+    MemoryLimitTooLarge(u64),
 }
 
 pub type CliResult<T> = Result<T, CliError>;
@@ -15,6 +24,15 @@ impl From<io::Error> for CliError {
     }
 }
 
+impl From<CorpusError> for CliError {
+    fn from(e: CorpusError) -> Self {
+        match e {
+            CorpusError::Io(e) => e.into(),
+            CorpusError::CantInferProblemFile(p) => Self::CantInferProblemFile(p),
+        }
+    }
+}
+
 impl From<carcara::Error> for CliError {
     fn from(e: carcara::Error) -> Self {
         Self::CarcaraError(e)
@@ -29,6 +47,26 @@ impl fmt::Display for CliError {
                 write!(f, "can't infer problem file: {}", p.display())
             }
             CliError::BothFilesStdin => write!(f, "problem and proof files can't both be `-`"),
+            CliError::StdinNotSupported => {
+                write!(f, "reading from stdin is not supported in this command")
+            }
+            CliError::FailedToRunChecker(path, e) => {
+                write!(f, "failed to run external checker '{}': {}", path, e)
+            }
+            CliError::NoConfigFile => write!(f, "couldn't find config file `carcara.toml`"),
+            CliError::InvalidConfigFile(p, e) => {
+                write!(f, "invalid config file {}: {}", p.display(), e)
+            }
+            CliError::UnknownProfile(name, p) => {
+                write!(f, "profile '{}' not found in {}", name, p.display())
+            }
+            CliError::UnknownRule(name) => write!(f, "no explanation for rule '{}'", name),
+            CliError::VerificationFailed(e) => {
+                write!(f, "output proof failed verification: {}", e)
+            }
+            CliError::MemoryLimitTooLarge(mb) => {
+                write!(f, "memory limit of {} megabytes is too large", mb)
+            }
         }
     }
 }
@@ -0,0 +1,451 @@
+use ahash::AHashSet;
+use carcara::{
+    ast::{Operator, Proof, ProofArg, ProofCommand, Quantifier, Rc, Sort, Term, Terminal},
+    checker::{current_rule_name, ProofChecker},
+    num::{Integer, Rational},
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// A theory of SMT-LIB used by the terms of a proof. The core theory, of booleans and equality,
+/// is used by every proof, so it is not listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theory {
+    UninterpretedFunctions,
+    Ints,
+    Reals,
+    Arrays,
+    BitVectors,
+    Strings,
+    FloatingPoint,
+}
+
+impl fmt::Display for Theory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Theory::UninterpretedFunctions => "uninterpreted functions",
+            Theory::Ints => "integers",
+            Theory::Reals => "reals",
+            Theory::Arrays => "arrays",
+            Theory::BitVectors => "bitvectors",
+            Theory::Strings => "strings",
+            Theory::FloatingPoint => "floating-point",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Whether Carcara can check the steps that use a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleStatus {
+    /// The rule is implemented by the checker.
+    Implemented,
+
+    /// The rule is only checked by calling an external solver, if that is enabled. Otherwise, its
+    /// steps are treated as holes.
+    External,
+
+    /// The rule always checks as valid, and marks a hole in the proof.
+    Hole,
+
+    /// The rule is not known by the checker, so its steps are errors, unless unknown rules are
+    /// skipped.
+    Unknown,
+}
+
+impl fmt::Display for RuleStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            RuleStatus::Implemented => "implemented",
+            RuleStatus::External => "needs an external solver",
+            RuleStatus::Hole => "hole",
+            RuleStatus::Unknown => "not implemented",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// The uses of a rule in a proof.
+#[derive(Debug, Serialize)]
+pub struct RuleUsage {
+    /// The number of steps that use the rule.
+    pub steps: usize,
+
+    pub status: RuleStatus,
+
+    /// If the rule is the old name of a renamed rule, the current name, by which it is checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_to: Option<&'static str>,
+}
+
+impl RuleUsage {
+    fn new(rule: &str) -> Self {
+        let renamed_to = current_rule_name(rule);
+        let status = match renamed_to.unwrap_or(rule) {
+            "hole" | "trust" => RuleStatus::Hole,
+            "lia_generic" => RuleStatus::External,
+            name if ProofChecker::get_rule(name, false).is_some() => RuleStatus::Implemented,
+            _ => RuleStatus::Unknown,
+        };
+        Self { steps: 0, status, renamed_to }
+    }
+}
+
+/// The smallest and largest values of a kind of numeral in a proof.
+#[derive(Debug, Clone)]
+pub struct Range<T> {
+    pub min: T,
+    pub max: T,
+}
+
+impl<T: Ord + Clone> Range<T> {
+    fn extend(range: &mut Option<Self>, value: &T) {
+        match range {
+            Some(r) if *value < r.min => r.min = value.clone(),
+            Some(r) if *value > r.max => r.max = value.clone(),
+            Some(_) => (),
+            None => {
+                *range = Some(Range {
+                    min: value.clone(),
+                    max: value.clone(),
+                });
+            }
+        }
+    }
+}
+
+impl<T: fmt::Display> Serialize for Range<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Numerals may not fit in a JSON number, so they are serialized as strings
+        #[derive(Serialize)]
+        struct Bounds {
+            min: String,
+            max: String,
+        }
+        Bounds {
+            min: self.min.to_string(),
+            max: self.max.to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Range<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} to {}", self.min, self.max)
+    }
+}
+
+/// The number of distinct terms that use each binder, and the structure of the subproofs.
+#[derive(Debug, Default, Serialize)]
+pub struct BinderCounts {
+    pub forall: usize,
+    pub exists: usize,
+    pub choice: usize,
+    pub r#let: usize,
+    pub lambda: usize,
+    pub subproofs: usize,
+
+    /// The number of subproofs whose anchor binds variables or assigns terms to them.
+    pub subproofs_with_args: usize,
+
+    /// The maximum nesting depth of subproofs, which is zero if the proof has no subproofs.
+    pub max_subproof_depth: usize,
+}
+
+/// The features used by a proof: the theories of its terms, the rules of its steps, its binders,
+/// and the ranges of its numerals. These can be used to predict whether Carcara can check a proof,
+/// without running the check.
+#[derive(Debug, Default, Serialize)]
+pub struct ProofFeatures {
+    pub theories: BTreeSet<Theory>,
+    pub rules: BTreeMap<String, RuleUsage>,
+
+    /// Whether every rule used by the proof is known by the checker, including holes and rules
+    /// checked by an external solver. If this is false, the proof can't be checked, unless unknown
+    /// rules are skipped.
+    pub all_rules_implemented: bool,
+
+    pub binders: BinderCounts,
+    pub integer_constants: Option<Range<Integer>>,
+    pub real_constants: Option<Range<Rational>>,
+
+    /// The widths of the bitvector sorts and constants.
+    pub bitvector_widths: BTreeSet<u64>,
+
+    /// The exponent and significand widths of the floating-point sorts.
+    pub floating_point_formats: BTreeSet<(u64, u64)>,
+}
+
+impl ProofFeatures {
+    pub fn new(proof: &Proof) -> Self {
+        let mut features = Self::default();
+        let mut visited = AHashSet::new();
+        let mut iter = proof.iter();
+        while let Some(command) = iter.next() {
+            match command {
+                ProofCommand::Assume { term, .. } => features.visit(term, &mut visited),
+                ProofCommand::Step(step) => {
+                    features
+                        .rules
+                        .entry(step.rule.clone())
+                        .or_insert_with(|| RuleUsage::new(&step.rule))
+                        .steps += 1;
+                    for term in &step.clause {
+                        features.visit(term, &mut visited);
+                    }
+                    for arg in &step.args {
+                        let (ProofArg::Term(term) | ProofArg::Assign(_, term)) = arg;
+                        features.visit(term, &mut visited);
+                    }
+                }
+                ProofCommand::Subproof(s) => {
+                    let binders = &mut features.binders;
+                    binders.subproofs += 1;
+                    binders.max_subproof_depth = binders.max_subproof_depth.max(iter.depth());
+                    if !s.assignment_args.is_empty() || !s.variable_args.is_empty() {
+                        binders.subproofs_with_args += 1;
+                    }
+                    for (_, term) in &s.assignment_args {
+                        features.visit(term, &mut visited);
+                    }
+                    for (_, sort) in &s.variable_args {
+                        features.visit_sort(sort);
+                    }
+                }
+            }
+        }
+        features.all_rules_implemented = features
+            .rules
+            .values()
+            .all(|usage| usage.status != RuleStatus::Unknown);
+        features
+    }
+
+    /// Records the features used by a term and its subterms. Since terms are hash-consed, each
+    /// distinct subterm is only visited once, and binders are counted once per distinct term.
+    fn visit(&mut self, term: &Rc<Term>, visited: &mut AHashSet<Rc<Term>>) {
+        if !visited.insert(term.clone()) {
+            return;
+        }
+        match &**term {
+            Term::Terminal(t) => match t {
+                Terminal::Integer(i) => Range::extend(&mut self.integer_constants, i),
+                Terminal::Real(r) => Range::extend(&mut self.real_constants, r),
+                Terminal::String(_) => {
+                    self.theories.insert(Theory::Strings);
+                }
+                Terminal::BitVec(_, width) => {
+                    self.theories.insert(Theory::BitVectors);
+                    self.bitvector_widths.insert(*width);
+                }
+                Terminal::Var(_, sort) => self.visit_sort(sort),
+            },
+            Term::App(f, args) => {
+                self.theories.insert(Theory::UninterpretedFunctions);
+                self.visit(f, visited);
+                for a in args {
+                    self.visit(a, visited);
+                }
+            }
+            Term::Op(op, args) => {
+                self.theories.extend(operator_theories(*op));
+                for a in args {
+                    self.visit(a, visited);
+                }
+            }
+            Term::IndexedOp(_, _, args) => {
+                // The theory of an indexed operator is also the theory of its result or of its
+                // arguments, so it is found when visiting them
+                for a in args {
+                    self.visit(a, visited);
+                }
+            }
+            Term::Quant(quantifier, bindings, inner, patterns) => {
+                match quantifier {
+                    Quantifier::Forall => self.binders.forall += 1,
+                    Quantifier::Exists => self.binders.exists += 1,
+                }
+                for (_, sort) in bindings.iter() {
+                    self.visit_sort(sort);
+                }
+                self.visit(inner, visited);
+                for p in patterns.iter().flatten() {
+                    self.visit(p, visited);
+                }
+            }
+            Term::Choice((_, sort), inner) => {
+                self.binders.choice += 1;
+                self.visit_sort(sort);
+                self.visit(inner, visited);
+            }
+            Term::Let(bindings, inner) => {
+                self.binders.r#let += 1;
+                for (_, value) in bindings.iter() {
+                    self.visit(value, visited);
+                }
+                self.visit(inner, visited);
+            }
+            Term::Lambda(bindings, inner) => {
+                self.binders.lambda += 1;
+                for (_, sort) in bindings.iter() {
+                    self.visit_sort(sort);
+                }
+                self.visit(inner, visited);
+            }
+        }
+    }
+
+    /// Records the theories used by a sort and the sorts it is built from.
+    fn visit_sort(&mut self, sort: &Sort) {
+        let theory = match sort {
+            Sort::Function(sorts) => {
+                for s in sorts {
+                    self.visit_sort(s);
+                }
+                Some(Theory::UninterpretedFunctions)
+            }
+            Sort::Atom(_, args) => {
+                for a in args {
+                    self.visit_sort(a);
+                }
+                Some(Theory::UninterpretedFunctions)
+            }
+            Sort::Array(index, element) => {
+                self.visit_sort(index);
+                self.visit_sort(element);
+                Some(Theory::Arrays)
+            }
+            Sort::Bool => None,
+            Sort::Int => Some(Theory::Ints),
+            Sort::Real => Some(Theory::Reals),
+            Sort::String | Sort::RegLan => Some(Theory::Strings),
+            Sort::BitVec(width) => {
+                self.bitvector_widths.insert(*width);
+                Some(Theory::BitVectors)
+            }
+            Sort::FloatingPoint(exponent, significand) => {
+                self.floating_point_formats
+                    .insert((*exponent, *significand));
+                Some(Theory::FloatingPoint)
+            }
+            Sort::RoundingMode => Some(Theory::FloatingPoint),
+        };
+        self.theories.extend(theory);
+    }
+}
+
+/// Returns the theories an operator belongs to. Arithmetic operators are not assigned a theory,
+/// since they can be used with both integers and reals, which is found from their arguments.
+fn operator_theories(op: Operator) -> &'static [Theory] {
+    use Operator::*;
+    match op {
+        ToReal | ToInt | IsInt => &[Theory::Ints, Theory::Reals],
+        Select | Store => &[Theory::Arrays],
+        Concat | BvNot | BvNeg | BvAnd | BvOr | BvXor | BvNand | BvNor | BvXnor | BvComp
+        | BvAdd | BvSub | BvMul | BvUDiv | BvURem | BvSDiv | BvSRem | BvSMod | BvShl | BvLShr
+        | BvAShr | BvULt | BvULe | BvUGt | BvUGe | BvSLt | BvSLe | BvSGt | BvSGe => {
+            &[Theory::BitVectors]
+        }
+        StrConcat | StrLen | StrLt | StrLe | StrAt | StrSubstr | StrPrefixOf | StrSuffixOf
+        | StrContains | StrIndexOf | StrReplace | StrReplaceAll | StrReplaceRe
+        | StrReplaceReAll | StrIsDigit | StrToCode | StrFromCode | StrToInt | StrFromInt
+        | StrToRe | StrInRe | ReNone | ReAll | ReAllChar | ReConcat | ReUnion | ReInter
+        | ReStar | RePlus | ReOpt | ReRange | ReComp | ReDiff => &[Theory::Strings],
+        Fp
+        | FpAbs
+        | FpNeg
+        | FpAdd
+        | FpSub
+        | FpMul
+        | FpDiv
+        | FpFma
+        | FpSqrt
+        | FpRem
+        | FpRoundToIntegral
+        | FpMin
+        | FpMax
+        | FpLeq
+        | FpLt
+        | FpGeq
+        | FpGt
+        | FpEq
+        | FpIsNormal
+        | FpIsSubnormal
+        | FpIsZero
+        | FpIsInfinite
+        | FpIsNaN
+        | FpIsNegative
+        | FpIsPositive
+        | FpToReal
+        | RoundNearestTiesToEven
+        | RoundNearestTiesToAway
+        | RoundTowardPositive
+        | RoundTowardNegative
+        | RoundTowardZero => &[Theory::FloatingPoint],
+        Not | Implies | And | Or | Xor | Equals | Distinct | Ite | Add | Sub | Mult | IntDiv
+        | RealDiv | Mod | Abs | LessThan | GreaterThan | LessEq | GreaterEq => &[],
+    }
+}
+
+impl fmt::Display for ProofFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theories: Vec<_> = self.theories.iter().map(Theory::to_string).collect();
+        if theories.is_empty() {
+            writeln!(f, "theories: core only")?;
+        } else {
+            writeln!(f, "theories: {}", theories.join(", "))?;
+        }
+
+        writeln!(f, "rules:")?;
+        for (rule, usage) in &self.rules {
+            write!(
+                f,
+                "  {:<24}{:>8} steps  {}",
+                rule, usage.steps, usage.status
+            )?;
+            match usage.renamed_to {
+                Some(new_name) => writeln!(f, " (renamed to '{}')", new_name)?,
+                None => writeln!(f)?,
+            }
+        }
+        writeln!(f, "all rules implemented: {}", self.all_rules_implemented)?;
+
+        let b = &self.binders;
+        writeln!(
+            f,
+            "binders: {} forall, {} exists, {} choice, {} let, {} lambda",
+            b.forall, b.exists, b.choice, b.r#let, b.lambda
+        )?;
+        writeln!(
+            f,
+            "subproofs: {} ({} with arguments), max depth {}",
+            b.subproofs, b.subproofs_with_args, b.max_subproof_depth
+        )?;
+
+        if let Some(range) = &self.integer_constants {
+            writeln!(f, "integer constants: {}", range)?;
+        }
+        if let Some(range) = &self.real_constants {
+            writeln!(f, "real constants: {}", range)?;
+        }
+        if !self.bitvector_widths.is_empty() {
+            let widths: Vec<_> = self.bitvector_widths.iter().map(u64::to_string).collect();
+            writeln!(f, "bitvector widths: {}", widths.join(", "))?;
+        }
+        if !self.floating_point_formats.is_empty() {
+            let formats: Vec<_> = self
+                .floating_point_formats
+                .iter()
+                .map(|(e, s)| format!("({} {})", e, s))
+                .collect();
+            writeln!(f, "floating-point formats: {}", formats.join(", "))?;
+        }
+        Ok(())
+    }
+}
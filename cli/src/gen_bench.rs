@@ -0,0 +1,200 @@
+//! A generator for large synthetic problems and proofs, used to create stress tests for the parser
+//! and the term pool. The output only depends on the generator options, including the seed, so the
+//! same options always produce the same files.
+//!
+//! The generated problem declares a number of boolean, integer and function symbols, and asserts
+//! a number of random formulas, as well as a contradictory pair of assertions. The proof assumes
+//! every assertion, adds a reflexivity step for each formula, and derives the empty clause from the
+//! contradictory pair, so it can also be checked.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{fmt::Write as _, io};
+
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    pub num_assertions: usize,
+    pub max_depth: usize,
+    pub num_symbols: usize,
+    pub max_numeral_digits: usize,
+}
+
+struct Generator {
+    rng: StdRng,
+    options: GeneratorOptions,
+}
+
+impl Generator {
+    fn bool_symbol(&mut self) -> String {
+        format!("p{}", self.rng.gen_range(0..self.options.num_symbols))
+    }
+
+    fn int_symbol(&mut self) -> String {
+        format!("x{}", self.rng.gen_range(0..self.options.num_symbols))
+    }
+
+    fn numeral(&mut self) -> String {
+        let num_digits = self.rng.gen_range(1..=self.options.max_numeral_digits);
+        let mut result = self.rng.gen_range(1..=9).to_string();
+        for _ in 1..num_digits {
+            result.push(char::from(b'0' + self.rng.gen_range(0..10)));
+        }
+        result
+    }
+
+    /// Writes a sequence of between `min` and `max` terms, each preceded by a space.
+    fn args(
+        &mut self,
+        out: &mut String,
+        min: usize,
+        max: usize,
+        f: fn(&mut Self, &mut String, usize),
+        depth: usize,
+    ) {
+        for _ in 0..self.rng.gen_range(min..=max) {
+            out.push(' ');
+            f(self, out, depth);
+        }
+    }
+
+    fn bool_term(&mut self, out: &mut String, depth: usize) {
+        if depth == 0 {
+            let symbol = self.bool_symbol();
+            out.push_str(&symbol);
+            return;
+        }
+        let depth = self.rng.gen_range(0..depth);
+        match self.rng.gen_range(0..8) {
+            0 => {
+                out.push_str("(not ");
+                self.bool_term(out, depth);
+            }
+            1 => {
+                out.push_str("(and");
+                self.args(out, 2, 4, Self::bool_term, depth);
+            }
+            2 => {
+                out.push_str("(or");
+                self.args(out, 2, 4, Self::bool_term, depth);
+            }
+            3 => {
+                out.push_str("(=>");
+                self.args(out, 2, 2, Self::bool_term, depth);
+            }
+            4 => {
+                out.push_str("(=");
+                self.args(out, 2, 2, Self::int_term, depth);
+            }
+            5 => {
+                out.push_str("(<=");
+                self.args(out, 2, 2, Self::int_term, depth);
+            }
+            6 => {
+                out.push_str("(ite ");
+                self.bool_term(out, depth);
+                self.args(out, 2, 2, Self::bool_term, depth);
+            }
+            _ => {
+                let i = self.rng.gen_range(0..self.options.num_symbols);
+                write!(out, "(q{} ", i).unwrap();
+                self.int_term(out, depth);
+            }
+        }
+        out.push(')');
+    }
+
+    fn int_term(&mut self, out: &mut String, depth: usize) {
+        if depth == 0 {
+            let leaf = if self.rng.gen_bool(0.5) {
+                self.int_symbol()
+            } else {
+                self.numeral()
+            };
+            out.push_str(&leaf);
+            return;
+        }
+        let depth = self.rng.gen_range(0..depth);
+        match self.rng.gen_range(0..5) {
+            0 => {
+                out.push_str("(+");
+                self.args(out, 2, 4, Self::int_term, depth);
+            }
+            1 => {
+                out.push_str("(-");
+                self.args(out, 1, 2, Self::int_term, depth);
+            }
+            2 => {
+                out.push_str("(*");
+                self.args(out, 2, 2, Self::int_term, depth);
+            }
+            3 => {
+                out.push_str("(ite ");
+                self.bool_term(out, depth);
+                self.args(out, 2, 2, Self::int_term, depth);
+            }
+            _ => {
+                let i = self.rng.gen_range(0..self.options.num_symbols);
+                write!(out, "(f{}", i).unwrap();
+                self.args(out, 2, 2, Self::int_term, depth);
+            }
+        }
+        out.push(')');
+    }
+}
+
+/// Generates a problem and its proof, and writes them to `problem` and `proof`.
+pub fn generate(
+    options: GeneratorOptions,
+    problem: &mut dyn io::Write,
+    proof: &mut dyn io::Write,
+) -> io::Result<()> {
+    // The generator needs at least one symbol of each kind, and numerals need at least one digit
+    let options = GeneratorOptions {
+        num_symbols: options.num_symbols.max(1),
+        max_numeral_digits: options.max_numeral_digits.max(1),
+        ..options
+    };
+    let mut generator = Generator {
+        rng: StdRng::seed_from_u64(options.seed),
+        options,
+    };
+
+    writeln!(problem, "(set-logic QF_UFLIA)")?;
+    for i in 0..options.num_symbols {
+        writeln!(problem, "(declare-fun p{} () Bool)", i)?;
+        writeln!(problem, "(declare-fun x{} () Int)", i)?;
+        writeln!(problem, "(declare-fun f{} (Int Int) Int)", i)?;
+        writeln!(problem, "(declare-fun q{} (Int) Bool)", i)?;
+    }
+
+    let mut formulas = Vec::with_capacity(options.num_assertions);
+    for _ in 0..options.num_assertions {
+        let mut formula = String::new();
+        generator.bool_term(&mut formula, options.max_depth);
+        writeln!(problem, "(assert {})", formula)?;
+        formulas.push(formula);
+    }
+    writeln!(problem, "(assert p0)")?;
+    writeln!(problem, "(assert (not p0))")?;
+    writeln!(problem, "(check-sat)")?;
+    writeln!(problem, "(exit)")?;
+
+    for (i, formula) in formulas.iter().enumerate() {
+        writeln!(proof, "(assume a{} {})", i, formula)?;
+    }
+    writeln!(proof, "(assume h1 p0)")?;
+    writeln!(proof, "(assume h2 (not p0))")?;
+    for (i, formula) in formulas.iter().enumerate() {
+        writeln!(
+            proof,
+            "(step t{} (cl (= {} {})) :rule eq_reflexive)",
+            i, formula, formula
+        )?;
+    }
+    writeln!(
+        proof,
+        "(step t{} (cl) :rule resolution :premises (h1 h2))",
+        formulas.len()
+    )?;
+    Ok(())
+}
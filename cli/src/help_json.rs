@@ -0,0 +1,80 @@
+//! A machine-readable description of the command line interface, built from the same clap model
+//! used to parse the arguments. This allows wrapper tools to introspect the available subcommands
+//! and flags.
+
+use clap::{Arg, Command};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct CommandModel {
+    name: String,
+    about: Option<String>,
+    args: Vec<ArgModel>,
+    subcommands: Vec<CommandModel>,
+}
+
+#[derive(Serialize)]
+pub struct ArgModel {
+    name: String,
+    long: Option<String>,
+    short: Option<char>,
+    help: Option<String>,
+    positional: bool,
+    takes_value: bool,
+    multiple: bool,
+    required: bool,
+    global: bool,
+    possible_values: Vec<String>,
+    default_values: Vec<String>,
+}
+
+impl From<&Arg<'_>> for ArgModel {
+    fn from(arg: &Arg) -> Self {
+        // Arguments derived with `arg_enum` declare their possible values directly, instead of
+        // through the value parser
+        let possible_values = match arg.get_value_parser().possible_values() {
+            Some(values) => values.map(|v| v.get_name().to_owned()).collect(),
+            None => (arg.get_possible_values().unwrap_or_default().iter())
+                .map(|v| v.get_name().to_owned())
+                .collect(),
+        };
+        Self {
+            name: arg.get_id().to_owned(),
+            long: arg.get_long().map(ToOwned::to_owned),
+            short: arg.get_short(),
+            help: arg.get_help().map(ToOwned::to_owned),
+            positional: arg.is_positional(),
+            takes_value: arg.is_takes_value_set(),
+            multiple: arg.is_multiple_occurrences_set() || arg.is_multiple_values_set(),
+            required: arg.is_required_set(),
+            global: arg.is_global_set(),
+            possible_values,
+            default_values: arg
+                .get_default_values()
+                .iter()
+                .map(|v| v.to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+}
+
+impl From<&Command<'_>> for CommandModel {
+    fn from(command: &Command) -> Self {
+        Self {
+            name: command.get_name().to_owned(),
+            about: command.get_about().map(ToOwned::to_owned),
+            args: command
+                .get_arguments()
+                .filter(|a| !a.is_hide_set())
+                .map(ArgModel::from)
+                .collect(),
+            subcommands: command.get_subcommands().map(CommandModel::from).collect(),
+        }
+    }
+}
+
+/// Returns a JSON description of all subcommands and arguments accepted by `command`.
+pub fn to_json(command: &Command) -> String {
+    // Serializing this model can't fail, since it only contains strings, booleans and sequences
+    serde_json::to_string_pretty(&CommandModel::from(command)).unwrap()
+}
@@ -1,8 +1,90 @@
 use ansi_term::{ANSIString, Color, Style};
 use log::{Level, LevelFilter, Log, Metadata, Record};
+use regex::Regex;
+use std::{env, str::FromStr};
+
+/// The environment variable used to set the logging level for specific modules. It contains a
+/// comma-separated list of directives, each of the form `level` or `module=level`. A directive with
+/// only a level overrides the level set by the `--log` flag. For example,
+/// `CARCARA_LOG=carcara::checker::rules::quantifier=trace` enables trace logging only for the
+/// quantifier rules.
+pub const LOG_ENV_VAR: &str = "CARCARA_LOG";
+
+/// The environment variable used to only show messages emitted while checking steps whose id
+/// matches a regex. Messages emitted outside of any step are not affected.
+pub const LOG_STEPS_ENV_VAR: &str = "CARCARA_LOG_STEPS";
+
+struct Filter {
+    default: LevelFilter,
+
+    /// The module-specific levels. These are sorted by decreasing module path length, so the first
+    /// directive that matches a target is also the most specific one.
+    directives: Vec<(String, LevelFilter)>,
+
+    steps: Option<Regex>,
+}
+
+impl Filter {
+    /// Builds a filter from the default level and the logging environment variables. Returns the
+    /// filter, and a list of errors encountered while parsing the environment variables.
+    fn from_env(default: LevelFilter) -> (Self, Vec<String>) {
+        let mut filter = Filter {
+            default,
+            directives: Vec::new(),
+            steps: None,
+        };
+        let mut errors = Vec::new();
+
+        if let Ok(spec) = env::var(LOG_ENV_VAR) {
+            for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+                let (module, level) = match directive.split_once('=') {
+                    Some((module, level)) => (Some(module.trim()), level.trim()),
+                    None => (None, directive),
+                };
+                match (module, LevelFilter::from_str(level)) {
+                    (None, Ok(level)) => filter.default = level,
+                    (Some(module), Ok(level)) => filter.directives.push((module.to_owned(), level)),
+                    (_, Err(_)) => errors.push(format!(
+                        "invalid directive in `{}`: '{}'",
+                        LOG_ENV_VAR, directive
+                    )),
+                }
+            }
+            filter
+                .directives
+                .sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        }
+
+        if let Ok(pattern) = env::var(LOG_STEPS_ENV_VAR) {
+            match Regex::new(&pattern) {
+                Ok(r) => filter.steps = Some(r),
+                Err(e) => errors.push(format!("invalid regex in `{}`: {}", LOG_STEPS_ENV_VAR, e)),
+            }
+        }
+
+        (filter, errors)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let is_match = |module: &str| {
+            target == module
+                || (target.starts_with(module) && target[module.len()..].starts_with("::"))
+        };
+        self.directives
+            .iter()
+            .find(|(module, _)| is_match(module))
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        let directives = self.directives.iter().map(|&(_, level)| level);
+        directives.fold(self.default, Ord::max)
+    }
+}
 
 pub struct Logger {
     colors_enabled: bool,
+    filter: Filter,
 }
 
 impl Logger {
@@ -24,18 +106,37 @@ impl Logger {
 }
 
 impl Log for Logger {
-    fn enabled(&self, _: &Metadata) -> bool {
-        true
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
     }
 
     fn log(&self, record: &Record) {
-        eprintln!("{} {}", self.prefix(record.level()), record.args());
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match carcara::checker::current_step() {
+            Some(step) => {
+                if let Some(steps) = &self.filter.steps {
+                    if !steps.is_match(&step) {
+                        return;
+                    }
+                }
+                let prefix = self.prefix(record.level());
+                eprintln!("{} (step '{}') {}", prefix, step, record.args());
+            }
+            None => eprintln!("{} {}", self.prefix(record.level()), record.args()),
+        }
     }
 
     fn flush(&self) {}
 }
 
-pub fn init(max_level: LevelFilter, colors_enabled: bool) {
-    log::set_boxed_logger(Box::new(Logger { colors_enabled })).expect("couldn't set up logger");
-    log::set_max_level(max_level);
+pub fn init(default_level: LevelFilter, colors_enabled: bool) {
+    let (filter, errors) = Filter::from_env(default_level);
+    log::set_max_level(filter.max_level());
+    log::set_boxed_logger(Box::new(Logger { colors_enabled, filter }))
+        .expect("couldn't set up logger");
+    for e in errors {
+        log::warn!("{}", e);
+    }
 }
@@ -1,22 +1,52 @@
 mod benchmarking;
+mod cache;
+mod config;
+mod cross_check;
+mod diagnostics;
 mod error;
+mod features;
+mod gen_bench;
+mod help_json;
 mod logger;
 mod path_args;
+mod report;
+mod slowest;
+mod stats;
 
 use carcara::{
-    ast::print_proof,
-    benchmarking::{Metrics, OnlineBenchmarkResults},
-    check, check_and_elaborate, generate_lia_smt_instances, parser, compress, CarcaraOptions,
+    ast::{print_proof, write_alethe_proof, write_proof, write_tstp_proof, ProofCommand},
+    benchmarking::{
+        CsvBenchmarkResults, Metrics, OnlineBenchmarkResults, OnlineMetrics, SampleFormat,
+        SampleRecorder,
+    },
+    checker::{
+        random_testing::find_counterexamples, rule_info, ErrorCategory, SamplingConfig, Severity,
+        SeverityLevels,
+    },
+    check_and_elaborate, check_lemma, check_with_problems, generate_lia_smt_instances, parser,
+    check, compress, CarcaraOptions,
 };
-use clap::{AppSettings, ArgEnum, Args, Parser, Subcommand};
+use ahash::AHashMap;
+use cache::ResultsCache;
+use clap::{AppSettings, ArgEnum, Args, CommandFactory, ErrorKind, Parser, Subcommand};
+use clap_complete::Shell;
+use config::{Preset, Profile};
 use const_format::{formatcp, str_index};
+use cross_check::{cross_check, ExternalChecker};
+use diagnostics::{report_error, ErrorSources};
 use error::{CliError, CliResult};
+use features::ProofFeatures;
 use git_version::git_version;
 use path_args::{get_instances_from_paths, infer_problem_path};
+use report::{write_report, ReportTarget};
+use serde::Deserialize;
+use stats::{write_comparisons_csv, ProofComparison, ProofStats, ProofSummary};
 use std::{
+    collections::BTreeMap,
     fs::File,
-    io::{self, BufRead},
-    path::Path,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 // `git describe --all` will try to find any ref (including tags) that describes the current commit.
@@ -46,15 +76,37 @@ const VERSION_STRING: &str = formatcp!(
 )]
 struct Cli {
     #[clap(subcommand)]
-    command: Command,
+    command: Option<Command>,
 
-    /// Sets the maximum logging level.
+    /// Prints a machine-readable description of all subcommands and arguments, in JSON.
+    #[clap(long)]
+    help_json: bool,
+
+    /// Sets the maximum logging level. More specific levels can be set with the `CARCARA_LOG`
+    /// environment variable, and messages can be restricted to certain steps with
+    /// `CARCARA_LOG_STEPS`.
     #[clap(arg_enum, global = true, long = "log", default_value_t = LogLevel::Warn)]
     log_level: LogLevel,
 
     /// Disables output coloring.
     #[clap(global = true, long)]
     no_color: bool,
+
+    /// Uses the options defined in a named profile in the config file. Flags enabled in the
+    /// profile can't be disabled from the command line, but other values given in the command
+    /// line take precedence.
+    #[clap(global = true, long)]
+    profile: Option<String>,
+
+    /// The config file from which profiles are loaded. If this argument is not present, the
+    /// checker will look for a `carcara.toml` file in the current directory and its ancestors.
+    #[clap(global = true, long, requires = "profile")]
+    config: Option<PathBuf>,
+
+    /// Uses a built-in set of options suited to the proofs produced by a given solver. This can be
+    /// combined with `--profile`, and works in the same way.
+    #[clap(arg_enum, global = true, long)]
+    preset: Option<Preset>,
 }
 
 #[derive(Subcommand)]
@@ -75,7 +127,46 @@ enum Command {
     GenerateLiaProblems(ParseCommandOptions),
 
     /// Compresses a proof file.
-    Compress(CheckCommandOptions),
+    Compress(CompressCommandOptions),
+
+    /// Checks a proof file using both Carcara and an external checker, and compares the results
+    /// step by step.
+    CrossCheck(CrossCheckCommandOptions),
+
+    /// Computes statistics about the shape of a proof, such as the distribution of clause lengths.
+    Stats(StatsCommandOptions),
+
+    /// Reports the theories, rules, binders and numerals used by a proof, and whether all of its
+    /// rules are implemented. This can be used to predict whether a proof can be checked, without
+    /// running the check.
+    Features(FeaturesCommandOptions),
+
+    /// Parses a proof file and prints it in a different proof format.
+    Export(ExportCommandOptions),
+
+    /// Evaluates the steps of a proof under random models, and reports any step whose premises
+    /// are true but whose conclusion is false.
+    RandomTest(RandomTestCommandOptions),
+
+    /// Generates a large synthetic problem and proof, to be used as a stress test for the parser.
+    GenBench(GenBenchCommandOptions),
+
+    /// Explains what a rule expects, with a small example of a valid step. If no rule is given,
+    /// lists all rules that can be explained.
+    Explain {
+        /// The name of the rule
+        rule: Option<String>,
+    },
+
+    /// Runs a single benchmark job in a worker process. Used internally by the `bench` command.
+    #[clap(hide = true)]
+    BenchWorker(BenchWorkerCommandOptions),
+
+    /// Generates a completion script for the given shell, and prints it to stdout.
+    Completions {
+        #[clap(arg_enum)]
+        shell: Shell,
+    },
 }
 
 #[derive(Args)]
@@ -105,9 +196,154 @@ struct ParsingOptions {
     /// to arithmetic operators that are expecting a term of sort `Real`.
     #[clap(long)]
     allow_int_real_subtyping: bool,
+
+    /// Promotes `Int` arguments of arithmetic operations and comparisons to `Real` when they are
+    /// mixed with `Real` arguments, like in `(+ x 1)` where `x` is a `Real`. Integer numerals are
+    /// converted to real numerals, and other terms are wrapped in `to_real`.
+    #[clap(long)]
+    coerce_int_to_real: bool,
+
+    /// The number of threads used to lex the proof. Using more than one thread can speed up the
+    /// parsing of very large proofs, but the whole proof is read into memory first.
+    #[clap(long, value_name = "N", default_value_t = 1)]
+    lexer_threads: usize,
+
+    /// How non-ASCII characters in symbols are handled.
+    #[clap(arg_enum, long, default_value_t = UnicodeSymbols::Reject)]
+    unicode_symbols: UnicodeSymbols,
+
+    /// Instead of stopping at the first error in the proof, skip to the next command and report
+    /// all errors at the end.
+    #[clap(long)]
+    recover_from_errors: bool,
+
+    /// Ignore attributes in annotated terms that are not known by the parser, like `:qid` or
+    /// `:weight`, instead of rejecting them.
+    #[clap(long)]
+    ignore_unknown_attributes: bool,
+
+    /// The solver that produced the proof. The small differences from the Alethe specification in
+    /// the proofs of this solver are normalized while parsing.
+    #[clap(arg_enum, long, default_value_t = ProofProducer::Generic)]
+    producer: ProofProducer,
+
+    /// The maximum number of parentheses that may be open at any point of the proof or problem.
+    /// Inputs that exceed this limit are rejected, which protects against stack overflows when
+    /// checking proofs from untrusted sources.
+    #[clap(long, value_name = "N")]
+    max_nesting_depth: Option<usize>,
+
+    /// The maximum number of commands in the proof.
+    #[clap(long, value_name = "N")]
+    max_commands: Option<usize>,
+
+    /// The maximum length of a single token, in bytes.
+    #[clap(long, value_name = "N")]
+    max_token_length: Option<usize>,
 }
 
-#[derive(Args, Clone, Copy)]
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum UnicodeSymbols {
+    /// Non-ASCII characters are only allowed in quoted symbols, as in the SMT-LIB standard.
+    Reject,
+
+    /// Non-ASCII characters are also allowed in simple symbols and keywords.
+    Accept,
+
+    /// Same as `accept`, but all symbols are normalized to the Unicode NFC form.
+    Normalize,
+}
+
+impl Default for UnicodeSymbols {
+    fn default() -> Self {
+        Self::Reject
+    }
+}
+
+impl From<UnicodeSymbols> for parser::UnicodePolicy {
+    fn from(value: UnicodeSymbols) -> Self {
+        match value {
+            UnicodeSymbols::Reject => Self::Reject,
+            UnicodeSymbols::Accept => Self::Accept,
+            UnicodeSymbols::Normalize => Self::Normalize,
+        }
+    }
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ProofProducer {
+    /// Any solver that follows the Alethe specification.
+    Generic,
+
+    /// cvc5. Upper case rule names, unknown attributes in annotated terms and choice terms written
+    /// with `witness` are accepted.
+    Cvc5,
+}
+
+impl Default for ProofProducer {
+    fn default() -> Self {
+        Self::Generic
+    }
+}
+
+impl From<ProofProducer> for parser::Producer {
+    fn from(value: ProofProducer) -> Self {
+        match value {
+            ProofProducer::Generic => Self::Generic,
+            ProofProducer::Cvc5 => Self::Cvc5,
+        }
+    }
+}
+
+impl ParsingOptions {
+    fn apply_profile(&mut self, profile: &Profile) {
+        self.apply_function_defs |= profile.apply_function_defs;
+        self.expand_let_bindings |= profile.expand_let_bindings;
+        self.allow_int_real_subtyping |= profile.allow_int_real_subtyping;
+        self.coerce_int_to_real |= profile.coerce_int_to_real;
+        self.recover_from_errors |= profile.recover_from_errors;
+        self.ignore_unknown_attributes |= profile.ignore_unknown_attributes;
+        if self.lexer_threads == 1 {
+            self.lexer_threads = profile.lexer_threads.unwrap_or(1);
+        }
+        if self.unicode_symbols == UnicodeSymbols::Reject {
+            self.unicode_symbols = profile.unicode_symbols;
+        }
+        if self.producer == ProofProducer::Generic {
+            self.producer = profile.producer;
+        }
+        self.max_nesting_depth = self.max_nesting_depth.or(profile.max_nesting_depth);
+        self.max_commands = self.max_commands.or(profile.max_commands);
+        self.max_token_length = self.max_token_length.or(profile.max_token_length);
+    }
+
+    fn limits(&self) -> parser::ResourceLimits {
+        parser::ResourceLimits {
+            max_nesting_depth: self.max_nesting_depth,
+            max_commands: self.max_commands,
+            max_token_length: self.max_token_length,
+        }
+    }
+
+    fn config(&self) -> parser::ParserConfig {
+        parser::ParserConfig {
+            apply_function_defs: self.apply_function_defs,
+            expand_lets: self.expand_let_bindings,
+            allow_int_real_subtyping: self.allow_int_real_subtyping,
+            coerce_int_to_real: self.coerce_int_to_real,
+            unicode_policy: self.unicode_symbols.into(),
+            recover_from_errors: self.recover_from_errors,
+            ignore_unknown_attributes: self.ignore_unknown_attributes,
+            producer: self.producer.into(),
+            limits: self.limits(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Args, Clone)]
 struct CheckingOptions {
     /// Enables the strict checking of certain rules.
     #[clap(short, long)]
@@ -120,6 +356,101 @@ struct CheckingOptions {
     /// Check `lia_generic` steps by calling into cvc5.
     #[clap(long)]
     lia_via_cvc5: bool,
+
+    /// Rejects proofs that write the same `choice` term in different ways, for example by
+    /// renaming its bound variable.
+    #[clap(long)]
+    audit_choice_terms: bool,
+
+    /// Accepts `trans` and `eq_transitive` steps whose premises imply the conclusion by
+    /// transitivity in any order, searching for the chain instead of following the premises
+    /// greedily.
+    #[clap(long)]
+    commutative_transitivity: bool,
+
+    /// Only checks a random sample of the steps of each rule, and treats the other steps as holes.
+    /// The rate can be given as a percentage, like `10%`, or as a fraction between 0 and 1.
+    #[clap(long, value_name = "RATE", value_parser = parse_sample_rate)]
+    sample: Option<f64>,
+
+    /// The seed used to choose which steps are checked when sampling.
+    #[clap(long, default_value_t = 0, requires = "sample")]
+    sample_seed: u64,
+
+    /// Reports errors of the given category as warnings, instead of making the proof invalid.
+    /// Proofs with such errors are considered holey if part of the proof could not be checked.
+    /// This flag can be given multiple times.
+    #[clap(arg_enum, long, value_name = "CATEGORY")]
+    warn: Vec<WarningCategory>,
+
+    /// Accepts the steps that use the given rule without checking them, and treats them as holes.
+    /// This flag can be given multiple times.
+    #[clap(long = "trust-rule", value_name = "RULE")]
+    trusted_rules: Vec<String>,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum WarningCategory {
+    /// A step uses a rule that is not known by the checker.
+    UnknownRule,
+
+    /// An `assume` command does not match any assertion in the problem.
+    Assume,
+
+    /// Two alpha-equivalent `choice` terms are written in different ways.
+    InconsistentChoiceTerms,
+
+    /// The proof does not conclude the empty clause.
+    NoEmptyClause,
+}
+
+impl From<WarningCategory> for ErrorCategory {
+    fn from(value: WarningCategory) -> Self {
+        match value {
+            WarningCategory::UnknownRule => Self::UnknownRule,
+            WarningCategory::Assume => Self::Assume,
+            WarningCategory::InconsistentChoiceTerms => Self::InconsistentChoiceTerms,
+            WarningCategory::NoEmptyClause => Self::NoEmptyClause,
+        }
+    }
+}
+
+fn parse_sample_rate(s: &str) -> Result<f64, String> {
+    let rate = match s.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => s.parse::<f64>(),
+    }
+    .map_err(|e| e.to_string())?;
+    if rate > 0.0 && rate <= 1.0 {
+        Ok(rate)
+    } else {
+        Err("the sample rate must be greater than 0% and at most 100%".to_owned())
+    }
+}
+
+impl CheckingOptions {
+    fn apply_profile(&mut self, profile: &Profile) {
+        self.strict |= profile.strict;
+        self.skip_unknown_rules |= profile.skip_unknown_rules;
+        self.lia_via_cvc5 |= profile.lia_via_cvc5;
+        self.audit_choice_terms |= profile.audit_choice_terms;
+        self.commutative_transitivity |= profile.commutative_transitivity;
+        self.sample = self.sample.or(profile.sample);
+        if self.sample_seed == 0 {
+            self.sample_seed = profile.sample_seed.unwrap_or(0);
+        }
+        for &category in &profile.warn {
+            if !self.warn.contains(&category) {
+                self.warn.push(category);
+            }
+        }
+        for rule in &profile.trusted_rules {
+            if !self.trusted_rules.contains(rule) {
+                self.trusted_rules.push(rule.clone());
+            }
+        }
+    }
 }
 
 #[derive(Args)]
@@ -134,20 +465,59 @@ fn build_carcara_options(
         apply_function_defs,
         expand_let_bindings,
         allow_int_real_subtyping,
+        coerce_int_to_real,
+        lexer_threads,
+        unicode_symbols,
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer,
+        max_nesting_depth,
+        max_commands,
+        max_token_length,
     }: ParsingOptions,
     CheckingOptions {
         strict,
         skip_unknown_rules,
         lia_via_cvc5,
+        audit_choice_terms,
+        commutative_transitivity,
+        sample,
+        sample_seed,
+        warn,
+        trusted_rules,
     }: CheckingOptions,
 ) -> CarcaraOptions {
+    let mut severities = SeverityLevels::default();
+    for category in warn {
+        severities.set(category.into(), Severity::Warning);
+    }
     CarcaraOptions {
         apply_function_defs,
         expand_lets: expand_let_bindings,
         allow_int_real_subtyping,
+        coerce_int_to_real,
         check_lia_using_cvc5: lia_via_cvc5,
         strict,
         skip_unknown_rules,
+        audit_choice_terms,
+        commutative_transitivity,
+        sampling: sample.map(|rate| SamplingConfig { rate, seed: sample_seed }),
+        lexer_threads,
+        unicode_policy: unicode_symbols.into(),
+        recover_from_errors,
+        ignore_unknown_attributes,
+        producer: producer.into(),
+        severities,
+        trace: None,
+        stream_results: false,
+        keep_going: None,
+        slow_step_threshold: None,
+        parser_limits: parser::ResourceLimits {
+            max_nesting_depth,
+            max_commands,
+            max_token_length,
+        },
+        trusted_rules,
     }
 }
 
@@ -156,6 +526,17 @@ struct ParseCommandOptions {
     #[clap(flatten)]
     input: Input,
 
+    /// A problem file to be parsed before the original problem file, whose declarations and
+    /// assertions are merged with the ones in the problem. This can be given multiple times, in
+    /// which case the files are parsed in order.
+    #[clap(long, value_name = "FILE")]
+    prelude: Vec<String>,
+
+    /// Parses the proof without a problem file. The proof must declare the functions and sorts it
+    /// uses itself, and its `assume` commands are taken as the problem assertions.
+    #[clap(long, conflicts_with_all = &["problem-file", "prelude"])]
+    standalone: bool,
+
     #[clap(flatten)]
     parsing: ParsingOptions,
 
@@ -173,6 +554,73 @@ struct CheckCommandOptions {
 
     #[clap(flatten)]
     checking: CheckingOptions,
+
+    /// After checking, print the N slowest steps, with their rules, sizes and positions in the
+    /// proof file. This can't be used when reading from stdin.
+    #[clap(long, value_name = "N")]
+    top_slowest: Option<usize>,
+
+    /// Checks the proof as a standalone lemma. Its `assume` commands are taken as hypotheses
+    /// instead of being matched against the problem assertions, and the proof may conclude any
+    /// clause. The problem file is only used for its declarations.
+    #[clap(long, conflicts_with = "top-slowest")]
+    lemma: bool,
+
+    /// A problem file to be parsed before the original problem file, whose declarations and
+    /// assertions are merged with the ones in the problem. This can be given multiple times, in
+    /// which case the files are parsed in order.
+    #[clap(long, value_name = "FILE", conflicts_with_all = &["lemma", "top-slowest"])]
+    prelude: Vec<String>,
+
+    /// Checks the proof without a problem file. The proof must declare the functions and sorts it
+    /// uses itself, and its `assume` commands are taken as the problem assertions.
+    #[clap(
+        long,
+        conflicts_with_all = &["problem-file", "prelude", "lemma", "top-slowest"]
+    )]
+    standalone: bool,
+
+    /// Writes a trace of the computation done to check each step to the given file, in the JSON
+    /// Lines format. This includes, for example, the pivots of `resolution` steps and the
+    /// coefficients of `la_generic` steps.
+    #[clap(long, value_name = "FILE", conflicts_with = "top-slowest")]
+    trace: Option<PathBuf>,
+
+    /// Prints one line for each step as soon as it is checked, with its index, rule, result and
+    /// checking time, as a JSON object. The final result is still printed after these lines.
+    #[clap(long, conflicts_with = "top-slowest")]
+    stream_results: bool,
+
+    /// Keeps checking the proof after an invalid step, and reports all errors found at the end,
+    /// instead of stopping at the first one.
+    #[clap(long, conflicts_with = "top-slowest")]
+    keep_going: bool,
+
+    /// With `--keep-going`, stops checking once this many errors are found.
+    #[clap(long, value_name = "N", default_value_t = 100)]
+    max_errors: usize,
+
+    /// Logs a warning as soon as a step takes longer than this many seconds to check, with its rule
+    /// and the size of its clause.
+    #[clap(long, value_name = "SECONDS", conflicts_with = "top-slowest")]
+    slow_step_threshold: Option<f64>,
+}
+
+#[derive(Args)]
+struct CompressCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    /// Check the output proof again before printing it, and fail if it is not valid. The output is
+    /// printed and then parsed and checked against the original problem.
+    #[clap(long)]
+    verify_output: bool,
 }
 
 #[derive(Args)]
@@ -188,6 +636,11 @@ struct ElaborateCommandOptions {
 
     #[clap(flatten)]
     printing: PrintingOptions,
+
+    /// Check the output proof again before printing it, and fail if it is not valid. The output is
+    /// printed and then parsed and checked against the original problem.
+    #[clap(long)]
+    verify_output: bool,
 }
 
 #[derive(Args)]
@@ -206,30 +659,406 @@ struct BenchCommandOptions {
     #[clap(short, long, default_value_t = 1)]
     num_runs: usize,
 
-    /// Number of threads to use when running the benchmark.
-    #[clap(short = 'j', long, default_value_t = 1)]
-    num_threads: usize,
+    /// Number of threads to use when running the benchmark. If this is 0, one thread is used for
+    /// each available core. Defaults to 1.
+    #[clap(short = 'j', long)]
+    num_threads: Option<usize>,
+
+    /// The maximum time, in seconds, that checking each file may take. If this or
+    /// `--memory-limit` is given, each file is checked in a separate worker process, which is
+    /// killed if it exceeds the limits.
+    #[clap(long)]
+    timeout: Option<f64>,
+
+    /// The maximum amount of memory, in megabytes, that checking each file may use. This is only
+    /// supported on Unix systems.
+    #[clap(long)]
+    memory_limit: Option<u64>,
+
+    /// Parse each problem only once in each thread, and share its terms between all of its
+    /// proofs, instead of parsing it again for every proof. This reduces memory use when checking
+    /// many proofs of the same problems. This can't be used together with `--timeout` or
+    /// `--memory-limit`, since then each proof is checked in a separate process.
+    #[clap(long, conflicts_with_all = &["timeout", "memory-limit"])]
+    share_problem_terms: bool,
+
+    /// Store the results for each file in this directory, and reuse them in later runs if the
+    /// problem, proof, options and `carcara` executable didn't change.
+    #[clap(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Don't use the results cache, even if a cache directory is given.
+    #[clap(long)]
+    no_cache: bool,
+
+    /// Ignore the existing entries in the results cache, and replace them with new results.
+    #[clap(long)]
+    refresh: bool,
 
     /// Show benchmark results sorted by total time taken, instead of by average time taken.
     #[clap(short = 't', long)]
     sort_by_total: bool,
 
+    /// Also print the N slowest steps across all files, with their rules, sizes and positions in
+    /// the proof files.
+    #[clap(long, value_name = "N", conflicts_with = "dump-to-csv")]
+    top_slowest: Option<usize>,
+
     /// Dump results to csv files instead of printing to screen.
     #[clap(long = "dump-to-csv")]
     dump_to_csv: bool,
 
+    /// Where the csv results are written. This can be a directory, in which the files `runs.csv`
+    /// and `by-rule.csv` are created, a single file, `-` for the standard output, or
+    /// `tcp://<host>:<port>` to send them through a TCP connection.
+    #[clap(
+        long,
+        value_name = "TARGET",
+        default_value = ".",
+        requires = "dump-to-csv"
+    )]
+    output: ReportTarget,
+
+    /// Also write every raw sample collected during the benchmark to this file, so the full
+    /// distribution of step and run times can be analyzed offline.
+    #[clap(long)]
+    dump_samples: Option<String>,
+
+    /// The format in which the raw samples are written.
+    #[clap(arg_enum, long, default_value_t = SamplesFormat::Csv, requires = "dump-samples")]
+    samples_format: SamplesFormat,
+
     /// The proof files on which the benchmark will be run. If a directory is passed, the checker
     /// will recursively find all '.proof' files in the directory. The problem files will be
     /// inferred from the proof files.
     files: Vec<String>,
 }
 
+#[derive(Args)]
+struct CrossCheckCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    /// The path to the external checker binary.
+    #[clap(long = "checker")]
+    checker_path: String,
+
+    /// An argument to pass to the external checker. Can be used multiple times. Occurrences of
+    /// `{proof}` and `{problem}` are replaced by the proof and problem file paths. If neither is
+    /// used, the file paths are passed after all other arguments.
+    #[clap(long = "checker-arg", allow_hyphen_values = true)]
+    checker_args: Vec<String>,
+
+    /// Carcara keeps checking the proof after an invalid step, so the steps that fail can be
+    /// compared with the external checker. It stops once this many errors are found.
+    #[clap(long, value_name = "N", default_value_t = 100)]
+    max_errors: usize,
+}
+
+impl Command {
+    fn apply_profile(&mut self, profile: &Profile) {
+        let (parsing, checking) = match self {
+            Command::Parse(options) | Command::GenerateLiaProblems(options) => {
+                (&mut options.parsing, None)
+            }
+            Command::Stats(options) => match &mut options.command {
+                Some(StatsCommand::Compare(options)) => (&mut options.parsing, None),
+                None => (&mut options.parsing, None),
+            },
+            Command::Features(options) => (&mut options.parsing, None),
+            Command::Export(options) => (&mut options.parsing, None),
+            Command::RandomTest(options) => (&mut options.parsing, None),
+            Command::Check(options) => {
+                options.keep_going |= profile.keep_going;
+                if options.max_errors == 100 {
+                    options.max_errors = profile.max_errors.unwrap_or(100);
+                }
+                options.slow_step_threshold =
+                    options.slow_step_threshold.or(profile.slow_step_threshold);
+                (&mut options.parsing, Some(&mut options.checking))
+            }
+            Command::Compress(options) => (&mut options.parsing, Some(&mut options.checking)),
+            Command::Elaborate(options) => (&mut options.parsing, Some(&mut options.checking)),
+            Command::Bench(options) => {
+                options.elaborate |= profile.elaborate;
+                options.num_threads = options.num_threads.or(profile.num_threads);
+                if options.cache_dir.is_none() {
+                    options.cache_dir = profile.cache_dir.clone();
+                }
+                options.timeout = options.timeout.or(profile.timeout);
+                options.memory_limit = options.memory_limit.or(profile.memory_limit);
+
+                // Problem terms can't be shared when each proof is checked in a separate process
+                let has_limits = options.timeout.is_some() || options.memory_limit.is_some();
+                options.share_problem_terms |= profile.share_problem_terms && !has_limits;
+                (&mut options.parsing, Some(&mut options.checking))
+            }
+            Command::CrossCheck(options) => {
+                if options.max_errors == 100 {
+                    options.max_errors = profile.max_errors.unwrap_or(100);
+                }
+                (&mut options.parsing, Some(&mut options.checking))
+            }
+            Command::BenchWorker(_)
+            | Command::GenBench(_)
+            | Command::Explain { .. }
+            | Command::Completions { .. } => return,
+        };
+        parsing.apply_profile(profile);
+        if let Some(checking) = checking {
+            checking.apply_profile(profile);
+        }
+    }
+
+    /// Returns the files read by the command, which are used to show excerpts of them when an
+    /// error is reported.
+    fn error_sources(&self) -> Option<ErrorSources> {
+        let (input, parsing, preludes, standalone): (_, _, &[String], _) = match self {
+            Command::Parse(options) | Command::GenerateLiaProblems(options) => (
+                &options.input,
+                options.parsing,
+                &options.prelude,
+                options.standalone,
+            ),
+            Command::Check(options) => (
+                &options.input,
+                options.parsing,
+                &options.prelude,
+                options.standalone,
+            ),
+            Command::Compress(options) => (&options.input, options.parsing, &[], false),
+            Command::Elaborate(options) => (&options.input, options.parsing, &[], false),
+            Command::CrossCheck(options) => (&options.input, options.parsing, &[], false),
+            Command::Features(options) => (&options.input, options.parsing, &[], false),
+            Command::Export(options) => (&options.input, options.parsing, &[], false),
+            Command::RandomTest(options) => (&options.input, options.parsing, &[], false),
+            Command::Stats(_)
+            | Command::Bench(_)
+            | Command::BenchWorker(_)
+            | Command::GenBench(_)
+            | Command::Explain { .. }
+            | Command::Completions { .. } => return None,
+        };
+        Some(ErrorSources {
+            proof_file: input.proof_file.clone(),
+            problem_file: input.problem_file.clone(),
+            has_preludes: !preludes.is_empty(),
+            standalone,
+            parsing,
+        })
+    }
+}
+
+#[derive(Args)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct StatsCommandOptions {
+    #[clap(subcommand)]
+    command: Option<StatsCommand>,
+
+    /// The proof file to be analyzed
+    #[clap(required = true)]
+    proof_file: Option<String>,
+
+    /// The original problem file. If this argument is not present, it will be inferred from the
+    /// proof file.
+    problem_file: Option<String>,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// The format in which the statistics are printed.
+    #[clap(arg_enum, long, default_value_t = StatsFormat::Text)]
+    format: StatsFormat,
+
+    /// Where the statistics are written. This can be a file, `-` for the standard output, or
+    /// `tcp://<host>:<port>` to send them through a TCP connection.
+    #[clap(long, value_name = "TARGET", default_value = "-")]
+    output: ReportTarget,
+}
+
+#[derive(Subcommand)]
+enum StatsCommand {
+    /// Compares two sets of proofs for the same benchmarks, for example produced by different
+    /// versions of a solver, and reports the changes in proof size, rule usage and subproof
+    /// structure of each benchmark.
+    Compare(StatsCompareOptions),
+}
+
+#[derive(Args)]
+struct StatsCompareOptions {
+    /// The directory containing the old proofs.
+    old_dir: String,
+
+    /// The directory containing the new proofs. Proofs are matched by their path relative to each
+    /// directory.
+    new_dir: String,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// The format in which the comparison is printed. In the text format, only the benchmarks whose
+    /// proofs changed are printed.
+    #[clap(arg_enum, long, default_value_t = StatsFormat::Text)]
+    format: StatsFormat,
+
+    /// Where the comparison is written. This can be a file, `-` for the standard output, or
+    /// `tcp://<host>:<port>` to send it through a TCP connection.
+    #[clap(long, value_name = "TARGET", default_value = "-")]
+    output: ReportTarget,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum StatsFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl StatsFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            StatsFormat::Text => "txt",
+            StatsFormat::Json => "json",
+            StatsFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Args)]
+struct FeaturesCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// The format in which the features are printed.
+    #[clap(arg_enum, long, default_value_t = FeaturesFormat::Text)]
+    format: FeaturesFormat,
+
+    /// Where the features are written. This can be a file, `-` for the standard output, or
+    /// `tcp://<host>:<port>` to send them through a TCP connection.
+    #[clap(long, value_name = "TARGET", default_value = "-")]
+    output: ReportTarget,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum FeaturesFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args)]
+struct ExportCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// The format to which the proof is exported.
+    #[clap(arg_enum, long, default_value_t = ExportFormat::Tstp)]
+    format: ExportFormat,
+}
+
+#[derive(Args)]
+struct RandomTestCommandOptions {
+    #[clap(flatten)]
+    input: Input,
+
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    /// The number of random models under which each step is evaluated.
+    #[clap(long, default_value_t = 100)]
+    models: usize,
+
+    /// The seed used to generate the random models.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+}
+
+#[derive(Args)]
+struct BenchWorkerCommandOptions {
+    #[clap(flatten)]
+    parsing: ParsingOptions,
+
+    #[clap(flatten)]
+    checking: CheckingOptions,
+
+    #[clap(long)]
+    elaborate: bool,
+
+    #[clap(long)]
+    run_index: usize,
+
+    proof_file: String,
+
+    problem_file: String,
+}
+
+#[derive(Args)]
+struct GenBenchCommandOptions {
+    /// The seed used by the generator. The same options and seed always generate the same files.
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// The number of random formulas asserted in the problem.
+    #[clap(long, default_value_t = 1000)]
+    assertions: usize,
+
+    /// The maximum nesting depth of each formula.
+    #[clap(long, default_value_t = 8)]
+    depth: usize,
+
+    /// The number of declared symbols of each kind.
+    #[clap(long, default_value_t = 20)]
+    symbols: usize,
+
+    /// The maximum number of digits in each numeral.
+    #[clap(long, default_value_t = 4)]
+    numeral_digits: usize,
+
+    /// The name of the generated files. The problem is written to `<OUTPUT>.smt2`, and the proof
+    /// to `<OUTPUT>.smt2.proof`.
+    output: String,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum SamplesFormat {
+    Csv,
+    Binary,
+}
+
+impl From<SamplesFormat> for SampleFormat {
+    fn from(f: SamplesFormat) -> Self {
+        match f {
+            SamplesFormat::Csv => Self::Csv,
+            SamplesFormat::Binary => Self::Binary,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum ExportFormat {
+    /// TSTP derivations, using the TFF language.
+    Tstp,
+}
+
 #[derive(ArgEnum, Clone)]
 enum LogLevel {
     Off,
     Error,
     Warn,
     Info,
+    Debug,
+    Trace,
 }
 
 impl From<LogLevel> for log::LevelFilter {
@@ -239,6 +1068,8 @@ impl From<LogLevel> for log::LevelFilter {
             LogLevel::Error => Self::Error,
             LogLevel::Warn => Self::Warn,
             LogLevel::Info => Self::Info,
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Trace => Self::Trace,
         }
     }
 }
@@ -248,14 +1079,39 @@ fn main() {
     let colors_enabled = !cli.no_color && atty::is(atty::Stream::Stderr);
     logger::init(cli.log_level.into(), colors_enabled);
 
-    let result = match cli.command {
+    if cli.help_json {
+        println!("{}", help_json::to_json(&Cli::command()));
+        return;
+    }
+    let mut command = match cli.command {
+        Some(c) => c,
+        None => Cli::command()
+            .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+            .exit(),
+    };
+
+    if let Some(preset) = cli.preset {
+        command.apply_profile(&preset.profile());
+    }
+    if let Some(name) = &cli.profile {
+        match config::load_profile(cli.config.as_deref(), name) {
+            Ok(profile) => command.apply_profile(&profile),
+            Err(e) => {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let sources = command.error_sources();
+    let result = match command {
         Command::Parse(options) => parse_command(options),
         Command::Check(options) => {
             match check_command(options) {
                 Ok(false) => println!("valid"),
                 Ok(true) => println!("holey"),
                 Err(e) => {
-                    log::error!("{}", e);
+                    report_error(&e, sources.as_ref());
                     println!("invalid");
                     std::process::exit(1);
                 }
@@ -270,16 +1126,45 @@ fn main() {
                 Ok(false) => println!("valid"),
                 Ok(true) => println!("holey"),
                 Err(e) => {
-                    log::error!("{}", e);
+                    report_error(&e, sources.as_ref());
                     println!("invalid");
                     std::process::exit(1);
                 }
             }
             return;
         }
+        Command::CrossCheck(options) => match cross_check_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        Command::Stats(options) => stats_command(options),
+        Command::Features(options) => features_command(options),
+        Command::Export(options) => export_command(options),
+        Command::GenBench(options) => gen_bench_command(options),
+        Command::Explain { rule } => explain_command(rule.as_deref()),
+        Command::RandomTest(options) => match random_test_command(options) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        Command::BenchWorker(options) => {
+            benchmarking::run_worker_job(
+                options.problem_file.as_ref(),
+                options.proof_file.as_ref(),
+                options.run_index,
+                &build_carcara_options(options.parsing, options.checking),
+                options.elaborate,
+            );
+            return;
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "carcara", &mut io::stdout());
+            return;
+        }
     };
     if let Err(e) = result {
-        log::error!("{}", e);
+        report_error(&e, sources.as_ref());
         std::process::exit(1);
     }
 }
@@ -301,43 +1186,146 @@ fn get_instance(options: &Input) -> CliResult<(Box<dyn BufRead>, Box<dyn BufRead
     }
 }
 
+/// The inputs of the problem files of an instance, in the order they are parsed.
+type ProblemInputs = Vec<Box<dyn BufRead>>;
+
+/// Similar to `get_instance`, but also opens the prelude files given with `--prelude`, which are
+/// returned before the problem. If `standalone` is true, only the proof is opened.
+fn get_problems(
+    input: &Input,
+    preludes: &[String],
+    standalone: bool,
+) -> CliResult<(ProblemInputs, Box<dyn BufRead>)> {
+    if standalone {
+        let proof: Box<dyn BufRead> = match input.proof_file.as_str() {
+            "-" => Box::new(io::stdin().lock()),
+            path => Box::new(io::BufReader::new(File::open(path)?)),
+        };
+        return Ok((Vec::new(), proof));
+    }
+
+    let mut problems = Vec::with_capacity(preludes.len() + 1);
+    for path in preludes {
+        problems.push(Box::new(io::BufReader::new(File::open(path)?)) as Box<dyn BufRead>);
+    }
+    let (problem, proof) = get_instance(input)?;
+    problems.push(problem);
+    Ok((problems, proof))
+}
+
 fn parse_command(options: ParseCommandOptions) -> CliResult<()> {
-    let (problem, proof) = get_instance(&options.input)?;
-    let (_, proof, _) = parser::parse_instance(
-        problem,
+    let (problems, proof) = get_problems(&options.input, &options.prelude, options.standalone)?;
+    let (_, proof, pool) = parser::parse_instance_with_problems(
+        problems,
         proof,
-        options.parsing.apply_function_defs,
-        options.parsing.expand_let_bindings,
-        options.parsing.allow_int_real_subtyping,
-    )
-    .map_err(carcara::Error::from)?;
-    print_proof(&proof.commands, options.printing.use_sharing)?;
+        options.parsing.config(),
+        options.parsing.lexer_threads,
+    )?;
+    let mut stdout = io::stdout();
+    write_alethe_proof(&mut stdout, &proof, &pool, options.printing.use_sharing)?;
     Ok(())
 }
 
 fn check_command(options: CheckCommandOptions) -> CliResult<bool> {
-    let (problem, proof) = get_instance(&options.input)?;
-    check(
-        problem,
-        proof,
-        build_carcara_options(options.parsing, options.checking),
-    )
-    .map_err(Into::into)
+    let mut carcara_options = build_carcara_options(options.parsing, options.checking);
+    carcara_options.trace = options.trace;
+    carcara_options.stream_results = options.stream_results;
+    carcara_options.keep_going = options.keep_going.then(|| options.max_errors);
+    carcara_options.slow_step_threshold = options.slow_step_threshold.map(Duration::from_secs_f64);
+
+    if options.lemma {
+        let (problem, proof) = get_instance(&options.input)?;
+        let (is_holey, lemma) = check_lemma(problem, proof, carcara_options)?;
+        println!("{}", lemma);
+        return Ok(is_holey);
+    }
+
+    let top_slowest = match options.top_slowest {
+        Some(n) => n,
+        None => {
+            let (problems, proof) =
+                get_problems(&options.input, &options.prelude, options.standalone)?;
+            return check_with_problems(problems, proof, carcara_options).map_err(Into::into);
+        }
+    };
+
+    // To measure the time spent on each step, the proof is checked in the same way as in the
+    // `bench` command, which needs the paths of both files
+    let input = &options.input;
+    let (problem_file, proof_file) =
+        match (input.problem_file.as_deref(), input.proof_file.as_str()) {
+            (Some("-"), _) | (_, "-") => return Err(CliError::StdinNotSupported),
+            (Some(problem), proof) => (PathBuf::from(problem), PathBuf::from(proof)),
+            (None, proof) => (infer_problem_path(proof)?, PathBuf::from(proof)),
+        };
+    let mut results = OnlineBenchmarkResults::new().with_slowest_steps(top_slowest);
+    let result = benchmarking::check_with_results(
+        &mut results,
+        &problem_file,
+        &proof_file,
+        &carcara_options,
+    );
+
+    // Steps checked before any error are still measured, so the report is printed even if the
+    // proof is invalid
+    slowest::print_slowest_steps(results.slowest_steps())?;
+    result.map_err(Into::into)
 }
 
 fn elaborate_command(options: ElaborateCommandOptions) -> CliResult<()> {
     let (problem, proof) = get_instance(&options.input)?;
+    let carcara_options = build_carcara_options(options.parsing, options.checking);
 
-    let elaborated = check_and_elaborate(
-        problem,
-        proof,
-        build_carcara_options(options.parsing, options.checking),
+    if !options.verify_output {
+        let elaborated = check_and_elaborate(problem, proof, carcara_options)?;
+        print_proof(&elaborated, options.printing.use_sharing)?;
+        return Ok(());
+    }
+    let (problem, problem_contents) = read_problem(problem)?;
+    let elaborated = check_and_elaborate(problem, proof, carcara_options.clone())?;
+    let output = verify_output(
+        &problem_contents,
+        &elaborated,
+        options.printing.use_sharing,
+        carcara_options,
     )?;
-    print_proof(&elaborated, options.printing.use_sharing)?;
+    io::stdout().write_all(&output)?;
     Ok(())
 }
 
+/// Reads the whole problem into memory, so it can be parsed again by `verify_output`. Returns a
+/// reader with the same contents, to be used in place of the original one.
+fn read_problem(mut problem: Box<dyn BufRead>) -> CliResult<(Box<dyn BufRead>, Vec<u8>)> {
+    let mut contents = Vec::new();
+    problem.read_to_end(&mut contents)?;
+    Ok((Box::new(io::Cursor::new(contents.clone())), contents))
+}
+
+/// Prints the output of a transformation, and checks that the printed proof is still valid for
+/// the problem. Since the printed proof is parsed again, this also catches proofs that can't be
+/// printed in a way that parses back. Returns the printed proof.
+fn verify_output(
+    problem: &[u8],
+    commands: &[ProofCommand],
+    use_sharing: bool,
+    options: CarcaraOptions,
+) -> CliResult<Vec<u8>> {
+    let mut output = Vec::new();
+    write_proof(&mut output, commands, use_sharing)?;
+    match check(problem, output.as_slice(), options) {
+        Ok(_) => Ok(output),
+        Err(e) => Err(CliError::VerificationFailed(e)),
+    }
+}
+
 fn bench_command(options: BenchCommandOptions) -> CliResult<()> {
+    let memory_limit = match options.memory_limit {
+        Some(mb) => match mb.checked_mul(1024 * 1024) {
+            Some(bytes) => Some(bytes),
+            None => return Err(CliError::MemoryLimitTooLarge(mb)),
+        },
+        None => None,
+    };
     let instances = get_instances_from_paths(options.files.iter().map(|s| s.as_str()))?;
     if instances.is_empty() {
         log::warn!("no files passed");
@@ -350,38 +1338,116 @@ fn bench_command(options: BenchCommandOptions) -> CliResult<()> {
         options.num_runs
     );
 
+    let num_threads = benchmarking::resolve_num_threads(options.num_threads.unwrap_or(1));
+    let cache = match &options.cache_dir {
+        Some(dir) if !options.no_cache => Some(ResultsCache::new(dir, options.refresh)?),
+        _ => None,
+    };
+    let carcara_options = build_carcara_options(options.parsing, options.checking);
+    let sharing = options
+        .share_problem_terms
+        .then(benchmarking::SharingStats::default);
+    let job_options = benchmarking::JobOptions {
+        carcara: &carcara_options,
+        elaborate: options.elaborate,
+        limits: benchmarking::JobLimits {
+            timeout: options.timeout.map(Duration::from_secs_f64),
+            memory_limit,
+        },
+        cache: cache.as_ref(),
+        sharing: sharing.as_ref(),
+    };
+    let recorder = match &options.dump_samples {
+        Some(path) => {
+            let dest = Box::new(io::BufWriter::new(File::create(path)?));
+            Some(SampleRecorder::new(dest, options.samples_format.into())?)
+        }
+        None => None,
+    };
     if options.dump_to_csv {
-        benchmarking::run_csv_benchmark(
+        let results = benchmarking::run_benchmark(
             &instances,
             options.num_runs,
-            options.num_threads,
-            &build_carcara_options(options.parsing, options.checking),
-            options.elaborate,
-            &mut File::create("runs.csv")?,
-            &mut File::create("by-rule.csv")?,
-        )?;
+            num_threads,
+            &job_options,
+            || match &recorder {
+                Some(r) => CsvBenchmarkResults::with_recorder(r.clone()),
+                None => CsvBenchmarkResults::new(),
+            },
+        );
+        print_cache_stats(cache.as_ref());
+        print_sharing_stats(sharing.as_ref());
+
+        // Both files are written at the same time, so they are buffered before being sent to the
+        // sink, which can only write one report at a time
+        let (mut runs, mut by_rule) = (Vec::new(), Vec::new());
+        benchmarking::write_csv_results(results, &mut runs, &mut by_rule)?;
+        let mut sink = options.output.open()?;
+        write_report(sink.as_mut(), "runs.csv", |dest| dest.write_all(&runs))?;
+        write_report(sink.as_mut(), "by-rule.csv", |dest| {
+            dest.write_all(&by_rule)
+        })?;
+        if let Some(recorder) = recorder {
+            recorder.finish()?;
+        }
         return Ok(());
     }
 
-    let results: OnlineBenchmarkResults = benchmarking::run_benchmark(
+    let results = benchmarking::run_benchmark(
         &instances,
         options.num_runs,
-        options.num_threads,
-        &build_carcara_options(options.parsing, options.checking),
-        options.elaborate,
+        num_threads,
+        &job_options,
+        || {
+            let results = match &recorder {
+                Some(r) => OnlineBenchmarkResults::with_recorder(r.clone()),
+                None => OnlineBenchmarkResults::new(),
+            };
+            results.with_slowest_steps(options.top_slowest.unwrap_or(0))
+        },
     );
+    print_cache_stats(cache.as_ref());
+    print_sharing_stats(sharing.as_ref());
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
     if results.is_empty() {
         println!("no benchmark data collected");
         return Ok(());
     }
 
-    print_benchmark_results(results, options.sort_by_total)
+    print_benchmark_results(&results, options.sort_by_total)?;
+    if options.top_slowest.is_some() {
+        slowest::print_slowest_steps(results.slowest_steps())?;
+    }
+    Ok(())
+}
+
+fn print_sharing_stats(sharing: Option<&benchmarking::SharingStats>) {
+    if let Some(sharing) = sharing {
+        let (num_problems, num_proofs, shared_terms, proof_terms) = sharing.summary();
+        println!(
+            "shared problem terms: {} problems parsed for {} proofs, each proof reused {:.0} \
+            problem terms and added {:.0} new terms on average",
+            num_problems, num_proofs, shared_terms, proof_terms
+        );
+    }
+}
+
+fn print_cache_stats(cache: Option<&ResultsCache>) {
+    if let Some(cache) = cache {
+        let (hits, misses) = cache.stats();
+        println!("results cache: {} hits, {} misses", hits, misses);
+    }
 }
 
-fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool) -> CliResult<()> {
-    let [parsing, checking, elaborating, accounted_for, total] = [
+fn print_benchmark_results(results: &OnlineBenchmarkResults, sort_by_total: bool) -> CliResult<()> {
+    let [parsing, lexing, sort_checking, checking, rule_logic, elaborating, accounted_for, total] = [
         results.parsing(),
+        results.lexing(),
+        results.sort_checking(),
         results.checking(),
+        results.rule_logic(),
         results.elaborating(),
         results.total_accounted_for(),
         results.total(),
@@ -395,7 +1461,10 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
     });
 
     println!("parsing:             {}", parsing);
+    println!("    lexing:          {}", lexing);
+    println!("    sort checking:   {}", sort_checking);
     println!("checking:            {}", checking);
+    println!("    rule logic:      {}", rule_logic);
     if !elaborating.is_empty() {
         println!("elaborating:      {}", elaborating);
     }
@@ -414,20 +1483,26 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
     println!("deep equality ratio: {}", results.deep_eq_time_ratio);
     println!("total accounted for: {}", accounted_for);
     println!("total:               {}", total);
-
-    let data_by_rule = results.step_time_by_rule();
-    let mut data_by_rule: Vec<_> = data_by_rule.iter().collect();
-    data_by_rule.sort_by_key(|(_, m)| if sort_by_total { m.total() } else { m.mean() });
-
-    println!("by rule:");
-    for (rule, data) in data_by_rule {
-        print!("    {: <18}", rule);
-        if sort_by_total {
-            println!("{:#}", data)
-        } else {
-            println!("{}", data)
+    println!("step per literal:    {}", results.time_per_literal());
+    println!("step per premise:    {}", results.time_per_premise());
+
+    let print_by_rule = |title: &str, data_by_rule: &AHashMap<String, OnlineMetrics<_>>| {
+        let mut data_by_rule: Vec<_> = data_by_rule.iter().collect();
+        data_by_rule.sort_by_key(|(_, m)| if sort_by_total { m.total() } else { m.mean() });
+
+        println!("{}:", title);
+        for (rule, data) in data_by_rule {
+            print!("    {: <18}", rule);
+            if sort_by_total {
+                println!("{:#}", data)
+            } else {
+                println!("{}", data)
+            }
         }
-    }
+    };
+    print_by_rule("by rule", results.step_time_by_rule());
+    print_by_rule("by rule, per literal", results.time_per_literal_by_rule());
+    print_by_rule("by rule, per premise", results.time_per_premise_by_rule());
 
     println!("worst cases:");
     let worst_step = results.step_time().max();
@@ -478,7 +1553,7 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
         num_hard_assumes, percent_hard
     );
 
-    let depths = results.deep_eq_depths;
+    let depths = &results.deep_eq_depths;
     if !depths.is_empty() {
         println!("    max deep equality depth: {}", depths.max().1);
         println!("  total deep equality depth: {}", depths.total());
@@ -493,8 +1568,6 @@ fn print_benchmark_results(results: OnlineBenchmarkResults, sort_by_total: bool)
 }
 
 fn generate_lia_problems_command(options: ParseCommandOptions) -> CliResult<()> {
-    use std::io::Write;
-
     let root_file_name = options.input.proof_file.clone();
     let (problem, proof) = get_instance(&options.input)?;
 
@@ -515,12 +1588,268 @@ fn generate_lia_problems_command(options: ParseCommandOptions) -> CliResult<()>
     Ok(())
 }
 
-fn compress_command(options : CheckCommandOptions) -> CliResult<bool> {
+fn compress_command(options : CompressCommandOptions) -> CliResult<bool> {
     let (problem, proof) = get_instance(&options.input)?;
+    let carcara_options = build_carcara_options(options.parsing, options.checking);
 
-    compress(
-        problem,
+    if !options.verify_output {
+        let (is_holey, compressed) = compress(problem, proof, carcara_options)?;
+        print_proof(&compressed, false)?;
+        return Ok(is_holey);
+    }
+    let (problem, problem_contents) = read_problem(problem)?;
+    let (is_holey, compressed) = compress(problem, proof, carcara_options.clone())?;
+    let output = verify_output(&problem_contents, &compressed, false, carcara_options)?;
+    io::stdout().write_all(&output)?;
+    Ok(is_holey)
+}
+
+fn cross_check_command(options: CrossCheckCommandOptions) -> CliResult<bool> {
+    let proof_file = PathBuf::from(&options.input.proof_file);
+    let problem_file = match options.input.problem_file.as_deref() {
+        Some(p) => PathBuf::from(p),
+        None => infer_problem_path(&proof_file)?,
+    };
+    if proof_file == Path::new("-") || problem_file == Path::new("-") {
+        return Err(CliError::StdinNotSupported);
+    }
+
+    let external = ExternalChecker {
+        path: &options.checker_path,
+        args: &options.checker_args,
+    };
+
+    // To compare the two checkers step by step, we need all the steps that fail, not only the
+    // first one
+    let mut carcara_options = build_carcara_options(options.parsing, options.checking);
+    carcara_options.keep_going = Some(options.max_errors);
+    let (ours, theirs) = cross_check(&problem_file, &proof_file, carcara_options, &external)?;
+
+    println!("carcara:  {}", ours);
+    println!("external: {}", theirs);
+    for d in ours.step_disagreements(&theirs) {
+        let (failed, passed) = if d.failed_in_first {
+            ("carcara", "external")
+        } else {
+            ("external", "carcara")
+        };
+        println!(
+            "step '{}': failed in {}, but not in {}",
+            d.step, failed, passed
+        );
+    }
+    let agree = ours.agrees_with(&theirs);
+    if agree {
+        println!("agree");
+    } else {
+        for (name, outcome) in [("carcara", &ours), ("external", &theirs)] {
+            if let Some(message) = &outcome.message {
+                log::info!("{} error: {}", name, message);
+            }
+        }
+        println!("disagree");
+    }
+    Ok(agree)
+}
+
+fn stats_command(options: StatsCommandOptions) -> CliResult<()> {
+    let input = match options.command {
+        Some(StatsCommand::Compare(options)) => return stats_compare_command(options),
+        None => Input {
+            // `clap` ensures that the proof file is present if there is no subcommand
+            proof_file: options.proof_file.unwrap(),
+            problem_file: options.problem_file,
+        },
+    };
+    let (problem, proof) = get_instance(&input)?;
+    let (_, proof, _) = parser::parse_instance_with_problems(
+        vec![problem],
+        proof,
+        options.parsing.config(),
+        options.parsing.lexer_threads,
+    )?;
+
+    let stats = ProofStats::new(&proof);
+    let mut sink = options.output.open()?;
+    let name = format!("stats.{}", options.format.extension());
+    write_report(sink.as_mut(), &name, |dest| match options.format {
+        StatsFormat::Text => write!(dest, "{}", stats),
+        StatsFormat::Json => {
+            // Serializing the statistics can't fail, since they only contain numbers and maps
+            writeln!(dest, "{}", serde_json::to_string_pretty(&stats).unwrap())
+        }
+        StatsFormat::Csv => stats.write_csv(dest),
+    })?;
+    Ok(())
+}
+
+fn features_command(options: FeaturesCommandOptions) -> CliResult<()> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (_, proof, _) = parser::parse_instance_with_problems(
+        vec![problem],
         proof,
-        build_carcara_options(options.parsing, options.checking),
-    ).map_err(Into::into)
-}
\ No newline at end of file
+        options.parsing.config(),
+        options.parsing.lexer_threads,
+    )?;
+
+    let features = ProofFeatures::new(&proof);
+    let mut sink = options.output.open()?;
+    let name = match options.format {
+        FeaturesFormat::Text => "features.txt",
+        FeaturesFormat::Json => "features.json",
+    };
+    write_report(sink.as_mut(), name, |dest| match options.format {
+        FeaturesFormat::Text => write!(dest, "{}", features),
+        FeaturesFormat::Json => {
+            // Numerals are serialized as strings, so serializing the features can't fail
+            writeln!(dest, "{}", serde_json::to_string_pretty(&features).unwrap())
+        }
+    })?;
+    Ok(())
+}
+
+fn stats_compare_command(options: StatsCompareOptions) -> CliResult<()> {
+    let parsing = options.parsing;
+    let summarize = |problem_file: &Path, proof_file: &Path| -> CliResult<ProofSummary> {
+        let (_, proof, _) = parser::parse_instance_with_problems(
+            vec![io::BufReader::new(File::open(problem_file)?)],
+            io::BufReader::new(File::open(proof_file)?),
+            parsing.config(),
+            parsing.lexer_threads,
+        )?;
+        Ok(ProofSummary::new(&proof))
+    };
+
+    // Finds and summarizes all proofs in a directory, indexed by their path relative to it. Proofs
+    // that can't be parsed are reported, and their summary is `None`
+    let load_dir = |dir: &str| -> CliResult<BTreeMap<String, Option<ProofSummary>>> {
+        let mut result = BTreeMap::new();
+        for (problem_file, proof_file) in get_instances_from_paths([dir].into_iter())? {
+            let summary = match summarize(&problem_file, &proof_file) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    log::error!("failed to parse '{}': {}", proof_file.display(), e);
+                    None
+                }
+            };
+            let benchmark = proof_file.strip_prefix(dir).unwrap_or(&proof_file);
+            result.insert(benchmark.display().to_string(), summary);
+        }
+        Ok(result)
+    };
+    let mut old = load_dir(&options.old_dir)?;
+    let mut new = load_dir(&options.new_dir)?;
+
+    let mut benchmarks: Vec<String> = old.keys().chain(new.keys()).cloned().collect();
+    benchmarks.sort_unstable();
+    benchmarks.dedup();
+    let comparisons: Vec<_> = benchmarks
+        .into_iter()
+        .map(|benchmark| ProofComparison {
+            old: old.remove(&benchmark).flatten(),
+            new: new.remove(&benchmark).flatten(),
+            benchmark,
+        })
+        .collect();
+
+    let mut sink = options.output.open()?;
+    let name = format!("comparison.{}", options.format.extension());
+    write_report(sink.as_mut(), &name, |dest| match options.format {
+        StatsFormat::Text => {
+            let changed: Vec<_> = comparisons.iter().filter(|c| c.is_changed()).collect();
+            for c in &changed {
+                write!(dest, "{}", c)?;
+            }
+            writeln!(
+                dest,
+                "compared {} benchmarks, {} changed",
+                comparisons.len(),
+                changed.len()
+            )
+        }
+        StatsFormat::Json => writeln!(
+            dest,
+            "{}",
+            serde_json::to_string_pretty(&comparisons).unwrap()
+        ),
+        StatsFormat::Csv => write_comparisons_csv(&comparisons, dest),
+    })?;
+    Ok(())
+}
+
+fn export_command(options: ExportCommandOptions) -> CliResult<()> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (prelude, proof, _) = parser::parse_instance_with_problems(
+        vec![problem],
+        proof,
+        options.parsing.config(),
+        options.parsing.lexer_threads,
+    )?;
+
+    match options.format {
+        ExportFormat::Tstp => write_tstp_proof(&mut io::stdout(), &prelude, &proof)?,
+    }
+    Ok(())
+}
+
+fn random_test_command(options: RandomTestCommandOptions) -> CliResult<bool> {
+    let (problem, proof) = get_instance(&options.input)?;
+    let (_, proof, _) = parser::parse_instance_with_problems(
+        vec![problem],
+        proof,
+        options.parsing.config(),
+        options.parsing.lexer_threads,
+    )?;
+
+    let counterexamples = find_counterexamples(&proof, options.models, options.seed);
+    for c in &counterexamples {
+        println!("{}", c);
+    }
+    Ok(counterexamples.is_empty())
+}
+
+fn explain_command(rule: Option<&str>) -> CliResult<()> {
+    let name = match rule {
+        Some(name) => name,
+        None => {
+            for info in rule_info::all_rules() {
+                println!("{:<20} {}", info.name, info.summary);
+            }
+            return Ok(());
+        }
+    };
+    let info =
+        rule_info::get_rule_info(name).ok_or_else(|| CliError::UnknownRule(name.to_owned()))?;
+
+    println!("{}: {}", info.name, info.summary);
+    if !info.aliases.is_empty() {
+        println!("also accepted as: {}", info.aliases.join(", "));
+    }
+    let or_none = |s: &'static str| if s.is_empty() { "none" } else { s };
+    println!();
+    println!("conclusion: {}", info.conclusion);
+    println!("premises:   {}", or_none(info.premises));
+    println!("arguments:  {}", or_none(info.args));
+    println!();
+    println!("example:");
+    for line in info.definitions.lines().chain(info.example.lines()) {
+        println!("  {}", line);
+    }
+    Ok(())
+}
+
+fn gen_bench_command(options: GenBenchCommandOptions) -> CliResult<()> {
+    let generator_options = gen_bench::GeneratorOptions {
+        seed: options.seed,
+        num_assertions: options.assertions,
+        max_depth: options.depth,
+        num_symbols: options.symbols,
+        max_numeral_digits: options.numeral_digits,
+    };
+    let problem_path = format!("{}.smt2", options.output);
+    let proof_path = format!("{}.proof", problem_path);
+    let mut problem = io::BufWriter::new(File::create(problem_path)?);
+    let mut proof = io::BufWriter::new(File::create(proof_path)?);
+    gen_bench::generate(generator_options, &mut problem, &mut proof)?;
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! Destinations to which reports, like proof statistics or benchmark results, are written. Each
+//! report format only needs to write to a `dyn io::Write`, and the sink decides where the bytes go.
+
+use std::{
+    convert::Infallible,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    net::TcpStream,
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// A destination for reports. A command may write more than one report to the same sink, for
+/// example the per-run and per-rule results of a benchmark.
+pub trait ReportSink {
+    /// Opens a writer for a new report. `name` is a file name for the report, like `runs.csv`,
+    /// which is used by sinks that write each report to a separate file.
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn Write + '_>>;
+}
+
+/// Writes all reports to the standard output.
+pub struct StdoutSink;
+
+impl ReportSink for StdoutSink {
+    fn open(&mut self, _: &str) -> io::Result<Box<dyn Write + '_>> {
+        Ok(Box::new(io::stdout().lock()))
+    }
+}
+
+/// Writes reports to the file system. If the path is a directory, each report is written to a
+/// file in it with the report's name. Otherwise, all reports are written to the same file, one
+/// after the other.
+pub struct FileSink {
+    path: PathBuf,
+    num_opened: usize,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), num_opened: 0 }
+    }
+}
+
+impl ReportSink for FileSink {
+    fn open(&mut self, name: &str) -> io::Result<Box<dyn Write + '_>> {
+        let file = if self.path.is_dir() {
+            File::create(self.path.join(name))?
+        } else if self.num_opened == 0 {
+            File::create(&self.path)?
+        } else {
+            OpenOptions::new().append(true).open(&self.path)?
+        };
+        self.num_opened += 1;
+        Ok(Box::new(io::BufWriter::new(file)))
+    }
+}
+
+/// Sends all reports through a TCP connection, for example to a dashboard that collects results.
+/// The connection is opened when the sink is created, and is shared by all reports.
+pub struct TcpSink {
+    stream: TcpStream,
+}
+
+impl TcpSink {
+    pub fn connect(address: &str) -> io::Result<Self> {
+        Ok(Self {
+            stream: TcpStream::connect(address)?,
+        })
+    }
+}
+
+impl ReportSink for TcpSink {
+    fn open(&mut self, _: &str) -> io::Result<Box<dyn Write + '_>> {
+        Ok(Box::new(io::BufWriter::new(&self.stream)))
+    }
+}
+
+/// Where reports should be written, as given in the command line. This is `-` for the standard
+/// output, `tcp://<host>:<port>` for a TCP connection, or a path otherwise.
+#[derive(Debug, Clone)]
+pub enum ReportTarget {
+    Stdout,
+    Path(PathBuf),
+    Tcp(String),
+}
+
+impl FromStr for ReportTarget {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s == "-" {
+            Self::Stdout
+        } else if let Some(address) = s.strip_prefix("tcp://") {
+            Self::Tcp(address.to_owned())
+        } else {
+            Self::Path(PathBuf::from(s))
+        })
+    }
+}
+
+impl ReportTarget {
+    /// Opens the sink for this target. If the target is a path ending with a separator, the
+    /// directory is created if it doesn't exist.
+    pub fn open(&self) -> io::Result<Box<dyn ReportSink>> {
+        Ok(match self {
+            Self::Stdout => Box::new(StdoutSink),
+            Self::Path(path) => {
+                if path
+                    .as_os_str()
+                    .to_string_lossy()
+                    .ends_with(std::path::is_separator)
+                {
+                    fs::create_dir_all(path)?;
+                }
+                Box::new(FileSink::new(path))
+            }
+            Self::Tcp(address) => Box::new(TcpSink::connect(address)?),
+        })
+    }
+}
+
+/// Writes a report to a sink, using `write` to produce its contents.
+pub fn write_report(
+    sink: &mut dyn ReportSink,
+    name: &str,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut dest = sink.open(name)?;
+    write(&mut dest)?;
+    dest.flush()
+}
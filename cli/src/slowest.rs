@@ -0,0 +1,74 @@
+//! The report of the slowest steps in a `check` or `bench` run, printed when `--top-slowest` is
+//! given.
+
+use ahash::AHashMap;
+use carcara::benchmarking::SlowStep;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+/// Prints the slowest steps, with their rules, sizes and positions in the proof file.
+pub fn print_slowest_steps(steps: &[SlowStep]) -> io::Result<()> {
+    // The positions are found by reading the proof files again, since the parsed proofs are dropped
+    // as soon as they are checked
+    let mut positions: AHashMap<&str, AHashMap<String, (usize, usize)>> = AHashMap::new();
+    for step in steps {
+        let file = step.id.file();
+        if !positions.contains_key(file) {
+            let ids: Vec<_> = (steps.iter())
+                .filter(|s| s.id.file() == file)
+                .map(|s| s.id.step_id())
+                .collect();
+            positions.insert(file, locate_steps(file, &ids)?);
+        }
+    }
+
+    println!("slowest steps:");
+    for (i, step) in steps.iter().enumerate() {
+        let (file, id) = (step.id.file(), step.id.step_id());
+        let location = match positions[file].get(id) {
+            Some((line, column)) => format!("{}:{}:{}", file, line, column),
+            None => file.to_owned(),
+        };
+        println!(
+            "    {:>3}. {:>12?}  {} ({}), {} literals, {} premises, at {}",
+            i + 1,
+            step.time,
+            id,
+            step.id.rule(),
+            step.size.num_literals,
+            step.size.num_premises,
+            location,
+        );
+    }
+    Ok(())
+}
+
+/// Finds the line and column at which each of the given commands starts in a proof file. Commands
+/// that are not found are not included in the result.
+fn locate_steps(path: &str, ids: &[&str]) -> io::Result<AHashMap<String, (usize, usize)>> {
+    const COMMANDS: [&str; 2] = ["(step ", "(assume "];
+
+    let mut result = AHashMap::new();
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        for command in COMMANDS {
+            for (start, _) in line.match_indices(command) {
+                let rest = line[start + command.len()..].trim_start();
+                let id = rest
+                    .split(|c: char| c.is_whitespace() || c == ')')
+                    .next()
+                    .unwrap_or_default();
+                if ids.contains(&id) && !result.contains_key(id) {
+                    let column = line[..start].chars().count() + 1;
+                    result.insert(id.to_owned(), (i + 1, column));
+                }
+            }
+        }
+        if result.len() == ids.len() {
+            break;
+        }
+    }
+    Ok(result)
+}
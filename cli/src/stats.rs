@@ -0,0 +1,345 @@
+use ahash::AHashMap;
+use carcara::{
+    ast::{Proof, ProofCommand, Rc, Term},
+    checker::current_rule_name,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, io, iter};
+
+/// A distribution of non-negative integer values, stored as a histogram mapping each value to the
+/// number of times it occurred.
+#[derive(Debug, Default)]
+pub struct Histogram(BTreeMap<usize, usize>);
+
+impl Histogram {
+    pub fn add(&mut self, value: usize) {
+        *self.0.entry(value).or_insert(0) += 1;
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.values().sum()
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.0.keys().next().copied()
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.0.keys().next_back().copied()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        let total: usize = self.0.iter().map(|(value, n)| value * n).sum();
+        (count > 0).then(|| total as f64 / count as f64)
+    }
+}
+
+impl Serialize for Histogram {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Summary<'a> {
+            count: usize,
+            min: Option<usize>,
+            max: Option<usize>,
+            mean: Option<f64>,
+            histogram: &'a BTreeMap<usize, usize>,
+        }
+        Summary {
+            count: self.count(),
+            min: self.min(),
+            max: self.max(),
+            mean: self.mean(),
+            histogram: &self.0,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.min(), self.max(), self.mean()) {
+            (Some(min), Some(max), Some(mean)) => write!(
+                f,
+                "{} values, min {}, max {}, mean {:.2}",
+                self.count(),
+                min,
+                max,
+                mean
+            ),
+            _ => write!(f, "no values"),
+        }
+    }
+}
+
+/// Distributions describing the shape of a proof.
+#[derive(Debug, Default, Serialize)]
+pub struct ProofStats {
+    /// The number of terms in the conclusion of each `assume` or `step` command.
+    pub clause_length: Histogram,
+
+    /// The number of premises of each `step` command.
+    pub premise_count: Histogram,
+
+    /// The depth of each term in the conclusion of each `assume` or `step` command.
+    pub term_depth: Histogram,
+
+    /// The number of steps that use each old rule name, which are checked as the rules that
+    /// replaced them.
+    pub renamed_rules: BTreeMap<String, usize>,
+
+    /// The attributes given in `set-info` commands in the proof, like the solver that produced it.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl ProofStats {
+    pub fn new(proof: &Proof) -> Self {
+        let mut stats = Self {
+            metadata: proof.metadata.clone(),
+            ..Self::default()
+        };
+        let mut depths = AHashMap::new();
+        for command in proof.iter() {
+            if let ProofCommand::Step(step) = command {
+                stats.premise_count.add(step.premises.len());
+                if current_rule_name(&step.rule).is_some() {
+                    *stats.renamed_rules.entry(step.rule.clone()).or_insert(0) += 1;
+                }
+            }
+            if !matches!(command, ProofCommand::Subproof(_)) {
+                let clause = command.clause();
+                stats.clause_length.add(clause.len());
+                for term in clause {
+                    stats.term_depth.add(term_depth(term, &mut depths));
+                }
+            }
+        }
+        stats
+    }
+
+    fn histograms(&self) -> [(&'static str, &Histogram); 3] {
+        [
+            ("clause_length", &self.clause_length),
+            ("premise_count", &self.premise_count),
+            ("term_depth", &self.term_depth),
+        ]
+    }
+
+    /// Prints the distributions in CSV format, with one line for each distinct value of each
+    /// metric.
+    pub fn write_csv(&self, dest: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(dest, "metric,value,count")?;
+        for (name, histogram) in self.histograms() {
+            for (value, count) in &histogram.0 {
+                writeln!(dest, "{},{},{}", name, value, count)?;
+            }
+        }
+        for (rule, count) in &self.renamed_rules {
+            writeln!(dest, "renamed_rule,{},{}", rule, count)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProofStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (keyword, value) in &self.metadata {
+            writeln!(f, "{:<15}{}", format!("{}:", keyword), value)?;
+        }
+        for (name, histogram) in self.histograms() {
+            writeln!(f, "{:<15}{}", format!("{}:", name), histogram)?;
+        }
+        for (rule, count) in &self.renamed_rules {
+            let new_name = current_rule_name(rule).unwrap();
+            writeln!(
+                f,
+                "renamed rule '{}' (now '{}'): {} steps",
+                rule, new_name, count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the depth of a term, where terminals and sorts have depth 1. Since terms are
+/// hash-consed, the depth of each subterm is cached in `cache` to avoid visiting shared subterms
+/// more than once.
+fn term_depth(term: &Rc<Term>, cache: &mut AHashMap<Rc<Term>, usize>) -> usize {
+    if let Some(&depth) = cache.get(term) {
+        return depth;
+    }
+    let mut max_depth = |terms: &mut dyn Iterator<Item = &Rc<Term>>| {
+        terms.map(|t| term_depth(t, cache)).max().unwrap_or(0)
+    };
+    let children_depth = match &**term {
+        Term::Terminal(_) => 0,
+        Term::App(f, args) => max_depth(&mut iter::once(f).chain(args)),
+        Term::Op(_, args) | Term::IndexedOp(_, _, args) => max_depth(&mut args.iter()),
+        Term::Let(bindings, inner) => {
+            let values = bindings.iter().map(|(_, value)| value);
+            max_depth(&mut values.chain(iter::once(inner)))
+        }
+        Term::Quant(_, _, inner, _) | Term::Choice(_, inner) | Term::Lambda(_, inner) => {
+            max_depth(&mut iter::once(inner))
+        }
+    };
+    let depth = children_depth + 1;
+    cache.insert(term.clone(), depth);
+    depth
+}
+
+/// A summary of the size and structure of a proof, used to compare proofs of the same problem
+/// produced by different solver versions.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ProofSummary {
+    /// The number of `assume` and `step` commands, including the ones inside subproofs.
+    pub num_commands: usize,
+
+    /// The sum of the number of terms in the conclusions of all `assume` and `step` commands.
+    pub total_clause_length: usize,
+
+    pub num_subproofs: usize,
+
+    /// The maximum nesting depth of subproofs, which is zero if the proof has no subproofs.
+    pub max_subproof_depth: usize,
+
+    /// The number of steps that use each rule.
+    pub rules: BTreeMap<String, usize>,
+}
+
+impl ProofSummary {
+    pub fn new(proof: &Proof) -> Self {
+        let mut summary = Self::default();
+        let mut iter = proof.iter();
+        while let Some(command) = iter.next() {
+            match command {
+                ProofCommand::Subproof(_) => {
+                    summary.num_subproofs += 1;
+                    summary.max_subproof_depth = summary.max_subproof_depth.max(iter.depth());
+                }
+                ProofCommand::Step(step) => {
+                    *summary.rules.entry(step.rule.clone()).or_insert(0) += 1;
+                }
+                ProofCommand::Assume { .. } => (),
+            }
+            if !matches!(command, ProofCommand::Subproof(_)) {
+                summary.num_commands += 1;
+                summary.total_clause_length += command.clause().len();
+            }
+        }
+        summary
+    }
+
+    fn metrics(&self) -> [(&'static str, usize); 4] {
+        [
+            ("num_commands", self.num_commands),
+            ("total_clause_length", self.total_clause_length),
+            ("num_subproofs", self.num_subproofs),
+            ("max_subproof_depth", self.max_subproof_depth),
+        ]
+    }
+}
+
+/// The summaries of two proofs of the same benchmark. A summary is `None` if the proof is missing
+/// from that side of the comparison, or if it could not be parsed.
+#[derive(Debug, Serialize)]
+pub struct ProofComparison {
+    pub benchmark: String,
+    pub old: Option<ProofSummary>,
+    pub new: Option<ProofSummary>,
+}
+
+impl ProofComparison {
+    pub fn is_changed(&self) -> bool {
+        self.old != self.new
+    }
+
+    /// Returns the rules whose number of uses differs between the two proofs, together with the
+    /// number of uses in each of them. If one of the proofs is missing, this returns an empty
+    /// vector.
+    fn changed_rules(&self) -> Vec<(&str, usize, usize)> {
+        let (old, new) = match (&self.old, &self.new) {
+            (Some(old), Some(new)) => (&old.rules, &new.rules),
+            _ => return Vec::new(),
+        };
+        let mut names: Vec<&str> = old.keys().chain(new.keys()).map(String::as_str).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+            .into_iter()
+            .map(|name| {
+                let count = |rules: &BTreeMap<String, usize>| rules.get(name).copied().unwrap_or(0);
+                (name, count(old), count(new))
+            })
+            .filter(|(_, old, new)| old != new)
+            .collect()
+    }
+}
+
+/// Formats a change in a value, like `12 -> 10 (-2)`.
+fn format_change(old: usize, new: usize) -> String {
+    if old == new {
+        old.to_string()
+    } else {
+        format!("{} -> {} ({:+})", old, new, new as i64 - old as i64)
+    }
+}
+
+impl fmt::Display for ProofComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.benchmark)?;
+        let (old, new) = match (&self.old, &self.new) {
+            (Some(old), Some(new)) => (old, new),
+            (None, _) => return writeln!(f, "  missing or invalid in old proofs"),
+            (_, None) => return writeln!(f, "  missing or invalid in new proofs"),
+        };
+        for ((name, old), (_, new)) in old.metrics().into_iter().zip(new.metrics()) {
+            writeln!(
+                f,
+                "  {:<21}{}",
+                format!("{}:", name),
+                format_change(old, new)
+            )?;
+        }
+        for (rule, old, new) in self.changed_rules() {
+            writeln!(
+                f,
+                "  rule {:<16}{}",
+                format!("{}:", rule),
+                format_change(old, new)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the comparisons in CSV format, with one line for each metric of each benchmark, and one
+/// line for each rule whose number of uses changed. Values that are missing in one of the sides are
+/// left empty.
+pub fn write_comparisons_csv(
+    comparisons: &[ProofComparison],
+    dest: &mut dyn io::Write,
+) -> io::Result<()> {
+    writeln!(dest, "benchmark,metric,old,new")?;
+    let names = ProofSummary::default().metrics().map(|(name, _)| name);
+    for c in comparisons {
+        let values = |s: &Option<ProofSummary>| s.as_ref().map(ProofSummary::metrics);
+        let (old, new) = (values(&c.old), values(&c.new));
+        for (i, name) in names.iter().enumerate() {
+            let value =
+                |m: Option<[(&str, usize); 4]>| m.map_or(String::new(), |m| m[i].1.to_string());
+            writeln!(
+                dest,
+                "{},{},{},{}",
+                c.benchmark,
+                name,
+                value(old),
+                value(new)
+            )?;
+        }
+        for (rule, old, new) in c.changed_rules() {
+            writeln!(dest, "{},rule:{},{},{}", c.benchmark, rule, old, new)?;
+        }
+    }
+    Ok(())
+}